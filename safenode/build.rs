@@ -7,7 +7,15 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("./src/protocol/safenode_proto/safenode.proto")?;
+    // The protos import one another relative to the root dir given as include.
+    tonic_build::configure().compile(
+        &[
+            "./src/protocol/safenode_proto/safenode/v1/safenode.proto",
+            // The deprecated unversioned package, served for compatibility.
+            "./src/protocol/safenode_proto/safenode.proto",
+        ],
+        &["./src/protocol/safenode_proto"],
+    )?;
 
     Ok(())
 }
@@ -23,7 +23,7 @@ use tokio_stream::StreamExt;
 
 // this includes code generated from .proto files
 mod safenode_proto {
-    tonic::include_proto!("safenode_proto");
+    tonic::include_proto!("safenode.v1");
 }
 
 #[derive(Parser, Debug)]
@@ -141,12 +141,15 @@ pub async fn node_events(addr: SocketAddr) -> Result<()> {
     let endpoint = format!("https://{addr}");
     let mut client = SafeNodeClient::connect(endpoint).await?;
     let response = client
-        .node_events(Request::new(NodeEventsRequest {}))
+        .node_events(Request::new(NodeEventsRequest { kinds: vec![] }))
         .await?;
 
     println!("Listening to node events... (press Ctrl+C to exit)");
     let mut stream = response.into_inner();
     while let Some(Ok(e)) = stream.next().await {
+        if e.skipped > 0 {
+            println!("{} events were skipped", e.skipped);
+        }
         println!("New event received: {}", e.event);
     }
 
@@ -7,22 +7,36 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use safenode::{
-    client::Client,
+    client::{Client, WalletClient},
     domain::{
         dbc_genesis::{get_tokens_from_faucet, load_faucet_wallet},
-        wallet::parse_public_address,
+        wallet::{parse_public_address, LocalWallet, TokenAmount},
     },
     log::init_node_logging,
 };
 
 use clap::{Parser, Subcommand};
-use eyre::Result;
-use sn_dbc::Token;
-use tracing::info;
+use eyre::{eyre, Result};
+use hyper::{
+    server::conn::AddrStream,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use sn_dbc::{Dbc, PublicAddress};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let _log_appender_guard = init_node_logging(&None)?;
+    let _log_appender_guard =
+        init_node_logging(&None, Default::default(), None, Default::default())?;
 
     let opt = Opt::parse();
 
@@ -50,10 +64,27 @@ enum SubCmd {
     /// This needs to be run before a testnet is opened to the public, as to not have
     /// the genesis claimed by someone else (the key and dbc are public for audit).
     ClaimGenesis,
+    /// Serve claims for tokens over HTTP, until stopped. A claim is a `GET /<address>` request,
+    /// with the hex-encoded `PublicAddress` to send the tokens to, which is answered with the
+    /// hex-encoded `Dbc` created for it, i.e. what `safe wallet get-faucet` expects.
+    /// The genesis DBC is claimed first, if it hasn't been yet.
+    Serve {
+        /// The address the HTTP server listens on.
+        #[clap(long, default_value = "0.0.0.0:8000")]
+        listen: SocketAddr,
+        /// The amount sent for each claim, in tokens, e.g. `1.5`, or in nanos, e.g. `1500000000nano`.
+        #[clap(long, default_value = "100")]
+        amount: TokenAmount,
+        /// The number of seconds a requester, identified both by its IP address and by the
+        /// address it claims tokens for, has to wait between two claims.
+        #[clap(long, default_value_t = 3600)]
+        cooldown_secs: u64,
+    },
     Send {
-        /// This shall be the number of nanos to send.
+        /// The amount to send, in tokens with up to nine decimals, e.g. `1.337`,
+        /// or in nanos with a `nano` suffix, e.g. `1337000000nano`.
         #[clap(name = "amount")]
-        amount: String,
+        amount: TokenAmount,
         /// This must be a hex-encoded `PublicAddress`.
         #[clap(name = "to")]
         to: String,
@@ -65,19 +96,259 @@ async fn faucet_cmds(cmds: SubCmd, client: &Client) -> Result<()> {
         SubCmd::ClaimGenesis => {
             let _wallet = load_faucet_wallet(client).await;
         }
+        SubCmd::Serve {
+            listen,
+            amount,
+            cooldown_secs,
+        } => {
+            if amount.is_zero() {
+                return Err(eyre!("The amount sent for each claim can't be zero"));
+            }
+            let wallet = load_faucet_wallet(client).await;
+            let faucet = Faucet {
+                client: client.clone(),
+                wallet: Mutex::new(Some(wallet)),
+                amount,
+                claims: Mutex::new(Claims::new(Duration::from_secs(cooldown_secs))),
+            };
+            serve(Arc::new(faucet), listen).await?;
+        }
         SubCmd::Send { amount, to } => {
             let to = parse_public_address(to)?;
-            use std::str::FromStr;
-            let amount = Token::from_str(&amount)?;
-            if amount.as_nano() == 0 {
-                println!("Invalid format or zero amount passed in. Nothing sent.");
+            if amount.is_zero() {
+                println!("Zero amount passed in. Nothing sent.");
                 return Ok(());
             }
 
-            let dbc = get_tokens_from_faucet(amount, to, client).await;
+            let dbc = get_tokens_from_faucet(amount.into(), to, client).await;
             let dbc_hex = dbc.to_hex()?;
             println!("{dbc_hex}");
         }
     }
     Ok(())
 }
+
+/// The state of the faucet served over HTTP.
+struct Faucet {
+    client: Client,
+    /// Taken out while tokens are being sent, as the wallet client owns it meanwhile.
+    wallet: Mutex<Option<LocalWallet>>,
+    amount: TokenAmount,
+    claims: Mutex<Claims>,
+}
+
+impl Faucet {
+    async fn handle(&self, req: Request<Body>, requester: IpAddr) -> Response<Body> {
+        if req.method() != Method::GET {
+            return response(StatusCode::METHOD_NOT_ALLOWED, "Only GET is supported");
+        }
+        let address_hex = req.uri().path().trim_matches('/');
+        let address = match parse_public_address(address_hex) {
+            Ok(address) => address,
+            Err(_) => {
+                return response(
+                    StatusCode::BAD_REQUEST,
+                    "Expected a hex-encoded address, as `GET /<address>`",
+                )
+            }
+        };
+
+        // Claimed right away, so that concurrent claims of the same requester are refused.
+        // The address is keyed by its bytes, as differently encoded addresses are the same.
+        let claimants = [
+            Claimant::Requester(requester),
+            Claimant::Address(address.to_bytes().to_vec()),
+        ];
+        if let Err(wait) = self.claims.lock().await.claim(&claimants, Instant::now()) {
+            return response(
+                StatusCode::TOO_MANY_REQUESTS,
+                &format!(
+                    "Tokens were claimed recently, try again in {}s",
+                    wait.as_secs()
+                ),
+            );
+        }
+
+        match self.send(address).await {
+            Ok(dbc) => match dbc.to_hex() {
+                Ok(dbc_hex) => {
+                    info!(
+                        "Sent {} tokens to {address:?}, claimed from {requester}",
+                        self.amount
+                    );
+                    response(StatusCode::OK, &dbc_hex)
+                }
+                Err(err) => {
+                    warn!("Failed to encode the DBC sent to {address:?}: {err}");
+                    response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to encode the DBC",
+                    )
+                }
+            },
+            Err(err) => {
+                warn!("Failed to send tokens to {address:?}: {err}");
+                // The claim failed, thus it doesn't count.
+                self.claims.lock().await.withdraw(&claimants);
+                response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to send tokens")
+            }
+        }
+    }
+
+    /// Send the amount of a claim to the address, one claim at a time.
+    async fn send(&self, to: PublicAddress) -> Result<Dbc> {
+        let mut wallet_slot = self.wallet.lock().await;
+        let wallet = wallet_slot
+            .take()
+            .ok_or_else(|| eyre!("The faucet wallet is not available"))?;
+
+        let mut wallet_client = WalletClient::new(self.client.clone(), wallet);
+        let result = wallet_client.send(self.amount.into(), to).await;
+        let wallet = wallet_client.into_wallet();
+        if let Err(err) = wallet.store().await {
+            warn!("Failed to store the faucet wallet: {err}");
+        }
+        *wallet_slot = Some(wallet);
+
+        Ok(result?)
+    }
+}
+
+/// Who claims tokens from the faucet, each of them being allowed one claim per cooldown.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Claimant {
+    /// The IP address the claim was sent from.
+    Requester(IpAddr),
+    /// The bytes of the address the tokens are claimed for.
+    Address(Vec<u8>),
+}
+
+/// When each claimant last claimed tokens, within the cooldown.
+struct Claims {
+    cooldown: Duration,
+    last_claims: HashMap<Claimant, Instant>,
+}
+
+impl Claims {
+    fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            last_claims: HashMap::new(),
+        }
+    }
+
+    /// Record a claim by the claimants at the given time, unless one of them claimed within
+    /// the cooldown, in which case the time left to wait is returned.
+    fn claim(&mut self, claimants: &[Claimant], now: Instant) -> Result<(), Duration> {
+        // The claims older than the cooldown are forgotten, for them not to pile up.
+        let cooldown = self.cooldown;
+        self.last_claims
+            .retain(|_, last| now.saturating_duration_since(*last) < cooldown);
+
+        let wait = claimants
+            .iter()
+            .filter_map(|claimant| self.last_claims.get(claimant))
+            .map(|last| cooldown.saturating_sub(now.saturating_duration_since(*last)))
+            .max()
+            .unwrap_or_default();
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        for claimant in claimants {
+            let _ = self.last_claims.insert(claimant.clone(), now);
+        }
+        Ok(())
+    }
+
+    /// Forget the last claim of the claimants, e.g. as it failed.
+    fn withdraw(&mut self, claimants: &[Claimant]) {
+        for claimant in claimants {
+            let _ = self.last_claims.remove(claimant);
+        }
+    }
+}
+
+fn response(status: StatusCode, body: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+async fn serve(faucet: Arc<Faucet>, listen: SocketAddr) -> Result<()> {
+    let make_service = make_service_fn(move |conn: &AddrStream| {
+        let faucet = faucet.clone();
+        let requester = conn.remote_addr().ip();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let faucet = faucet.clone();
+                async move { Ok::<_, Infallible>(faucet.handle(req, requester).await) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&listen)?.serve(make_service);
+    println!("Faucet serving claims at http://{}", server.local_addr());
+    info!("Faucet serving claims at http://{}", server.local_addr());
+    server.await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::Ipv4Addr;
+
+    fn claimants(requester: u8, address: u8) -> [Claimant; 2] {
+        [
+            Claimant::Requester(IpAddr::from(Ipv4Addr::new(10, 0, 0, requester))),
+            Claimant::Address(vec![address; 48]),
+        ]
+    }
+
+    #[test]
+    fn claims_are_refused_within_the_cooldown() {
+        let mut claims = Claims::new(Duration::from_secs(60));
+        let start = Instant::now();
+        assert_eq!(claims.claim(&claimants(1, 1), start), Ok(()));
+
+        // Neither the same requester nor the same address can claim again within the cooldown.
+        let later = start + Duration::from_secs(20);
+        assert_eq!(
+            claims.claim(&claimants(1, 2), later),
+            Err(Duration::from_secs(40))
+        );
+        assert_eq!(
+            claims.claim(&claimants(2, 1), later),
+            Err(Duration::from_secs(40))
+        );
+        assert_eq!(claims.claim(&claimants(2, 2), later), Ok(()));
+
+        // Once the cooldown is over, they can.
+        let after_cooldown = start + Duration::from_secs(60);
+        assert_eq!(claims.claim(&claimants(1, 1), after_cooldown), Ok(()));
+    }
+
+    #[test]
+    fn claims_older_than_the_cooldown_are_forgotten() {
+        let mut claims = Claims::new(Duration::from_secs(60));
+        let start = Instant::now();
+        for claimant in 0..10 {
+            assert_eq!(claims.claim(&claimants(claimant, claimant), start), Ok(()));
+        }
+        assert_eq!(claims.last_claims.len(), 20);
+
+        let after_cooldown = start + Duration::from_secs(61);
+        assert_eq!(claims.claim(&claimants(42, 42), after_cooldown), Ok(()));
+        assert_eq!(claims.last_claims.len(), 2);
+    }
+
+    #[test]
+    fn withdrawn_claims_do_not_count() {
+        let mut claims = Claims::new(Duration::from_secs(60));
+        let start = Instant::now();
+        assert_eq!(claims.claim(&claimants(1, 1), start), Ok(()));
+        claims.withdraw(&claimants(1, 1));
+        assert_eq!(claims.claim(&claimants(1, 1), start), Ok(()));
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::output::Output;
+
+use safenode::{
+    client::{Client, ClientEvent, ClientEventsReceiver, Files},
+    protocol::storage::ChunkAddress,
+};
+
+use bytes::Bytes;
+use clap::Subcommand;
+use eyre::{eyre, Result};
+use futures::Future;
+use indicatif::HumanBytes;
+use rand::RngCore;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// The default size of the data uploaded or downloaded in each iteration: 10 MiB.
+const DEFAULT_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Subcommand, Debug)]
+pub enum BenchCmds {
+    /// Measure how fast random data is uploaded to the network.
+    Upload {
+        #[clap(flatten)]
+        params: BenchParams,
+        /// The number of chunks stored at the same time. When chunks are stored one at a time,
+        /// the time between two of them being stored is the latency of storing the latter.
+        #[clap(long, default_value_t = 1)]
+        concurrency: usize,
+    },
+    /// Measure how fast data is downloaded from the network.
+    /// Random data is uploaded first, which is not part of the measurements.
+    Download {
+        #[clap(flatten)]
+        params: BenchParams,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+pub struct BenchParams {
+    /// The size of the data, in bytes, uploaded or downloaded in each iteration.
+    #[clap(long, default_value_t = DEFAULT_SIZE)]
+    size: usize,
+    /// The number of times the data is uploaded or downloaded.
+    #[clap(long, default_value_t = 3)]
+    iterations: usize,
+}
+
+/// The measurements of a benchmark.
+#[derive(Serialize)]
+struct BenchSummary {
+    operation: &'static str,
+    iterations: usize,
+    bytes_per_iteration: usize,
+    total_secs: f64,
+    /// In bytes per second.
+    throughput: f64,
+    iteration_secs: Stats,
+    chunks: usize,
+    /// The time between consecutive chunks being completed.
+    chunk_latency_millis: Stats,
+}
+
+#[derive(Serialize, Default)]
+struct Stats {
+    min: f64,
+    mean: f64,
+    p50: f64,
+    p95: f64,
+    max: f64,
+}
+
+impl Stats {
+    fn from_samples(mut samples: Vec<f64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_by(|a, b| a.total_cmp(b));
+        let percentile = |p: f64| samples[((samples.len() - 1) as f64 * p).round() as usize];
+        Self {
+            min: samples[0],
+            mean: samples.iter().sum::<f64>() / samples.len() as f64,
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+pub(crate) async fn bench_cmds(cmds: BenchCmds, client: &Client, output: Output) -> Result<()> {
+    let summary = match cmds {
+        BenchCmds::Upload {
+            params,
+            concurrency,
+        } => {
+            let file_api = Files::new(client.clone())
+                .with_batch_size(concurrency)
+                .with_max_concurrent_chunks(concurrency);
+            bench_upload(&params, &file_api, client, output).await?
+        }
+        BenchCmds::Download { params } => {
+            let file_api = Files::new(client.clone());
+            bench_download(&params, &file_api, client, output).await?
+        }
+    };
+
+    output.result(&summary, || {
+        println!(
+            "{} of {} x {} iterations:",
+            summary.operation,
+            HumanBytes(summary.bytes_per_iteration as u64),
+            summary.iterations
+        );
+        println!(
+            "  throughput: {}/s over {:.2}s",
+            HumanBytes(summary.throughput as u64),
+            summary.total_secs
+        );
+        let stats = &summary.iteration_secs;
+        println!(
+            "  iteration: min {:.2}s, mean {:.2}s, max {:.2}s",
+            stats.min, stats.mean, stats.max
+        );
+        let stats = &summary.chunk_latency_millis;
+        println!(
+            "  {} chunks, latency: min {:.0}ms, p50 {:.0}ms, p95 {:.0}ms, max {:.0}ms",
+            summary.chunks, stats.min, stats.p50, stats.p95, stats.max
+        );
+    })
+}
+
+fn random_bytes(size: usize) -> Bytes {
+    let mut data = vec![0u8; size];
+    rand::thread_rng().fill_bytes(&mut data);
+    Bytes::from(data)
+}
+
+async fn bench_upload(
+    params: &BenchParams,
+    file_api: &Files,
+    client: &Client,
+    output: Output,
+) -> Result<BenchSummary> {
+    let mut iterations = vec![];
+    for iteration in 1..=params.iterations {
+        // Fresh data each time, as storing the same chunks again may be cheaper.
+        let bytes = random_bytes(params.size);
+        output.info(format!(
+            "Uploading {} ({iteration}/{})...",
+            HumanBytes(params.size as u64),
+            params.iterations
+        ));
+        let (result, measurement) = measure(client, file_api.upload(bytes)).await;
+        let _address = result?;
+        iterations.push(measurement);
+    }
+    Ok(summarise("upload", params, iterations))
+}
+
+async fn bench_download(
+    params: &BenchParams,
+    file_api: &Files,
+    client: &Client,
+    output: Output,
+) -> Result<BenchSummary> {
+    output.info(format!(
+        "Uploading {} of data to download...",
+        HumanBytes(params.size as u64)
+    ));
+    let address: ChunkAddress = file_api.upload(random_bytes(params.size)).await?;
+
+    let mut iterations = vec![];
+    for iteration in 1..=params.iterations {
+        output.info(format!(
+            "Downloading {} ({iteration}/{})...",
+            HumanBytes(params.size as u64),
+            params.iterations
+        ));
+        let (result, measurement) = measure(client, file_api.read_bytes(address)).await;
+        let bytes = result?;
+        if bytes.len() != params.size {
+            return Err(eyre!(
+                "Downloaded {} bytes rather than the {} uploaded",
+                bytes.len(),
+                params.size
+            ));
+        }
+        iterations.push(measurement);
+    }
+    Ok(summarise("download", params, iterations))
+}
+
+/// The time an operation took, and the time between its chunks being completed.
+struct Measurement {
+    duration: Duration,
+    chunk_latencies: Vec<Duration>,
+}
+
+/// Run the operation, timing it along with each of the chunks it stores or retrieves.
+async fn measure<T>(client: &Client, operation: impl Future<Output = T>) -> (T, Measurement) {
+    let mut events_rx: ClientEventsReceiver = client.events_channel();
+    let start = Instant::now();
+    let mut last_chunk = start;
+    let mut chunk_latencies = vec![];
+
+    tokio::pin!(operation);
+    let result = loop {
+        tokio::select! {
+            // Events are handled first, so those sent before the operation completes are all timed.
+            biased;
+            event = events_rx.recv() => {
+                if let Ok(ClientEvent::ChunkStored { .. }) | Ok(ClientEvent::ChunkRetrieved { .. }) = event {
+                    let now = Instant::now();
+                    chunk_latencies.push(now - last_chunk);
+                    last_chunk = now;
+                }
+            }
+            result = &mut operation => break result,
+        }
+    };
+
+    let measurement = Measurement {
+        duration: start.elapsed(),
+        chunk_latencies,
+    };
+    (result, measurement)
+}
+
+fn summarise(
+    operation: &'static str,
+    params: &BenchParams,
+    iterations: Vec<Measurement>,
+) -> BenchSummary {
+    let total: Duration = iterations.iter().map(|m| m.duration).sum();
+    let total_secs = total.as_secs_f64();
+    let throughput = if total_secs > 0.0 {
+        (params.size * iterations.len()) as f64 / total_secs
+    } else {
+        0.0
+    };
+    let chunk_latencies: Vec<f64> = iterations
+        .iter()
+        .flat_map(|m| &m.chunk_latencies)
+        .map(|latency| latency.as_secs_f64() * 1000.0)
+        .collect();
+
+    BenchSummary {
+        operation,
+        iterations: iterations.len(),
+        bytes_per_iteration: params.size,
+        total_secs,
+        throughput,
+        iteration_secs: Stats::from_samples(
+            iterations
+                .iter()
+                .map(|m| m.duration.as_secs_f64())
+                .collect(),
+        ),
+        chunks: chunk_latencies.len(),
+        chunk_latency_millis: Stats::from_samples(chunk_latencies),
+    }
+}
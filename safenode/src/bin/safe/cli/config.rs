@@ -0,0 +1,126 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use eyre::{eyre, Result};
+use libp2p::Multiaddr;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILE_NAME: &str = "config";
+
+/// Settings of the CLI persisted in the client dir, as a JSON document, so they don't
+/// need to be passed in on every invocation. Arguments given in the command line take precedence.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Peers to dial at start, used when no `--peer` is given and no network is selected.
+    pub(crate) peers: Vec<Multiaddr>,
+    /// Known networks, by name, as managed with `safe networks`.
+    pub(crate) networks: BTreeMap<String, NetworkDefinition>,
+    /// The name of the network to join, from the known `networks`.
+    pub(crate) network: Option<String>,
+    /// Always print the results of the commands as JSON documents.
+    pub(crate) json: bool,
+    /// The name of the key the client signs with.
+    pub(crate) key: Option<String>,
+    /// The maximum number of seconds to wait for the client to connect to the network.
+    pub(crate) connection_timeout_secs: Option<u64>,
+    /// The maximum number of requests sent to the network per second.
+    pub(crate) max_requests_per_sec: Option<f64>,
+    /// The maximum number of bytes of data stored or retrieved per second.
+    pub(crate) max_bandwidth: Option<f64>,
+    /// The url of the faucet to get test tokens from, used when no network is selected.
+    pub(crate) faucet: Option<String>,
+    /// Hex-encoded public addresses to send tokens to, by contact name, as managed with `safe contacts`.
+    pub(crate) contacts: BTreeMap<String, String>,
+    /// Files kept alive on the network, by hex-encoded address, as managed with `safe pin`.
+    pub(crate) pins: BTreeMap<String, Pin>,
+}
+
+/// A file kept alive on the network by `safe pin watch`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Pin {
+    /// The local copy of the file its missing chunks are stored again from.
+    pub(crate) local_copy: PathBuf,
+    /// The local copy was downloaded into the client dir when pinned, and is
+    /// deleted when unpinned.
+    #[serde(default)]
+    pub(crate) cached: bool,
+}
+
+/// How to join a network.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct NetworkDefinition {
+    /// The bootstrap peers to dial to join the network.
+    #[serde(default)]
+    pub(crate) peers: Vec<Multiaddr>,
+    /// The path or url of the contacts of the network, whose peers are dialed along with the
+    /// bootstrap ones, read each time the network is joined so they're kept up to date.
+    #[serde(default)]
+    pub(crate) contacts: Option<String>,
+    /// An identifier of the network, e.g. to tell testnets apart, for reference only.
+    #[serde(default)]
+    pub(crate) network_id: Option<String>,
+    /// The url of the faucet handing out test tokens on the network, if any.
+    #[serde(default)]
+    pub(crate) faucet: Option<String>,
+}
+
+impl Config {
+    /// Load the config from the client dir. A default config is returned if there's none.
+    pub(crate) fn load(root_dir: &Path) -> Result<Self> {
+        let path = root_dir.join(CONFIG_FILE_NAME);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .map_err(|err| eyre!("Invalid config file at {path:?}: {err}"))
+    }
+
+    /// Store the config to the client dir.
+    pub(crate) fn store(&self, root_dir: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(root_dir.join(CONFIG_FILE_NAME), content)?;
+        Ok(())
+    }
+
+    /// The peers to dial at start, from the selected network if any, or the default peers otherwise.
+    pub(crate) fn default_peers(&self) -> Result<Vec<Multiaddr>> {
+        match &self.network {
+            Some(network) => self
+                .networks
+                .get(network)
+                .map(|definition| definition.peers.clone())
+                .ok_or_else(|| eyre!("The configured network '{network}' is not known")),
+            None => Ok(self.peers.clone()),
+        }
+    }
+
+    /// The contacts of the selected network, if any.
+    pub(crate) fn default_contacts(&self) -> Option<String> {
+        self.network
+            .as_ref()
+            .and_then(|network| self.networks.get(network))
+            .and_then(|definition| definition.contacts.clone())
+    }
+
+    /// The url of the faucet of the selected network if any, or the default faucet otherwise.
+    pub(crate) fn faucet_url(&self) -> Option<String> {
+        match &self.network {
+            Some(network) => self
+                .networks
+                .get(network)
+                .and_then(|definition| definition.faucet.clone()),
+            None => self.faucet.clone(),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{config::Config, output::Output};
+
+use safenode::domain::wallet::parse_public_address;
+
+use clap::Subcommand;
+use eyre::{eyre, Result};
+use serde_json::json;
+use std::path::Path;
+
+#[derive(Subcommand, Debug)]
+pub enum ContactsCmds {
+    /// Add a contact, replacing any previous one with the same name.
+    Add {
+        /// The name of the contact, which can be given to `safe wallet send` instead of the address.
+        #[clap(name = "name")]
+        name: String,
+        /// The hex-encoded `PublicAddress` of the contact.
+        #[clap(name = "address")]
+        address: String,
+    },
+    /// List the contacts.
+    List,
+    /// Remove a contact.
+    Remove {
+        /// The name of the contact.
+        #[clap(name = "name")]
+        name: String,
+    },
+}
+
+pub(crate) fn contacts_cmds(cmds: ContactsCmds, root_dir: &Path, output: Output) -> Result<()> {
+    let mut config = Config::load(root_dir)?;
+    match cmds {
+        ContactsCmds::Add { name, address } => {
+            let address = address.trim().to_string();
+            let _ = parse_public_address(&address)
+                .map_err(|err| eyre!("The address is not a hex-encoded PublicAddress: {err}"))?;
+            let _ = config.contacts.insert(name.clone(), address);
+            config.store(root_dir)?;
+            output.info(format!("Contact '{name}' added."));
+        }
+        ContactsCmds::List => {}
+        ContactsCmds::Remove { name } => {
+            if config.contacts.remove(&name).is_none() {
+                return Err(eyre!("There is no contact named '{name}'"));
+            }
+            config.store(root_dir)?;
+            output.info(format!("Contact '{name}' removed."));
+        }
+    }
+
+    output.result(&json!(config.contacts), || {
+        if config.contacts.is_empty() {
+            println!("No contacts defined.");
+        }
+        for (name, address) in &config.contacts {
+            println!("{name}: {address}");
+        }
+    })
+}
@@ -0,0 +1,522 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    files::parse_xorname,
+    output::{
+        error_json, exit_code, Output, EXIT_CONNECTION, EXIT_INSUFFICIENT_FUNDS, EXIT_NOT_FOUND,
+        EXIT_TIMEOUT,
+    },
+    register::{entries_json, register_xorname, REGISTER_TAG},
+    wallet::load_wallet,
+};
+
+use safenode::{
+    client::{Client, Error as ClientError, Files, WalletClient},
+    domain::wallet::{parse_public_address, LocalWallet, OfflineTransfer, TokenAmount, Wallet},
+    protocol::{
+        storage::{ChunkAddress, DbcAddress},
+        xorurl::{DataType, XorUrl},
+    },
+};
+
+use bytes::{Bytes, BytesMut};
+use eyre::{eyre, Report, Result};
+use hyper::{
+    body::HttpBody,
+    header::{AUTHORIZATION, CONTENT_TYPE, ORIGIN, WWW_AUTHENTICATE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, sync::Mutex};
+use xor_name::XorName;
+
+/// The file of the wallet dir the token authenticating the requests to the daemon is written to.
+const TOKEN_FILE: &str = "daemon_token";
+
+/// The largest body of a request served, i.e. the largest file uploaded through the daemon.
+const MAX_BODY_SIZE: usize = 64 * 1024 * 1024;
+
+const JSON: &str = "application/json";
+const OCTET_STREAM: &str = "application/octet-stream";
+
+/// The state of the daemon, shared by the requests it serves.
+struct Daemon {
+    client: Client,
+    /// The bearer token every request must be authenticated with.
+    token: String,
+    /// Taken out while tokens are being sent, as the wallet client owns it meanwhile.
+    wallet: Mutex<Option<LocalWallet>>,
+}
+
+/// The body of a `POST /registers` request.
+#[derive(Deserialize)]
+struct CreateRegister {
+    name: String,
+}
+
+/// The body of a `POST /wallet/send` request.
+#[derive(Deserialize)]
+struct SendTokens {
+    amount: TokenAmount,
+    to: String,
+}
+
+/// Serve the HTTP+JSON API of the daemon on the listen address, until the process is stopped,
+/// running every request with the same connected client and the wallet of the wallet dir.
+/// The wallet is loaded once, i.e. its passphrase is only asked for at start.
+///
+/// A new token is generated at each start, and written to the wallet dir, only readable by the
+/// current user. Every request must carry it as a bearer token, and requests from web pages,
+/// i.e. carrying an `Origin` header, are rejected, for no page browsed to to use the wallet.
+pub(crate) async fn run_daemon(
+    client: &Client,
+    listen: SocketAddr,
+    wallet_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    if !listen.ip().is_loopback() {
+        output.info(format!(
+            "Warning: the daemon is reachable from other machines at {listen}, and \
+             anyone who can reach it can spend the tokens of the wallet."
+        ));
+    }
+    let wallet = load_wallet(wallet_dir).await?;
+    let (token, token_file) = write_token(wallet_dir).await?;
+    let daemon = Arc::new(Daemon {
+        client: client.clone(),
+        token,
+        wallet: Mutex::new(Some(wallet)),
+    });
+
+    let make_service = make_service_fn(move |_| {
+        let daemon = daemon.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let daemon = daemon.clone();
+                async move { Ok::<_, Infallible>(daemon.handle(req).await) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&listen)?.serve(make_service);
+    output.result(
+        &json!({
+            "listen": format!("http://{}", server.local_addr()),
+            "token_file": token_file,
+        }),
+        || {
+            println!("Daemon serving the API at http://{}", server.local_addr());
+            println!(
+                "Requests must carry the token of {} as a bearer token.",
+                token_file.display()
+            );
+        },
+    )?;
+    info!("Daemon serving the API at http://{}", server.local_addr());
+    server.await?;
+    Ok(())
+}
+
+impl Daemon {
+    async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        let method = req.method().clone();
+        let path = req.uri().path().trim_matches('/').to_string();
+        let segments: Vec<&str> = path.split('/').collect();
+        debug!("Daemon request: {method} /{path}");
+
+        if let Err(response) = check_access(&req, &self.token) {
+            return response;
+        }
+
+        // The bodies are JSON documents, or the raw content of a file or a register entry.
+        let body_type = match (&method, segments.as_slice()) {
+            (&Method::POST, ["registers"]) | (&Method::POST, ["wallet", "send"]) => Some(JSON),
+            (&Method::POST, ["files"]) | (&Method::POST, ["registers", _]) => Some(OCTET_STREAM),
+            _ => None,
+        };
+        if let Some(body_type) = body_type {
+            if !has_content_type(&req, body_type) {
+                return error_response(
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    eyre!("The body of {method} /{path} must be of type {body_type}"),
+                );
+            }
+        }
+
+        let body = match read_body(req.into_body(), MAX_BODY_SIZE).await {
+            Ok(body) => body,
+            Err(response) => return response,
+        };
+
+        let result = match (&method, segments.as_slice()) {
+            (&Method::POST, ["files"]) => self.upload(body).await,
+            (&Method::GET, ["files", address]) => return self.download(address).await,
+            (&Method::POST, ["registers"]) => match serde_json::from_slice(&body) {
+                Ok(CreateRegister { name }) => self.create_register(name).await,
+                Err(err) => Err(bad_request(err)),
+            },
+            (&Method::GET, ["registers", name]) => self.read_register(name).await,
+            (&Method::POST, ["registers", name]) => self.write_register(name, body.to_vec()).await,
+            (&Method::GET, ["wallet", "address"]) => self.address().await,
+            (&Method::GET, ["wallet", "balance"]) => self.balance().await,
+            (&Method::POST, ["wallet", "send"]) => match serde_json::from_slice(&body) {
+                Ok(SendTokens { amount, to }) => self.send(amount, &to).await,
+                Err(err) => Err(bad_request(err)),
+            },
+            _ => {
+                return error_response(
+                    StatusCode::NOT_FOUND,
+                    eyre!("No such endpoint: {method} /{path}"),
+                )
+            }
+        };
+
+        match result {
+            Ok(value) => json_response(StatusCode::OK, &value),
+            Err(err) => {
+                warn!("Daemon request {method} /{path} failed: {err:#}");
+                error_response(status_code(&err), err)
+            }
+        }
+    }
+
+    /// Store the body of the request as a file, returning its address.
+    async fn upload(&self, bytes: Bytes) -> Result<Value> {
+        let size = bytes.len();
+        let address = Files::new(self.client.clone()).upload(bytes).await?;
+        Ok(json!({
+            "address": format!("{:64x}", address.name()),
+            "url": XorUrl::new(DataType::File, *address.name()).to_string(),
+            "size": size,
+        }))
+    }
+
+    /// Answer with the content of the file at the address.
+    async fn download(&self, address: &str) -> Response<Body> {
+        let address = match parse_xorname(address) {
+            Ok(xorname) => ChunkAddress::new(xorname),
+            Err(err) => return error_response(StatusCode::BAD_REQUEST, err),
+        };
+        match Files::new(self.client.clone()).read_bytes(address).await {
+            Ok(bytes) => {
+                let mut response = Response::new(Body::from(bytes));
+                if let Ok(value) = OCTET_STREAM.parse() {
+                    let _ = response.headers_mut().insert(CONTENT_TYPE, value);
+                }
+                response
+            }
+            Err(err) => {
+                let err = Report::new(err);
+                error_response(status_code(&err), err)
+            }
+        }
+    }
+
+    async fn create_register(&self, name: String) -> Result<Value> {
+        let xorname = XorName::from_content(name.as_bytes());
+        let _register = self.client.create_register(xorname, REGISTER_TAG).await?;
+        Ok(register_json(&name, xorname))
+    }
+
+    async fn read_register(&self, name: &str) -> Result<Value> {
+        let register = self
+            .client
+            .get_register(register_xorname(name)?, REGISTER_TAG)
+            .await?;
+        let mut value = register_json(name, *register.name());
+        value["entries"] = Value::from(entries_json(&register.read(), false));
+        Ok(value)
+    }
+
+    /// Write the body of the request to the register, atop all its current entries,
+    /// merging them first if they are branched.
+    async fn write_register(&self, name: &str, entry: Vec<u8>) -> Result<Value> {
+        let mut register = self
+            .client
+            .get_register(register_xorname(name)?, REGISTER_TAG)
+            .await?;
+        let mut merged_branches = 0;
+        match register.write(&entry).await {
+            Ok(()) => {}
+            Err(ClientError::ContentBranchDetected(branches)) => {
+                merged_branches = branches.len();
+                register.write_merging_branches(&entry).await?;
+            }
+            Err(err) => return Err(err.into()),
+        }
+        let mut value = register_json(name, *register.name());
+        value["merged_branches"] = Value::from(merged_branches);
+        Ok(value)
+    }
+
+    async fn address(&self) -> Result<Value> {
+        let wallet = self.wallet.lock().await;
+        let wallet = wallet.as_ref().ok_or_else(wallet_unavailable)?;
+        Ok(json!({ "address": hex::encode(wallet.address().to_bytes()) }))
+    }
+
+    /// The balance of the wallet, once the DBCs deposited in the wallet dir are loaded.
+    async fn balance(&self) -> Result<Value> {
+        let mut wallet = self.wallet.lock().await;
+        let wallet = wallet.as_mut().ok_or_else(wallet_unavailable)?;
+        wallet.try_load_deposits().await?;
+        Ok(json!({ "balance": TokenAmount::from(wallet.balance()) }))
+    }
+
+    /// Send tokens to the address, one send at a time, storing the transfer for the
+    /// recipient in the wallet dir, as `safe wallet send` does.
+    async fn send(&self, amount: TokenAmount, to: &str) -> Result<Value> {
+        let address = parse_public_address(to)?;
+        if amount.is_zero() {
+            return Err(eyre!("Zero amount passed in. Nothing sent."));
+        }
+
+        let mut wallet_slot = self.wallet.lock().await;
+        let wallet = wallet_slot.take().ok_or_else(wallet_unavailable)?;
+        let mut wallet_client = WalletClient::new(self.client.clone(), wallet);
+        let result = wallet_client.send(amount.into(), address).await;
+        // Put back in its slot before anything can fail, for the next requests to use it.
+        let wallet = &*wallet_slot.insert(wallet_client.into_wallet());
+        if let Err(err) = wallet.store().await {
+            warn!("Failed to store the wallet: {err}");
+        }
+        let new_dbc = result?;
+
+        let dbc_id = hex::encode(DbcAddress::from_dbc_id(&new_dbc.id()).name());
+        let transfer = OfflineTransfer::new(vec![new_dbc], address, amount.into());
+        let transfer_file = wallet.store_transfer_file(&transfer).await?;
+
+        Ok(json!({
+            "sent": amount,
+            "to": hex::encode(address.to_bytes()),
+            "dbc_id": dbc_id,
+            "transfer_file": transfer_file,
+            "balance": TokenAmount::from(wallet.balance()),
+        }))
+    }
+}
+
+/// Generate a token and write it to the wallet dir, replacing the one of a previous start,
+/// the file being only readable and writable by the current user.
+async fn write_token(wallet_dir: &Path) -> Result<(String, PathBuf)> {
+    let token = hex::encode(rand::random::<[u8; 32]>());
+    let path = wallet_dir.join(TOKEN_FILE);
+    // Removed rather than truncated, for the file to be created with the permissions below.
+    match tokio::fs::remove_file(&path).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    let mut options = OpenOptions::new();
+    let _ = options.write(true).create_new(true);
+    #[cfg(unix)]
+    let _ = options.mode(0o600);
+    let mut file = options.open(&path).await?;
+    file.write_all(token.as_bytes()).await?;
+    file.flush().await?;
+    Ok((token, path))
+}
+
+/// Refuse the requests from web pages, and those which don't carry the token of the daemon
+/// as a bearer token, answering them with an error.
+fn check_access(req: &Request<Body>, token: &str) -> Result<(), Response<Body>> {
+    if req.headers().contains_key(ORIGIN) {
+        return Err(error_response(
+            StatusCode::FORBIDDEN,
+            eyre!("Requests from web pages are not served"),
+        ));
+    }
+    let authorized = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map_or(false, |given| {
+            constant_time_eq(given.trim().as_bytes(), token.as_bytes())
+        });
+    if !authorized {
+        let mut response = error_response(
+            StatusCode::UNAUTHORIZED,
+            eyre!("The request must carry the token of the daemon as a bearer token"),
+        );
+        if let Ok(value) = "Bearer".parse() {
+            let _ = response.headers_mut().insert(WWW_AUTHENTICATE, value);
+        }
+        return Err(response);
+    }
+    Ok(())
+}
+
+/// Compares the tokens in a time independent of where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Whether the body of the request is of the given media type, whatever its parameters.
+fn has_content_type(req: &Request<Body>, media_type: &str) -> bool {
+    req.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map_or(false, |value| value.trim().eq_ignore_ascii_case(media_type))
+}
+
+/// Read the body of a request, answering with an error if it's larger than the given size,
+/// i.e. [`MAX_BODY_SIZE`].
+async fn read_body(mut body: Body, max_size: usize) -> Result<Bytes, Response<Body>> {
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk
+            .map_err(|err| error_response(StatusCode::BAD_REQUEST, eyre!("Invalid body: {err}")))?;
+        if bytes.len() + chunk.len() > max_size {
+            return Err(error_response(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                eyre!("The body is larger than {max_size} bytes"),
+            ));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes.freeze())
+}
+
+fn register_json(name: &str, xorname: XorName) -> Value {
+    json!({
+        "name": name,
+        "address": format!("{xorname:x}"),
+        "tag": REGISTER_TAG,
+        "url": XorUrl::new(DataType::Register(REGISTER_TAG), xorname).to_string(),
+    })
+}
+
+fn wallet_unavailable() -> Report {
+    eyre!("The wallet is not available")
+}
+
+fn bad_request(err: serde_json::Error) -> Report {
+    Report::new(err).wrap_err("Invalid JSON body")
+}
+
+/// The HTTP status of a failed request, after the exit code the CLI would fail with.
+fn status_code(err: &Report) -> StatusCode {
+    if err
+        .chain()
+        .any(|cause| cause.downcast_ref::<serde_json::Error>().is_some())
+    {
+        return StatusCode::BAD_REQUEST;
+    }
+    match exit_code(err) {
+        EXIT_NOT_FOUND => StatusCode::NOT_FOUND,
+        EXIT_INSUFFICIENT_FUNDS => StatusCode::PAYMENT_REQUIRED,
+        EXIT_CONNECTION => StatusCode::SERVICE_UNAVAILABLE,
+        EXIT_TIMEOUT => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn json_response(status: StatusCode, value: &Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(value.to_string()));
+    *response.status_mut() = status;
+    if let Ok(value) = JSON.parse() {
+        let _ = response.headers_mut().insert(CONTENT_TYPE, value);
+    }
+    response
+}
+
+fn error_response(status: StatusCode, err: Report) -> Response<Body> {
+    json_response(status, &error_json(&err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use assert_fs::TempDir;
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn request(authorization: Option<&str>, origin: Option<&str>) -> Result<Request<Body>> {
+        let mut builder = Request::get("/wallet/balance");
+        if let Some(authorization) = authorization {
+            builder = builder.header(AUTHORIZATION, authorization);
+        }
+        if let Some(origin) = origin {
+            builder = builder.header(ORIGIN, origin);
+        }
+        Ok(builder.body(Body::empty())?)
+    }
+
+    fn status_of(result: Result<(), Response<Body>>) -> Option<StatusCode> {
+        result.err().map(|response| response.status())
+    }
+
+    #[test]
+    fn requests_without_the_token_are_unauthorized() -> Result<()> {
+        let missing = check_access(&request(None, None)?, TOKEN);
+        assert_eq!(status_of(missing), Some(StatusCode::UNAUTHORIZED));
+
+        let wrong = check_access(&request(Some("Bearer fedcba9876543210"), None)?, TOKEN);
+        assert_eq!(status_of(wrong), Some(StatusCode::UNAUTHORIZED));
+
+        let not_bearer = check_access(&request(Some(TOKEN), None)?, TOKEN);
+        assert_eq!(status_of(not_bearer), Some(StatusCode::UNAUTHORIZED));
+
+        let bearer = format!("Bearer {TOKEN}");
+        assert_eq!(
+            status_of(check_access(&request(Some(&bearer), None)?, TOKEN)),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn requests_from_web_pages_are_rejected() -> Result<()> {
+        let bearer = format!("Bearer {TOKEN}");
+        let result = check_access(&request(Some(&bearer), Some("https://example.com"))?, TOKEN);
+        assert_eq!(status_of(result), Some(StatusCode::FORBIDDEN));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bodies_larger_than_the_max_size_are_refused() {
+        let body = read_body(Body::from("1234"), 4).await;
+        assert_eq!(body.ok(), Some(Bytes::from("1234")));
+
+        let body = read_body(Body::from("12345"), 4).await;
+        assert_eq!(
+            body.err().map(|response| response.status()),
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn the_token_file_is_only_accessible_by_the_user() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let wallet_dir = TempDir::new()?;
+        // A token file left readable by others is replaced.
+        let stale = wallet_dir.path().join(TOKEN_FILE);
+        std::fs::write(&stale, "stale")?;
+        std::fs::set_permissions(&stale, std::fs::Permissions::from_mode(0o644))?;
+
+        let (token, path) = write_token(wallet_dir.path()).await?;
+        assert_eq!(path, stale);
+        assert_eq!(std::fs::read_to_string(&path)?, token);
+        let mode = std::fs::metadata(&path)?.permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        Ok(())
+    }
+}
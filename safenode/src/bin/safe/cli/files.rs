@@ -6,123 +6,951 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::{
+    import::import_dir,
+    names::{resolve_address, strip_scheme},
+    output::{Output, Verbosity},
+};
+
 use safenode::{
-    client::{Client, Files},
-    protocol::storage::ChunkAddress,
+    client::{
+        calculate_address, ChunkProvenance, Client, ClientEvent, Error as ClientError, Files,
+        FilesContainer,
+    },
+    domain::wallet::TokenAmount,
+    protocol::{
+        storage::ChunkAddress,
+        xorurl::{DataType, XorUrl, XOR_URL_SCHEME},
+    },
 };
 
 use bytes::Bytes;
 use clap::Parser;
-use eyre::Result;
+use eyre::{eyre, Report, Result};
+use futures::StreamExt;
+use indicatif::{HumanBytes, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
 use std::{
+    collections::BTreeSet,
     fs,
     path::{Path, PathBuf},
 };
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    sync::broadcast::error::RecvError,
+    task::JoinHandle,
+};
 use walkdir::WalkDir;
 use xor_name::XorName;
 
+/// The path given to upload the data read from stdin.
+const STDIN_PATH: &str = "-";
+
 #[derive(Parser, Debug)]
 pub enum FilesCmds {
+    /// Upload files and directories, recording the relative path of every file
+    /// in a FilesContainer which is uploaded as well.
     Upload {
-        /// The location of the files to upload.
-        #[clap(name = "path", value_name = "DIRECTORY")]
-        path: PathBuf,
+        /// The files or directories to upload, or glob patterns matching them.
+        /// Directories are uploaded recursively.
+        /// Give `-` alone to upload the data read from stdin as a single file, only
+        /// printing its address, e.g. `tar cz dir | safe files upload -`.
+        #[clap(name = "path", value_name = "PATH", required = true)]
+        paths: Vec<PathBuf>,
+        /// Only estimate the cost of storing the files, without uploading them.
+        #[clap(long)]
+        dry_run: bool,
+        /// The number of chunks of a file uploaded in each batch, the next batch
+        /// starting once all the chunks of the previous one were stored.
+        #[clap(long)]
+        batch_size: Option<usize>,
+        /// The maximum number of chunks being uploaded at the same time.
+        /// Raise it to make the most of a fast link, or lower it on a weak network.
+        #[clap(long)]
+        max_concurrent_chunks: Option<usize>,
     },
+    /// Import a large directory, e.g. to migrate terabytes onto the network, uploading many
+    /// files at a time. The files stored are checkpointed as they are, so an interrupted import
+    /// resumes where it stopped when run again. A manifest of the address of every file, keyed
+    /// by its path relative to the directory, is written once done.
+    ImportDir {
+        /// The directory to import.
+        #[clap(name = "dir")]
+        dir: PathBuf,
+        /// The number of files uploaded at the same time.
+        #[clap(long, default_value_t = 8)]
+        jobs: usize,
+        /// The file recording the files stored so far, to resume the import from.
+        /// Defaults to a file named after the directory under the client's `imports` directory.
+        #[clap(long)]
+        checkpoint: Option<PathBuf>,
+        /// Where to write the manifest of the files stored.
+        /// Defaults to a file named after the directory under the client's `imports` directory.
+        #[clap(long)]
+        manifest: Option<PathBuf>,
+        /// The maximum number of chunks being uploaded at the same time, across all the files.
+        #[clap(long)]
+        max_concurrent_chunks: Option<usize>,
+    },
+    /// Download a file by its network address, or, when no address is given,
+    /// all the files recorded as uploaded from this device.
     Download {
-        /// Name of the file to download.
-        #[clap(name = "file_name")]
-        file_name: Option<String>,
-        /// Address of the file to download, in hex string.
-        #[clap(name = "file_addr")]
-        file_addr: Option<String>,
+        /// Address of the file to download, in hex string, as a url, or as `safe://<name>`.
+        #[clap(name = "address")]
+        address: Option<String>,
+        /// Where to write the downloaded file. If it's an existing directory, the
+        /// file is written into it, named after its address.
+        /// Defaults to the client's `downloaded_files` directory.
+        #[clap(name = "dest")]
+        dest: Option<PathBuf>,
+        /// Report which peer served each chunk, how long it took, and how many times it was
+        /// retried, e.g. to diagnose a slow or failing download, or report problematic nodes.
+        #[clap(long)]
+        trace: bool,
+    },
+    /// List the files stored in a FilesContainer.
+    Ls {
+        /// Address of the FilesContainer, in hex string, as a url, or as `safe://<name>`.
+        #[clap(name = "container_address")]
+        container_address: String,
+        /// Display the files as a tree of directories.
+        #[clap(long)]
+        tree: bool,
+    },
+    /// Print a shareable url of a file or FilesContainer, accepted anywhere an address is.
+    Share {
+        /// Address of the file, in hex string.
+        #[clap(name = "address")]
+        address: String,
+        /// The address is that of a FilesContainer rather than a file.
+        #[clap(long)]
+        container: bool,
     },
+    /// Synchronise a FilesContainer with a local directory, only uploading the files which are
+    /// new or changed, and upload the resulting FilesContainer. Its address changes with its
+    /// content, thus give `--name` to have a name map to the latest one.
+    Sync {
+        /// The local directory, whose files are recorded with their paths relative to it.
+        #[clap(name = "dir")]
+        dir: PathBuf,
+        /// Address of the FilesContainer, in hex string, as a url, or as `safe://<name>`.
+        #[clap(name = "container_address")]
+        container_address: String,
+        /// Remove the files which are no longer in the local directory from the FilesContainer.
+        #[clap(long)]
+        prune: bool,
+        /// A name owned by the client, mapped to the synchronised FilesContainer once uploaded.
+        #[clap(long)]
+        name: Option<String>,
+    },
+    /// Verify every chunk of a file can be retrieved from the network, e.g. to confirm
+    /// a backup actually landed. Optionally check it holds the same content as a local file.
+    Verify {
+        /// Address of the file, in hex string, as a url, or as `safe://<name>`.
+        #[clap(name = "address")]
+        address: String,
+        /// A local file to compare the content of the file on the network against.
+        #[clap(long)]
+        file: Option<PathBuf>,
+    },
+}
+
+/// The outcome of uploading, downloading, or estimating the cost of a single file.
+#[derive(Serialize, Default)]
+struct FileResult {
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost: Option<TokenAmount>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// The provenance of the chunks retrieved, when downloading with `--trace`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chunks: Option<Vec<ChunkTrace>>,
+    /// The error the file failed with, for the process to exit with its code.
+    #[serde(skip)]
+    failure: Option<Report>,
+}
+
+impl FileResult {
+    fn fail(&mut self, error: impl Into<Report>) {
+        let error = error.into();
+        self.error = Some(error.to_string());
+        self.failure = Some(error);
+    }
+}
+
+/// Fails with the error of the first file which failed, if any, once the results are printed.
+fn first_failure(results: Vec<FileResult>) -> Result<()> {
+    let failed = results
+        .iter()
+        .filter(|result| result.failure.is_some())
+        .count();
+    match results.into_iter().find_map(|result| result.failure) {
+        Some(error) => Err(error.wrap_err(format!("{failed} file/s failed"))),
+        None => Ok(()),
+    }
+}
+
+/// Where a chunk of a file downloaded with `--trace` was retrieved from.
+#[derive(Serialize)]
+struct ChunkTrace {
+    address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peer: Option<String>,
+    elapsed_ms: u128,
+    retries: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    wrong_chunk_peers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl From<ChunkProvenance> for ChunkTrace {
+    fn from(provenance: ChunkProvenance) -> Self {
+        Self {
+            address: format!("{:64x}", provenance.address.name()),
+            peer: provenance.peer.map(|peer| peer.to_string()),
+            elapsed_ms: provenance.elapsed.as_millis(),
+            retries: provenance.retries(),
+            wrong_chunk_peers: provenance
+                .wrong_chunk_peers
+                .iter()
+                .map(|peer| peer.to_string())
+                .collect(),
+            error: provenance.error,
+        }
+    }
+}
+
+pub(crate) async fn files_cmds(
+    cmds: FilesCmds,
+    client: Client,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    let mut file_api: Files = Files::new(client.clone());
+    if let FilesCmds::Upload {
+        batch_size,
+        max_concurrent_chunks,
+        ..
+    } = &cmds
+    {
+        if let Some(batch_size) = batch_size {
+            file_api = file_api.with_batch_size(*batch_size);
+        }
+        if let Some(max_concurrent_chunks) = max_concurrent_chunks {
+            file_api = file_api.with_max_concurrent_chunks(*max_concurrent_chunks);
+        }
+    }
+    if let FilesCmds::ImportDir {
+        max_concurrent_chunks: Some(max_concurrent_chunks),
+        ..
+    } = &cmds
+    {
+        file_api = file_api.with_max_concurrent_chunks(*max_concurrent_chunks);
+    }
+    let progress = ChunksProgress::start(&client, output);
+    let result = run_files_cmd(cmds, &client, &file_api, root_dir, output).await;
+    progress.finish();
+    result
 }
 
-pub(crate) async fn files_cmds(cmds: FilesCmds, client: Client, root_dir: &Path) -> Result<()> {
-    let file_api: Files = Files::new(client);
+async fn run_files_cmd(
+    cmds: FilesCmds,
+    client: &Client,
+    file_api: &Files,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
     match cmds {
-        FilesCmds::Upload { path } => upload_files(path, &file_api, root_dir).await?,
+        FilesCmds::Upload { paths, dry_run, .. } if reads_stdin(&paths)? => {
+            upload_stdin(dry_run, client, file_api, output).await?
+        }
+        FilesCmds::Upload {
+            paths,
+            dry_run: true,
+            ..
+        } => estimate_files_cost(&paths, client, output).await?,
+        FilesCmds::Upload {
+            paths,
+            dry_run: false,
+            ..
+        } => upload_files(&paths, file_api, root_dir, output).await?,
+        FilesCmds::ImportDir {
+            dir,
+            jobs,
+            checkpoint,
+            manifest,
+            ..
+        } => import_dir(&dir, jobs, checkpoint, manifest, file_api, root_dir, output).await?,
         FilesCmds::Download {
-            file_name,
-            file_addr,
-        } => match (file_name, file_addr) {
-            (Some(name), Some(address)) => {
-                let bytes = hex::decode(address).expect("Input address is not a hex string");
-                download_file(
-                    &file_api,
-                    &XorName(
-                        bytes
-                            .try_into()
-                            .expect("Failed to parse XorName from hex string"),
-                    ),
-                    &name,
-                    root_dir,
-                )
-                .await
-            }
-            _ => {
-                println!("Trying to download files recorded in uploaded_files folder");
-                download_files(&file_api, root_dir).await?
-            }
-        },
+            address: Some(address),
+            dest,
+            trace,
+        } => {
+            let address = resolve_address(&address, client).await?;
+            let xorname = parse_xorname(&address)?;
+            let file_name = format!("{xorname:64x}");
+            let dest = match dest {
+                Some(dest) if dest.is_dir() => dest.join(&file_name),
+                Some(dest) => dest,
+                None => {
+                    let download_path = root_dir.join("downloaded_files");
+                    tokio::fs::create_dir_all(download_path.as_path()).await?;
+                    download_path.join(&file_name)
+                }
+            };
+            let results =
+                vec![download_file(file_api, &xorname, &address, &dest, trace, output).await];
+            output.result(&results, || {})?;
+            first_failure(results)?
+        }
+        FilesCmds::Download {
+            address: None,
+            trace,
+            ..
+        } => {
+            output.info("Trying to download files recorded in uploaded_files folder");
+            download_files(file_api, root_dir, trace, output).await?
+        }
+        FilesCmds::Ls {
+            container_address,
+            tree,
+        } => {
+            let container_address = resolve_address(&container_address, client).await?;
+            list_files(&container_address, tree, file_api, output).await?
+        }
+        FilesCmds::Share { address, container } => share_file(&address, container, output)?,
+        FilesCmds::Sync {
+            dir,
+            container_address,
+            prune,
+            name,
+        } => {
+            let container_address = resolve_address(&container_address, client).await?;
+            sync_files(
+                &dir,
+                &container_address,
+                prune,
+                name.as_deref(),
+                client,
+                file_api,
+                output,
+            )
+            .await?
+        }
+        FilesCmds::Verify { address, file } => {
+            let address = resolve_address(&address, client).await?;
+            verify_file(&address, file.as_deref(), file_api, output).await?
+        }
     };
     Ok(())
 }
 
-async fn upload_files(files_path: PathBuf, file_api: &Files, root_dir: &Path) -> Result<()> {
-    // The input files_path has to be a dir
-    let file_names_path = root_dir.join("uploaded_files");
-    let mut chunks_to_fetch = Vec::new();
+/// Progress bar, drawn on stderr, of the chunks stored or retrieved by the client.
+struct ChunksProgress {
+    bar: ProgressBar,
+    handle: JoinHandle<()>,
+}
 
-    for entry in WalkDir::new(files_path).into_iter().flatten() {
-        if entry.file_type().is_file() {
-            let file = fs::read(entry.path())?;
-            let bytes = Bytes::from(file);
-            let file_name = if let Some(file_name) = entry.file_name().to_str() {
-                file_name.to_string()
-            } else {
-                println!(
-                    "Skipping file {:?} as it is not valid UTF-8.",
-                    entry.file_name()
-                );
-                continue;
-            };
+impl ChunksProgress {
+    /// Start tracking the client's chunk events. Nothing is drawn when quiet,
+    /// while each chunk is reported when very verbose.
+    fn start(client: &Client, output: Output) -> Self {
+        let draw_target = if output.verbosity() == Verbosity::Quiet {
+            ProgressDrawTarget::hidden()
+        } else {
+            ProgressDrawTarget::stderr()
+        };
+        let report_chunks = output.verbosity() >= Verbosity::Debug;
+        let bar = ProgressBar::with_draw_target(Some(0), draw_target);
+        bar.set_style(
+            ProgressStyle::with_template(
+                "[{elapsed_precise}] {wide_bar} {pos}/{len} chunks ({msg}) ETA {eta}",
+            )
+            .unwrap_or_else(|_| ProgressStyle::default_bar()),
+        );
 
-            println!("Storing file {file_name:?} of {} bytes..", bytes.len());
-
-            match file_api.upload(bytes).await {
-                Ok(address) => {
-                    // Output address in hex string.
-                    println!(
-                        "Successfully stored file {:?} to {:64x}",
-                        entry.file_name(),
-                        address.name()
-                    );
-                    chunks_to_fetch.push((*address.name(), file_name));
+        let mut events_rx = client.events_channel();
+        let bar_clone = bar.clone();
+        let handle = tokio::spawn(async move {
+            let mut bytes = 0;
+            loop {
+                match events_rx.recv().await {
+                    Ok(ClientEvent::UploadStarted { chunks, .. })
+                    | Ok(ClientEvent::DownloadStarted { chunks }) => {
+                        bar_clone.inc_length(chunks as u64)
+                    }
+                    Ok(ClientEvent::ChunkStored { address, size }) => {
+                        if report_chunks {
+                            bar_clone.println(format!(
+                                "Stored chunk {:64x} of {size} bytes",
+                                address.name()
+                            ));
+                        }
+                        bytes += size as u64;
+                        bar_clone.inc(1);
+                        bar_clone.set_message(HumanBytes(bytes).to_string());
+                    }
+                    Ok(ClientEvent::ChunkRetrieved { address, size }) => {
+                        if report_chunks {
+                            bar_clone.println(format!(
+                                "Retrieved chunk {:64x} of {size} bytes",
+                                address.name()
+                            ));
+                        }
+                        bytes += size as u64;
+                        bar_clone.inc(1);
+                        bar_clone.set_message(HumanBytes(bytes).to_string());
+                    }
+                    Ok(_) => {}
+                    // some events were missed, the bar will be off but can keep going
+                    Err(ClientError::EventsReceiver(RecvError::Lagged(_))) => {}
+                    Err(_) => break,
                 }
-                Err(error) => {
-                    println!(
-                        "Did not store file {file_name:?} to all nodes in the close group! {error}"
-                    )
+            }
+        });
+
+        Self { bar, handle }
+    }
+
+    fn finish(self) {
+        self.handle.abort();
+        self.bar.finish();
+    }
+}
+
+/// Stream the contents of the file at the given address to stdout.
+pub(crate) async fn cat_file(address: &str, client: Client) -> Result<()> {
+    let address = &resolve_address(address, &client).await?;
+    let file_api = Files::new(client);
+    let address = ChunkAddress::new(parse_xorname(address)?);
+
+    let mut stdout = tokio::io::stdout();
+    let mut content = file_api.read_stream(address).await?;
+    while let Some(bytes) = content.next().await {
+        stdout.write_all(&bytes?).await?;
+    }
+    stdout.flush().await?;
+
+    Ok(())
+}
+
+/// Parse a network address, either hex encoded or as the url of a file or FilesContainer, into a `XorName`.
+pub(crate) fn parse_xorname(address: &str) -> Result<XorName> {
+    if address.starts_with(XOR_URL_SCHEME) {
+        let url: XorUrl = address.parse()?;
+        return match url.data_type {
+            DataType::File | DataType::FilesContainer => Ok(url.name),
+            data_type => Err(eyre!(
+                "The url points at a {data_type:?}, not a file or FilesContainer"
+            )),
+        };
+    }
+    let bytes = hex::decode(address).map_err(|err| eyre!("Address is not a hex string: {err}"))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| eyre!("Address must be 32 bytes long, i.e. 64 hex characters"))?;
+    Ok(XorName(bytes))
+}
+
+/// Resolve the given paths, expanding glob patterns and walking directories, into
+/// the files to upload along with their paths relative to the directory they were found in.
+/// The name of an uploaded directory is kept as the first component of its files' paths.
+fn files_to_upload(paths: &[PathBuf]) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        let matched_paths = if path.exists() {
+            vec![path.clone()]
+        } else {
+            let pattern = path
+                .to_str()
+                .ok_or_else(|| eyre!("Path {path:?} is not valid UTF-8"))?;
+            let matched_paths = glob::glob(pattern)?.collect::<Result<Vec<_>, _>>()?;
+            if matched_paths.is_empty() {
+                return Err(eyre!("No files found at {path:?}"));
+            }
+            matched_paths
+        };
+
+        for matched_path in matched_paths {
+            let base = matched_path.parent().unwrap_or_else(|| Path::new(""));
+            for entry in WalkDir::new(&matched_path).into_iter().flatten() {
+                if entry.file_type().is_file() {
+                    let relative_path = entry.path().strip_prefix(base)?.to_path_buf();
+                    files.push((entry.path().to_path_buf(), relative_path));
                 }
-            };
+            }
         }
     }
 
+    Ok(files)
+}
+
+async fn upload_files(
+    paths: &[PathBuf],
+    file_api: &Files,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    let file_names_path = root_dir.join("uploaded_files");
+    let mut chunks_to_fetch = Vec::new();
+    let mut container = FilesContainer::new();
+    let mut results = Vec::new();
+
+    for (path, relative_path) in files_to_upload(paths)? {
+        let file = fs::read(&path)?;
+        let bytes = Bytes::from(file);
+        let file_name = if let Some(file_name) = path.file_name().and_then(|name| name.to_str()) {
+            file_name.to_string()
+        } else {
+            output.info(format!("Skipping file {path:?} as it is not valid UTF-8."));
+            continue;
+        };
+
+        output.info(format!(
+            "Storing file {relative_path:?} of {} bytes..",
+            bytes.len()
+        ));
+
+        let size = bytes.len() as u64;
+        let mut result = FileResult {
+            path: relative_path.display().to_string(),
+            size: Some(size),
+            ..Default::default()
+        };
+        match file_api.upload(bytes).await {
+            Ok(address) => {
+                // Output address in hex string.
+                output.info(format!(
+                    "Successfully stored file {relative_path:?} to {:64x}",
+                    address.name()
+                ));
+                chunks_to_fetch.push((*address.name(), file_name));
+                container.add_file(&relative_path, address, size);
+                result.address = Some(format!("{:64x}", address.name()));
+            }
+            Err(error) => {
+                output.info(format!(
+                    "Did not store file {relative_path:?} to all nodes in the close group! {error}"
+                ));
+                result.fail(error);
+            }
+        };
+        results.push(result);
+    }
+
     let content = bincode::serialize(&chunks_to_fetch)?;
     tokio::fs::create_dir_all(file_names_path.as_path()).await?;
     let date_time = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
     let file_names_path = file_names_path.join(format!("file_names_{date_time}"));
-    println!("Writing {} bytes to {file_names_path:?}", content.len());
+    output.info(format!(
+        "Writing {} bytes to {file_names_path:?}",
+        content.len()
+    ));
     fs::write(file_names_path, content)?;
 
+    let container_address = if container.is_empty() {
+        output.info("No files were stored, thus no FilesContainer was created.");
+        None
+    } else {
+        let container_address = container.upload(file_api).await?;
+        output.info(format!(
+            "Successfully stored FilesContainer with {} files to {:64x}",
+            container.len(),
+            container_address.name()
+        ));
+        output.info(format!(
+            "Share it with {}",
+            XorUrl::new(DataType::FilesContainer, *container_address.name())
+        ));
+        Some(format!("{:64x}", container_address.name()))
+    };
+
+    output.result(
+        &serde_json::json!({ "files": results, "container": container_address }),
+        || {},
+    )?;
+    first_failure(results)
+}
+
+/// Whether the data to upload is read from stdin, i.e. the only path given is `-`.
+fn reads_stdin(paths: &[PathBuf]) -> Result<bool> {
+    let stdin = Path::new(STDIN_PATH);
+    match paths {
+        [path] => Ok(path == stdin),
+        paths if paths.iter().any(|path| path == stdin) => Err(eyre!(
+            "`{STDIN_PATH}` uploads the data read from stdin, and can't be given along with other paths"
+        )),
+        _ => Ok(false),
+    }
+}
+
+/// Upload the data read from stdin as a single file, printing nothing but its address, for
+/// scripts to keep. It's not recorded in the `uploaded_files` as it has no file name.
+async fn upload_stdin(
+    dry_run: bool,
+    client: &Client,
+    file_api: &Files,
+    output: Output,
+) -> Result<()> {
+    if dry_run {
+        let mut bytes = vec![];
+        let size = tokio::io::stdin().read_to_end(&mut bytes).await?;
+        let cost = TokenAmount::from(client.estimate_file_cost(Bytes::from(bytes)).await?);
+        return output.result(&serde_json::json!({ "size": size, "cost": cost }), || {
+            println!("Storing the {size} bytes read from stdin would cost {cost} tokens")
+        });
+    }
+
+    let address = file_api.upload_from_reader(tokio::io::stdin()).await?;
+    let url = XorUrl::new(DataType::File, *address.name());
+    output.result(
+        &serde_json::json!({
+            "address": format!("{:64x}", address.name()),
+            "url": url.to_string(),
+        }),
+        || println!("{:64x}", address.name()),
+    )
+}
+
+/// Upload the files of the directory which are not in the container, or whose content differs
+/// from that of the file at the same path in it, then the updated container, if it changed.
+async fn sync_files(
+    dir: &Path,
+    container_address: &str,
+    prune: bool,
+    name: Option<&str>,
+    client: &Client,
+    file_api: &Files,
+    output: Output,
+) -> Result<()> {
+    if !dir.is_dir() {
+        return Err(eyre!("{dir:?} is not a directory"));
+    }
+    let previous_address = ChunkAddress::new(parse_xorname(container_address)?);
+    let mut container = FilesContainer::retrieve(file_api, previous_address).await?;
+
+    let mut local_paths = BTreeSet::new();
+    let mut uploaded = Vec::new();
+    let mut failed = Vec::new();
+    let mut unchanged = 0;
+    for entry in WalkDir::new(dir).into_iter().flatten() {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative_path = entry.path().strip_prefix(dir)?;
+        let path = FilesContainer::path_key(relative_path);
+        let _ = local_paths.insert(path.clone());
+
+        let bytes = Bytes::from(fs::read(entry.path())?);
+        let size = bytes.len() as u64;
+        // Only files of the same size can have the same content.
+        if let Some(stored) = container.get(&path).filter(|stored| stored.size == size) {
+            if calculate_address(bytes.clone())? == *stored.address.name() {
+                unchanged += 1;
+                continue;
+            }
+        }
+
+        output.info(format!("Storing file {path:?} of {size} bytes.."));
+        match file_api.upload(bytes).await {
+            Ok(address) => {
+                container.add_file(relative_path, address, size);
+                uploaded.push(path);
+            }
+            Err(error) => {
+                output.info(format!("Did not store file {path:?}: {error}"));
+                failed.push(FileResult {
+                    path,
+                    size: Some(size),
+                    error: Some(error.to_string()),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    let mut removed = Vec::new();
+    if prune {
+        let stale: Vec<String> = container
+            .files()
+            .map(|(path, _)| path.clone())
+            .filter(|path| !local_paths.contains(path))
+            .collect();
+        for path in stale {
+            let _ = container.remove_file(&path);
+            removed.push(path);
+        }
+    }
+
+    let address = if uploaded.is_empty() && removed.is_empty() {
+        output.info("The FilesContainer is already in sync, thus it was not uploaded again.");
+        previous_address
+    } else {
+        container.upload(file_api).await?
+    };
+    let url = XorUrl::new(DataType::FilesContainer, *address.name());
+    let version = match name {
+        Some(name) => Some(
+            client
+                .register_name(strip_scheme(name), &url)
+                .await
+                .map_err(|err| eyre!(err).wrap_err(format!("Failed to map {name} to {url}")))?,
+        ),
+        None => None,
+    };
+
+    output.result(
+        &serde_json::json!({
+            "container": format!("{:64x}", address.name()),
+            "url": url.to_string(),
+            "uploaded": uploaded,
+            "unchanged": unchanged,
+            "removed": removed,
+            "failed": failed,
+            "name": name,
+            "name_version": version,
+        }),
+        || {
+            println!(
+                "{} files uploaded, {unchanged} unchanged, {} removed, {} failed.",
+                uploaded.len(),
+                removed.len(),
+                failed.len()
+            );
+            println!("FilesContainer with {} files at {url}", container.len());
+            if let (Some(name), Some(version)) = (name, version) {
+                println!("Name '{name}' now maps to it (version {version}).");
+            }
+        },
+    )
+}
+
+async fn estimate_files_cost(paths: &[PathBuf], client: &Client, output: Output) -> Result<()> {
+    let mut total_cost = TokenAmount::default();
+    let mut results = Vec::new();
+
+    for (path, relative_path) in files_to_upload(paths)? {
+        let file = fs::read(&path)?;
+        let bytes = Bytes::from(file);
+
+        let mut result = FileResult {
+            path: relative_path.display().to_string(),
+            size: Some(bytes.len() as u64),
+            ..Default::default()
+        };
+        match client.estimate_file_cost(bytes.clone()).await {
+            Ok(cost) => {
+                let cost = TokenAmount::from(cost);
+                output.info(format!(
+                    "Storing file {relative_path:?} of {} bytes would cost {cost} tokens",
+                    bytes.len()
+                ));
+                total_cost = total_cost
+                    .checked_add(cost)
+                    .ok_or_else(|| eyre!("Total cost overflow"))?;
+                result.cost = Some(cost);
+            }
+            Err(error) => {
+                output.info(format!(
+                    "Could not estimate the cost of storing file {relative_path:?}: {error}"
+                ));
+                result.error = Some(error.to_string());
+            }
+        }
+        results.push(result);
+    }
+
+    output.result(
+        &serde_json::json!({ "files": results, "total_cost": total_cost }),
+        || println!("Total cost of storing the files would be {total_cost} tokens"),
+    )
+}
+
+async fn list_files(
+    container_address: &str,
+    tree: bool,
+    file_api: &Files,
+    output: Output,
+) -> Result<()> {
+    let address = ChunkAddress::new(parse_xorname(container_address)?);
+    let container = FilesContainer::retrieve(file_api, address).await?;
+
+    let files: Vec<_> = container
+        .files()
+        .map(|(path, entry)| FileResult {
+            path: path.clone(),
+            address: Some(format!("{:64x}", entry.address.name())),
+            size: Some(entry.size),
+            ..Default::default()
+        })
+        .collect();
+
+    output.result(
+        &serde_json::json!({ "container": container_address, "files": files }),
+        || {
+            if container.is_empty() {
+                println!("The FilesContainer at {container_address} is empty.");
+                return;
+            }
+
+            if tree {
+                print_files_tree(&container);
+            } else {
+                for (path, entry) in container.files() {
+                    println!("{:64x}  {:>12}  {path}", entry.address.name(), entry.size);
+                }
+            }
+
+            println!(
+                "{} files, {} bytes in total",
+                container.len(),
+                container.files().map(|(_, entry)| entry.size).sum::<u64>()
+            );
+        },
+    )
+}
+
+/// Print the url of the file, or FilesContainer, at the given address.
+pub(crate) fn share_file(address: &str, container: bool, output: Output) -> Result<()> {
+    let data_type = if container {
+        DataType::FilesContainer
+    } else {
+        DataType::File
+    };
+    let url = XorUrl::new(data_type, parse_xorname(address)?);
+    output.result(
+        &serde_json::json!({ "address": format!("{:64x}", url.name), "url": url.to_string() }),
+        || println!("{url}"),
+    )
+}
+
+async fn verify_file(
+    address: &str,
+    local_file: Option<&Path>,
+    file_api: &Files,
+    output: Output,
+) -> Result<()> {
+    let xorname = parse_xorname(address)?;
+
+    // The address of a file is derived from its content, thus re-chunking the local
+    // file yields the same address only if the contents are the same.
+    let matches_local_file = match local_file {
+        Some(path) => {
+            let bytes = Bytes::from(tokio::fs::read(path).await?);
+            Some(calculate_address(bytes)? == xorname)
+        }
+        None => None,
+    };
+
+    output.info(format!("Verifying the chunks of file {address}..."));
+    let report = file_api.verify(ChunkAddress::new(xorname)).await?;
+    let chunks: Vec<_> = report
+        .chunks
+        .iter()
+        .map(|(chunk_address, error)| {
+            serde_json::json!({
+                "address": format!("{:64x}", chunk_address.name()),
+                "error": error,
+            })
+        })
+        .collect();
+    let missing_chunks = report.missing_chunks();
+
+    output.result(
+        &serde_json::json!({
+            "address": address,
+            "complete": report.is_complete(),
+            "matches_local_file": matches_local_file,
+            "chunks": chunks,
+        }),
+        || {
+            for (chunk_address, error) in &report.chunks {
+                match error {
+                    None => println!("{:64x}  OK", chunk_address.name()),
+                    Some(error) => println!("{:64x}  FAILED: {error}", chunk_address.name()),
+                }
+            }
+            if missing_chunks.is_empty() {
+                println!(
+                    "All {} chunks of the file were retrieved.",
+                    report.chunks.len()
+                );
+            } else {
+                println!(
+                    "{} of the {} chunks of the file could not be retrieved.",
+                    missing_chunks.len(),
+                    report.chunks.len()
+                );
+            }
+            match (matches_local_file, local_file) {
+                (Some(true), Some(path)) => println!("The file has the same content as {path:?}."),
+                (Some(false), Some(path)) => {
+                    println!("The file does NOT have the same content as {path:?}.")
+                }
+                _ => {}
+            }
+        },
+    )?;
+
+    if !missing_chunks.is_empty() {
+        return Err(eyre!(
+            "{} chunks of the file could not be retrieved",
+            missing_chunks.len()
+        ));
+    }
+    if matches_local_file == Some(false) {
+        return Err(eyre!("The file's content differs from the local file"));
+    }
     Ok(())
 }
 
-async fn download_files(file_api: &Files, root_dir: &Path) -> Result<()> {
+/// Print the container's files indented under the directories they are in.
+/// As the files are ordered by path, each directory only needs printing the first time it's found.
+fn print_files_tree(container: &FilesContainer) {
+    let mut current_dirs: Vec<&str> = Vec::new();
+
+    for (path, entry) in container.files() {
+        let mut components: Vec<&str> = path.split('/').collect();
+        let file_name = components.pop().unwrap_or_default();
+
+        let common = current_dirs
+            .iter()
+            .zip(components.iter())
+            .take_while(|(current, new)| current == new)
+            .count();
+        current_dirs.truncate(common);
+
+        for dir in &components[common..] {
+            println!("{}{dir}/", "    ".repeat(current_dirs.len()));
+            current_dirs.push(dir);
+        }
+
+        println!(
+            "{}{file_name} ({} bytes, {:64x})",
+            "    ".repeat(current_dirs.len()),
+            entry.size,
+            entry.address.name()
+        );
+    }
+}
+
+async fn download_files(
+    file_api: &Files,
+    root_dir: &Path,
+    trace: bool,
+    output: Output,
+) -> Result<()> {
     let docs_of_uploaded_files_path = root_dir.join("uploaded_files");
     let download_path = root_dir.join("downloaded_files");
     tokio::fs::create_dir_all(download_path.as_path()).await?;
+    let mut results = Vec::new();
 
     for entry in WalkDir::new(docs_of_uploaded_files_path)
         .into_iter()
@@ -132,42 +960,98 @@ async fn download_files(file_api: &Files, root_dir: &Path) -> Result<()> {
             let index_doc_bytes = Bytes::from(fs::read(entry.path())?);
             let index_doc_name = entry.file_name();
 
-            println!("Loading file names from index doc {index_doc_name:?}");
+            output.info(format!(
+                "Loading file names from index doc {index_doc_name:?}"
+            ));
             let files_to_fetch: Vec<(XorName, String)> = bincode::deserialize(&index_doc_bytes)?;
 
             if files_to_fetch.is_empty() {
-                println!("No files to download!");
+                output.info("No files to download!");
             }
             for (xorname, file_name) in files_to_fetch.iter() {
-                download_file(file_api, xorname, file_name, &download_path).await;
+                let file_name_path = download_path.join(file_name);
+                results.push(
+                    download_file(file_api, xorname, file_name, &file_name_path, trace, output)
+                        .await,
+                );
             }
         }
     }
 
-    Ok(())
+    output.result(&results, || {})?;
+    first_failure(results)
 }
 
 async fn download_file(
     file_api: &Files,
     xorname: &XorName,
     file_name: &String,
-    download_path: &Path,
-) {
-    println!(
+    file_name_path: &Path,
+    trace: bool,
+    output: Output,
+) -> FileResult {
+    output.info(format!(
         "Downloading file {file_name:?} with address {:64x}",
         xorname
-    );
-    match file_api.read_bytes(ChunkAddress::new(*xorname)).await {
+    ));
+    let mut result = FileResult {
+        path: file_name_path.display().to_string(),
+        address: Some(format!("{:64x}", xorname)),
+        ..Default::default()
+    };
+    let address = ChunkAddress::new(*xorname);
+    let read = if trace {
+        let (read, provenance) = file_api.read_bytes_with_provenance(address).await;
+        let chunks: Vec<ChunkTrace> = provenance.into_iter().map(ChunkTrace::from).collect();
+        if !output.is_json() {
+            print_chunk_traces(&chunks);
+        }
+        result.chunks = Some(chunks);
+        read
+    } else {
+        file_api.read_bytes(address).await
+    };
+    match read {
         Ok(bytes) => {
-            println!("Successfully got file {file_name}!");
-            let file_name_path = download_path.join(file_name);
-            println!("Writing {} bytes to {file_name_path:?}", bytes.len());
+            output.info(format!("Successfully got file {file_name}!"));
+            output.info(format!(
+                "Writing {} bytes to {file_name_path:?}",
+                bytes.len()
+            ));
+            result.size = Some(bytes.len() as u64);
             if let Err(err) = fs::write(file_name_path, bytes) {
-                println!("Failed to create file {file_name:?} with error {err:?}");
+                output.info(format!(
+                    "Failed to create file {file_name:?} with error {err:?}"
+                ));
+                result.fail(err);
             }
         }
         Err(error) => {
-            println!("Did not get file {file_name:?} from the network! {error}")
+            output.info(format!(
+                "Did not get file {file_name:?} from the network! {error}"
+            ));
+            result.fail(error);
+        }
+    }
+    result
+}
+
+fn print_chunk_traces(chunks: &[ChunkTrace]) {
+    for chunk in chunks {
+        let peer = chunk.peer.as_deref().unwrap_or("-");
+        let mut line = format!(
+            "{}  from {peer}  in {}ms  with {} retries",
+            chunk.address, chunk.elapsed_ms, chunk.retries
+        );
+        if !chunk.wrong_chunk_peers.is_empty() {
+            line.push_str(&format!(
+                "  wrong chunk served by {}",
+                chunk.wrong_chunk_peers.join(", ")
+            ));
+        }
+        if let Some(error) = &chunk.error {
+            line.push_str(&format!("  FAILED: {error}"));
         }
+        println!("{line}");
     }
 }
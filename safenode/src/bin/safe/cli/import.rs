@@ -0,0 +1,330 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Import of large local datasets, e.g. to migrate terabytes onto the network.
+//!
+//! The files are uploaded many at a time, while the directory is walked, each of them being
+//! streamed from disk rather than read in memory at once. Each file stored is
+//! appended to a checkpoint as soon as it is, so an interrupted import resumes where it stopped
+//! when run again, only uploading the files not stored yet, or modified since they were.
+
+use super::output::Output;
+
+use safenode::client::{Files, FilesContainer};
+
+use eyre::{eyre, Result};
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+use walkdir::{DirEntry, WalkDir};
+use xor_name::XorName;
+
+/// The directory of the client where the checkpoints and manifests of imports are kept
+/// unless other paths are given.
+const IMPORTS_DIR: &str = "imports";
+
+/// A file stored by an import, as recorded in its checkpoint.
+#[derive(Serialize, Deserialize, Clone)]
+struct ImportedFile {
+    /// The path of the file relative to the imported directory, with `/` separators.
+    path: String,
+    address: String,
+    size: u64,
+    /// When the file was last modified before it was stored, in seconds since the Unix epoch,
+    /// for it to be stored again by a resumed import if it has been modified since.
+    modified: u64,
+}
+
+/// The address of a file as listed in the manifest of an import.
+#[derive(Serialize)]
+struct ManifestEntry {
+    address: String,
+    size: u64,
+}
+
+enum Imported {
+    /// Not a file, e.g. a directory.
+    Skipped,
+    /// Stored by a previous run of the import, and not modified since.
+    Resumed(ImportedFile),
+    Stored(ImportedFile),
+    Failed {
+        path: String,
+        error: String,
+    },
+}
+
+/// Upload all the files under the directory, `jobs` at a time, then write the manifest of the
+/// addresses they were stored at, keyed by their paths relative to the directory.
+pub(crate) async fn import_dir(
+    dir: &Path,
+    jobs: usize,
+    checkpoint_path: Option<PathBuf>,
+    manifest_path: Option<PathBuf>,
+    file_api: &Files,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    if !dir.is_dir() {
+        return Err(eyre!("{dir:?} is not a directory"));
+    }
+    let dir = dir.canonicalize()?;
+    // Imports of the same directory share their checkpoint, to resume one another.
+    let import_id = hex::encode(&XorName::from_content(dir.to_string_lossy().as_bytes()).0[..8]);
+    let imports_dir = root_dir.join(IMPORTS_DIR);
+    let checkpoint_path =
+        checkpoint_path.unwrap_or_else(|| imports_dir.join(format!("{import_id}.checkpoint")));
+    let manifest_path =
+        manifest_path.unwrap_or_else(|| imports_dir.join(format!("{import_id}.manifest.json")));
+    for path in [&checkpoint_path, &manifest_path] {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+    }
+
+    let checkpointed = read_checkpoint(&checkpoint_path, output).await?;
+    if !checkpointed.is_empty() {
+        output.info(format!(
+            "Resuming the import of {dir:?}, {} files were already stored according to {checkpoint_path:?}",
+            checkpointed.len()
+        ));
+    }
+    let mut checkpoint = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&checkpoint_path)
+        .await?;
+
+    let mut manifest = BTreeMap::new();
+    let mut stored = 0;
+    let mut resumed = 0;
+    let mut failed: Vec<Value> = Vec::new();
+    let mut imports = stream::iter(WalkDir::new(&dir))
+        .map(|entry| import_entry(entry, &dir, &checkpointed, file_api))
+        .buffer_unordered(jobs.max(1));
+    while let Some(imported) = imports.next().await {
+        let file = match imported {
+            Imported::Skipped => continue,
+            Imported::Resumed(file) => {
+                resumed += 1;
+                file
+            }
+            Imported::Stored(file) => {
+                // Each line starts a new one, not to follow one cut short by an interruption.
+                let mut line = vec![b'\n'];
+                line.extend(serde_json::to_vec(&file)?);
+                checkpoint.write_all(&line).await?;
+                checkpoint.flush().await?;
+                output.detail(format!("Stored file {:?} to {}", file.path, file.address));
+                stored += 1;
+                file
+            }
+            Imported::Failed { path, error } => {
+                output.info(format!("Did not store file {path:?}: {error}"));
+                failed.push(json!({ "path": path, "error": error }));
+                continue;
+            }
+        };
+        let _ = manifest.insert(
+            file.path,
+            ManifestEntry {
+                address: file.address,
+                size: file.size,
+            },
+        );
+    }
+
+    let content = serde_json::to_string_pretty(&json!({ "dir": dir, "files": manifest }))?;
+    tokio::fs::write(&manifest_path, content).await?;
+
+    output.result(
+        &json!({
+            "dir": dir,
+            "stored": stored,
+            "resumed": resumed,
+            "failed": failed,
+            "manifest": manifest_path,
+            "checkpoint": checkpoint_path,
+        }),
+        || {
+            println!(
+                "{stored} files stored, {resumed} stored by a previous run, {} failed.",
+                failed.len()
+            );
+            println!(
+                "The addresses of the {} files stored are listed in {manifest_path:?}",
+                manifest.len()
+            );
+            if !failed.is_empty() {
+                println!("Run the same command again to retry the files which failed.");
+            }
+        },
+    )
+}
+
+/// The files stored by previous runs of the import, keyed by their relative path.
+/// A line cut short by an interruption is ignored, as the file it records is stored again.
+async fn read_checkpoint(path: &Path, output: Output) -> Result<HashMap<String, ImportedFile>> {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut files = HashMap::new();
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<ImportedFile>(line) {
+            Ok(file) => {
+                let _ = files.insert(file.path.clone(), file);
+            }
+            Err(err) => output.info(format!(
+                "Ignoring an invalid line of the checkpoint {path:?}: {err}"
+            )),
+        }
+    }
+    Ok(files)
+}
+
+async fn import_entry(
+    entry: walkdir::Result<DirEntry>,
+    dir: &Path,
+    checkpointed: &HashMap<String, ImportedFile>,
+    file_api: &Files,
+) -> Imported {
+    let entry = match entry {
+        Ok(entry) if entry.file_type().is_file() => entry,
+        Ok(_) => return Imported::Skipped,
+        Err(err) => {
+            return Imported::Failed {
+                path: err
+                    .path()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_default(),
+                error: err.to_string(),
+            }
+        }
+    };
+    match store_file(&entry, dir, checkpointed, file_api).await {
+        Ok(imported) => imported,
+        Err(err) => Imported::Failed {
+            path: entry.path().display().to_string(),
+            error: err.to_string(),
+        },
+    }
+}
+
+/// Store the file, unless it was stored by a previous run and hasn't been modified since.
+async fn store_file(
+    entry: &DirEntry,
+    dir: &Path,
+    checkpointed: &HashMap<String, ImportedFile>,
+    file_api: &Files,
+) -> Result<Imported> {
+    let path = FilesContainer::path_key(entry.path().strip_prefix(dir)?);
+    let metadata = entry.metadata()?;
+    let size = metadata.len();
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or_default();
+    if let Some(file) = stored_before(checkpointed, &path, size, modified) {
+        return Ok(Imported::Resumed(file.clone()));
+    }
+
+    let file = tokio::fs::File::open(entry.path()).await?;
+    let address = file_api.upload_from_reader(file).await?;
+    Ok(Imported::Stored(ImportedFile {
+        path,
+        address: format!("{:64x}", address.name()),
+        size,
+        modified,
+    }))
+}
+
+/// The file at the path as stored by a previous run, unless it has been modified since,
+/// going by its size and when it was last modified.
+fn stored_before<'a>(
+    checkpointed: &'a HashMap<String, ImportedFile>,
+    path: &str,
+    size: u64,
+    modified: u64,
+) -> Option<&'a ImportedFile> {
+    checkpointed
+        .get(path)
+        .filter(|file| file.size == size && file.modified == modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::output::Verbosity;
+
+    use assert_fs::TempDir;
+
+    fn imported_file(path: &str, size: u64, modified: u64) -> ImportedFile {
+        ImportedFile {
+            path: path.to_string(),
+            address: hex::encode([size as u8; 32]),
+            size,
+            modified,
+        }
+    }
+
+    fn checkpoint_line(file: &ImportedFile) -> Result<String> {
+        Ok(format!("\n{}", serde_json::to_string(file)?))
+    }
+
+    #[tokio::test]
+    async fn checkpoints_are_read_ignoring_lines_cut_short() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let path = tmp_dir.path().join("import.checkpoint");
+        let output = Output::new(true, Verbosity::Quiet);
+
+        // No import was run yet.
+        assert!(read_checkpoint(&path, output).await?.is_empty());
+
+        let a = imported_file("dir/a.txt", 1, 1_000);
+        let b = imported_file("b.txt", 2, 2_000);
+        let a_modified = imported_file("dir/a.txt", 3, 3_000);
+        let cut_short = checkpoint_line(&imported_file("c.txt", 4, 4_000))?;
+        let content = [
+            checkpoint_line(&a)?,
+            checkpoint_line(&b)?,
+            // An interrupted run, the next one starting a new line.
+            cut_short[..cut_short.len() / 2].to_string(),
+            checkpoint_line(&a_modified)?,
+        ]
+        .concat();
+        std::fs::write(&path, content)?;
+
+        let checkpointed = read_checkpoint(&path, output).await?;
+        assert_eq!(checkpointed.len(), 2);
+        // The file was stored again after it was modified.
+        assert_eq!(checkpointed["dir/a.txt"].size, 3);
+        assert_eq!(checkpointed["b.txt"].address, b.address);
+        assert!(!checkpointed.contains_key("c.txt"));
+        Ok(())
+    }
+
+    #[test]
+    fn only_files_unmodified_since_stored_are_skipped() {
+        let file = imported_file("a.txt", 10, 1_000);
+        let checkpointed = HashMap::from([(file.path.clone(), file)]);
+
+        assert!(stored_before(&checkpointed, "a.txt", 10, 1_000).is_some());
+        assert!(stored_before(&checkpointed, "a.txt", 11, 1_000).is_none());
+        assert!(stored_before(&checkpointed, "a.txt", 10, 1_001).is_none());
+        assert!(stored_before(&checkpointed, "b.txt", 10, 1_000).is_none());
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{names::resolve_address, output::Output};
+
+use safenode::{
+    client::{ChunkContent, Client, Files, FilesContainer},
+    protocol::{
+        storage::{ChunkAddress, DbcAddress},
+        xorurl::{DataType, XorUrl, XOR_URL_SCHEME},
+    },
+};
+
+use eyre::{eyre, Result};
+use serde_json::{json, Value};
+use xor_name::XorName;
+
+/// Files larger than this are not retrieved to check whether they are a FilesContainer.
+const MAX_CONTAINER_SIZE: usize = 4 * 1024 * 1024;
+
+/// Find out what's stored at the given address, i.e. a file, a FilesContainer, a chunk,
+/// a register or a spend, and print a description of each thing found.
+/// The address is either hex encoded, in which case each kind of data is looked for,
+/// or a url, in which case only the kind of data it points at is.
+pub(crate) async fn inspect(
+    address: &str,
+    register_tag: u64,
+    client: &Client,
+    output: Output,
+) -> Result<()> {
+    let address = &resolve_address(address, client).await?;
+    let (name, data_type) = if address.starts_with(XOR_URL_SCHEME) {
+        let url: XorUrl = address.parse()?;
+        (url.name, Some(url.data_type))
+    } else {
+        let bytes =
+            hex::decode(address).map_err(|err| eyre!("Address is not a hex string: {err}"))?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| eyre!("Address must be 32 bytes long, i.e. 64 hex characters"))?;
+        (XorName(bytes), None)
+    };
+
+    let look_for_chunk = matches!(
+        data_type,
+        None | Some(DataType::File) | Some(DataType::FilesContainer)
+    );
+    let register_tag = match data_type {
+        Some(DataType::Register(tag)) => Some(tag),
+        None => Some(register_tag),
+        Some(_) => None,
+    };
+    let look_for_spend = matches!(data_type, None | Some(DataType::Spend));
+
+    output.info(format!("Inspecting {name:64x}..."));
+    let (chunk, register, spend) = futures::join!(
+        async {
+            if look_for_chunk {
+                inspect_chunk(name, client, output).await
+            } else {
+                vec![]
+            }
+        },
+        async {
+            match register_tag {
+                Some(tag) => inspect_register(name, tag, client, output).await,
+                None => None,
+            }
+        },
+        async {
+            if look_for_spend {
+                inspect_spend(name, client, output).await
+            } else {
+                None
+            }
+        },
+    );
+
+    let found: Vec<Value> = chunk.into_iter().chain(register).chain(spend).collect();
+    if found.is_empty() {
+        return Err(eyre!("Nothing was found at {name:64x}"));
+    }
+
+    output.result(
+        &json!({ "address": format!("{name:64x}"), "found": found }),
+        || {
+            println!("{name:64x}:");
+            for description in &found {
+                let kind = description["type"].as_str().unwrap_or_default();
+                let details = description
+                    .as_object()
+                    .map(|fields| {
+                        fields
+                            .iter()
+                            .filter(|(key, _)| *key != "type")
+                            .map(|(key, value)| format!("{key}: {value}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .unwrap_or_default();
+                println!("  {kind} ({details})");
+            }
+        },
+    )
+}
+
+/// Describe the chunk at the given address, and the file or FilesContainer it's the head of.
+async fn inspect_chunk(name: XorName, client: &Client, output: Output) -> Vec<Value> {
+    let file_api = Files::new(client.clone());
+    let address = ChunkAddress::new(name);
+    let content = match file_api.inspect_chunk(address).await {
+        Ok(content) => content,
+        Err(error) => {
+            output.detail(format!("No chunk found at {name:64x}: {error}"));
+            return vec![];
+        }
+    };
+
+    let (mut found, file_size) = match content {
+        ChunkContent::DataMap { chunks, file_size } => (
+            vec![json!({ "type": "data_map", "chunks": chunks, "file_size": file_size })],
+            file_size,
+        ),
+        ChunkContent::Raw { size } => (vec![json!({ "type": "chunk", "size": size })], size),
+    };
+
+    if file_size <= MAX_CONTAINER_SIZE {
+        match FilesContainer::retrieve(&file_api, address).await {
+            Ok(container) => found.push(json!({
+                "type": "files_container",
+                "files": container.len(),
+                "total_size": container.files().map(|(_, entry)| entry.size).sum::<u64>(),
+            })),
+            Err(error) => output.detail(format!("Not a FilesContainer: {error}")),
+        }
+    }
+
+    found
+}
+
+async fn inspect_register(
+    name: XorName,
+    tag: u64,
+    client: &Client,
+    output: Output,
+) -> Option<Value> {
+    match client.get_register(name, tag).await {
+        Ok(register) => Some(json!({
+            "type": "register",
+            "tag": tag,
+            "owner": format!("{:?}", register.owner()),
+            "entries": register.size(),
+            "current_entries": register.read().len(),
+        })),
+        Err(error) => {
+            output.detail(format!(
+                "No register with tag {tag} found at {name:64x}: {error}"
+            ));
+            None
+        }
+    }
+}
+
+async fn inspect_spend(name: XorName, client: &Client, output: Output) -> Option<Value> {
+    match client.get_spend(DbcAddress::new(name)).await {
+        Ok(spend) => Some(json!({
+            "type": "spend",
+            "dbc_id": format!("{:?}", spend.dbc_id()),
+            "src_tx_hash": format!("{:?}", spend.src_tx_hash()),
+        })),
+        Err(error) => {
+            output.detail(format!("No spend found at {name:64x}: {error}"));
+            None
+        }
+    }
+}
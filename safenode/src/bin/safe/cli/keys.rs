@@ -0,0 +1,149 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::output::Output;
+
+use bls::SecretKey;
+use clap::Subcommand;
+use eyre::{eyre, Result};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+const KEYS_DIR_NAME: &str = "keys";
+/// The name of the key the client signs with, unless another one is chosen.
+pub(crate) const DEFAULT_KEY_NAME: &str = "default";
+
+#[derive(Subcommand, Debug)]
+pub enum KeysCmds {
+    /// Create a new random key with the given name.
+    Create {
+        /// The name of the key.
+        #[clap(name = "name", default_value = DEFAULT_KEY_NAME)]
+        name: String,
+    },
+    /// Print the public key of the key with the given name.
+    Show {
+        /// The name of the key.
+        #[clap(name = "name", default_value = DEFAULT_KEY_NAME)]
+        name: String,
+    },
+    /// Import a hex-encoded secret key with the given name.
+    Import {
+        /// The hex-encoded secret key.
+        #[clap(name = "secret_key")]
+        secret_key: String,
+        /// The name of the key.
+        #[clap(long, default_value = DEFAULT_KEY_NAME)]
+        name: String,
+        /// Overwrite the key if one with the same name exists.
+        #[clap(long)]
+        force: bool,
+    },
+    /// Print the hex-encoded secret key with the given name. Keep it safe!
+    Export {
+        /// The name of the key.
+        #[clap(name = "name", default_value = DEFAULT_KEY_NAME)]
+        name: String,
+    },
+}
+
+pub(crate) async fn keys_cmds(cmds: KeysCmds, root_dir: &Path, output: Output) -> Result<()> {
+    match cmds {
+        KeysCmds::Create { name } => {
+            let secret_key = SecretKey::random();
+            store_key(root_dir, &name, &secret_key, false).await?;
+            print_public_key(&name, &secret_key, output)
+        }
+        KeysCmds::Show { name } => {
+            let secret_key = load_key(root_dir, &name)
+                .await?
+                .ok_or_else(|| eyre!("There is no key named '{name}'"))?;
+            print_public_key(&name, &secret_key, output)
+        }
+        KeysCmds::Import {
+            secret_key,
+            name,
+            force,
+        } => {
+            let secret_key = parse_secret_key(&secret_key)?;
+            store_key(root_dir, &name, &secret_key, force).await?;
+            print_public_key(&name, &secret_key, output)
+        }
+        KeysCmds::Export { name } => {
+            let secret_key = load_key(root_dir, &name)
+                .await?
+                .ok_or_else(|| eyre!("There is no key named '{name}'"))?;
+            let secret_key_hex = hex::encode(secret_key.to_bytes());
+            output.result(
+                &json!({ "name": name, "secret_key": secret_key_hex }),
+                || println!("{secret_key_hex}"),
+            )
+        }
+    }
+}
+
+/// Load the key with the given name, creating and storing a new one if it doesn't exist yet.
+pub(crate) async fn load_or_create_key(root_dir: &Path, name: &str) -> Result<SecretKey> {
+    match load_key(root_dir, name).await? {
+        Some(secret_key) => Ok(secret_key),
+        None => {
+            let secret_key = SecretKey::random();
+            store_key(root_dir, name, &secret_key, false).await?;
+            Ok(secret_key)
+        }
+    }
+}
+
+fn print_public_key(name: &str, secret_key: &SecretKey, output: Output) -> Result<()> {
+    let public_key_hex = hex::encode(secret_key.public_key().to_bytes());
+    output.result(
+        &json!({ "name": name, "public_key": public_key_hex }),
+        || println!("{name}: {public_key_hex}"),
+    )
+}
+
+fn key_path(root_dir: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(eyre!(
+            "Key names can only contain alphanumeric characters, '-' and '_'"
+        ));
+    }
+    Ok(root_dir.join(KEYS_DIR_NAME).join(format!("{name}.sk")))
+}
+
+fn parse_secret_key(secret_key_hex: &str) -> Result<SecretKey> {
+    let bytes = hex::decode(secret_key_hex.trim())
+        .map_err(|err| eyre!("The secret key is not a hex string: {err}"))?;
+    let bytes = bytes
+        .try_into()
+        .map_err(|_| eyre!("The secret key must be 32 bytes long, i.e. 64 hex characters"))?;
+    Ok(SecretKey::from_bytes(bytes)?)
+}
+
+async fn load_key(root_dir: &Path, name: &str) -> Result<Option<SecretKey>> {
+    let path = key_path(root_dir, name)?;
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let secret_key_hex = tokio::fs::read_to_string(path).await?;
+    parse_secret_key(&secret_key_hex).map(Some)
+}
+
+async fn store_key(root_dir: &Path, name: &str, secret_key: &SecretKey, force: bool) -> Result<()> {
+    let path = key_path(root_dir, name)?;
+    if path.exists() && !force {
+        return Err(eyre!("A key named '{name}' already exists"));
+    }
+    tokio::fs::create_dir_all(root_dir.join(KEYS_DIR_NAME)).await?;
+    tokio::fs::write(path, hex::encode(secret_key.to_bytes())).await?;
+    Ok(())
+}
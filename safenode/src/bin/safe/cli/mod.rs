@@ -5,27 +5,167 @@
 // under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
+mod bench;
+mod config;
+mod contacts;
+mod daemon;
 mod files;
+mod import;
+mod inspect;
+mod keys;
+#[cfg(all(unix, feature = "fuse"))]
+mod mount;
+mod names;
+mod networks;
+mod node;
+mod output;
+mod pin;
 mod register;
+mod shell;
+mod sign;
 mod wallet;
 
+use safenode::log::{LogFormat, LogOutputDest};
+
 use clap::{Parser, Subcommand};
+use clap_complete::Shell as CompletionShell;
 use libp2p::Multiaddr;
+use std::{net::SocketAddr, path::PathBuf, str::FromStr};
+use tracing::Level;
+
+#[cfg(all(unix, feature = "fuse"))]
+pub(super) use self::mount::mount;
+pub(super) use self::{
+    bench::bench_cmds,
+    config::Config,
+    contacts::contacts_cmds,
+    daemon::run_daemon,
+    files::{cat_file, files_cmds, share_file, FilesCmds},
+    inspect::inspect,
+    keys::{keys_cmds, load_or_create_key, DEFAULT_KEY_NAME},
+    names::name_cmds,
+    networks::networks_cmds,
+    node::node_cmds,
+    output::{exit_code, Output, Verbosity},
+    pin::{list_pins, pin_cmds, remove_pin, PinCmds},
+    register::register_cmds,
+    shell::Shell,
+    sign::{sign, verify},
+    wallet::{create_genesis, list_wallets, sign_transfer, wallet_cmds, watch, WalletCmds},
+};
 
-pub(super) use self::{files::files_cmds, register::register_cmds, wallet::wallet_cmds};
+/// Describes the exit codes of the process, see the `EXIT_*` constants in the `output` module.
+const EXIT_CODES_HELP: &str = "Exit codes:
+  0  Success
+  1  Failure not listed below
+  2  Invalid arguments
+  3  Could not connect to the network
+  4  Data not found
+  5  Insufficient funds in the wallet
+  6  The network did not respond in time";
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = EXIT_CODES_HELP)]
 pub(super) struct Opt {
     /// Nodes we dial at start to help us get connected to the network. Can be specified multiple times.
-    #[clap(long = "peer")]
+    /// Nodes behind a NAT can be reached through a relay, e.g. `<relay-addr>/p2p-circuit/p2p/<peer-id>`.
+    /// If none are given, the peers of the network selected with `safe networks switch` are used,
+    /// or else the peers from the config file in the client dir,
+    /// or else the comma-separated peers in the `SAFE_PEERS` env var.
+    #[clap(long = "peer", value_delimiter = ',')]
     pub peers: Vec<Multiaddr>,
 
+    /// The contacts of the network to join, as the path or http(s) url of a JSON document
+    /// listing the multiaddrs of its peers, dialed along with those given with `--peer`.
+    #[clap(long, env = "SAFE_CONTACTS", value_name = "PATH|URL")]
+    pub contacts: Option<String>,
+
+    /// The maximum number of seconds to wait for the client to connect to the network.
+    #[clap(long = "connection-timeout")]
+    pub connection_timeout_secs: Option<u64>,
+
+    /// The maximum number of requests sent to the network per second, e.g. to remain polite
+    /// to a small network during bulk operations. Defaults to the cap in the config file, if any.
+    #[clap(long, global = true, value_name = "N")]
+    pub max_requests_per_sec: Option<f64>,
+
+    /// The maximum number of bytes of data stored or retrieved per second.
+    /// Defaults to the cap in the config file, if any.
+    #[clap(long, global = true, value_name = "BYTES")]
+    pub max_bandwidth: Option<f64>,
+
+    /// Print the results of the commands as JSON documents, for scripts to consume.
+    /// Any other messages are written to stderr.
+    #[clap(long, global = true)]
+    pub json: bool,
+
+    /// Only print the outcome of the commands and errors, without any progress messages.
+    #[clap(long, short, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Where the client's logs are written to: `stdout`, `stderr`, `file:<dir>` for rotated
+    /// log files in the given directory, or `none`.
+    /// Defaults to log files in the `safe-client.log` directory within the temp dir.
+    #[clap(long, global = true)]
+    pub log_output: Option<LogOutput>,
+
+    /// The format of the logs: `default`, or `json` for one JSON object per line, with the
+    /// timestamp, level, target and fields of each event.
+    #[clap(long, global = true, default_value_t = LogFormat::Default)]
+    pub log_format: LogFormat,
+
+    /// The minimum level of the events logged, i.e. one of `trace`, `debug`, `info`, `warn` or `error`.
+    #[clap(long, global = true, default_value = "trace")]
+    pub log_level: Level,
+
+    /// Which events are logged, with comma-separated `target=level` directives, e.g.
+    /// "safenode=debug,libp2p=warn". Takes precedence over `--log-level`.
+    /// Defaults to the directives in the RUST_LOG env var, if it's set.
+    #[clap(long, global = true, value_name = "DIRECTIVES")]
+    pub log_filter: Option<String>,
+
+    /// Print details of what's being done. Give it twice, i.e. `-vv`, to print every step.
+    #[clap(long, short, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// The name of the key, as managed with `safe keys`, the client signs with,
+    /// e.g. when creating registers. It's created if it doesn't exist yet.
+    /// Defaults to the key set in the config file, or the `default` key.
+    #[clap(long, global = true)]
+    pub key: Option<String>,
+
+    /// The name of the wallet the `wallet` commands use, e.g. `node-rewards`, kept apart from the
+    /// others in the `wallets/<name>` dir within the client dir. It's created if it doesn't exist yet.
+    /// Defaults to the wallet of the client dir itself.
+    #[clap(long, global = true, conflicts_with = "wallet_dir")]
+    pub wallet: Option<String>,
+
+    /// The dir the wallet the `wallet` commands use is kept in, instead of the client dir,
+    /// e.g. on an external drive. The wallet files are in its `wallet` subdir.
+    #[clap(long, global = true)]
+    pub wallet_dir: Option<PathBuf>,
+
     /// Available sub commands.
     #[clap(subcommand)]
     pub cmd: SubCmd,
 }
 
+/// Where the CLI writes its logs to, if anywhere.
+#[derive(Clone, Debug)]
+pub(super) struct LogOutput(pub Option<LogOutputDest>);
+
+impl FromStr for LogOutput {
+    type Err = safenode::log::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "none" {
+            Ok(Self(None))
+        } else {
+            s.parse().map(|dest| Self(Some(dest)))
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub(super) enum SubCmd {
     #[clap(name = "wallet", subcommand)]
@@ -37,4 +177,102 @@ pub(super) enum SubCmd {
     #[clap(name = "register", subcommand)]
     /// Manage files on the SAFE Network
     Register(register::RegisterCmds),
+    #[clap(name = "pin", subcommand)]
+    /// Keep files alive on the SAFE Network, storing their missing chunks again
+    Pin(pin::PinCmds),
+    #[clap(name = "name", subcommand)]
+    /// Manage the human-readable names mapped to addresses on the SAFE Network
+    Name(names::NameCmds),
+    #[clap(name = "keys", subcommand)]
+    /// Manage the keys the client signs with
+    Keys(keys::KeysCmds),
+    #[clap(name = "contacts", subcommand)]
+    /// Manage the addresses of the contacts tokens are sent to
+    Contacts(contacts::ContactsCmds),
+    #[clap(name = "networks", subcommand)]
+    /// Manage the networks the client can join
+    Networks(networks::NetworksCmds),
+    #[clap(name = "node", subcommand)]
+    /// Run a node on this machine, to contribute to the SAFE Network
+    Node(node::NodeCmds),
+    #[clap(name = "cat")]
+    /// Write the contents of a file on the SAFE Network to stdout
+    Cat {
+        /// Address of the file, in hex string, as a url, or as `safe://<name>`.
+        #[clap(name = "address")]
+        address: String,
+    },
+    #[clap(name = "inspect")]
+    /// Find out what's stored at an address, i.e. a file, a FilesContainer, a chunk,
+    /// a register or a spend, and print a description of it
+    Inspect {
+        /// The address, in hex string, as a url, or as `safe://<name>`.
+        #[clap(name = "address")]
+        address: String,
+        /// The type tag of the register to look for at the address.
+        #[clap(long, default_value_t = register::REGISTER_TAG)]
+        tag: u64,
+    },
+    #[clap(name = "sign")]
+    /// Sign the contents of a file with the client key, e.g. to prove ownership
+    /// of a network identity out-of-band, such as the owner of a register
+    Sign {
+        /// The path to the file, or `-` to sign what's read from stdin.
+        #[clap(name = "file")]
+        file: String,
+    },
+    #[clap(name = "verify")]
+    /// Verify a signature made with `safe sign`
+    Verify {
+        /// The path to the signed file, or `-` to read it from stdin.
+        #[clap(name = "file")]
+        file: String,
+        /// The hex-encoded signature.
+        #[clap(name = "signature")]
+        signature: String,
+        /// The hex-encoded public key of the signer.
+        #[clap(name = "public_key")]
+        public_key: String,
+    },
+    #[clap(name = "shell")]
+    /// Start an interactive shell, running any number of commands with the same
+    /// connection to the network, rather than connecting anew for each of them
+    Shell,
+    #[clap(name = "daemon")]
+    /// Serve a localhost HTTP+JSON API over the same connection to the network, until stopped,
+    /// so other applications can upload and download files, read and write registers, and
+    /// send tokens from the wallet without connecting to the network themselves:
+    /// `POST /files`, `GET /files/<address>`, `POST /registers`, `GET|POST /registers/<name>`,
+    /// `GET /wallet/address`, `GET /wallet/balance` and `POST /wallet/send`.
+    /// Requests must carry the token written to `daemon_token` in the wallet dir as a bearer token
+    Daemon {
+        /// The address the API is served on. Anyone able to reach it can spend the
+        /// tokens of the wallet, thus it should only be reachable from this machine.
+        #[clap(long, default_value = "127.0.0.1:12600")]
+        listen: SocketAddr,
+    },
+    #[clap(name = "bench", subcommand)]
+    /// Measure the throughput and chunk latencies of uploads and downloads,
+    /// using synthetic data, against the network connected to
+    Bench(bench::BenchCmds),
+    #[cfg(all(unix, feature = "fuse"))]
+    #[clap(name = "mount")]
+    /// Mount a FilesContainer as a read-only filesystem, so its files can be browsed and read
+    /// with any tool, until it's unmounted, e.g. with `fusermount -u <mountpoint>`
+    Mount {
+        /// Address of the FilesContainer, in hex string, as a url, or as `safe://<name>`.
+        #[clap(name = "container_address")]
+        container_address: String,
+        /// The existing, empty directory to mount it at.
+        #[clap(name = "mountpoint")]
+        mountpoint: PathBuf,
+    },
+    #[clap(name = "completions")]
+    /// Print the tab completion script for the given shell,
+    /// e.g. `safe completions bash > /etc/bash_completion.d/safe`
+    Completions {
+        /// The shell to generate the script for.
+        #[clap(name = "shell")]
+        shell: CompletionShell,
+    },
 }
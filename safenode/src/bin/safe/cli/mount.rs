@@ -0,0 +1,249 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{files::parse_xorname, names::resolve_address, output::Output};
+
+use safenode::{
+    client::{Client, FileEntry, Files, FilesContainer},
+    protocol::storage::ChunkAddress,
+};
+
+use eyre::Result;
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    Request,
+};
+use libc::{EIO, EISDIR, ENOENT, ENOTDIR};
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+use tokio::runtime::Handle;
+
+/// How long the kernel may cache the attributes of the files, which never change.
+const TTL: Duration = Duration::from_secs(3600);
+/// The inode of the root directory, as expected by FUSE.
+const ROOT_INODE: u64 = 1;
+const BLOCK_SIZE: u32 = 512;
+
+/// Mount the FilesContainer at the mountpoint as a read-only filesystem, until it's unmounted,
+/// e.g. with `fusermount -u <mountpoint>`. Files are read from the network as they are read
+/// from the filesystem, only fetching the chunks holding the bytes read.
+pub(crate) async fn mount(
+    container_address: &str,
+    mountpoint: &Path,
+    client: &Client,
+    output: Output,
+) -> Result<()> {
+    let container_address = resolve_address(container_address, client).await?;
+    let files = Files::new(client.clone());
+    let address = ChunkAddress::new(parse_xorname(&container_address)?);
+    let container = FilesContainer::retrieve(&files, address).await?;
+
+    let filesystem = ContainerFs::new(&container, files, Handle::current());
+    let options = vec![
+        MountOption::RO,
+        MountOption::FSName("safe".to_string()),
+        MountOption::Subtype("safe".to_string()),
+    ];
+    output.info(format!(
+        "Mounting FilesContainer with {} files at {}, until it's unmounted",
+        container.len(),
+        mountpoint.display()
+    ));
+    let mountpoint: PathBuf = mountpoint.to_path_buf();
+    tokio::task::spawn_blocking(move || fuser::mount2(filesystem, mountpoint, &options)).await??;
+    output.info("Unmounted the FilesContainer");
+    Ok(())
+}
+
+/// An entry of the filesystem, i.e. a file of the container, or a directory of the paths of its files.
+enum Node {
+    Dir {
+        parent: u64,
+        children: BTreeMap<String, u64>,
+    },
+    File(FileEntry),
+}
+
+/// The files of a FilesContainer, exposed read-only as a filesystem, their inodes being
+/// the index of their node plus one.
+struct ContainerFs {
+    nodes: Vec<Node>,
+    files: Files,
+    /// Used to run the reads from the network from the threads of the filesystem.
+    runtime: Handle,
+}
+
+impl ContainerFs {
+    /// Build the tree of directories of the paths of the files of the container.
+    fn new(container: &FilesContainer, files: Files, runtime: Handle) -> Self {
+        let mut fs = Self {
+            nodes: vec![Node::Dir {
+                parent: ROOT_INODE,
+                children: BTreeMap::new(),
+            }],
+            files,
+            runtime,
+        };
+        for (path, entry) in container.files() {
+            let mut parent = ROOT_INODE;
+            let mut components = path.split('/').filter(|c| !c.is_empty()).peekable();
+            while let Some(component) = components.next() {
+                let node = if components.peek().is_none() {
+                    Node::File(*entry)
+                } else {
+                    Node::Dir {
+                        parent,
+                        children: BTreeMap::new(),
+                    }
+                };
+                parent = match fs.child(parent, component) {
+                    Some(inode) => inode,
+                    None => fs.add(parent, component, node),
+                };
+            }
+        }
+        fs
+    }
+
+    fn node(&self, inode: u64) -> Option<&Node> {
+        self.nodes.get(inode.checked_sub(1)? as usize)
+    }
+
+    fn child(&self, parent: u64, name: &str) -> Option<u64> {
+        match self.node(parent)? {
+            Node::Dir { children, .. } => children.get(name).copied(),
+            Node::File(_) => None,
+        }
+    }
+
+    fn add(&mut self, parent: u64, name: &str, node: Node) -> u64 {
+        self.nodes.push(node);
+        let inode = self.nodes.len() as u64;
+        if let Some(Node::Dir { children, .. }) = self.nodes.get_mut(parent as usize - 1) {
+            let _ = children.insert(name.to_string(), inode);
+        }
+        inode
+    }
+
+    fn attr(&self, inode: u64, req: &Request) -> Option<FileAttr> {
+        let (kind, size, perm, nlink) = match self.node(inode)? {
+            Node::Dir { .. } => (FileType::Directory, 0, 0o555, 2),
+            Node::File(entry) => (FileType::RegularFile, entry.size, 0o444, 1),
+        };
+        Some(FileAttr {
+            ino: inode,
+            size,
+            blocks: (size + BLOCK_SIZE as u64 - 1) / BLOCK_SIZE as u64,
+            atime: UNIX_EPOCH,
+            mtime: UNIX_EPOCH,
+            ctime: UNIX_EPOCH,
+            crtime: UNIX_EPOCH,
+            kind,
+            perm,
+            nlink,
+            uid: req.uid(),
+            gid: req.gid(),
+            rdev: 0,
+            blksize: BLOCK_SIZE,
+            flags: 0,
+        })
+    }
+}
+
+impl Filesystem for ContainerFs {
+    fn lookup(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        match name
+            .to_str()
+            .and_then(|name| self.child(parent, name))
+            .and_then(|inode| self.attr(inode, req))
+        {
+            Some(attr) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, req: &Request, inode: u64, reply: ReplyAttr) {
+        match self.attr(inode, req) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request,
+        inode: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let entry = match self.node(inode) {
+            Some(Node::File(entry)) => *entry,
+            Some(Node::Dir { .. }) => return reply.error(EISDIR),
+            None => return reply.error(ENOENT),
+        };
+        let position = offset.max(0) as u64;
+        if position >= entry.size {
+            return reply.data(&[]);
+        }
+        let length = (size as u64).min(entry.size - position);
+        let bytes = self.runtime.block_on(self.files.read_from(
+            entry.address,
+            position as usize,
+            length as usize,
+        ));
+        match bytes {
+            Ok(bytes) => reply.data(&bytes),
+            Err(err) => {
+                warn!("Failed to read {length} bytes at {position} of {entry:?}: {err}");
+                reply.error(EIO)
+            }
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request,
+        inode: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let (parent, children) = match self.node(inode) {
+            Some(Node::Dir { parent, children }) => (*parent, children),
+            Some(Node::File(_)) => return reply.error(ENOTDIR),
+            None => return reply.error(ENOENT),
+        };
+        let entries = [
+            (inode, FileType::Directory, "."),
+            (parent, FileType::Directory, ".."),
+        ]
+        .into_iter()
+        .chain(children.iter().map(|(name, child)| {
+            let kind = match self.node(*child) {
+                Some(Node::Dir { .. }) => FileType::Directory,
+                _ => FileType::RegularFile,
+            };
+            (*child, kind, name.as_str())
+        }));
+        for (i, (inode, kind, name)) in entries.enumerate().skip(offset.max(0) as usize) {
+            // The offset given is that of the next entry to add, once the buffer is full.
+            if reply.add(inode, (i + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok()
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::output::Output;
+
+use safenode::{
+    client::Client,
+    protocol::xorurl::{DataType, XorUrl, XOR_URL_SCHEME},
+};
+
+use clap::Subcommand;
+use eyre::{eyre, Result};
+use serde_json::json;
+use xor_name::XorName;
+
+#[derive(Subcommand, Debug)]
+pub enum NameCmds {
+    /// Map a name to an address, so that `safe://<name>` can be given instead of the address,
+    /// e.g. to `safe cat`, `safe files download` or `safe inspect`. The first client to register
+    /// a name owns it, and only it can map the name to another address afterwards.
+    Register {
+        /// The name, made of lowercase letters, digits and '-'.
+        #[clap(name = "name")]
+        name: String,
+        /// The address the name maps to, as a url, or in hex for the address of a file.
+        #[clap(name = "target")]
+        target: String,
+    },
+    /// Print the address a name currently maps to.
+    Resolve {
+        /// The name, with or without the `safe://` scheme.
+        #[clap(name = "name")]
+        name: String,
+    },
+    /// Print every version of the mapping of a name, latest first.
+    History {
+        /// The name, with or without the `safe://` scheme.
+        #[clap(name = "name")]
+        name: String,
+    },
+}
+
+pub(crate) async fn name_cmds(cmds: NameCmds, client: &Client, output: Output) -> Result<()> {
+    match cmds {
+        NameCmds::Register { name, target } => {
+            let target = parse_target(&target)?;
+            let version = client.register_name(strip_scheme(&name), &target).await?;
+            output.result(
+                &json!({ "name": name, "target": target.to_string(), "version": version }),
+                || println!("Name '{name}' now maps to {target} (version {version})."),
+            )
+        }
+        NameCmds::Resolve { name } => {
+            let target = client.resolve_name(strip_scheme(&name)).await?;
+            output.result(
+                &json!({ "name": name, "target": target.to_string() }),
+                || println!("{target}"),
+            )
+        }
+        NameCmds::History { name } => {
+            let versions = client.name_history(strip_scheme(&name)).await?;
+            let versions_json: Vec<_> = versions
+                .iter()
+                .map(
+                    |(version, target)| json!({ "version": version, "target": target.to_string() }),
+                )
+                .collect();
+            output.result(&json!({ "name": name, "versions": versions_json }), || {
+                for (version, target) in &versions {
+                    println!("{version}: {target}");
+                }
+            })
+        }
+    }
+}
+
+/// Resolve `safe://<name>` into the url the name maps to. Any other address, i.e. a url
+/// of an address or a hex-encoded address, is returned as is.
+pub(crate) async fn resolve_address(address: &str, client: &Client) -> Result<String> {
+    if !address.starts_with(XOR_URL_SCHEME) || address.parse::<XorUrl>().is_ok() {
+        return Ok(address.to_string());
+    }
+    let target = client.resolve_name(strip_scheme(address)).await?;
+    info!("Resolved {address} into {target}");
+    Ok(target.to_string())
+}
+
+pub(crate) fn strip_scheme(name: &str) -> &str {
+    name.strip_prefix(XOR_URL_SCHEME).unwrap_or(name)
+}
+
+/// Parse the address a name maps to, either a url or the hex-encoded address of a file.
+fn parse_target(target: &str) -> Result<XorUrl> {
+    if target.starts_with(XOR_URL_SCHEME) {
+        return Ok(target.parse()?);
+    }
+    let bytes = hex::decode(target).map_err(|err| eyre!("Address is not a hex string: {err}"))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| eyre!("Address must be 32 bytes long, i.e. 64 hex characters"))?;
+    Ok(XorUrl::new(DataType::File, XorName(bytes)))
+}
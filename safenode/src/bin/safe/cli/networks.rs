@@ -0,0 +1,180 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    config::{Config, NetworkDefinition},
+    output::Output,
+};
+
+use clap::Subcommand;
+use eyre::{eyre, Result};
+use libp2p::Multiaddr;
+use safenode::peers::NetworkContacts;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand, Debug)]
+pub enum NetworksCmds {
+    /// Add a network definition, replacing any previous one with the same name.
+    Add {
+        /// The name of the network.
+        #[clap(name = "name")]
+        name: String,
+        /// The bootstrap peers of the network. Can be specified multiple times.
+        #[clap(long = "peer", required_unless_present = "contacts")]
+        peers: Vec<Multiaddr>,
+        /// The path or http(s) url of the contacts of the network, a JSON document listing
+        /// the multiaddrs of its peers, read each time the network is joined.
+        #[clap(long, value_name = "PATH|URL")]
+        contacts: Option<String>,
+        /// An identifier of the network, for reference only.
+        #[clap(long)]
+        network_id: Option<String>,
+        /// The url of the faucet handing out test tokens on the network, for `safe wallet get-faucet`.
+        #[clap(long)]
+        faucet: Option<String>,
+        /// Also switch to the network once added.
+        #[clap(long)]
+        switch: bool,
+    },
+    /// List the known networks, marking the one currently selected.
+    List,
+    /// Select the network the other commands join.
+    Switch {
+        /// The name of the network.
+        #[clap(name = "name")]
+        name: String,
+    },
+    /// Remove a network definition.
+    Remove {
+        /// The name of the network.
+        #[clap(name = "name")]
+        name: String,
+    },
+    /// Write the bootstrap peers of a network to a contacts file, to be shared with the
+    /// nodes and clients joining it, e.g. with their `--contacts` option.
+    Export {
+        /// The name of the network.
+        #[clap(name = "name")]
+        name: String,
+        /// The path of the contacts file to write.
+        #[clap(name = "path")]
+        path: PathBuf,
+    },
+}
+
+pub(crate) fn networks_cmds(cmds: NetworksCmds, root_dir: &Path, output: Output) -> Result<()> {
+    let mut config = Config::load(root_dir)?;
+    match cmds {
+        NetworksCmds::Add {
+            name,
+            peers,
+            contacts,
+            network_id,
+            faucet,
+            switch,
+        } => {
+            let definition = NetworkDefinition {
+                peers,
+                contacts,
+                network_id,
+                faucet,
+            };
+            let _ = config.networks.insert(name.clone(), definition);
+            if switch {
+                config.network = Some(name.clone());
+            }
+            config.store(root_dir)?;
+            output.info(format!("Network '{name}' added."));
+        }
+        NetworksCmds::List => {}
+        NetworksCmds::Switch { name } => {
+            if !config.networks.contains_key(&name) {
+                return Err(eyre!("There is no network named '{name}'"));
+            }
+            config.network = Some(name.clone());
+            config.store(root_dir)?;
+            output.info(format!("Switched to network '{name}'."));
+        }
+        NetworksCmds::Remove { name } => {
+            if config.networks.remove(&name).is_none() {
+                return Err(eyre!("There is no network named '{name}'"));
+            }
+            if config.network.as_ref() == Some(&name) {
+                config.network = None;
+            }
+            config.store(root_dir)?;
+            output.info(format!("Network '{name}' removed."));
+        }
+        NetworksCmds::Export { name, path } => {
+            let definition = config
+                .networks
+                .get(&name)
+                .ok_or_else(|| eyre!("There is no network named '{name}'"))?;
+            if definition.peers.is_empty() {
+                return Err(eyre!(
+                    "The network '{name}' has no bootstrap peers to export"
+                ));
+            }
+            let contacts = NetworkContacts {
+                network_id: definition.network_id.clone(),
+                peers: definition.peers.clone(),
+            };
+            contacts.write(&path)?;
+            output.info(format!(
+                "Wrote the {} peers of network '{name}' to {path:?}.",
+                contacts.peers.len()
+            ));
+            return Ok(());
+        }
+    }
+
+    list_networks(&config, output)
+}
+
+fn list_networks(config: &Config, output: Output) -> Result<()> {
+    let networks: Vec<_> = config
+        .networks
+        .iter()
+        .map(|(name, definition)| {
+            json!({
+                "name": name,
+                "network_id": definition.network_id,
+                "peers": definition.peers,
+                "contacts": definition.contacts,
+                "faucet": definition.faucet,
+                "selected": config.network.as_ref() == Some(name),
+            })
+        })
+        .collect();
+
+    output.result(&networks, || {
+        if config.networks.is_empty() {
+            println!("No networks defined.");
+        }
+        for (name, definition) in &config.networks {
+            let selected = if config.network.as_ref() == Some(name) {
+                "*"
+            } else {
+                " "
+            };
+            let network_id = definition
+                .network_id
+                .as_ref()
+                .map(|id| format!(" ({id})"))
+                .unwrap_or_default();
+            println!("{selected} {name}{network_id}");
+            for peer in &definition.peers {
+                println!("      {peer}");
+            }
+            if let Some(contacts) = &definition.contacts {
+                println!("      contacts: {contacts}");
+            }
+        }
+    })
+}
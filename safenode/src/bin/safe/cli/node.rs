@@ -0,0 +1,458 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::output::Output;
+
+use safenode_proto::{
+    safe_node_client::SafeNodeClient, NetworkInfoRequest, NodeInfoRequest, StopRequest,
+};
+
+use clap::Subcommand;
+use eyre::{eyre, Result};
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    io::SeekFrom,
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tonic::Request;
+
+// this includes code generated from .proto files
+mod safenode_proto {
+    tonic::include_proto!("safenode.v1");
+}
+
+const NODE_DIR_NAME: &str = "node";
+const DATA_DIR_NAME: &str = "data";
+const LOGS_DIR_NAME: &str = "logs";
+const STATE_FILE_NAME: &str = "node.json";
+const DEFAULT_RPC_PORT: u16 = 12001;
+
+#[cfg(windows)]
+const SAFENODE_BIN_NAME: &str = "safenode.exe";
+#[cfg(not(windows))]
+const SAFENODE_BIN_NAME: &str = "safenode";
+
+#[derive(Subcommand, Debug)]
+pub enum NodeCmds {
+    /// Install the `safenode` binary in the node dir, so it can be started with `safe node start`.
+    Install {
+        /// Path of the `safenode` binary to install.
+        /// Defaults to the one next to this `safe` binary, or else the first one found in `PATH`.
+        #[clap(long)]
+        path: Option<PathBuf>,
+        /// Overwrite the binary if one is installed already.
+        #[clap(long)]
+        force: bool,
+    },
+    /// Start the installed node in the background, joining the network through the given peers.
+    Start {
+        /// Peers to dial to join the network. Can be specified multiple times.
+        /// Defaults to the peers the client would dial.
+        #[clap(long = "peer")]
+        peers: Vec<Multiaddr>,
+        /// The port the node listens on for other peers. Defaults to any available port.
+        #[clap(long, default_value_t = 0)]
+        port: u16,
+        /// The local port the node serves its RPC service on, used to manage it.
+        #[clap(long, default_value_t = DEFAULT_RPC_PORT)]
+        rpc_port: u16,
+    },
+    /// Stop the running node.
+    Stop,
+    /// Print information about the running node and its connections.
+    Status,
+    /// Print the latest lines of the node's logs.
+    Logs {
+        /// The number of lines to print.
+        #[clap(long, short = 'n', default_value_t = 50)]
+        lines: usize,
+        /// Keep printing the lines appended to the logs, until interrupted.
+        #[clap(long, short = 'f')]
+        follow: bool,
+    },
+}
+
+/// What's recorded in the node dir about the node last started, to manage it afterwards.
+#[derive(Debug, Serialize, Deserialize)]
+struct NodeState {
+    pid: u32,
+    rpc_addr: SocketAddr,
+}
+
+pub(crate) async fn node_cmds(
+    cmds: NodeCmds,
+    client_dir: &Path,
+    default_peers: Vec<Multiaddr>,
+    output: Output,
+) -> Result<()> {
+    let node_dir = node_dir(client_dir);
+    match cmds {
+        NodeCmds::Install { path, force } => install(&node_dir, path, force, output).await,
+        NodeCmds::Start {
+            peers,
+            port,
+            rpc_port,
+        } => {
+            let peers = if peers.is_empty() {
+                default_peers
+            } else {
+                peers
+            };
+            start(&node_dir, peers, port, rpc_port, output).await
+        }
+        NodeCmds::Stop => stop(&node_dir, output).await,
+        NodeCmds::Status => status(&node_dir, output).await,
+        NodeCmds::Logs { lines, follow } => logs(&node_dir, lines, follow).await,
+    }
+}
+
+/// The node dir sits next to the client dir, i.e. at `~/.safe/node`.
+fn node_dir(client_dir: &Path) -> PathBuf {
+    client_dir
+        .parent()
+        .unwrap_or(client_dir)
+        .join(NODE_DIR_NAME)
+}
+
+/// Look for a `safenode` binary next to the running executable, or else in `PATH`.
+fn find_safenode_bin() -> Option<PathBuf> {
+    let sibling = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(SAFENODE_BIN_NAME)));
+    let in_path = std::env::var_os("PATH")
+        .map(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join(SAFENODE_BIN_NAME))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    sibling
+        .into_iter()
+        .chain(in_path)
+        .find(|path| path.is_file())
+}
+
+async fn install(
+    node_dir: &Path,
+    path: Option<PathBuf>,
+    force: bool,
+    output: Output,
+) -> Result<()> {
+    let source = match path {
+        Some(path) if path.is_file() => path,
+        Some(path) => return Err(eyre!("There is no binary at {path:?}")),
+        None => find_safenode_bin().ok_or_else(|| {
+            eyre!("Could not find a `safenode` binary, please give its path with `--path`")
+        })?,
+    };
+
+    let dest = node_dir.join(SAFENODE_BIN_NAME);
+    if dest.exists() && !force {
+        return Err(eyre!(
+            "A node binary is installed already at {dest:?}, use `--force` to overwrite it"
+        ));
+    }
+    tokio::fs::create_dir_all(node_dir).await?;
+    // Copying keeps the permissions, thus the binary stays executable.
+    let _ = tokio::fs::copy(&source, &dest).await?;
+
+    output.result(&json!({ "source": source, "path": dest }), || {
+        println!("Installed the node binary from {source:?} at {dest:?}")
+    })
+}
+
+async fn start(
+    node_dir: &Path,
+    peers: Vec<Multiaddr>,
+    port: u16,
+    rpc_port: u16,
+    output: Output,
+) -> Result<()> {
+    let bin = node_dir.join(SAFENODE_BIN_NAME);
+    if !bin.is_file() {
+        return Err(eyre!(
+            "No node binary is installed, please install it with `safe node install`"
+        ));
+    }
+    if let Some(state) = load_state(node_dir).await? {
+        if node_client(state.rpc_addr).await.is_ok() {
+            return Err(eyre!(
+                "A node is running already, with pid {}, stop it first with `safe node stop`",
+                state.pid
+            ));
+        }
+    }
+
+    let rpc_addr = SocketAddr::from((Ipv4Addr::LOCALHOST, rpc_port));
+    let mut cmd = Command::new(&bin);
+    let _ = cmd
+        .arg("--root-dir")
+        .arg(node_dir.join(DATA_DIR_NAME))
+        .arg("--log-dir")
+        .arg(node_dir.join(LOGS_DIR_NAME))
+        .arg("--port")
+        .arg(port.to_string())
+        .arg("--rpc")
+        .arg(rpc_addr.to_string());
+    for peer in &peers {
+        let _ = cmd.arg("--peer").arg(peer.to_string());
+    }
+    // The node outlives this command, its output goes to its log files.
+    let child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| eyre!("Failed to launch the node binary at {bin:?}: {err}"))?;
+
+    let state = NodeState {
+        pid: child.id(),
+        rpc_addr,
+    };
+    tokio::fs::write(
+        node_dir.join(STATE_FILE_NAME),
+        serde_json::to_string_pretty(&state)?,
+    )
+    .await?;
+
+    output.result(
+        &json!({ "pid": state.pid, "rpc_addr": rpc_addr, "node_dir": node_dir }),
+        || {
+            println!(
+                "Node started with pid {}, serving RPC at {rpc_addr}.",
+                state.pid
+            );
+            println!("Check on it with `safe node status` and `safe node logs`.");
+        },
+    )
+}
+
+async fn stop(node_dir: &Path, output: Output) -> Result<()> {
+    let state = running_node_state(node_dir).await?;
+    let mut client = node_client(state.rpc_addr).await?;
+    let _response = client
+        .stop(Request::new(StopRequest { delay_millis: 0 }))
+        .await?;
+    tokio::fs::remove_file(node_dir.join(STATE_FILE_NAME)).await?;
+
+    output.result(&json!({ "pid": state.pid, "stopped": true }), || {
+        println!("Node with pid {} was requested to stop.", state.pid)
+    })
+}
+
+async fn status(node_dir: &Path, output: Output) -> Result<()> {
+    let state = running_node_state(node_dir).await?;
+    let mut client = node_client(state.rpc_addr).await?;
+
+    let response = client.node_info(Request::new(NodeInfoRequest {})).await?;
+    let node_info = response.get_ref();
+    let peer_id = PeerId::from_bytes(&node_info.peer_id)?;
+    let uptime = Duration::from_secs(node_info.uptime_secs);
+
+    let response = client
+        .network_info(Request::new(NetworkInfoRequest {}))
+        .await?;
+    let network_info = response.get_ref();
+    let connected_peers = network_info
+        .connected_peers
+        .iter()
+        .map(|bytes| PeerId::from_bytes(bytes).map(|peer_id| peer_id.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    output.result(
+        &json!({
+            "peer_id": peer_id.to_string(),
+            "pid": node_info.pid,
+            "rpc_addr": state.rpc_addr,
+            "version": node_info.bin_version,
+            "uptime_secs": node_info.uptime_secs,
+            "log_dir": node_info.log_dir,
+            "listeners": network_info.listeners,
+            "connected_peers": connected_peers,
+        }),
+        || {
+            println!("Node status:");
+            println!("============");
+            println!("Peer Id: {peer_id}");
+            println!("PID: {}", node_info.pid);
+            println!("RPC endpoint: {}", state.rpc_addr);
+            println!("Binary version: {}", node_info.bin_version);
+            println!("Time since last restart: {uptime:?}");
+            println!("Logs dir: {}", node_info.log_dir);
+            for listener in &network_info.listeners {
+                println!("Listener: {listener}");
+            }
+            println!("Connected peers: {}", connected_peers.len());
+        },
+    )
+}
+
+async fn logs(node_dir: &Path, lines: usize, follow: bool) -> Result<()> {
+    let log_file = latest_log_file(&node_dir.join(LOGS_DIR_NAME))
+        .await?
+        .ok_or_else(|| eyre!("The node hasn't written any logs yet"))?;
+
+    let mut file = tokio::fs::File::open(&log_file).await?;
+    let mut content = String::new();
+    let _ = file.read_to_string(&mut content).await?;
+    let all_lines = content.lines().collect::<Vec<_>>();
+    for line in &all_lines[all_lines.len().saturating_sub(lines)..] {
+        println!("{line}");
+    }
+
+    if !follow {
+        return Ok(());
+    }
+
+    let mut offset = file.seek(SeekFrom::Current(0)).await?;
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let len = file.metadata().await?.len();
+        if len < offset {
+            // The file was truncated, start over from its beginning.
+            offset = file.seek(SeekFrom::Start(0)).await?;
+        }
+        let mut appended = String::new();
+        offset += file.read_to_string(&mut appended).await? as u64;
+        print!("{appended}");
+    }
+}
+
+/// The most recently modified file in the logs dir, i.e. the one the node is writing to.
+async fn latest_log_file(logs_dir: &Path) -> Result<Option<PathBuf>> {
+    if !logs_dir.is_dir() {
+        return Ok(None);
+    }
+    let mut latest = None;
+    let mut entries = tokio::fs::read_dir(logs_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata.modified()?;
+        if latest
+            .as_ref()
+            .map_or(true, |(latest_modified, _)| modified > *latest_modified)
+        {
+            latest = Some((modified, entry.path()));
+        }
+    }
+    Ok(latest.map(|(_, path)| path))
+}
+
+async fn load_state(node_dir: &Path) -> Result<Option<NodeState>> {
+    let path = node_dir.join(STATE_FILE_NAME);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = tokio::fs::read_to_string(&path).await?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|err| eyre!("Invalid node state file at {path:?}: {err}"))
+}
+
+async fn running_node_state(node_dir: &Path) -> Result<NodeState> {
+    load_state(node_dir)
+        .await?
+        .ok_or_else(|| eyre!("No node was started, start one with `safe node start`"))
+}
+
+async fn node_client(rpc_addr: SocketAddr) -> Result<SafeNodeClient<tonic::transport::Channel>> {
+    SafeNodeClient::connect(format!("https://{rpc_addr}"))
+        .await
+        .map_err(|err| eyre!("The node is not reachable at {rpc_addr}, is it running? {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::output::Verbosity;
+
+    use assert_fs::TempDir;
+
+    fn output() -> Output {
+        Output::new(true, Verbosity::Quiet)
+    }
+
+    #[test]
+    fn the_node_dir_is_next_to_the_client_dir() {
+        assert_eq!(
+            node_dir(Path::new("/home/user/.safe/client")),
+            Path::new("/home/user/.safe/node")
+        );
+    }
+
+    #[tokio::test]
+    async fn an_installed_binary_is_only_overwritten_when_forced() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let node_dir = tmp_dir.path().join(NODE_DIR_NAME);
+        let source = tmp_dir.path().join("safenode-build");
+        let dest = node_dir.join(SAFENODE_BIN_NAME);
+
+        std::fs::write(&source, "v1")?;
+        install(&node_dir, Some(source.clone()), false, output()).await?;
+        assert_eq!(std::fs::read_to_string(&dest)?, "v1");
+
+        std::fs::write(&source, "v2")?;
+        assert!(install(&node_dir, Some(source.clone()), false, output())
+            .await
+            .is_err());
+        assert_eq!(std::fs::read_to_string(&dest)?, "v1");
+
+        install(&node_dir, Some(source), true, output()).await?;
+        assert_eq!(std::fs::read_to_string(&dest)?, "v2");
+
+        let missing = tmp_dir.path().join("missing");
+        assert!(install(&node_dir, Some(missing), true, output())
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn a_node_is_not_started_without_an_installed_binary() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let result = start(tmp_dir.path(), vec![], 0, DEFAULT_RPC_PORT, output()).await;
+        assert!(result.is_err());
+        assert!(load_state(tmp_dir.path()).await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn the_state_of_the_node_started_is_read_from_the_node_dir() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let state_file = tmp_dir.path().join(STATE_FILE_NAME);
+
+        // No node was started.
+        assert!(load_state(tmp_dir.path()).await?.is_none());
+        assert!(running_node_state(tmp_dir.path()).await.is_err());
+        assert!(status(tmp_dir.path(), output()).await.is_err());
+        assert!(stop(tmp_dir.path(), output()).await.is_err());
+
+        let state = NodeState {
+            pid: 42,
+            rpc_addr: SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_RPC_PORT)),
+        };
+        std::fs::write(&state_file, serde_json::to_string_pretty(&state)?)?;
+        let loaded = running_node_state(tmp_dir.path()).await?;
+        assert_eq!(loaded.pid, state.pid);
+        assert_eq!(loaded.rpc_addr, state.rpc_addr);
+
+        std::fs::write(&state_file, "not a node state")?;
+        assert!(load_state(tmp_dir.path()).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use safenode::{
+    client::Error as ClientError,
+    domain::{client_transfers::Error as TransferError, wallet::Error as WalletError},
+    network::Error as NetworkError,
+    protocol::error::{Error as ProtocolError, StorageError},
+};
+
+use eyre::{Report, Result};
+use serde::Serialize;
+use std::fmt::Display;
+
+/// The process exit code when a command fails for a reason not listed below.
+pub(crate) const EXIT_FAILURE: i32 = 1;
+// Exit code 2 is set by clap when the arguments are invalid.
+/// The process exit code when the client couldn't connect to the network.
+pub(crate) const EXIT_CONNECTION: i32 = 3;
+/// The process exit code when the requested data doesn't exist.
+pub(crate) const EXIT_NOT_FOUND: i32 = 4;
+/// The process exit code when the wallet holds too few tokens for a payment.
+pub(crate) const EXIT_INSUFFICIENT_FUNDS: i32 = 5;
+/// The process exit code when the network didn't respond in time.
+pub(crate) const EXIT_TIMEOUT: i32 = 6;
+
+/// How many messages are printed besides the outcome of the commands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Verbosity {
+    /// Only the outcome of the commands and errors.
+    Quiet,
+    /// Progress and informational messages as well.
+    Normal,
+    /// Details of what's being done as well.
+    Verbose,
+    /// Every step, e.g. each chunk stored or retrieved, as well.
+    Debug,
+}
+
+impl Verbosity {
+    /// The verbosity set by the `--quiet` flag and the number of `-v` flags.
+    pub(crate) fn new(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Self::Quiet,
+            (false, 0) => Self::Normal,
+            (false, 1) => Self::Verbose,
+            (false, _) => Self::Debug,
+        }
+    }
+}
+
+/// Decides how the outcome of the commands is displayed: human readable text,
+/// or JSON documents on stdout meant to be consumed by scripts.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Output {
+    json: bool,
+    verbosity: Verbosity,
+}
+
+impl Output {
+    pub(crate) fn new(json: bool, verbosity: Verbosity) -> Self {
+        Self { json, verbosity }
+    }
+
+    /// Print a progress or informational message, unless quiet.
+    /// In JSON mode it goes to stderr, so that stdout only holds the JSON result.
+    pub(crate) fn info(&self, msg: impl Display) {
+        self.print_at(Verbosity::Normal, msg)
+    }
+
+    /// Print a detail of what's being done, only when verbose.
+    pub(crate) fn detail(&self, msg: impl Display) {
+        self.print_at(Verbosity::Verbose, msg)
+    }
+
+    pub(crate) fn verbosity(&self) -> Verbosity {
+        self.verbosity
+    }
+
+    fn print_at(&self, verbosity: Verbosity, msg: impl Display) {
+        if self.verbosity < verbosity {
+            return;
+        }
+        if self.json {
+            eprintln!("{msg}");
+        } else {
+            println!("{msg}");
+        }
+    }
+
+    /// Print the outcome of a command, either as the JSON serialisation
+    /// of `value`, or by running `print_human`.
+    pub(crate) fn result<T: Serialize>(&self, value: &T, print_human: impl FnOnce()) -> Result<()> {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(value)?);
+        } else {
+            print_human();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_json(&self) -> bool {
+        self.json
+    }
+
+    /// Print an error which aborted a command as a JSON document, along with its code.
+    pub(crate) fn error(&self, err: &Report) {
+        println!("{:#}", error_json(err));
+    }
+}
+
+/// The process exit code for the given error, see the `EXIT_*` constants.
+pub(crate) fn exit_code(err: &Report) -> i32 {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<ClientError>() {
+            match err {
+                ClientError::ConnectionTimeout { .. } => return EXIT_CONNECTION,
+                ClientError::ResponseTimeout(_) => return EXIT_TIMEOUT,
+                ClientError::NameNotFound(_) => return EXIT_NOT_FOUND,
+                _ => {}
+            }
+        } else if let Some(err) = cause.downcast_ref::<NetworkError>() {
+            match err {
+                NetworkError::NotEnoughPeers => return EXIT_CONNECTION,
+                NetworkError::RecordNotFound => return EXIT_NOT_FOUND,
+                _ => {}
+            }
+        } else if let Some(ProtocolError::Storage(err)) = cause.downcast_ref::<ProtocolError>() {
+            match err {
+                StorageError::ChunkNotFound(_)
+                | StorageError::RegisterNotFound(_)
+                | StorageError::SpendNotFound(_) => return EXIT_NOT_FOUND,
+                _ => {}
+            }
+        } else if let Some(TransferError::NotEnoughBalance(_)) =
+            cause.downcast_ref::<TransferError>()
+        {
+            return EXIT_INSUFFICIENT_FUNDS;
+        } else if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                return EXIT_NOT_FOUND;
+            }
+        }
+    }
+    EXIT_FAILURE
+}
+
+/// The JSON document describing an error which aborted a command, along with its code.
+pub(crate) fn error_json(err: &Report) -> serde_json::Value {
+    serde_json::json!({
+        "error": {
+            "code": error_code(err),
+            "message": format!("{err:#}"),
+        }
+    })
+}
+
+/// A stable code identifying the kind of error, for scripts to match on.
+fn error_code(err: &Report) -> &'static str {
+    if let Some(err) = err.downcast_ref::<ClientError>() {
+        match err {
+            ClientError::ConnectionTimeout { .. } => "connection_timeout",
+            ClientError::ResponseTimeout(_) => "response_timeout",
+            ClientError::Chunks(_) => "chunks",
+            ClientError::ContentBranchDetected(_) => "register_branches",
+            ClientError::NameNotFound(_) => "name_not_found",
+            ClientError::NameTaken(_) => "name_taken",
+            _ => "client",
+        }
+    } else if err.downcast_ref::<WalletError>().is_some() {
+        "wallet"
+    } else if err.downcast_ref::<std::io::Error>().is_some() {
+        "io"
+    } else {
+        "other"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use eyre::eyre;
+    use std::{collections::BTreeMap, io, time::Duration};
+
+    #[test]
+    fn errors_are_mapped_to_their_exit_code() {
+        let connection_timeout = Report::new(ClientError::ConnectionTimeout {
+            found: 1,
+            required: 9,
+            timeout: Duration::from_secs(30),
+            dial_results: BTreeMap::new(),
+        });
+        assert_eq!(exit_code(&connection_timeout), EXIT_CONNECTION);
+
+        let not_enough_balance = Report::new(TransferError::NotEnoughBalance("1.0".to_string()));
+        assert_eq!(exit_code(&not_enough_balance), EXIT_INSUFFICIENT_FUNDS);
+
+        let file_not_found = Report::new(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+        assert_eq!(exit_code(&file_not_found), EXIT_NOT_FOUND);
+
+        // The cause of an error is found through the errors and contexts wrapping it.
+        let record_not_found = Report::new(ClientError::Network(NetworkError::RecordNotFound))
+            .wrap_err("Failed to get the register");
+        assert_eq!(exit_code(&record_not_found), EXIT_NOT_FOUND);
+
+        assert_eq!(exit_code(&eyre!("Something went wrong")), EXIT_FAILURE);
+    }
+
+    #[test]
+    fn errors_are_described_with_their_code() {
+        let name_not_found = Report::new(ClientError::NameNotFound("site".to_string()))
+            .wrap_err("Failed to resolve");
+        let json = error_json(&name_not_found);
+        assert_eq!(json["error"]["code"], "name_not_found");
+        assert_eq!(
+            json["error"]["message"],
+            "Failed to resolve: The name 'site' is not registered"
+        );
+
+        let connection_timeout = Report::new(ClientError::ConnectionTimeout {
+            found: 0,
+            required: 9,
+            timeout: Duration::from_secs(30),
+            dial_results: BTreeMap::new(),
+        });
+        assert_eq!(
+            error_json(&connection_timeout)["error"]["code"],
+            "connection_timeout"
+        );
+
+        let file_not_found = Report::new(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+        assert_eq!(error_json(&file_not_found)["error"]["code"], "io");
+        assert_eq!(
+            error_json(&eyre!("Something went wrong"))["error"]["code"],
+            "other"
+        );
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    config::{Config, Pin},
+    files::parse_xorname,
+    names::resolve_address,
+    output::Output,
+};
+
+use safenode::{
+    client::{calculate_address, Client, Files},
+    protocol::storage::ChunkAddress,
+};
+
+use bytes::Bytes;
+use clap::Subcommand;
+use eyre::{eyre, Result};
+use serde_json::{json, Value};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// The directory of the client the pinned files are downloaded into, when pinned without
+/// a local copy.
+const PINS_DIR: &str = "pins";
+
+#[derive(Subcommand, Debug)]
+pub enum PinCmds {
+    /// Pin a file, for `safe pin watch` to keep it alive on the network.
+    Add {
+        /// Address of the file, in hex string, as a url, or as `safe://<name>`.
+        #[clap(name = "address")]
+        address: String,
+        /// A local copy of the file, its missing chunks are stored again from.
+        /// If not provided, the file is downloaded into the client dir.
+        #[clap(long)]
+        local: Option<PathBuf>,
+    },
+    /// List the pinned files.
+    List,
+    /// Unpin a file, deleting the copy downloaded when it was pinned, if any.
+    Remove {
+        /// Address of the file, in hex string.
+        #[clap(name = "address")]
+        address: String,
+    },
+    /// Verify every pinned file can be retrieved from the network, storing again the chunks
+    /// missing out of its local copy, then do it again at every interval, until stopped.
+    Watch {
+        /// The number of seconds between the verifications of the pinned files.
+        #[clap(long, default_value_t = 3600)]
+        interval: u64,
+        /// Verify the pinned files once, then exit.
+        #[clap(long)]
+        once: bool,
+    },
+}
+
+pub(crate) async fn pin_cmds(
+    cmds: PinCmds,
+    client: &Client,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    match cmds {
+        PinCmds::Add { address, local } => {
+            add_pin(&address, local.as_deref(), client, root_dir, output).await
+        }
+        PinCmds::List => list_pins(root_dir, output),
+        PinCmds::Remove { address } => remove_pin(&address, root_dir, output),
+        PinCmds::Watch { interval, once } => {
+            watch_pins(
+                Duration::from_secs(interval),
+                once,
+                client,
+                root_dir,
+                output,
+            )
+            .await
+        }
+    }
+}
+
+async fn add_pin(
+    address: &str,
+    local_copy: Option<&Path>,
+    client: &Client,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    let xorname = parse_xorname(&resolve_address(address, client).await?)?;
+    let address = format!("{xorname:64x}");
+
+    let pin = match local_copy {
+        Some(path) => {
+            // The address of a file is derived from its content, thus re-chunking the local
+            // copy yields the same address only if the contents are the same.
+            let bytes = Bytes::from(tokio::fs::read(path).await?);
+            if calculate_address(bytes)? != xorname {
+                return Err(eyre!(
+                    "{path:?} is not a copy of the file at {address}, their contents differ"
+                ));
+            }
+            Pin {
+                local_copy: path.canonicalize()?,
+                cached: false,
+            }
+        }
+        None => {
+            output.info(format!("Downloading the file at {address}..."));
+            let bytes = Files::new(client.clone())
+                .read_bytes(ChunkAddress::new(xorname))
+                .await?;
+            let pins_dir = root_dir.join(PINS_DIR);
+            tokio::fs::create_dir_all(&pins_dir).await?;
+            let local_copy = pins_dir.join(&address);
+            tokio::fs::write(&local_copy, bytes).await?;
+            Pin {
+                local_copy,
+                cached: true,
+            }
+        }
+    };
+
+    let mut config = Config::load(root_dir)?;
+    let _ = config.pins.insert(address.clone(), pin.clone());
+    config.store(root_dir)?;
+    output.result(
+        &json!({ "address": address, "local_copy": pin.local_copy }),
+        || {
+            println!(
+                "Pinned the file at {address}, kept alive from {:?}.",
+                pin.local_copy
+            )
+        },
+    )
+}
+
+pub(crate) fn list_pins(root_dir: &Path, output: Output) -> Result<()> {
+    let config = Config::load(root_dir)?;
+    output.result(&json!(config.pins), || {
+        if config.pins.is_empty() {
+            println!("No files pinned.");
+        }
+        for (address, pin) in &config.pins {
+            println!("{address}: {:?}", pin.local_copy);
+        }
+    })
+}
+
+pub(crate) fn remove_pin(address: &str, root_dir: &Path, output: Output) -> Result<()> {
+    let address = format!("{:64x}", parse_xorname(address)?);
+    let mut config = Config::load(root_dir)?;
+    let pin = config
+        .pins
+        .remove(&address)
+        .ok_or_else(|| eyre!("The file at {address} is not pinned"))?;
+    config.store(root_dir)?;
+    if pin.cached {
+        std::fs::remove_file(&pin.local_copy)?;
+    }
+    output.result(&json!({ "address": address }), || {
+        println!("Unpinned the file at {address}.")
+    })
+}
+
+async fn watch_pins(
+    interval: Duration,
+    once: bool,
+    client: &Client,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    let file_api = Files::new(client.clone());
+    loop {
+        // Reloaded every time, to watch the files pinned or unpinned in the meantime.
+        let config = Config::load(root_dir)?;
+        let mut results = Vec::new();
+        for (address, pin) in &config.pins {
+            output.info(format!("Verifying the pinned file at {address}..."));
+            let result = match check_pin(address, pin, &file_api).await {
+                Ok(repaired) => json!({ "address": address, "repaired_chunks": repaired }),
+                Err(err) => json!({ "address": address, "error": format!("{err:#}") }),
+            };
+            results.push(result);
+        }
+        print_checks(&results, output)?;
+
+        if once {
+            return Ok(());
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Store again the chunks of the pinned file missing from the network, returning their
+/// addresses in hex.
+async fn check_pin(address: &str, pin: &Pin, file_api: &Files) -> Result<Vec<String>> {
+    let address = ChunkAddress::new(parse_xorname(address)?);
+    let report = file_api.verify(address).await?;
+    if report.is_complete() {
+        return Ok(vec![]);
+    }
+    let bytes = Bytes::from(tokio::fs::read(&pin.local_copy).await.map_err(|err| {
+        eyre!(
+            "{} chunks are missing, but the local copy {:?} can't be read: {err}",
+            report.missing_chunks().len(),
+            pin.local_copy
+        )
+    })?);
+    let repaired = file_api.repair(address, bytes).await?;
+    Ok(repaired
+        .iter()
+        .map(|chunk_address| format!("{:64x}", chunk_address.name()))
+        .collect())
+}
+
+fn print_checks(results: &[Value], output: Output) -> Result<()> {
+    output.result(&results, || {
+        if results.is_empty() {
+            println!("No files pinned.");
+        }
+        for result in results {
+            let address = result["address"].as_str().unwrap_or_default();
+            match (
+                result["error"].as_str(),
+                result["repaired_chunks"].as_array(),
+            ) {
+                (Some(error), _) => println!("{address}  FAILED: {error}"),
+                (None, Some(repaired)) if !repaired.is_empty() => {
+                    println!("{address}  REPAIRED {} chunks", repaired.len())
+                }
+                _ => println!("{address}  OK"),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::output::Verbosity;
+
+    use assert_fs::TempDir;
+    use xor_name::XorName;
+
+    fn random_address() -> String {
+        format!("{:64x}", XorName::random(&mut rand::thread_rng()))
+    }
+
+    #[test]
+    fn pins_are_kept_in_the_config() -> Result<()> {
+        let root_dir = TempDir::new()?;
+        let output = Output::new(true, Verbosity::Quiet);
+
+        // A file downloaded when pinned, and one pinned with a local copy of the user.
+        let cached_address = random_address();
+        let cached_copy = root_dir.path().join(PINS_DIR).join(&cached_address);
+        std::fs::create_dir_all(root_dir.path().join(PINS_DIR))?;
+        std::fs::write(&cached_copy, "cached")?;
+        let local_address = random_address();
+        let local_copy = root_dir.path().join("local.txt");
+        std::fs::write(&local_copy, "local")?;
+
+        let mut config = Config::load(root_dir.path())?;
+        config.json = true;
+        let pins = [
+            (&cached_address, &cached_copy, true),
+            (&local_address, &local_copy, false),
+        ];
+        for (address, local_copy, cached) in pins {
+            let pin = Pin {
+                local_copy: local_copy.clone(),
+                cached,
+            };
+            let _ = config.pins.insert(address.clone(), pin);
+        }
+        config.store(root_dir.path())?;
+
+        let config = Config::load(root_dir.path())?;
+        assert_eq!(config.pins.len(), 2);
+        assert!(config.pins[&cached_address].cached);
+        assert_eq!(config.pins[&local_address].local_copy, local_copy);
+        list_pins(root_dir.path(), output)?;
+
+        // Unpinning deletes the copy downloaded, but not the local copy of the user.
+        remove_pin(&cached_address, root_dir.path(), output)?;
+        assert!(!cached_copy.exists());
+        remove_pin(&local_address, root_dir.path(), output)?;
+        assert!(local_copy.exists());
+        assert!(remove_pin(&local_address, root_dir.path(), output).is_err());
+
+        let config = Config::load(root_dir.path())?;
+        assert!(config.pins.is_empty());
+        // The rest of the config is left untouched.
+        assert!(config.json);
+        Ok(())
+    }
+
+    #[test]
+    fn pins_stored_without_their_origin_were_not_downloaded() -> Result<()> {
+        let pin: Pin = serde_json::from_value(json!({ "local_copy": "/data/file.txt" }))?;
+        assert_eq!(pin.local_copy, Path::new("/data/file.txt"));
+        assert!(!pin.cached);
+        Ok(())
+    }
+}
@@ -6,12 +6,129 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use safenode::client::{Client, Error as ClientError};
+use super::output::Output;
 
+use safenode::{
+    client::{Client, Error as ClientError, Register, RegisterOffline},
+    protocol::{
+        storage::registers::{Entry, EntryHash},
+        xorurl::{DataType, XorUrl, XOR_URL_SCHEME},
+    },
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use clap::Subcommand;
-use eyre::Result;
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::BTreeSet, path::PathBuf};
 use xor_name::XorName;
 
+/// The type tag of the registers managed by the CLI.
+pub(crate) const REGISTER_TAG: u64 = 3006;
+
+/// The version of the format of the register snapshots, checked on import.
+const SNAPSHOT_VERSION: u8 = 1;
+
+/// The entries of a register along with their causal relations, as exported to JSON.
+#[derive(Serialize, Deserialize)]
+struct RegisterSnapshot {
+    version: u8,
+    /// The address of the exported register in hex.
+    address: String,
+    tag: u64,
+    /// All the entries ever written to the register, latest first.
+    entries: Vec<SnapshotEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotEntry {
+    /// The hash of the entry in hex.
+    hash: String,
+    /// The hashes of the entries it was written atop, i.e. its parents, in hex.
+    parents: BTreeSet<String>,
+    /// The entry, base64 encoded.
+    entry: String,
+}
+
+/// A register the entries of a snapshot are replayed into.
+trait ReplayTarget {
+    /// Write the entry atop the given entries.
+    fn write_atop(&mut self, entry: &[u8], parents: BTreeSet<EntryHash>) -> Result<()>;
+    /// Whether the entry of the given hash was written.
+    fn contains(&self, hash: EntryHash) -> bool;
+}
+
+impl ReplayTarget for RegisterOffline {
+    fn write_atop(&mut self, entry: &[u8], parents: BTreeSet<EntryHash>) -> Result<()> {
+        Ok(RegisterOffline::write_atop(self, entry, parents)?)
+    }
+
+    fn contains(&self, hash: EntryHash) -> bool {
+        self.get(hash).is_ok()
+    }
+}
+
+impl RegisterSnapshot {
+    /// The snapshot of a register, from all its entries along with their parents, latest first.
+    fn new(
+        address: &XorName,
+        tag: u64,
+        entries: impl IntoIterator<Item = (EntryHash, Entry, BTreeSet<EntryHash>)>,
+    ) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            address: format!("{address:x}"),
+            tag,
+            entries: entries
+                .into_iter()
+                .map(|(hash, entry, parents)| SnapshotEntry {
+                    hash: hex::encode(hash.0),
+                    parents: parents
+                        .into_iter()
+                        .map(|parent| hex::encode(parent.0))
+                        .collect(),
+                    entry: BASE64.encode(entry),
+                })
+                .collect(),
+        }
+    }
+
+    /// Write the entries into the target oldest first, each atop the same entries as in the
+    /// exported register. An entry's hash only depends on its content and parents, so it's
+    /// unchanged, unless the snapshot was tampered with.
+    fn replay(&self, target: &mut impl ReplayTarget) -> Result<()> {
+        for snapshot_entry in self.entries.iter().rev() {
+            let hash = entry_hash(&snapshot_entry.hash)?;
+            let parents = snapshot_entry
+                .parents
+                .iter()
+                .map(|parent| entry_hash(parent))
+                .collect::<Result<BTreeSet<_>>>()?;
+            if let Some(parent) = parents.iter().find(|parent| !target.contains(**parent)) {
+                return Err(eyre!(
+                    "The entry {} is written atop {parent:?}, which doesn't precede it in the snapshot",
+                    snapshot_entry.hash
+                ));
+            }
+            let entry = BASE64.decode(&snapshot_entry.entry).map_err(|err| {
+                eyre!(
+                    "The entry {} is not valid base64: {err}",
+                    snapshot_entry.hash
+                )
+            })?;
+            target.write_atop(&entry, parents)?;
+            if !target.contains(hash) {
+                return Err(eyre!(
+                    "The entry {} doesn't match its hash, the snapshot is corrupted",
+                    snapshot_entry.hash
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum RegisterCmds {
     /// Create a new register with the given pet name.
@@ -20,96 +137,483 @@ pub enum RegisterCmds {
         #[clap(name = "name")]
         name: String,
     },
-    Edit {
-        /// The name of the register to edit.
+    /// Write an entry to a register, atop all its current entries.
+    #[clap(alias = "edit")]
+    Write {
+        /// The name of the register, its address in hex, or its url.
         #[clap(name = "name")]
         name: String,
         /// The entry to add to the register.
         #[clap(name = "entry")]
         entry: String,
+        /// The entry is base64 encoded binary data, which is decoded before writing it.
+        #[clap(long)]
+        base64: bool,
     },
-    Get {
-        /// The register pet names to get.
-        #[clap(name = "names")]
+    /// Read the current entries of registers; there's more than one when there are branches.
+    #[clap(alias = "get")]
+    Read {
+        /// The names of the registers, their addresses in hex, or their urls.
+        #[clap(name = "names", required = true)]
         names: Vec<String>,
+        /// Print the entries base64 encoded, even those which are valid UTF-8.
+        #[clap(long)]
+        base64: bool,
+    },
+    /// Print all the entries ever written to a register, latest first.
+    History {
+        /// The name of the register, its address in hex, or its url.
+        #[clap(name = "name")]
+        name: String,
+        /// Print the entries base64 encoded, even those which are valid UTF-8.
+        #[clap(long)]
+        base64: bool,
+    },
+    /// Export all the entries of a register, along with the entries each was written atop,
+    /// as a JSON snapshot, e.g. to back it up or migrate it to another network.
+    Export {
+        /// The name of the register, its address in hex, or its url.
+        #[clap(name = "name")]
+        name: String,
+        /// The file to write the snapshot to. It's printed if not provided.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Create a new register with the given pet name, and replay into it the entries of a
+    /// snapshot made with `export`, preserving their branches and merges.
+    Import {
+        /// The snapshot file.
+        #[clap(name = "snapshot")]
+        snapshot: PathBuf,
+        /// The name of the register to create.
+        #[clap(name = "name")]
+        name: String,
     },
 }
 
-pub(crate) async fn register_cmds(cmds: RegisterCmds, client: &Client) -> Result<()> {
+pub(crate) async fn register_cmds(
+    cmds: RegisterCmds,
+    client: &Client,
+    output: Output,
+) -> Result<()> {
     match cmds {
-        RegisterCmds::Create { name } => create_register(name, client).await?,
-        RegisterCmds::Edit { name, entry } => edit_register(name, entry, client).await?,
-        RegisterCmds::Get { names } => get_registers(names, client).await?,
+        RegisterCmds::Create { name } => create_register(name, client, output).await?,
+        RegisterCmds::Write {
+            name,
+            entry,
+            base64,
+        } => {
+            let entry = if base64 {
+                BASE64
+                    .decode(entry)
+                    .map_err(|err| eyre!("The entry is not valid base64: {err}"))?
+            } else {
+                entry.into_bytes()
+            };
+            write_register(name, entry, client, output).await?
+        }
+        RegisterCmds::Read { names, base64 } => {
+            read_registers(names, base64, client, output).await?
+        }
+        RegisterCmds::History { name, base64 } => {
+            register_history(name, base64, client, output).await?
+        }
+        RegisterCmds::Export { name, output: path } => {
+            export_register(name, path, client, output).await?
+        }
+        RegisterCmds::Import { snapshot, name } => {
+            import_register(snapshot, name, client, output).await?
+        }
     }
     Ok(())
 }
 
-async fn create_register(name: String, client: &Client) -> Result<()> {
-    let tag = 3006;
-    let xorname = XorName::from_content(name.as_bytes());
-    println!("Creating Register with '{name}' at xorname: {xorname:x} and tag {tag}");
+/// Resolve the name of a register into its address: a register url or 64 hex characters
+/// are taken as the address itself, anything else as a pet name which is hashed into it.
+pub(crate) fn register_xorname(name: &str) -> Result<XorName> {
+    if name.starts_with(XOR_URL_SCHEME) {
+        let url: XorUrl = name.parse()?;
+        return match url.data_type {
+            DataType::Register(REGISTER_TAG) => Ok(url.name),
+            data_type => Err(eyre!(
+                "The url points at a {data_type:?}, not a Register with tag {REGISTER_TAG}"
+            )),
+        };
+    }
+    Ok(
+        match hex::decode(name)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+        {
+            Some(bytes) => XorName(bytes),
+            None => XorName::from_content(name.as_bytes()),
+        },
+    )
+}
 
-    let _register = client.create_register(xorname, tag).await?;
-    println!("Successfully created register '{name}' at {xorname:?}, {tag}!");
-    Ok(())
+/// Display an entry as text when it's valid UTF-8, or base64 encoded otherwise or when requested.
+fn display_entry(entry: &Entry, base64: bool) -> String {
+    match std::str::from_utf8(entry) {
+        Ok(text) if !base64 => text.to_string(),
+        _ => BASE64.encode(entry),
+    }
+}
+
+pub(crate) fn entries_json<'a>(
+    entries: impl IntoIterator<Item = &'a (EntryHash, Entry)>,
+    base64: bool,
+) -> Vec<Value> {
+    entries
+        .into_iter()
+        .map(|(hash, entry)| {
+            json!({ "hash": hex::encode(hash.0), "entry": display_entry(entry, base64) })
+        })
+        .collect()
+}
+
+async fn get_register(name: &str, client: &Client, output: Output) -> Result<Register> {
+    let xorname = register_xorname(name)?;
+    output.info(format!(
+        "Trying to retrieve Register from {xorname:?}, {REGISTER_TAG}"
+    ));
+    client
+        .get_register(xorname, REGISTER_TAG)
+        .await
+        .map_err(|error| {
+            eyre::Report::new(error).wrap_err(format!(
+                "Did not retrieve Register '{name}' from all nodes in the close group!"
+            ))
+        })
 }
 
-async fn edit_register(name: String, entry: String, client: &Client) -> Result<()> {
-    let tag = 3006;
+async fn create_register(name: String, client: &Client, output: Output) -> Result<()> {
     let xorname = XorName::from_content(name.as_bytes());
-    println!("Trying to retrieve Register from {xorname:?}, {tag}");
+    output.info(format!(
+        "Creating Register with '{name}' at xorname: {xorname:x} and tag {REGISTER_TAG}"
+    ));
 
-    match client.get_register(xorname, tag).await {
-        Ok(mut register) => {
-            println!(
-                "Successfully retrieved Register '{name}' from {}, {}!",
-                register.name(),
-                register.tag()
-            );
-            println!("Editing Register '{name}' with: {entry}");
-            match register.write(entry.as_bytes()).await {
-                Ok(()) => {}
-                Err(ref err @ ClientError::ContentBranchDetected(ref branches)) => {
+    let _register = client.create_register(xorname, REGISTER_TAG).await?;
+    let url = XorUrl::new(DataType::Register(REGISTER_TAG), xorname);
+    output.result(
+        &json!({
+            "name": name,
+            "address": format!("{xorname:x}"),
+            "tag": REGISTER_TAG,
+            "url": url.to_string(),
+        }),
+        || {
+            println!("Successfully created register '{name}' at {xorname:x}, {REGISTER_TAG}!");
+            println!("Share it with {url}");
+        },
+    )
+}
+
+async fn write_register(name: String, entry: Entry, client: &Client, output: Output) -> Result<()> {
+    let mut register = get_register(&name, client, output).await?;
+    output.info(format!(
+        "Successfully retrieved Register '{name}' from {}, {}!",
+        register.name(),
+        register.tag()
+    ));
+    output.info(format!(
+        "Writing to Register '{name}': {}",
+        display_entry(&entry, false)
+    ));
+    let mut merged_branches = 0;
+    match register.write(&entry).await {
+        Ok(()) => {}
+        Err(ref err @ ClientError::ContentBranchDetected(ref branches)) => {
+            output.info(format!(
+                "We need to merge {} branches in Register entries: {err}",
+                branches.len()
+            ));
+            merged_branches = branches.len();
+            register.write_merging_branches(&entry).await?;
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    output.result(
+        &json!({
+            "name": name,
+            "address": format!("{:x}", register.name()),
+            "tag": REGISTER_TAG,
+            "merged_branches": merged_branches,
+        }),
+        || println!("Successfully wrote to Register '{name}'!"),
+    )
+}
+
+async fn read_registers(
+    names: Vec<String>,
+    base64: bool,
+    client: &Client,
+    output: Output,
+) -> Result<()> {
+    let mut results = Vec::new();
+    for name in names {
+        match get_register(&name, client, output).await {
+            Ok(register) => {
+                let entries = register.read();
+                if !output.is_json() {
                     println!(
-                        "We need to merge {} branches in Register entries: {err}",
-                        branches.len()
+                        "Register '{name}' at {:x}, {} has {} current entries:",
+                        register.name(),
+                        register.tag(),
+                        entries.len()
                     );
-                    register.write_merging_branches(entry.as_bytes()).await?;
+                    for (hash, entry) in &entries {
+                        println!("{hash:?}: {}", display_entry(entry, base64));
+                    }
                 }
-                Err(err) => return Err(err.into()),
+                results.push(json!({
+                    "name": name,
+                    "address": format!("{:x}", register.name()),
+                    "tag": REGISTER_TAG,
+                    "entries": entries_json(&entries, base64),
+                }));
+            }
+            Err(error) => {
+                output.info(format!("{error:#}"));
+                results.push(json!({
+                    "name": name,
+                    "address": register_xorname(&name).ok().map(|xorname| format!("{xorname:x}")),
+                    "tag": REGISTER_TAG,
+                    "error": format!("{error:#}"),
+                }));
             }
         }
-        Err(error) => {
+    }
+
+    output.result(&results, || {})
+}
+
+async fn register_history(
+    name: String,
+    base64: bool,
+    client: &Client,
+    output: Output,
+) -> Result<()> {
+    let register = get_register(&name, client, output).await?;
+    let history = register.history();
+
+    output.result(
+        &json!({
+            "name": name,
+            "address": format!("{:x}", register.name()),
+            "tag": REGISTER_TAG,
+            "entries": entries_json(&history, base64),
+        }),
+        || {
             println!(
-                "Did not retrieve Register '{name}' from all nodes in the close group! {error}"
-            )
+                "Register '{name}' at {:x}, {} has {} entries:",
+                register.name(),
+                register.tag(),
+                history.len()
+            );
+            for (hash, entry) in &history {
+                println!("{hash:?}: {}", display_entry(entry, base64));
+            }
+        },
+    )
+}
+
+async fn export_register(
+    name: String,
+    path: Option<PathBuf>,
+    client: &Client,
+    output: Output,
+) -> Result<()> {
+    let register = get_register(&name, client, output).await?;
+    let entries = register
+        .history()
+        .into_iter()
+        .map(|(hash, entry)| Ok((hash, entry, register.parents(hash)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let snapshot = RegisterSnapshot::new(register.name(), register.tag(), entries);
+    let content = serde_json::to_string_pretty(&snapshot)?;
+
+    let path = match path {
+        Some(path) => path,
+        None => {
+            println!("{content}");
+            return Ok(());
         }
+    };
+    std::fs::write(&path, content)?;
+    output.result(
+        &json!({
+            "name": name,
+            "address": snapshot.address,
+            "tag": snapshot.tag,
+            "entries": snapshot.entries.len(),
+            "path": path,
+        }),
+        || {
+            println!(
+                "Exported the {} entries of Register '{name}' to {path:?}",
+                snapshot.entries.len()
+            )
+        },
+    )
+}
+
+async fn import_register(
+    snapshot_path: PathBuf,
+    name: String,
+    client: &Client,
+    output: Output,
+) -> Result<()> {
+    let content = std::fs::read_to_string(&snapshot_path)?;
+    let snapshot: RegisterSnapshot = serde_json::from_str(&content)
+        .map_err(|err| eyre!("{snapshot_path:?} is not a register snapshot: {err}"))?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(eyre!(
+            "The snapshot has an unsupported version {}, expected {SNAPSHOT_VERSION}",
+            snapshot.version
+        ));
     }
 
-    Ok(())
+    let xorname = XorName::from_content(name.as_bytes());
+    output.info(format!(
+        "Importing {} entries into a Register '{name}' at xorname: {xorname:x} and tag {REGISTER_TAG}",
+        snapshot.entries.len()
+    ));
+    let mut register = RegisterOffline::create(client.clone(), xorname, REGISTER_TAG)?;
+    snapshot.replay(&mut register)?;
+    let register = register.online().await?;
+
+    let url = XorUrl::new(DataType::Register(REGISTER_TAG), xorname);
+    output.result(
+        &json!({
+            "name": name,
+            "address": format!("{xorname:x}"),
+            "tag": REGISTER_TAG,
+            "url": url.to_string(),
+            "entries": register.size(),
+        }),
+        || {
+            println!(
+                "Successfully imported {} entries into Register '{name}' at {xorname:x}, {REGISTER_TAG}!",
+                register.size()
+            );
+            println!("Share it with {url}");
+        },
+    )
 }
 
-async fn get_registers(names: Vec<String>, client: &Client) -> Result<()> {
-    let tag = 3006;
-    for name in names {
-        println!("Register name passed in via `register get` is '{name}'...");
-        let xorname = XorName::from_content(name.as_bytes());
+fn entry_hash(hex_hash: &str) -> Result<EntryHash> {
+    hex::decode(hex_hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(EntryHash)
+        .ok_or_else(|| eyre!("{hex_hash} is not a valid entry hash"))
+}
 
-        println!("Trying to retrieve Register from {xorname:?}, {tag}");
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        match client.get_register(xorname, tag).await {
-            Ok(register) => println!(
-                "Successfully retrieved Register '{name}' from {}, {}!",
-                register.name(),
-                register.tag()
-            ),
-            Err(error) => {
-                println!(
-                    "Did not retrieve Register '{name}' from all nodes in the close group! {error}"
-                )
-            }
+    use crdts::{merkle_reg::MerkleReg, CmRDT};
+
+    impl ReplayTarget for MerkleReg<Entry> {
+        fn write_atop(&mut self, entry: &[u8], parents: BTreeSet<EntryHash>) -> Result<()> {
+            let parents = parents.into_iter().map(|parent| parent.0).collect();
+            let node = self.write(entry.to_vec(), parents);
+            self.apply(node);
+            Ok(())
+        }
+
+        fn contains(&self, hash: EntryHash) -> bool {
+            self.node(hash.0).is_some()
         }
     }
 
-    Ok(())
+    // Writes the entry atop the given ones, returning it along with its hash and parents.
+    fn write(
+        register: &mut MerkleReg<Entry>,
+        entry: &[u8],
+        parents: &[EntryHash],
+    ) -> (EntryHash, Entry, BTreeSet<EntryHash>) {
+        let parents: BTreeSet<_> = parents.iter().copied().collect();
+        let node = register.write(
+            entry.to_vec(),
+            parents.iter().map(|parent| parent.0).collect(),
+        );
+        let hash = EntryHash(node.hash());
+        register.apply(node);
+        (hash, entry.to_vec(), parents)
+    }
+
+    // Two branches written atop a first entry, then merged by a last one, latest first.
+    fn exported_entries() -> Vec<(EntryHash, Entry, BTreeSet<EntryHash>)> {
+        let mut register = MerkleReg::new();
+        let first = write(&mut register, b"first", &[]);
+        let branch_1 = write(&mut register, b"branch 1", &[first.0]);
+        let branch_2 = write(&mut register, b"branch 2", &[first.0]);
+        let last = write(&mut register, b"last", &[branch_1.0, branch_2.0]);
+        vec![last, branch_2, branch_1, first]
+    }
+
+    fn snapshot_json(entries: Vec<(EntryHash, Entry, BTreeSet<EntryHash>)>) -> Result<String> {
+        let address = XorName::random(&mut rand::thread_rng());
+        Ok(serde_json::to_string(&RegisterSnapshot::new(
+            &address,
+            REGISTER_TAG,
+            entries,
+        ))?)
+    }
+
+    #[test]
+    fn snapshots_are_imported_as_exported() -> Result<()> {
+        let entries = exported_entries();
+        let snapshot: RegisterSnapshot = serde_json::from_str(&snapshot_json(entries.clone())?)?;
+
+        let mut imported = MerkleReg::<Entry>::new();
+        snapshot.replay(&mut imported)?;
+        for (hash, entry, parents) in entries {
+            let node = imported
+                .node(hash.0)
+                .expect("The entry should have been imported");
+            assert_eq!(node.value, entry);
+            assert_eq!(
+                node.children,
+                parents
+                    .iter()
+                    .map(|parent| parent.0)
+                    .collect::<BTreeSet<_>>()
+            );
+        }
+        assert_eq!(imported.read().values().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_entries_are_not_imported() -> Result<()> {
+        let mut snapshot: RegisterSnapshot =
+            serde_json::from_str(&snapshot_json(exported_entries())?)?;
+        snapshot.entries[1].entry = BASE64.encode(b"tampered");
+
+        let err = snapshot
+            .replay(&mut MerkleReg::<Entry>::new())
+            .expect_err("A tampered entry should not be imported");
+        assert!(err.to_string().contains("doesn't match its hash"));
+        Ok(())
+    }
+
+    #[test]
+    fn entries_written_atop_foreign_entries_are_not_imported() -> Result<()> {
+        // The last entry is written atop an entry of another register, missing from the snapshot.
+        let mut other = MerkleReg::new();
+        let (foreign, _, _) = write(&mut other, b"foreign", &[]);
+        let mut entries = exported_entries();
+        entries.insert(0, write(&mut other, b"atop foreign", &[foreign]));
+        let snapshot: RegisterSnapshot = serde_json::from_str(&snapshot_json(entries)?)?;
+
+        let err = snapshot
+            .replay(&mut MerkleReg::<Entry>::new())
+            .expect_err("An entry written atop a foreign entry should not be imported");
+        assert!(err.to_string().contains("doesn't precede it"));
+        Ok(())
+    }
 }
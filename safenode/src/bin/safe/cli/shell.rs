@@ -0,0 +1,151 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Opt;
+
+use clap::{Command, CommandFactory, Parser};
+use eyre::{eyre, Result};
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    history::DefaultHistory,
+    Context, Editor, Helper, Highlighter, Hinter, Validator,
+};
+use std::path::{Path, PathBuf};
+
+const HISTORY_FILE_NAME: &str = "shell_history";
+const PROMPT: &str = "safe> ";
+
+type ShellEditor = Editor<ShellHelper, DefaultHistory>;
+
+/// Reads the commands of the interactive shell, with history and tab completion.
+pub(crate) struct Shell {
+    /// Taken while waiting for a line, which is read on a blocking thread.
+    editor: Option<ShellEditor>,
+    history_path: PathBuf,
+}
+
+impl Shell {
+    /// Create a shell, with the history of previous sessions stored in the client dir.
+    pub(crate) fn new(root_dir: &Path) -> Result<Self> {
+        let mut editor = ShellEditor::new()?;
+        editor.set_helper(Some(ShellHelper {
+            command: Opt::command(),
+        }));
+        let history_path = root_dir.join(HISTORY_FILE_NAME);
+        if history_path.is_file() {
+            editor.load_history(&history_path)?;
+        }
+        println!("Type a command as given to `safe`, e.g. `wallet balance`, or `exit` to leave.");
+        Ok(Self {
+            editor: Some(editor),
+            history_path,
+        })
+    }
+
+    /// Read the next command, returning `None` once the user exits the shell.
+    pub(crate) async fn read_cmd(&mut self) -> Result<Option<Opt>> {
+        loop {
+            let mut editor = self
+                .editor
+                .take()
+                .ok_or_else(|| eyre!("The shell editor was lost"))?;
+            // Reading blocks, which must not hold up the client's tasks on the runtime.
+            let (editor, line) = tokio::task::spawn_blocking(move || {
+                let line = editor.readline(PROMPT);
+                (editor, line)
+            })
+            .await?;
+            let editor = self.editor.insert(editor);
+
+            let line = match line {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let _ = editor.add_history_entry(line)?;
+            if line == "exit" || line == "quit" {
+                return Ok(None);
+            }
+
+            let args = match shell_words::split(line) {
+                Ok(args) => args,
+                Err(err) => {
+                    eprintln!("Error: {err}");
+                    continue;
+                }
+            };
+            match Opt::try_parse_from(std::iter::once("safe".to_string()).chain(args)) {
+                Ok(opt) => return Ok(Some(opt)),
+                // Prints the help too, when asked for.
+                Err(err) => err.print()?,
+            }
+        }
+    }
+
+    /// Store the history of the commands, for later sessions.
+    pub(crate) fn save_history(&mut self) -> Result<()> {
+        if let Some(editor) = &mut self.editor {
+            editor.save_history(&self.history_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Completes the names of subcommands and options, according to the definition of the CLI.
+#[derive(Helper, Highlighter, Hinter, Validator)]
+struct ShellHelper {
+    command: Command,
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line
+            .rfind(char::is_whitespace)
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let prefix = &line[start..];
+
+        let mut command = &self.command;
+        for word in line[..start].split_whitespace() {
+            if let Some(subcommand) = command.find_subcommand(word) {
+                command = subcommand;
+            }
+        }
+
+        let candidates = command
+            .get_subcommands()
+            .map(|subcommand| subcommand.get_name().to_string())
+            .chain(
+                command
+                    .get_arguments()
+                    .filter_map(|arg| arg.get_long())
+                    .map(|long| format!("--{long}")),
+            )
+            .filter(|candidate| candidate.starts_with(prefix))
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+        Ok((start, candidates))
+    }
+}
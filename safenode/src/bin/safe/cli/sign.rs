@@ -0,0 +1,79 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{keys::load_or_create_key, output::Output};
+
+use bls::{PublicKey, Signature};
+use eyre::{eyre, Result};
+use serde_json::json;
+use std::path::Path;
+use tokio::io::AsyncReadExt;
+
+/// Sign the contents of the file, or of stdin if the path is `-`, with the key of the given name,
+/// printing the hex-encoded signature along with the public key to verify it with.
+pub(crate) async fn sign(
+    path: &str,
+    key_name: &str,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    let secret_key = load_or_create_key(root_dir, key_name).await?;
+    let content = read_input(path).await?;
+
+    let signature_hex = hex::encode(secret_key.sign(&content).to_bytes());
+    let public_key_hex = hex::encode(secret_key.public_key().to_bytes());
+    output.result(
+        &json!({ "signature": signature_hex, "public_key": public_key_hex }),
+        || {
+            println!("Signature: {signature_hex}");
+            println!("Public key: {public_key_hex}");
+        },
+    )
+}
+
+/// Verify that the hex-encoded signature was made over the contents of the file, or of stdin
+/// if the path is `-`, with the secret key of the hex-encoded public key.
+pub(crate) async fn verify(
+    path: &str,
+    signature: &str,
+    public_key: &str,
+    output: Output,
+) -> Result<()> {
+    let signature = hex::decode(signature.trim())
+        .map_err(|err| eyre!("The signature is not a hex string: {err}"))?
+        .try_into()
+        .map_err(|_| eyre!("The signature must be 96 bytes long, i.e. 192 hex characters"))
+        .and_then(|bytes| Ok(Signature::from_bytes(bytes)?))?;
+    let public_key = hex::decode(public_key.trim())
+        .map_err(|err| eyre!("The public key is not a hex string: {err}"))?
+        .try_into()
+        .map_err(|_| eyre!("The public key must be 48 bytes long, i.e. 96 hex characters"))
+        .and_then(|bytes| Ok(PublicKey::from_bytes(bytes)?))?;
+    let content = read_input(path).await?;
+
+    if !public_key.verify(&signature, &content) {
+        return Err(eyre!(
+            "The signature is not valid for the given content and public key"
+        ));
+    }
+    output.result(&json!({ "valid": true }), || {
+        println!("The signature is valid.")
+    })
+}
+
+async fn read_input(path: &str) -> Result<Vec<u8>> {
+    if path == "-" {
+        let mut content = vec![];
+        let _ = tokio::io::stdin().read_to_end(&mut content).await?;
+        Ok(content)
+    } else {
+        tokio::fs::read(path)
+            .await
+            .map_err(|err| eyre!(err).wrap_err(format!("Failed to read {path}")))
+    }
+}
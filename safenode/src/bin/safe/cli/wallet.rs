@@ -6,120 +6,1222 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::{config::Config, output::Output};
+
 use safenode::{
     client::{Client, WalletClient},
-    domain::wallet::{parse_public_address, LocalWallet, Wallet},
+    domain::{
+        dbc_genesis::create_genesis_dbc,
+        wallet::{
+            parse_public_address, DepositWallet, Error as WalletError, HistoryFilter, LocalWallet,
+            OfflineTransfer, SignedTransfer, TokenAmount, TransferDirection, UnsignedTransfer,
+            VerifyingClient, Wallet, WatchOnlyWallet,
+        },
+    },
+    protocol::{
+        storage::DbcAddress,
+        xorurl::{XorUrl, XOR_URL_SCHEME},
+    },
 };
 
-use sn_dbc::Token;
+use sn_dbc::{Dbc, PublicAddress, Token};
 
 use clap::Parser;
-use eyre::Result;
-use std::path::Path;
+use eyre::{eyre, Result};
+use qrcode::{render::unicode, QrCode};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use xor_name::XorName;
+
+/// The env var the passphrase of an encrypted wallet is read from, rather than prompting for it.
+const PASSPHRASE_ENV_VAR: &str = "SAFE_WALLET_PASSPHRASE";
+/// The env var the new passphrase is read from by `wallet encrypt`, rather than prompting for it.
+const NEW_PASSPHRASE_ENV_VAR: &str = "SAFE_WALLET_NEW_PASSPHRASE";
+/// The file in the dir of the wallet the genesis dbc of a new network is written to.
+const GENESIS_DBC_FILE_NAME: &str = "genesis_dbc";
+/// The env var the mnemonic is read from by `wallet restore`, rather than prompting for it.
+const MNEMONIC_ENV_VAR: &str = "SAFE_WALLET_MNEMONIC";
 
 #[derive(Parser, Debug)]
 pub enum WalletCmds {
-    /// Print the address of the wallet.
-    Address,
+    /// Print the address of the wallet, for others to send tokens to.
+    /// It is printed hex-encoded, which is the form `wallet send` expects.
+    Address {
+        /// Also print the address as a QR code.
+        #[clap(long)]
+        qr: bool,
+    },
+    /// List the receiving addresses handed out with `wallet new-address`.
+    Addresses,
     /// Print the balance of the wallet.
-    Balance,
+    Balance {
+        /// Check the spend status of each of the wallet's DBCs on the network,
+        /// flagging any which were spent elsewhere, and print the verified balance.
+        #[clap(long)]
+        verify: bool,
+    },
+    /// Print the mnemonic the key of the wallet was derived from, to write down and keep safe.
+    /// The key can be restored from it with `wallet restore`, e.g. after losing the disk.
+    Backup,
+    /// Register a transfer signed with `wallet sign-transfer` in the network, from the watch-only
+    /// wallet which built it. A transfer file is written for each recipient, to be handed to them.
+    Broadcast {
+        /// The path to the signed transfer file.
+        #[clap(name = "file")]
+        file: PathBuf,
+    },
+    /// Build a transfer from the watch-only wallet, to be signed with `wallet sign-transfer` on
+    /// the machine holding the key. Any `Dbc`s placed in the `received_dbcs` path of the
+    /// watch-only wallet dir are verified against the network and watched beforehand.
+    BuildTransfer {
+        /// The amount to send, in tokens with up to nine decimals, e.g. `1.337`,
+        /// or in nanos with a `nano` suffix, e.g. `1337000000nano`.
+        #[clap(name = "amount")]
+        amount: TokenAmount,
+        /// This must be a hex-encoded `PublicAddress`, or the name of a contact
+        /// added with `safe contacts add`.
+        #[clap(name = "to")]
+        to: String,
+        /// The path to write the unsigned transfer file to.
+        #[clap(long, default_value = "unsigned_transfer.json")]
+        out: PathBuf,
+    },
+    /// Reissue the many small `Dbc`s of the wallet, e.g. from lots of small receipts and
+    /// change outputs, into a few larger ones, keeping the wallet fast.
+    Consolidate {
+        /// The maximum number of `Dbc`s combined into one, in a single transfer.
+        #[clap(long, default_value_t = 10)]
+        batch_size: usize,
+    },
+    /// Mint the initial supply of a new network into the wallet, e.g. for a private network.
+    /// The genesis `Dbc` is written to the `genesis_dbc` file in the dir of the wallet, which every
+    /// node of the network has to be started with, using `safenode --genesis-dbc <path>`.
+    /// Only an empty wallet can create it, and the nodes of an existing network don't accept it.
+    CreateGenesis,
+    /// Decrypt the wallet files, storing them unencrypted from then on.
+    Decrypt,
     /// Deposit `Dbc`s to the local wallet.
-    /// Tries to load any `Dbc`s from the `received_dbcs`
-    /// path in the wallet dir, and deposit it to the wallet.
+    /// If a `Dbc` is given, either as a path to a transfer file written by `wallet send`,
+    /// a path to a file containing a hex-encoded `Dbc`, or as a hex string, its spends are
+    /// verified against the network before depositing it.
+    /// Otherwise, tries to load any `Dbc`s, or transfer files, from the `received_dbcs`
+    /// path in the wallet dir, and deposit them to the wallet.
     /// The user has to manually place received dbc files to
     /// that dir, for example by choosing that path when downloading
     /// the dbc file from email or browser.
-    Deposit,
+    Deposit {
+        /// The path to a transfer file or to a file containing a hex-encoded `Dbc`,
+        /// or the hex string itself.
+        #[clap(name = "dbc")]
+        dbc: Option<String>,
+    },
+    /// Encrypt the wallet files, i.e. its key, DBCs and history, with a passphrase.
+    /// It's then asked for whenever the wallet is used, unless it's set in the
+    /// `SAFE_WALLET_PASSPHRASE` env var. If the wallet is already encrypted, its passphrase is changed.
+    /// The new passphrase is asked for, unless it's set in the `SAFE_WALLET_NEW_PASSPHRASE` env var.
+    Encrypt,
+    /// Get test tokens from a faucet, verify them against the network and deposit them to the wallet.
+    /// The faucet is asked for tokens at `<url>/<address>`, with the hex-encoded address of the
+    /// wallet, and is expected to respond with a hex-encoded `Dbc`.
+    GetFaucet {
+        /// The url of the faucet. Defaults to the faucet of the selected network,
+        /// as set with `safe networks add --faucet`, or else the faucet in the config file.
+        #[clap(name = "url")]
+        url: Option<String>,
+    },
+    /// Print the transfers the wallet took part in, oldest first.
+    History {
+        /// Only print the tokens sent.
+        #[clap(long, conflicts_with = "received")]
+        sent: bool,
+        /// Only print the tokens received.
+        #[clap(long)]
+        received: bool,
+        /// Only print the transfers made on or after this date, given as `YYYY-MM-DD` (UTC)
+        /// or in seconds since the Unix epoch.
+        #[clap(long, value_parser = parse_time)]
+        since: Option<u64>,
+        /// Only print the transfers made before this date, given as `YYYY-MM-DD` (UTC)
+        /// or in seconds since the Unix epoch.
+        #[clap(long, value_parser = parse_time)]
+        until: Option<u64>,
+    },
+    /// List the named wallets kept in the client dir, as selected with `--wallet <name>`.
+    List,
+    /// Hand out a new receiving address, e.g. to a single counterparty, so that the transfers to
+    /// the wallet can't be linked together. The tokens sent to it are spent along with the others.
+    NewAddress {
+        /// What the address is for, e.g. the name of the counterparty it's handed out to.
+        #[clap(name = "label")]
+        label: String,
+    },
+    /// Complete the transfers left pending by a failed or interrupted send, after looking up
+    /// their spends in the network, so that their tokens are not stranded.
+    Pending {
+        /// Cancel the pending transfers which didn't reach the network instead, returning their
+        /// inputs to the wallet. The transfer files written for their recipients are then invalid.
+        /// The transfers which partly reached the network are completed.
+        #[clap(long)]
+        cancel: bool,
+    },
+    /// Print the receipt of the payment the wallet made for storing the data at an address,
+    /// after checking its spends are the ones registered in the network, to prove the payment
+    /// was made, e.g. when the network reports it missing as the data is uploaded again.
+    Receipt {
+        /// The address of the data, hex-encoded or as a url.
+        #[clap(name = "address")]
+        address: String,
+    },
+    /// Restore the key of the wallet from the mnemonic printed by `wallet backup`, which is asked
+    /// for unless it's set in the `SAFE_WALLET_MNEMONIC` env var. The DBCs of the wallet are not
+    /// restored along with the key, they have to be deposited again.
+    Restore {
+        /// Replace any existing wallet with another key. It's moved to a
+        /// `wallet-replaced-<timestamp>` dir next to it, rather than deleted.
+        #[clap(long)]
+        force: bool,
+    },
+    /// Scan the inboxes of the wallet on the network for the `Dbc`s delivered to it with
+    /// `wallet send --inbox`, verify them against the network and deposit them to the wallet.
+    Scan,
+    /// Send tokens to an address. The `Dbc` created for the recipient is written to a transfer
+    /// file in the `created_dbcs` path in the wallet dir, to be handed to them over any channel,
+    /// e.g. email or USB, and deposited with `wallet deposit`.
     Send {
-        /// This shall be the number of nanos to send.
-        /// Necessary if the `to` argument has been given.
+        /// The amount to send, in tokens with up to nine decimals, e.g. `1.337`,
+        /// or in nanos with a `nano` suffix, e.g. `1337000000nano`.
         #[clap(name = "amount")]
-        amount: String,
-        /// This must be a hex-encoded `PublicAddress`.
+        amount: TokenAmount,
+        /// This must be a hex-encoded `PublicAddress`, or the name of a contact
+        /// added with `safe contacts add`.
         #[clap(name = "to")]
         to: String,
+        /// Also deliver the `Dbc`s to the inbox of the recipient on the network, encrypted to
+        /// its key, for it to be found with `wallet scan` without the transfer file.
+        #[clap(long)]
+        inbox: bool,
+    },
+    /// Send tokens to many addresses in a single transfer, e.g. for a payroll, spending the
+    /// `Dbc`s of the wallet only once for all of them. A transfer file is written for each recipient.
+    SendMany {
+        /// The path to a file listing the recipients, one per line, as `<amount>,<to>`, where the
+        /// amount and address are as given to `wallet send`. Empty lines and lines starting with
+        /// `#` are ignored.
+        #[clap(name = "file")]
+        file: PathBuf,
+        /// Also deliver the `Dbc`s to the inbox of the recipient on the network, encrypted to
+        /// its key, for it to be found with `wallet scan` without the transfer file.
+        #[clap(long)]
+        inbox: bool,
+    },
+    /// Sign a transfer built with `wallet build-transfer`, e.g. on an air-gapped machine holding
+    /// the key. Doesn't connect to the network, nor change the wallet.
+    SignTransfer {
+        /// The path to the unsigned transfer file.
+        #[clap(name = "file")]
+        file: PathBuf,
+        /// The path to write the signed transfer file to.
+        #[clap(long, default_value = "signed_transfer.json")]
+        out: PathBuf,
+    },
+    /// Create a watch-only wallet of an address next to the wallet, e.g. on an online machine
+    /// while the key is kept on an air-gapped one. It builds the transfers signed with the key,
+    /// and broadcasts them, with `wallet build-transfer` and `wallet broadcast`.
+    Watch {
+        /// The hex-encoded address of the wallet holding the key, as printed by `wallet address`.
+        #[clap(name = "address")]
+        address: String,
     },
 }
 
-pub(crate) async fn wallet_cmds(cmds: WalletCmds, client: &Client, root_dir: &Path) -> Result<()> {
+pub(crate) async fn wallet_cmds(
+    cmds: WalletCmds,
+    client: &Client,
+    root_dir: &Path,
+    config: &Config,
+    output: Output,
+) -> Result<()> {
     match cmds {
-        WalletCmds::Address => address(root_dir).await?,
-        WalletCmds::Balance => balance(root_dir).await?,
-        WalletCmds::Deposit => deposit(root_dir).await?,
-        WalletCmds::Send { amount, to } => send(amount, to, client, root_dir).await?,
+        WalletCmds::Address { qr } => address(qr, root_dir, output).await?,
+        WalletCmds::Addresses => receive_addresses(root_dir, output).await?,
+        WalletCmds::Backup => backup(root_dir, output).await?,
+        WalletCmds::Broadcast { file } => broadcast(&file, client, root_dir, output).await?,
+        WalletCmds::BuildTransfer { amount, to, out } => {
+            let to = config.contacts.get(&to).cloned().unwrap_or(to);
+            build_transfer(amount, to, &out, client, root_dir, output).await?
+        }
+        WalletCmds::Balance { verify: false } => balance(root_dir, output).await?,
+        WalletCmds::Balance { verify: true } => verified_balance(client, root_dir, output).await?,
+        WalletCmds::Consolidate { batch_size } => {
+            consolidate(batch_size, client, root_dir, output).await?
+        }
+        WalletCmds::CreateGenesis => create_genesis(root_dir, output).await?,
+        WalletCmds::Decrypt => set_passphrase(false, root_dir, output).await?,
+        WalletCmds::Encrypt => set_passphrase(true, root_dir, output).await?,
+        WalletCmds::Deposit { dbc: None } => deposit(root_dir, output).await?,
+        WalletCmds::Deposit { dbc: Some(dbc) } => {
+            deposit_dbc(&dbc, client, root_dir, output).await?
+        }
+        WalletCmds::GetFaucet { url } => {
+            let url = url.or_else(|| config.faucet_url()).ok_or_else(|| {
+                eyre!("No faucet url given, and none is configured for the network")
+            })?;
+            get_faucet(&url, client, root_dir, output).await?
+        }
+        WalletCmds::History {
+            sent,
+            received,
+            since,
+            until,
+        } => {
+            let direction = match (sent, received) {
+                (true, _) => Some(TransferDirection::Sent),
+                (_, true) => Some(TransferDirection::Received),
+                _ => None,
+            };
+            let filter = HistoryFilter {
+                direction,
+                since,
+                until,
+            };
+            history(&filter, root_dir, output).await?
+        }
+        // Run without connecting to the network, along with the other offline commands.
+        WalletCmds::List => {}
+        WalletCmds::NewAddress { label } => new_receive_address(label, root_dir, output).await?,
+        WalletCmds::Pending { cancel } => pending(cancel, client, root_dir, output).await?,
+        WalletCmds::Receipt { address } => receipt(&address, client, root_dir, output).await?,
+        WalletCmds::Restore { force } => restore(force, root_dir, output).await?,
+        WalletCmds::Scan => scan(client, root_dir, output).await?,
+        WalletCmds::Send { amount, to, inbox } => {
+            let to = config.contacts.get(&to).cloned().unwrap_or(to);
+            send(amount, to, inbox, client, root_dir, output).await?
+        }
+        WalletCmds::SendMany { file, inbox } => {
+            send_many(&file, inbox, client, root_dir, config, output).await?
+        }
+        WalletCmds::SignTransfer { file, out } => {
+            sign_transfer(&file, &out, root_dir, output).await?
+        }
+        WalletCmds::Watch { address } => watch(&address, root_dir, output).await?,
     }
     Ok(())
 }
 
-async fn address(root_dir: &Path) -> Result<()> {
-    let wallet = LocalWallet::load_from(root_dir).await?;
+/// Load the wallet, with the passphrase from the env var or else prompted for, if it's encrypted.
+pub(crate) async fn load_wallet(root_dir: &Path) -> Result<LocalWallet> {
+    let passphrase = if LocalWallet::is_encrypted(root_dir).await? {
+        Some(read_passphrase("Wallet passphrase: ")?)
+    } else {
+        None
+    };
+    Ok(LocalWallet::load_with_passphrase(root_dir, passphrase).await?)
+}
+
+fn read_passphrase(prompt: &str) -> Result<String> {
+    match std::env::var(PASSPHRASE_ENV_VAR) {
+        Ok(passphrase) => Ok(passphrase),
+        Err(_) => Ok(rpassword::prompt_password(prompt)?),
+    }
+}
+
+async fn set_passphrase(encrypt: bool, root_dir: &Path, output: Output) -> Result<()> {
+    let mut wallet = load_wallet(root_dir).await?;
+    if encrypt {
+        let passphrase = match std::env::var(NEW_PASSPHRASE_ENV_VAR) {
+            Ok(passphrase) => passphrase,
+            Err(_) => {
+                let passphrase = rpassword::prompt_password("New passphrase: ")?;
+                if rpassword::prompt_password("Repeat the new passphrase: ")? != passphrase {
+                    return Err(eyre!("The passphrases do not match"));
+                }
+                passphrase
+            }
+        };
+        if passphrase.is_empty() {
+            return Err(eyre!("The passphrase cannot be empty"));
+        }
+        wallet.set_passphrase(Some(passphrase));
+    } else {
+        wallet.set_passphrase(None);
+    }
+    wallet.store().await?;
+
+    output.result(&json!({ "encrypted": encrypt }), || {
+        if encrypt {
+            println!("The wallet is now encrypted. Keep the passphrase safe, the wallet can't be used without it.");
+        } else {
+            println!("The wallet is now stored unencrypted.");
+        }
+    })
+}
+
+async fn backup(root_dir: &Path, output: Output) -> Result<()> {
+    let wallet = load_wallet(root_dir).await?;
+    let mnemonic = wallet.mnemonic().ok_or_else(|| {
+        eyre!(
+            "The wallet was created before keys were derived from mnemonics, \
+            back up the `main_key` file in the wallet dir instead"
+        )
+    })?;
+    output.result(&json!({ "mnemonic": mnemonic }), || {
+        println!("{mnemonic}");
+        println!("Write these words down and keep them safe, anyone who has them can spend the tokens of the wallet.");
+    })
+}
+
+async fn restore(force: bool, root_dir: &Path, output: Output) -> Result<()> {
+    let mnemonic = match std::env::var(MNEMONIC_ENV_VAR) {
+        Ok(mnemonic) => mnemonic,
+        Err(_) => rpassword::prompt_password("Mnemonic: ")?,
+    };
+    // An existing encrypted wallet keeps its passphrase when restored.
+    let passphrase = if LocalWallet::is_encrypted(root_dir).await? {
+        Some(read_passphrase("Wallet passphrase: ")?)
+    } else {
+        None
+    };
+    let wallet = LocalWallet::restore_from_mnemonic(root_dir, &mnemonic, passphrase, force)
+        .await
+        .map_err(|err| match err {
+            WalletError::WalletAlreadyExists => eyre!(err)
+                .wrap_err("Use --force to replace the existing wallet with the restored one"),
+            err => eyre!(err),
+        })?;
+
     let address_hex = hex::encode(wallet.address().to_bytes());
-    println!("{address_hex}");
-    Ok(())
+    output.result(&json!({ "address": address_hex }), || {
+        println!("Restored the wallet with address {address_hex}.");
+        println!("Its DBCs can now be deposited again with `wallet deposit`.");
+    })
 }
 
-async fn balance(root_dir: &Path) -> Result<()> {
-    let wallet = LocalWallet::load_from(root_dir).await?;
-    let balance = wallet.balance();
-    println!("{balance}");
-    Ok(())
+async fn address(qr: bool, root_dir: &Path, output: Output) -> Result<()> {
+    let wallet = load_wallet(root_dir).await?;
+    let address_hex = hex::encode(wallet.address().to_bytes());
+
+    let qr_code = if qr {
+        let code = QrCode::new(address_hex.as_bytes())?;
+        Some(
+            code.render::<unicode::Dense1x2>()
+                .dark_color(unicode::Dense1x2::Light)
+                .light_color(unicode::Dense1x2::Dark)
+                .build(),
+        )
+    } else {
+        None
+    };
+
+    output.result(
+        &json!({ "address": address_hex, "qr_code": qr_code }),
+        || {
+            println!("{address_hex}");
+            if let Some(qr_code) = &qr_code {
+                println!("{qr_code}");
+            }
+        },
+    )
+}
+
+async fn new_receive_address(label: String, root_dir: &Path, output: Output) -> Result<()> {
+    let mut wallet = load_wallet(root_dir).await?;
+    let address = wallet.new_receive_address(label.clone())?;
+    wallet.store().await?;
+
+    let address_hex = hex::encode(address.to_bytes());
+    output.result(&json!({ "label": label, "address": address_hex }), || {
+        println!("{address_hex}")
+    })
+}
+
+pub(crate) async fn list_wallets(root_dir: &Path, output: Output) -> Result<()> {
+    let names = LocalWallet::named_wallets(root_dir).await?;
+    output.result(&json!({ "wallets": names }), || {
+        if names.is_empty() {
+            println!(
+                "No named wallets, create one by passing `--wallet <name>` to any wallet command."
+            );
+        }
+        for name in &names {
+            println!("{name}");
+        }
+    })
+}
+
+async fn receive_addresses(root_dir: &Path, output: Output) -> Result<()> {
+    let wallet = load_wallet(root_dir).await?;
+    let addresses: Vec<_> = wallet
+        .receive_addresses()?
+        .into_iter()
+        .map(|(receive_address, address)| (receive_address.label, hex::encode(address.to_bytes())))
+        .collect();
+
+    output.result(
+        &addresses
+            .iter()
+            .map(|(label, address)| json!({ "label": label, "address": address }))
+            .collect::<Vec<_>>(),
+        || {
+            if addresses.is_empty() {
+                println!("No receiving addresses were handed out yet.");
+            }
+            for (label, address) in &addresses {
+                println!("{label}: {address}");
+            }
+        },
+    )
+}
+
+async fn balance(root_dir: &Path, output: Output) -> Result<()> {
+    let wallet = load_wallet(root_dir).await?;
+    let balance = TokenAmount::from(wallet.balance());
+    output.result(&json!({ "balance": balance }), || println!("{balance}"))
+}
+
+async fn history(filter: &HistoryFilter, root_dir: &Path, output: Output) -> Result<()> {
+    let wallet = load_wallet(root_dir).await?;
+    let history = wallet.filtered_history(filter);
+    let entries: Vec<_> = history
+        .iter()
+        .map(|entry| {
+            json!({
+                "direction": match entry.direction {
+                    TransferDirection::Sent => "sent",
+                    TransferDirection::Received => "received",
+                },
+                "amount": TokenAmount::from(entry.amount),
+                "counterpart": entry
+                    .counterpart
+                    .map(|address| hex::encode(address.to_bytes())),
+                "timestamp": entry.timestamp,
+                "time": format_timestamp(entry.timestamp),
+                "dbc_ids": entry
+                    .dbc_ids
+                    .iter()
+                    .map(|id| hex::encode(DbcAddress::from_dbc_id(id).name()))
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    output.result(&entries, || {
+        if wallet.history().is_empty() {
+            println!("The wallet has not taken part in any transfers yet.");
+        } else if history.is_empty() {
+            println!("None of the transfers of the wallet match.");
+        }
+        for entry in &history {
+            let time = format_timestamp(entry.timestamp);
+            let amount = TokenAmount::from(entry.amount);
+            match (entry.direction, entry.counterpart) {
+                (TransferDirection::Sent, Some(to)) => {
+                    println!("{time}  sent {amount} to {}", hex::encode(to.to_bytes()))
+                }
+                (TransferDirection::Sent, None) => println!("{time}  sent {amount}"),
+                (TransferDirection::Received, _) => println!("{time}  received {amount}"),
+            }
+            for dbc_id in &entry.dbc_ids {
+                println!(
+                    "    DBC {}",
+                    hex::encode(DbcAddress::from_dbc_id(dbc_id).name())
+                );
+            }
+        }
+    })
+}
+
+/// Parse a date as `YYYY-MM-DD`, at midnight UTC, or else seconds since the Unix epoch.
+fn parse_time(time: &str) -> Result<u64> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(time, "%Y-%m-%d") {
+        let timestamp = date
+            .and_hms_opt(0, 0, 0)
+            .map(|time| time.timestamp())
+            .unwrap_or_default();
+        return Ok(timestamp.max(0) as u64);
+    }
+    time.parse()
+        .map_err(|_| eyre!("Expected a date as YYYY-MM-DD, or seconds since the Unix epoch"))
+}
+
+/// Format seconds since the Unix epoch as a UTC date and time.
+fn format_timestamp(timestamp: u64) -> String {
+    chrono::NaiveDateTime::from_timestamp_opt(timestamp as i64, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_default()
+}
+
+async fn verified_balance(client: &Client, root_dir: &Path, output: Output) -> Result<()> {
+    let wallet = load_wallet(root_dir).await?;
+    let local_balance = TokenAmount::from(wallet.balance());
+
+    let mut verified_balance = TokenAmount::default();
+    let mut dbcs = Vec::new();
+    for (dbc_id, amount) in wallet.available_dbcs() {
+        let amount = TokenAmount::from(amount);
+        let dbc_name = hex::encode(DbcAddress::from_dbc_id(&dbc_id).name());
+        let status = match client.is_dbc_spent(&dbc_id).await {
+            Ok(false) => {
+                verified_balance = verified_balance
+                    .checked_add(amount)
+                    .ok_or_else(|| eyre!("Balance overflow"))?;
+                "unspent".to_string()
+            }
+            Ok(true) => {
+                output.info(format!("DBC {dbc_name} of {amount} was spent elsewhere!"));
+                "spent".to_string()
+            }
+            Err(err) => {
+                output.info(format!(
+                    "Could not verify the spend status of DBC {dbc_name}: {err}"
+                ));
+                format!("unknown: {err}")
+            }
+        };
+        dbcs.push(json!({ "dbc": dbc_name, "amount": amount, "status": status }));
+    }
+
+    output.result(
+        &json!({
+            "balance": local_balance,
+            "verified_balance": verified_balance,
+            "dbcs": dbcs,
+        }),
+        || {
+            println!("{verified_balance}");
+            if verified_balance != local_balance {
+                println!("Warning: the local wallet balance of {local_balance} could not be fully verified.");
+            }
+        },
+    )
+}
+
+async fn consolidate(
+    batch_size: usize,
+    client: &Client,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    let mut wallet = load_wallet(root_dir).await?;
+    let dbcs_before = wallet.available_dbcs().len();
+
+    output.info(format!(
+        "Consolidating {dbcs_before} DBCs in batches of up to {batch_size}..."
+    ));
+    let transfers = wallet.consolidate(batch_size, client).await?;
+    let dbcs_after = wallet.available_dbcs().len();
+
+    if transfers > 0 {
+        wallet
+            .store()
+            .await
+            .map_err(|err| eyre!(err).wrap_err("Failed to store the consolidated wallet"))?;
+    }
+
+    output.result(
+        &json!({
+            "transfers": transfers,
+            "dbcs_before": dbcs_before,
+            "dbcs_after": dbcs_after,
+            "balance": TokenAmount::from(wallet.balance()),
+        }),
+        || {
+            if transfers == 0 {
+                println!("Nothing to consolidate.");
+            } else {
+                println!(
+                    "Consolidated {dbcs_before} DBCs into {dbcs_after}, in {transfers} transfers."
+                );
+            }
+        },
+    )
 }
 
-async fn deposit(root_dir: &Path) -> Result<()> {
-    let mut wallet = LocalWallet::load_from(root_dir).await?;
+/// Mint the initial supply of a new network into the wallet, writing the genesis dbc to a file
+/// for the nodes to be started with. It can't be verified against the network, as no node
+/// accepts it before then.
+pub(crate) async fn create_genesis(root_dir: &Path, output: Output) -> Result<()> {
+    let mut wallet = load_wallet(root_dir).await?;
+    if wallet.balance().as_nano() > 0 {
+        return Err(eyre!(
+            "The wallet already holds tokens, the genesis DBC of a new network \
+            is only created by an empty wallet"
+        ));
+    }
+
+    let genesis_dbc = create_genesis_dbc(wallet.address())?;
+    let genesis_path = root_dir.join(GENESIS_DBC_FILE_NAME);
+    tokio::fs::write(&genesis_path, genesis_dbc.to_hex()?).await?;
+
+    wallet.deposit(vec![genesis_dbc.clone()]);
+    wallet
+        .store()
+        .await
+        .map_err(|err| eyre!(err).wrap_err("Failed to store the genesis amount"))?;
+
+    let balance = TokenAmount::from(wallet.balance());
+    output.result(
+        &json!({
+            "genesis_dbc": genesis_path,
+            "dbc_id": hex::encode(DbcAddress::from_dbc_id(&genesis_dbc.id()).name()),
+            "balance": balance,
+        }),
+        || {
+            println!("Minted {balance} tokens into the wallet.");
+            println!(
+                "Start every node of the new network with `safenode --genesis-dbc {}`.",
+                genesis_path.display()
+            );
+        },
+    )
+}
+
+async fn deposit(root_dir: &Path, output: Output) -> Result<()> {
+    let mut wallet = load_wallet(root_dir).await?;
 
     let previous_balance = wallet.balance();
 
     wallet.try_load_deposits().await?;
 
     let new_balance = wallet.balance();
-    let deposited = previous_balance.as_nano() - new_balance.as_nano();
+    let deposited = deposited_amount(previous_balance, new_balance);
 
-    if deposited > 0 {
+    if !deposited.is_zero() {
         if let Err(err) = wallet.store().await {
-            println!("Failed to store deposited amount: {:?}", err);
-        } else {
-            println!("Deposited {:?}.", sn_dbc::Token::from_nano(deposited));
+            return Err(eyre!(err).wrap_err("Failed to store deposited amount"));
         }
+    }
+
+    output.result(
+        &json!({ "deposited": deposited, "balance": TokenAmount::from(new_balance) }),
+        || {
+            if !deposited.is_zero() {
+                println!("Deposited {deposited} tokens.");
+            } else {
+                println!("Nothing deposited.");
+            }
+        },
+    )
+}
+
+async fn deposit_dbc(dbc: &str, client: &Client, root_dir: &Path, output: Output) -> Result<()> {
+    let content = if Path::new(dbc).is_file() {
+        tokio::fs::read_to_string(dbc).await?
     } else {
-        println!("Nothing deposited.");
+        dbc.to_string()
+    };
+    let dbcs = if OfflineTransfer::is_transfer_file(&content) {
+        let transfer = OfflineTransfer::decode(&content)?;
+        output.detail(format!(
+            "Read a transfer of {} tokens to {}, in {} DBC(s).",
+            TokenAmount::from(transfer.amount),
+            hex::encode(transfer.recipient.to_bytes()),
+            transfer.dbcs.len()
+        ));
+        transfer.dbcs
+    } else {
+        let dbc = Dbc::from_hex(content.trim()).map_err(|err| {
+            eyre!("The input is not a path to a file nor a hex-encoded DBC: {err}")
+        })?;
+        vec![dbc]
+    };
+
+    verify_and_deposit(dbcs, client, root_dir, output).await
+}
+
+async fn get_faucet(url: &str, client: &Client, root_dir: &Path, output: Output) -> Result<()> {
+    let wallet = load_wallet(root_dir).await?;
+    let address_hex = hex::encode(wallet.address().to_bytes());
+    let url = format!("{}/{address_hex}", url.trim_end_matches('/'));
+
+    output.info(format!("Requesting tokens from the faucet at {url}..."));
+    let response = reqwest::get(&url)
+        .await
+        .map_err(|err| eyre!("Failed to reach the faucet: {err}"))?;
+    if !response.status().is_success() {
+        return Err(eyre!(
+            "The faucet refused the request: {}",
+            response.status()
+        ));
     }
+    let dbc_hex = response.text().await?;
+    let dbc = Dbc::from_hex(dbc_hex.trim())
+        .map_err(|err| eyre!("The faucet did not respond with a hex-encoded DBC: {err}"))?;
 
-    Ok(())
+    verify_and_deposit(vec![dbc], client, root_dir, output).await
+}
+
+/// Verify the spends of the DBCs against the network, and deposit them to the local wallet.
+async fn verify_and_deposit(
+    dbcs: Vec<Dbc>,
+    client: &Client,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    let wallet = load_wallet(root_dir).await?;
+    let previous_balance = wallet.balance();
+
+    output.info("Verifying the DBC spends against the network...");
+    let mut wallet_client = WalletClient::new(client.clone(), wallet);
+    for dbc in dbcs {
+        wallet_client.verify_and_deposit(dbc).await?;
+    }
+    let wallet = wallet_client.into_wallet();
+
+    let new_balance = wallet.balance();
+    let deposited = deposited_amount(previous_balance, new_balance);
+    if deposited.is_zero() {
+        return Err(eyre!(
+            "The DBC is valid but could not be deposited, as it either isn't \
+            accessible by this wallet or has already been deposited."
+        ));
+    }
+
+    wallet
+        .store()
+        .await
+        .map_err(|err| eyre!(err).wrap_err("Failed to store deposited amount"))?;
+
+    output.result(
+        &json!({ "deposited": deposited, "balance": TokenAmount::from(new_balance) }),
+        || println!("Deposited {deposited} tokens."),
+    )
 }
 
-async fn send(amount: String, to: String, client: &Client, root_dir: &Path) -> Result<()> {
+/// The amount the balance of the wallet increased by.
+fn deposited_amount(previous_balance: Token, new_balance: Token) -> TokenAmount {
+    TokenAmount::from_nano(
+        new_balance
+            .as_nano()
+            .saturating_sub(previous_balance.as_nano()),
+    )
+}
+
+async fn send(
+    amount: TokenAmount,
+    to: String,
+    inbox: bool,
+    client: &Client,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
     let address = parse_public_address(to)?;
+    let to = hex::encode(address.to_bytes());
+    if amount.is_zero() {
+        return Err(eyre!("Zero amount passed in. Nothing sent."));
+    }
+
+    let wallet = load_wallet(root_dir).await?;
+    let mut wallet_client = WalletClient::new(client.clone(), wallet);
+
+    let new_dbc = wallet_client
+        .send(amount.into(), address)
+        .await
+        .map_err(|err| eyre!("Failed to send {amount} tokens to {to} due to {err:?}."))?;
+    output.info(format!("Sent {amount} tokens to {to}"));
+
+    let wallet = wallet_client.into_wallet();
+    let new_balance = TokenAmount::from(wallet.balance());
 
-    use std::str::FromStr;
-    let amount = Token::from_str(&amount)?;
-    if amount.as_nano() == 0 {
-        println!("Invalid format or zero amount passed in. Nothing sent.");
-        return Ok(());
+    if let Err(err) = wallet.store().await {
+        output.info(format!("Failed to store wallet: {err:?}"));
+    } else {
+        output.info(format!(
+            "Successfully stored wallet with new balance {new_balance}."
+        ));
+    }
+
+    let dbc_id = hex::encode(DbcAddress::from_dbc_id(&new_dbc.id()).name());
+    let transfer = OfflineTransfer::new(vec![new_dbc], address, amount.into());
+    let transfer_file = wallet.store_transfer_file(&transfer).await?;
+    if inbox {
+        deliver_to_inbox(&transfer, client, output).await;
+    }
+
+    output.result(
+        &json!({
+            "sent": amount,
+            "to": to,
+            "dbc_id": dbc_id,
+            "transfer_file": transfer_file,
+            "balance": new_balance,
+        }),
+        || {
+            println!("Successfully stored the transfer to {}.", transfer_file.display());
+            println!("It can now be sent to the recipient, using any channel of choice, and deposited with `safe wallet deposit <file>`.");
+        },
+    )
+}
+
+/// Read the recipients of `wallet send-many`, resolving the names of contacts to their addresses.
+fn read_recipients(content: &str, config: &Config) -> Result<Vec<(TokenAmount, PublicAddress)>> {
+    let mut recipients = vec![];
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (amount, to) = line
+            .split_once(',')
+            .ok_or_else(|| eyre!("Line {}: expected `<amount>,<to>`", number + 1))?;
+        let amount: TokenAmount = amount
+            .parse()
+            .map_err(|err| eyre!("Line {}: {err}", number + 1))?;
+        if amount.is_zero() {
+            return Err(eyre!("Line {}: the amount is zero", number + 1));
+        }
+        let to = to.trim();
+        let to = config.contacts.get(to).map(String::as_str).unwrap_or(to);
+        let address = parse_public_address(to)
+            .map_err(|err| eyre!("Line {}: invalid address: {err}", number + 1))?;
+        recipients.push((amount, address));
     }
+    if recipients.is_empty() {
+        return Err(eyre!("No recipients are listed in the file"));
+    }
+    Ok(recipients)
+}
+
+async fn send_many(
+    file: &Path,
+    inbox: bool,
+    client: &Client,
+    root_dir: &Path,
+    config: &Config,
+    output: Output,
+) -> Result<()> {
+    let content = tokio::fs::read_to_string(file).await?;
+    let recipients = read_recipients(&content, config)?;
+    let total = recipients
+        .iter()
+        .try_fold(TokenAmount::default(), |total, (amount, _)| {
+            total.checked_add(*amount)
+        })
+        .ok_or_else(|| eyre!("The total amount to send overflows"))?;
 
-    let wallet = LocalWallet::load_from(root_dir).await?;
+    let wallet = load_wallet(root_dir).await?;
     let mut wallet_client = WalletClient::new(client.clone(), wallet);
+    let new_dbcs = wallet_client
+        .send_to_many(
+            recipients
+                .iter()
+                .map(|(amount, address)| ((*amount).into(), *address))
+                .collect(),
+        )
+        .await
+        .map_err(|err| {
+            eyre!(
+                "Failed to send {total} tokens to {} recipients due to {err:?}.",
+                recipients.len()
+            )
+        })?;
+    output.info(format!(
+        "Sent {total} tokens to {} recipients",
+        recipients.len()
+    ));
 
-    match wallet_client.send(amount, address).await {
-        Ok(new_dbc) => {
-            println!("Sent {amount:?} to {address:?}");
-            let mut wallet = wallet_client.into_wallet();
-            let new_balance = wallet.balance();
+    let wallet = wallet_client.into_wallet();
+    let new_balance = TokenAmount::from(wallet.balance());
+    if let Err(err) = wallet.store().await {
+        output.info(format!("Failed to store wallet: {err:?}"));
+    }
 
-            if let Err(err) = wallet.store().await {
-                println!("Failed to store wallet: {err:?}");
+    let mut transfers = vec![];
+    for (amount, address) in recipients {
+        let dbcs: Vec<_> = new_dbcs
+            .iter()
+            .filter(|dbc| dbc.public_address() == &address)
+            .cloned()
+            .collect();
+        if dbcs.is_empty() {
+            // E.g. the wallet's own address, which isn't sent to.
+            continue;
+        }
+        let transfer = OfflineTransfer::new(dbcs, address, amount.into());
+        let transfer_file = wallet.store_transfer_file(&transfer).await?;
+        if inbox {
+            deliver_to_inbox(&transfer, client, output).await;
+        }
+        transfers.push((amount, address, transfer_file));
+    }
+
+    output.result(
+        &json!({
+            "sent": total,
+            "transfers": transfers
+                .iter()
+                .map(|(amount, address, file)| json!({
+                    "amount": amount,
+                    "to": hex::encode(address.to_bytes()),
+                    "transfer_file": file,
+                }))
+                .collect::<Vec<_>>(),
+            "balance": new_balance,
+        }),
+        || {
+            for (amount, address, file) in &transfers {
+                println!(
+                    "Stored the transfer of {amount} tokens to {} to {}.",
+                    hex::encode(address.to_bytes()),
+                    file.display()
+                );
+            }
+            println!("They can now be sent to the recipients, using any channel of choice.");
+        },
+    )
+}
+
+/// Deliver the dbcs of the transfer to the inbox of the recipient. The transfer file is written
+/// either way, thus a failure is reported without failing the command.
+async fn deliver_to_inbox(transfer: &OfflineTransfer, client: &Client, output: Output) {
+    for dbc in &transfer.dbcs {
+        match client.deliver_to_inbox(dbc).await {
+            Ok(()) => output.info(format!(
+                "Delivered the DBC to the inbox of {}.",
+                hex::encode(transfer.recipient.to_bytes())
+            )),
+            Err(err) => output.info(format!(
+                "Failed to deliver the DBC to the inbox of the recipient, the transfer file \
+                has to be sent to them instead: {err}"
+            )),
+        }
+    }
+}
+
+async fn scan(client: &Client, root_dir: &Path, output: Output) -> Result<()> {
+    let wallet = load_wallet(root_dir).await?;
+    let previous_balance = wallet.balance();
+
+    output.info("Scanning the inboxes of the wallet on the network...");
+    let mut wallet_client = WalletClient::new(client.clone(), wallet);
+    let deposited_dbcs = wallet_client.scan_for_deposits().await?;
+    let wallet = wallet_client.into_wallet();
+
+    let new_balance = wallet.balance();
+    let deposited = deposited_amount(previous_balance, new_balance);
+    if !deposited_dbcs.is_empty() {
+        wallet
+            .store()
+            .await
+            .map_err(|err| eyre!(err).wrap_err("Failed to store deposited amount"))?;
+    }
+
+    output.result(
+        &json!({
+            "dbcs": deposited_dbcs.len(),
+            "deposited": deposited,
+            "balance": TokenAmount::from(new_balance),
+        }),
+        || {
+            if deposited_dbcs.is_empty() {
+                println!("No new deposits found.");
             } else {
-                println!("Successfully stored wallet with new balance {new_balance:?}.");
+                println!(
+                    "Deposited {deposited} tokens from {} DBC(s).",
+                    deposited_dbcs.len()
+                );
             }
+        },
+    )
+}
+
+async fn pending(cancel: bool, client: &Client, root_dir: &Path, output: Output) -> Result<()> {
+    let mut wallet = load_wallet(root_dir).await?;
+    let pending = wallet.pending_transfers();
+    if pending == 0 {
+        return output.result(&json!({ "pending": 0 }), || {
+            println!("No transfers are pending.")
+        });
+    }
 
-            wallet.store_created_dbc(new_dbc).await?;
-            println!("Successfully stored new dbc to wallet dir. It can now be sent to the recipient, using any channel of choice.");
+    output.info(format!(
+        "Looking up the spends of {pending} pending transfer(s) in the network..."
+    ));
+    let (completed, cancelled) = if cancel {
+        let cancelled = wallet.cancel_pending(client).await?;
+        (pending - cancelled - wallet.pending_transfers(), cancelled)
+    } else {
+        (wallet.resend_pending(client).await?, 0)
+    };
+    let still_pending = wallet.pending_transfers();
+    let balance = TokenAmount::from(wallet.balance());
+
+    output.result(
+        &json!({
+            "completed": completed,
+            "cancelled": cancelled,
+            "pending": still_pending,
+            "balance": balance,
+        }),
+        || {
+            println!("Completed {completed} and cancelled {cancelled} transfer(s).");
+            if still_pending > 0 {
+                println!("{still_pending} transfer(s) are still pending, try again later, or cancel them with `safe wallet pending --cancel`.");
+            }
+            println!("The balance is now {balance} tokens.");
+        },
+    )
+}
+
+async fn receipt(address: &str, client: &Client, root_dir: &Path, output: Output) -> Result<()> {
+    let name = if address.starts_with(XOR_URL_SCHEME) {
+        address.parse::<XorUrl>()?.name
+    } else {
+        let bytes =
+            hex::decode(address).map_err(|err| eyre!("Address is not a hex string: {err}"))?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| eyre!("Address must be 32 bytes long, i.e. 64 hex characters"))?;
+        XorName(bytes)
+    };
+
+    let wallet = load_wallet(root_dir).await?;
+    output.info("Checking the payment against the network...");
+    let wallet_client = WalletClient::new(client.clone(), wallet);
+    let receipt = wallet_client.prove_payment(&name).await?;
+
+    let amount = TokenAmount::from(receipt.amount);
+    output.result(
+        &json!({
+            "paid_for": receipt
+                .paid_for
+                .iter()
+                .map(|name| format!("{name:64x}"))
+                .collect::<Vec<_>>(),
+            "amount": amount,
+            "timestamp": receipt.timestamp,
+            "time": format_timestamp(receipt.timestamp),
+            "spends": receipt
+                .spends
+                .iter()
+                .map(|spend| hex::encode(spend.to_bytes()))
+                .collect::<Vec<_>>(),
+        }),
+        || {
+            println!(
+                "Paid {amount} tokens on {} for storing {} address(es), including {name:64x}.",
+                format_timestamp(receipt.timestamp),
+                receipt.paid_for.len()
+            );
+            for spend in &receipt.spends {
+                println!(
+                    "    Spend of DBC {}",
+                    hex::encode(DbcAddress::from_dbc_id(spend.dbc_id()).name())
+                );
+            }
+        },
+    )
+}
+
+pub(crate) async fn watch(address: &str, root_dir: &Path, output: Output) -> Result<()> {
+    let address = parse_public_address(address)?;
+    let wallet = WatchOnlyWallet::create(root_dir, address).await?;
+    let address = hex::encode(address.to_bytes());
+    output.result(
+        &json!({ "address": address, "wallet_dir": wallet.wallet_dir() }),
+        || {
+            println!("Watching {address}.");
+            println!(
+                "Place the DBCs received in {}, to build transfers from them with `safe wallet build-transfer`.",
+                wallet.wallet_dir().join("received_dbcs").display()
+            );
+        },
+    )
+}
+
+async fn build_transfer(
+    amount: TokenAmount,
+    to: String,
+    out: &Path,
+    client: &Client,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    let address = parse_public_address(to)?;
+    if amount.is_zero() {
+        return Err(eyre!("Zero amount passed in. Nothing to transfer."));
+    }
+
+    let mut wallet = WatchOnlyWallet::load(root_dir).await?;
+    let received = wallet.received_dbcs().await?;
+    if !received.is_empty() {
+        output.info("Verifying the DBC spends of the received DBCs against the network...");
+        let mut verified = vec![];
+        for dbc in received {
+            match client.verify(&dbc).await {
+                Ok(()) => verified.push(dbc),
+                Err(err) => output.info(format!("Skipping DBC {:?}: {err}", dbc.id())),
+            }
         }
-        Err(err) => {
-            println!("Failed to send {amount:?} to {address:?} due to {err:?}.");
+        let deposited = wallet.deposit(verified);
+        if !deposited.is_empty() {
+            wallet.store().await?;
+            output.info(format!("Watching {} more DBC(s).", deposited.len()));
         }
     }
 
-    Ok(())
+    let unsigned = wallet.build_transfer(vec![(amount.into(), address)])?;
+    tokio::fs::write(out, unsigned.encode()?).await?;
+
+    output.result(
+        &json!({
+            "amount": amount,
+            "to": hex::encode(address.to_bytes()),
+            "inputs": unsigned.inputs.len(),
+            "unsigned_transfer_file": out,
+        }),
+        || {
+            println!("Wrote the unsigned transfer to {}.", out.display());
+            println!("Sign it with `safe wallet sign-transfer <file>` on the machine holding the key, then broadcast it with `safe wallet broadcast <file>`.");
+        },
+    )
+}
+
+pub(crate) async fn sign_transfer(
+    file: &Path,
+    out: &Path,
+    root_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    let unsigned = UnsignedTransfer::decode(&tokio::fs::read_to_string(file).await?)?;
+    let wallet = load_wallet(root_dir).await?;
+    let signed = wallet.sign_transfer(&unsigned)?;
+    tokio::fs::write(out, signed.encode()?).await?;
+
+    let recipients: Vec<_> = signed
+        .recipients
+        .iter()
+        .map(|(amount, address)| (TokenAmount::from(*amount), hex::encode(address.to_bytes())))
+        .collect();
+    output.result(
+        &json!({
+            "recipients": recipients
+                .iter()
+                .map(|(amount, to)| json!({ "amount": amount, "to": to }))
+                .collect::<Vec<_>>(),
+            "signed_transfer_file": out,
+        }),
+        || {
+            for (amount, to) in &recipients {
+                println!("Signed the transfer of {amount} tokens to {to}.");
+            }
+            println!("Wrote the signed transfer to {}, to be broadcast with `safe wallet broadcast <file>`.", out.display());
+        },
+    )
+}
+
+async fn broadcast(file: &Path, client: &Client, root_dir: &Path, output: Output) -> Result<()> {
+    let signed = SignedTransfer::decode(&tokio::fs::read_to_string(file).await?)?;
+    let mut wallet = WatchOnlyWallet::load(root_dir).await?;
+    output.info("Registering the transfer in the network...");
+    let transfer_files = wallet
+        .broadcast(&signed, client)
+        .await
+        .map_err(|err| eyre!("Failed to broadcast the transfer due to {err:?}."))?;
+
+    output.result(&json!({ "transfer_files": transfer_files }), || {
+        for transfer_file in &transfer_files {
+            println!("Successfully stored the transfer to {}.", transfer_file.display());
+        }
+        println!("They can now be sent to the recipients, using any channel of choice, and deposited with `safe wallet deposit <file>`.");
+    })
 }
@@ -11,41 +11,284 @@ extern crate tracing;
 
 mod cli;
 
-use self::cli::{files_cmds, register_cmds, wallet_cmds, Opt, SubCmd};
+use self::cli::{
+    bench_cmds, cat_file, contacts_cmds, create_genesis, exit_code, files_cmds, inspect, keys_cmds,
+    list_pins, list_wallets, load_or_create_key, name_cmds, networks_cmds, node_cmds, pin_cmds,
+    register_cmds, remove_pin, run_daemon, share_file, sign, sign_transfer, verify, wallet_cmds,
+    watch, Config, FilesCmds, LogOutput, Opt, Output, PinCmds, Shell, SubCmd, Verbosity,
+    WalletCmds, DEFAULT_KEY_NAME,
+};
 
-use clap::Parser;
-use eyre::{eyre, Result};
-use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
-use safenode::client::Client;
-use safenode::log::init_node_logging;
-use std::path::PathBuf;
+use clap::{CommandFactory, Parser};
+use eyre::{eyre, Report, Result};
+use libp2p::Multiaddr;
+use safenode::log::{init_logging, LogOutputDest};
+use safenode::{
+    client::{Client, ClientBuilder, RateLimit},
+    domain::wallet::LocalWallet,
+    peers::{bootstrap_peers, peers_from_env},
+};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let opt = Opt::parse();
-    // For client, default to log to std::out
-    // This is ruining the log output for the CLI. Needs to be fixed.
-    let tmp_dir = std::env::temp_dir();
-    let _log_appender_guard = init_node_logging(&Some(tmp_dir.join("safe-client.log")))?;
+    let root_dir = get_client_dir().await?;
+    let config = Config::load(&root_dir)?;
+    let output = Output::new(
+        opt.json || config.json,
+        Verbosity::new(opt.quiet, opt.verbose),
+    );
 
-    info!("Full client logs will be written to {:?}", tmp_dir);
+    if let Err(err) = run(opt, config, &root_dir, output).await {
+        print_error(&err, output);
+        std::process::exit(exit_code(&err));
+    }
+    Ok(())
+}
 
-    println!("Instantiating a SAFE client...");
+async fn run(opt: Opt, config: Config, root_dir: &Path, output: Output) -> Result<()> {
+    let wallet_dir = selected_wallet_dir(&opt, root_dir)?.unwrap_or_else(|| root_dir.to_path_buf());
+    let key_name = opt
+        .key
+        .or(config.key.clone())
+        .unwrap_or_else(|| DEFAULT_KEY_NAME.to_string());
 
-    let secret_key = bls::SecretKey::random();
-    let peers = parse_peer_multiaddresses(&opt.peers)?;
+    // No need to connect to the network for these commands.
+    let cmd = match run_offline_cmd(
+        opt.cmd,
+        &key_name,
+        &opt.peers,
+        &config,
+        root_dir,
+        &wallet_dir,
+        output,
+    )
+    .await?
+    {
+        Some(cmd) => cmd,
+        None => return Ok(()),
+    };
 
-    let client = Client::new(secret_key, Some(peers)).await?;
+    let log_output = opt.log_output.unwrap_or_else(|| {
+        LogOutput(Some(LogOutputDest::Path(
+            std::env::temp_dir().join("safe-client.log"),
+        )))
+    });
+    let _log_appender_guard = match &log_output.0 {
+        Some(dest) => {
+            output.detail(format!("Client logs will be written to {dest}"));
+            let guard = init_logging(
+                dest,
+                opt.log_format,
+                opt.log_filter.as_deref(),
+                opt.log_level,
+            )?;
+            info!("Client logs will be written to {dest}");
+            guard
+        }
+        None => None,
+    };
 
-    let root_dir = get_client_dir().await?;
+    // Written to stderr so it doesn't get mixed with content piped from stdout, e.g. by `safe cat`.
+    if output.verbosity() > Verbosity::Quiet {
+        eprintln!("Instantiating a SAFE client...");
+    }
 
-    match opt.cmd {
-        SubCmd::Wallet(cmds) => wallet_cmds(cmds, &client, &root_dir).await?,
-        SubCmd::Files(cmds) => files_cmds(cmds, client.clone(), &root_dir).await?,
-        SubCmd::Register(cmds) => register_cmds(cmds, &client).await?,
+    let secret_key = load_or_create_key(root_dir, &key_name).await?;
+
+    let (peers, contacts) = if opt.peers.is_empty() && opt.contacts.is_none() {
+        (config.default_peers()?, config.default_contacts())
+    } else {
+        (opt.peers, opt.contacts)
     };
+    let mut peers = bootstrap_peers(&peers, contacts.as_deref()).await?;
+    if peers.is_empty() {
+        peers = peers_from_env()?;
+    }
+    for (peer_id, addr) in &peers {
+        output.detail(format!("Dialing peer {peer_id} at {addr}"));
+    }
 
-    Ok(())
+    let rate_limit = RateLimit {
+        requests_per_sec: opt.max_requests_per_sec.or(config.max_requests_per_sec),
+        bytes_per_sec: opt.max_bandwidth.or(config.max_bandwidth),
+    };
+
+    let mut builder = ClientBuilder::new(secret_key)
+        .with_peers(peers)
+        .with_rate_limit(rate_limit);
+    if let Some(secs) = opt
+        .connection_timeout_secs
+        .or(config.connection_timeout_secs)
+    {
+        builder = builder.with_connection_timeout(Duration::from_secs(secs));
+    }
+    let client = builder.build().await?;
+
+    match cmd {
+        SubCmd::Shell => run_shell(&client, &key_name, &config, root_dir, &wallet_dir).await,
+        cmd => run_client_cmd(cmd, &client, &config, root_dir, &wallet_dir, output).await,
+    }
+}
+
+/// The root dir of the wallet selected with `--wallet-dir` or `--wallet`, if any.
+fn selected_wallet_dir(opt: &Opt, root_dir: &Path) -> Result<Option<PathBuf>> {
+    match (&opt.wallet_dir, &opt.wallet) {
+        (Some(wallet_dir), _) => Ok(Some(wallet_dir.clone())),
+        (None, Some(name)) => Ok(Some(LocalWallet::named_root_dir(root_dir, name)?)),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Run the commands which don't need a connection to the network.
+/// Any other command is returned, to be run once connected.
+async fn run_offline_cmd(
+    cmd: SubCmd,
+    key_name: &str,
+    peers: &[Multiaddr],
+    config: &Config,
+    root_dir: &Path,
+    wallet_dir: &Path,
+    output: Output,
+) -> Result<Option<SubCmd>> {
+    match cmd {
+        SubCmd::Completions { shell } => {
+            clap_complete::generate(shell, &mut Opt::command(), "safe", &mut std::io::stdout());
+        }
+        SubCmd::Files(FilesCmds::Share { address, container }) => {
+            share_file(&address, container, output)?
+        }
+        SubCmd::Contacts(cmds) => contacts_cmds(cmds, root_dir, output)?,
+        SubCmd::Pin(PinCmds::List) => list_pins(root_dir, output)?,
+        SubCmd::Pin(PinCmds::Remove { address }) => remove_pin(&address, root_dir, output)?,
+        SubCmd::Wallet(WalletCmds::CreateGenesis) => create_genesis(wallet_dir, output).await?,
+        SubCmd::Wallet(WalletCmds::List) => list_wallets(root_dir, output).await?,
+        SubCmd::Wallet(WalletCmds::SignTransfer { file, out }) => {
+            sign_transfer(&file, &out, wallet_dir, output).await?
+        }
+        SubCmd::Wallet(WalletCmds::Watch { address }) => {
+            watch(&address, wallet_dir, output).await?
+        }
+        SubCmd::Keys(cmds) => keys_cmds(cmds, root_dir, output).await?,
+        SubCmd::Networks(cmds) => networks_cmds(cmds, root_dir, output)?,
+        SubCmd::Sign { file } => sign(&file, key_name, root_dir, output).await?,
+        SubCmd::Verify {
+            file,
+            signature,
+            public_key,
+        } => verify(&file, &signature, &public_key, output).await?,
+        SubCmd::Node(cmds) => {
+            let mut default_peers = if peers.is_empty() {
+                config.default_peers()?
+            } else {
+                peers.to_vec()
+            };
+            if default_peers.is_empty() {
+                default_peers = peers_from_env()?
+                    .into_iter()
+                    .map(|(_, multiaddr)| multiaddr)
+                    .collect();
+            }
+            node_cmds(cmds, root_dir, default_peers, output).await?
+        }
+        cmd => return Ok(Some(cmd)),
+    }
+    Ok(None)
+}
+
+/// Run the commands which need a connection to the network, with the given connected client.
+async fn run_client_cmd(
+    cmd: SubCmd,
+    client: &Client,
+    config: &Config,
+    root_dir: &Path,
+    wallet_dir: &Path,
+    output: Output,
+) -> Result<()> {
+    match cmd {
+        SubCmd::Wallet(cmds) => wallet_cmds(cmds, client, wallet_dir, config, output).await,
+        SubCmd::Files(cmds) => files_cmds(cmds, client.clone(), root_dir, output).await,
+        SubCmd::Register(cmds) => register_cmds(cmds, client, output).await,
+        SubCmd::Name(cmds) => name_cmds(cmds, client, output).await,
+        SubCmd::Pin(cmds) => pin_cmds(cmds, client, root_dir, output).await,
+        SubCmd::Cat { address } => cat_file(&address, client.clone()).await,
+        SubCmd::Inspect { address, tag } => inspect(&address, tag, client, output).await,
+        SubCmd::Bench(cmds) => bench_cmds(cmds, client, output).await,
+        #[cfg(all(unix, feature = "fuse"))]
+        SubCmd::Mount {
+            container_address,
+            mountpoint,
+        } => cli::mount(&container_address, &mountpoint, client, output).await,
+        SubCmd::Daemon { listen } => run_daemon(client, listen, wallet_dir, output).await,
+        SubCmd::Shell => Err(eyre!("The shell is already running")),
+        SubCmd::Completions { .. }
+        | SubCmd::Contacts(_)
+        | SubCmd::Keys(_)
+        | SubCmd::Networks(_)
+        | SubCmd::Node(_)
+        | SubCmd::Sign { .. }
+        | SubCmd::Verify { .. } => Ok(()),
+    }
+}
+
+/// Read commands from the interactive shell until it's exited, running each with the same client.
+/// Options given along with a command, other than those about its output, the key it signs
+/// with and the wallet it uses, are ignored, since the client is already connected.
+async fn run_shell(
+    client: &Client,
+    key_name: &str,
+    config: &Config,
+    root_dir: &Path,
+    wallet_dir: &Path,
+) -> Result<()> {
+    let mut shell = Shell::new(root_dir)?;
+    while let Some(opt) = shell.read_cmd().await? {
+        let output = Output::new(
+            opt.json || config.json,
+            Verbosity::new(opt.quiet, opt.verbose),
+        );
+        let key_name = opt.key.as_deref().unwrap_or(key_name);
+        let wallet_dir = match selected_wallet_dir(&opt, root_dir) {
+            Ok(selected) => selected.unwrap_or_else(|| wallet_dir.to_path_buf()),
+            Err(err) => {
+                print_error(&err, output);
+                continue;
+            }
+        };
+        let result = match run_offline_cmd(
+            opt.cmd,
+            key_name,
+            &opt.peers,
+            config,
+            root_dir,
+            &wallet_dir,
+            output,
+        )
+        .await
+        {
+            Ok(Some(cmd)) => {
+                run_client_cmd(cmd, client, config, root_dir, &wallet_dir, output).await
+            }
+            Ok(None) => Ok(()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            print_error(&err, output);
+        }
+    }
+    shell.save_history()
+}
+
+fn print_error(err: &Report, output: Output) {
+    if output.is_json() {
+        output.error(err);
+    } else {
+        eprintln!("Error: {err:?}");
+    }
 }
 
 async fn get_client_dir() -> Result<PathBuf> {
@@ -55,28 +298,3 @@ async fn get_client_dir() -> Result<PathBuf> {
     tokio::fs::create_dir_all(home_dirs.as_path()).await?;
     Ok(home_dirs)
 }
-
-// TODO: dedupe
-/// Parse multiaddresses containing the P2p protocol (`/p2p/<PeerId>`).
-/// Returns an error for the first invalid multiaddress.
-fn parse_peer_multiaddresses(multiaddrs: &[Multiaddr]) -> Result<Vec<(PeerId, Multiaddr)>> {
-    multiaddrs
-        .iter()
-        .map(|multiaddr| {
-            // Take hash from the `/p2p/<hash>` component.
-            let p2p_multihash = multiaddr
-                .iter()
-                .find_map(|p| match p {
-                    Protocol::P2p(hash) => Some(hash),
-                    _ => None,
-                })
-                .ok_or_else(|| eyre!("address does not contain `/p2p/<PeerId>`"))?;
-            // Parse the multihash into the `PeerId`.
-            let peer_id =
-                PeerId::from_multihash(p2p_multihash).map_err(|_| eyre!("invalid p2p PeerId"))?;
-
-            Ok((peer_id, multiaddr.clone()))
-        })
-        // Short circuit on the first error. See rust docs `Result::from_iter`.
-        .collect::<Result<Vec<(PeerId, Multiaddr)>>>()
-}
@@ -0,0 +1,362 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use safenode::{
+    network::{Network, SwarmDriver},
+    protocol::{
+        messages::{
+            Cmd, CmdResponse, CreateRegister, Query, QueryResponse, RegisterCmd, Request, Response,
+            SignedRegisterCreate,
+        },
+        storage::{
+            registers::{DataAuthority, Policy, User},
+            Chunk, ChunkAddress,
+        },
+    },
+};
+
+use bls::SecretKey;
+use bytes::Bytes;
+use eyre::{eyre, Result};
+use libp2p::{Multiaddr, PeerId};
+use rand::{seq::SliceRandom, Rng, RngCore};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{debug, warn};
+
+/// The number of chunks stored before the run, for the GetChunk requests to fetch
+/// until the StoreChunk requests of the run have stored some.
+const SEED_CHUNKS: usize = 10;
+
+/// The traffic to drive the target node with.
+pub(crate) struct BenchmarkConfig {
+    /// The StoreChunk requests sent per second.
+    pub(crate) store_rate: u32,
+    /// The GetChunk requests sent per second.
+    pub(crate) get_rate: u32,
+    /// The register creations sent per second.
+    pub(crate) register_rate: u32,
+    /// The size of the chunks stored.
+    pub(crate) chunk_size: usize,
+    /// How long requests are sent for.
+    pub(crate) duration: Duration,
+    /// The maximum number of requests awaiting their response, beyond which the requests
+    /// are sent as responses arrive, i.e. below the requested rates.
+    pub(crate) max_in_flight: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Op {
+    StoreChunk,
+    GetChunk,
+    CreateRegister,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::StoreChunk => write!(f, "StoreChunk"),
+            Op::GetChunk => write!(f, "GetChunk"),
+            Op::CreateRegister => write!(f, "CreateRegister"),
+        }
+    }
+}
+
+/// The outcome of the requests of an op.
+#[derive(Default)]
+struct OpStats {
+    /// The latencies of the requests which succeeded.
+    latencies: Vec<Duration>,
+    failed: usize,
+}
+
+/// Shared by the tasks sending the requests.
+struct Bench {
+    network: Network,
+    target: PeerId,
+    chunk_size: usize,
+    /// The chunks stored so far, for the GetChunk requests to fetch.
+    stored: Mutex<Vec<ChunkAddress>>,
+    stats: Mutex<BTreeMap<Op, OpStats>>,
+    in_flight: Arc<Semaphore>,
+}
+
+/// Drive the node at the given address with the configured traffic, sent directly to it from a
+/// client of its own, then print the latency percentiles of each type of request.
+pub(crate) async fn run_benchmark(
+    target: (PeerId, Multiaddr),
+    config: BenchmarkConfig,
+) -> Result<()> {
+    let (peer_id, addr) = target;
+    let (network, mut events, swarm_driver) = SwarmDriver::new_client()?;
+    let _swarm_driver = tokio::spawn(swarm_driver.run());
+    // The events of the client are of no use here, they are only drained.
+    let _events = tokio::spawn(async move { while events.recv().await.is_some() {} });
+    network.add_to_routing_table(peer_id, addr.clone()).await?;
+    network.dial(peer_id, addr.clone()).await?;
+    println!("Benchmarking {peer_id} at {addr}");
+
+    let bench = Arc::new(Bench {
+        network,
+        target: peer_id,
+        chunk_size: config.chunk_size,
+        stored: Mutex::new(vec![]),
+        stats: Mutex::new(BTreeMap::new()),
+        in_flight: Arc::new(Semaphore::new(config.max_in_flight.max(1))),
+    });
+
+    if config.get_rate > 0 {
+        for _ in 0..SEED_CHUNKS {
+            let chunk = bench.random_chunk();
+            let address = *chunk.address();
+            match bench.send(Request::Cmd(Cmd::StoreChunk(chunk))).await {
+                Ok(_) => bench.stored_chunk(address),
+                Err(err) => return Err(eyre!("Failed to store the seed chunks: {err}")),
+            }
+        }
+    }
+
+    let started = Instant::now();
+    let mut senders = JoinSet::new();
+    for (op, rate) in [
+        (Op::StoreChunk, config.store_rate),
+        (Op::GetChunk, config.get_rate),
+        (Op::CreateRegister, config.register_rate),
+    ] {
+        if rate > 0 {
+            let _handle = senders.spawn(bench.clone().send_at_rate(op, rate, config.duration));
+        }
+    }
+    while let Some(sent) = senders.join_next().await {
+        sent?;
+    }
+    let elapsed = started.elapsed();
+
+    let stats = bench
+        .stats
+        .lock()
+        .map_err(|_| eyre!("The benchmark stats are poisoned"))?;
+    print_report(&stats, elapsed);
+    Ok(())
+}
+
+impl Bench {
+    /// Send the requests of the op at the rate, for the duration, then wait for the
+    /// responses to those still in flight.
+    async fn send_at_rate(self: Arc<Self>, op: Op, rate: u32, duration: Duration) {
+        let mut ticker = tokio::time::interval(Duration::from_secs(1) / rate);
+        let mut pending = JoinSet::new();
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline {
+            let _ = ticker.tick().await;
+            let permit = match self.in_flight.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+            let bench = self.clone();
+            let _handle = pending.spawn(async move {
+                bench.run_op(op).await;
+                drop(permit);
+            });
+        }
+        while pending.join_next().await.is_some() {}
+    }
+
+    async fn run_op(&self, op: Op) {
+        let (request, stored) = match op {
+            Op::StoreChunk => {
+                let chunk = self.random_chunk();
+                let address = *chunk.address();
+                (Request::Cmd(Cmd::StoreChunk(chunk)), Some(address))
+            }
+            Op::GetChunk => {
+                let address = match self.random_stored_chunk() {
+                    Some(address) => address,
+                    None => return,
+                };
+                (Request::Query(Query::GetChunk(address)), None)
+            }
+            Op::CreateRegister => match random_register_create() {
+                Ok(cmd) => (Request::Cmd(Cmd::Register(cmd)), None),
+                Err(err) => {
+                    warn!("Failed to create a register cmd: {err}");
+                    return;
+                }
+            },
+        };
+
+        let started = Instant::now();
+        let result = self.send(request).await;
+        let latency = started.elapsed();
+        if result.is_ok() {
+            if let Some(address) = stored {
+                self.stored_chunk(address);
+            }
+        }
+        if let Ok(mut stats) = self.stats.lock() {
+            let stats = stats.entry(op).or_default();
+            match result {
+                Ok(()) => stats.latencies.push(latency),
+                Err(err) => {
+                    debug!("{op} request failed: {err}");
+                    stats.failed += 1;
+                }
+            }
+        }
+    }
+
+    /// Send the request to the target, failing unless it's successfully responded to.
+    async fn send(&self, request: Request) -> Result<()> {
+        match self.network.send_request(request, self.target).await? {
+            Response::Cmd(CmdResponse::StoreChunk(Ok(())))
+            | Response::Cmd(CmdResponse::CreateRegister(Ok(())))
+            | Response::Query(QueryResponse::GetChunk(Ok(_))) => Ok(()),
+            response => Err(eyre!("Unexpected response: {response}")),
+        }
+    }
+
+    fn random_chunk(&self) -> Chunk {
+        let mut bytes = vec![0u8; self.chunk_size];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Chunk::new(Bytes::from(bytes))
+    }
+
+    fn stored_chunk(&self, address: ChunkAddress) {
+        if let Ok(mut stored) = self.stored.lock() {
+            stored.push(address);
+        }
+    }
+
+    fn random_stored_chunk(&self) -> Option<ChunkAddress> {
+        let stored = self.stored.lock().ok()?;
+        stored.choose(&mut rand::thread_rng()).copied()
+    }
+}
+
+/// The cmd creating a register of a random name, owned by a new key.
+fn random_register_create() -> Result<RegisterCmd> {
+    let sk = SecretKey::random();
+    let owner = User::Key(sk.public_key());
+    let op = CreateRegister {
+        name: xor_name::rand::random(),
+        tag: rand::thread_rng().gen(),
+        policy: Policy {
+            owner,
+            permissions: Default::default(),
+        },
+    };
+    let auth = DataAuthority {
+        public_key: sk.public_key(),
+        signature: sk.sign(bincode::serialize(&op)?),
+    };
+    Ok(RegisterCmd::Create(SignedRegisterCreate { op, auth }))
+}
+
+fn print_report(stats: &BTreeMap<Op, OpStats>, elapsed: Duration) {
+    println!(
+        "{:<16}{:>8}{:>8}{:>10}{:>10}{:>10}{:>10}{:>10}",
+        "request", "ok", "failed", "req/s", "p50 ms", "p90 ms", "p99 ms", "max ms"
+    );
+    for (op, stats) in stats {
+        let mut latencies = stats.latencies.clone();
+        latencies.sort();
+        let ms = |p: f64| {
+            percentile(&latencies, p).map_or("-".to_string(), |latency| {
+                format!("{:.1}", latency.as_secs_f64() * 1000.0)
+            })
+        };
+        let rate = (latencies.len() + stats.failed) as f64 / elapsed.as_secs_f64();
+        println!(
+            "{:<16}{:>8}{:>8}{:>10.1}{:>10}{:>10}{:>10}{:>10}",
+            op.to_string(),
+            latencies.len(),
+            stats.failed,
+            rate,
+            ms(50.0),
+            ms(90.0),
+            ms(99.0),
+            ms(100.0),
+        );
+    }
+}
+
+/// The nearest-rank percentile of the sorted latencies.
+fn percentile(sorted: &[Duration], p: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len()) - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(latencies: &[u64]) -> Vec<Duration> {
+        latencies
+            .iter()
+            .copied()
+            .map(Duration::from_millis)
+            .collect()
+    }
+
+    #[test]
+    fn percentiles_are_the_nearest_rank() {
+        let latencies = millis(&[15, 20, 35, 40, 50]);
+        assert_eq!(
+            percentile(&latencies, 30.0),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            percentile(&latencies, 40.0),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(
+            percentile(&latencies, 50.0),
+            Some(Duration::from_millis(35))
+        );
+        assert_eq!(
+            percentile(&latencies, 100.0),
+            Some(Duration::from_millis(50))
+        );
+        // The lowest percentiles are the smallest latency, rather than none.
+        assert_eq!(percentile(&latencies, 0.0), Some(Duration::from_millis(15)));
+    }
+
+    #[test]
+    fn percentiles_of_many_latencies() {
+        let latencies = millis(&(1..=1000).collect::<Vec<_>>());
+        assert_eq!(
+            percentile(&latencies, 50.0),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            percentile(&latencies, 90.0),
+            Some(Duration::from_millis(900))
+        );
+        assert_eq!(
+            percentile(&latencies, 99.0),
+            Some(Duration::from_millis(990))
+        );
+        assert_eq!(
+            percentile(&latencies, 100.0),
+            Some(Duration::from_millis(1000))
+        );
+    }
+
+    #[test]
+    fn there_is_no_percentile_without_latencies() {
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+}
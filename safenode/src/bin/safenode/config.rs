@@ -0,0 +1,270 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! The node's config file, whose values apply to the options which are neither
+//! given on the command line nor set with their env var.
+
+use super::Opt;
+
+use clap::{parser::ValueSource, ArgMatches};
+use eyre::{eyre, Result, WrapErr};
+use libp2p::Multiaddr;
+use serde::Deserialize;
+use std::{
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+};
+use tracing::info;
+
+/// Name of the config file looked up in the platform config directory.
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// The options of the node which can be set in its config file, named as
+/// their command line flags, with underscores, e.g. `log_dir = "/var/log/safenode"`.
+///
+/// Relative paths are relative to the directory of the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NodeConfigFile {
+    log_dir: Option<PathBuf>,
+    log_format: Option<String>,
+    log_filter: Option<String>,
+    log_max_file_size: Option<usize>,
+    log_max_files: Option<usize>,
+    root_dir: Option<PathBuf>,
+    count: Option<u16>,
+    port: Option<u16>,
+    ip: Option<IpAddr>,
+    peers: Option<Vec<String>>,
+    rpc: Option<SocketAddr>,
+    contacts: Option<String>,
+    home_network: Option<bool>,
+    no_upnp: Option<bool>,
+    enable_relay: Option<bool>,
+    metrics_addr: Option<SocketAddr>,
+    status_interval: Option<u64>,
+    genesis_dbc: Option<PathBuf>,
+}
+
+impl NodeConfigFile {
+    /// Reads the config file at the given path, or else the one in the platform config
+    /// directory if there's one, e.g. `$HOME/.config/safe/node/config.toml` on Linux.
+    /// Returns `None` if no path was given and there's no file in the config directory.
+    pub(crate) fn read(path: Option<&Path>) -> Result<Option<Self>> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_config_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(None),
+            },
+        };
+        let content = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read the config file {path:?}"))?;
+        let mut config =
+            Self::parse(&content).wrap_err_with(|| format!("Invalid config file {path:?}"))?;
+        if let Some(dir) = path.parent() {
+            config.resolve_paths(dir);
+        }
+        info!("Read the config file {path:?}");
+        Ok(Some(config))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    fn resolve_paths(&mut self, dir: &Path) {
+        for path in [&mut self.log_dir, &mut self.root_dir, &mut self.genesis_dbc]
+            .into_iter()
+            .flatten()
+        {
+            if path.is_relative() {
+                *path = dir.join(&*path);
+            }
+        }
+    }
+
+    /// Sets the options which were neither given on the command line nor with
+    /// their env var, to their values in the config file.
+    pub(crate) fn apply(self, opt: &mut Opt, matches: &ArgMatches) -> Result<()> {
+        let unset = |id: &str| {
+            !matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+
+        if unset("log_dir") && self.log_dir.is_some() {
+            opt.log_dir = self.log_dir;
+        }
+        if let Some(format) = self.log_format.filter(|_| unset("log_format")) {
+            opt.log_format = format
+                .parse()
+                .map_err(|err| eyre!("Invalid log_format in the config file: {err}"))?;
+        }
+        if unset("log_filter") && self.log_filter.is_some() {
+            opt.log_filter = self.log_filter;
+        }
+        if unset("log_max_file_size") && self.log_max_file_size.is_some() {
+            opt.log_max_file_size = self.log_max_file_size;
+        }
+        if unset("log_max_files") && self.log_max_files.is_some() {
+            opt.log_max_files = self.log_max_files;
+        }
+        if unset("root_dir") && self.root_dir.is_some() {
+            opt.root_dir = self.root_dir;
+        }
+        if let Some(count) = self.count.filter(|_| unset("count")) {
+            opt.count = count;
+        }
+        if let Some(port) = self.port.filter(|_| unset("port")) {
+            opt.port = port;
+        }
+        if let Some(ip) = self.ip.filter(|_| unset("ip")) {
+            opt.ip = ip;
+        }
+        if let Some(peers) = self.peers.filter(|_| unset("peers")) {
+            opt.peers = peers
+                .iter()
+                .map(|peer| {
+                    peer.parse::<Multiaddr>()
+                        .map_err(|err| eyre!("Invalid peer {peer:?} in the config file: {err}"))
+                })
+                .collect::<Result<_>>()?;
+        }
+        if unset("rpc") && self.rpc.is_some() {
+            opt.rpc = self.rpc;
+        }
+        if unset("contacts") && self.contacts.is_some() {
+            opt.contacts = self.contacts;
+        }
+        if let Some(home_network) = self.home_network.filter(|_| unset("home_network")) {
+            opt.home_network = home_network;
+        }
+        if let Some(no_upnp) = self.no_upnp.filter(|_| unset("no_upnp")) {
+            opt.no_upnp = no_upnp;
+        }
+        if let Some(enable_relay) = self.enable_relay.filter(|_| unset("enable_relay")) {
+            opt.enable_relay = enable_relay;
+        }
+        if unset("metrics_addr") && self.metrics_addr.is_some() {
+            opt.metrics_addr = self.metrics_addr;
+        }
+        if let Some(interval) = self.status_interval.filter(|_| unset("status_interval")) {
+            opt.status_interval = interval;
+        }
+        if unset("genesis_dbc") && self.genesis_dbc.is_some() {
+            opt.genesis_dbc = self.genesis_dbc;
+        }
+        Ok(())
+    }
+}
+
+/// The path of the config file in the platform config directory:
+///
+/// * Linux: $HOME/.config/safe/node/config.toml
+///
+/// * macOS: $HOME/Library/Application Support/safe/node/config.toml
+///
+/// * Windows: C:\Users\{username}\AppData\Roaming\safe\node\config.toml
+fn default_config_path() -> Option<PathBuf> {
+    dirs_next::config_dir().map(|dir| dir.join("safe").join("node").join(CONFIG_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::{CommandFactory, FromArgMatches};
+
+    const CONFIG: &str = r#"
+        port = 12000
+        count = 3
+        status_interval = 10
+        contacts = "https://example.com/contacts.json"
+        root_dir = "node"
+        log_dir = "/var/log/safenode"
+    "#;
+
+    fn parse_args(args: &[&str]) -> (Opt, ArgMatches) {
+        let matches = Opt::command()
+            .get_matches_from(std::iter::once("safenode").chain(args.iter().copied()));
+        let opt = Opt::from_arg_matches(&matches).expect("Should parse the args");
+        (opt, matches)
+    }
+
+    #[test]
+    fn options_given_on_the_command_line_take_precedence_over_the_file() -> Result<()> {
+        let (mut opt, matches) = parse_args(&["--port", "13000"]);
+        NodeConfigFile::parse(CONFIG)?.apply(&mut opt, &matches)?;
+
+        assert_eq!(opt.port, 13000);
+        assert_eq!(opt.status_interval, 10);
+        Ok(())
+    }
+
+    #[test]
+    fn options_set_with_their_env_var_take_precedence_over_the_file() -> Result<()> {
+        std::env::set_var("SAFE_CONTACTS", "contacts.json");
+        let (mut opt, matches) = parse_args(&[]);
+        std::env::remove_var("SAFE_CONTACTS");
+        assert_eq!(
+            matches.value_source("contacts"),
+            Some(ValueSource::EnvVariable)
+        );
+        NodeConfigFile::parse(CONFIG)?.apply(&mut opt, &matches)?;
+
+        assert_eq!(opt.contacts.as_deref(), Some("contacts.json"));
+        Ok(())
+    }
+
+    #[test]
+    fn options_in_the_file_take_precedence_over_the_defaults() -> Result<()> {
+        let (mut opt, matches) = parse_args(&[]);
+        assert_eq!(
+            matches.value_source("port"),
+            Some(ValueSource::DefaultValue)
+        );
+        NodeConfigFile::parse(CONFIG)?.apply(&mut opt, &matches)?;
+
+        assert_eq!(opt.port, 12000);
+        assert_eq!(opt.count, 3);
+        assert_eq!(opt.status_interval, 10);
+        assert_eq!(opt.log_dir, Some(PathBuf::from("/var/log/safenode")));
+        Ok(())
+    }
+
+    #[test]
+    fn defaults_apply_to_options_missing_from_the_file() -> Result<()> {
+        let (mut opt, matches) = parse_args(&[]);
+        NodeConfigFile::parse("port = 12000")?.apply(&mut opt, &matches)?;
+
+        assert_eq!(opt.count, 1);
+        assert_eq!(opt.status_interval, 5);
+        assert_eq!(opt.root_dir, None);
+        Ok(())
+    }
+
+    #[test]
+    fn relative_paths_are_resolved_against_the_dir_of_the_file() -> Result<()> {
+        let dir = assert_fs::TempDir::new()?;
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        std::fs::write(&path, CONFIG)?;
+
+        let config = NodeConfigFile::read(Some(&path))?.expect("Should read the config file");
+
+        assert_eq!(config.root_dir, Some(dir.path().join("node")));
+        assert_eq!(config.log_dir, Some(PathBuf::from("/var/log/safenode")));
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_options_are_rejected() {
+        assert!(NodeConfigFile::parse("prot = 12000").is_err());
+    }
+}
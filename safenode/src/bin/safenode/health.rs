@@ -0,0 +1,85 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use safenode_proto::{safe_node_client::SafeNodeClient, NetworkInfoRequest, NodeInfoRequest};
+
+use eyre::{eyre, Result};
+use std::{net::SocketAddr, time::Duration};
+use tonic::Request;
+
+// this includes code generated from .proto files
+mod safenode_proto {
+    tonic::include_proto!("safenode.v1");
+}
+
+/// What a node has to satisfy to be deemed healthy.
+pub(crate) struct HealthThresholds {
+    /// The minimum number of peers the node has to be connected to.
+    pub(crate) min_peers: usize,
+    /// The minimum space, in bytes, left on the disk of the root dir of the node.
+    pub(crate) min_free_space: u64,
+}
+
+/// Query the node through its RPC service, printing a one-line summary of its health, and
+/// returning whether it's healthy, i.e. reachable within the timeout, connected to enough
+/// peers, and with enough space left to store records.
+pub(crate) async fn check_health(
+    rpc_addr: SocketAddr,
+    thresholds: &HealthThresholds,
+    timeout: Duration,
+) -> bool {
+    match tokio::time::timeout(timeout, node_problems(rpc_addr, thresholds)).await {
+        Ok(Ok(problems)) if problems.is_empty() => {
+            println!("healthy");
+            true
+        }
+        Ok(Ok(problems)) => {
+            println!("unhealthy: {}", problems.join(", "));
+            false
+        }
+        Ok(Err(err)) => {
+            println!("unhealthy: {err}");
+            false
+        }
+        Err(_) => {
+            println!("unhealthy: the node did not respond within {timeout:?}");
+            false
+        }
+    }
+}
+
+/// The reasons the node is not healthy, if any.
+async fn node_problems(rpc_addr: SocketAddr, thresholds: &HealthThresholds) -> Result<Vec<String>> {
+    let mut client = SafeNodeClient::connect(format!("https://{rpc_addr}"))
+        .await
+        .map_err(|err| eyre!("the node is not reachable at {rpc_addr}: {err}"))?;
+    let node_info = client
+        .node_info(Request::new(NodeInfoRequest {}))
+        .await?
+        .into_inner();
+    let network_info = client
+        .network_info(Request::new(NetworkInfoRequest {}))
+        .await?
+        .into_inner();
+
+    let mut problems = vec![];
+    let peers = network_info.connected_peers.len();
+    if peers < thresholds.min_peers {
+        problems.push(format!(
+            "{peers} connected peers, below the minimum of {}",
+            thresholds.min_peers
+        ));
+    }
+    if node_info.disk_available_bytes < thresholds.min_free_space {
+        problems.push(format!(
+            "{} bytes left on disk, below the minimum of {}",
+            node_info.disk_available_bytes, thresholds.min_free_space
+        ));
+    }
+    Ok(problems)
+}
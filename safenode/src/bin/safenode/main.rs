@@ -5,24 +5,33 @@
 // under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
+mod benchmark;
+mod config;
+mod health;
+mod metrics;
 mod rpc;
 
 use safenode::{
-    log::init_node_logging,
-    node::{Node, NodeEvent, NodeEventsReceiver},
+    domain::dbc_genesis::set_genesis_dbc,
+    log::{init_node_logging, LogFormat, LogRotation},
+    network::{NatStatus, NatTraversal},
+    node::{Node, NodeEvent, NodeEventsReceiver, RunningNode},
+    peers::{bootstrap_peers, peer_id_of, peers_from_env},
 };
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::Shell;
 use eyre::{eyre, Error, Result};
-use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+use futures::future::join_all;
+use libp2p::{Multiaddr, PeerId};
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
-    path::{Path, PathBuf},
+    path::PathBuf,
     time::Duration,
 };
 use tokio::{
-    runtime::Runtime,
     sync::{broadcast::error::RecvError, mpsc},
+    task::JoinHandle,
     time::sleep,
 };
 use tracing::{error, info, warn};
@@ -32,12 +41,52 @@ use tracing::{error, info, warn};
 #[derive(Parser, Debug)]
 #[clap(name = "safenode cli")]
 struct Opt {
+    /// Specify the path to a TOML config file setting the options of the node, named as
+    /// their flags with underscores, e.g. `root_dir = "/var/lib/safenode"`.
+    ///
+    /// Options given on the command line or with their env var take precedence over the file.
+    ///
+    /// If not provided, the config file is read from the platform specific location, if any:
+    ///
+    /// * Linux: $HOME/.config/safe/node/config.toml
+    ///
+    /// * macOS: $HOME/Library/Application Support/safe/node/config.toml
+    ///
+    /// * Windows: C:\Users\{username}\AppData\Roaming\safe\node\config.toml
+    #[clap(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
     /// Specify the node's logging output directory.
     ///
     /// If not provided, logging will go to stdout.
     #[clap(long)]
     log_dir: Option<PathBuf>,
 
+    /// Specify the format of the logs: `default`, or `json` for one JSON object per line,
+    /// with the timestamp, level, target and fields of each event.
+    #[clap(long, default_value_t = LogFormat::Default)]
+    log_format: LogFormat,
+
+    /// Specify which events are logged, with comma-separated `target=level` directives,
+    /// e.g. "safenode=debug,libp2p=warn".
+    ///
+    /// If not provided, the directives in the RUST_LOG env var are used, or else all the events
+    /// of the node are logged.
+    #[clap(long, value_name = "DIRECTIVES")]
+    log_filter: Option<String>,
+
+    /// Specify the size in bytes a log file can reach before it's rotated.
+    ///
+    /// Only applies when logging to a directory. Defaults to 10 MiB.
+    #[clap(long, value_name = "BYTES")]
+    log_max_file_size: Option<usize>,
+
+    /// Specify the number of rotated log files to keep, the oldest being deleted first.
+    ///
+    /// Only applies when logging to a directory. Defaults to 100.
+    #[clap(long, value_name = "N")]
+    log_max_files: Option<usize>,
+
     /// Specify the node's data directory.
     ///
     /// If not provided, the default location is platform specific:
@@ -50,6 +99,16 @@ struct Opt {
     #[clap(long)]
     root_dir: Option<PathBuf>,
 
+    /// Specify the number of nodes to run in this process, e.g. to contribute several nodes
+    /// from a single machine.
+    ///
+    /// Each node has its own `node-<N>` dir within the root dir, N being its index from 0, and
+    /// listens on the given port, RPC port and metrics port plus N, unless a port is 0.
+    ///
+    /// The logs of all the nodes are written to the same output.
+    #[clap(long, value_name = "N", default_value_t = 1)]
+    count: u16,
+
     /// Specify the port to listen on.
     ///
     /// Defaults to 0, which means any available port.
@@ -74,17 +133,146 @@ struct Opt {
     ///
     /// Many peers can be provided by using the argument multiple times.
     ///
-    /// If none are provided, a connection will be attempted to a local network.
-    #[clap(long = "peer", value_name = "MultiAddr")]
+    /// If none are provided, neither with `--contacts` nor in the config file, the comma-separated
+    /// peers in the SAFE_PEERS env var are used.
+    ///
+    /// If there are none either, a connection will be attempted to a local network.
+    #[clap(long = "peer", value_name = "MultiAddr", value_delimiter = ',')]
     peers: Vec<Multiaddr>,
 
     /// Enable the admin/ctrl RPC service by providing an IP and port for it to listen on.
     #[clap(long)]
     rpc: Option<SocketAddr>,
+
+    /// Provide the contacts of the network to join, as the path or http(s) url of a JSON
+    /// document listing the MultiAddr of its peers, e.g. as published for a public network.
+    ///
+    /// The peers are dialed along with those provided with `--peer`, if any.
+    #[clap(long, value_name = "PATH|URL", env = "SAFE_CONTACTS")]
+    contacts: Option<String>,
+
+    /// Make the node reachable from behind the router of a home network.
+    ///
+    /// Peers are asked to dial the node back with AutoNAT, to learn whether it's reachable.
+    ///
+    /// If it isn't, the node listens through relays of its peers, and connections relayed to it
+    /// are upgraded to direct ones by hole punching when possible.
+    #[clap(long)]
+    home_network: bool,
+
+    /// Do not map the port of the node in the router of its local network.
+    ///
+    /// By default, the port is mapped with UPnP, or NAT-PMP if the router doesn't support UPnP,
+    /// for the node to be reachable by peers from outside of its local network. The address it
+    /// is mapped to is logged and reported as a `PortMapped` node event.
+    #[clap(long)]
+    no_upnp: bool,
+
+    /// Relay the connections of peers which are not publicly reachable, with circuit relay v2.
+    ///
+    /// Only worth enabling on a publicly reachable node. Whether it is, as probed by asking
+    /// peers to dial it back, is logged and reported as a `NatStatus` node event.
+    #[clap(long)]
+    enable_relay: bool,
+
+    /// Serve the metrics of the node in the Prometheus format, at http://<IP:port>/metrics.
+    ///
+    /// These include its peers, the records it holds, the requests it handled, its bandwidth,
+    /// and the balance of the wallet in its root dir, if there's one.
+    #[clap(long, value_name = "IP:PORT")]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Specify the interval in minutes at which a one-line status of the node is logged,
+    /// i.e. its peers, records, disk and memory usage, uptime and reward balance.
+    ///
+    /// Defaults to 5 minutes. Set it to 0 to disable the status lines.
+    #[clap(long, value_name = "MINUTES", default_value_t = 5)]
+    status_interval: u64,
+
+    /// Specify the path to the genesis DBC of a new network, as created with
+    /// `safe wallet create-genesis`, to be used instead of the default one.
+    ///
+    /// Every node of the network has to be started with the same genesis DBC.
+    #[clap(long, value_name = "PATH")]
+    genesis_dbc: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Cmd {
+    /// Check the health of a running node through its RPC service, and exit with 0 if it's
+    /// healthy or 1 otherwise, e.g. as the HEALTHCHECK of a container.
+    ///
+    /// The node is healthy when it responds in time, is connected to enough peers,
+    /// and has enough space left on disk to store records.
+    Health {
+        /// The address of the RPC service of the node, as given with its `--rpc` option.
+        #[clap(long, value_name = "IP:PORT")]
+        rpc: SocketAddr,
+
+        /// The minimum number of peers the node has to be connected to.
+        #[clap(long, default_value_t = 1)]
+        min_peers: usize,
+
+        /// The minimum space in bytes left on the disk of the root dir of the node.
+        ///
+        /// Defaults to 100 MiB.
+        #[clap(long, value_name = "BYTES", default_value_t = 100 * 1024 * 1024)]
+        min_free_space: u64,
+
+        /// The number of seconds to wait for the node to respond.
+        #[clap(long, value_name = "SECS", default_value_t = 10)]
+        timeout: u64,
+    },
+    /// Drive a running node with synthetic StoreChunk, GetChunk and register creation
+    /// requests at the given rates, then print the latency percentiles of each of them.
+    ///
+    /// The requests are sent to the node only, e.g. to compare the performance of its storage
+    /// across releases. Setting a rate to 0 disables that type of request.
+    Benchmark {
+        /// The node to drive, using the MultiAddr format, ending with its peer ID.
+        #[clap(long, value_name = "MultiAddr")]
+        target: Multiaddr,
+
+        /// The number of seconds to send requests for.
+        #[clap(long, value_name = "SECS", default_value_t = 60)]
+        duration: u64,
+
+        /// The StoreChunk requests sent per second.
+        #[clap(long, value_name = "N", default_value_t = 10)]
+        store_rate: u32,
+
+        /// The GetChunk requests sent per second, for chunks stored during the benchmark.
+        #[clap(long, value_name = "N", default_value_t = 10)]
+        get_rate: u32,
+
+        /// The register creation requests sent per second.
+        #[clap(long, value_name = "N", default_value_t = 0)]
+        register_rate: u32,
+
+        /// The size in bytes of the chunks stored.
+        ///
+        /// Defaults to 512 KiB.
+        #[clap(long, value_name = "BYTES", default_value_t = 512 * 1024)]
+        chunk_size: usize,
+
+        /// The maximum number of requests awaiting their response at any time.
+        #[clap(long, value_name = "N", default_value_t = 64)]
+        max_in_flight: usize,
+    },
+    /// Print the tab completion script for the given shell,
+    /// e.g. `safenode completions bash > /etc/bash_completion.d/safenode`
+    Completions {
+        /// The shell to generate the script for.
+        #[clap(name = "shell")]
+        shell: Shell,
+    },
 }
 
 #[derive(Debug)]
-// To be sent to the main thread in order to stop/restart the execution of the safenode app.
+// To be sent to the supervisor in order to stop/restart the execution of the safenode app.
 enum NodeCtrl {
     // Request to stop the exeution of the safenode app, providing an error as a reason for it.
     Stop { delay: Duration, cause: Error },
@@ -95,17 +283,74 @@ enum NodeCtrl {
     Update(Duration),
 }
 
-fn main() -> Result<()> {
-    let opt = Opt::parse();
-    #[cfg(not(feature = "otlp"))]
-    let _log_appender_guard = init_node_logging(&opt.log_dir)?;
-    #[cfg(feature = "otlp")]
-    let (_rt, _guard) = {
-        // init logging in a separate runtime if we are sending traces to an opentelemetry server
-        let rt = Runtime::new()?;
-        let guard = rt.block_on(async { init_node_logging(&opt.log_dir) })?;
-        (rt, guard)
+#[tokio::main]
+async fn main() -> Result<()> {
+    let matches = Opt::command().get_matches();
+    let mut opt = Opt::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    if let Some(Cmd::Completions { shell }) = opt.cmd {
+        clap_complete::generate(
+            shell,
+            &mut Opt::command(),
+            "safenode",
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+    if let Some(config) = config::NodeConfigFile::read(opt.config.as_deref())? {
+        config.apply(&mut opt, &matches)?;
+    }
+    match opt.cmd {
+        Some(Cmd::Health {
+            rpc,
+            min_peers,
+            min_free_space,
+            timeout,
+        }) => {
+            let thresholds = health::HealthThresholds {
+                min_peers,
+                min_free_space,
+            };
+            let healthy =
+                health::check_health(rpc, &thresholds, Duration::from_secs(timeout)).await;
+            std::process::exit(if healthy { 0 } else { 1 });
+        }
+        Some(Cmd::Benchmark {
+            target,
+            duration,
+            store_rate,
+            get_rate,
+            register_rate,
+            chunk_size,
+            max_in_flight,
+        }) => {
+            let target = (peer_id_of(&target)?, target);
+            let config = benchmark::BenchmarkConfig {
+                store_rate,
+                get_rate,
+                register_rate,
+                chunk_size,
+                duration: Duration::from_secs(duration),
+                max_in_flight,
+            };
+            return benchmark::run_benchmark(target, config).await;
+        }
+        Some(Cmd::Completions { .. }) | None => {}
+    }
+
+    let default_rotation = LogRotation::default();
+    let log_rotation = LogRotation {
+        max_file_bytes: opt
+            .log_max_file_size
+            .unwrap_or(default_rotation.max_file_bytes),
+        max_files: opt.log_max_files.unwrap_or(default_rotation.max_files),
     };
+    // Initialised within the runtime, as required to send traces to an opentelemetry server.
+    let _log_appender_guard = init_node_logging(
+        &opt.log_dir,
+        opt.log_format,
+        opt.log_filter.as_deref(),
+        log_rotation,
+    )?;
 
     let root_dir = get_root_dir_path(opt.root_dir)?;
     let log_dir = if let Some(path) = opt.log_dir {
@@ -114,92 +359,281 @@ fn main() -> Result<()> {
         "stdout".to_string()
     };
 
-    let node_socket_addr = SocketAddr::new(opt.ip, opt.port);
-    let peers = parse_peer_multiaddreses(&opt.peers)?;
+    if let Some(path) = &opt.genesis_dbc {
+        let genesis_hex = std::fs::read_to_string(path)?;
+        let genesis_dbc = sn_dbc::Dbc::from_hex(genesis_hex.trim())
+            .map_err(|err| eyre!("Failed to read the genesis DBC from {path:?}: {err}"))?;
+        info!("Using the genesis DBC {:?} from {path:?}", genesis_dbc.id());
+        set_genesis_dbc(genesis_dbc);
+    }
 
-    loop {
-        let msg = format!(
-            "Running {} v{}",
-            env!("CARGO_BIN_NAME"),
-            env!("CARGO_PKG_VERSION")
-        );
-        info!("\n{}\n{}", msg, "=".repeat(msg.len()));
+    let nat = NatTraversal {
+        upnp: !opt.no_upnp,
+        autonat: opt.home_network || opt.enable_relay,
+        relay_server: opt.enable_relay,
+        ..if opt.home_network {
+            NatTraversal::home_network()
+        } else {
+            NatTraversal::default()
+        }
+    };
+
+    let msg = format!(
+        "Running {} v{}",
+        env!("CARGO_BIN_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    info!("\n{}\n{}", msg, "=".repeat(msg.len()));
 
-        // Create a tokio runtime per `start_node` attempt, this ensures
-        // any spawned tasks are closed before this would be run again.
-        let rt = Runtime::new()?;
-        rt.block_on(start_node(
+    let mut peers = bootstrap_peers(&opt.peers, opt.contacts.as_deref()).await?;
+    if peers.is_empty() {
+        peers = peers_from_env()?;
+    }
+    let node_socket_addr = SocketAddr::new(opt.ip, opt.port);
+    if opt.count <= 1 {
+        return Supervisor {
             node_socket_addr,
-            peers.clone(),
-            opt.rpc,
-            &log_dir,
-            &root_dir,
-        ))?;
-
-        // actively shut down the runtime
-        rt.shutdown_timeout(Duration::from_secs(2));
+            peers,
+            rpc: opt.rpc,
+            nat,
+            metrics_addr: opt.metrics_addr,
+            status_interval: opt.status_interval,
+            log_dir,
+            root_dir,
+        }
+        .run()
+        .await;
+    }
+
+    info!("Running {} nodes in this process", opt.count);
+    let mut supervisors = Vec::new();
+    for index in 0..opt.count {
+        let root_dir = root_dir.join(format!("node-{index}"));
+        std::fs::create_dir_all(&root_dir)?;
+        supervisors.push(Supervisor {
+            node_socket_addr: offset_port(node_socket_addr, index)?,
+            peers: peers.clone(),
+            rpc: opt.rpc.map(|addr| offset_port(addr, index)).transpose()?,
+            nat,
+            metrics_addr: opt
+                .metrics_addr
+                .map(|addr| offset_port(addr, index))
+                .transpose()?,
+            status_interval: opt.status_interval,
+            log_dir: log_dir.clone(),
+            root_dir,
+        });
+    }
+
+    // The nodes are independent, one of them stopping doesn't stop the others.
+    let results = join_all(supervisors.into_iter().map(Supervisor::run)).await;
+    results.into_iter().collect()
+}
+
+/// Returns the address with its port increased by the index of the node it's for,
+/// unless it's 0, i.e. any available port.
+fn offset_port(mut addr: SocketAddr, index: u16) -> Result<SocketAddr> {
+    if addr.port() != 0 {
+        let port = addr
+            .port()
+            .checked_add(index)
+            .ok_or_else(|| eyre!("No port {} + {index} for node {index}", addr.port()))?;
+        addr.set_port(port);
     }
+    Ok(addr)
 }
 
-async fn start_node(
+/// The number of times the node is started when restarting it, before giving up.
+const RESTART_ATTEMPTS: u32 = 5;
+
+/// The wait before the first retry of a failed restart, doubled before each of the next ones.
+const RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Runs the node, along with the tasks serving and monitoring it, i.e. the RPC service,
+/// the metrics server, and the monitors of its events and status, and handles the
+/// `NodeCtrl` cmds sent by them, restarting all of them in-process on request.
+struct Supervisor {
     node_socket_addr: SocketAddr,
     peers: Vec<(PeerId, Multiaddr)>,
     rpc: Option<SocketAddr>,
-    log_dir: &str,
-    root_dir: &Path,
-) -> Result<()> {
-    let started_instant = std::time::Instant::now();
+    nat: NatTraversal,
+    metrics_addr: Option<SocketAddr>,
+    status_interval: u64,
+    log_dir: String,
+    root_dir: PathBuf,
+}
 
-    info!("Starting node ...");
-    let running_node = Node::run(node_socket_addr, peers, root_dir).await?;
+/// A node started by the `Supervisor`, and the tasks serving and monitoring it.
+struct SupervisedNode {
+    running_node: RunningNode,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    /// Runs the node until it's stopped, by a `NodeCtrl::Stop` cmd, or by Ctrl-C.
+    async fn run(self) -> Result<()> {
+        // The channel outlives the nodes, the cmds are received from the RPC service and the
+        // events monitor of whichever node is running.
+        let (ctrl_tx, mut ctrl_rx) = mpsc::channel::<NodeCtrl>(5);
+        let mut node = self.start_node(ctrl_tx.clone()).await?;
 
-    // Channel to receive node ctrl cmds from RPC service (if enabled), and events monitoring task
-    let (ctrl_tx, mut ctrl_rx) = mpsc::channel::<NodeCtrl>(5);
+        loop {
+            let ctrl = tokio::select! {
+                ctrl = ctrl_rx.recv() => ctrl,
+                result = tokio::signal::ctrl_c() => {
+                    if let Err(err) = result {
+                        error!("Failed to listen for Ctrl-C: {err}");
+                    }
+                    info!("Ctrl-C received, stopping the node");
+                    self.teardown(node).await;
+                    return Ok(());
+                }
+            };
+            match ctrl {
+                Some(NodeCtrl::Restart(delay)) => {
+                    let msg = format!("Node is restarting in {delay:?}...");
+                    info!("{msg}");
+                    println!("{msg} Node log path: {}", self.log_dir);
+                    sleep(delay).await;
+                    self.teardown(node).await;
+                    node = self.restart_node(&ctrl_tx).await?;
+                }
+                Some(NodeCtrl::Stop { delay, cause }) => {
+                    let msg = format!("Node is stopping in {delay:?}...");
+                    info!("{msg}");
+                    println!("{msg} Node log path: {}", self.log_dir);
+                    sleep(delay).await;
+                    self.teardown(node).await;
+                    return Err(cause);
+                }
+                Some(NodeCtrl::Update(_delay)) => {
+                    // TODO: implement self-update once safenode app releases are published again
+                    println!("No self-update supported yet.");
+                }
+                // Never the case, as a sender is held here to be given to the restarted nodes.
+                None => {}
+            }
+        }
+    }
+
+    async fn start_node(&self, ctrl_tx: mpsc::Sender<NodeCtrl>) -> Result<SupervisedNode> {
+        let started_instant = std::time::Instant::now();
+
+        info!("Starting node ...");
+        let running_node = Node::run(
+            self.node_socket_addr,
+            self.peers.clone(),
+            &self.root_dir,
+            self.nat,
+        )
+        .await?;
+
+        let mut tasks = Vec::new();
+
+        // Monitor `NodeEvents`
+        let node_events_rx = running_node.node_events_channel().subscribe();
+        tasks.push(monitor_node_events(node_events_rx, ctrl_tx.clone()));
+
+        if self.status_interval > 0 {
+            tasks.push(log_status(
+                running_node.clone(),
+                Duration::from_secs(self.status_interval * 60),
+            ));
+        }
+
+        if let Some(addr) = self.metrics_addr {
+            tasks.extend(metrics::start_metrics_service(addr, running_node.clone()));
+        }
 
-    // Monitor `NodeEvents`
-    let node_events_rx = running_node.node_events_channel().subscribe();
-    monitor_node_events(node_events_rx, ctrl_tx.clone());
+        // Start up gRPC interface if enabled by user
+        if let Some(addr) = self.rpc {
+            tasks.push(rpc::start_rpc_service(
+                addr,
+                &self.log_dir,
+                running_node.clone(),
+                ctrl_tx,
+                started_instant,
+            ));
+        }
 
-    // Start up gRPC interface if enabled by user
-    if let Some(addr) = rpc {
-        rpc::start_rpc_service(addr, log_dir, running_node, ctrl_tx, started_instant);
+        Ok(SupervisedNode {
+            running_node,
+            tasks,
+        })
     }
 
-    // Keep the node and gRPC service (if enabled) running.
-    // We'll monitor any NodeCtrl cmd to restart/stop/update,
-    loop {
-        match ctrl_rx.recv().await {
-            Some(NodeCtrl::Restart(delay)) => {
-                let msg = format!("Node is restarting in {delay:?}...");
-                info!("{msg}");
-                println!("{msg} Node log path: {log_dir}");
-                sleep(delay).await;
-                break Ok(());
-            }
-            Some(NodeCtrl::Stop { delay, cause }) => {
-                let msg = format!("Node is stopping in {delay:?}...");
-                info!("{msg}");
-                println!("{msg} Node log path: {log_dir}");
-                sleep(delay).await;
-                return Err(cause);
-            }
-            Some(NodeCtrl::Update(_delay)) => {
-                // TODO: implement self-update once safenode app releases are published again
-                println!("No self-update supported yet.");
-            }
-            None => {
-                info!("Internal node ctrl cmds channel has been closed, restarting node");
-                break Ok(());
+    /// Starts the node again once torn down, retrying with a backoff when it fails to start,
+    /// e.g. as the ports of the previous node are not released yet.
+    async fn restart_node(&self, ctrl_tx: &mpsc::Sender<NodeCtrl>) -> Result<SupervisedNode> {
+        let mut failed_attempts = 0;
+        loop {
+            match self.start_node(ctrl_tx.clone()).await {
+                Ok(node) => return Ok(node),
+                Err(err) => {
+                    failed_attempts += 1;
+                    match restart_backoff(failed_attempts) {
+                        Some(backoff) => {
+                            warn!("Failed to restart the node, retrying in {backoff:?}: {err}");
+                            sleep(backoff).await;
+                        }
+                        None => return Err(err),
+                    }
+                }
             }
         }
     }
+
+    /// Stops the tasks serving and monitoring the node, then the node itself, waiting for all of
+    /// them to have stopped, for the ports they listened on to be released.
+    async fn teardown(&self, node: SupervisedNode) {
+        for task in &node.tasks {
+            task.abort();
+        }
+        for task in node.tasks {
+            let _ = task.await;
+        }
+        node.running_node.shutdown().await;
+    }
 }
 
-fn monitor_node_events(mut node_events_rx: NodeEventsReceiver, ctrl_tx: mpsc::Sender<NodeCtrl>) {
-    let _handle = tokio::spawn(async move {
+/// The wait before starting the node again after the given number of failed attempts to
+/// restart it, or `None` once it's given up on.
+fn restart_backoff(failed_attempts: u32) -> Option<Duration> {
+    if failed_attempts >= RESTART_ATTEMPTS {
+        return None;
+    }
+    Some(RESTART_BACKOFF * 2u32.pow(failed_attempts.saturating_sub(1)))
+}
+
+fn log_status(running_node: RunningNode, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let start = tokio::time::Instant::now() + interval;
+        let mut interval = tokio::time::interval_at(start, interval);
+        loop {
+            let _ = interval.tick().await;
+            if let Err(err) = running_node.log_status().await {
+                warn!("Failed to log the status of the node: {err}");
+            }
+        }
+    })
+}
+
+fn monitor_node_events(
+    mut node_events_rx: NodeEventsReceiver,
+    ctrl_tx: mpsc::Sender<NodeCtrl>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
         loop {
             match node_events_rx.recv().await {
                 Ok(NodeEvent::ConnectedToNetwork) => info!("Connected to the Network"),
+                Ok(NodeEvent::PortMapped(addr)) => {
+                    info!("The node is reachable from outside its local network at {addr}")
+                }
+                Ok(NodeEvent::NatStatus(NatStatus::Private)) => warn!(
+                    "The node is not publicly reachable, it listens through relays of its \
+                    peers if started with --home-network"
+                ),
+                Ok(NodeEvent::NatStatus(status)) => info!("The NAT status of the node is {status}"),
                 Ok(_) => { /* we ignore other evvents */ }
                 Err(RecvError::Closed) => {
                     if let Err(err) = ctrl_tx
@@ -212,8 +646,8 @@ fn monitor_node_events(mut node_events_rx: NodeEventsReceiver, ctrl_tx: mpsc::Se
                         error!(
                             "Failed to send node control msg to safenode bin main thread: {err}"
                         );
-                        break;
                     }
+                    break;
                 }
                 Err(RecvError::Lagged(n)) => {
                     warn!("Skipped {n} node events!");
@@ -221,31 +655,7 @@ fn monitor_node_events(mut node_events_rx: NodeEventsReceiver, ctrl_tx: mpsc::Se
                 }
             }
         }
-    });
-}
-
-/// Parse multiaddresses containing the P2p protocol (`/p2p/<PeerId>`).
-/// Returns an error for the first invalid multiaddress.
-fn parse_peer_multiaddreses(multiaddrs: &[Multiaddr]) -> Result<Vec<(PeerId, Multiaddr)>> {
-    multiaddrs
-        .iter()
-        .map(|multiaddr| {
-            // Take hash from the `/p2p/<hash>` component.
-            let p2p_multihash = multiaddr
-                .iter()
-                .find_map(|p| match p {
-                    Protocol::P2p(hash) => Some(hash),
-                    _ => None,
-                })
-                .ok_or_else(|| eyre!("address does not contain `/p2p/<PeerId>`"))?;
-            // Parse the multihash into the `PeerId`.
-            let peer_id =
-                PeerId::from_multihash(p2p_multihash).map_err(|_| eyre!("invalid p2p PeerId"))?;
-
-            Ok((peer_id, multiaddr.clone()))
-        })
-        // Short circuit on the first error. See rust docs `Result::from_iter`.
-        .collect::<Result<Vec<(PeerId, Multiaddr)>>>()
+    })
 }
 
 fn get_root_dir_path(root_dir_path: Option<PathBuf>) -> Result<PathBuf> {
@@ -260,3 +670,23 @@ fn get_root_dir_path(root_dir_path: Option<PathBuf>) -> Result<PathBuf> {
     std::fs::create_dir_all(path.clone())?;
     Ok(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_restarts_are_retried_with_a_doubling_backoff() {
+        let backoffs: Vec<_> = (1..RESTART_ATTEMPTS).map(restart_backoff).collect();
+        assert_eq!(
+            backoffs,
+            [1, 2, 4, 8].map(|secs| Some(Duration::from_secs(secs)))
+        );
+    }
+
+    #[test]
+    fn restarts_are_given_up_after_their_attempts() {
+        assert_eq!(restart_backoff(RESTART_ATTEMPTS), None);
+        assert_eq!(restart_backoff(u32::MAX), None);
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use safenode::node::RunningNode;
+
+use hyper::{
+    header::{HeaderValue, CONTENT_TYPE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use std::{convert::Infallible, net::SocketAddr};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// The content type of the Prometheus text exposition format.
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Serves the metrics of the node at `http://<addr>/metrics`, for Prometheus to scrape.
+/// Returns the task serving them, unless the address couldn't be bound.
+pub(super) fn start_metrics_service(
+    addr: SocketAddr,
+    running_node: RunningNode,
+) -> Option<JoinHandle<()>> {
+    let make_service = make_service_fn(move |_| {
+        let running_node = running_node.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let running_node = running_node.clone();
+                async move { Ok::<_, Infallible>(handle(&running_node, req).await) }
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&addr) {
+        Ok(server) => server.serve(make_service),
+        Err(err) => {
+            error!("Failed to bind the metrics server to {addr}: {err}");
+            println!("Failed to bind the metrics server to {addr}: {err}");
+            return None;
+        }
+    };
+    info!("Metrics served at http://{}/metrics", server.local_addr());
+    println!("Metrics served at http://{}/metrics", server.local_addr());
+
+    Some(tokio::spawn(async move {
+        if let Err(err) = server.await {
+            error!("The metrics server stopped: {err}");
+        }
+    }))
+}
+
+async fn handle(running_node: &RunningNode, req: Request<Body>) -> Response<Body> {
+    if req.method() != Method::GET || req.uri().path() != "/metrics" {
+        return reply(
+            StatusCode::NOT_FOUND,
+            "Metrics are served at /metrics\n".to_string(),
+        );
+    }
+    match running_node.metrics().await {
+        Ok(metrics) => {
+            let mut response = reply(StatusCode::OK, metrics);
+            let _ = response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(METRICS_CONTENT_TYPE));
+            response
+        }
+        Err(err) => {
+            warn!("Failed to gather the metrics of the node: {err}");
+            reply(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to gather the metrics of the node: {err}\n"),
+            )
+        }
+    }
+}
+
+fn reply(status: StatusCode, body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response
+}
@@ -6,21 +6,32 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use safenode::node::RunningNode;
+use safenode::{
+    node::{self, RunningNode},
+    protocol::storage::DbcAddress,
+};
 
 use super::NodeCtrl;
 
 use eyre::{ErrReport, Result};
 use std::{
+    collections::BTreeSet,
     env,
     net::SocketAddr,
     process,
-    time::{Duration, Instant},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    sync::{
+        broadcast::error::RecvError,
+        mpsc::{self, Sender},
+    },
+    task::JoinHandle,
 };
-use tokio::sync::mpsc::{self, Sender};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{transport::Server, Code, Request, Response, Status};
-use tracing::{debug, info, trace};
+use tracing::{debug, error, info, trace};
 
 use safenode_proto::safe_node_server::{SafeNode, SafeNodeServer};
 use safenode_proto::{
@@ -31,9 +42,11 @@ use safenode_proto::{
 
 // this includes code generated from .proto files
 mod safenode_proto {
-    tonic::include_proto!("safenode_proto");
+    tonic::include_proto!("safenode.v1");
 }
 
+mod legacy;
+
 // Defining a struct to hold information used by our gRPC service backend
 struct SafeNodeRpcService {
     addr: SocketAddr,
@@ -58,12 +71,16 @@ impl SafeNode for SafeNodeRpcService {
             request.get_ref()
         );
 
+        let storage = self.running_node.storage_info().await;
         let resp = Response::new(NodeInfoResponse {
             peer_id: self.running_node.peer_id().to_bytes(),
             log_dir: self.log_dir.clone(),
             pid: process::id(),
             bin_version: env!("CARGO_PKG_VERSION").to_string(),
             uptime_secs: self.started_instant.elapsed().as_secs(),
+            records_held: storage.records,
+            records_bytes: storage.records_bytes,
+            disk_available_bytes: storage.available_bytes.unwrap_or_default(),
         });
 
         Ok(resp)
@@ -101,14 +118,27 @@ impl SafeNode for SafeNodeRpcService {
             request.get_ref()
         );
 
+        let kinds: BTreeSet<String> = request.into_inner().kinds.into_iter().collect();
         let (client_tx, client_rx) = mpsc::channel(4);
 
         let mut events_rx = self.running_node.node_events_channel().subscribe();
         let _handle = tokio::spawn(async move {
-            while let Ok(event) = events_rx.recv().await {
-                let event = NodeEvent {
-                    event: format!("Event-{event:?}"),
+            let mut skipped = 0;
+            loop {
+                let event = match events_rx.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(n)) => {
+                        // Reported along with the next event, rather than ending the stream.
+                        skipped += n;
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
                 };
+                let event = to_rpc_event(&event, skipped);
+                if !kinds.is_empty() && !kinds.contains(&event.kind) {
+                    continue;
+                }
+                skipped = 0;
 
                 if let Err(err) = client_tx.send(Ok(event)).await {
                     debug!(
@@ -130,24 +160,10 @@ impl SafeNode for SafeNodeRpcService {
             request.get_ref()
         );
 
-        let cause = if let Some(addr) = request.remote_addr() {
-            ErrReport::msg(format!(
-                "Node has been stopped by an RPC request from {addr}."
-            ))
-        } else {
-            ErrReport::msg("Node has been stopped by an RPC request from an unknown address.")
-        };
-
         let delay = Duration::from_millis(request.get_ref().delay_millis);
-        match self.ctrl_tx.send(NodeCtrl::Stop { delay, cause }).await {
-            Ok(()) => Ok(Response::new(StopResponse {})),
-            Err(err) => Err(Status::new(
-                Code::Internal,
-                format!("Failed to stop the node: {err}"),
-            )),
-        }
+        self.stop_node(delay, request.remote_addr()).await?;
+        Ok(Response::new(StopResponse {}))
     }
-
     async fn restart(
         &self,
         request: Request<RestartRequest>,
@@ -189,13 +205,32 @@ impl SafeNode for SafeNodeRpcService {
     }
 }
 
+impl SafeNodeRpcService {
+    /// Stops the node after the delay, blaming the given caller, as requested by any version of
+    /// the RPC interface.
+    async fn stop_node(&self, delay: Duration, caller: Option<SocketAddr>) -> Result<(), Status> {
+        let cause = if let Some(addr) = caller {
+            ErrReport::msg(format!(
+                "Node has been stopped by an RPC request from {addr}."
+            ))
+        } else {
+            ErrReport::msg("Node has been stopped by an RPC request from an unknown address.")
+        };
+
+        self.ctrl_tx
+            .send(NodeCtrl::Stop { delay, cause })
+            .await
+            .map_err(|err| Status::new(Code::Internal, format!("Failed to stop the node: {err}")))
+    }
+}
+
 pub(super) fn start_rpc_service(
     addr: SocketAddr,
     log_dir: &str,
     running_node: RunningNode,
     ctrl_tx: Sender<NodeCtrl>,
     started_instant: Instant,
-) {
+) -> JoinHandle<()> {
     // creating a service
     let service = SafeNodeRpcService {
         addr,
@@ -207,11 +242,60 @@ pub(super) fn start_rpc_service(
     info!("RPC Server listening on {addr}");
     println!("RPC Server listening on {addr}");
 
-    let _handle = tokio::spawn(async move {
-        // adding our service to our server.
-        Server::builder()
-            .add_service(SafeNodeServer::new(service))
+    // The previous version of the interface is served alongside, for the tools built against it.
+    let service = Arc::new(service);
+    let legacy_service =
+        legacy::LegacySafeNodeServer::new(legacy::LegacyRpcService::new(service.clone()));
+
+    tokio::spawn(async move {
+        // adding our services to our server.
+        if let Err(err) = Server::builder()
+            .add_service(SafeNodeServer::from_arc(service))
+            .add_service(legacy_service)
             .serve(addr)
             .await
-    });
+        {
+            error!("The RPC server stopped: {err}");
+        }
+    })
+}
+
+/// Converts an event of the node into the one streamed to RPC clients, noting the number of
+/// events dropped just before it.
+fn to_rpc_event(event: &node::NodeEvent, skipped: u64) -> NodeEvent {
+    let (kind, subject) = match event {
+        node::NodeEvent::ConnectedToNetwork => ("ConnectedToNetwork", String::new()),
+        node::NodeEvent::PeerAdded(peer) => ("PeerAdded", peer.to_string()),
+        node::NodeEvent::PortMapped(addr) => ("PortMapped", addr.to_string()),
+        node::NodeEvent::NatStatus(status) => ("NatStatus", status.to_string()),
+        node::NodeEvent::DirectConnectionUpgraded(peer) => {
+            ("DirectConnectionUpgraded", peer.to_string())
+        }
+        node::NodeEvent::ChunkStored(address) => ("ChunkStored", format!("{:64x}", address.name())),
+        node::NodeEvent::RegisterCreated(address) => {
+            ("RegisterCreated", format!("{:64x}", address.name()))
+        }
+        node::NodeEvent::RegisterEdited(address) => {
+            ("RegisterEdited", format!("{:64x}", address.name()))
+        }
+        node::NodeEvent::RegisterOwnershipTransferred(address) => (
+            "RegisterOwnershipTransferred",
+            format!("{:64x}", address.name()),
+        ),
+        node::NodeEvent::SpendStored(dbc_id) => (
+            "SpendStored",
+            format!("{:64x}", DbcAddress::from_dbc_id(dbc_id).name()),
+        ),
+    };
+    let timestamp_millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or_default();
+    NodeEvent {
+        event: format!("Event-{event:?}"),
+        kind: kind.to_string(),
+        subject,
+        skipped,
+        timestamp_millis,
+    }
 }
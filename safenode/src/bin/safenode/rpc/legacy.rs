@@ -0,0 +1,143 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Compatibility shim serving the deprecated unversioned `safenode_proto` package, translating
+//! its calls to the `safenode.v1` service, which has the same messages and rpcs.
+
+use super::{safenode_proto as v1, SafeNodeRpcService};
+
+use v1::safe_node_server::SafeNode as _;
+
+use safenode_proto::safe_node_server::SafeNode;
+use safenode_proto::{
+    NetworkInfoRequest, NetworkInfoResponse, NodeEvent, NodeEventsRequest, NodeInfoRequest,
+    NodeInfoResponse, RestartRequest, RestartResponse, StopRequest, StopResponse, UpdateRequest,
+    UpdateResponse,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tonic::{Request, Response, Status};
+use tracing::debug;
+
+pub(super) use safenode_proto::safe_node_server::SafeNodeServer as LegacySafeNodeServer;
+
+// this includes code generated from .proto files
+mod safenode_proto {
+    tonic::include_proto!("safenode_proto");
+}
+
+/// Serves the deprecated package with the `safenode.v1` service.
+pub(super) struct LegacyRpcService {
+    v1: Arc<SafeNodeRpcService>,
+}
+
+impl LegacyRpcService {
+    pub(super) fn new(v1: Arc<SafeNodeRpcService>) -> Self {
+        Self { v1 }
+    }
+}
+
+#[tonic::async_trait]
+impl SafeNode for LegacyRpcService {
+    type NodeEventsStream = ReceiverStream<Result<NodeEvent, Status>>;
+
+    async fn node_info(
+        &self,
+        _request: Request<NodeInfoRequest>,
+    ) -> Result<Response<NodeInfoResponse>, Status> {
+        let info = self
+            .v1
+            .node_info(Request::new(v1::NodeInfoRequest {}))
+            .await?
+            .into_inner();
+        Ok(Response::new(NodeInfoResponse {
+            peer_id: info.peer_id,
+            pid: info.pid,
+            log_dir: info.log_dir,
+            bin_version: info.bin_version,
+            uptime_secs: info.uptime_secs,
+            records_held: info.records_held,
+            records_bytes: info.records_bytes,
+            disk_available_bytes: info.disk_available_bytes,
+        }))
+    }
+
+    async fn network_info(
+        &self,
+        _request: Request<NetworkInfoRequest>,
+    ) -> Result<Response<NetworkInfoResponse>, Status> {
+        let info = self
+            .v1
+            .network_info(Request::new(v1::NetworkInfoRequest {}))
+            .await?
+            .into_inner();
+        Ok(Response::new(NetworkInfoResponse {
+            connected_peers: info.connected_peers,
+            listeners: info.listeners,
+        }))
+    }
+
+    async fn node_events(
+        &self,
+        _request: Request<NodeEventsRequest>,
+    ) -> Result<Response<Self::NodeEventsStream>, Status> {
+        let mut events = self
+            .v1
+            .node_events(Request::new(v1::NodeEventsRequest { kinds: vec![] }))
+            .await?
+            .into_inner();
+
+        let (client_tx, client_rx) = mpsc::channel(4);
+        let _handle = tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                let event = event.map(|event| NodeEvent { event: event.event });
+                if let Err(err) = client_tx.send(event).await {
+                    debug!(
+                        "Dropping stream sender to legacy RPC client due to failure in \
+                        last attempt to notify an event: {err}"
+                    );
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(client_rx)))
+    }
+
+    async fn stop(&self, request: Request<StopRequest>) -> Result<Response<StopResponse>, Status> {
+        // Not delegated to the v1 rpc, for the address of the caller to be kept.
+        let delay = Duration::from_millis(request.get_ref().delay_millis);
+        self.v1.stop_node(delay, request.remote_addr()).await?;
+        Ok(Response::new(StopResponse {}))
+    }
+
+    async fn restart(
+        &self,
+        request: Request<RestartRequest>,
+    ) -> Result<Response<RestartResponse>, Status> {
+        let delay_millis = request.into_inner().delay_millis;
+        let _ = self
+            .v1
+            .restart(Request::new(v1::RestartRequest { delay_millis }))
+            .await?;
+        Ok(Response::new(RestartResponse {}))
+    }
+
+    async fn update(
+        &self,
+        request: Request<UpdateRequest>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        let delay_millis = request.into_inner().delay_millis;
+        let _ = self
+            .v1
+            .update(Request::new(v1::UpdateRequest { delay_millis }))
+            .await?;
+        Ok(Response::new(UpdateResponse {}))
+    }
+}
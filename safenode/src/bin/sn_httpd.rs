@@ -0,0 +1,519 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use safenode::{
+    client::{ChunkContent, ClientBuilder, Files, FilesContainer},
+    log::init_node_logging,
+    peers::{parse_peer_multiaddrs, peers_from_env},
+    protocol::{
+        storage::ChunkAddress,
+        xorurl::{DataType, XorUrl, XOR_URL_SCHEME},
+    },
+};
+
+use clap::Parser;
+use eyre::{eyre, Result};
+use hyper::{
+    header::{
+        ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_SECURITY_POLICY, CONTENT_TYPE, RANGE,
+    },
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use libp2p::Multiaddr;
+use std::{convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
+use tracing::{info, warn};
+use xor_name::XorName;
+
+/// The file served when a FilesContainer is requested without a path, if it holds one.
+const INDEX_FILE: &str = "index.html";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let _log_appender_guard =
+        init_node_logging(&None, Default::default(), None, Default::default())?;
+
+    let opt = Opt::parse();
+
+    info!("Instantiating a SAFE HTTP gateway...");
+
+    let peers = if opt.peers.is_empty() {
+        peers_from_env()?
+    } else {
+        parse_peer_multiaddrs(&opt.peers)?
+    };
+    let mut builder = ClientBuilder::new(bls::SecretKey::random());
+    if !peers.is_empty() {
+        builder = builder.with_peers(peers);
+    }
+    if let Some(secs) = opt.connection_timeout_secs {
+        builder = builder.with_connection_timeout(Duration::from_secs(secs));
+    }
+    let client = builder.build().await?;
+
+    let gateway = Gateway {
+        files: Files::new(client),
+    };
+    serve(Arc::new(gateway), opt.listen).await
+}
+
+/// Serves the files published on the network over HTTP, read-only, so they can be viewed
+/// in a browser. A file is requested with `GET /<address>`, and a file of a FilesContainer
+/// with `GET /<address>/<path>`, the address being either hex-encoded or a `safe://` url.
+/// A FilesContainer requested without a path is answered with its `index.html`, if it
+/// holds one, or else with the list of its files.
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Opt {
+    /// The address the HTTP server listens on.
+    #[clap(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+    /// Nodes we dial at start to help us get connected to the network. Can be specified multiple times.
+    /// If none are given, the comma-separated peers in the `SAFE_PEERS` env var are used.
+    #[clap(long = "peer", value_delimiter = ',')]
+    peers: Vec<Multiaddr>,
+    /// The maximum number of seconds to wait for the client to connect to the network.
+    #[clap(long = "connection-timeout")]
+    connection_timeout_secs: Option<u64>,
+}
+
+/// The state of the gateway served over HTTP.
+struct Gateway {
+    files: Files,
+}
+
+/// What a request resolves to on the network.
+enum Resource {
+    /// A file, with its size if already known.
+    File {
+        address: ChunkAddress,
+        size: Option<u64>,
+        path: Option<String>,
+    },
+    /// The list of the files of a FilesContainer.
+    Listing {
+        address: XorName,
+        container: FilesContainer,
+    },
+}
+
+impl Gateway {
+    async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        let head_only = match *req.method() {
+            Method::GET => false,
+            Method::HEAD => true,
+            _ => {
+                return response(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    "Only GET and HEAD are supported",
+                )
+            }
+        };
+
+        let path = req.uri().path().trim_start_matches('/').to_string();
+        let resource = match self.resolve(&path).await {
+            Ok(resource) => resource,
+            Err((status, reason)) => return response(status, &reason),
+        };
+
+        match resource {
+            Resource::Listing { address, container } => {
+                let mut response = response(StatusCode::OK, &listing(address, &container));
+                set_header(&mut response, CONTENT_TYPE, "text/html; charset=utf-8");
+                if head_only {
+                    *response.body_mut() = Body::empty();
+                }
+                response
+            }
+            Resource::File {
+                address,
+                size,
+                path,
+            } => {
+                let range = req
+                    .headers()
+                    .get(RANGE)
+                    .and_then(|value| value.to_str().ok());
+                self.serve_file(address, size, path.as_deref(), range, head_only)
+                    .await
+            }
+        }
+    }
+
+    /// Resolve the path of a request, i.e. `<address>[/<path>]`, into what is to be served.
+    async fn resolve(&self, path: &str) -> Result<Resource, (StatusCode, String)> {
+        let (address, file_path) = match path.split_once('/') {
+            Some((address, file_path)) => (address, Some(percent_decode(file_path))),
+            None => (path, None),
+        };
+        let (address, data_type) = parse_address(address).map_err(|err| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Expected `GET /<address>[/<path>]`: {err}"),
+            )
+        })?;
+        let address = ChunkAddress::new(address);
+
+        let is_container = data_type == Some(DataType::FilesContainer)
+            || file_path.as_ref().map_or(false, |path| !path.is_empty());
+        if !is_container {
+            return Ok(Resource::File {
+                address,
+                size: None,
+                path: None,
+            });
+        }
+
+        let container = FilesContainer::retrieve(&self.files, address)
+            .await
+            .map_err(|err| {
+                warn!("Failed to retrieve FilesContainer at {address:?}: {err}");
+                (
+                    StatusCode::NOT_FOUND,
+                    format!("No FilesContainer found at {:x}", address.name()),
+                )
+            })?;
+
+        let file_path = match file_path.as_deref().filter(|path| !path.is_empty()) {
+            Some(file_path) => file_path,
+            None if container.get(INDEX_FILE).is_some() => INDEX_FILE,
+            None => {
+                return Ok(Resource::Listing {
+                    address: *address.name(),
+                    container,
+                })
+            }
+        };
+        let entry = container.get(file_path).ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("No file at {file_path} in the FilesContainer"),
+            )
+        })?;
+        Ok(Resource::File {
+            address: entry.address,
+            size: Some(entry.size),
+            path: Some(file_path.to_string()),
+        })
+    }
+
+    /// Serve the file at the address, or the part of it requested with a `Range` header.
+    async fn serve_file(
+        &self,
+        address: ChunkAddress,
+        size: Option<u64>,
+        path: Option<&str>,
+        range: Option<&str>,
+        head_only: bool,
+    ) -> Response<Body> {
+        let size = match size {
+            Some(size) => size,
+            None => match self.files.inspect_chunk(address).await {
+                Ok(ChunkContent::DataMap { file_size, .. }) => file_size as u64,
+                Ok(ChunkContent::Raw { size }) => size as u64,
+                Err(err) => {
+                    warn!("Failed to retrieve file at {address:?}: {err}");
+                    return response(
+                        StatusCode::NOT_FOUND,
+                        &format!("No file found at {:x}", address.name()),
+                    );
+                }
+            },
+        };
+
+        let range = match range.map(|range| parse_range(range, size)) {
+            None => None,
+            Some(Some(range)) => Some(range),
+            Some(None) => {
+                let mut response = response(
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    "The requested range is not satisfiable",
+                );
+                set_header(&mut response, CONTENT_RANGE, &format!("bytes */{size}"));
+                return response;
+            }
+        };
+        let (start, end) = range.unwrap_or((0, size.saturating_sub(1)));
+        let length = if size == 0 { 0 } else { end - start + 1 };
+
+        let body = if head_only || length == 0 {
+            Body::empty()
+        } else {
+            let bytes = if range.is_some() {
+                self.files
+                    .read_from(address, start as usize, length as usize)
+                    .await
+            } else {
+                self.files.read_bytes(address).await
+            };
+            match bytes {
+                Ok(bytes) => Body::from(bytes),
+                Err(err) => {
+                    warn!("Failed to read file at {address:?}: {err}");
+                    return response(StatusCode::BAD_GATEWAY, "Failed to read the file");
+                }
+            }
+        };
+
+        let mut response = Response::new(body);
+        if range.is_some() {
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            set_header(
+                &mut response,
+                CONTENT_RANGE,
+                &format!("bytes {start}-{end}/{size}"),
+            );
+        }
+        set_header(&mut response, ACCEPT_RANGES, "bytes");
+        set_header(&mut response, CONTENT_LENGTH, &length.to_string());
+        set_header(&mut response, CONTENT_TYPE, content_type(path));
+        // The files published by anyone share the origin of the gateway, so their scripts are
+        // sandboxed, without access to that origin.
+        set_header(&mut response, CONTENT_SECURITY_POLICY, "sandbox");
+        response
+    }
+}
+
+/// Parse a network address, either hex encoded or as a url with or without the `safe://`
+/// scheme, into a `XorName` along with the type of data a url points at.
+fn parse_address(address: &str) -> Result<(XorName, Option<DataType>)> {
+    if let Ok(bytes) = hex::decode(address) {
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| eyre!("Address must be 32 bytes long, i.e. 64 hex characters"))?;
+        return Ok((XorName(bytes), None));
+    }
+    let url = if address.starts_with(XOR_URL_SCHEME) {
+        address.parse::<XorUrl>()?
+    } else {
+        format!("{XOR_URL_SCHEME}{address}").parse::<XorUrl>()?
+    };
+    match url.data_type {
+        DataType::File | DataType::FilesContainer => Ok((url.name, Some(url.data_type))),
+        data_type => Err(eyre!(
+            "The url points at a {data_type:?}, not a file or FilesContainer"
+        )),
+    }
+}
+
+/// Parse a `Range` header holding a single range of bytes, e.g. `bytes=0-499`, `bytes=500-`
+/// or `bytes=-500`, into the first and last positions it covers in a file of the given size.
+/// Returns `None` if the range is malformed or not satisfiable.
+fn parse_range(range: &str, size: u64) -> Option<(u64, u64)> {
+    let (start, end) = range.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 {
+                return None;
+            }
+            (size.saturating_sub(suffix), size.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, size.checked_sub(1)?),
+        (start, end) => {
+            let end: u64 = end.parse().ok()?;
+            (start.parse().ok()?, end.min(size.checked_sub(1)?))
+        }
+    };
+    (start <= end).then_some((start, end))
+}
+
+/// Guess the content type of a file from the extension of its path.
+fn content_type(path: Option<&str>) -> &'static str {
+    let extension = path
+        .and_then(|path| path.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js" | "mjs") => "text/javascript",
+        Some("json") => "application/json",
+        Some("txt" | "md") => "text/plain; charset=utf-8",
+        Some("xml") => "application/xml",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("ico") => "image/x-icon",
+        Some("mp3") => "audio/mpeg",
+        Some("mp4") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("pdf") => "application/pdf",
+        Some("wasm") => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+/// An HTML page linking to each of the files of the FilesContainer.
+fn listing(address: XorName, container: &FilesContainer) -> String {
+    let address = hex::encode(address);
+    let items: String = container
+        .files()
+        .map(|(path, entry)| {
+            format!(
+                "<li><a href=\"/{address}/{}\">{}</a> ({} bytes)</li>\n",
+                percent_encode(path),
+                escape_html(path),
+                entry.size
+            )
+        })
+        .collect();
+    format!(
+        "<!DOCTYPE html>\n<html><head><title>{address}</title></head><body>\n\
+         <h1>{address}</h1>\n<ul>\n{items}</ul>\n</body></html>\n"
+    )
+}
+
+/// Decode the `%XX` escapes of a request path, e.g. `%20` for the spaces of file names,
+/// leaving any invalid escape as is.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Encode the bytes of a path which are not allowed as they are in a url path, or which would
+/// end it, e.g. the `#` and `?` of file names, as `%XX` escapes, keeping its `/` separators.
+fn percent_encode(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn set_header(response: &mut Response<Body>, name: hyper::header::HeaderName, value: &str) {
+    if let Ok(value) = value.parse() {
+        let _ = response.headers_mut().insert(name, value);
+    }
+}
+
+fn response(status: StatusCode, body: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+async fn serve(gateway: Arc<Gateway>, listen: SocketAddr) -> Result<()> {
+    let make_service = make_service_fn(move |_| {
+        let gateway = gateway.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let gateway = gateway.clone();
+                async move { Ok::<_, Infallible>(gateway.handle(req).await) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&listen)?.serve(make_service);
+    println!("Gateway serving files at http://{}", server.local_addr());
+    info!("Gateway serving files at http://{}", server.local_addr());
+    server.await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_is_parsed_within_the_file() {
+        assert_eq!(parse_range("bytes=0-499", 1000), Some((0, 499)));
+        assert_eq!(parse_range("bytes=500-", 1000), Some((500, 999)));
+        assert_eq!(parse_range("bytes=-500", 1000), Some((500, 999)));
+        assert_eq!(parse_range(" bytes=999-999 ", 1000), Some((999, 999)));
+
+        // Ranges going past the end of the file are truncated.
+        assert_eq!(parse_range("bytes=-2000", 1000), Some((0, 999)));
+        assert_eq!(parse_range("bytes=900-2000", 1000), Some((900, 999)));
+    }
+
+    #[test]
+    fn unsatisfiable_or_malformed_range_is_rejected() {
+        assert_eq!(parse_range("bytes=-0", 1000), None);
+        assert_eq!(parse_range("bytes=0-", 0), None);
+        assert_eq!(parse_range("bytes=-500", 0), None);
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=500-100", 1000), None);
+        assert_eq!(parse_range("bytes=a-b", 1000), None);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), None);
+        assert_eq!(parse_range("items=0-499", 1000), None);
+        assert_eq!(parse_range("bytes=0", 1000), None);
+    }
+
+    #[test]
+    fn percent_escapes_are_decoded() {
+        assert_eq!(percent_decode("dir/a%20b.txt"), "dir/a b.txt");
+        assert_eq!(percent_decode("%C3%A9t%c3%a9"), "été");
+        assert_eq!(percent_decode("100%25"), "100%");
+
+        // Invalid escapes are left as they are.
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%2"), "%2");
+        assert_eq!(percent_decode("%zz%"), "%zz%");
+    }
+
+    #[test]
+    fn percent_encoding_keeps_separators_and_roundtrips() {
+        assert_eq!(percent_encode("dir/index.html"), "dir/index.html");
+        assert_eq!(percent_encode("dir/a b#1?.txt"), "dir/a%20b%231%3F.txt");
+        assert_eq!(percent_encode("100%"), "100%25");
+
+        for path in ["dir/a b#1?.txt", "100%/%20", "été/<x>&\"y\""] {
+            assert_eq!(percent_decode(&percent_encode(path)), path);
+        }
+    }
+
+    #[test]
+    fn address_is_parsed_from_hex_or_url() -> Result<()> {
+        let name = XorName::random(&mut rand::thread_rng());
+
+        assert_eq!(parse_address(&hex::encode(name))?, (name, None));
+        assert!(parse_address(&hex::encode(&name.0[..16])).is_err());
+
+        let url = XorUrl::new(DataType::FilesContainer, name).encode();
+        let expected = (name, Some(DataType::FilesContainer));
+        assert_eq!(parse_address(&url)?, expected);
+        assert_eq!(parse_address(&url[XOR_URL_SCHEME.len()..])?, expected);
+
+        let register = XorUrl::new(DataType::Register(3006), name).encode();
+        assert!(parse_address(&register).is_err());
+        assert!(parse_address("not-an-address").is_err());
+
+        Ok(())
+    }
+}
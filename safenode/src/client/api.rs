@@ -7,63 +7,135 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
+    chunks::{Encryptor, Error as ChunksError},
     error::{Error, Result},
-    Client, ClientEvent, ClientEventsChannel, ClientEventsReceiver, Register, RegisterOffline,
+    file_apis::{chunk_bytes_in_pool, FileVerificationReport},
+    operation,
+    provenance::ChunkProvenance,
+    rate_limit::RateLimiter,
+    Client, ClientBuilder, ClientEvent, ClientEventsChannel, ClientEventsReceiver, EventCallbackId,
+    Files, Register, RegisterOffline,
 };
 
 use crate::{
     domain::client_transfers::SpendRequest,
-    network::{close_group_majority, NetworkEvent, SwarmDriver, CLOSE_GROUP_SIZE},
+    network::{close_group_majority, NetworkEvent, SwarmDriver},
     protocol::{
+        error::{Error as ProtocolError, StorageError, TransferError},
         messages::{Cmd, CmdResponse, Query, QueryResponse, Request, Response, SpendQuery},
         storage::{Chunk, ChunkAddress, DbcAddress},
         NetworkAddress,
     },
 };
 
-use sn_dbc::{DbcId, SignedSpend};
+use sn_dbc::{DbcId, SignedSpend, Token};
 
 use bls::{PublicKey, SecretKey, Signature};
-use futures::future::select_all;
-use itertools::Itertools;
+use bytes::Bytes;
+use futures::future::{join_all, select_all};
 use libp2p::{kad::RecordKey, Multiaddr, PeerId};
-use tokio::task::spawn;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc, Semaphore},
+    task::spawn,
+    time::timeout,
+};
 use tracing::trace;
 use xor_name::XorName;
 
+/// The default amount of time the client waits to get connected to the network.
+pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Maximum number of requests the client has in flight at any time, across all operations.
+const MAX_CONCURRENT_REQUESTS: usize = 32;
+
 impl Client {
     /// Instantiate a new client.
+    ///
+    /// If not enough peers are added to the client's routing table within [`CONNECTION_TIMEOUT`],
+    /// an [`Error::ConnectionTimeout`] is returned reporting which peers were dialed and why
+    /// dialing them failed.
+    ///
+    /// See [`ClientBuilder`] to tune how the client connects and sends its requests, e.g. to
+    /// wait for another connection timeout.
     pub async fn new(signer: SecretKey, peers: Option<Vec<(PeerId, Multiaddr)>>) -> Result<Self> {
+        let mut builder = ClientBuilder::new(signer);
+        if let Some(peers) = peers {
+            builder = builder.with_peers(peers);
+        }
+        builder.build().await
+    }
+
+    /// Instantiate the client built with the given builder.
+    pub(super) async fn connect(builder: ClientBuilder) -> Result<Self> {
+        let ClientBuilder {
+            signer,
+            peers,
+            connection_timeout,
+            peers_to_wait_for,
+            request_timeouts,
+            retry_policy,
+            events_capacity,
+            local,
+            rate_limit,
+            encryption_threads,
+        } = builder;
+
         info!("Starting Kad swarm in client mode...");
-        let (network, mut network_event_receiver, swarm_driver) = SwarmDriver::new_client()?;
+        let (network, mut network_event_receiver, swarm_driver) =
+            SwarmDriver::new_client_with(local, request_timeouts.longest())?;
         info!("Client constructed network and swarm_driver");
-        let events_channel = ClientEventsChannel::default();
+        let events_channel = ClientEventsChannel::with_capacity(events_capacity);
         let client = Self {
             network: network.clone(),
             events_channel,
             signer,
+            requests_limiter: Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS)),
+            rate_limiter: Arc::new(RateLimiter::new(rate_limit)),
+            encryptor: Encryptor::new(encryption_threads)?,
+            request_timeouts,
+            retry_policy,
         };
 
         let mut must_dial_network = true;
 
+        // Keep track of the peers we dial, so we can report on them if we fail to get connected.
+        let mut dial_results: BTreeMap<PeerId, String> = peers
+            .iter()
+            .flatten()
+            .map(|(peer_id, _)| (*peer_id, "dial still pending".to_string()))
+            .collect();
+        let (dial_result_sender, mut dial_result_receiver) = mpsc::unbounded_channel();
+
         let mut client_clone = client.clone();
 
-        let _swarm_driver = spawn({
+        let swarm_driver_handle = spawn({
             trace!("Starting up client swarm_driver");
             swarm_driver.run()
         });
-        let _event_handler = spawn(async move {
+        let event_handler = spawn(async move {
             loop {
                 if let Some(peers) = peers.clone() {
                     if must_dial_network {
                         let network = network.clone();
+                        let dial_result_sender = dial_result_sender.clone();
                         let _handle = spawn(async move {
                             trace!("Client dialing network");
                             for (peer_id, addr) in peers {
                                 let _ = network.add_to_routing_table(peer_id, addr.clone()).await;
-                                if let Err(err) = network.dial(peer_id, addr.clone()).await {
-                                    tracing::error!("Failed to dial {peer_id}: {err:?}");
+                                let result = match network.dial(peer_id, addr.clone()).await {
+                                    Ok(()) => Ok(format!("dialing {addr}")),
+                                    Err(err) => {
+                                        tracing::error!("Failed to dial {peer_id}: {err:?}");
+                                        Err(format!("failed to dial {addr}: {err}"))
+                                    }
                                 };
+                                let _ = dial_result_sender.send((peer_id, result));
                             }
                         });
 
@@ -76,7 +148,7 @@ impl Client {
                     Some(event) => event,
                     None => {
                         error!("The `NetworkEvent` channel has been closed");
-                        continue;
+                        break;
                     }
                 };
                 trace!("Client recevied a network event {event:?}");
@@ -86,18 +158,53 @@ impl Client {
             }
         });
 
-        // Wait till client confirmed with connected to enough nodes.
+        // Wait till client confirmed with connected to enough nodes, giving up early when
+        // joining a public network none of the peers provided could be dialed, as no other
+        // peer can then be found.
         let mut client_events_rx = client.events_channel();
         let mut added_node = 0;
-        while added_node <= CLOSE_GROUP_SIZE {
-            if let Ok(event) = client_events_rx.recv().await {
-                match event {
-                    ClientEvent::ConnectedToNetwork => {
-                        added_node += 1;
-                        info!("Client connected to the Network with {added_node:?} nodes added");
+        let dials = dial_results.len();
+        let mut failed_dials = 0;
+        let wait_for_enough_peers = async {
+            while added_node < peers_to_wait_for {
+                tokio::select! {
+                    event = client_events_rx.recv() => match event {
+                        Ok(ClientEvent::ConnectedToNetwork) => {
+                            added_node += 1;
+                            info!("Client connected to the Network with {added_node:?} nodes added");
+                        }
+                        Err(Error::EventsReceiver(RecvError::Closed)) => break,
+                        _ => {}
+                    },
+                    Some((peer_id, result)) = dial_result_receiver.recv() => {
+                        failed_dials += usize::from(result.is_err());
+                        let _ = dial_results.insert(peer_id, result.unwrap_or_else(|err| err));
+                        if !local && failed_dials == dials {
+                            break;
+                        }
                     }
                 }
             }
+        };
+        let _ = timeout(connection_timeout, wait_for_enough_peers).await;
+
+        if added_node < peers_to_wait_for {
+            while let Ok((peer_id, result)) = dial_result_receiver.try_recv() {
+                let _ = dial_results.insert(peer_id, result.unwrap_or_else(|err| err));
+            }
+            error!(
+                "Client could not connect to the network within {connection_timeout:?}, \
+                with {added_node} of {peers_to_wait_for} nodes added. Dialed peers: {dial_results:?}"
+            );
+            // Nothing is left running for a client which is not returned.
+            event_handler.abort();
+            swarm_driver_handle.abort();
+            return Err(Error::ConnectionTimeout {
+                found: added_node,
+                required: peers_to_wait_for,
+                timeout: connection_timeout,
+                dial_results,
+            });
         }
 
         Ok(client)
@@ -108,7 +215,13 @@ impl Client {
             // Clients do not handle requests.
             NetworkEvent::RequestReceived { .. } => {}
             // We do not listen on sockets.
-            NetworkEvent::NewListenAddr(_) => {}
+            NetworkEvent::NewListenAddr(_) | NetworkEvent::PortMapped(_) => {}
+            // We do not probe whether we're reachable.
+            NetworkEvent::NatStatusChanged(_) => {}
+            NetworkEvent::DirectConnectionUpgraded(peer) => {
+                self.events_channel
+                    .broadcast(ClientEvent::DirectConnectionUpgraded(peer));
+            }
             NetworkEvent::PeerAdded(peer_id) => {
                 self.events_channel
                     .broadcast(ClientEvent::ConnectedToNetwork);
@@ -131,6 +244,27 @@ impl Client {
         self.events_channel.subscribe()
     }
 
+    /// Register a callback to be called with every event the client broadcasts from now on,
+    /// as an alternative to listening to the [`events_channel`](Self::events_channel), e.g.
+    /// for bindings to other languages, or GUI frameworks with no async runtime of their own.
+    ///
+    /// The callback is called from the task of the client broadcasting the event, and thus has
+    /// to return quickly, e.g. by forwarding the event to the thread handling it, and must not
+    /// register nor remove callbacks itself. Events broadcast before the callback is registered,
+    /// e.g. [`ClientEvent::ConnectedToNetwork`] while the client is being created, are not replayed.
+    pub fn on_event<F>(&self, callback: F) -> EventCallbackId
+    where
+        F: FnMut(ClientEvent) + Send + 'static,
+    {
+        self.events_channel.add_callback(Box::new(callback))
+    }
+
+    /// Unregister a callback registered with [`on_event`](Self::on_event), returning whether
+    /// it was still registered.
+    pub fn remove_event_callback(&self, id: EventCallbackId) -> bool {
+        self.events_channel.remove_callback(id)
+    }
+
     /// Sign the given data
     pub fn sign(&self, data: &[u8]) -> Signature {
         self.signer.sign(data)
@@ -141,6 +275,93 @@ impl Client {
         self.signer.public_key()
     }
 
+    /// Get the peers the client believes to be the close group of the given address,
+    /// sorted by their distance to it, along with the addresses known for each of them.
+    ///
+    /// This is meant as a diagnostic tool, e.g. to find out where a piece of data should be held.
+    pub async fn get_close_group(
+        &self,
+        address: NetworkAddress,
+    ) -> Result<Vec<(PeerId, Vec<Multiaddr>)>> {
+        trace!("Getting the close group of {address:?}");
+        let closest_peers = self.network.client_get_closest_peers(&address).await?;
+        let close_group = self.network.get_peers_addresses(closest_peers).await?;
+        Ok(close_group)
+    }
+
+    /// Get the cost of storing data at the given address, as quoted by its close group.
+    ///
+    /// The median of the quotes returned by the close group is used, so a single
+    /// node cannot skew the cost much in either direction.
+    pub async fn get_store_cost(&self, address: NetworkAddress) -> Result<Token> {
+        trace!("Getting store cost for {address:?}");
+        let request = Request::Query(Query::GetStoreCost(address.clone()));
+        let responses = self.send_to_closest(request).await?;
+
+        let mut costs: Vec<Token> = responses
+            .iter()
+            .flatten()
+            .filter_map(|resp| match resp {
+                Response::Query(QueryResponse::GetStoreCost(Ok(cost))) => Some(*cost),
+                _ => None,
+            })
+            .collect();
+
+        if costs.len() >= close_group_majority() {
+            costs.sort();
+            return Ok(costs[costs.len() / 2]);
+        }
+
+        // If not enough quotes were gotten, we return the first error sent to us.
+        for resp in responses.iter().flatten() {
+            if let Response::Query(QueryResponse::GetStoreCost(result)) = resp {
+                let _ = result.clone()?;
+            };
+        }
+
+        // If there were no success or fail to the expected query,
+        // we check if there were any send errors.
+        for resp in responses {
+            let _ = resp?;
+        }
+
+        Err(Error::CouldNotGetStoreCost(format!(
+            "Not enough close group nodes quoted a store cost for {address:?}. Got {}, required: {}.",
+            costs.len(),
+            close_group_majority()
+        )))
+    }
+
+    /// Estimate the cost of storing the given file contents on the network.
+    ///
+    /// The contents are self-encrypted locally, without storing anything, and the
+    /// store cost is then queried for each of the resulting chunks' addresses.
+    pub async fn estimate_file_cost(&self, bytes: Bytes) -> Result<Token> {
+        let (head_address, chunks) = chunk_bytes_in_pool(self, bytes).await?;
+        debug!(
+            "Estimating store cost of {} chunk/s for file at {head_address:?}",
+            chunks.len()
+        );
+
+        let mut total_cost = Token::zero();
+        for chunk in chunks {
+            let address = NetworkAddress::from_chunk_address(*chunk.address());
+            let cost = self.get_store_cost(address).await?;
+            total_cost = total_cost
+                .checked_add(cost)
+                .ok_or_else(|| Error::CouldNotGetStoreCost("Total cost overflow".to_string()))?;
+        }
+
+        Ok(total_cost)
+    }
+
+    /// Verify the file at the given address can be fully retrieved from the network,
+    /// returning a report on each of the chunks it's made of.
+    pub async fn verify_file(&self, address: ChunkAddress) -> Result<FileVerificationReport> {
+        info!("Verifying file at {address:?}");
+        Files::new(self.clone()).verify(address).await
+    }
+
     /// Retrieve a Register from the network.
     pub async fn get_register(&self, xorname: XorName, tag: u64) -> Result<Register> {
         info!("Retrieving a Register replica with name {xorname} and tag {tag}");
@@ -194,15 +415,102 @@ impl Client {
 
     /// Retrieve a `Chunk` from the kad network.
     pub(super) async fn get_chunk(&self, address: ChunkAddress) -> Result<Chunk> {
+        self.get_chunk_and_holder(address)
+            .await
+            .map(|(chunk, _holder)| chunk)
+    }
+
+    /// Retrieve a `Chunk` being downloaded from the kad network, along with where it was
+    /// retrieved from. When it can't be retrieved or its content doesn't match its address,
+    /// it's requested from each of the other peers of its close group in turn, before failing.
+    pub(super) async fn get_chunk_with_provenance(
+        &self,
+        address: ChunkAddress,
+    ) -> (Result<Chunk>, ChunkProvenance) {
+        let started = Instant::now();
+        let mut provenance = ChunkProvenance::new(address);
+        provenance.attempts += 1;
+        let mut tried = BTreeSet::new();
+        let mut result = match self.get_chunk_and_holder(address).await {
+            Ok((chunk, holder)) => {
+                tried.extend(holder);
+                verify_chunk(address, chunk, holder, &mut provenance)
+            }
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = &result {
+            debug!("Requesting chunk {address:?} from its close group, after: {error}");
+            let alternates = match self
+                .network
+                .client_get_closest_peers(&NetworkAddress::from_chunk_address(address))
+                .await
+            {
+                Ok(peers) => peers,
+                Err(error) => {
+                    warn!("Could not find the close group of chunk {address:?}: {error}");
+                    vec![]
+                }
+            };
+            for peer in alternates.into_iter().filter(|peer| !tried.contains(peer)) {
+                provenance.attempts += 1;
+                result = match self.get_chunk_from(address, peer).await {
+                    Ok(chunk) => verify_chunk(address, chunk, Some(peer), &mut provenance),
+                    Err(error) => Err(error),
+                };
+                match &result {
+                    Ok(_) => break,
+                    Err(error) => debug!("Chunk {address:?} not retrieved from {peer:?}: {error}"),
+                }
+            }
+        }
+
+        if let Err(error) = &result {
+            provenance.error = Some(error.to_string());
+        }
+        provenance.elapsed = started.elapsed();
+        (result, provenance)
+    }
+
+    /// Request a `Chunk` from the given peer, e.g. one of its close group.
+    async fn get_chunk_from(&self, address: ChunkAddress, peer: PeerId) -> Result<Chunk> {
+        let request = Request::Query(Query::GetChunk(address));
+        match self.send_request(request, peer).await? {
+            Response::Query(QueryResponse::GetChunk(result)) => {
+                let chunk = result?;
+                self.rate_limiter.bytes(chunk.value().len()).await;
+                Ok(chunk)
+            }
+            other => {
+                warn!("On querying chunk {address:?} from {peer:?} received unexpected response {other:?}");
+                Err(Error::UnexpectedResponses)
+            }
+        }
+    }
+
+    /// Retrieve a `Chunk` from the kad network, along with the peer which held it.
+    async fn get_chunk_and_holder(&self, address: ChunkAddress) -> Result<(Chunk, Option<PeerId>)> {
         info!("Getting chunk: {address:?}");
         let xorname = address.name();
-        match self
-            .network
-            .get_provided_data(RecordKey::new(xorname))
-            .await?
-        {
-            Ok(QueryResponse::GetChunk(result)) => Ok(result?),
-            Ok(other) => {
+        self.rate_limiter.request(0).await;
+        let get_chunk = move || async move {
+            let result = timeout(
+                self.request_timeouts.get_chunk,
+                self.network
+                    .get_provided_data_and_holder(RecordKey::new(xorname)),
+            )
+            .await??;
+            Ok(result)
+        };
+        match self.with_retries(get_chunk).await? {
+            Ok((QueryResponse::GetChunk(result), holder)) => {
+                let chunk = result?;
+                // The size of the chunk is only known once retrieved, thus it's accounted for
+                // by delaying the requests which follow.
+                self.rate_limiter.bytes(chunk.value().len()).await;
+                Ok((chunk, holder))
+            }
+            Ok((other, _holder)) => {
                 warn!("On querying chunk {xorname:?} received unexpected response {other:?}",);
                 Err(Error::UnexpectedResponses)
             }
@@ -214,14 +522,56 @@ impl Client {
     }
 
     pub(crate) async fn send_to_closest(&self, request: Request) -> Result<Vec<Result<Response>>> {
-        let responses = self
-            .network
-            .client_send_to_closest(&request)
-            .await?
-            .into_iter()
-            .map(|res| res.map_err(Error::Network))
-            .collect_vec();
-        Ok(responses)
+        operation("send_to_closest", async {
+            info!(
+                "Sending {request:?} with dst {:?} to the closest peers.",
+                request.dst()
+            );
+            let closest_peers = self
+                .network
+                .client_get_closest_peers(&request.dst())
+                .await?;
+
+            let responses = join_all(
+                closest_peers
+                    .into_iter()
+                    .map(|peer| self.send_request(request.clone(), peer)),
+            )
+            .await;
+            Ok(responses)
+        })
+        .await
+    }
+
+    // Send a `Request` to the given peer, waiting for its rate limit, and for a slot within
+    // the client-wide limit of concurrent requests before each attempt.
+    async fn send_request(&self, request: Request, peer: PeerId) -> Result<Response> {
+        let bytes = match &request {
+            Request::Cmd(Cmd::StoreChunk(chunk)) => chunk.value().len(),
+            _ => 0,
+        };
+        self.rate_limiter.request(bytes).await;
+        let request_timeout = self.request_timeouts.of(&request);
+        let request = &request;
+        self.with_retries(move || async move {
+            let response = timeout(
+                request_timeout,
+                self.network.send_request(request.clone(), peer),
+            )
+            .await??;
+            Ok(response)
+        })
+        .await
+    }
+
+    // Run the attempt until it succeeds, retrying it as per the retry policy of the client,
+    // each attempt taking a slot within the client-wide limit of concurrent requests.
+    async fn with_retries<T, F, Fut>(&self, attempt: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.retry_policy.run(&self.requests_limiter, attempt).await
     }
 
     pub(crate) async fn expect_closest_majority_ok(&self, spend: SpendRequest) -> Result<()> {
@@ -244,7 +594,7 @@ impl Client {
         let mut list_of_futures = vec![];
         for peer in closest_peers {
             let request = Request::Cmd(cmd.clone());
-            let future = Box::pin(self.network.send_request(request, peer));
+            let future = Box::pin(self.send_request(request, peer));
             list_of_futures.push(future);
         }
 
@@ -281,6 +631,83 @@ impl Client {
         )))
     }
 
+    /// Checks whether the dbc of the given id has been spent, i.e. whether a majority
+    /// of its close group hold a spend for it, or a majority answered they hold none.
+    /// Returns an error if the close group could not agree either way, any other error
+    /// answered being no answer at all.
+    pub async fn is_dbc_spent(&self, dbc_id: &DbcId) -> Result<bool> {
+        let address = DbcAddress::from_dbc_id(dbc_id);
+        let request = Request::Query(Query::Spend(SpendQuery::GetDbcSpend(address)));
+        let responses = self.send_to_closest(request).await?;
+
+        let mut spent = 0;
+        let mut unspent = 0;
+        for response in responses {
+            match response {
+                Ok(Response::Query(QueryResponse::GetDbcSpend(Ok(spend))))
+                    if spend.dbc_id() == dbc_id =>
+                {
+                    spent += 1
+                }
+                Ok(Response::Query(QueryResponse::GetDbcSpend(Err(ProtocolError::Transfers(
+                    TransferError::Storage(StorageError::SpendNotFound(_)),
+                ))))) => unspent += 1,
+                other => {
+                    trace!("Unexpected response when checking if {dbc_id:?} is spent: {other:?}")
+                }
+            }
+        }
+
+        if spent >= close_group_majority() {
+            Ok(true)
+        } else if unspent >= close_group_majority() {
+            Ok(false)
+        } else {
+            Err(Error::CouldNotVerifyTransfer(format!(
+                "Not enough close group nodes agreed on the spend status of {dbc_id:?}. \
+                Spent: {spent}, unspent: {unspent}, required: {}.",
+                close_group_majority()
+            )))
+        }
+    }
+
+    /// Retrieves the spend at the given address, i.e. the spend of the dbc whose id hashes
+    /// to it, as held by a majority of its close group.
+    pub async fn get_spend(&self, address: DbcAddress) -> Result<SignedSpend> {
+        let request = Request::Query(Query::Spend(SpendQuery::GetDbcSpend(address)));
+        let responses = self.send_to_closest(request).await?;
+
+        let mut spends = BTreeMap::new();
+        let mut errors = vec![];
+        for response in responses {
+            match response {
+                Ok(Response::Query(QueryResponse::GetDbcSpend(Ok(spend))))
+                    if DbcAddress::from_dbc_id(spend.dbc_id()) == address =>
+                {
+                    let (_, count) = spends.entry(spend.hash()).or_insert((spend, 0));
+                    *count += 1;
+                }
+                Ok(Response::Query(QueryResponse::GetDbcSpend(Err(err)))) => errors.push(err),
+                other => {
+                    trace!("Unexpected response when getting the spend at {address:?}: {other:?}")
+                }
+            }
+        }
+
+        if let Some((spend, _)) = spends
+            .into_values()
+            .find(|(_, count)| *count >= close_group_majority())
+        {
+            return Ok(spend);
+        }
+        if errors.len() >= close_group_majority() {
+            return Err(errors.swap_remove(0).into());
+        }
+        Err(Error::CouldNotVerifyTransfer(format!(
+            "Not enough close group nodes agreed on the spend at {address:?}"
+        )))
+    }
+
     pub(crate) async fn expect_closest_majority_same(&self, dbc_id: &DbcId) -> Result<SignedSpend> {
         let address = DbcAddress::from_dbc_id(dbc_id);
         let network_address = NetworkAddress::from_dbc_address(address);
@@ -296,7 +723,7 @@ impl Client {
         let mut list_of_futures = vec![];
         for peer in closest_peers {
             let request = Request::Query(query.clone());
-            let future = Box::pin(self.network.send_request(request, peer));
+            let future = Box::pin(self.send_request(request, peer));
             list_of_futures.push(future);
         }
 
@@ -354,3 +781,26 @@ impl Client {
         )))
     }
 }
+
+/// Checks the content of the chunk retrieved matches its address, recording the peer which
+/// served it as the one it was retrieved from, or as serving a wrong chunk.
+fn verify_chunk(
+    address: ChunkAddress,
+    chunk: Chunk,
+    holder: Option<PeerId>,
+    provenance: &mut ChunkProvenance,
+) -> Result<Chunk> {
+    if chunk.address() == &address {
+        provenance.peer = holder;
+        return Ok(chunk);
+    }
+    warn!(
+        "Chunk retrieved from {address:?} by {holder:?} has different content address: {:?}",
+        chunk.address()
+    );
+    provenance.wrong_chunk_peers.extend(holder);
+    Err(Error::Chunks(ChunksError::ChunkAddressMismatch {
+        expected: *address.name(),
+        actual: *chunk.name(),
+    }))
+}
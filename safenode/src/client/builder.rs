@@ -0,0 +1,349 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    rate_limit::RateLimit,
+    Client, CONNECTION_TIMEOUT,
+};
+
+use crate::{
+    network::CLOSE_GROUP_SIZE,
+    protocol::messages::{Cmd, Query, Request},
+};
+
+use bls::SecretKey;
+use libp2p::{Multiaddr, PeerId};
+use std::{future::Future, time::Duration};
+use tokio::{sync::Semaphore, time::sleep};
+
+/// The default capacity of the channel the client broadcasts its events through.
+const EVENTS_CAPACITY: usize = 100;
+
+/// Builds a [`Client`], tuning how it connects to the network and sends its requests, e.g.
+/// for an application embedding the client to fail fast, or to be patient with a slow network.
+#[derive(Clone, Debug)]
+pub struct ClientBuilder {
+    pub(super) signer: SecretKey,
+    pub(super) peers: Option<Vec<(PeerId, Multiaddr)>>,
+    pub(super) connection_timeout: Duration,
+    pub(super) peers_to_wait_for: usize,
+    pub(super) request_timeouts: RequestTimeouts,
+    pub(super) retry_policy: RetryPolicy,
+    pub(super) events_capacity: usize,
+    pub(super) local: bool,
+    pub(super) rate_limit: RateLimit,
+    pub(super) encryption_threads: Option<usize>,
+}
+
+impl ClientBuilder {
+    /// A builder of a client signing with the given key, with the defaults of [`Client::new`].
+    pub fn new(signer: SecretKey) -> Self {
+        Self {
+            signer,
+            peers: None,
+            connection_timeout: CONNECTION_TIMEOUT,
+            peers_to_wait_for: CLOSE_GROUP_SIZE + 1,
+            request_timeouts: RequestTimeouts::default(),
+            retry_policy: RetryPolicy::default(),
+            events_capacity: EVENTS_CAPACITY,
+            local: true,
+            rate_limit: RateLimit::default(),
+            encryption_threads: None,
+        }
+    }
+
+    /// Dial the given peers to get connected to the network, rather than only discovering
+    /// peers on the local network.
+    pub fn with_peers(mut self, peers: Vec<(PeerId, Multiaddr)>) -> Self {
+        self.peers = Some(peers);
+        self
+    }
+
+    /// Fail to build the client if it doesn't get connected to enough peers within the given
+    /// time. Defaults to [`CONNECTION_TIMEOUT`].
+    pub fn with_connection_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_timeout = timeout;
+        self
+    }
+
+    /// Wait for the given number of peers to be added to the routing table of the client before
+    /// it's built. Defaults to one more than the size of a close group, i.e. enough to store data,
+    /// whereas a smaller number gets a client connected to a small network, e.g. to read data only.
+    pub fn with_peers_to_wait_for(mut self, peers: usize) -> Self {
+        self.peers_to_wait_for = peers;
+        self
+    }
+
+    /// Set how long each kind of request is waited a response for.
+    pub fn with_request_timeouts(mut self, timeouts: RequestTimeouts) -> Self {
+        self.request_timeouts = timeouts;
+        self
+    }
+
+    /// Set how the requests which fail to be sent or time out are retried.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Set how many events the client keeps for each of its receivers which is lagging behind,
+    /// before dropping the oldest. Defaults to 100.
+    pub fn with_events_capacity(mut self, capacity: usize) -> Self {
+        self.events_capacity = capacity;
+        self
+    }
+
+    /// Whether the network joined is a local one, its peers being discovered with mDNS,
+    /// or a public one, only reached through the peers given. Defaults to `true`.
+    pub fn local(mut self, local: bool) -> Self {
+        self.local = local;
+        self
+    }
+
+    /// Cap the rate of the requests the client sends, across all its operations and its clones,
+    /// e.g. to remain polite to a small network during bulk operations. Defaults to no cap.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Set the number of threads self-encrypting the files uploaded, shared by all the
+    /// uploads of the client. Defaults to one per core of the machine.
+    pub fn with_encryption_threads(mut self, threads: usize) -> Self {
+        self.encryption_threads = Some(threads);
+        self
+    }
+
+    /// Build the client, returning once it's connected to the network.
+    ///
+    /// If not enough peers are added to the client's routing table within the connection
+    /// timeout, or if none of the peers provided to join a public network could be dialed,
+    /// an [`Error::ConnectionTimeout`](super::Error::ConnectionTimeout) is returned, reporting
+    /// which peers were dialed and why dialing them failed.
+    pub async fn build(self) -> Result<Client> {
+        Client::connect(self).await
+    }
+}
+
+/// How long each kind of request sent by a client is waited a response for, before it's
+/// retried as per the [`RetryPolicy`], or failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestTimeouts {
+    /// Storing a chunk.
+    pub store_chunk: Duration,
+    /// Retrieving a chunk, which may take a Kademlia query through the network.
+    pub get_chunk: Duration,
+    /// Creating, editing or retrieving a register.
+    pub register: Duration,
+    /// Spending a DBC or retrieving a spend.
+    pub spend: Duration,
+    /// Getting the cost of storing data.
+    pub store_cost: Duration,
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        Self {
+            store_chunk: Duration::from_secs(10),
+            get_chunk: Duration::from_secs(60),
+            register: Duration::from_secs(10),
+            spend: Duration::from_secs(10),
+            store_cost: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RequestTimeouts {
+    /// The timeout of the given request.
+    pub(super) fn of(&self, request: &Request) -> Duration {
+        match request {
+            Request::Cmd(Cmd::StoreChunk(_)) => self.store_chunk,
+            Request::Cmd(Cmd::Register(_)) | Request::Query(Query::Register(_)) => self.register,
+            Request::Cmd(Cmd::SpendDbc { .. }) | Request::Query(Query::Spend(_)) => self.spend,
+            Request::Query(Query::GetChunk(_)) => self.get_chunk,
+            Request::Query(Query::GetStoreCost(_)) => self.store_cost,
+        }
+    }
+
+    /// The longest of the timeouts, which the network layer must not time requests out before.
+    pub(super) fn longest(&self) -> Duration {
+        [
+            self.store_chunk,
+            self.get_chunk,
+            self.register,
+            self.spend,
+            self.store_cost,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or_default()
+    }
+}
+
+/// How a client retries the requests which fail to be sent or time out, waiting longer before
+/// each retry. Requests are not retried by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of times a request is retried, after its first attempt.
+    pub retries: usize,
+    /// The wait before the first retry, doubled before each of the next ones.
+    pub backoff: Duration,
+    /// The longest wait before a retry.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The wait before the given retry, counted from 1.
+    pub(super) fn backoff(&self, retry: usize) -> Duration {
+        let doublings = retry.saturating_sub(1).min(u32::MAX as usize) as u32;
+        self.backoff
+            .checked_mul(2u32.saturating_pow(doublings))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// Run the attempt until it succeeds or is out of retries. Each attempt waits for a permit
+    /// of the given limiter, which isn't held while waiting before the next retry.
+    pub(super) async fn run<T, F, Fut>(&self, limiter: &Semaphore, mut attempt: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut retry = 0;
+        loop {
+            let result = {
+                let _permit = limiter
+                    .acquire()
+                    .await
+                    .map_err(|_| Error::RequestsLimiterClosed)?;
+                attempt().await
+            };
+            match result {
+                Err(err) if retry < self.retries => {
+                    retry += 1;
+                    let backoff = self.backoff(retry);
+                    debug!("Retrying in {backoff:?}, attempt failed with: {err}");
+                    sleep(backoff).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{storage::ChunkAddress, NetworkAddress};
+
+    use futures::future::join_all;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use xor_name::XorName;
+
+    #[test]
+    fn backoff_doubles_up_to_its_max() {
+        let policy = RetryPolicy {
+            retries: 10,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(3),
+        };
+        let backoffs: Vec<_> = (1..=5).map(|retry| policy.backoff(retry)).collect();
+        assert_eq!(
+            backoffs,
+            [
+                Duration::from_millis(500),
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(3),
+                Duration::from_secs(3),
+            ]
+        );
+        assert_eq!(policy.backoff(usize::MAX), Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn attempts_in_flight_are_capped_by_the_limiter() {
+        let limiter = Semaphore::new(2);
+        let policy = RetryPolicy {
+            retries: 2,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+        };
+        let in_flight = &AtomicUsize::new(0);
+        let most_in_flight = &AtomicUsize::new(0);
+        let attempts = &AtomicUsize::new(0);
+        let requests = (0..10).map(|_| {
+            policy.run(&limiter, move || async move {
+                let now_in_flight = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                let _ = most_in_flight.fetch_max(now_in_flight, Ordering::SeqCst);
+                let _ = attempts.fetch_add(1, Ordering::SeqCst);
+                sleep(Duration::from_millis(20)).await;
+                let _ = in_flight.fetch_sub(1, Ordering::SeqCst);
+                Err::<(), _>(Error::UnexpectedResponses)
+            })
+        });
+        let results = join_all(requests).await;
+
+        assert!(results.iter().all(|result| result.is_err()));
+        assert_eq!(attempts.load(Ordering::SeqCst), 30);
+        assert_eq!(most_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn requests_time_out_as_per_their_kind() {
+        let timeouts = RequestTimeouts {
+            get_chunk: Duration::from_secs(30),
+            spend: Duration::from_secs(45),
+            ..Default::default()
+        };
+        let address = ChunkAddress::new(XorName::random(&mut rand::thread_rng()));
+        let get_chunk = Request::Query(Query::GetChunk(address));
+        assert_eq!(timeouts.of(&get_chunk), Duration::from_secs(30));
+        let get_store_cost = Request::Query(Query::GetStoreCost(
+            NetworkAddress::from_chunk_address(address),
+        ));
+        assert_eq!(timeouts.of(&get_store_cost), Duration::from_secs(10));
+        assert_eq!(timeouts.longest(), Duration::from_secs(45));
+    }
+
+    #[tokio::test]
+    async fn build_fails_without_enough_peers_within_the_connection_timeout() {
+        // A public network is only reached through the peers given, so none is ever found.
+        let result = ClientBuilder::new(SecretKey::random())
+            .local(false)
+            .with_connection_timeout(Duration::from_millis(500))
+            .build()
+            .await;
+        match result {
+            Err(Error::ConnectionTimeout {
+                found,
+                required,
+                timeout,
+                dial_results,
+            }) => {
+                assert_eq!(found, 0);
+                assert_eq!(required, CLOSE_GROUP_SIZE + 1);
+                assert_eq!(timeout, Duration::from_millis(500));
+                assert!(dial_results.is_empty());
+            }
+            Err(err) => panic!("Unexpected error: {err}"),
+            Ok(_) => panic!("The client was built without any peer"),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{encrypt_large, encrypt_segment, encrypt_segmented, Error, Result};
+
+use crate::protocol::storage::Chunk;
+
+use bytes::Bytes;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use self_encryption::DataMap;
+use std::{sync::Arc, thread};
+use tokio::sync::{oneshot, Semaphore};
+use xor_name::XorName;
+
+/// Maximum number of files being self-encrypted at the same time. Each of them is held in
+/// memory along with its encrypted chunks until encrypted, so this bounds the memory used
+/// when uploading many large files at once.
+const MAX_CONCURRENT_ENCRYPTIONS: usize = 2;
+
+/// Self-encrypts files on a dedicated pool of threads, off the async runtime, the chunks of
+/// each file being encrypted in parallel across all the threads of the pool.
+#[derive(Clone, Debug)]
+pub(crate) struct Encryptor {
+    pool: Arc<ThreadPool>,
+    permits: Arc<Semaphore>,
+}
+
+impl Encryptor {
+    /// Builds a pool of the given number of threads, or one per core of the machine if `None`.
+    pub(crate) fn new(threads: Option<usize>) -> Result<Self> {
+        let threads = threads.unwrap_or_else(|| {
+            thread::available_parallelism()
+                .map(usize::from)
+                .unwrap_or(1)
+        });
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads.max(1))
+            .thread_name(|index| format!("self-encryption-{index}"))
+            .build()
+            .map_err(|error| Error::EncryptionPool(error.to_string()))?;
+        Ok(Self {
+            pool: Arc::new(pool),
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT_ENCRYPTIONS)),
+        })
+    }
+
+    /// Self-encrypts the data on the pool, once fewer than [`MAX_CONCURRENT_ENCRYPTIONS`]
+    /// files are being encrypted, returning the address of its data map and all its chunks.
+    pub(crate) async fn encrypt(&self, data: Bytes) -> Result<(XorName, Vec<Chunk>)> {
+        self.run(move || encrypt_large(data)).await
+    }
+
+    /// Self-encrypts a segment of a file on the pool, as [`Encryptor::encrypt`] does,
+    /// returning the data map of the segment and its chunks.
+    pub(crate) async fn encrypt_segment(&self, data: Bytes) -> Result<(DataMap, Vec<Chunk>)> {
+        self.run(move || encrypt_segment(data)).await
+    }
+
+    /// Self-encrypts the data on the pool in segments, as it is when uploaded from a reader,
+    /// returning the address of its data map and all its chunks.
+    pub(crate) async fn encrypt_segmented(&self, data: Bytes) -> Result<(XorName, Vec<Chunk>)> {
+        self.run(move || encrypt_segmented(data)).await
+    }
+
+    // Runs an encryption on the pool, once a permit to encrypt is acquired.
+    async fn run<T: Send + 'static>(
+        &self,
+        encryption: impl FnOnce() -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|_| Error::EncryptionAborted)?;
+        let (sender, receiver) = oneshot::channel();
+        let pool = self.pool.clone();
+        self.pool.spawn(move || {
+            // Installed in the pool, for the parallel iterators of the encryption to run on it.
+            let _ = sender.send(pool.install(encryption));
+        });
+        receiver.await.map_err(|_| Error::EncryptionAborted)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+    use self_encryption::MIN_ENCRYPTABLE_BYTES;
+
+    #[tokio::test]
+    async fn encryption_on_the_pool_matches_the_one_in_place() -> eyre::Result<()> {
+        let mut bytes = vec![0u8; 10 * MIN_ENCRYPTABLE_BYTES];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let bytes = Bytes::from(bytes);
+
+        let encryptor = Encryptor::new(Some(3))?;
+        let (address, chunks) = encryptor.encrypt(bytes.clone()).await?;
+        let (expected_address, expected_chunks) = encrypt_large(bytes)?;
+
+        assert_eq!(address, expected_address);
+        assert_eq!(chunks, expected_chunks);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn segment_encryption_on_the_pool_matches_the_one_in_place() -> eyre::Result<()> {
+        let mut bytes = vec![0u8; 10 * MIN_ENCRYPTABLE_BYTES];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let bytes = Bytes::from(bytes);
+
+        let encryptor = Encryptor::new(Some(1))?;
+        let (data_map, chunks) = encryptor.encrypt_segment(bytes.clone()).await?;
+        let (expected_data_map, expected_chunks) = encrypt_segment(bytes)?;
+
+        assert_eq!(data_map, expected_data_map);
+        assert_eq!(chunks, expected_chunks);
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@
 
 use std::io;
 use thiserror::Error;
+use xor_name::XorName;
 
 pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -24,6 +25,12 @@ pub enum Error {
     #[error(transparent)]
     Serialisation(#[from] Box<bincode::ErrorKind>),
 
+    #[error("Could not build the thread pool for self-encryption: {0}")]
+    EncryptionPool(String),
+
+    #[error("The self-encryption was aborted before it completed.")]
+    EncryptionAborted,
+
     #[error("Cannot store empty file.")]
     EmptyFileProvided,
 
@@ -63,6 +70,14 @@ pub enum Error {
         retrieved: usize,
     },
 
+    #[error("Chunk content does not match its address, expected {expected:?}, got {actual:?}.")]
+    ChunkAddressMismatch {
+        /// The address the chunk was requested from
+        expected: XorName,
+        /// The address derived from the chunk's content
+        actual: XorName,
+    },
+
     #[error("Not all data was chunked, expected {expected}, but we have {chunked}.)")]
     NotAllDataWasChunked {
         /// Number of Chunks expected to be generated
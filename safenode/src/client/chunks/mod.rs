@@ -6,11 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod encryptor;
 mod error;
 mod pac_man;
 
-pub(crate) use self::error::{Error, Result};
-pub(crate) use pac_man::{encrypt_large, to_chunk, DataMapLevel};
+pub(crate) use self::{
+    encryptor::Encryptor,
+    error::{Error, Result},
+};
+pub(crate) use pac_man::{
+    encrypt_large, encrypt_segment, encrypt_segmented, pack_segments, split_segments, to_chunk,
+    DataMapLevel, SEGMENT_SIZE,
+};
 
 use bytes::Bytes;
 use self_encryption::MIN_ENCRYPTABLE_BYTES;
@@ -10,7 +10,7 @@ use super::{Error, Result};
 
 use crate::protocol::storage::Chunk;
 
-use self_encryption::{DataMap, EncryptedChunk, MAX_CHUNK_SIZE};
+use self_encryption::{DataMap, EncryptedChunk, MAX_CHUNK_SIZE, MIN_ENCRYPTABLE_BYTES};
 
 use bincode::serialize;
 use bytes::Bytes;
@@ -27,8 +27,16 @@ pub(crate) enum DataMapLevel {
     // resulting from chunking up a previous level data map.
     // This happens when that previous level data map was too big to fit in a chunk itself.
     Additional(DataMap),
+    // Holds the data maps of the consecutive segments the source data was split into,
+    // each of them self-encrypted on its own, so that the source data is never held in
+    // memory as a whole when it's encrypted.
+    Segments(Vec<DataMap>),
 }
 
+/// The size of the segments data is split into when encrypted in bounded memory. The last
+/// segment is larger, should what's left after it be too small to be self-encrypted.
+pub(crate) const SEGMENT_SIZE: usize = 16 * 1024 * 1024;
+
 #[allow(unused)]
 pub(crate) fn encrypt_from_path(path: &Path) -> Result<(XorName, Vec<Chunk>)> {
     let (data_map, encrypted_chunks) = encrypt_file(path)?;
@@ -40,6 +48,58 @@ pub(crate) fn encrypt_large(data: Bytes) -> Result<(XorName, Vec<Chunk>)> {
     pack(data_map, encrypted_chunks)
 }
 
+/// Self-encrypts a segment of some data, returning its data map and its encrypted chunks,
+/// for the data maps of all the segments to be packed with [`pack_segments`].
+pub(crate) fn encrypt_segment(data: Bytes) -> Result<(DataMap, Vec<Chunk>)> {
+    let (data_map, encrypted_chunks) = encrypt_data(data)?;
+    let chunks = encrypted_chunks
+        .into_par_iter()
+        .map(|c| to_chunk(c.content))
+        .collect();
+    Ok((data_map, chunks))
+}
+
+/// Self-encrypts the data in segments of [`SEGMENT_SIZE`], as it is when uploaded in bounded
+/// memory, returning the top-most chunk address and all the chunks. Data too small to be split
+/// in segments, see [`split_segments`], is encrypted as a whole, as [`encrypt_large`] does.
+pub(crate) fn encrypt_segmented(data: Bytes) -> Result<(XorName, Vec<Chunk>)> {
+    if data.len() < SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES {
+        return encrypt_large(data);
+    }
+    let mut data_maps = vec![];
+    let mut chunks = vec![];
+    for segment in split_segments(data) {
+        let (data_map, segment_chunks) = encrypt_segment(segment)?;
+        data_maps.push(data_map);
+        chunks.extend(segment_chunks);
+    }
+    let (address, data_map_chunks) = pack_segments(data_maps)?;
+    chunks.extend(data_map_chunks);
+    Ok((address, chunks))
+}
+
+/// Splits the data in segments of [`SEGMENT_SIZE`], the last one being larger should what's
+/// left after it be too small to be self-encrypted. Data smaller than
+/// `SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES` is thus a single segment.
+pub(crate) fn split_segments(mut data: Bytes) -> Vec<Bytes> {
+    let mut segments = vec![];
+    while !data.is_empty() {
+        let len = if data.len() < SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES {
+            data.len()
+        } else {
+            SEGMENT_SIZE
+        };
+        segments.push(data.split_to(len));
+    }
+    segments
+}
+
+/// Packs the data maps of the segments some data was encrypted in, returning the top-most
+/// chunk address and the chunks the data maps are packed in, the top-most one being the last.
+pub(crate) fn pack_segments(data_maps: Vec<DataMap>) -> Result<(XorName, Vec<Chunk>)> {
+    pack_level(DataMapLevel::Segments(data_maps), vec![])
+}
+
 /// Returns the top-most chunk address through which the entire
 /// data tree can be accessed, and all the other encrypted chunks.
 /// If encryption is provided, the additional `DataMapLevel` chunks are encrypted with it.
@@ -48,14 +108,21 @@ pub(crate) fn pack(
     data_map: DataMap,
     encrypted_chunks: Vec<EncryptedChunk>,
 ) -> Result<(XorName, Vec<Chunk>)> {
-    // Produces a chunk out of the first `DataMap`, which is validated for its size.
+    pack_level(DataMapLevel::First(data_map), encrypted_chunks)
+}
+
+fn pack_level(
+    level: DataMapLevel,
+    encrypted_chunks: Vec<EncryptedChunk>,
+) -> Result<(XorName, Vec<Chunk>)> {
+    // Produces a chunk out of the first level, which is validated for its size.
     // If the chunk is too big, it is self-encrypted and the resulting (additional level) `DataMap` is put into a chunk.
     // The above step is repeated as many times as required until the chunk size is valid.
     // In other words: If the chunk content is too big, it will be
     // self encrypted into additional chunks, and now we have a new `DataMap`
     // which points to all of those additional chunks.. and so on.
     let mut chunks = vec![];
-    let mut chunk_content = pack_data_map(DataMapLevel::First(data_map))?;
+    let mut chunk_content = pack_data_map(level)?;
 
     let (address, additional_chunks) = loop {
         let chunk = to_chunk(chunk_content);
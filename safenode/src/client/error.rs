@@ -10,7 +10,11 @@ pub(super) type Result<T, E = Error> = std::result::Result<T, E>;
 
 use crate::protocol::storage::registers::{Entry, EntryHash};
 
-use std::collections::BTreeSet;
+use libp2p::PeerId;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
 use thiserror::Error;
 
 /// Internal error.
@@ -29,6 +33,24 @@ pub enum Error {
     #[error("ResponseTimeout.")]
     ResponseTimeout(#[from] tokio::time::error::Elapsed),
 
+    /// The client could not get connected to enough peers, within its connection timeout,
+    /// or at all, none of the peers provided to join a public network could be dialed.
+    #[error(
+        "Could not connect to the network: only {found} of the {required} peers required were \
+        added to the routing table within {timeout:?}. Dialed peers: {dial_results:?}"
+    )]
+    ConnectionTimeout {
+        found: usize,
+        required: usize,
+        timeout: Duration,
+        /// The outcome of dialing each of the peers provided at startup.
+        dial_results: BTreeMap<PeerId, String>,
+    },
+
+    /// The semaphore bounding concurrent requests was closed.
+    #[error("The limiter of concurrent requests has been closed")]
+    RequestsLimiterClosed,
+
     /// Unexpected responses.
     #[error("Unexpected responses")]
     UnexpectedResponses,
@@ -37,6 +59,13 @@ pub enum Error {
     #[error("Failed to verify transfer validity in the network {0}")]
     CouldNotVerifyTransfer(String),
 
+    /// A general error when obtaining the store cost from the network.
+    #[error("Failed to get the store cost from the network {0}")]
+    CouldNotGetStoreCost(String),
+
+    #[error("Wallet error {0}.")]
+    Wallet(#[from] crate::domain::wallet::Error),
+
     #[error("Chunks error {0}.")]
     Chunks(#[from] super::chunks::Error),
 
@@ -48,4 +77,31 @@ pub enum Error {
         Entries hashes of branches are: {0:?}"
     )]
     ContentBranchDetected(BTreeSet<(EntryHash, Entry)>),
+
+    /// The name is not one that can be registered.
+    #[error(
+        "Invalid name '{0}': only lowercase letters, digits and '-' not at either end are \
+        allowed, up to 63 characters"
+    )]
+    InvalidName(String),
+
+    /// The name was registered by another owner.
+    #[error("The name '{0}' is owned by someone else")]
+    NameTaken(String),
+
+    /// The name was never registered.
+    #[error("The name '{0}' is not registered")]
+    NameNotFound(String),
+
+    /// The mapping of a name could not be encoded.
+    #[error("Invalid name entry: {0}")]
+    InvalidNameEntry(String),
+
+    /// The file doesn't start with the header of a FilesContainer.
+    #[error("The file is not a FilesContainer")]
+    NotAFilesContainer,
+
+    /// The FilesContainer was stored in a format this version doesn't support.
+    #[error("Unsupported FilesContainer version {0}, expected {1}")]
+    UnsupportedFilesContainerVersion(u8, u8),
 }
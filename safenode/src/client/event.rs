@@ -8,31 +8,95 @@
 
 use super::error::Result;
 
+use crate::protocol::storage::ChunkAddress;
+
+use libp2p::PeerId;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+};
 use tokio::sync::broadcast;
 
-// Channel where events will be broadcasted by the client.
-#[derive(Clone, Debug)]
-pub(super) struct ClientEventsChannel(broadcast::Sender<ClientEvent>);
+/// A callback registered to be called with each event broadcast by the client.
+type EventCallback = Box<dyn FnMut(ClientEvent) + Send>;
+
+/// The id of a callback registered with [`Client::on_event`](super::Client::on_event),
+/// to unregister it with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EventCallbackId(u64);
+
+// Channel where events will be broadcasted by the client, and callbacks called with them.
+#[derive(Clone)]
+pub(super) struct ClientEventsChannel {
+    sender: broadcast::Sender<ClientEvent>,
+    callbacks: Arc<Mutex<EventCallbacks>>,
+}
+
+#[derive(Default)]
+struct EventCallbacks {
+    next_id: u64,
+    callbacks: BTreeMap<EventCallbackId, EventCallback>,
+}
 
 impl Default for ClientEventsChannel {
     fn default() -> Self {
-        Self(broadcast::channel(100).0)
+        Self::with_capacity(100)
+    }
+}
+
+impl std::fmt::Debug for ClientEventsChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientEventsChannel")
+            .field("sender", &self.sender)
+            .finish_non_exhaustive()
     }
 }
 
 impl ClientEventsChannel {
+    /// A channel keeping up to `capacity` events for each of its lagging receivers.
+    pub(super) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            sender: broadcast::channel(capacity).0,
+            callbacks: Arc::default(),
+        }
+    }
+
     /// Returns a new receiver to listen to the channel.
     /// Multiple receivers can be actively listening.
     pub(super) fn subscribe(&self) -> ClientEventsReceiver {
-        ClientEventsReceiver(self.0.subscribe())
+        ClientEventsReceiver(self.sender.subscribe())
+    }
+
+    /// Registers the callback to be called with every event broadcast from now on.
+    pub(super) fn add_callback(&self, callback: EventCallback) -> EventCallbackId {
+        let mut callbacks = self.lock_callbacks();
+        let id = EventCallbackId(callbacks.next_id);
+        callbacks.next_id += 1;
+        let _ = callbacks.callbacks.insert(id, callback);
+        id
+    }
+
+    /// Unregisters the callback, returning whether it was registered.
+    pub(super) fn remove_callback(&self, id: EventCallbackId) -> bool {
+        self.lock_callbacks().callbacks.remove(&id).is_some()
     }
 
     // Broadcast a new event, meant to be a helper only used by the client's internals.
     pub(crate) fn broadcast(&self, event: ClientEvent) {
-        if let Err(err) = self.0.send(event.clone()) {
+        for callback in self.lock_callbacks().callbacks.values_mut() {
+            callback(event.clone());
+        }
+        if let Err(err) = self.sender.send(event.clone()) {
             trace!("Error occurred when trying to broadcast a client event ({event:?}): {err}");
         }
     }
+
+    // A callback which panicked doesn't prevent the others from being called afterwards.
+    fn lock_callbacks(&self) -> std::sync::MutexGuard<'_, EventCallbacks> {
+        self.callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 /// Type of events broadcasted by the client to the public API.
@@ -40,6 +104,35 @@ impl ClientEventsChannel {
 pub enum ClientEvent {
     /// The client has been connected to the network
     ConnectedToNetwork,
+    /// A file is about to be uploaded.
+    UploadStarted {
+        /// The number of chunks the file is stored as.
+        chunks: usize,
+        /// The total size of the chunks.
+        bytes: usize,
+    },
+    /// A chunk of a file being uploaded has been stored on the network.
+    ChunkStored {
+        /// The address of the chunk.
+        address: ChunkAddress,
+        /// The size of the chunk.
+        size: usize,
+    },
+    /// Some chunks of a file are about to be retrieved.
+    DownloadStarted {
+        /// The number of chunks to retrieve.
+        chunks: usize,
+    },
+    /// A chunk of a file being downloaded has been retrieved from the network.
+    ChunkRetrieved {
+        /// The address of the chunk.
+        address: ChunkAddress,
+        /// The size of the chunk.
+        size: usize,
+    },
+    /// The connection to a node behind a NAT, relayed until then, was upgraded to a direct one
+    /// by hole punching.
+    DirectConnectionUpgraded(PeerId),
 }
 
 /// Receiver Channel where users of the public API can listen to events broadcasted by the client.
@@ -53,3 +146,33 @@ impl ClientEventsReceiver {
         Ok(event)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn callbacks_are_called_with_events_until_removed() {
+        let channel = ClientEventsChannel::default();
+        let events = Arc::new(Mutex::new(vec![]));
+        let id = channel.add_callback(Box::new({
+            let events = events.clone();
+            move |event| {
+                if let Ok(mut events) = events.lock() {
+                    events.push(event);
+                }
+            }
+        }));
+
+        channel.broadcast(ClientEvent::DownloadStarted { chunks: 2 });
+        assert!(channel.remove_callback(id));
+        channel.broadcast(ClientEvent::ConnectedToNetwork);
+        assert!(!channel.remove_callback(id));
+
+        let events = events.lock().unwrap_or_else(PoisonError::into_inner);
+        assert!(matches!(
+            events.as_slice(),
+            [ClientEvent::DownloadStarted { chunks: 2 }]
+        ));
+    }
+}
@@ -7,51 +7,156 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    chunks::{to_chunk, DataMapLevel, Error, LargeFile, SmallFile},
+    chunks::{pack_segments, to_chunk, DataMapLevel, Error, LargeFile, SmallFile, SEGMENT_SIZE},
     error::Result,
-    Client,
+    operation,
+    provenance::{ChunkProvenance, DownloadTrace},
+    Client, ClientEvent,
 };
 
-use crate::protocol::storage::{Chunk, ChunkAddress};
+use crate::protocol::{
+    messages::CorrelationId,
+    storage::{Chunk, ChunkAddress},
+};
 
-use self_encryption::{self, ChunkInfo, DataMap, EncryptedChunk};
+use self_encryption::{self, ChunkInfo, DataMap, EncryptedChunk, MIN_ENCRYPTABLE_BYTES};
 
 use bincode::deserialize;
-use bytes::Bytes;
-use futures::future::join_all;
+use bytes::{Bytes, BytesMut};
+use futures::{
+    future::{self, join_all},
+    stream::{self, BoxStream, StreamExt, TryStreamExt},
+};
 use itertools::Itertools;
-use tokio::task;
-use tracing::trace;
+use std::collections::BTreeSet;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    task,
+};
+use tracing::{trace, Instrument};
 use xor_name::XorName;
 
 // Maximum number of concurrent chunks to be uploaded/retrieved for a file
 const CHUNKS_BATCH_MAX_SIZE: usize = 5;
 
+/// Report on the retrievability of each of the chunks a file is made of.
+#[derive(Debug, Clone)]
+pub struct FileVerificationReport {
+    /// The address of each chunk, along with the error found when retrieving it, if any.
+    pub chunks: Vec<(ChunkAddress, Option<String>)>,
+}
+
+impl FileVerificationReport {
+    /// Returns true if all the chunks of the file were retrieved successfully.
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(|(_, error)| error.is_none())
+    }
+
+    /// Returns the addresses of the chunks which could not be retrieved.
+    pub fn missing_chunks(&self) -> Vec<ChunkAddress> {
+        self.chunks
+            .iter()
+            .filter(|(_, error)| error.is_some())
+            .map(|(address, _)| *address)
+            .collect()
+    }
+}
+
+/// What a chunk holds, as found by [`Files::inspect_chunk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkContent {
+    /// The data map of a file, i.e. the address of the file is that of the chunk.
+    DataMap {
+        /// The number of chunks the file content is made of.
+        chunks: usize,
+        /// The size of the file in bytes.
+        file_size: usize,
+    },
+    /// Any other content: a file small enough to fit in a single chunk, or
+    /// one of the encrypted chunks a larger file is made of.
+    Raw {
+        /// The size of the chunk content in bytes.
+        size: usize,
+    },
+}
+
 /// File APIs.
 pub struct Files {
     client: Client,
+    // Number of chunks uploaded in each batch, the next batch starting once the previous one is done.
+    batch_size: usize,
+    // Maximum number of chunks being uploaded at the same time within a batch.
+    max_concurrent_chunks: usize,
+    // Where the chunks retrieved are recorded from, when tracing a download.
+    trace: Option<DownloadTrace>,
 }
 
 impl Files {
     /// Create file apis instance.
     pub fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            batch_size: CHUNKS_BATCH_MAX_SIZE,
+            max_concurrent_chunks: CHUNKS_BATCH_MAX_SIZE,
+            trace: None,
+        }
+    }
+
+    /// Set the number of chunks of a file uploaded in each batch, the next batch
+    /// starting once all the chunks of the previous one were stored. Defaults to 5.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set the maximum number of chunks being uploaded at the same time within a batch.
+    /// Defaults to 5.
+    pub fn with_max_concurrent_chunks(mut self, max_concurrent_chunks: usize) -> Self {
+        self.max_concurrent_chunks = max_concurrent_chunks.max(1);
+        self
     }
 
     #[instrument(skip(self), level = "debug")]
     /// Reads [`Bytes`] from the network, whose contents are contained within one or more chunks.
     pub async fn read_bytes(&self, address: ChunkAddress) -> Result<Bytes> {
-        let chunk = self.client.get_chunk(address).await?;
+        self.client
+            .events_channel
+            .broadcast(ClientEvent::DownloadStarted { chunks: 1 });
+        let chunk = self.download_chunk(address).await?;
+        self.client
+            .events_channel
+            .broadcast(ClientEvent::ChunkRetrieved {
+                address,
+                size: chunk.value().len(),
+            });
 
         // first try to deserialize a LargeFile, if it works, we go and seek it
-        if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
-            self.read_all(data_map).await
+        if let Ok(segments) = self.unpack_chunk(chunk.clone()).await {
+            self.read_segments(segments).await
         } else {
             // if an error occurs, we assume it's a SmallFile
             Ok(chunk.value().clone())
         }
     }
 
+    /// Reads [`Bytes`] from the network like [`Files::read_bytes`], along with the provenance
+    /// of each of the chunks retrieved, i.e. which peer served it, how long it took, and how
+    /// many times it was retried, whether the download succeeded or not.
+    pub async fn read_bytes_with_provenance(
+        &self,
+        address: ChunkAddress,
+    ) -> (Result<Bytes>, Vec<ChunkProvenance>) {
+        let trace = DownloadTrace::default();
+        let traced = Self {
+            client: self.client.clone(),
+            batch_size: self.batch_size,
+            max_concurrent_chunks: self.max_concurrent_chunks,
+            trace: Some(trace.clone()),
+        };
+        let result = traced.read_bytes(address).await;
+        (result, trace.take())
+    }
+
     /// Read bytes from the network. The contents are spread across
     /// multiple chunks in the network. This function invokes the self-encryptor and returns
     /// the data that was initially stored.
@@ -71,12 +176,12 @@ impl Files {
         Self: Sized,
     {
         trace!("Reading {length} bytes at: {address:?}, starting from position: {position}");
-        let chunk = self.client.get_chunk(address).await?;
+        let chunk = self.download_chunk(address).await?;
 
         // First try to deserialize a LargeFile, if it works, we go and seek it.
         // If an error occurs, we consider it to be a SmallFile.
-        if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
-            return self.seek(data_map, position, length).await;
+        if let Ok(segments) = self.unpack_chunk(chunk.clone()).await {
+            return self.seek_segments(&segments, position, length).await;
         }
 
         // The error above is ignored to avoid leaking the storage format detail of SmallFiles and LargeFiles.
@@ -90,11 +195,60 @@ impl Files {
         Ok(bytes)
     }
 
+    /// Streams the contents of the file at the given address, fetching and decrypting
+    /// up to `CHUNKS_BATCH_MAX_SIZE` chunks at a time, so the whole file is never held in memory.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn read_stream(&self, address: ChunkAddress) -> Result<BoxStream<'_, Result<Bytes>>> {
+        let chunk = self.download_chunk(address).await?;
+
+        let segments = match self.unpack_chunk(chunk.clone()).await {
+            Ok(segments) => segments,
+            // if an error occurs, we assume it's a SmallFile
+            Err(_) => return Ok(stream::once(async move { Ok(chunk.value().clone()) }).boxed()),
+        };
+
+        // the position and length of the content held by each batch of chunks of each segment
+        let mut position = 0;
+        let mut ranges = vec![];
+        for data_map in &segments {
+            for batch in data_map.infos().chunks(CHUNKS_BATCH_MAX_SIZE) {
+                let length: usize = batch.iter().map(|info| info.src_size).sum();
+                ranges.push((position, length));
+                position += length;
+            }
+        }
+
+        Ok(stream::iter(ranges)
+            .then(move |(position, length)| {
+                let segments = segments.clone();
+                async move { self.seek_segments(&segments, position, length).await }
+            })
+            .boxed())
+    }
+
     /// Directly writes [`Bytes`] to the network in the
     /// form of immutable chunks, without any batching.
     #[instrument(skip(self, bytes), level = "debug")]
     pub async fn upload(&self, bytes: Bytes) -> Result<ChunkAddress> {
-        self.upload_bytes(bytes, false).await
+        operation("upload", self.upload_bytes(bytes, false)).await
+    }
+
+    /// Writes what's read from the reader until its end to the network as [`upload`] does,
+    /// e.g. the output of another process piped into stdin, in bounded memory whatever its size.
+    ///
+    /// What's read is self-encrypted and stored in segments of 16 MiB, with no more than two
+    /// segments held in memory at a time, and nothing written to disk. Content smaller than a
+    /// segment plus the minimum size of self-encrypted data, i.e. too small to be split in two
+    /// segments, is stored as [`upload`] stores it, whereas larger content gets a different
+    /// address than it would with [`upload`], as each of its segments is encrypted on its own.
+    ///
+    /// [`upload`]: Self::upload
+    #[instrument(skip_all, level = "debug")]
+    pub async fn upload_from_reader<R: AsyncRead + Unpin>(
+        &self,
+        reader: R,
+    ) -> Result<ChunkAddress> {
+        operation("upload_from_reader", self.upload_streaming(reader)).await
     }
 
     /// Directly writes [`Bytes`] to the network in the
@@ -103,13 +257,179 @@ impl Files {
     /// It does this via running `read_bytes` with each chunk with `query_timeout` set.
     #[instrument(skip_all, level = "trace")]
     pub async fn upload_and_verify(&self, bytes: Bytes) -> Result<ChunkAddress> {
-        self.upload_bytes(bytes, true).await
+        operation("upload_and_verify", self.upload_bytes(bytes, true)).await
+    }
+
+    /// Verifies all the chunks of the file at the given address can be retrieved from the network,
+    /// and that their contents match their addresses, returning a report for each of them.
+    /// The data map chunk is the first one in the report, followed by the chunks of the additional
+    /// data map levels, if any, and then by the chunks of the content. Should any chunk of a data
+    /// map level be missing, the chunks it refers to can't be known, and are not reported.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn verify(&self, address: ChunkAddress) -> Result<FileVerificationReport> {
+        let mut chunks = vec![];
+        let mut chunk = match self.get_verified_chunk(address).await {
+            Ok(chunk) => {
+                chunks.push((address, None));
+                chunk
+            }
+            Err(error) => {
+                chunks.push((address, Some(error.to_string())));
+                return Ok(FileVerificationReport { chunks });
+            }
+        };
+
+        let segments = loop {
+            let level = match deserialize::<DataMapLevel>(chunk.value()) {
+                Ok(level) => level,
+                // If it's not a LargeFile, the head chunk is all there is to it.
+                Err(_) => return Ok(FileVerificationReport { chunks }),
+            };
+            match level {
+                DataMapLevel::First(data_map) => break vec![data_map],
+                DataMapLevel::Segments(data_maps) => break data_maps,
+                DataMapLevel::Additional(data_map) => {
+                    let infos = data_map.infos();
+                    let encrypted_chunks = self.verify_chunks(&infos, &mut chunks, true).await;
+                    if encrypted_chunks.len() < infos.len() {
+                        return Ok(FileVerificationReport { chunks });
+                    }
+                    let serialized_chunk =
+                        self_encryption::decrypt_full_set(&data_map, &encrypted_chunks)
+                            .map_err(Error::SelfEncryption)?;
+                    chunk = deserialize(&serialized_chunk).map_err(Error::Serialisation)?;
+                }
+            }
+        };
+
+        let infos = segments
+            .iter()
+            .flat_map(|data_map| data_map.infos())
+            .collect_vec();
+        let _ = self.verify_chunks(&infos, &mut chunks, false).await;
+
+        Ok(FileVerificationReport { chunks })
+    }
+
+    /// Stores again the chunks of the file at the given address which can't be retrieved from the
+    /// network, out of the content of the file, e.g. a local copy of it, returning the addresses of
+    /// the chunks stored. Fails if the content is not that of the file at the address.
+    #[instrument(skip(self, bytes), level = "debug")]
+    pub async fn repair(&self, address: ChunkAddress, bytes: Bytes) -> Result<Vec<ChunkAddress>> {
+        let (mut head_address, mut chunks) =
+            chunk_bytes_in_pool(&self.client, bytes.clone()).await?;
+        // The content may have been uploaded from a reader, encrypted in segments.
+        if head_address != *address.name() && bytes.len() >= SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES {
+            (head_address, chunks) = self.client.encryptor.encrypt_segmented(bytes).await?;
+        }
+        if head_address != *address.name() {
+            return Err(Error::ChunkAddressMismatch {
+                expected: *address.name(),
+                actual: head_address,
+            })?;
+        }
+
+        let missing: BTreeSet<_> = self
+            .verify(address)
+            .await?
+            .missing_chunks()
+            .into_iter()
+            .collect();
+        let mut repaired = vec![];
+        for chunk in chunks
+            .into_iter()
+            .filter(|chunk| missing.contains(chunk.address()))
+        {
+            let chunk_address = *chunk.address();
+            let size = chunk.value().len();
+            self.client.store_chunk(chunk).await?;
+            self.client
+                .events_channel
+                .broadcast(ClientEvent::ChunkStored {
+                    address: chunk_address,
+                    size,
+                });
+            repaired.push(chunk_address);
+        }
+        Ok(repaired)
+    }
+
+    /// Retrieves the chunk at the given address, and tells whether it holds the data map of
+    /// a file or any other content. Only the chunks of additional data map levels are retrieved
+    /// besides it, never the content of the file.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn inspect_chunk(&self, address: ChunkAddress) -> Result<ChunkContent> {
+        let chunk = self.get_verified_chunk(address).await?;
+        let size = chunk.value().len();
+        // A chunk not holding a data map is not deserialisable into one.
+        if deserialize::<DataMapLevel>(chunk.value()).is_err() {
+            return Ok(ChunkContent::Raw { size });
+        }
+        let segments = self.unpack_chunk(chunk).await?;
+        Ok(ChunkContent::DataMap {
+            chunks: segments.iter().map(|data_map| data_map.infos().len()).sum(),
+            file_size: segments.iter().map(|data_map| data_map.file_size()).sum(),
+        })
     }
 
     // --------------------------------------------
     // ---------- Private helpers -----------------
     // --------------------------------------------
 
+    // Retrieves a chunk of a download, recording where it was retrieved from when tracing.
+    async fn download_chunk(&self, address: ChunkAddress) -> Result<Chunk> {
+        download_chunk(&self.client, self.trace.as_ref(), address).await
+    }
+
+    // Retrieves a chunk, checking its content matches the address it was requested from.
+    async fn get_verified_chunk(&self, address: ChunkAddress) -> Result<Chunk> {
+        let chunk = self.client.get_chunk(address).await?;
+        if chunk.address() != &address {
+            warn!(
+                "Chunk retrieved from {address:?} has different content address: {:?}",
+                chunk.address()
+            );
+            return Err(Error::ChunkAddressMismatch {
+                expected: *address.name(),
+                actual: *chunk.name(),
+            })?;
+        }
+        Ok(chunk)
+    }
+
+    // Retrieves and verifies the chunks, adding the outcome for each of them to the report, and
+    // returning those retrieved if they are to be kept, e.g. to decrypt a data map level.
+    async fn verify_chunks(
+        &self,
+        infos: &[ChunkInfo],
+        report: &mut Vec<(ChunkAddress, Option<String>)>,
+        keep: bool,
+    ) -> Vec<EncryptedChunk> {
+        let mut encrypted_chunks = vec![];
+        for next_batch in infos.chunks(CHUNKS_BATCH_MAX_SIZE) {
+            let tasks = next_batch.iter().map(|chunk_info| async move {
+                let chunk_address = ChunkAddress::new(chunk_info.dst_hash);
+                (chunk_info, self.get_verified_chunk(chunk_address).await)
+            });
+            for (chunk_info, result) in join_all(tasks).await {
+                let chunk_address = ChunkAddress::new(chunk_info.dst_hash);
+                match result {
+                    Ok(chunk) => {
+                        report.push((chunk_address, None));
+                        if keep {
+                            encrypted_chunks.push(EncryptedChunk {
+                                index: chunk_info.index,
+                                content: chunk.value().clone(),
+                            });
+                        }
+                    }
+                    Err(error) => report.push((chunk_address, Some(error.to_string()))),
+                }
+            }
+        }
+        encrypted_chunks
+    }
+
     #[instrument(skip(self, bytes), level = "trace")]
     async fn upload_bytes(&self, bytes: Bytes, verify: bool) -> Result<ChunkAddress> {
         match LargeFile::new(bytes.clone()) {
@@ -122,14 +442,85 @@ impl Files {
         }
     }
 
+    /// Uploads what's read from the reader one segment at a time, only the segment being
+    /// stored and the next one being held in memory.
+    async fn upload_streaming<R: AsyncRead + Unpin>(&self, reader: R) -> Result<ChunkAddress> {
+        let mut segments = SegmentReader::new(reader);
+        let mut data_maps = vec![];
+        loop {
+            let (segment, is_last) = segments.next_segment().await?;
+            if is_last && data_maps.is_empty() {
+                return self.upload_bytes(Bytes::from(segment), false).await;
+            }
+            data_maps.push(self.upload_segment(Bytes::from(segment)).await?);
+            if is_last {
+                break;
+            }
+        }
+
+        let (head_address, data_map_chunks) = pack_segments(data_maps)?;
+        self.client
+            .events_channel
+            .broadcast(ClientEvent::UploadStarted {
+                chunks: data_map_chunks.len(),
+                bytes: data_map_chunks
+                    .iter()
+                    .map(|chunk| chunk.value().len())
+                    .sum(),
+            });
+        // The chunk of the top-most data map is the last one, only stored once all the others are.
+        for chunk in data_map_chunks {
+            self.store_file_chunk(chunk).await?;
+        }
+        Ok(ChunkAddress::new(head_address))
+    }
+
+    // Encrypts and stores a segment of a file being uploaded, returning its data map.
+    async fn upload_segment(&self, segment: Bytes) -> Result<DataMap> {
+        let (data_map, chunks) = self.client.encryptor.encrypt_segment(segment).await?;
+        self.client
+            .events_channel
+            .broadcast(ClientEvent::UploadStarted {
+                chunks: chunks.len(),
+                bytes: chunks.iter().map(|chunk| chunk.value().len()).sum(),
+            });
+        stream::iter(chunks)
+            .map(|chunk| self.store_file_chunk(chunk))
+            .buffer_unordered(self.max_concurrent_chunks)
+            .try_for_each(|()| future::ready(Ok(())))
+            .await?;
+        Ok(data_map)
+    }
+
+    // Stores a chunk of a file being uploaded.
+    async fn store_file_chunk(&self, chunk: Chunk) -> Result<()> {
+        let address = *chunk.address();
+        let size = chunk.value().len();
+        self.client.store_chunk(chunk).await?;
+        self.client
+            .events_channel
+            .broadcast(ClientEvent::ChunkStored { address, size });
+        Ok(())
+    }
+
     /// Directly writes a [`SmallFile`] to the network in the
     /// form of a single chunk, without any batching.
     #[instrument(skip_all, level = "trace")]
     async fn upload_small(&self, small: SmallFile, verify: bool) -> Result<ChunkAddress> {
         let chunk = package_small(small)?;
         let address = *chunk.address();
+        let size = chunk.value().len();
 
+        self.client
+            .events_channel
+            .broadcast(ClientEvent::UploadStarted {
+                chunks: 1,
+                bytes: size,
+            });
         self.client.store_chunk(chunk).await?;
+        self.client
+            .events_channel
+            .broadcast(ClientEvent::ChunkStored { address, size });
 
         if verify {
             self.verify_chunk_is_stored(address).await?;
@@ -138,26 +529,42 @@ impl Files {
         Ok(address)
     }
 
-    /// Directly writes a [`LargeFile`] to the network in the
-    /// form of immutable self encrypted chunks, without any batching.
+    /// Writes a [`LargeFile`] to the network in the form of immutable self encrypted
+    /// chunks, in batches of `batch_size` chunks, up to `max_concurrent_chunks` at a time.
     #[instrument(skip_all, level = "trace")]
     async fn upload_large(&self, large: LargeFile, verify: bool) -> Result<ChunkAddress> {
-        let (head_address, all_chunks) = encrypt_large(large)?;
-        for next_batch in all_chunks.chunks(CHUNKS_BATCH_MAX_SIZE) {
+        let (head_address, all_chunks) = self.client.encryptor.encrypt(large.bytes()).await?;
+        self.client
+            .events_channel
+            .broadcast(ClientEvent::UploadStarted {
+                chunks: all_chunks.len(),
+                bytes: all_chunks.iter().map(|chunk| chunk.value().len()).sum(),
+            });
+        for next_batch in all_chunks.chunks(self.batch_size) {
             let tasks = next_batch.iter().cloned().map(|chunk| {
                 let client = self.client.clone();
 
-                task::spawn(async move {
-                    let chunk_addr = *chunk.address();
-                    client.store_chunk(chunk).await?;
-                    if verify {
-                        let _ = client.get_chunk(chunk_addr).await?;
+                task::spawn(CorrelationId::propagate(
+                    async move {
+                        let chunk_addr = *chunk.address();
+                        let size = chunk.value().len();
+                        client.store_chunk(chunk).await?;
+                        client.events_channel.broadcast(ClientEvent::ChunkStored {
+                            address: chunk_addr,
+                            size,
+                        });
+                        if verify {
+                            let _ = client.get_chunk(chunk_addr).await?;
+                        }
+                        Ok::<(), super::error::Error>(())
                     }
-                    Ok::<(), super::error::Error>(())
-                })
+                    .in_current_span(),
+                ))
             });
 
-            let respones = join_all(tasks)
+            let respones = stream::iter(tasks)
+                .buffer_unordered(self.max_concurrent_chunks)
+                .collect::<Vec<_>>()
                 .await
                 .into_iter()
                 .flatten() // swallows errors
@@ -187,15 +594,28 @@ impl Files {
         Ok(bytes)
     }
 
+    // Gets and decrypts the segments of a file, then returns their raw data one after the other.
+    async fn read_segments(&self, segments: Vec<DataMap>) -> Result<Bytes> {
+        let mut bytes = BytesMut::new();
+        for data_map in segments {
+            bytes.extend_from_slice(&self.read_all(data_map).await?);
+        }
+        Ok(bytes.freeze())
+    }
+
     /// Extracts a file DataMapLevel from a chunk.
     /// If the DataMapLevel is not the first level mapping directly to the user's contents,
-    /// the process repeats itself until it obtains the first level DataMapLevel.
+    /// the process repeats itself until it obtains the first level DataMapLevel, or the
+    /// data maps of the segments the file was encrypted in, which are returned in order.
     #[instrument(skip_all, level = "trace")]
-    async fn unpack_chunk(&self, mut chunk: Chunk) -> Result<DataMap> {
+    async fn unpack_chunk(&self, mut chunk: Chunk) -> Result<Vec<DataMap>> {
         loop {
             match deserialize(chunk.value()).map_err(Error::Serialisation)? {
                 DataMapLevel::First(data_map) => {
-                    return Ok(data_map);
+                    return Ok(vec![data_map]);
+                }
+                DataMapLevel::Segments(data_maps) => {
+                    return Ok(data_maps);
                 }
                 DataMapLevel::Additional(data_map) => {
                     let serialized_chunk = self.read_all(data_map).await?;
@@ -228,22 +648,53 @@ impl Files {
         Ok(bytes)
     }
 
+    // Reads `len` bytes of a file at the given `pos`, out of the segments overlapping them.
+    async fn seek_segments(&self, segments: &[DataMap], pos: usize, len: usize) -> Result<Bytes> {
+        if let [data_map] = segments {
+            return self.seek(data_map.clone(), pos, len).await;
+        }
+        let sizes = segments.iter().map(DataMap::file_size).collect_vec();
+        let mut bytes = BytesMut::new();
+        for (index, start, length) in segment_ranges(&sizes, pos, len) {
+            let segment_bytes = self.seek(segments[index].clone(), start, length).await?;
+            bytes.extend_from_slice(&segment_bytes);
+        }
+        Ok(bytes.freeze())
+    }
+
     #[instrument(skip_all, level = "trace")]
     async fn try_get_chunks(&self, chunks_info: Vec<ChunkInfo>) -> Result<Vec<EncryptedChunk>> {
         let expected_count = chunks_info.len();
+        self.client
+            .events_channel
+            .broadcast(ClientEvent::DownloadStarted {
+                chunks: expected_count,
+            });
         let mut retrieved_chunks = vec![];
         for next_batch in chunks_info.chunks(CHUNKS_BATCH_MAX_SIZE) {
             let tasks = next_batch.iter().cloned().map(|chunk_info| {
                 let client = self.client.clone();
+                let trace = self.trace.clone();
                 task::spawn(async move {
-                    match client
-                        .get_chunk(ChunkAddress::new(chunk_info.dst_hash))
-                        .await
+                    match download_chunk(
+                        &client,
+                        trace.as_ref(),
+                        ChunkAddress::new(chunk_info.dst_hash),
+                    )
+                    .await
                     {
-                        Ok(chunk) => Ok(EncryptedChunk {
-                            index: chunk_info.index,
-                            content: chunk.value().clone(),
-                        }),
+                        Ok(chunk) => {
+                            client
+                                .events_channel
+                                .broadcast(ClientEvent::ChunkRetrieved {
+                                    address: *chunk.address(),
+                                    size: chunk.value().len(),
+                                });
+                            Ok(EncryptedChunk {
+                                index: chunk_info.index,
+                                content: chunk.value().clone(),
+                            })
+                        }
                         Err(err) => {
                             warn!(
                                 "Reading chunk {} from network, resulted in error {err:?}.",
@@ -271,6 +722,76 @@ impl Files {
     }
 }
 
+// Retrieves a chunk of a download, with retries, recording where it was retrieved from in the trace.
+async fn download_chunk(
+    client: &Client,
+    trace: Option<&DownloadTrace>,
+    address: ChunkAddress,
+) -> Result<Chunk> {
+    let (result, provenance) = client.get_chunk_with_provenance(address).await;
+    if let Some(trace) = trace {
+        trace.record(provenance);
+    }
+    result
+}
+
+// Reads a segment of the data to upload, i.e. until the reader's end or `SEGMENT_SIZE` bytes.
+async fn read_segment<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut segment = Vec::with_capacity(SEGMENT_SIZE);
+    let _ = reader
+        .take(SEGMENT_SIZE as u64)
+        .read_to_end(&mut segment)
+        .await
+        .map_err(Error::Io)?;
+    Ok(segment)
+}
+
+// Reads the segments of the data to upload one at a time, only the segment returned and the
+// next one being held in memory. They are split as `split_segments` splits data in memory.
+struct SegmentReader<R> {
+    reader: R,
+    next: Option<Vec<u8>>,
+}
+
+impl<R: AsyncRead + Unpin> SegmentReader<R> {
+    fn new(reader: R) -> Self {
+        Self { reader, next: None }
+    }
+
+    // Returns the next segment, and whether it's the last one, what's left after the last
+    // segment being part of it if too small to be encrypted.
+    async fn next_segment(&mut self) -> Result<(Vec<u8>, bool)> {
+        let mut segment = match self.next.take() {
+            Some(segment) => segment,
+            None => read_segment(&mut self.reader).await?,
+        };
+        let next = read_segment(&mut self.reader).await?;
+        if next.len() < MIN_ENCRYPTABLE_BYTES {
+            segment.extend_from_slice(&next);
+            return Ok((segment, true));
+        }
+        self.next = Some(next);
+        Ok((segment, false))
+    }
+}
+
+// The index of each of the segments, of the given sizes, overlapping the `len` bytes at `pos`
+// of a file, along with the position and length of the bytes to read in that segment.
+fn segment_ranges(segment_sizes: &[usize], pos: usize, len: usize) -> Vec<(usize, usize, usize)> {
+    let end = pos.saturating_add(len);
+    let mut ranges = vec![];
+    let mut segment_start = 0;
+    for (index, size) in segment_sizes.iter().enumerate() {
+        let segment_end = segment_start + size;
+        if segment_start < end && pos < segment_end {
+            let start = pos.max(segment_start);
+            ranges.push((index, start - segment_start, end.min(segment_end) - start));
+        }
+        segment_start = segment_end;
+    }
+    ranges
+}
+
 /// Calculates a LargeFile's/SmallFile's address from self encrypted chunks,
 /// without storing them onto the network.
 #[instrument(skip(bytes), level = "debug")]
@@ -292,6 +813,19 @@ pub fn chunk_bytes(bytes: Bytes) -> Result<(XorName, Vec<Chunk>)> {
     }
 }
 
+/// Chunks the bytes as [`chunk_bytes`] does, self-encrypting them on the encryption thread
+/// pool of the client rather than on the current thread.
+pub(super) async fn chunk_bytes_in_pool(
+    client: &Client,
+    bytes: Bytes,
+) -> Result<(XorName, Vec<Chunk>)> {
+    match LargeFile::new(bytes.clone()) {
+        Ok(file) => Ok(client.encryptor.encrypt(file.bytes()).await?),
+        Err(Error::TooSmallForSelfEncryption { .. }) => chunk_bytes(bytes),
+        Err(error) => Err(error)?,
+    }
+}
+
 /// Encrypts a [`LargeFile`] and returns the resulting address and all chunks.
 /// Does not store anything to the network.
 #[instrument(skip(file), level = "trace")]
@@ -308,3 +842,81 @@ fn package_small(file: SmallFile) -> Result<Chunk> {
     }
     Ok(chunk)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::client::chunks::split_segments;
+
+    #[tokio::test]
+    async fn data_is_split_in_the_same_segments_when_read_or_in_memory() -> Result<()> {
+        let sizes = [
+            1,
+            SEGMENT_SIZE,
+            SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES - 1,
+            SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES,
+            2 * SEGMENT_SIZE,
+            2 * SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES - 1,
+            2 * SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES,
+        ];
+        for size in sizes {
+            let data = vec![0; size];
+            let mut reader = SegmentReader::new(data.as_slice());
+            let mut read = vec![];
+            loop {
+                let (segment, is_last) = reader.next_segment().await?;
+                read.push(segment.len());
+                if is_last {
+                    break;
+                }
+            }
+            let split = split_segments(Bytes::from(data))
+                .iter()
+                .map(Bytes::len)
+                .collect_vec();
+            assert_eq!(read, split, "Segments of {size} bytes");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn data_is_split_in_two_segments_from_a_segment_plus_the_minimum_encryptable_bytes() {
+        let sizes = |size| {
+            split_segments(Bytes::from(vec![0; size]))
+                .iter()
+                .map(Bytes::len)
+                .collect_vec()
+        };
+        assert_eq!(
+            sizes(SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES - 1),
+            [SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES - 1]
+        );
+        assert_eq!(
+            sizes(SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES),
+            [SEGMENT_SIZE, MIN_ENCRYPTABLE_BYTES]
+        );
+        assert_eq!(
+            sizes(2 * SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES - 1),
+            [SEGMENT_SIZE, SEGMENT_SIZE + MIN_ENCRYPTABLE_BYTES - 1]
+        );
+    }
+
+    #[test]
+    fn ranges_are_read_out_of_the_segments_they_overlap() {
+        let sizes = [10, 10, 15];
+        // Within a segment, starting at its start, ending at its end.
+        assert_eq!(segment_ranges(&sizes, 0, 10), [(0, 0, 10)]);
+        assert_eq!(segment_ranges(&sizes, 10, 3), [(1, 0, 3)]);
+        assert_eq!(segment_ranges(&sizes, 5, 5), [(0, 5, 5)]);
+        // Straddling segment boundaries.
+        assert_eq!(segment_ranges(&sizes, 5, 10), [(0, 5, 5), (1, 0, 5)]);
+        assert_eq!(
+            segment_ranges(&sizes, 5, usize::MAX),
+            [(0, 5, 5), (1, 0, 10), (2, 0, 15)]
+        );
+        // Beyond the end of the file, or empty.
+        assert!(segment_ranges(&sizes, 35, 10).is_empty());
+        assert!(segment_ranges(&sizes, 5, 0).is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    Files,
+};
+
+use crate::protocol::storage::ChunkAddress;
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+};
+
+/// The tag a stored [`FilesContainer`] starts with, followed by its format version.
+const MAGIC: &[u8] = b"SAFE-FILES-CONTAINER";
+/// The version of the format containers are stored in.
+const FILES_CONTAINER_VERSION: u8 = 1;
+
+/// A file stored in a [`FilesContainer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// The network address of the file.
+    pub address: ChunkAddress,
+    /// The size of the file in bytes.
+    pub size: u64,
+}
+
+/// Maps the relative paths of a set of files, e.g. those of an uploaded directory,
+/// to their network addresses. The container is itself stored on the network as a file,
+/// tagged with a header so it can be told apart from other files.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilesContainer {
+    files: BTreeMap<String, FileEntry>,
+}
+
+impl FilesContainer {
+    /// Create an empty container.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file at the given relative path, replacing any file previously stored at it.
+    /// Path components are always joined with `/`, regardless of the platform.
+    pub fn add_file(&mut self, relative_path: &Path, address: ChunkAddress, size: u64) {
+        let path = Self::path_key(relative_path);
+        let _ = self.files.insert(path, FileEntry { address, size });
+    }
+
+    /// The path a file at the given relative path is stored at, i.e. its components joined with `/`.
+    pub fn path_key(relative_path: &Path) -> String {
+        relative_path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Returns the file stored at the given relative path, if any.
+    pub fn get(&self, path: &str) -> Option<&FileEntry> {
+        self.files.get(path)
+    }
+
+    /// Remove the file stored at the given relative path, returning it if there was one.
+    pub fn remove_file(&mut self, path: &str) -> Option<FileEntry> {
+        self.files.remove(path)
+    }
+
+    /// Iterate over the files in the container, ordered by their relative paths.
+    pub fn files(&self) -> impl Iterator<Item = (&String, &FileEntry)> {
+        self.files.iter()
+    }
+
+    /// Returns the number of files in the container.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns true if the container holds no files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Serialize the container, after its header.
+    pub fn to_bytes(&self) -> Result<Bytes> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(FILES_CONTAINER_VERSION);
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(Bytes::from(bytes))
+    }
+
+    /// Deserialize a container, checking its header first.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (version, content) = bytes
+            .strip_prefix(MAGIC)
+            .and_then(|rest| rest.split_first())
+            .ok_or(Error::NotAFilesContainer)?;
+        if *version != FILES_CONTAINER_VERSION {
+            return Err(Error::UnsupportedFilesContainerVersion(
+                *version,
+                FILES_CONTAINER_VERSION,
+            ));
+        }
+        Ok(bincode::deserialize(content)?)
+    }
+
+    /// Store the container on the network, returning its address.
+    pub async fn upload(&self, file_api: &Files) -> Result<ChunkAddress> {
+        file_api.upload(self.to_bytes()?).await
+    }
+
+    /// Retrieve a container from the network.
+    pub async fn retrieve(file_api: &Files, address: ChunkAddress) -> Result<Self> {
+        let bytes = file_api.read_bytes(address).await?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+    use xor_name::XorName;
+
+    #[test]
+    fn files_container_normalises_paths_and_roundtrips() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let mut container = FilesContainer::new();
+        let address = ChunkAddress::new(XorName::random(&mut rng));
+
+        let path: PathBuf = ["./", "photos", "2023", "cat.jpg"].iter().collect();
+        container.add_file(&path, address, 42);
+
+        assert_eq!(
+            container.get("photos/2023/cat.jpg"),
+            Some(&FileEntry { address, size: 42 })
+        );
+
+        let bytes = container.to_bytes()?;
+        assert_eq!(FilesContainer::from_bytes(&bytes)?, container);
+
+        assert_eq!(
+            container.remove_file("photos/2023/cat.jpg"),
+            Some(FileEntry { address, size: 42 })
+        );
+        assert!(container.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn files_container_header_is_checked() -> Result<()> {
+        let bytes = FilesContainer::new().to_bytes()?;
+        assert!(bytes.starts_with(MAGIC));
+        assert!(FilesContainer::from_bytes(&bytes)?.is_empty());
+
+        // Another file, even one deserializing as a container without the header.
+        let untagged = bincode::serialize(&FilesContainer::new())?;
+        assert!(matches!(
+            FilesContainer::from_bytes(&untagged),
+            Err(Error::NotAFilesContainer)
+        ));
+
+        let mut newer = bytes.to_vec();
+        newer[MAGIC.len()] = FILES_CONTAINER_VERSION + 1;
+        assert!(matches!(
+            FilesContainer::from_bytes(&newer),
+            Err(Error::UnsupportedFilesContainerVersion(
+                _,
+                FILES_CONTAINER_VERSION
+            ))
+        ));
+
+        Ok(())
+    }
+}
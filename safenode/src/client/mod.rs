@@ -7,24 +7,39 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod api;
+mod builder;
 mod chunks;
 mod error;
 mod event;
 mod file_apis;
+mod files_container;
+mod nrs;
+mod provenance;
+mod rate_limit;
 mod register;
 mod wallet;
 
 pub use self::{
+    api::CONNECTION_TIMEOUT,
+    builder::{ClientBuilder, RequestTimeouts, RetryPolicy},
     error::Error,
-    event::{ClientEvent, ClientEventsReceiver},
-    file_apis::Files,
+    event::{ClientEvent, ClientEventsReceiver, EventCallbackId},
+    file_apis::{calculate_address, ChunkContent, FileVerificationReport, Files},
+    files_container::{FileEntry, FilesContainer},
+    nrs::{name_register_address, validate_name, NRS_REGISTER_TAG},
+    provenance::ChunkProvenance,
+    rate_limit::RateLimit,
     register::{Register, RegisterOffline},
     wallet::WalletClient,
 };
 
-use self::event::ClientEventsChannel;
+use self::{chunks::Encryptor, event::ClientEventsChannel, rate_limit::RateLimiter};
 
-use crate::network::Network;
+use crate::{network::Network, protocol::messages::CorrelationId};
+
+use std::{future::Future, sync::Arc};
+use tokio::sync::Semaphore;
+use tracing::Instrument;
 
 /// Client API implementation to store and get data.
 #[derive(Clone)]
@@ -32,4 +47,27 @@ pub struct Client {
     network: Network,
     events_channel: ClientEventsChannel,
     signer: bls::SecretKey,
+    /// Bounds the number of requests in flight across all the client's operations.
+    requests_limiter: Arc<Semaphore>,
+    /// Throttles the requests to the caps set with [`ClientBuilder::with_rate_limit`], if any.
+    rate_limiter: Arc<RateLimiter>,
+    /// Self-encrypts the files uploaded, off the async runtime.
+    encryptor: Encryptor,
+    /// How long each kind of request is waited a response for.
+    request_timeouts: RequestTimeouts,
+    /// How the requests which fail to be sent or time out are retried.
+    retry_policy: RetryPolicy,
+}
+
+/// Runs the future as an operation of the client, with an id carried by all the requests sent
+/// for it, so that the logs of the nodes handling them can be correlated with those of the client.
+/// An operation run from within another one, e.g. storing a chunk of a file being uploaded,
+/// is part of the outer one.
+async fn operation<F: Future>(name: &'static str, future: F) -> F::Output {
+    if CorrelationId::current().is_some() {
+        return future.await;
+    }
+    let correlation_id = CorrelationId::random();
+    let span = info_span!("Operation", operation = name, %correlation_id);
+    CorrelationId::scope(Some(correlation_id), future.instrument(span)).await
 }
@@ -0,0 +1,183 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    client::{error::Result, Client, Error},
+    protocol::{
+        error::{Error as ProtocolError, StorageError},
+        storage::registers::User,
+        xorurl::XorUrl,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use xor_name::XorName;
+
+/// The type tag of the registers holding the mappings of names to addresses.
+pub const NRS_REGISTER_TAG: u64 = 3007;
+
+/// The maximum length of a name, as that of a DNS label.
+const MAX_NAME_LEN: usize = 63;
+
+/// A version of the mapping of a name to an address, as written to the register of the name.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct NameEntry {
+    version: u64,
+    target: String,
+}
+
+/// Check the name is valid, i.e. made of lowercase ASCII letters, digits and `-` not at either
+/// end, and no longer than 63 characters. The name is lowercased first.
+pub fn validate_name(name: &str) -> Result<String> {
+    let name = name.to_ascii_lowercase();
+    let is_valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if is_valid {
+        Ok(name)
+    } else {
+        Err(Error::InvalidName(name))
+    }
+}
+
+/// The address of the register holding the mappings of the given (valid) name.
+pub fn name_register_address(name: &str) -> XorName {
+    XorName::from_content(format!("nrs:{name}").as_bytes())
+}
+
+impl Client {
+    /// Map the name to the url, creating the register of the name if it's not taken yet, or
+    /// else adding a version of the mapping atop the previous ones, if this client owns it.
+    /// Returns the version of the mapping, the first one being 1.
+    pub async fn register_name(&self, name: &str, target: &XorUrl) -> Result<u64> {
+        let name = validate_name(name)?;
+        let address = name_register_address(&name);
+        let mut register = match self.get_register(address, NRS_REGISTER_TAG).await {
+            Ok(register) => register,
+            Err(Error::Protocol(ProtocolError::Storage(StorageError::RegisterNotFound(_)))) => {
+                self.create_register(address, NRS_REGISTER_TAG).await?
+            }
+            Err(err) => return Err(err),
+        };
+        if register.owner() != User::Key(self.signer_pk()) {
+            return Err(Error::NameTaken(name));
+        }
+
+        let version = latest(register.history().into_iter().map(|(_, entry)| entry))
+            .map_or(1, |(version, _)| version + 1);
+        let entry = serde_json::to_vec(&NameEntry {
+            version,
+            target: target.to_string(),
+        })
+        .map_err(|err| Error::InvalidNameEntry(err.to_string()))?;
+        register.write_merging_branches(&entry).await?;
+        info!("Registered version {version} of name {name} mapped to {target}");
+        Ok(version)
+    }
+
+    /// Resolve the name into the url it's currently mapped to, i.e. its latest version.
+    pub async fn resolve_name(&self, name: &str) -> Result<XorUrl> {
+        let (_, target) = self
+            .name_history(name)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::NameNotFound(name.to_string()))?;
+        Ok(target)
+    }
+
+    /// Every version of the mapping of the name, latest first.
+    pub async fn name_history(&self, name: &str) -> Result<Vec<(u64, XorUrl)>> {
+        let name = validate_name(name)?;
+        let register = match self
+            .get_register(name_register_address(&name), NRS_REGISTER_TAG)
+            .await
+        {
+            Ok(register) => register,
+            Err(Error::Protocol(ProtocolError::Storage(StorageError::RegisterNotFound(_)))) => {
+                return Err(Error::NameNotFound(name))
+            }
+            Err(err) => return Err(err),
+        };
+
+        let mut versions: Vec<(u64, XorUrl)> = register
+            .history()
+            .into_iter()
+            .filter_map(|(_, entry)| decode(&entry))
+            .collect();
+        // Concurrent writes of the same version are ordered as they are in the history.
+        versions.sort_by(|(a, _), (b, _)| b.cmp(a));
+        Ok(versions)
+    }
+}
+
+/// The latest version among the entries of a name register, skipping invalid entries.
+fn latest(entries: impl IntoIterator<Item = Vec<u8>>) -> Option<(u64, XorUrl)> {
+    entries
+        .into_iter()
+        .filter_map(|entry| decode(&entry))
+        .max_by_key(|(version, _)| *version)
+}
+
+fn decode(entry: &[u8]) -> Option<(u64, XorUrl)> {
+    let entry: NameEntry = match serde_json::from_slice(entry) {
+        Ok(entry) => entry,
+        Err(err) => {
+            warn!("Skipping a name register entry which is not a mapping: {err}");
+            return None;
+        }
+    };
+    match entry.target.parse() {
+        Ok(target) => Some((entry.version, target)),
+        Err(err) => {
+            warn!("Skipping a name register entry with an invalid url: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::xorurl::DataType;
+
+    #[test]
+    fn names_are_lowercased_and_validated() {
+        assert_eq!(validate_name("My-Site2").ok(), Some("my-site2".to_string()));
+        for name in [
+            "",
+            "-site",
+            "site-",
+            "my site",
+            "my.site",
+            "sïte",
+            &"a".repeat(64),
+        ] {
+            assert!(validate_name(name).is_err(), "{name:?} should be invalid");
+        }
+    }
+
+    #[test]
+    fn the_latest_version_of_a_mapping_wins() {
+        let url = |byte| XorUrl::new(DataType::File, XorName([byte; 32]));
+        let entry = |version, byte| {
+            serde_json::to_vec(&NameEntry {
+                version,
+                target: url(byte).to_string(),
+            })
+            .expect("Entry should serialise.")
+        };
+        let entries = vec![entry(2, 2), b"not a mapping".to_vec(), entry(1, 1)];
+        assert_eq!(latest(entries), Some((2, url(2))));
+        assert_eq!(latest(vec![]), None);
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::storage::ChunkAddress;
+
+use libp2p::PeerId;
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+/// Where a chunk of a download was retrieved from, and how long it took, to diagnose slow or
+/// failing retrievals, e.g. to report the peers serving wrong chunks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkProvenance {
+    /// The address of the chunk.
+    pub address: ChunkAddress,
+    /// The peer which served the chunk, `None` if it wasn't retrieved.
+    pub peer: Option<PeerId>,
+    /// The time taken to retrieve the chunk, across all the attempts.
+    pub elapsed: Duration,
+    /// The number of attempts made, i.e. 1 if it was retrieved without retrying.
+    pub attempts: usize,
+    /// The peers which served a chunk whose content doesn't match its address.
+    pub wrong_chunk_peers: Vec<PeerId>,
+    /// The error of the last attempt, if the chunk wasn't retrieved.
+    pub error: Option<String>,
+}
+
+impl ChunkProvenance {
+    /// The retrieval of the chunk at the given address, before its first attempt.
+    pub(super) fn new(address: ChunkAddress) -> Self {
+        Self {
+            address,
+            peer: None,
+            elapsed: Duration::ZERO,
+            attempts: 0,
+            wrong_chunk_peers: vec![],
+            error: None,
+        }
+    }
+
+    /// The number of times the retrieval of the chunk was retried.
+    pub fn retries(&self) -> usize {
+        self.attempts.saturating_sub(1)
+    }
+}
+
+/// The provenance of the chunks retrieved by a download, recorded from the concurrent
+/// retrievals of its chunks.
+#[derive(Debug, Clone, Default)]
+pub(super) struct DownloadTrace(Arc<Mutex<Vec<ChunkProvenance>>>);
+
+impl DownloadTrace {
+    pub(super) fn record(&self, provenance: ChunkProvenance) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(provenance);
+    }
+
+    /// The provenance of the chunks recorded so far, in the order they were retrieved.
+    pub(super) fn take(&self) -> Vec<ChunkProvenance> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xor_name::XorName;
+
+    #[test]
+    fn download_trace_collects_the_provenance_of_concurrent_retrievals() {
+        let mut rng = rand::thread_rng();
+        let trace = DownloadTrace::default();
+        let handles: Vec<_> = (1..=3)
+            .map(|attempts| {
+                let mut provenance =
+                    ChunkProvenance::new(ChunkAddress::new(XorName::random(&mut rng)));
+                provenance.attempts = attempts;
+                let trace = trace.clone();
+                std::thread::spawn(move || trace.record(provenance))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("The thread should not panic.");
+        }
+
+        let mut retries: Vec<_> = trace.take().iter().map(ChunkProvenance::retries).collect();
+        retries.sort();
+        assert_eq!(retries, vec![0, 1, 2]);
+        assert!(trace.take().is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use std::{
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Caps on the rate of the requests a client sends, across all its operations,
+/// e.g. to remain polite to a small network. There's no cap by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimit {
+    /// The maximum number of requests sent per second.
+    pub requests_per_sec: Option<f64>,
+    /// The maximum number of bytes of data stored or retrieved per second.
+    pub bytes_per_sec: Option<f64>,
+}
+
+/// Throttles the requests of a client to its [`RateLimit`], with a token bucket per cap
+/// allowing bursts of up to one second worth of requests or bytes.
+#[derive(Debug)]
+pub(super) struct RateLimiter {
+    buckets: Mutex<(Option<Bucket>, Option<Bucket>)>,
+}
+
+impl RateLimiter {
+    pub(super) fn new(limit: RateLimit) -> Self {
+        let now = Instant::now();
+        let bucket = |rate: Option<f64>| {
+            rate.filter(|rate| *rate > 0.0)
+                .map(|rate| Bucket::new(rate, now))
+        };
+        Self {
+            buckets: Mutex::new((bucket(limit.requests_per_sec), bucket(limit.bytes_per_sec))),
+        }
+    }
+
+    /// Wait for a request carrying the given number of bytes of data to be within the caps.
+    pub(super) async fn request(&self, bytes: usize) {
+        self.wait(1.0, bytes as f64).await
+    }
+
+    /// Wait for the given number of bytes of data, e.g. retrieved by a previous request,
+    /// to be within the caps.
+    pub(super) async fn bytes(&self, bytes: usize) {
+        self.wait(0.0, bytes as f64).await
+    }
+
+    async fn wait(&self, requests: f64, bytes: f64) {
+        let wait = {
+            let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+            let now = Instant::now();
+            let (requests_bucket, bytes_bucket) = &mut *buckets;
+            let requests_wait = requests_bucket
+                .as_mut()
+                .map_or(Duration::ZERO, |bucket| bucket.take(requests, now));
+            let bytes_wait = bytes_bucket
+                .as_mut()
+                .map_or(Duration::ZERO, |bucket| bucket.take(bytes, now));
+            requests_wait.max(bytes_wait)
+        };
+        if !wait.is_zero() {
+            trace!("Throttling the client for {wait:?}");
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Tokens are added at `rate` per second, up to `rate`. Those taken beyond the ones available
+/// are borrowed from the future, the taker having to wait for them to be added.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate,
+            refilled: now,
+        }
+    }
+
+    /// Take the tokens, returning how long to wait for them to have been added.
+    fn take(&mut self, tokens: f64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.refilled = now;
+        self.tokens -= tokens;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_allows_a_burst_then_spaces_out_the_takes() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(2.0, start);
+        assert_eq!(bucket.take(1.0, start), Duration::ZERO);
+        assert_eq!(bucket.take(1.0, start), Duration::ZERO);
+        // Each take beyond the burst waits for half a second more than the previous one.
+        assert_eq!(bucket.take(1.0, start), Duration::from_millis(500));
+        assert_eq!(bucket.take(1.0, start), Duration::from_secs(1));
+
+        // Once the debt is paid off, the bucket fills up again, up to its rate.
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.take(2.0, later), Duration::ZERO);
+        assert_eq!(bucket.take(1.0, later), Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limiter_waits_for_the_most_constraining_cap() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_sec: Some(100.0),
+            bytes_per_sec: Some(1000.0),
+        });
+        let start = Instant::now();
+        limiter.request(1000).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter.request(2000).await;
+        assert!(start.elapsed() >= Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn limiter_without_caps_never_waits() {
+        let limiter = RateLimiter::new(RateLimit::default());
+        let start = Instant::now();
+        for _ in 0..1000 {
+            limiter.request(1024 * 1024).await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
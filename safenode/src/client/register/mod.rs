@@ -15,9 +15,10 @@ use super::{
     Client,
 };
 
-use crate::protocol::storage::registers::{Entry, EntryHash, Policy};
+use crate::protocol::storage::registers::{Entry, EntryHash, Policy, User};
 
-use std::collections::BTreeSet;
+use bls::PublicKey;
+use std::collections::{BTreeMap, BTreeSet};
 use xor_name::XorName;
 
 /// Operations made to a Register instance are applied not only locally,
@@ -61,6 +62,11 @@ impl Register {
         self.offline_reg.tag()
     }
 
+    /// Return the owner of the Register.
+    pub fn owner(&self) -> User {
+        self.offline_reg.owner()
+    }
+
     /// Return the number of items held in the register
     pub fn size(&self) -> u64 {
         self.offline_reg.size()
@@ -71,11 +77,41 @@ impl Register {
         self.offline_reg.get(hash)
     }
 
+    /// Return the hashes of the entries the entry of the provided 'hash' was written atop,
+    /// i.e. its parents, if present.
+    pub fn parents(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.offline_reg.parents(hash)
+    }
+
     /// Read the last entry, or entries when there are branches, if the register is not empty.
     pub fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.offline_reg.read()
     }
 
+    /// Return all the entries ever written to the Register, ordered so that
+    /// each entry comes before the entries it was written atop, i.e. latest first.
+    pub fn history(&self) -> Vec<(EntryHash, Entry)> {
+        self.offline_reg.history()
+    }
+
+    /// Return the hashes of all the entries the entry of the provided 'hash' was written atop,
+    /// directly or not, i.e. its causal ancestors, if present.
+    pub fn ancestors(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.offline_reg.ancestors(hash)
+    }
+
+    /// Return the hashes of the entries written concurrently to the entry of the provided 'hash',
+    /// i.e. those which are neither its causal ancestors nor were written atop it, if present.
+    pub fn concurrent(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.offline_reg.concurrent(hash)
+    }
+
+    /// Return the branches of the Register, keyed by the hash of their latest entry, each
+    /// holding the entries written to it since it diverged from the others, latest first.
+    pub fn branches(&self) -> BTreeMap<EntryHash, Vec<(EntryHash, Entry)>> {
+        self.offline_reg.branches()
+    }
+
     /// Write a new value onto the Register atop latest value.
     /// It returns an error if it finds branches in the content/entries; if it is
     /// required to merge/resolve the branches, invoke the `write_merging_branches` API.
@@ -103,6 +139,14 @@ impl Register {
         self.offline_reg.push().await
     }
 
+    /// Transfer the ownership of the Register to the given public key.
+    /// Once the replicas apply it, only the new owner is allowed to transfer it again,
+    /// and edits are checked against the new owner's permissions.
+    pub async fn transfer_ownership(&mut self, new_owner: PublicKey) -> Result<()> {
+        self.offline_reg.transfer_ownership(new_owner)?;
+        self.offline_reg.push().await
+    }
+
     /// Sync this Register with the replicas on the network.
     pub async fn sync(&mut self) -> Result<()> {
         self.offline_reg.sync().await
@@ -12,9 +12,11 @@ use crate::{
     domain::storage::RegisterReplica,
     protocol::{
         error::Error as ProtocolError,
+        error::StorageError,
         messages::{
             Cmd, CmdResponse, CreateRegister, EditRegister, Query, QueryResponse, RegisterCmd,
             RegisterQuery, Request, Response, SignedRegisterCreate, SignedRegisterEdit,
+            SignedRegisterOwnershipTransfer, TransferRegisterOwnership,
         },
         storage::{
             registers::{Action, DataAuthority, Entry, EntryHash, Permissions, Policy, User},
@@ -24,8 +26,9 @@ use crate::{
 };
 
 use bincode::serialize;
+use bls::PublicKey;
 use std::{
-    collections::{BTreeSet, LinkedList},
+    collections::{BTreeMap, BTreeSet, LinkedList},
     convert::From,
 };
 use xor_name::XorName;
@@ -86,6 +89,11 @@ impl RegisterOffline {
         self.register.tag()
     }
 
+    /// Return the owner of the Register.
+    pub fn owner(&self) -> User {
+        self.register.owner()
+    }
+
     /// Return the number of items held in the register
     pub fn size(&self) -> u64 {
         self.register.size()
@@ -97,11 +105,56 @@ impl RegisterOffline {
         Ok(entry)
     }
 
+    /// Return the hashes of the entries the entry of the provided 'hash' was written atop,
+    /// i.e. its parents, if present.
+    pub fn parents(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        let parents = self
+            .register
+            .parents(hash)
+            .map_err(ProtocolError::Storage)?;
+        Ok(parents)
+    }
+
     /// Read the last entry, or entries when there are branches, if the register is not empty.
     pub fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.register.read()
     }
 
+    /// Return all the entries ever written to the Register, ordered so that
+    /// each entry comes before the entries it was written atop, i.e. latest first.
+    pub fn history(&self) -> Vec<(EntryHash, Entry)> {
+        self.register.history()
+    }
+
+    /// Return the hashes of all the entries the entry of the provided 'hash' was written atop,
+    /// directly or not, i.e. its causal ancestors, if present.
+    pub fn ancestors(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        let ancestors = self
+            .register
+            .ancestors(hash)
+            .map_err(ProtocolError::Storage)?;
+        Ok(ancestors)
+    }
+
+    /// Return the hashes of the entries written concurrently to the entry of the provided 'hash',
+    /// i.e. those which are neither its causal ancestors nor were written atop it, if present.
+    pub fn concurrent(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        let concurrent = self
+            .register
+            .concurrent(hash)
+            .map_err(ProtocolError::Storage)?;
+        Ok(concurrent)
+    }
+
+    /// Return the branches of the Register, keyed by the hash of their latest entry, as
+    /// returned by `read`. Each holds the entries written to it since it diverged from the
+    /// others, latest first, so that conflicting edits can be merged or displayed as the
+    /// application sees fit. There's a single branch holding all the entries when the
+    /// Register has no concurrent latest entries.
+    pub fn branches(&self) -> BTreeMap<EntryHash, Vec<(EntryHash, Entry)>> {
+        self.register.branches()
+    }
+
     /// Write a new value onto the Register atop latest value.
     /// It returns an error if it finds branches in the content/entries; if it is
     /// required to merge/resolve the branches, invoke the `write_merging_branches` API.
@@ -160,6 +213,36 @@ impl RegisterOffline {
         Ok(())
     }
 
+    /// Transfer the ownership of the Register to the given public key.
+    /// Only the current owner can transfer the ownership, and once the transfer
+    /// is applied by the replicas, edits are checked against the new owner.
+    pub fn transfer_ownership(&mut self, new_owner: PublicKey) -> Result<()> {
+        let public_key = self.client.signer_pk();
+        let current_owner = self.register.owner();
+        if current_owner != User::Key(public_key) {
+            return Err(
+                ProtocolError::Storage(StorageError::AccessDenied(User::Key(public_key))).into(),
+            );
+        }
+
+        let op = TransferRegisterOwnership {
+            address: *self.register.address(),
+            current_owner,
+            new_owner: User::Key(new_owner),
+            sequence: self.register.ownership_transfers(),
+        };
+        let auth = DataAuthority {
+            public_key,
+            signature: self.client.sign(&serialize(&op)?),
+        };
+        self.register.transfer_ownership(op.new_owner);
+        let cmd = RegisterCmd::TransferOwnership(SignedRegisterOwnershipTransfer { op, auth });
+
+        self.ops.push_front(cmd);
+
+        Ok(())
+    }
+
     /// Sync this Register with the replicas on the network.
     pub async fn sync(&mut self) -> Result<()> {
         debug!("Syncing Register at {}, {}!", self.name(), self.tag(),);
@@ -182,6 +265,9 @@ impl RegisterOffline {
                 let result = match cmd {
                     RegisterCmd::Create { .. } => self.publish_register_create(cmd.clone()).await,
                     RegisterCmd::Edit { .. } => self.publish_register_edit(cmd.clone()).await,
+                    RegisterCmd::TransferOwnership { .. } => {
+                        self.publish_register_ownership_transfer(cmd.clone()).await
+                    }
                 };
 
                 if let Err(err) = result {
@@ -292,6 +378,44 @@ impl RegisterOffline {
         Err(Error::UnexpectedResponses)
     }
 
+    // Publish a `Register` ownership transfer command in the network.
+    async fn publish_register_ownership_transfer(&self, cmd: RegisterCmd) -> Result<()> {
+        debug!(
+            "Publishing Register ownership transfer cmd: {:?}",
+            cmd.dst()
+        );
+        let request = Request::Cmd(Cmd::Register(cmd));
+        let responses = self.client.send_to_closest(request).await?;
+
+        let all_ok = responses.iter().all(|resp| {
+            matches!(
+                resp,
+                Ok(Response::Cmd(CmdResponse::TransferRegisterOwnership(
+                    Ok(())
+                )))
+            )
+        });
+        if all_ok {
+            return Ok(());
+        }
+
+        // If not all were Ok, we will return the first error sent to us.
+        for resp in responses.iter().flatten() {
+            if let Response::Cmd(CmdResponse::TransferRegisterOwnership(result)) = resp {
+                result.clone()?;
+            };
+        }
+
+        // If there were no success or fail to the expected query,
+        // we check if there were any send errors.
+        for resp in responses {
+            let _ = resp?;
+        }
+
+        // If there were no register errors, then we had unexpected responses.
+        Err(Error::UnexpectedResponses)
+    }
+
     // Retrieve a `Register` from the closest peers.
     async fn get_register(client: &Client, name: XorName, tag: u64) -> Result<RegisterReplica> {
         let address = RegisterAddress { name, tag };
@@ -308,7 +432,7 @@ impl RegisterOffline {
 
         // If no register was gotten, we will return the first error sent to us.
         for resp in responses.iter().flatten() {
-            if let Response::Query(QueryResponse::GetChunk(result)) = resp {
+            if let Response::Query(QueryResponse::GetRegister(result)) = resp {
                 let _ = result.clone()?;
             };
         }
@@ -0,0 +1,76 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    client::{error::Result, Client, Error, Files},
+    domain::wallet::{encrypt_for_inbox, inbox_name, INBOX_REGISTER_TAG},
+    protocol::{
+        error::{Error as ProtocolError, StorageError},
+        storage::ChunkAddress,
+    },
+};
+
+use bytes::Bytes;
+use sn_dbc::{Dbc, PublicAddress};
+use xor_name::XorName;
+
+impl Client {
+    /// Deliver the dbc to the inbox of its recipient on the network, encrypted to its key,
+    /// for the recipient to find it when scanning for deposits.
+    pub async fn deliver_to_inbox(&self, dbc: &Dbc) -> Result<()> {
+        let encrypted = encrypt_for_inbox(dbc)?;
+        let chunk_address = Files::new(self.clone())
+            .upload(Bytes::from(encrypted))
+            .await?;
+
+        let name = inbox_name(dbc.public_address());
+        let mut inbox = match self.get_register(name, INBOX_REGISTER_TAG).await {
+            Ok(inbox) => inbox,
+            Err(Error::Protocol(ProtocolError::Storage(StorageError::RegisterNotFound(_)))) => {
+                self.create_register(name, INBOX_REGISTER_TAG).await?
+            }
+            Err(err) => return Err(err),
+        };
+        inbox
+            .write_merging_branches(chunk_address.name().as_ref())
+            .await
+    }
+
+    /// Read the encrypted dbcs delivered to the inbox of the address, oldest first.
+    /// An address no dbc was ever delivered to has no inbox, thus nothing is read.
+    pub async fn read_inbox(&self, address: &PublicAddress) -> Result<Vec<Bytes>> {
+        let inbox = match self
+            .get_register(inbox_name(address), INBOX_REGISTER_TAG)
+            .await
+        {
+            Ok(inbox) => inbox,
+            Err(Error::Protocol(ProtocolError::Storage(StorageError::RegisterNotFound(_)))) => {
+                return Ok(vec![])
+            }
+            Err(err) => return Err(err),
+        };
+
+        let files = Files::new(self.clone());
+        let mut delivered = vec![];
+        // The history is latest first.
+        for (_, entry) in inbox.history().into_iter().rev() {
+            let name: [u8; 32] = match entry.as_slice().try_into() {
+                Ok(name) => name,
+                Err(_) => {
+                    warn!("Skipping an inbox entry which is not a chunk address");
+                    continue;
+                }
+            };
+            match files.read_bytes(ChunkAddress::new(XorName(name))).await {
+                Ok(bytes) => delivered.push(bytes),
+                Err(err) => warn!("Could not read a dbc delivered to the inbox: {err}"),
+            }
+        }
+        Ok(delivered)
+    }
+}
@@ -6,14 +6,21 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod inbox;
 pub(crate) mod send_client;
 pub(crate) mod verifying_client;
 
 use super::Client;
 
-use crate::domain::wallet::{Error, Result, SendWallet};
+use crate::{
+    domain::wallet::{
+        DepositWallet, Error, LocalWallet, PaymentReceipt, Result, SendWallet, VerifyingClient,
+    },
+    protocol::storage::DbcAddress,
+};
 
 use sn_dbc::{Dbc, PublicAddress, Token};
+use xor_name::XorName;
 
 /// A wallet client can be used to send and
 /// receive tokens to/from other wallets.
@@ -39,8 +46,90 @@ impl<W: SendWallet> WalletClient<W> {
         }
     }
 
+    /// Send tokens to many wallets at once, e.g. for a payroll, in a single transfer.
+    /// The inputs are spent only once, for all the recipients together, rather than in a
+    /// transfer per recipient. Returns the dbcs created for the recipients.
+    pub async fn send_to_many(&mut self, to: Vec<(Token, PublicAddress)>) -> Result<Vec<Dbc>> {
+        if to.is_empty() {
+            return Err(Error::CouldNotSendTokens(
+                "No recipients were given.".into(),
+            ));
+        }
+        let dbcs = self.wallet.send(to, &self.client).await?;
+        if dbcs.is_empty() {
+            return Err(Error::CouldNotSendTokens(
+                "No DBCs were returned from the wallet.".into(),
+            ));
+        }
+        Ok(dbcs.into_iter().map(|created| created.dbc).collect())
+    }
+
+    /// Verify the spends of a received dbc against the network, and if they
+    /// are valid, deposit it to the wallet.
+    /// Note that the dbc is only deposited if it is accessible by the wallet.
+    pub async fn verify_and_deposit(&mut self, dbc: Dbc) -> Result<()> {
+        self.client.verify(&dbc).await?;
+        self.wallet.deposit(vec![dbc]);
+        Ok(())
+    }
+
     /// Return the wallet.
     pub fn into_wallet(self) -> W {
         self.wallet
     }
 }
+
+impl WalletClient<LocalWallet> {
+    /// Scan the inboxes of the wallet on the network for dbcs sent to it which it hasn't
+    /// deposited yet, verify their spends against the network and deposit them.
+    /// Returns the dbcs deposited.
+    pub async fn scan_for_deposits(&mut self) -> Result<Vec<Dbc>> {
+        let mut deposited = vec![];
+        for address in self.wallet.inbox_addresses() {
+            let delivered = self
+                .client
+                .read_inbox(&address)
+                .await
+                .map_err(|err| Error::CouldNotReadInbox(err.to_string()))?;
+            for encrypted in delivered {
+                let dbc = match self.wallet.decrypt_inbox_dbc(&encrypted) {
+                    Some(dbc) => dbc,
+                    None => continue,
+                };
+                if let Err(err) = self.client.verify(&dbc).await {
+                    warn!("Skipping a dbc from the inbox, which is not valid: {err}");
+                    continue;
+                }
+                self.wallet.deposit(vec![dbc.clone()]);
+                deposited.push(dbc);
+            }
+        }
+        Ok(deposited)
+    }
+
+    /// Re-prove the payment for storing the data at the address, e.g. to dispute the network
+    /// reporting it missing when the data is uploaded again. The receipt of the payment kept
+    /// by the wallet is verified, and its spends checked to be the ones registered in the network.
+    pub async fn prove_payment(&self, address: &XorName) -> Result<PaymentReceipt> {
+        let receipt = self
+            .wallet
+            .payment_receipt(address)
+            .ok_or(Error::PaymentReceiptNotFound(*address))?
+            .clone();
+        receipt.verify()?;
+        for spend in &receipt.spends {
+            let registered = self
+                .client
+                .get_spend(DbcAddress::from_dbc_id(spend.dbc_id()))
+                .await
+                .map_err(|err| Error::CouldNotVerifyTransfer(err.to_string()))?;
+            if registered.hash() != spend.hash() {
+                return Err(Error::InvalidPaymentReceipt(format!(
+                    "The spend of {:?} registered in the network is not the one of the payment",
+                    spend.dbc_id()
+                )));
+            }
+        }
+        Ok(receipt)
+    }
+}
@@ -8,9 +8,12 @@
 
 use super::Client;
 
-use crate::domain::{
-    client_transfers::Outputs as TransferDetails,
-    wallet::{Error, Result, SendClient},
+use crate::{
+    client::operation,
+    domain::{
+        client_transfers::Outputs as TransferDetails,
+        wallet::{Error, Result, SendClient},
+    },
 };
 
 use futures::future::join_all;
@@ -23,7 +26,7 @@ impl SendClient for Client {
             tasks.push(self.expect_closest_majority_ok(spend_request.clone()));
         }
 
-        for spend_attempt_result in join_all(tasks).await {
+        for spend_attempt_result in operation("send_tokens", join_all(tasks)).await {
             spend_attempt_result.map_err(|err| Error::CouldNotSendTokens(err.to_string()))?;
         }
 
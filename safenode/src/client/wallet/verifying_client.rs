@@ -8,10 +8,17 @@
 
 use super::Client;
 
-use crate::domain::wallet::{Error, Result, VerifyingClient};
+use crate::{
+    client::{operation, Error as ClientError},
+    domain::wallet::{Error, Result, VerifyingClient},
+    protocol::{
+        error::{Error as ProtocolError, StorageError},
+        storage::DbcAddress,
+    },
+};
 
 use futures::future::join_all;
-use sn_dbc::Dbc;
+use sn_dbc::{Dbc, DbcId, SignedSpend};
 
 #[async_trait::async_trait]
 impl VerifyingClient for Client {
@@ -25,7 +32,7 @@ impl VerifyingClient for Client {
         }
 
         let mut received_spends = std::collections::BTreeSet::new();
-        for result in join_all(tasks).await {
+        for result in operation("verify_dbc", join_all(tasks)).await {
             let network_valid_spend =
                 result.map_err(|err| Error::CouldNotVerifyTransfer(err.to_string()))?;
             let _ = received_spends.insert(network_valid_spend);
@@ -40,4 +47,14 @@ impl VerifyingClient for Client {
             "The spends in network were not the same as the ones in the DBC.".into(),
         ))
     }
+
+    async fn registered_spend(&self, dbc_id: &DbcId) -> Result<Option<SignedSpend>> {
+        match self.get_spend(DbcAddress::from_dbc_id(dbc_id)).await {
+            Ok(spend) => Ok(Some(spend)),
+            Err(ClientError::Protocol(ProtocolError::Storage(StorageError::SpendNotFound(_)))) => {
+                Ok(None)
+            }
+            Err(err) => Err(Error::CouldNotVerifyTransfer(err.to_string())),
+        }
+    }
 }
@@ -30,10 +30,9 @@
 mod error;
 mod transfer;
 
-pub(crate) use self::{
-    error::{Error, Result},
-    transfer::create_transfer,
-};
+pub use self::error::Error;
+
+pub(crate) use self::{error::Result, transfer::create_transfer};
 
 use sn_dbc::{
     Dbc, DbcIdSource, DbcTransaction, DerivedKey, PublicAddress, RevealedAmount, SignedSpend, Token,
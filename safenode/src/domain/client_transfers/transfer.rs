@@ -26,16 +26,17 @@ use std::collections::BTreeMap;
 /// Once enough peers have accepted all the spends of the transaction, and serve
 /// them upon request, the transaction will be completed.
 ///
-/// (Disabled for now: DbcReason, can be added later.)
-#[allow(unused)]
+/// The spends are made for the reason hash, e.g. the hash of the addresses of the data paid for
+/// by a payment for storage, or `Hash::default()` for a transfer without a reason.
 pub(crate) fn create_transfer(
     available_dbcs: Vec<(Dbc, DerivedKey)>,
     recipients: Vec<(Token, DbcIdSource)>,
     change_to: PublicAddress,
+    reason_hash: Hash,
 ) -> Result<Outputs> {
     // We need to select the necessary number of dbcs from those that we were passed.
     let selected_inputs = select_inputs(available_dbcs, recipients, change_to)?;
-    create_transfer_with(selected_inputs)
+    create_transfer_with(selected_inputs, reason_hash)
 }
 
 /// Select the necessary number of dbcs from those that we were passed.
@@ -121,7 +122,7 @@ fn verify_amounts(total_input_amount: Token, total_output_amount: Token) -> Resu
 /// To do that, the `signed_spends` of each new dbc, has to be uploaded
 /// to the network. When those same signed spends can be retrieved from
 /// enough peers in the network, the transaction will be completed.
-fn create_transfer_with(selected_inputs: Inputs) -> Result<Outputs> {
+fn create_transfer_with(selected_inputs: Inputs, reason_hash: Hash) -> Result<Outputs> {
     let Inputs {
         dbcs_to_spend,
         recipients,
@@ -161,7 +162,7 @@ fn create_transfer_with(selected_inputs: Inputs) -> Result<Outputs> {
 
     // Finalize the tx builder to get the dbc builder.
     let dbc_builder = tx_builder
-        .build(Hash::default(), &mut rng)
+        .build(reason_hash, &mut rng)
         .map_err(Error::Dbcs)?;
 
     let tx_hash = dbc_builder.dst_tx.hash();
@@ -10,30 +10,29 @@ use super::wallet::LocalWallet;
 
 use crate::client::{Client, WalletClient};
 
-#[cfg(test)]
 use sn_dbc::{
-    rng, DbcIdSource, Hash, InputHistory, RevealedAmount, RevealedInput, TransactionBuilder,
+    rng, Dbc, DbcId, DbcIdSource, DbcTransaction, Error as DbcError, Hash, InputHistory, MainKey,
+    PublicAddress, RevealedAmount, RevealedInput, Token, TransactionBuilder,
 };
 
-use sn_dbc::{Dbc, DbcTransaction, Error as DbcError, MainKey, PublicAddress, Token};
-
 use lazy_static::lazy_static;
-use std::{fmt::Debug, path::PathBuf};
+use std::{
+    fmt::Debug,
+    path::PathBuf,
+    sync::{PoisonError, RwLock},
+};
 use thiserror::Error;
 
 /// Number of tokens in the Genesis DBC.
 /// At the inception of the Network 30 % of total supply - i.e. 1,288,490,189 - whole tokens will be created.
 /// Each whole token can be subdivided 10^9 times,
 /// thus creating a total of 1,288,490,189,000,000,000 available units.
-#[cfg(test)]
 pub(super) const GENESIS_DBC_AMOUNT: u64 = (0.3 * TOTAL_SUPPLY as f64) as u64;
 
 /// A specialised `Result` type for dbc_genesis crate.
-#[cfg(test)]
-pub(super) type GenesisResult<T> = Result<T, Error>;
+pub type GenesisResult<T> = Result<T, Error>;
 
 /// Total supply of tokens that will eventually exist in the network: 4,294,967,295 * 10^9 = 4,294,967,295,000,000,000.
-#[cfg(test)]
 const TOTAL_SUPPLY: u64 = u32::MAX as u64 * u64::pow(10, 9);
 
 /// The secret key for the genesis DBC.
@@ -64,9 +63,47 @@ lazy_static! {
     };
 }
 
+lazy_static! {
+    /// The genesis DBC of a new network, as set with `set_genesis_dbc`,
+    /// which is then used instead of the default one.
+    static ref NETWORK_GENESIS_DBC: RwLock<Option<Dbc>> = RwLock::new(None);
+}
+
+/// Set the genesis DBC of a new network, e.g. one created with `create_genesis_dbc`, to be used
+/// instead of the default genesis DBC. Nodes only accept the spends of the genesis DBC of their
+/// network, thus every node of the network has to set the same one before it starts.
+pub fn set_genesis_dbc(dbc: Dbc) {
+    *NETWORK_GENESIS_DBC
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(dbc);
+}
+
+/// Run the function with the genesis DBC of the network.
+fn with_genesis_dbc<R>(f: impl FnOnce(&Dbc) -> R) -> R {
+    let network_genesis = NETWORK_GENESIS_DBC
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    f(network_genesis.as_ref().unwrap_or(&GENESIS_DBC))
+}
+
 /// Return if provided DbcTransaction is genesis parent tx.
 pub(crate) fn is_genesis_parent_tx(parent_tx: &DbcTransaction) -> bool {
-    parent_tx == &GENESIS_DBC.src_tx
+    with_genesis_dbc(|genesis| parent_tx == &genesis.src_tx)
+}
+
+/// Return if provided DbcId is the one of the genesis DBC.
+pub(crate) fn is_genesis_dbc_id(dbc_id: &DbcId) -> bool {
+    with_genesis_dbc(|genesis| dbc_id == &genesis.id)
+}
+
+/// Mint the initial supply of a new network into a genesis DBC owned by the given address.
+/// The DBC has to be set as the genesis DBC of every node of the network, with `set_genesis_dbc`,
+/// for it to be spent. The input of the genesis transaction is never spent, thus its key
+/// is thrown away.
+pub fn create_genesis_dbc(to: PublicAddress) -> GenesisResult<Dbc> {
+    let input_key = MainKey::random();
+    let dbc_id_src = to.random_dbc_id_src(&mut rng::thread_rng());
+    create_dbc_from_nothing(&input_key, dbc_id_src)
 }
 
 /// Returns a dbc with the requested number of tokens, for use by E2E test instances.
@@ -197,13 +234,18 @@ pub(crate) fn create_first_dbc_from_key(first_dbc_key: &MainKey) -> GenesisResul
         public_address: first_dbc_key.public_address(),
         derivation_index: [0u8; 32],
     };
-    let derived_key = first_dbc_key.derive_key(&dbc_id_src.derivation_index);
+    create_dbc_from_nothing(first_dbc_key, dbc_id_src)
+}
+
+/// Create a DBC holding the genesis amount, out of a transaction
+/// whose single input, of the input key, was created from nothing.
+fn create_dbc_from_nothing(input_key: &MainKey, dbc_id_src: DbcIdSource) -> GenesisResult<Dbc> {
+    let derived_key = input_key.derive_key(&dbc_id_src.derivation_index);
     let revealed_amount = RevealedAmount {
         value: GENESIS_DBC_AMOUNT,
         blinding_factor: sn_dbc::BlindingFactor::from_bits([0u8; 32]),
     };
 
-    // Use the same key as the input and output of Genesis Tx.
     // The src tx is empty as this is the first DBC.
     let genesis_input = InputHistory {
         input: RevealedInput::new(derived_key, revealed_amount),
@@ -317,3 +359,24 @@ async fn get_faucet_dir() -> PathBuf {
         .expect("Faucet test path to be successfully created.");
     home_dirs
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn genesis_dbc_holds_the_initial_supply_for_its_owner() -> GenesisResult<()> {
+        let owner = MainKey::random();
+        let genesis = create_genesis_dbc(owner.public_address())?;
+
+        assert_eq!(&owner.public_address(), genesis.public_address());
+        let derived_key = genesis.derived_key(&owner)?;
+        assert_eq!(
+            GENESIS_DBC_AMOUNT,
+            genesis.revealed_amount(&derived_key)?.value()
+        );
+        assert!(genesis.derived_key(&MainKey::random()).is_err());
+
+        Ok(())
+    }
+}
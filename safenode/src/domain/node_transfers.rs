@@ -6,10 +6,11 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::dbc_genesis::GENESIS_DBC;
-
 use crate::{
-    domain::{dbc_genesis::is_genesis_parent_tx, storage::SpendStorage},
+    domain::{
+        dbc_genesis::{is_genesis_dbc_id, is_genesis_parent_tx},
+        storage::SpendStorage,
+    },
     protocol::{error::TransferError, storage::DbcAddress},
 };
 
@@ -119,7 +120,7 @@ fn validate_parent_spends(
         .map(|s| s.spend.blinded_amount)
         .collect();
 
-    if is_genesis_parent_tx(parent_tx) && signed_spend.dbc_id() == &GENESIS_DBC.id {
+    if is_genesis_parent_tx(parent_tx) && is_genesis_dbc_id(signed_spend.dbc_id()) {
         return Ok(());
     }
 
@@ -16,11 +16,18 @@ use libp2p::{
         store::{Error, RecordStore, Result},
     },
 };
+use sn_dbc::Token;
 use std::{borrow::Cow, collections::HashSet, fs, path::PathBuf, vec};
 
 // Control the random replication factor, which means `one in x` copies got replicated each time.
+/// Name of the dir within the root dir of a node, its records are stored in.
+pub(crate) const RECORD_STORE_DIR_NAME: &str = "record_store";
+
 const RANDOM_REPLICATION_FACTOR: usize = CLOSE_GROUP_SIZE / 2;
 
+// The cost of storing a record, in nanos, when the store is empty.
+const MIN_STORE_COST: u64 = 1_000;
+
 /// A `RecordStore` that stores records on disk.
 pub(crate) struct DiskBackedRecordStore {
     /// The identity of the peer owning the store.
@@ -85,6 +92,19 @@ impl DiskBackedRecordStore {
         to_be_removed.iter().for_each(|key| self.remove(key));
     }
 
+    /// Returns the cost of storing a new record in this store. The cost grows
+    /// as the store fills up, making it more expensive to store data on full nodes.
+    pub(crate) fn store_cost(&self) -> Token {
+        let max_records = self.config.max_records.max(1) as u64;
+        let num_records = (self.records.len() as u64).min(max_records);
+        // The cost grows quadratically, from MIN_STORE_COST when empty to 4x that when full.
+        let cost = MIN_STORE_COST
+            .saturating_mul(max_records.saturating_add(num_records))
+            .saturating_mul(max_records.saturating_add(num_records))
+            / max_records.saturating_mul(max_records);
+        Token::from_nano(cost.max(MIN_STORE_COST))
+    }
+
     // Converts a Key into a Hex string.
     fn key_to_hex(key: &Key) -> String {
         let key_bytes = key.as_ref();
@@ -314,4 +334,25 @@ mod tests {
         }
         quickcheck(prop as fn(_))
     }
+
+    #[test]
+    fn store_cost_grows_with_records_stored() {
+        let mut store = DiskBackedRecordStore::new(PeerId::random());
+        let empty_cost = store.store_cost();
+        assert_eq!(empty_cost, Token::from_nano(MIN_STORE_COST));
+
+        let mut last_cost = empty_cost;
+        for i in 0..10u8 {
+            let record = Record {
+                key: Key::new(&[i]),
+                value: vec![i],
+                publisher: None,
+                expires: None,
+            };
+            assert!(store.put(record).is_ok());
+            let cost = store.store_cost();
+            assert!(cost >= last_cost);
+            last_cost = cost;
+        }
+    }
 }
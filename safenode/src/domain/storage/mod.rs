@@ -11,9 +11,11 @@ mod registers;
 mod spends;
 
 pub(crate) use self::{
-    disk_backed_record_store::{DiskBackedRecordStore, DiskBackedRecordStoreConfig},
-    registers::{RegisterReplica, RegisterStorage},
-    spends::SpendStorage,
+    disk_backed_record_store::{
+        DiskBackedRecordStore, DiskBackedRecordStoreConfig, RECORD_STORE_DIR_NAME,
+    },
+    registers::{RegisterReplica, RegisterStorage, REGISTERS_STORE_DIR_NAME},
+    spends::{SpendStorage, DOUBLE_SPENDS_STORE_DIR_NAME, VALID_SPENDS_STORE_DIR_NAME},
 };
 
 use crate::protocol::error::StorageError;
@@ -17,7 +17,8 @@ use crate::protocol::{
     error::{Error as ProtocolError, StorageError as Error},
     messages::{
         EditRegister, QueryResponse, RegisterCmd, RegisterQuery, ReplicatedRegisterLog,
-        SignedRegisterCreate, SignedRegisterEdit,
+        SignedRegisterCreate, SignedRegisterEdit, SignedRegisterOwnershipTransfer,
+        TransferRegisterOwnership,
     },
     storage::{
         registers::{Action, EntryHash, User},
@@ -36,7 +37,7 @@ use walkdir::WalkDir;
 
 pub(super) type RegisterLog = Vec<RegisterCmd>;
 
-const REGISTERS_STORE_DIR_NAME: &str = "registers";
+pub(crate) const REGISTERS_STORE_DIR_NAME: &str = "registers";
 
 #[derive(Clone, Debug)]
 struct StoredRegister {
@@ -185,19 +186,29 @@ impl RegisterStorage {
             path.display()
         );
 
-        let entry_hash = if let RegisterCmd::Edit(edit_cmd) = cmd {
-            let entry_hash = EntryHash(edit_cmd.op.edit.crdt_op.hash());
-            trace!(
-                "Writing RegisterEdit cmd log for {addr:?}, entry hash: {entry_hash}, at {}",
-                path.display()
-            );
-            Some(entry_hash)
-        } else {
-            trace!(
-                "Writing RegisterCreate cmd log for {addr:?} at {}",
-                path.display()
-            );
-            None
+        let entry_hash = match cmd {
+            RegisterCmd::Edit(edit_cmd) => {
+                let entry_hash = EntryHash(edit_cmd.op.edit.crdt_op.hash());
+                trace!(
+                    "Writing RegisterEdit cmd log for {addr:?}, entry hash: {entry_hash}, at {}",
+                    path.display()
+                );
+                Some(entry_hash)
+            }
+            RegisterCmd::Create(_) => {
+                trace!(
+                    "Writing RegisterCreate cmd log for {addr:?} at {}",
+                    path.display()
+                );
+                None
+            }
+            RegisterCmd::TransferOwnership(_) => {
+                trace!(
+                    "Writing RegisterTransferOwnership cmd log for {addr:?} at {}",
+                    path.display()
+                );
+                None
+            }
         };
 
         // It's deterministic, so they are exactly the same op so we can leave.
@@ -338,7 +349,12 @@ impl RegisterStorage {
         // verified until we have the `Register create` cmd.
         match (stored_reg.state.as_mut(), cmd) {
             (Some(_), RegisterCmd::Create { .. }) => return Ok(()), // no op, since already created
-            (Some(ref mut register), RegisterCmd::Edit(_)) => self.apply(cmd, register)?,
+            (Some(_), RegisterCmd::TransferOwnership(_)) if stored_reg.op_log.contains(cmd) => {
+                return Ok(()); // no op, since already applied
+            }
+            (Some(ref mut register), RegisterCmd::Edit(_) | RegisterCmd::TransferOwnership(_)) => {
+                self.apply(cmd, register)?
+            }
             (None, RegisterCmd::Create(cmd)) => {
                 // the target Register is not in our store or we don't have the 'Register create',
                 // let's verify the create cmd we received is valid and try to apply stored cmds we may have.
@@ -355,9 +371,7 @@ impl RegisterStorage {
                 let mut register =
                     RegisterReplica::new(op.policy.owner, op.name, op.tag, op.policy.clone());
 
-                for cmd in &stored_reg.op_log {
-                    self.apply(cmd, &mut register)?;
-                }
+                self.apply_log(&stored_reg.op_log, &mut register)?;
 
                 stored_reg.state = Some(register);
             }
@@ -403,6 +417,70 @@ impl RegisterStorage {
                     }
                 }
             }
+            RegisterCmd::TransferOwnership(SignedRegisterOwnershipTransfer { op, auth }) => {
+                let serialised_op = serialize(op).map_err(|err| {
+                    warn!("We couldn't serialise the Register cmd to write it to disk: {err:?}");
+                    Error::RegisterCmdNotStored(dst_addr)
+                })?;
+                verify_authority(auth, serialised_op)?;
+
+                // Only the current owner can hand the Register over to someone else.
+                let requester = User::Key(auth.public_key);
+                if requester != register.owner() || op.current_owner != register.owner() {
+                    trace!("Transferring Register ownership denied {dst_addr:?}: {requester:?}");
+                    return Err(Error::AccessDenied(requester));
+                }
+                // Transfers are applied one after the other, in the order they were made.
+                if op.sequence != register.ownership_transfers() {
+                    trace!(
+                        "Transferring Register ownership out of sequence {dst_addr:?}: {}",
+                        op.sequence
+                    );
+                    return Err(Error::OwnershipTransferOutOfSequence {
+                        expected: register.ownership_transfers(),
+                        found: op.sequence,
+                    });
+                }
+
+                info!(
+                    "Transferring Register ownership: {dst_addr:?}, new owner: {:?}",
+                    op.new_owner
+                );
+                register.transfer_ownership(op.new_owner);
+                Ok(())
+            }
+        }
+    }
+
+    // Apply all cmds in the log to the register state. Cmds which fail are retried as long as
+    // others keep being applied, since the log is not kept in the order the cmds were made,
+    // e.g. an edit made by a new owner can only be applied after the ownership transfer.
+    fn apply_log(&self, log: &RegisterLog, register: &mut RegisterReplica) -> Result<()> {
+        // The log may contain duplicated cmds, which cannot all be applied
+        // (e.g. an ownership transfer is only valid once), so we skip them.
+        let mut pending: Vec<&RegisterCmd> = vec![];
+        for cmd in log {
+            if !pending.contains(&cmd) {
+                pending.push(cmd);
+            }
+        }
+
+        loop {
+            let pending_len = pending.len();
+            let mut last_err = None;
+            pending.retain(|cmd| match self.apply(cmd, register) {
+                Ok(()) => false,
+                Err(err) => {
+                    last_err = Some(err);
+                    true
+                }
+            });
+
+            match last_err {
+                None => return Ok(()),
+                Some(err) if pending.len() == pending_len => return Err(err),
+                Some(_) => { /* some progress was made, let's retry those pending */ }
+            }
         }
     }
 
@@ -413,10 +491,31 @@ impl RegisterStorage {
         let mut stored_reg = self.open_reg_log_from_disk(addr).await?;
         // if we have the Register creation cmd, apply all ops to reconstruct the Register
         if let Some(register) = &mut stored_reg.state {
+            let mut transfers = vec![];
             for cmd in &stored_reg.op_log {
-                if let RegisterCmd::Edit(SignedRegisterEdit { op, .. }) = cmd {
-                    let EditRegister { edit, .. } = op;
-                    register.apply_op(edit.clone())?;
+                match cmd {
+                    RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => {
+                        let EditRegister { edit, .. } = op;
+                        register.apply_op(edit.clone())?;
+                    }
+                    RegisterCmd::TransferOwnership(SignedRegisterOwnershipTransfer {
+                        op, ..
+                    }) => transfers.push(op),
+                    RegisterCmd::Create(_) => {}
+                }
+            }
+
+            // Ownership transfers are applied in the order of their sequence numbers,
+            // skipping the duplicates the log may contain.
+            transfers.sort_by_key(|op| op.sequence);
+            for TransferRegisterOwnership {
+                new_owner,
+                sequence,
+                ..
+            } in transfers
+            {
+                if *sequence == register.ownership_transfers() {
+                    register.transfer_ownership(*new_owner);
                 }
             }
         }
@@ -579,9 +678,13 @@ mod test {
         error::Error as ProtocolError,
         messages::{
             CreateRegister, EditRegister, QueryResponse, RegisterCmd, RegisterQuery,
-            SignedRegisterCreate, SignedRegisterEdit,
+            SignedRegisterCreate, SignedRegisterEdit, SignedRegisterOwnershipTransfer,
+            TransferRegisterOwnership,
+        },
+        storage::{
+            registers::{DataAuthority, EntryHash, Policy, User},
+            RegisterAddress,
         },
-        storage::registers::{DataAuthority, EntryHash, Policy, User},
     };
 
     use bincode::serialize;
@@ -933,6 +1036,98 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_register_transfer_ownership() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, name, policy) = create_register()?;
+        let addr = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        let (new_owner, new_sk) = random_user();
+        let cmd_transfer = transfer_register(addr, authority, new_owner, 0, &sk)?;
+        store.write(&cmd_transfer).await?;
+
+        // Re-applying the same transfer should change nothing.
+        store.write(&cmd_transfer).await?;
+
+        match store.read(&RegisterQuery::GetOwner(addr), authority).await {
+            QueryResponse::GetRegisterOwner(Ok(owner)) => assert_eq!(owner, new_owner),
+            e => bail!("Could not read register owner! {:?}", e),
+        }
+
+        // The previous owner can neither edit nor transfer the register anymore.
+        let mut register = RegisterReplica::new(policy.owner, name, 0, policy);
+        let cmd_edit = edit_register(&mut register, &sk)?;
+        match store.write(&cmd_edit).await {
+            Err(Error::AccessDenied(user)) => assert_eq!(user, authority),
+            other => bail!("Previous owner should not be able to edit: {:?}", other),
+        }
+        let cmd_transfer_back = transfer_register(addr, authority, authority, 1, &sk)?;
+        match store.write(&cmd_transfer_back).await {
+            Err(Error::AccessDenied(user)) => assert_eq!(user, authority),
+            other => bail!("Previous owner should not be able to transfer: {:?}", other),
+        }
+
+        // The new owner can edit the register.
+        let cmd_edit = edit_register(&mut register, &new_sk)?;
+        store.write(&cmd_edit).await?;
+
+        // Reloading it from disk keeps the new owner.
+        let stored_reg = store.try_load_stored_register(&addr).await?;
+        assert_eq!(stored_reg.state.map(|reg| reg.owner()), Some(new_owner));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_transfer_ownership_back_and_forth() -> Result<()> {
+        let store = new_store();
+
+        let (cmd_create, authority, sk, _, _) = create_register()?;
+        let addr = cmd_create.dst();
+        store.write(&cmd_create).await?;
+
+        // A transfer made out of sequence is rejected.
+        let (new_owner, new_sk) = random_user();
+        let cmd_transfer = transfer_register(addr, authority, new_owner, 1, &sk)?;
+        match store.write(&cmd_transfer).await {
+            Err(Error::OwnershipTransferOutOfSequence { expected, found }) => {
+                assert_eq!((expected, found), (0, 1))
+            }
+            other => bail!("Transfer out of sequence should be rejected: {:?}", other),
+        }
+
+        // The register is handed over to the new owner, back, and to the new owner again,
+        // the last transfer being that of the first one but for its sequence number.
+        store
+            .write(&transfer_register(addr, authority, new_owner, 0, &sk)?)
+            .await?;
+        store
+            .write(&transfer_register(addr, new_owner, authority, 1, &new_sk)?)
+            .await?;
+        store
+            .write(&transfer_register(addr, authority, new_owner, 2, &sk)?)
+            .await?;
+
+        // Replaying the transfer back changes nothing, as it was already applied.
+        let cmd_replayed = transfer_register(addr, new_owner, authority, 1, &new_sk)?;
+        store.write(&cmd_replayed).await?;
+
+        match store.read(&RegisterQuery::GetOwner(addr), authority).await {
+            QueryResponse::GetRegisterOwner(Ok(owner)) => assert_eq!(owner, new_owner),
+            e => bail!("Could not read register owner! {:?}", e),
+        }
+
+        // Reloading it from disk follows the transfers in their order.
+        let stored_reg = store.try_load_stored_register(&addr).await?;
+        let register = stored_reg.state.expect("The register to be stored");
+        assert_eq!(register.owner(), new_owner);
+        assert_eq!(register.ownership_transfers(), 3);
+
+        Ok(())
+    }
+
     fn random_user() -> (User, SecretKey) {
         let sk = SecretKey::random();
         let authority = User::Key(sk.public_key());
@@ -972,6 +1167,32 @@ mod test {
         }))
     }
 
+    fn transfer_register(
+        address: RegisterAddress,
+        current_owner: User,
+        new_owner: User,
+        sequence: u64,
+        sk: &SecretKey,
+    ) -> Result<RegisterCmd> {
+        let op = TransferRegisterOwnership {
+            address,
+            current_owner,
+            new_owner,
+            sequence,
+        };
+        let signature = sk.sign(serialize(&op)?);
+
+        Ok(RegisterCmd::TransferOwnership(
+            SignedRegisterOwnershipTransfer {
+                op,
+                auth: DataAuthority {
+                    public_key: sk.public_key(),
+                    signature,
+                },
+            },
+        ))
+    }
+
     fn new_store() -> RegisterStorage {
         let tmp_dir = assert_fs::TempDir::new().expect("Should be able to create a temp dir.");
         let path = tmp_dir.path();
@@ -19,7 +19,7 @@ use super::Result;
 use crdts::{merkle_reg::MerkleReg, CmRDT, CvRDT};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Debug, Display, Formatter},
     hash::Hash,
 };
@@ -140,6 +140,15 @@ impl RegisterCrdtImpl {
         self.data.node(hash.0).map(|node| &node.value)
     }
 
+    /// Get the hashes of the entries the entry corresponding to the provided `hash`
+    /// was written atop, i.e. its parents, if it exists. The Merkle DAG of the register
+    /// calls them the children of the entry's node, as they're hashed into it.
+    pub(crate) fn parents(&self, hash: EntryHash) -> Option<BTreeSet<EntryHash>> {
+        self.data
+            .node(hash.0)
+            .map(|node| node.children.iter().copied().map(EntryHash).collect())
+    }
+
     /// Read current entries (multiple entries occur on concurrent writes).
     pub(crate) fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.data
@@ -148,6 +157,160 @@ impl RegisterCrdtImpl {
             .map(|(hash, node)| (EntryHash(hash), node.value.clone()))
             .collect()
     }
+
+    /// Returns all the entries reachable from the current ones, ordered so that
+    /// each entry comes before the entries it was written atop, i.e. latest first.
+    pub(crate) fn history(&self) -> Vec<(EntryHash, Entry)> {
+        // A depth-first traversal from the current entries, whose reversed
+        // post-order lists every entry before its children.
+        let mut visited = BTreeSet::new();
+        let mut post_order = Vec::new();
+        let mut stack: Vec<_> = self
+            .data
+            .read()
+            .hashes_and_nodes()
+            .map(|(hash, _)| (hash, false))
+            .collect();
+
+        while let Some((hash, children_visited)) = stack.pop() {
+            if children_visited {
+                post_order.push(hash);
+                continue;
+            }
+            if !visited.insert(hash) {
+                continue;
+            }
+            stack.push((hash, true));
+            if let Some(node) = self.data.node(hash) {
+                stack.extend(
+                    node.children
+                        .iter()
+                        .filter(|child| !visited.contains(*child))
+                        .map(|child| (*child, false)),
+                );
+            }
+        }
+
+        post_order
+            .into_iter()
+            .rev()
+            .filter_map(|hash| {
+                self.data
+                    .node(hash)
+                    .map(|node| (EntryHash(hash), node.value.clone()))
+            })
+            .collect()
+    }
+
+    /// Get the hashes of all the entries the entry corresponding to the provided `hash`
+    /// was written atop, directly or not, i.e. its causal ancestors, if it exists.
+    pub(crate) fn ancestors(&self, hash: EntryHash) -> Option<BTreeSet<EntryHash>> {
+        let node = self.data.node(hash.0)?;
+        Some(
+            self.reachable(node.children.iter().copied())
+                .into_iter()
+                .map(EntryHash)
+                .collect(),
+        )
+    }
+
+    /// Get the hashes of the entries written concurrently to the entry corresponding to the
+    /// provided `hash`, i.e. neither its causal ancestors nor written atop it, if it exists.
+    pub(crate) fn concurrent(&self, hash: EntryHash) -> Option<BTreeSet<EntryHash>> {
+        let ancestors = self.ancestors(hash)?;
+        let current: Vec<_> = self
+            .data
+            .read()
+            .hashes_and_nodes()
+            .map(|(hash, _)| hash)
+            .collect();
+        let entries = self.reachable(current.into_iter());
+
+        // The entries written atop each entry, for its descendants to be found in one traversal.
+        let mut written_atop: BTreeMap<[u8; 32], Vec<[u8; 32]>> = BTreeMap::new();
+        for entry in &entries {
+            if let Some(node) = self.data.node(*entry) {
+                for parent in &node.children {
+                    written_atop.entry(*parent).or_default().push(*entry);
+                }
+            }
+        }
+        let mut descendants = BTreeSet::new();
+        let mut stack = vec![hash.0];
+        while let Some(entry) = stack.pop() {
+            for descendant in written_atop.get(&entry).into_iter().flatten() {
+                if descendants.insert(*descendant) {
+                    stack.push(*descendant);
+                }
+            }
+        }
+
+        Some(
+            entries
+                .into_iter()
+                .filter(|other| *other != hash.0 && !descendants.contains(other))
+                .map(EntryHash)
+                .filter(|other| !ancestors.contains(other))
+                .collect(),
+        )
+    }
+
+    /// Returns the branches of the register, keyed by their current entry, each made of the
+    /// entries only reachable from that current entry, i.e. written since the branch diverged
+    /// from the others, latest first. There's a single branch holding the whole history when
+    /// the register has no concurrent current entries.
+    pub(crate) fn branches(&self) -> BTreeMap<EntryHash, Vec<(EntryHash, Entry)>> {
+        let current: Vec<_> = self
+            .data
+            .read()
+            .hashes_and_nodes()
+            .map(|(hash, _)| hash)
+            .collect();
+        let reachable: Vec<_> = current
+            .iter()
+            .map(|hash| self.reachable(std::iter::once(*hash)))
+            .collect();
+        let history = self.history();
+
+        current
+            .iter()
+            .enumerate()
+            .map(|(index, hash)| {
+                let entries = history
+                    .iter()
+                    .filter(|(entry_hash, _)| {
+                        reachable[index].contains(&entry_hash.0)
+                            && reachable
+                                .iter()
+                                .enumerate()
+                                .all(|(other, set)| other == index || !set.contains(&entry_hash.0))
+                    })
+                    .cloned()
+                    .collect();
+                (EntryHash(*hash), entries)
+            })
+            .collect()
+    }
+
+    /// Returns the hashes of the given entries and of all the entries they were
+    /// written atop, directly or not.
+    fn reachable(&self, from: impl Iterator<Item = [u8; 32]>) -> BTreeSet<[u8; 32]> {
+        let mut reachable = BTreeSet::new();
+        let mut stack: Vec<_> = from.collect();
+        while let Some(hash) = stack.pop() {
+            if !reachable.insert(hash) {
+                continue;
+            }
+            if let Some(node) = self.data.node(hash) {
+                stack.extend(
+                    node.children
+                        .iter()
+                        .filter(|child| !reachable.contains(*child)),
+                );
+            }
+        }
+        reachable
+    }
 }
 
 #[cfg(test)]
@@ -192,4 +355,114 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn history_lists_latest_entries_first() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let address = RegisterAddress {
+            name: XorName::random(&mut rng),
+            tag: 0,
+        };
+        let mut crdt = RegisterCrdtImpl::new(address);
+
+        // Two branches written atop a first entry, then merged by a last one.
+        let (first, _) = crdt.write(vec![0x1], BTreeSet::new(), User::Anyone)?;
+        let (branch_1, _) = crdt.write(vec![0x2], BTreeSet::from([first]), User::Anyone)?;
+        let (branch_2, _) = crdt.write(vec![0x3], BTreeSet::from([first]), User::Anyone)?;
+        let (last, _) = crdt.write(
+            vec![0x4],
+            BTreeSet::from([branch_1, branch_2]),
+            User::Anyone,
+        )?;
+
+        let history: Vec<_> = crdt.history().into_iter().map(|(hash, _)| hash).collect();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[0], last);
+        assert_eq!(history[3], first);
+        assert!(history[1..3].contains(&branch_1));
+        assert!(history[1..3].contains(&branch_2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parents_are_the_entries_written_atop() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let address = RegisterAddress {
+            name: XorName::random(&mut rng),
+            tag: 0,
+        };
+        let mut crdt = RegisterCrdtImpl::new(address);
+
+        let (first, _) = crdt.write(vec![0x1], BTreeSet::new(), User::Anyone)?;
+        let (branch_1, _) = crdt.write(vec![0x2], BTreeSet::from([first]), User::Anyone)?;
+        let (branch_2, _) = crdt.write(vec![0x3], BTreeSet::from([first]), User::Anyone)?;
+        let (last, _) = crdt.write(
+            vec![0x4],
+            BTreeSet::from([branch_1, branch_2]),
+            User::Anyone,
+        )?;
+
+        assert_eq!(
+            crdt.parents(last),
+            Some(BTreeSet::from([branch_1, branch_2]))
+        );
+        assert_eq!(crdt.parents(branch_1), Some(BTreeSet::from([first])));
+        assert_eq!(crdt.parents(first), Some(BTreeSet::new()));
+        assert_eq!(crdt.parents(EntryHash([0; 32])), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn causality_of_concurrent_branches() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let address = RegisterAddress {
+            name: XorName::random(&mut rng),
+            tag: 0,
+        };
+        let mut crdt = RegisterCrdtImpl::new(address);
+
+        // A first entry, with a branch of two entries and another of one written atop it.
+        let (first, _) = crdt.write(vec![0x1], BTreeSet::new(), User::Anyone)?;
+        let (branch_1, _) = crdt.write(vec![0x2], BTreeSet::from([first]), User::Anyone)?;
+        let (branch_1_next, _) = crdt.write(vec![0x3], BTreeSet::from([branch_1]), User::Anyone)?;
+        let (branch_2, _) = crdt.write(vec![0x4], BTreeSet::from([first]), User::Anyone)?;
+
+        assert_eq!(
+            crdt.ancestors(branch_1_next),
+            Some(BTreeSet::from([branch_1, first]))
+        );
+        assert_eq!(crdt.ancestors(first), Some(BTreeSet::new()));
+        assert_eq!(
+            crdt.concurrent(branch_2),
+            Some(BTreeSet::from([branch_1, branch_1_next]))
+        );
+        assert_eq!(crdt.concurrent(branch_1), Some(BTreeSet::from([branch_2])));
+        assert_eq!(crdt.concurrent(first), Some(BTreeSet::new()));
+        assert_eq!(crdt.concurrent(EntryHash([0; 32])), None);
+
+        let branches = crdt.branches();
+        let hashes = |entries: &Vec<(EntryHash, Entry)>| -> Vec<EntryHash> {
+            entries.iter().map(|(hash, _)| *hash).collect()
+        };
+        assert_eq!(branches.len(), 2);
+        assert_eq!(
+            hashes(&branches[&branch_1_next]),
+            vec![branch_1_next, branch_1]
+        );
+        assert_eq!(hashes(&branches[&branch_2]), vec![branch_2]);
+
+        // Once merged, the whole history is a single branch.
+        let (merged, _) = crdt.write(
+            vec![0x5],
+            BTreeSet::from([branch_1_next, branch_2]),
+            User::Anyone,
+        )?;
+        let branches = crdt.branches();
+        assert_eq!(branches.len(), 1);
+        assert_eq!(branches[&merged].len(), 5);
+
+        Ok(())
+    }
 }
@@ -18,7 +18,10 @@ use super::{reg_crdt::RegisterCrdtImpl, Result};
 
 use self_encryption::MIN_ENCRYPTABLE_BYTES;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeSet, hash::Hash};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    hash::Hash,
+};
 
 use xor_name::XorName;
 
@@ -34,6 +37,7 @@ pub(crate) struct RegisterReplica {
     authority: User,
     crdt: RegisterCrdtImpl,
     policy: Policy,
+    ownership_transfers: u64,
 }
 
 impl From<Register> for RegisterReplica {
@@ -42,6 +46,7 @@ impl From<Register> for RegisterReplica {
             authority: reg.authority,
             crdt: reg.crdt.into(),
             policy: reg.policy,
+            ownership_transfers: reg.ownership_transfers,
         }
     }
 }
@@ -54,6 +59,7 @@ impl Into<Register> for RegisterReplica {
             authority: self.authority,
             crdt: self.crdt.into(),
             policy: self.policy,
+            ownership_transfers: self.ownership_transfers,
         }
     }
 }
@@ -66,6 +72,7 @@ impl RegisterReplica {
             authority,
             crdt: RegisterCrdtImpl::new(address),
             policy,
+            ownership_transfers: 0,
         }
     }
 
@@ -118,11 +125,38 @@ impl RegisterReplica {
         self.crdt.get(hash).ok_or(Error::NoSuchEntry(hash))
     }
 
+    /// Return the hashes of the entries the entry of the provided 'hash' was written atop,
+    /// i.e. its parents.
+    pub(crate) fn parents(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.crdt.parents(hash).ok_or(Error::NoSuchEntry(hash))
+    }
+
     /// Read the last entry, or entries when there are branches, if the register is not empty.
     pub(crate) fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.crdt.read()
     }
 
+    /// Return all the entries, latest first.
+    pub(crate) fn history(&self) -> Vec<(EntryHash, Entry)> {
+        self.crdt.history()
+    }
+
+    /// Return the hashes of all the entries the entry of the provided 'hash' was written
+    /// atop, directly or not.
+    pub(crate) fn ancestors(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.crdt.ancestors(hash).ok_or(Error::NoSuchEntry(hash))
+    }
+
+    /// Return the hashes of the entries written concurrently to the entry of the provided 'hash'.
+    pub(crate) fn concurrent(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.crdt.concurrent(hash).ok_or(Error::NoSuchEntry(hash))
+    }
+
+    /// Return the entries of each branch since it diverged, keyed by its latest entry.
+    pub(crate) fn branches(&self) -> BTreeMap<EntryHash, Vec<(EntryHash, Entry)>> {
+        self.crdt.branches()
+    }
+
     /// Return user permissions, if applicable.
     pub(crate) fn permissions(&self, user: User) -> Result<Permissions> {
         if user == self.policy.owner {
@@ -162,8 +196,25 @@ impl RegisterReplica {
         self.crdt.apply_op(op)
     }
 
-    /// Merge another Register into this one.
+    /// Return the number of times the ownership of the Register was transferred,
+    /// i.e. the sequence number of the next transfer.
+    pub(crate) fn ownership_transfers(&self) -> u64 {
+        self.ownership_transfers
+    }
+
+    /// Set the given user as the new owner of the Register.
+    /// Permissions over the transfer are expected to be checked by the caller.
+    pub(crate) fn transfer_ownership(&mut self, new_owner: User) {
+        self.policy.owner = new_owner;
+        self.ownership_transfers += 1;
+    }
+
+    /// Merge another Register into this one, taking its owner if it went through more transfers.
     pub(crate) fn merge(&mut self, other: Self) {
+        if other.ownership_transfers > self.ownership_transfers {
+            self.policy.owner = other.policy.owner;
+            self.ownership_transfers = other.ownership_transfers;
+        }
         self.crdt.merge(other.crdt);
     }
 
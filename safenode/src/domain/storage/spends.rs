@@ -29,8 +29,8 @@ use tokio::{
 };
 use tracing::trace;
 
-const VALID_SPENDS_STORE_DIR_NAME: &str = "valid_spends";
-const DOUBLE_SPENDS_STORE_DIR_NAME: &str = "double_spends";
+pub(crate) const VALID_SPENDS_STORE_DIR_NAME: &str = "valid_spends";
+pub(crate) const DOUBLE_SPENDS_STORE_DIR_NAME: &str = "double_spends";
 
 /// Storage of Dbc spends.
 ///
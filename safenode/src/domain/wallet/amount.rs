@@ -0,0 +1,185 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sn_dbc::Token;
+use std::{fmt, str::FromStr};
+
+/// The number of nanos, the indivisible units of the amounts, in a token.
+const NANOS_PER_TOKEN: u64 = 1_000_000_000;
+/// The number of decimals of an amount in tokens, i.e. of nanos.
+const DECIMALS: usize = 9;
+/// The suffix of amounts given in nanos rather than in tokens, e.g. `1337nano`.
+const NANO_SUFFIX: &str = "nano";
+
+/// An amount of tokens, as given by and shown to users: in tokens with up to nine decimals,
+/// e.g. `1.337`, or in nanos with a `nano` suffix, e.g. `1337000000nano`.
+/// It's serialized the same way, as a string in tokens, so that the amounts in the JSON
+/// documents of the CLI can't be taken for nanos, which they are a billion times apart from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenAmount(u64);
+
+impl TokenAmount {
+    /// The amount of the given number of nanos.
+    pub const fn from_nano(nanos: u64) -> Self {
+        Self(nanos)
+    }
+
+    /// The number of nanos of the amount.
+    pub const fn as_nano(self) -> u64 {
+        self.0
+    }
+
+    /// Whether the amount is zero.
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The sum of the amounts, or `None` if it overflows.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+}
+
+impl From<Token> for TokenAmount {
+    fn from(token: Token) -> Self {
+        Self(token.as_nano())
+    }
+}
+
+impl From<TokenAmount> for Token {
+    fn from(amount: TokenAmount) -> Self {
+        Token::from_nano(amount.0)
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    /// Formats the amount in tokens, without trailing zeros, e.g. `1.337` or `2`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tokens = self.0 / NANOS_PER_TOKEN;
+        let nanos = self.0 % NANOS_PER_TOKEN;
+        if nanos == 0 {
+            return write!(f, "{tokens}");
+        }
+        let decimals = format!("{nanos:0DECIMALS$}");
+        write!(f, "{tokens}.{}", decimals.trim_end_matches('0'))
+    }
+}
+
+impl FromStr for TokenAmount {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidAmount(s.to_string());
+        let amount = s.trim();
+        if let Some(nanos) = amount.strip_suffix(NANO_SUFFIX) {
+            return parse_digits(nanos.trim()).map(Self).ok_or_else(invalid);
+        }
+
+        let (tokens, decimals) = amount.split_once('.').unwrap_or((amount, ""));
+        if (tokens.is_empty() && decimals.is_empty()) || decimals.len() > DECIMALS {
+            return Err(invalid());
+        }
+        let tokens = if tokens.is_empty() {
+            0
+        } else {
+            parse_digits(tokens).ok_or_else(invalid)?
+        };
+        let nanos = if decimals.is_empty() {
+            0
+        } else {
+            let padded = format!("{decimals:0<DECIMALS$}");
+            parse_digits(&padded).ok_or_else(invalid)?
+        };
+        tokens
+            .checked_mul(NANOS_PER_TOKEN)
+            .and_then(|tokens| tokens.checked_add(nanos))
+            .map(Self)
+            .ok_or_else(invalid)
+    }
+}
+
+/// Parses a number made only of digits, unlike `u64::from_str` which also takes a sign.
+fn parse_digits(digits: &str) -> Option<u64> {
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+impl Serialize for TokenAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TokenAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let amount = String::deserialize(deserializer)?;
+        amount.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amounts_are_parsed_and_formatted_in_tokens() -> Result<()> {
+        for (amount, nanos, formatted) in [
+            ("1.337", 1_337_000_000, "1.337"),
+            ("2", 2_000_000_000, "2"),
+            ("2.000", 2_000_000_000, "2"),
+            (".5", 500_000_000, "0.5"),
+            ("0.000000001", 1, "0.000000001"),
+            (" 1337nano ", 1337, "0.000001337"),
+            (
+                "18446744073709551615nano",
+                u64::MAX,
+                "18446744073.709551615",
+            ),
+        ] {
+            let parsed: TokenAmount = amount.parse()?;
+            assert_eq!(nanos, parsed.as_nano());
+            assert_eq!(formatted, parsed.to_string());
+            assert_eq!(parsed, formatted.parse()?);
+        }
+
+        for invalid in [
+            "",
+            ".",
+            "-1",
+            "+1",
+            "1.0000000001",
+            "1,5",
+            "1.2.3",
+            "nano",
+            "1.5nano",
+            "99999999999",
+        ] {
+            assert!(
+                matches!(invalid.parse::<TokenAmount>(), Err(Error::InvalidAmount(_))),
+                "{invalid:?} should not be a valid amount"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn amounts_are_serialized_as_strings_in_tokens() -> Result<()> {
+        let amount = TokenAmount::from_nano(1_500_000_000);
+        let json = serde_json::to_string(&amount).expect("Amounts should serialize.");
+        assert_eq!("\"1.5\"", json);
+        let deserialized: TokenAmount =
+            serde_json::from_str(&json).expect("Amounts should deserialize.");
+        assert_eq!(amount, deserialized);
+        Ok(())
+    }
+}
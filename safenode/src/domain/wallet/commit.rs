@@ -0,0 +1,121 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::Result;
+
+use std::path::{Path, PathBuf};
+use tokio::{fs, io::AsyncWriteExt};
+
+/// Name of the dir within the wallet dir, the files of the wallet are written to before
+/// they are moved in place all together.
+const STAGING_DIR_NAME: &str = "staging";
+/// Filename of the marker written to the staging dir once all the files were written to it.
+const COMMITTED_FILE_NAME: &str = "committed";
+
+/// Writes the content to a temporary file next to the given path, which is then renamed to it.
+/// The file at the path thus either has its previous content or the new one, never a part of it.
+pub(super) async fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp_path).await?;
+    file.write_all(content).await?;
+    file.sync_all().await?;
+    fs::rename(tmp_path, path).await?;
+    Ok(())
+}
+
+/// Starts a commit of the files of the wallet, returning the dir they are to be written to.
+/// Anything left over from an unfinished commit is discarded.
+pub(super) async fn begin_commit(wallet_dir: &Path) -> Result<PathBuf> {
+    let staging_dir = wallet_dir.join(STAGING_DIR_NAME);
+    if staging_dir.is_dir() {
+        fs::remove_dir_all(&staging_dir).await?;
+    }
+    fs::create_dir_all(&staging_dir).await?;
+    Ok(staging_dir)
+}
+
+/// Commits the files written to the staging dir, moving them to the wallet dir.
+/// Once the commit is marked, the files are moved even if the process stops half way,
+/// as the move is finished by `recover_commit` when the wallet is next loaded.
+pub(super) async fn finish_commit(wallet_dir: &Path) -> Result<()> {
+    let staging_dir = wallet_dir.join(STAGING_DIR_NAME);
+    write_atomically(&staging_dir.join(COMMITTED_FILE_NAME), &[]).await?;
+    move_staged_files(wallet_dir, &staging_dir).await
+}
+
+/// Finishes the commit the process stopped in the middle of, if any. The staged files of
+/// a commit which was not marked are discarded, leaving the wallet in its previous state.
+pub(super) async fn recover_commit(wallet_dir: &Path) -> Result<()> {
+    let staging_dir = wallet_dir.join(STAGING_DIR_NAME);
+    if !staging_dir.is_dir() {
+        return Ok(());
+    }
+    if staging_dir.join(COMMITTED_FILE_NAME).is_file() {
+        warn!("Finishing the interrupted commit of the wallet at {wallet_dir:?}");
+        move_staged_files(wallet_dir, &staging_dir).await
+    } else {
+        warn!("Discarding the unfinished commit of the wallet at {wallet_dir:?}");
+        fs::remove_dir_all(staging_dir).await?;
+        Ok(())
+    }
+}
+
+/// Moves the staged files to the wallet dir, then removes the staging dir along with the marker.
+async fn move_staged_files(wallet_dir: &Path, staging_dir: &Path) -> Result<()> {
+    let mut entries = fs::read_dir(staging_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        if name != COMMITTED_FILE_NAME && entry.file_type().await?.is_file() {
+            fs::rename(entry.path(), wallet_dir.join(name)).await?;
+        }
+    }
+    fs::remove_dir_all(staging_dir).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use assert_fs::TempDir;
+
+    #[tokio::test]
+    async fn commits_are_rolled_forward_only_once_marked() -> Result<()> {
+        let dir = TempDir::new().expect("Should be able to create a temp dir.");
+        let wallet_dir = dir.path();
+        write_atomically(&wallet_dir.join("wallet"), b"previous").await?;
+
+        // The process stopped before all the files were staged.
+        let staging_dir = begin_commit(wallet_dir).await?;
+        write_atomically(&staging_dir.join("wallet"), b"unfinished").await?;
+        recover_commit(wallet_dir).await?;
+        assert_eq!(
+            b"previous".to_vec(),
+            fs::read(wallet_dir.join("wallet")).await?
+        );
+        assert!(!staging_dir.exists());
+
+        // The process stopped after the commit was marked, before the files were moved.
+        let staging_dir = begin_commit(wallet_dir).await?;
+        write_atomically(&staging_dir.join("wallet"), b"committed").await?;
+        write_atomically(&staging_dir.join("history"), b"committed").await?;
+        write_atomically(&staging_dir.join(COMMITTED_FILE_NAME), &[]).await?;
+        recover_commit(wallet_dir).await?;
+        assert_eq!(
+            b"committed".to_vec(),
+            fs::read(wallet_dir.join("wallet")).await?
+        );
+        assert_eq!(
+            b"committed".to_vec(),
+            fs::read(wallet_dir.join("history")).await?
+        );
+        assert!(!staging_dir.exists());
+
+        Ok(())
+    }
+}
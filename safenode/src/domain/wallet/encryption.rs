@@ -0,0 +1,122 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Encryption of the wallet files with a key derived from a passphrase.
+//! An encrypted file is made of a header identifying it as such, the salt the key was derived
+//! with (using Argon2), and the nonce and ciphertext of the content (using ChaCha20Poly1305).
+
+use super::{
+    commit::write_atomically,
+    error::{Error, Result},
+};
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::RngCore;
+use std::path::Path;
+use tokio::fs;
+
+/// The header of encrypted files, including the version of the format.
+const HEADER: &[u8] = b"SAFE-WALLET-ENC-1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Whether the content was encrypted with a passphrase.
+pub(super) fn is_encrypted(content: &[u8]) -> bool {
+    content.starts_with(HEADER)
+}
+
+/// Write the content to the file, encrypting it if a passphrase is given.
+pub(super) async fn write_file(
+    path: &Path,
+    content: &[u8],
+    passphrase: Option<&str>,
+) -> Result<()> {
+    match passphrase {
+        Some(passphrase) => write_atomically(path, &encrypt(content, passphrase)?).await,
+        None => write_atomically(path, content).await,
+    }
+}
+
+/// Read the content of the file, decrypting it if it was encrypted.
+pub(super) async fn read_file(path: &Path, passphrase: Option<&str>) -> Result<Vec<u8>> {
+    let content = fs::read(path).await?;
+    if !is_encrypted(&content) {
+        return Ok(content);
+    }
+    let passphrase = passphrase.ok_or(Error::PassphraseRequired)?;
+    decrypt(&content, passphrase)
+}
+
+fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut rng = rand::thread_rng();
+    let mut salt = [0; SALT_LEN];
+    rng.fill_bytes(&mut salt);
+    let mut nonce = [0; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+
+    let cipher = cipher(passphrase, &salt)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|err| Error::Encryption(err.to_string()))?;
+
+    let mut content = HEADER.to_vec();
+    content.extend_from_slice(&salt);
+    content.extend_from_slice(&nonce);
+    content.extend(ciphertext);
+    Ok(content)
+}
+
+fn decrypt(content: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let content = &content[HEADER.len()..];
+    if content.len() < SALT_LEN + NONCE_LEN {
+        return Err(Error::Encryption(
+            "The encrypted file is truncated".to_string(),
+        ));
+    }
+    let (salt, content) = content.split_at(SALT_LEN);
+    let (nonce, ciphertext) = content.split_at(NONCE_LEN);
+
+    let cipher = cipher(passphrase, salt)?;
+    // Authentication fails when the key, thus the passphrase, is not the one encrypted with.
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| Error::WrongPassphrase)
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| Error::Encryption(err.to_string()))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_content_only_decrypts_with_the_same_passphrase() -> Result<()> {
+        let plaintext = b"the secrets of the wallet";
+        let encrypted = encrypt(plaintext, "correct horse")?;
+
+        assert!(is_encrypted(&encrypted));
+        assert!(!is_encrypted(plaintext));
+        assert_eq!(decrypt(&encrypted, "correct horse")?, plaintext);
+        assert!(matches!(
+            decrypt(&encrypted, "battery staple"),
+            Err(Error::WrongPassphrase)
+        ));
+
+        Ok(())
+    }
+}
@@ -23,6 +23,9 @@ pub enum Error {
     /// A general error when verifying a transfer validity in the network.
     #[error("Failed to verify transfer validity in the network {0}")]
     CouldNotVerifyTransfer(String),
+    /// A general error when reading the inbox of an address in the network.
+    #[error("Failed to read the inbox in the network {0}")]
+    CouldNotReadInbox(String),
     /// Failed to parse bytes into a bls key.
     #[error("Failed to parse bls key")]
     FailedToParseBlsKey,
@@ -41,6 +44,53 @@ pub enum Error {
     /// Bincode error.
     #[error("Bincode error:: {0}")]
     Bincode(#[from] bincode::Error),
+    /// The wallet files are encrypted, but no passphrase was given.
+    #[error("The wallet is encrypted, a passphrase is required")]
+    PassphraseRequired,
+    /// The wallet files could not be decrypted with the given passphrase.
+    #[error("The passphrase is not the one the wallet was encrypted with")]
+    WrongPassphrase,
+    /// The amount is neither in tokens with up to nine decimals nor in nanos.
+    #[error("Invalid amount {0:?}, expected tokens with up to nine decimals, e.g. 1.337, or nanos, e.g. 1337nano")]
+    InvalidAmount(String),
+    /// The mnemonic is not a valid BIP39 mnemonic.
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+    /// The name of a wallet is empty, or not made of letters, digits, `-` and `_` only.
+    #[error("Invalid wallet name {0:?}, expected letters, digits, '-' and '_' only")]
+    InvalidWalletName(String),
+    /// A wallet with another key already exists where one is to be restored.
+    #[error("A wallet with another key already exists")]
+    WalletAlreadyExists,
+    /// The wallet was stored by a newer release, whose format this one can't read.
+    #[error("The wallet has version {0} of the format, the client has to be upgraded to open it")]
+    UnsupportedWalletVersion(u32),
+    /// The version the wallet is stamped with is not a number.
+    #[error("Invalid wallet format version: {0}")]
+    InvalidWalletVersion(String),
+    /// No watch-only wallet was created in the dir.
+    #[error("No watch-only wallet exists, it has to be created first")]
+    WatchOnlyWalletNotFound,
+    /// Another wallet instance is using the wallet, and didn't release it in time.
+    #[error("The wallet is in use by another wallet instance")]
+    WalletLocked,
+    /// Failed to encrypt or decrypt the wallet files.
+    #[error("Wallet encryption error: {0}")]
+    Encryption(String),
+    /// The transfer file is malformed or corrupted.
+    #[error("Invalid transfer file: {0}")]
+    InvalidTransferFile(String),
+    /// The transfer file was written by a newer client, with a format this one can't read.
+    #[error(
+        "The transfer file has version {0} of the format, the client has to be upgraded to read it"
+    )]
+    UnsupportedTransferFileVersion(u16),
+    /// The receipt of a payment for storage doesn't prove the payment.
+    #[error("Invalid payment receipt: {0}")]
+    InvalidPaymentReceipt(String),
+    /// No payment for storing the data at the address was made by the wallet.
+    #[error("No payment was made for storing the data at {0:?}")]
+    PaymentReceiptNotFound(xor_name::XorName),
     /// I/O error.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
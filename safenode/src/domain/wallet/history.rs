@@ -0,0 +1,127 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_dbc::{DbcId, PublicAddress, Token};
+
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Whether tokens left or entered the wallet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferDirection {
+    /// Tokens were sent to another address.
+    Sent,
+    /// Tokens were deposited into the wallet.
+    Received,
+}
+
+/// A transfer the wallet took part in, as recorded in its history.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Whether the tokens were sent or received.
+    pub direction: TransferDirection,
+    /// The amount of tokens transferred.
+    pub amount: Token,
+    /// The address the tokens were sent to. The sender of received
+    /// tokens can't be told from the dbcs, thus it's unknown for them.
+    pub counterpart: Option<PublicAddress>,
+    /// When the transfer was made, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The ids of the dbcs resulting from the transfer: those
+    /// created for the recipient, or those received.
+    pub dbc_ids: Vec<DbcId>,
+}
+
+impl HistoryEntry {
+    pub(super) fn now(
+        direction: TransferDirection,
+        amount: Token,
+        counterpart: Option<PublicAddress>,
+        dbc_ids: Vec<DbcId>,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self {
+            direction,
+            amount,
+            counterpart,
+            timestamp,
+            dbc_ids,
+        }
+    }
+}
+
+/// Selects the history entries of a statement, e.g. the transfers received over a month.
+/// Each criterion left unset matches every entry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    /// Only the transfers in this direction.
+    pub direction: Option<TransferDirection>,
+    /// Only the transfers made at or after this time, in seconds since the Unix epoch.
+    pub since: Option<u64>,
+    /// Only the transfers made before this time, in seconds since the Unix epoch.
+    pub until: Option<u64>,
+}
+
+impl HistoryFilter {
+    /// Whether the entry meets all the criteria of the filter.
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.direction
+            .map_or(true, |direction| direction == entry.direction)
+            && self.since.map_or(true, |since| entry.timestamp >= since)
+            && self.until.map_or(true, |until| entry.timestamp < until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_filter_matches_direction_and_time_range() {
+        let entry = HistoryEntry {
+            direction: TransferDirection::Received,
+            amount: Token::from_nano(100),
+            counterpart: None,
+            timestamp: 1_000,
+            dbc_ids: vec![],
+        };
+
+        assert!(HistoryFilter::default().matches(&entry));
+
+        let received = HistoryFilter {
+            direction: Some(TransferDirection::Received),
+            ..Default::default()
+        };
+        assert!(received.matches(&entry));
+        let sent = HistoryFilter {
+            direction: Some(TransferDirection::Sent),
+            ..Default::default()
+        };
+        assert!(!sent.matches(&entry));
+
+        let within = HistoryFilter {
+            since: Some(1_000),
+            until: Some(1_001),
+            ..Default::default()
+        };
+        assert!(within.matches(&entry));
+        let after = HistoryFilter {
+            since: Some(1_001),
+            ..Default::default()
+        };
+        assert!(!after.matches(&entry));
+        let before = HistoryFilter {
+            until: Some(1_000),
+            ..Default::default()
+        };
+        assert!(!before.matches(&entry));
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Each address has an inbox on the network, which is a register that anyone can write to,
+//! at a name derived from the address. A sender uploads the dbc created for the recipient as
+//! a chunk, encrypted to the key of the recipient, and writes the address of the chunk to the
+//! inbox. The recipient then scans its inboxes for dbcs it hasn't deposited yet.
+
+use super::error::{Error, Result};
+
+use sn_dbc::{Dbc, MainKey, PublicAddress};
+
+use xor_name::XorName;
+
+/// The type tag of the inbox registers.
+pub const INBOX_REGISTER_TAG: u64 = 0x1b0c;
+
+/// The name of the inbox register of the address.
+pub fn inbox_name(address: &PublicAddress) -> XorName {
+    XorName::from_content(&[b"inbox".as_slice(), address.to_bytes().as_slice()].concat())
+}
+
+/// Encrypts the dbc to the key of its recipient, to be delivered to its inbox.
+pub fn encrypt_for_inbox(dbc: &Dbc) -> Result<Vec<u8>> {
+    let bytes: [u8; bls::PK_SIZE] = dbc
+        .public_address()
+        .to_bytes()
+        .as_slice()
+        .try_into()
+        .map_err(|_| Error::FailedToParseBlsKey)?;
+    let public_key = bls::PublicKey::from_bytes(bytes)?;
+    let ciphertext = public_key.encrypt(dbc.to_hex()?.as_bytes());
+    Ok(bincode::serialize(&ciphertext)?)
+}
+
+/// Decrypts a dbc delivered to an inbox, if it was encrypted to any of the given keys.
+pub(super) fn decrypt_from_inbox(encrypted: &[u8], keys: &[MainKey]) -> Option<Dbc> {
+    let ciphertext: bls::Ciphertext = bincode::deserialize(encrypted).ok()?;
+    keys.iter().find_map(|key| {
+        let bytes: [u8; bls::SK_SIZE] = key.to_bytes().as_slice().try_into().ok()?;
+        let secret_key = bls::SecretKey::from_bytes(bytes).ok()?;
+        let hex = secret_key.decrypt(&ciphertext)?;
+        Dbc::from_hex(std::str::from_utf8(&hex).ok()?).ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::dbc_genesis::create_first_dbc_from_key;
+
+    #[test]
+    fn only_the_recipient_decrypts_dbcs_delivered_to_its_inbox() -> eyre::Result<()> {
+        let recipient = MainKey::random();
+        let dbc = create_first_dbc_from_key(&recipient).expect("Genesis creation to succeed.");
+
+        let encrypted = encrypt_for_inbox(&dbc)?;
+
+        assert_eq!(None, decrypt_from_inbox(&encrypted, &[MainKey::random()]));
+        assert_eq!(
+            Some(dbc),
+            decrypt_from_inbox(&encrypted, &[MainKey::random(), recipient])
+        );
+
+        Ok(())
+    }
+}
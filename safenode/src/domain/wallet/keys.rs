@@ -6,11 +6,18 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::error::{Error, Result};
+use super::{
+    commit::write_atomically,
+    encryption::{is_encrypted, read_file, write_file},
+    error::{Error, Result},
+};
 
+use bip39::Mnemonic;
 use sn_dbc::{MainKey, PublicAddress};
 
 use hex::{decode, encode};
+use hkdf::Hkdf;
+use sha2::{Digest, Sha256};
 use std::path::Path;
 use tokio::fs;
 
@@ -18,6 +25,13 @@ use tokio::fs;
 const MAIN_KEY_FILENAME: &str = "main_key";
 /// Filename for storing the node's reward (BLS hex-encoded) public address.
 const PUBLIC_ADDRESS_FILENAME: &str = "public_address";
+const MNEMONIC_FILENAME: &str = "mnemonic";
+/// The number of words of the mnemonics generated for new wallets.
+const MNEMONIC_WORD_COUNT: usize = 24;
+/// The salt of the EIP-2333 key derivation, hashed before each attempt at deriving a key.
+const KEYGEN_SALT: &[u8] = b"BLS-SIG-KEYGEN-SALT-";
+/// The number of bytes expanded by the EIP-2333 key derivation, reduced to a key.
+const KEYGEN_OKM_SIZE: usize = 48;
 
 /// Parse a public address from a hex-encoded string.
 pub fn parse_public_address<T: AsRef<[u8]>>(hex: T) -> Result<PublicAddress> {
@@ -26,32 +40,124 @@ pub fn parse_public_address<T: AsRef<[u8]>>(hex: T) -> Result<PublicAddress> {
 }
 
 /// Writes the public address and main key (hex-encoded) to different locations at disk.
-pub(super) async fn store_new_keypair(wallet_dir: &Path, main_key: &MainKey) -> Result<()> {
+pub(super) async fn store_new_keypair(
+    wallet_dir: &Path,
+    main_key: &MainKey,
+    passphrase: Option<&str>,
+) -> Result<()> {
     let secret_key_path = wallet_dir.join(MAIN_KEY_FILENAME);
     let public_key_path = wallet_dir.join(PUBLIC_ADDRESS_FILENAME);
-    fs::write(secret_key_path, encode(main_key.to_bytes())).await?;
-    fs::write(
-        public_key_path,
-        encode(main_key.public_address().to_bytes()),
+    write_file(
+        &secret_key_path,
+        encode(main_key.to_bytes()).as_bytes(),
+        passphrase,
+    )
+    .await?;
+    write_atomically(
+        &public_key_path,
+        encode(main_key.public_address().to_bytes()).as_bytes(),
     )
     .await
-    .map_err(|e| Error::FailedToHexEncodeKey(e.to_string()))?;
-    Ok(())
 }
 
 /// Returns Some(sn_dbc::MainKey) or None if file doesn't exist. It assumes it's hex-encoded.
-pub(super) async fn get_main_key(wallet_dir: &Path) -> Result<Option<MainKey>> {
+pub(super) async fn get_main_key(
+    wallet_dir: &Path,
+    passphrase: Option<&str>,
+) -> Result<Option<MainKey>> {
     let path = wallet_dir.join(MAIN_KEY_FILENAME);
     if !path.is_file() {
         return Ok(None);
     }
 
-    let secret_hex_bytes = fs::read(&path).await?;
+    let secret_hex_bytes = read_file(&path, passphrase).await?;
     let secret = bls_secret_from_hex(secret_hex_bytes)?;
 
     Ok(Some(MainKey::new(secret)))
 }
 
+/// Generates a new mnemonic, to derive the main key of a new wallet from.
+pub(super) fn generate_mnemonic() -> Result<Mnemonic> {
+    Mnemonic::generate(MNEMONIC_WORD_COUNT).map_err(|err| Error::InvalidMnemonic(err.to_string()))
+}
+
+/// Parses a BIP39 mnemonic of 12 to 24 english words.
+pub(super) fn parse_mnemonic(phrase: &str) -> Result<Mnemonic> {
+    Mnemonic::parse_normalized(phrase).map_err(|err| Error::InvalidMnemonic(err.to_string()))
+}
+
+/// Derives the main key from the BIP39 seed of the mnemonic, with no passphrase, as the
+/// master secret key of EIP-2333, i.e. the key other BLS wallets derive from the same seed.
+pub(super) fn main_key_from_mnemonic(mnemonic: &Mnemonic) -> MainKey {
+    MainKey::new(derive_master_secret_key(&mnemonic.to_seed("")))
+}
+
+/// The `derive_master_SK` function of EIP-2333, i.e. `HKDF_mod_r` of the seed, with no key info.
+fn derive_master_secret_key(seed: &[u8]) -> bls::SecretKey {
+    let mut ikm = seed.to_vec();
+    ikm.push(0);
+    let info = (KEYGEN_OKM_SIZE as u16).to_be_bytes();
+    let mut salt = Sha256::digest(KEYGEN_SALT);
+    loop {
+        let mut okm = [0; KEYGEN_OKM_SIZE];
+        // Expanding fails only for more than 255 times the size of a hash.
+        let _ = Hkdf::<Sha256>::new(Some(salt.as_slice()), &ikm).expand(&info, &mut okm);
+        let mut secret = fr_from_be_bytes(&okm);
+        // A zero key is not valid, so another one is derived with the hash of the salt.
+        if secret != bls::Fr::from(0u64) {
+            return bls::SecretKey::from_mut(&mut secret);
+        }
+        salt = Sha256::digest(salt);
+    }
+}
+
+/// The big endian integer of the bytes, reduced modulo the order of the BLS12-381 curve.
+fn fr_from_be_bytes(bytes: &[u8]) -> bls::Fr {
+    // 2^64, each limb of 64 bits being smaller than the order.
+    let limb_base = bls::Fr::from(u64::MAX) + bls::Fr::from(1u64);
+    bytes.chunks(8).fold(bls::Fr::from(0u64), |value, chunk| {
+        let mut limb = [0; 8];
+        limb[8 - chunk.len()..].copy_from_slice(chunk);
+        value * limb_base + bls::Fr::from(u64::from_be_bytes(limb))
+    })
+}
+
+/// Writes the mnemonic the main key was derived from, for it to be backed up.
+pub(super) async fn store_mnemonic(
+    wallet_dir: &Path,
+    mnemonic: &Mnemonic,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let path = wallet_dir.join(MNEMONIC_FILENAME);
+    write_file(&path, mnemonic.to_string().as_bytes(), passphrase).await
+}
+
+/// Returns the mnemonic the main key was derived from, which wallets created
+/// before keys were derived from mnemonics don't have.
+pub(super) async fn get_mnemonic(
+    wallet_dir: &Path,
+    passphrase: Option<&str>,
+) -> Result<Option<Mnemonic>> {
+    let path = wallet_dir.join(MNEMONIC_FILENAME);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let phrase = read_file(&path, passphrase).await?;
+    let phrase =
+        String::from_utf8(phrase).map_err(|err| Error::InvalidMnemonic(err.to_string()))?;
+    parse_mnemonic(&phrase).map(Some)
+}
+
+/// Whether the main key in the wallet dir is encrypted with a passphrase.
+/// The other wallet files are encrypted along with it.
+pub(super) async fn is_main_key_encrypted(wallet_dir: &Path) -> Result<bool> {
+    let path = wallet_dir.join(MAIN_KEY_FILENAME);
+    if !path.is_file() {
+        return Ok(false);
+    }
+    Ok(is_encrypted(&fs::read(&path).await?))
+}
+
 /// Construct a BLS secret key from a hex-encoded string.
 fn bls_secret_from_hex<T: AsRef<[u8]>>(hex: T) -> Result<bls::SecretKey> {
     let bytes = decode(hex).map_err(|_| Error::FailedToDecodeHexToKey)?;
@@ -76,18 +182,56 @@ fn bls_public_from_hex<T: AsRef<[u8]>>(hex: T) -> Result<bls::PublicKey> {
 
 #[cfg(test)]
 mod test {
-    use super::{get_main_key, store_new_keypair, MainKey};
+    use super::{
+        derive_master_secret_key, generate_mnemonic, get_main_key, main_key_from_mnemonic,
+        parse_mnemonic, store_new_keypair, MainKey,
+    };
 
     use assert_fs::TempDir;
     use eyre::Result;
 
+    #[test]
+    fn main_key_from_mnemonic_is_deterministic() -> Result<()> {
+        let mnemonic = generate_mnemonic()?;
+        let restored = parse_mnemonic(&mnemonic.to_string())?;
+
+        assert_eq!(24, mnemonic.word_count());
+        assert_eq!(
+            main_key_from_mnemonic(&mnemonic).public_address(),
+            main_key_from_mnemonic(&restored).public_address()
+        );
+        assert_ne!(
+            main_key_from_mnemonic(&mnemonic).public_address(),
+            main_key_from_mnemonic(&generate_mnemonic()?).public_address()
+        );
+        assert!(parse_mnemonic("not a valid mnemonic").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn master_secret_key_matches_eip_2333_test_vector() -> Result<()> {
+        // Test case 0 of EIP-2333.
+        let seed = hex::decode(
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e5349553\
+             1f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        )?;
+        let secret_key = derive_master_secret_key(&seed);
+        // 6083874454709270928345386274498605044986640685124978867557563392430687146096
+        assert_eq!(
+            hex::encode(secret_key.to_bytes()),
+            "0d7359d57963ab8fbbde1852dcf553fedbc31f464d80ee7d40ae683122b45070"
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn reward_key_to_and_from_file() -> Result<()> {
         let main_key = MainKey::random();
         let dir = create_temp_dir();
         let root_dir = dir.path().to_path_buf();
-        store_new_keypair(&root_dir, &main_key).await?;
-        let secret_result = get_main_key(&root_dir)
+        store_new_keypair(&root_dir, &main_key, None).await?;
+        let secret_result = get_main_key(&root_dir, None)
             .await?
             .expect("There to be a key on disk.");
         assert_eq!(secret_result.public_address(), main_key.public_address());
@@ -7,24 +7,41 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    keys::{get_main_key, store_new_keypair},
+    commit::{begin_commit, finish_commit, recover_commit},
+    inbox::decrypt_from_inbox,
+    keys::{
+        generate_mnemonic, get_main_key, get_mnemonic, is_main_key_encrypted,
+        main_key_from_mnemonic, parse_mnemonic, store_mnemonic, store_new_keypair,
+    },
+    lock::WalletLock,
+    migration::migrate,
+    payment_reason,
+    receive_address::dbc_derived_key,
     wallet_file::{
-        create_received_dbcs_dir, get_wallet, load_received_dbcs, store_created_dbcs, store_wallet,
+        create_received_dbcs_dir, get_history, get_payment_receipts, get_receive_addresses,
+        get_wallet, load_received_dbcs, store_created_dbcs, store_history, store_payment_receipts,
+        store_receive_addresses, store_transfer_file, store_wallet,
     },
-    DepositWallet, KeyLessWallet, Result, SendClient, SendWallet, SigningWallet, Wallet,
+    DepositWallet, Error, HistoryEntry, HistoryFilter, KeyLessWallet, OfflineTransfer,
+    PaymentReceipt, ReceiveAddress, Result, SendClient, SendWallet, SignedTransfer, SigningWallet,
+    TransferDirection, UnsignedTransfer, VerifyingClient, Wallet,
 };
 
 use crate::domain::client_transfers::{create_transfer, CreatedDbc, Outputs as TransferDetails};
 
-use sn_dbc::{Dbc, DbcIdSource, MainKey, PublicAddress, Token};
+use bip39::Mnemonic;
+use sn_dbc::{Dbc, DbcId, DbcIdSource, Hash, MainKey, PublicAddress, Token};
 
 use async_trait::async_trait;
 use std::{
     collections::{BTreeMap, BTreeSet},
     path::{Path, PathBuf},
 };
+use xor_name::XorName;
 
 const WALLET_DIR_NAME: &str = "wallet";
+/// Name of the dir within the root dir, the named wallets are kept in, each in a dir of its name.
+const NAMED_WALLETS_DIR_NAME: &str = "wallets";
 
 /// A wallet that can only receive tokens.
 pub struct LocalWallet {
@@ -35,12 +52,226 @@ pub struct LocalWallet {
     wallet: KeyLessWallet,
     /// The dir of the wallet file, main key, public address, and new dbcs.
     wallet_dir: PathBuf,
+    /// The transfers the wallet took part in, oldest first.
+    history: Vec<HistoryEntry>,
+    /// The passphrase the wallet files are encrypted with, if any.
+    passphrase: Option<String>,
+    /// The mnemonic the main key was derived from, which wallets
+    /// created before keys were derived from mnemonics don't have.
+    mnemonic: Option<Mnemonic>,
+    /// The receiving addresses handed out, whose keys are derived from the main key.
+    receive_addresses: Vec<ReceiveAddress>,
+    /// The receipts of the payments for storage made by the wallet, oldest first.
+    payment_receipts: Vec<PaymentReceipt>,
+    /// Keeps other processes from using the wallet while it's loaded.
+    _lock: WalletLock,
 }
 
 impl LocalWallet {
-    /// Stores the wallet to disk.
+    /// Stores the wallet, its main key and the history of its transfers, to disk.
+    /// They are encrypted if the wallet has a passphrase.
+    /// The files are committed all together, so that if the process stops while they are
+    /// being written, the wallet is loaded either as it was before or as it is now.
     pub async fn store(&self) -> Result<()> {
-        store_wallet(&self.wallet_dir, &self.wallet).await
+        let passphrase = self.passphrase.as_deref();
+        let staging_dir = begin_commit(&self.wallet_dir).await?;
+        store_new_keypair(&staging_dir, &self.key, passphrase).await?;
+        if let Some(mnemonic) = &self.mnemonic {
+            store_mnemonic(&staging_dir, mnemonic, passphrase).await?;
+        }
+        store_wallet(&staging_dir, &self.wallet, passphrase).await?;
+        store_receive_addresses(&staging_dir, &self.receive_addresses, passphrase).await?;
+        store_payment_receipts(&staging_dir, &self.payment_receipts, passphrase).await?;
+        store_history(&staging_dir, &self.history, passphrase).await?;
+        finish_commit(&self.wallet_dir).await
+    }
+
+    /// Hands out a new receiving address, e.g. for a single counterparty, so that transfers
+    /// to the wallet can't be linked together, and can be told apart by their address.
+    /// The key of the address is derived from the main key, at the next unused index,
+    /// so the addresses of a wallet restored from its mnemonic are the same when handed out again.
+    pub fn new_receive_address(&mut self, label: String) -> Result<PublicAddress> {
+        let receive_address = ReceiveAddress {
+            index: self.receive_addresses.len() as u32,
+            label,
+        };
+        let address = receive_address.main_key(&self.key)?.public_address();
+        self.receive_addresses.push(receive_address);
+        Ok(address)
+    }
+
+    /// Returns the receiving addresses handed out, along with their public address, oldest first.
+    pub fn receive_addresses(&self) -> Result<Vec<(ReceiveAddress, PublicAddress)>> {
+        self.receive_addresses
+            .iter()
+            .map(|receive_address| {
+                let address = receive_address.main_key(&self.key)?.public_address();
+                Ok((receive_address.clone(), address))
+            })
+            .collect()
+    }
+
+    /// The main key along with the keys of the receiving addresses, which the dbcs held are accessed with.
+    fn spending_keys(&self) -> Vec<MainKey> {
+        let mut keys = vec![self.key.clone()];
+        for receive_address in &self.receive_addresses {
+            match receive_address.main_key(&self.key) {
+                Ok(key) => keys.push(key),
+                Err(err) => warn!(
+                    "Could not derive the key of receiving address {}: {err}",
+                    receive_address.index
+                ),
+            }
+        }
+        keys
+    }
+
+    /// The addresses whose inboxes on the network are scanned for the dbcs sent to the wallet,
+    /// i.e. the address of the wallet and its receiving addresses.
+    pub fn inbox_addresses(&self) -> Vec<PublicAddress> {
+        self.spending_keys()
+            .iter()
+            .map(|key| key.public_address())
+            .collect()
+    }
+
+    /// Decrypts a dbc delivered to one of the inboxes of the wallet.
+    /// Returns `None` if it's not for the wallet, or if the wallet already holds or spent it.
+    pub fn decrypt_inbox_dbc(&self, encrypted: &[u8]) -> Option<Dbc> {
+        let dbc = decrypt_from_inbox(encrypted, &self.spending_keys())?;
+        let id = dbc.id();
+        let known = self.wallet.available_dbcs.contains_key(&id)
+            || self.wallet.spent_dbcs.contains_key(&id);
+        (!known).then_some(dbc)
+    }
+
+    /// Returns the BIP39 mnemonic the main key of the wallet was derived from, to back it up with.
+    /// Wallets created before keys were derived from mnemonics don't have one.
+    pub fn mnemonic(&self) -> Option<String> {
+        self.mnemonic.as_ref().map(|mnemonic| mnemonic.to_string())
+    }
+
+    /// Restores the wallet whose main key was derived from the given BIP39 mnemonic, storing it
+    /// with the passphrase if any. If the wallet already exists, it's loaded as it is. If a wallet
+    /// with another key exists, it's moved to a `wallet-replaced-<timestamp>` dir in the root dir
+    /// when `replace_existing` is set, and an error is returned otherwise.
+    /// The dbcs of the restored wallet are not restored, they have to be deposited again.
+    pub async fn restore_from_mnemonic(
+        root_dir: &Path,
+        phrase: &str,
+        passphrase: Option<String>,
+        replace_existing: bool,
+    ) -> Result<Self> {
+        let mnemonic = parse_mnemonic(phrase)?;
+        let key = main_key_from_mnemonic(&mnemonic);
+        let wallet_dir = root_dir.join(WALLET_DIR_NAME);
+        tokio::fs::create_dir_all(&wallet_dir).await?;
+        let lock = WalletLock::acquire(&wallet_dir).await?;
+        recover_commit(&wallet_dir).await?;
+
+        match get_main_key(&wallet_dir, passphrase.as_deref()).await {
+            Ok(Some(existing)) if existing.public_address() == key.public_address() => {
+                drop(lock);
+                let mut wallet = Self::load_with_passphrase(root_dir, passphrase).await?;
+                wallet.mnemonic = Some(mnemonic);
+                wallet.store().await?;
+                return Ok(wallet);
+            }
+            Ok(None) => {}
+            _ if replace_existing => {
+                let timestamp = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or_default();
+                let replaced_dir = root_dir.join(format!("{WALLET_DIR_NAME}-replaced-{timestamp}"));
+                warn!("Moving the existing wallet to {replaced_dir:?}");
+                tokio::fs::rename(&wallet_dir, replaced_dir).await?;
+            }
+            _ => return Err(Error::WalletAlreadyExists),
+        }
+        // The lock file was moved along with a replaced wallet.
+        drop(lock);
+
+        tokio::fs::create_dir_all(&wallet_dir).await?;
+        let lock = WalletLock::acquire(&wallet_dir).await?;
+        migrate(&wallet_dir).await?;
+        create_received_dbcs_dir(&wallet_dir).await?;
+        let wallet = Self {
+            key,
+            wallet: KeyLessWallet::new(),
+            wallet_dir,
+            history: vec![],
+            passphrase,
+            mnemonic: Some(mnemonic),
+            receive_addresses: vec![],
+            payment_receipts: vec![],
+            _lock: lock,
+        };
+        wallet.store().await?;
+        Ok(wallet)
+    }
+
+    /// Sets the passphrase the wallet files are encrypted with from the next time it's stored,
+    /// or stores them unencrypted if `None`.
+    pub fn set_passphrase(&mut self, passphrase: Option<String>) {
+        self.passphrase = passphrase;
+    }
+
+    /// Whether a wallet was created in the root dir.
+    pub fn exists(root_dir: &Path) -> bool {
+        root_dir.join(WALLET_DIR_NAME).is_dir()
+    }
+
+    /// Whether the wallet files are encrypted with a passphrase, which is then needed to load it.
+    pub async fn is_encrypted(root_dir: &Path) -> Result<bool> {
+        is_main_key_encrypted(&root_dir.join(WALLET_DIR_NAME)).await
+    }
+
+    /// Returns the root dir of the wallet of the given name, e.g. `node-rewards`, kept apart
+    /// from the default wallet of the root dir, and from the other named wallets. It's to be
+    /// loaded with `load_from` like any other root dir, which creates the wallet if it doesn't exist.
+    pub fn named_root_dir(root_dir: &Path, name: &str) -> Result<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Error::InvalidWalletName(name.to_string()));
+        }
+        Ok(root_dir.join(NAMED_WALLETS_DIR_NAME).join(name))
+    }
+
+    /// Returns the names of the wallets kept in the root dir, other than its default one, sorted.
+    pub async fn named_wallets(root_dir: &Path) -> Result<Vec<String>> {
+        let named_wallets_dir = root_dir.join(NAMED_WALLETS_DIR_NAME);
+        if !named_wallets_dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut names = vec![];
+        let mut entries = tokio::fs::read_dir(named_wallets_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.path().join(WALLET_DIR_NAME).is_dir() {
+                continue;
+            }
+            if let Some(name) = entry.file_name().to_str() {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Returns the transfers the wallet took part in, oldest first.
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    /// Returns the transfers the wallet took part in which match the filter, oldest first.
+    pub fn filtered_history(&self, filter: &HistoryFilter) -> Vec<&HistoryEntry> {
+        self.history
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .collect()
     }
 
     /// Stores the given dbc to the `created dbcs dir` in the wallet dir.
@@ -50,48 +281,350 @@ impl LocalWallet {
         store_created_dbcs(vec![dbc], &self.wallet_dir).await
     }
 
+    /// Stores the given transfer as a file in the `created dbcs dir` in the wallet dir, along with
+    /// the dbcs for the same recipient. Returns the path of the file, to be sent to the recipient.
+    pub async fn store_transfer_file(&self, transfer: &OfflineTransfer) -> Result<PathBuf> {
+        store_transfer_file(transfer, &self.wallet_dir).await
+    }
+
     /// Try to load any new dbcs from the `received dbcs dir` in the wallet dir.
     pub async fn try_load_deposits(&mut self) -> Result<()> {
         let deposited = load_received_dbcs(&self.wallet_dir).await?;
-        self.wallet.deposit(deposited, &self.key);
+        self.deposit(deposited);
         Ok(())
     }
 
+    /// Reissues the dbcs held by the wallet into fewer, larger ones, to keep the wallet fast
+    /// after many small receipts and change outputs. The dbcs are spent smallest first, in
+    /// batches of at most `batch_size` (and at least 2), each to a single new dbc of the wallet.
+    /// Returns the number of transfers made.
+    pub async fn consolidate<C: SendClient>(
+        &mut self,
+        batch_size: usize,
+        client: &C,
+    ) -> Result<usize> {
+        resend_pending_txs(self, client).await;
+
+        let keys = self.spending_keys();
+        let mut available_dbcs: Vec<_> = self
+            .wallet
+            .available_dbcs
+            .values()
+            .filter_map(|dbc| {
+                let derived_key = dbc_derived_key(dbc, &keys)?;
+                let amount = dbc.revealed_amount(&derived_key).ok()?.value();
+                Some((amount, dbc.clone()))
+            })
+            .collect();
+        available_dbcs.sort_by_key(|(amount, _)| *amount);
+
+        let mut transfers = 0;
+        for batch in available_dbcs.chunks(batch_size.max(2)) {
+            if batch.len() < 2 {
+                continue;
+            }
+            let total = batch.iter().fold(0, |total, (amount, _)| total + amount);
+            let inputs = batch
+                .iter()
+                .filter_map(|(_, dbc)| {
+                    let derived_key = dbc_derived_key(dbc, &keys)?;
+                    Some((dbc.clone(), derived_key))
+                })
+                .collect();
+            let to_self = self.key.random_dbc_id_src(&mut rand::thread_rng());
+            let transfer = create_transfer(
+                inputs,
+                vec![(Token::from_nano(total), to_self)],
+                self.address(),
+                Hash::default(),
+            )?;
+
+            for (_, dbc) in batch {
+                let id = dbc.id();
+                if let Some(dbc) = self.wallet.available_dbcs.remove(&id) {
+                    let _ = self.wallet.spent_dbcs.insert(id, dbc);
+                }
+            }
+            // The tokens stay in the wallet, thus this is not recorded in the history.
+            let new_dbcs = transfer
+                .created_dbcs
+                .iter()
+                .map(|created| created.dbc.clone())
+                .chain(transfer.change_dbc.clone())
+                .collect();
+            let _ = self.wallet.deposit(new_dbcs, &keys);
+
+            if let Err(error) = store_and_send(self, transfer, client).await? {
+                println!("The consolidation was not successfully registered in the network: {error:?}. It will be retried later.");
+            }
+            transfers += 1;
+        }
+
+        Ok(transfers)
+    }
+
+    /// Returns the number of transfers made by the wallet, which are not known
+    /// to be registered in the network yet.
+    pub fn pending_transfers(&self) -> usize {
+        self.wallet.unconfirmed_txs.len()
+    }
+
+    /// Completes the transfers left pending, e.g. by a failed send or a crash in the middle of one.
+    /// The spends of each are looked up in the network: those found registered are confirmed,
+    /// the others are sent again. Transfers any of whose inputs were spent elsewhere are kept
+    /// pending, as they can't be completed, only cancelled. Returns the number of transfers completed.
+    pub async fn resend_pending<C: SendClient + VerifyingClient>(
+        &mut self,
+        client: &C,
+    ) -> Result<usize> {
+        let mut completed = 0;
+        let mut pending = vec![];
+        for transfer in self.wallet.unconfirmed_txs.clone() {
+            let status = pending_transfer_status(&transfer, client).await?;
+            if !status.spent_elsewhere.is_empty() {
+                warn!(
+                    "Some inputs of the pending tx {:?} were spent elsewhere, it can only be cancelled",
+                    transfer.tx_hash
+                );
+                pending.push(transfer);
+            } else if status.unregistered == 0 || client.send(transfer.clone()).await.is_ok() {
+                completed += 1;
+            } else {
+                pending.push(transfer);
+            }
+        }
+        self.wallet.unconfirmed_txs = pending;
+        self.store().await?;
+        Ok(completed)
+    }
+
+    /// Cancels the transfers left pending, which none of the spends of were registered in the
+    /// network, returning their inputs to the spendable dbcs of the wallet, and dropping their
+    /// outputs, along with the history entries and payment receipts of them. The transfer files
+    /// written for their recipients are thus no longer valid. The transfers partly registered
+    /// can't be cancelled without losing their inputs, thus they are completed instead.
+    /// Returns the number of transfers cancelled.
+    pub async fn cancel_pending<C: SendClient + VerifyingClient>(
+        &mut self,
+        client: &C,
+    ) -> Result<usize> {
+        let keys = self.spending_keys();
+        let mut cancelled = 0;
+        let mut pending = vec![];
+        // Latest first, as a transfer can spend the change of an earlier one.
+        for transfer in self.wallet.unconfirmed_txs.clone().into_iter().rev() {
+            let status = pending_transfer_status(&transfer, client).await?;
+            let change_spent = transfer.change_dbc.as_ref().map_or(false, |change| {
+                self.wallet.spent_dbcs.contains_key(&change.id())
+            });
+            if status.registered > 0 || change_spent {
+                if status.unregistered > 0 && client.send(transfer.clone()).await.is_err() {
+                    pending.push(transfer);
+                }
+                continue;
+            }
+
+            // The inputs spent elsewhere are lost either way.
+            let inputs: Vec<_> = transfer
+                .all_spend_requests
+                .iter()
+                .map(|request| request.signed_spend.dbc_id())
+                .filter(|id| !status.spent_elsewhere.contains(*id))
+                .filter_map(|id| self.wallet.spent_dbcs.remove(id))
+                .collect();
+            if let Some(change) = &transfer.change_dbc {
+                let _ = self.wallet.available_dbcs.remove(&change.id());
+            }
+            let created: BTreeSet<_> = transfer
+                .created_dbcs
+                .iter()
+                .map(|created| created.dbc.id())
+                .collect();
+            self.wallet
+                .dbcs_created_for_others
+                .retain(|other| !created.contains(&other.dbc.id()));
+            self.history.retain(|entry| {
+                entry.direction != TransferDirection::Sent
+                    || !entry.dbc_ids.iter().any(|id| created.contains(id))
+            });
+            let spent: BTreeSet<_> = transfer
+                .all_spend_requests
+                .iter()
+                .map(|request| *request.signed_spend.dbc_id())
+                .collect();
+            self.payment_receipts.retain(|receipt| {
+                !receipt
+                    .spends
+                    .iter()
+                    .any(|spend| spent.contains(spend.dbc_id()))
+            });
+            // The inputs are not a receipt from a counterpart, thus not recorded as such.
+            let _ = self.wallet.deposit(inputs, &keys);
+            cancelled += 1;
+        }
+        pending.reverse();
+        self.wallet.unconfirmed_txs = pending;
+        self.store().await?;
+        Ok(cancelled)
+    }
+
+    /// Pays the recipients for storing the data at the given addresses, with spends made for the
+    /// hash of the addresses, and keeps the receipt of the payment, which is returned.
+    /// The receipt is stored along with the spends, before they are registered in the network,
+    /// so that it's kept whenever the payment is made.
+    pub async fn pay_for_storage<C: SendClient>(
+        &mut self,
+        to: Vec<(Token, PublicAddress)>,
+        paid_for: Vec<XorName>,
+        client: &C,
+    ) -> Result<PaymentReceipt> {
+        if paid_for.is_empty() {
+            return Err(Error::CouldNotSendTokens(
+                "No addresses to pay for were given.".into(),
+            ));
+        }
+        resend_pending_txs(self, client).await;
+
+        let transfer = apply_transfer(self, to, payment_reason(&paid_for))?.ok_or_else(|| {
+            Error::CouldNotSendTokens("No recipients other than the wallet were given.".into())
+        })?;
+        let amount = transfer
+            .created_dbcs
+            .iter()
+            .map(|created| created.amount.value())
+            .sum();
+        let spends = transfer
+            .all_spend_requests
+            .iter()
+            .map(|request| request.signed_spend.clone())
+            .collect();
+        let receipt = PaymentReceipt::new(paid_for, Token::from_nano(amount), spends);
+        self.payment_receipts.push(receipt.clone());
+
+        if let Err(error) = store_and_send(self, transfer, client).await? {
+            println!("The payment was not successfully registered in the network: {error:?}. It will be retried later.");
+        }
+        Ok(receipt)
+    }
+
+    /// Returns the receipt of the latest payment for storing the data at the address, if any.
+    pub fn payment_receipt(&self, address: &XorName) -> Option<&PaymentReceipt> {
+        self.payment_receipts
+            .iter()
+            .rev()
+            .find(|receipt| receipt.covers(address))
+    }
+
+    /// Returns the receipts of the payments for storage made by the wallet, oldest first.
+    pub fn payment_receipts(&self) -> &[PaymentReceipt] {
+        &self.payment_receipts
+    }
+
+    /// Returns the ids of the unspent dbcs held by the wallet, along with the amount of each.
+    pub fn available_dbcs(&self) -> Vec<(DbcId, Token)> {
+        let keys = self.spending_keys();
+        self.wallet
+            .available_dbcs
+            .iter()
+            .flat_map(|(id, dbc)| {
+                dbc_derived_key(dbc, &keys).map(|derived_key| (id, dbc, derived_key))
+            })
+            .flat_map(|(id, dbc, derived_key)| {
+                dbc.revealed_input(&derived_key)
+                    .map(|input| (*id, Token::from_nano(input.revealed_amount().value())))
+            })
+            .collect()
+    }
+
+    /// Signs the transfer built by the watch-only wallet of this wallet, e.g. on an air-gapped
+    /// machine, selecting its inputs from the dbcs given. The wallet itself is not changed,
+    /// as the dbcs spent and the change are kept track of by the watch-only wallet.
+    pub fn sign_transfer(&self, unsigned: &UnsignedTransfer) -> Result<SignedTransfer> {
+        if unsigned.change_to != self.address() {
+            return Err(Error::CouldNotSendTokens(
+                "The transfer was not built by the watch-only wallet of this wallet.".into(),
+            ));
+        }
+        let keys = self.spending_keys();
+        let inputs = unsigned
+            .inputs
+            .iter()
+            .filter_map(|dbc| {
+                dbc_derived_key(dbc, &keys).map(|derived_key| (dbc.clone(), derived_key))
+            })
+            .collect();
+        let to = unsigned
+            .recipients
+            .iter()
+            .map(|(amount, address)| (*amount, address.random_dbc_id_src(&mut rand::thread_rng())))
+            .collect();
+        let transfer = create_transfer(inputs, to, self.address(), Hash::default())?;
+        Ok(SignedTransfer {
+            transfer,
+            recipients: unsigned.recipients.clone(),
+        })
+    }
+
     /// Loads a serialized wallet from a path.
     pub async fn load_from(root_dir: &Path) -> Result<Self> {
+        Self::load_with_passphrase(root_dir, None).await
+    }
+
+    /// Loads a serialized wallet from a path, decrypting it with the passphrase if it's encrypted.
+    /// The wallet keeps the passphrase, to encrypt its files with when stored.
+    pub async fn load_with_passphrase(root_dir: &Path, passphrase: Option<String>) -> Result<Self> {
         let wallet_dir = root_dir.join(WALLET_DIR_NAME);
         // This creates the received_dbcs dir if it doesn't exist.
         tokio::fs::create_dir_all(&wallet_dir).await?;
-        let (key, wallet) = load_from_path(&wallet_dir).await?;
+        // Locked before reading the files, so they don't change until the wallet is dropped.
+        let lock = WalletLock::acquire(&wallet_dir).await?;
+        recover_commit(&wallet_dir).await?;
+        migrate(&wallet_dir).await?;
+        let (key, wallet, mnemonic) = load_from_path(&wallet_dir, passphrase.as_deref()).await?;
+        let history = get_history(&wallet_dir, passphrase.as_deref()).await?;
+        let receive_addresses = get_receive_addresses(&wallet_dir, passphrase.as_deref()).await?;
+        let payment_receipts = get_payment_receipts(&wallet_dir, passphrase.as_deref()).await?;
         Ok(Self {
             key,
             wallet,
             wallet_dir: wallet_dir.to_path_buf(),
+            history,
+            passphrase,
+            mnemonic,
+            receive_addresses,
+            payment_receipts,
+            _lock: lock,
         })
     }
 }
 
 /// Loads a serialized wallet from a path.
-async fn load_from_path(wallet_dir: &Path) -> Result<(MainKey, KeyLessWallet)> {
-    let key = match get_main_key(wallet_dir).await? {
-        Some(key) => key,
+async fn load_from_path(
+    wallet_dir: &Path,
+    passphrase: Option<&str>,
+) -> Result<(MainKey, KeyLessWallet, Option<Mnemonic>)> {
+    let (key, mnemonic) = match get_main_key(wallet_dir, passphrase).await? {
+        Some(key) => (key, get_mnemonic(wallet_dir, passphrase).await?),
         None => {
-            let key = MainKey::random();
-            store_new_keypair(wallet_dir, &key).await?;
-            key
+            // New keys are derived from a mnemonic, for the wallet to be backed up with.
+            let mnemonic = generate_mnemonic()?;
+            let key = main_key_from_mnemonic(&mnemonic);
+            store_new_keypair(wallet_dir, &key, passphrase).await?;
+            store_mnemonic(wallet_dir, &mnemonic, passphrase).await?;
+            (key, Some(mnemonic))
         }
     };
-    let wallet = match get_wallet(wallet_dir).await? {
+    let wallet = match get_wallet(wallet_dir, passphrase).await? {
         Some(wallet) => wallet,
         None => {
             let wallet = KeyLessWallet::new();
-            store_wallet(wallet_dir, &wallet).await?;
+            store_wallet(wallet_dir, &wallet, passphrase).await?;
             create_received_dbcs_dir(wallet_dir).await?;
             wallet
         }
     };
 
-    Ok((key, wallet))
+    Ok((key, wallet, mnemonic))
 }
 
 impl KeyLessWallet {
@@ -109,18 +642,32 @@ impl KeyLessWallet {
         self.balance
     }
 
-    fn deposit(&mut self, dbcs: Vec<Dbc>, key: &MainKey) {
+    /// Deposits the dbcs which can be accessed with any of the given keys,
+    /// returning the ids and amounts of those not held already.
+    fn deposit(&mut self, dbcs: Vec<Dbc>, keys: &[MainKey]) -> Vec<(DbcId, Token)> {
         if dbcs.is_empty() {
-            return;
+            return vec![];
         }
 
-        let mut received_dbcs = dbcs
+        let mut received_dbcs: BTreeMap<_, _> = dbcs
             .into_iter()
             .filter_map(|dbc| {
                 let id = dbc.id();
                 (!self.spent_dbcs.contains_key(&id)).then_some((id, dbc))
             })
-            .filter_map(|(id, dbc)| dbc.derived_key(key).is_ok().then_some((id, dbc)))
+            .filter_map(|(id, dbc)| dbc_derived_key(&dbc, keys).is_some().then_some((id, dbc)))
+            .collect();
+
+        let newly_received = received_dbcs
+            .iter()
+            .filter(|(id, _)| !self.available_dbcs.contains_key(id))
+            .flat_map(|(id, dbc)| {
+                dbc_derived_key(dbc, keys).map(|derived_key| (id, dbc, derived_key))
+            })
+            .flat_map(|(id, dbc, derived_key)| {
+                dbc.revealed_input(&derived_key)
+                    .map(|input| (*id, Token::from_nano(input.revealed_amount().value())))
+            })
             .collect();
 
         self.available_dbcs.append(&mut received_dbcs);
@@ -128,11 +675,13 @@ impl KeyLessWallet {
         let new_balance = self
             .available_dbcs
             .iter()
-            .flat_map(|(_, dbc)| dbc.derived_key(key).map(|derived_key| (dbc, derived_key)))
+            .flat_map(|(_, dbc)| dbc_derived_key(dbc, keys).map(|derived_key| (dbc, derived_key)))
             .flat_map(|(dbc, derived_key)| dbc.revealed_input(&derived_key))
             .fold(0, |total, amount| total + amount.revealed_amount().value());
 
         self.balance = Token::from_nano(new_balance);
+
+        newly_received
     }
 }
 
@@ -158,7 +707,20 @@ impl DepositWallet for LocalWallet {
     }
 
     fn deposit(&mut self, dbcs: Vec<Dbc>) {
-        self.wallet.deposit(dbcs, &self.key);
+        let keys = self.spending_keys();
+        let received = self.wallet.deposit(dbcs, &keys);
+        if received.is_empty() {
+            return;
+        }
+        let amount = received
+            .iter()
+            .fold(0, |total, (_, amount)| total + amount.as_nano());
+        self.history.push(HistoryEntry::now(
+            TransferDirection::Received,
+            Token::from_nano(amount),
+            None,
+            received.into_iter().map(|(id, _)| id).collect(),
+        ));
     }
 }
 
@@ -180,100 +742,191 @@ impl SendWallet for LocalWallet {
         // solution of being able to check and clean out the list later seems to be necessary.
         resend_pending_txs(self, client).await;
 
-        // do not make a pointless send to ourselves
-
-        let to: Vec<_> = to
-            .into_iter()
-            .filter_map(|(amount, address)| {
-                let dbc_id_src = address.random_dbc_id_src(&mut rand::thread_rng());
-                (address != self.address()).then_some((amount, dbc_id_src))
-            })
-            .collect();
-        if to.is_empty() {
-            return Ok(vec![]);
-        }
+        let transfer = match apply_transfer(self, to, Hash::default())? {
+            Some(transfer) => transfer,
+            None => return Ok(vec![]),
+        };
+        let created_dbcs = transfer.created_dbcs.clone();
 
-        let mut available_dbcs = vec![];
-        for dbc in self.wallet.available_dbcs.values() {
-            if let Ok(derived_key) = dbc.derived_key(&self.key) {
-                available_dbcs.push((dbc.clone(), derived_key));
-            } else {
-                println!(
-                    "Skipping DBC {:?} because we don't have the key to spend it",
-                    dbc.id()
-                );
-            }
+        // Last of all, register the spend in the network.
+        if let Err(error) = store_and_send(self, transfer, client).await? {
+            println!("The transfer was not successfully registered in the network: {error:?}. It will be retried later.");
         }
 
-        let transfer = create_transfer(available_dbcs, to, self.address())?;
-
-        let TransferDetails {
-            change_dbc,
-            created_dbcs,
-            ..
-        } = transfer.clone();
+        Ok(created_dbcs)
+    }
+}
 
-        // First of all, update client local state.
-        let spent_dbc_ids: BTreeSet<_> = created_dbcs
-            .iter()
-            .flat_map(|created| &created.dbc.signed_spends)
-            .map(|spend| spend.dbc_id())
-            .collect();
+/// Applies a transfer to the recipients, with spends made for the reason hash, to the local state
+/// of the wallet, returning it to be registered in the network. Returns `None` when there's no
+/// recipient other than the wallet itself, as it doesn't make a pointless send to itself.
+fn apply_transfer(
+    local: &mut LocalWallet,
+    to: Vec<(Token, PublicAddress)>,
+    reason_hash: Hash,
+) -> Result<Option<TransferDetails>> {
+    // do not make a pointless send to ourselves
+
+    let to: Vec<_> = to
+        .into_iter()
+        .filter_map(|(amount, address)| {
+            let dbc_id_src = address.random_dbc_id_src(&mut rand::thread_rng());
+            (address != local.address()).then_some((amount, dbc_id_src))
+        })
+        .collect();
+    if to.is_empty() {
+        return Ok(None);
+    }
 
-        let mut spent_dbcs = spent_dbc_ids
-            .into_iter()
-            .filter_map(|id| self.wallet.available_dbcs.remove(id).map(|dbc| (*id, dbc)))
-            .collect();
+    let keys = local.spending_keys();
+    let mut available_dbcs = vec![];
+    for dbc in local.wallet.available_dbcs.values() {
+        if let Some(derived_key) = dbc_derived_key(dbc, &keys) {
+            available_dbcs.push((dbc.clone(), derived_key));
+        } else {
+            println!(
+                "Skipping DBC {:?} because we don't have the key to spend it",
+                dbc.id()
+            );
+        }
+    }
 
-        self.deposit(change_dbc.into_iter().collect());
-        self.wallet.spent_dbcs.append(&mut spent_dbcs);
-        self.wallet
-            .dbcs_created_for_others
-            .extend(created_dbcs.clone());
+    let transfer = create_transfer(available_dbcs, to, local.address(), reason_hash)?;
+
+    let TransferDetails {
+        change_dbc,
+        created_dbcs,
+        ..
+    } = transfer.clone();
+
+    // First of all, update client local state.
+    let spent_dbc_ids: BTreeSet<_> = created_dbcs
+        .iter()
+        .flat_map(|created| &created.dbc.signed_spends)
+        .map(|spend| spend.dbc_id())
+        .collect();
+
+    let mut spent_dbcs = spent_dbc_ids
+        .into_iter()
+        .filter_map(|id| local.wallet.available_dbcs.remove(id).map(|dbc| (*id, dbc)))
+        .collect();
+
+    // The change is not a receipt from a counterpart, thus not recorded as such in the history.
+    let _ = local
+        .wallet
+        .deposit(change_dbc.into_iter().collect(), &keys);
+    local.wallet.spent_dbcs.append(&mut spent_dbcs);
+    local
+        .wallet
+        .dbcs_created_for_others
+        .extend(created_dbcs.clone());
+    for created in &created_dbcs {
+        local.history.push(HistoryEntry::now(
+            TransferDirection::Sent,
+            Token::from_nano(created.amount.value()),
+            Some(*created.dbc.public_address()),
+            vec![created.dbc.id()],
+        ));
+    }
 
-        // Last of all, register the spend in the network.
-        if let Err(error) = client.send(transfer.clone()).await {
-            println!("The transfer was not successfully registered in the network: {error:?}. It will be retried later.");
-            let _ = self.wallet.unconfirmed_txs.push(transfer);
-        }
+    Ok(Some(transfer))
+}
 
-        Ok(created_dbcs)
+/// Stores the state of the wallet the transfer was applied to, with the transfer as unconfirmed,
+/// before registering its spends in the network. If the process stops in between, the transfer
+/// is thus sent again when the wallet is next used, rather than its inputs being spent twice
+/// or its outputs being lost. Returns the error of the network, if the transfer is left unconfirmed.
+async fn store_and_send<C: SendClient>(
+    local: &mut LocalWallet,
+    transfer: TransferDetails,
+    client: &C,
+) -> Result<Result<()>> {
+    local.wallet.unconfirmed_txs.push(transfer.clone());
+    local.store().await?;
+
+    if let Err(error) = client.send(transfer).await {
+        return Ok(Err(error));
     }
+    let _ = local.wallet.unconfirmed_txs.pop();
+    local.store().await?;
+    Ok(Ok(()))
 }
 
 async fn resend_pending_txs<C: SendClient>(local: &mut LocalWallet, client: &C) {
-    for (index, transfer) in local.wallet.unconfirmed_txs.clone().into_iter().enumerate() {
+    let mut pending = vec![];
+    for transfer in local.wallet.unconfirmed_txs.clone() {
         println!("Trying to republish pending tx: {:?}..", transfer.tx_hash);
         if client.send(transfer.clone()).await.is_ok() {
             println!("Tx {:?} was successfully republished!", transfer.tx_hash);
-            let _ = local.wallet.unconfirmed_txs.remove(index);
             // We might want to be _really_ sure and do the below
             // as well, but it's not necessary.
             // use crate::domain::wallet::VerifyingClient;
             // client.verify(tx_hash).await.ok();
+        } else {
+            pending.push(transfer);
         }
     }
+    local.wallet.unconfirmed_txs = pending;
+}
+
+/// How much of a pending transfer was registered in the network.
+struct PendingTransferStatus {
+    /// The number of its spends registered in the network.
+    registered: usize,
+    /// The number of its spends not registered in the network.
+    unregistered: usize,
+    /// Its inputs which were spent by another transfer.
+    spent_elsewhere: BTreeSet<DbcId>,
+}
+
+async fn pending_transfer_status<C: VerifyingClient>(
+    transfer: &TransferDetails,
+    client: &C,
+) -> Result<PendingTransferStatus> {
+    let mut status = PendingTransferStatus {
+        registered: 0,
+        unregistered: 0,
+        spent_elsewhere: BTreeSet::new(),
+    };
+    for request in &transfer.all_spend_requests {
+        let dbc_id = request.signed_spend.dbc_id();
+        match client.registered_spend(dbc_id).await? {
+            Some(spend) if spend.hash() == request.signed_spend.hash() => status.registered += 1,
+            Some(_) => {
+                let _ = status.spent_elsewhere.insert(*dbc_id);
+            }
+            None => status.unregistered += 1,
+        }
+    }
+    Ok(status)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{get_wallet, store_wallet, LocalWallet};
+    use super::{get_wallet, store_wallet, LocalWallet, WalletLock};
 
     use crate::{
         domain::{
             client_transfers::Outputs as TransferDetails,
             dbc_genesis::{create_first_dbc_from_key, GENESIS_DBC_AMOUNT},
             wallet::{
-                local_store::WALLET_DIR_NAME, public_address_name, KeyLessWallet, SendClient,
+                local_store::WALLET_DIR_NAME, public_address_name, Error, KeyLessWallet,
+                OfflineTransfer, SendClient, SignedTransfer, TransferDirection, UnsignedTransfer,
+                VerifyingClient, WatchOnlyWallet,
             },
         },
         protocol::storage::DbcAddress,
     };
 
-    use sn_dbc::{MainKey, Token};
+    use sn_dbc::{Dbc, DbcId, MainKey, SignedSpend, Token};
 
     use assert_fs::TempDir;
     use eyre::Result;
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+    use xor_name::XorName;
 
     #[tokio::test]
     async fn keyless_wallet_to_and_from_file() -> Result<()> {
@@ -284,11 +937,11 @@ mod tests {
         let dir = create_temp_dir();
         let wallet_dir = dir.path().to_path_buf();
 
-        wallet.deposit(vec![genesis], &key);
+        let _ = wallet.deposit(vec![genesis], std::slice::from_ref(&key));
 
-        store_wallet(&wallet_dir, &wallet).await?;
+        store_wallet(&wallet_dir, &wallet, None).await?;
 
-        let deserialized = get_wallet(&wallet_dir)
+        let deserialized = get_wallet(&wallet_dir, None)
             .await?
             .expect("There to be a wallet on disk.");
 
@@ -311,6 +964,12 @@ mod tests {
             key,
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            history: vec![],
+            passphrase: None,
+            mnemonic: None,
+            receive_addresses: vec![],
+            payment_receipts: vec![],
+            _lock: WalletLock::try_acquire(dir.path())?,
         };
 
         assert_eq!(public_address, deposit_only.address());
@@ -342,6 +1001,12 @@ mod tests {
             key: MainKey::random(),
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            history: vec![],
+            passphrase: None,
+            mnemonic: None,
+            receive_addresses: vec![],
+            payment_receipts: vec![],
+            _lock: WalletLock::try_acquire(dir.path())?,
         };
 
         deposit_only.deposit(vec![]);
@@ -368,6 +1033,12 @@ mod tests {
             key,
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            history: vec![],
+            passphrase: None,
+            mnemonic: None,
+            receive_addresses: vec![],
+            payment_receipts: vec![],
+            _lock: WalletLock::try_acquire(dir.path())?,
         };
 
         deposit_only.deposit(vec![genesis]);
@@ -390,6 +1061,12 @@ mod tests {
             key: MainKey::random(),
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            history: vec![],
+            passphrase: None,
+            mnemonic: None,
+            receive_addresses: vec![],
+            payment_receipts: vec![],
+            _lock: WalletLock::try_acquire(dir.path())?,
         };
 
         local_wallet.deposit(vec![genesis]);
@@ -413,6 +1090,12 @@ mod tests {
             key,
             wallet: KeyLessWallet::new(),
             wallet_dir: dir.path().to_path_buf(),
+            history: vec![],
+            passphrase: None,
+            mnemonic: None,
+            receive_addresses: vec![],
+            payment_receipts: vec![],
+            _lock: WalletLock::try_acquire(dir.path())?,
         };
 
         deposit_only.deposit(vec![genesis_0.clone()]);
@@ -441,33 +1124,42 @@ mod tests {
         depositor.deposit(vec![genesis]);
         depositor.store().await?;
 
-        let deserialized = LocalWallet::load_from(&root_dir).await?;
-
-        assert_eq!(depositor.address(), deserialized.address());
         assert_eq!(GENESIS_DBC_AMOUNT, depositor.balance().as_nano());
-        assert_eq!(GENESIS_DBC_AMOUNT, deserialized.balance().as_nano());
-
         assert_eq!(1, depositor.wallet.available_dbcs.len());
         assert_eq!(0, depositor.wallet.dbcs_created_for_others.len());
         assert_eq!(0, depositor.wallet.spent_dbcs.len());
 
-        assert_eq!(1, deserialized.wallet.available_dbcs.len());
-        assert_eq!(0, deserialized.wallet.dbcs_created_for_others.len());
-        assert_eq!(0, deserialized.wallet.spent_dbcs.len());
+        let available = depositor.available_dbcs();
+        assert_eq!(1, available.len());
+        assert_eq!(GENESIS_DBC_AMOUNT, available[0].1.as_nano());
 
+        let depositor_address = depositor.address();
         let a_available = depositor
             .wallet
             .available_dbcs
             .values()
             .last()
-            .expect("There to be an available DBC.");
+            .expect("There to be an available DBC.")
+            .clone();
+
+        // The wallet is locked until the instance holding it is dropped.
+        drop(depositor);
+        let deserialized = LocalWallet::load_from(&root_dir).await?;
+
+        assert_eq!(depositor_address, deserialized.address());
+        assert_eq!(GENESIS_DBC_AMOUNT, deserialized.balance().as_nano());
+
+        assert_eq!(1, deserialized.wallet.available_dbcs.len());
+        assert_eq!(0, deserialized.wallet.dbcs_created_for_others.len());
+        assert_eq!(0, deserialized.wallet.spent_dbcs.len());
+
         let b_available = deserialized
             .wallet
             .available_dbcs
             .values()
             .last()
             .expect("There to be an available DBC.");
-        assert_eq!(a_available, b_available);
+        assert_eq!(&a_available, b_available);
 
         Ok(())
     }
@@ -511,6 +1203,478 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn unregistered_transfers_are_stored_before_being_sent() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+
+        let mut sender = LocalWallet::load_from(&root_dir).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+
+        let send_amount = 100;
+        let to = vec![(
+            Token::from_nano(send_amount),
+            MainKey::random().public_address(),
+        )];
+        let _created_dbcs = sender.send(to, &UnreachableSendClient).await?;
+
+        // The wallet is loaded as if the process had stopped without storing it after the send.
+        drop(sender);
+        let reloaded = LocalWallet::load_from(&root_dir).await?;
+        assert_eq!(
+            GENESIS_DBC_AMOUNT - send_amount,
+            reloaded.balance().as_nano()
+        );
+        assert_eq!(1, reloaded.wallet.unconfirmed_txs.len());
+        assert_eq!(1, reloaded.wallet.dbcs_created_for_others.len());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pending_transfers_are_resent_or_cancelled() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let mut sender = LocalWallet::load_from(dir.path()).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+        let network = MockNetwork::default();
+
+        // A transfer which didn't reach the network is cancelled, returning its input.
+        let to = vec![(Token::from_nano(100), MainKey::random().public_address())];
+        let _created_dbcs = sender.send(to, &UnreachableSendClient).await?;
+        assert_eq!(1, sender.pending_transfers());
+        assert_eq!(1, sender.cancel_pending(&network).await?);
+        assert_eq!(0, sender.pending_transfers());
+        assert_eq!(GENESIS_DBC_AMOUNT, sender.balance().as_nano());
+        assert!(sender.history().is_empty());
+        assert!(sender.wallet.dbcs_created_for_others.is_empty());
+        assert_eq!(0, network.registered_spends());
+
+        // The input can then be spent again, the transfer being resent to the network.
+        let to = vec![(Token::from_nano(200), MainKey::random().public_address())];
+        let _created_dbcs = sender.send(to, &UnreachableSendClient).await?;
+        assert_eq!(1, sender.resend_pending(&network).await?);
+        assert_eq!(0, sender.pending_transfers());
+        assert_eq!(GENESIS_DBC_AMOUNT - 200, sender.balance().as_nano());
+        assert_eq!(1, network.registered_spends());
+
+        // A transfer registered in the network is not cancelled, but confirmed.
+        let to = vec![(Token::from_nano(300), MainKey::random().public_address())];
+        let _created_dbcs = sender.send(to, &UnreachableSendClient).await?;
+        let pending = sender.wallet.unconfirmed_txs[0].clone();
+        network.send(pending).await?;
+        assert_eq!(0, sender.cancel_pending(&network).await?);
+        assert_eq!(0, sender.pending_transfers());
+        assert_eq!(GENESIS_DBC_AMOUNT - 500, sender.balance().as_nano());
+
+        drop(sender);
+        let reloaded = LocalWallet::load_from(dir.path()).await?;
+        assert_eq!(0, reloaded.pending_transfers());
+        assert_eq!(GENESIS_DBC_AMOUNT - 500, reloaded.balance().as_nano());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn named_wallets_are_kept_apart() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::Wallet;
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path();
+
+        let default = LocalWallet::load_from(root_dir).await?;
+        let rewards =
+            LocalWallet::load_from(&LocalWallet::named_root_dir(root_dir, "node-rewards")?).await?;
+        let testing =
+            LocalWallet::load_from(&LocalWallet::named_root_dir(root_dir, "testing_1")?).await?;
+        assert_ne!(default.address(), rewards.address());
+        assert_ne!(rewards.address(), testing.address());
+
+        assert_eq!(
+            vec!["node-rewards".to_string(), "testing_1".to_string()],
+            LocalWallet::named_wallets(root_dir).await?
+        );
+
+        for invalid in ["", "..", "a/b", "a b", "wallet.old"] {
+            assert!(matches!(
+                LocalWallet::named_root_dir(root_dir, invalid),
+                Err(Error::InvalidWalletName(_))
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn payments_for_storage_keep_a_verifiable_receipt() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+
+        let mut payer = LocalWallet::load_from(&root_dir).await?;
+        let payer_dbc =
+            create_first_dbc_from_key(&payer.key).expect("Genesis creation to succeed.");
+        payer.deposit(vec![payer_dbc]);
+
+        let mut rng = rand::thread_rng();
+        let paid_for = vec![XorName::random(&mut rng), XorName::random(&mut rng)];
+        let to = vec![(Token::from_nano(100), MainKey::random().public_address())];
+        let receipt = payer
+            .pay_for_storage(to, paid_for.clone(), &MockSendClient)
+            .await?;
+
+        assert_eq!(100, receipt.amount.as_nano());
+        assert_eq!(GENESIS_DBC_AMOUNT - 100, payer.balance().as_nano());
+        receipt.verify()?;
+
+        drop(payer);
+        let reloaded = LocalWallet::load_from(&root_dir).await?;
+        let kept = reloaded
+            .payment_receipt(&paid_for[1])
+            .expect("The receipt should be kept.");
+        assert_eq!(paid_for, kept.paid_for);
+        assert!(reloaded
+            .payment_receipt(&XorName::random(&mut rng))
+            .is_none());
+
+        // The spends were not made for other addresses than those paid for.
+        let mut forged = kept.clone();
+        forged.paid_for.push(XorName::random(&mut rng));
+        assert!(matches!(
+            forged.verify(),
+            Err(Error::InvalidPaymentReceipt(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn transfers_are_signed_offline_and_broadcast_by_the_watch_only_wallet() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, Wallet};
+
+        let offline_dir = create_temp_dir();
+        let online_dir = create_temp_dir();
+
+        // The key is only on the offline machine.
+        let mut offline = LocalWallet::load_from(offline_dir.path()).await?;
+        let mut watch_only = WatchOnlyWallet::create(online_dir.path(), offline.address()).await?;
+        let dbc = create_first_dbc_from_key(&offline.key).expect("Genesis creation to succeed.");
+        assert_eq!(1, watch_only.deposit(vec![dbc.clone(), dbc]).len());
+
+        let recipient = MainKey::random().public_address();
+        let unsigned = watch_only.build_transfer(vec![(Token::from_nano(100), recipient)])?;
+        let unsigned = UnsignedTransfer::decode(&unsigned.encode()?)?;
+
+        let signed = offline.sign_transfer(&unsigned)?;
+        let signed_file = signed.encode()?;
+        assert!(matches!(
+            UnsignedTransfer::decode(&signed_file),
+            Err(Error::InvalidTransferFile(_))
+        ));
+        let signed = SignedTransfer::decode(&signed_file)?;
+
+        let transfer_files = watch_only.broadcast(&signed, &MockSendClient).await?;
+        assert_eq!(1, transfer_files.len());
+        let transfer =
+            OfflineTransfer::decode(&tokio::fs::read_to_string(&transfer_files[0]).await?)?;
+        assert_eq!(recipient, transfer.recipient);
+        assert_eq!(100, transfer.amount.as_nano());
+
+        // The change is watched, in place of the dbc spent.
+        drop(watch_only);
+        let watch_only = WatchOnlyWallet::load(online_dir.path()).await?;
+        let change: Vec<_> = watch_only.dbcs().cloned().collect();
+        assert_eq!(1, change.len());
+        offline.deposit(change);
+        assert_eq!(GENESIS_DBC_AMOUNT - 100, offline.balance().as_nano());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sending_to_many_recipients_spends_the_inputs_once() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+
+        let mut sender = LocalWallet::load_from(&root_dir).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+
+        let recipients: Vec<_> = (1..=3)
+            .map(|amount| {
+                (
+                    Token::from_nano(amount * 100),
+                    MainKey::random().public_address(),
+                )
+            })
+            .collect();
+        let created_dbcs = sender.send(recipients.clone(), &MockSendClient).await?;
+
+        assert_eq!(3, created_dbcs.len());
+        assert_eq!(GENESIS_DBC_AMOUNT - 600, sender.balance().as_nano());
+        for (amount, address) in &recipients {
+            let created = created_dbcs
+                .iter()
+                .find(|created| created.dbc.public_address() == address)
+                .expect("A dbc to be created for each recipient.");
+            assert_eq!(amount.as_nano(), created.amount.value());
+        }
+        // All the dbcs are created in the same transaction, from the same spends.
+        let spends = &created_dbcs[0].dbc.signed_spends;
+        assert!(created_dbcs
+            .iter()
+            .all(|created| &created.dbc.signed_spends == spends));
+        assert_eq!(1, sender.wallet.spent_dbcs.len());
+        assert_eq!(4, sender.history().len());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn transfers_are_recorded_in_history() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet};
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+
+        let mut sender = LocalWallet::load_from(&root_dir).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        let sender_dbc_id = sender_dbc.id();
+        sender.deposit(vec![sender_dbc.clone()]);
+        // Depositing the same dbc again is not another receipt.
+        sender.deposit(vec![sender_dbc]);
+
+        let send_amount = 100;
+        let recipient_public_address = MainKey::random().public_address();
+        let to = vec![(Token::from_nano(send_amount), recipient_public_address)];
+        let created_dbcs = sender.send(to, &MockSendClient).await?;
+
+        // The change of the send is not recorded as a receipt.
+        assert_eq!(2, sender.history().len());
+
+        let received = &sender.history()[0];
+        assert_eq!(TransferDirection::Received, received.direction);
+        assert_eq!(GENESIS_DBC_AMOUNT, received.amount.as_nano());
+        assert_eq!(None, received.counterpart);
+        assert_eq!(vec![sender_dbc_id], received.dbc_ids);
+
+        let sent = &sender.history()[1];
+        assert_eq!(TransferDirection::Sent, sent.direction);
+        assert_eq!(send_amount, sent.amount.as_nano());
+        assert_eq!(Some(recipient_public_address), sent.counterpart);
+        assert_eq!(vec![created_dbcs[0].dbc.id()], sent.dbc_ids);
+
+        sender.store().await?;
+        let sender_history = sender.history().to_vec();
+        drop(sender);
+        let deserialized = LocalWallet::load_from(&root_dir).await?;
+        assert_eq!(sender_history, deserialized.history());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn consolidate_combines_dbcs_in_batches() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let sender_dir = create_temp_dir();
+        let mut sender = LocalWallet::load_from(sender_dir.path()).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+
+        let recipient_dir = create_temp_dir();
+        let mut recipient = LocalWallet::load_from(recipient_dir.path()).await?;
+        for amount in [100, 200, 300, 400, 500] {
+            let to = vec![(Token::from_nano(amount), recipient.address())];
+            let created_dbcs = sender.send(to, &MockSendClient).await?;
+            recipient.deposit(
+                created_dbcs
+                    .into_iter()
+                    .map(|created| created.dbc)
+                    .collect(),
+            );
+        }
+        assert_eq!(5, recipient.wallet.available_dbcs.len());
+        let history_len = recipient.history().len();
+
+        // Two batches of two dbcs are combined, while the largest dbc is left alone.
+        let transfers = recipient.consolidate(2, &MockSendClient).await?;
+
+        assert_eq!(2, transfers);
+        assert_eq!(3, recipient.wallet.available_dbcs.len());
+        assert_eq!(4, recipient.wallet.spent_dbcs.len());
+        assert_eq!(1500, recipient.balance().as_nano());
+        let mut amounts: Vec<_> = recipient
+            .available_dbcs()
+            .into_iter()
+            .map(|(_, amount)| amount.as_nano())
+            .collect();
+        amounts.sort();
+        assert_eq!(vec![300, 500, 700], amounts);
+        assert_eq!(history_len, recipient.history().len());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encrypted_wallet_to_and_from_file() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, Wallet};
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+
+        let mut depositor = LocalWallet::load_from(&root_dir).await?;
+        let genesis =
+            create_first_dbc_from_key(&depositor.key).expect("Genesis creation to succeed.");
+        depositor.deposit(vec![genesis]);
+        depositor.set_passphrase(Some("passphrase".to_string()));
+        depositor.store().await?;
+        let depositor_address = depositor.address();
+        let depositor_history = depositor.history().to_vec();
+        drop(depositor);
+
+        assert!(LocalWallet::is_encrypted(&root_dir).await?);
+        assert!(matches!(
+            LocalWallet::load_from(&root_dir).await,
+            Err(Error::PassphraseRequired)
+        ));
+        assert!(matches!(
+            LocalWallet::load_with_passphrase(&root_dir, Some("wrong".to_string())).await,
+            Err(Error::WrongPassphrase)
+        ));
+
+        let mut deserialized =
+            LocalWallet::load_with_passphrase(&root_dir, Some("passphrase".to_string())).await?;
+        assert_eq!(depositor_address, deserialized.address());
+        assert_eq!(GENESIS_DBC_AMOUNT, deserialized.balance().as_nano());
+        assert_eq!(depositor_history, deserialized.history());
+
+        // Removing the passphrase stores the wallet unencrypted again.
+        deserialized.set_passphrase(None);
+        deserialized.store().await?;
+        drop(deserialized);
+        assert!(!LocalWallet::is_encrypted(&root_dir).await?);
+        let decrypted = LocalWallet::load_from(&root_dir).await?;
+        assert_eq!(GENESIS_DBC_AMOUNT, decrypted.balance().as_nano());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wallet_is_restored_from_its_mnemonic() -> Result<()> {
+        // Bring in the necessary trait.
+        use super::Wallet;
+
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+        let original = LocalWallet::load_from(&root_dir).await?;
+        let original_address = original.address();
+        let mnemonic = original
+            .mnemonic()
+            .expect("New wallets to have a mnemonic.");
+        drop(original);
+
+        // Restoring the same wallet keeps it as it is.
+        let restored =
+            LocalWallet::restore_from_mnemonic(&root_dir, &mnemonic, None, false).await?;
+        assert_eq!(original_address, restored.address());
+        drop(restored);
+
+        // Restoring to another dir recreates the same key.
+        let other_dir = create_temp_dir();
+        let restored =
+            LocalWallet::restore_from_mnemonic(other_dir.path(), &mnemonic, None, false).await?;
+        assert_eq!(original_address, restored.address());
+        drop(restored);
+        let loaded = LocalWallet::load_from(other_dir.path()).await?;
+        assert_eq!(original_address, loaded.address());
+        assert_eq!(Some(mnemonic), loaded.mnemonic());
+
+        // Another wallet is only replaced when asked to.
+        let other_mnemonic = LocalWallet::load_from(create_temp_dir().path())
+            .await?
+            .mnemonic()
+            .expect("New wallets to have a mnemonic.");
+        assert!(matches!(
+            LocalWallet::restore_from_mnemonic(&root_dir, &other_mnemonic, None, false).await,
+            Err(Error::WalletAlreadyExists)
+        ));
+        let replaced =
+            LocalWallet::restore_from_mnemonic(&root_dir, &other_mnemonic, None, true).await?;
+        assert_ne!(original_address, replaced.address());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tokens_sent_to_receive_addresses_are_spendable() -> Result<()> {
+        // Bring in the necessary traits.
+        use super::{DepositWallet, SendWallet, Wallet};
+
+        let sender_dir = create_temp_dir();
+        let mut sender = LocalWallet::load_from(sender_dir.path()).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+
+        let recipient_dir = create_temp_dir();
+        let mut recipient = LocalWallet::load_from(recipient_dir.path()).await?;
+        let alice = recipient.new_receive_address("alice".to_string())?;
+        let bob = recipient.new_receive_address("bob".to_string())?;
+        assert_ne!(alice, bob);
+        assert_ne!(recipient.address(), alice);
+
+        for (amount, address) in [(100, alice), (200, bob)] {
+            let to = vec![(Token::from_nano(amount), address)];
+            let created_dbcs = sender.send(to, &MockSendClient).await?;
+            recipient.deposit(
+                created_dbcs
+                    .into_iter()
+                    .map(|created| created.dbc)
+                    .collect(),
+            );
+        }
+        assert_eq!(300, recipient.balance().as_nano());
+
+        // The addresses are the same once loaded again, and their tokens can be spent.
+        recipient.store().await?;
+        drop(recipient);
+        let mut recipient = LocalWallet::load_from(recipient_dir.path()).await?;
+        let receive_addresses = recipient.receive_addresses()?;
+        assert_eq!(2, receive_addresses.len());
+        assert_eq!("alice", receive_addresses[0].0.label);
+        assert_eq!(alice, receive_addresses[0].1);
+        assert_eq!(bob, receive_addresses[1].1);
+
+        let to = vec![(Token::from_nano(250), MainKey::random().public_address())];
+        let _created_dbcs = recipient.send(to, &MockSendClient).await?;
+        assert_eq!(50, recipient.balance().as_nano());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn send_wallet_to_and_from_file() -> Result<()> {
         // Bring in the necessary traits.
@@ -532,26 +1696,29 @@ mod tests {
         let _created_dbcs = sender.send(to, &MockSendClient).await?;
 
         sender.store().await?;
+        let sender_address = sender.address();
+        assert_eq!(GENESIS_DBC_AMOUNT - send_amount, sender.balance().as_nano());
+        let sender_wallet = std::mem::replace(&mut sender.wallet, KeyLessWallet::new());
 
+        // The wallet is locked until the instance holding it is dropped.
+        drop(sender);
         let deserialized = LocalWallet::load_from(&root_dir).await?;
 
-        assert_eq!(sender.address(), deserialized.address());
-        assert_eq!(GENESIS_DBC_AMOUNT - send_amount, sender.balance().as_nano());
+        assert_eq!(sender_address, deserialized.address());
         assert_eq!(
             GENESIS_DBC_AMOUNT - send_amount,
             deserialized.balance().as_nano()
         );
 
-        assert_eq!(1, sender.wallet.available_dbcs.len());
-        assert_eq!(1, sender.wallet.dbcs_created_for_others.len());
-        assert_eq!(1, sender.wallet.spent_dbcs.len());
+        assert_eq!(1, sender_wallet.available_dbcs.len());
+        assert_eq!(1, sender_wallet.dbcs_created_for_others.len());
+        assert_eq!(1, sender_wallet.spent_dbcs.len());
 
         assert_eq!(1, deserialized.wallet.available_dbcs.len());
         assert_eq!(1, deserialized.wallet.dbcs_created_for_others.len());
         assert_eq!(1, deserialized.wallet.spent_dbcs.len());
 
-        let a_available = sender
-            .wallet
+        let a_available = sender_wallet
             .available_dbcs
             .values()
             .last()
@@ -564,7 +1731,7 @@ mod tests {
             .expect("There to be an available DBC.");
         assert_eq!(a_available, b_available);
 
-        let a_created_for_others = &sender.wallet.dbcs_created_for_others[0];
+        let a_created_for_others = &sender_wallet.dbcs_created_for_others[0];
         let b_created_for_others = &deserialized.wallet.dbcs_created_for_others[0];
         assert_eq!(a_created_for_others.dbc, b_created_for_others.dbc);
         assert_eq!(
@@ -576,8 +1743,7 @@ mod tests {
             b_created_for_others.amount.blinding_factor
         );
 
-        let a_spent = sender
-            .wallet
+        let a_spent = sender_wallet
             .spent_dbcs
             .values()
             .last()
@@ -672,6 +1838,56 @@ mod tests {
         }
     }
 
+    #[derive(Clone)]
+    struct UnreachableSendClient;
+
+    #[async_trait::async_trait]
+    impl SendClient for UnreachableSendClient {
+        async fn send(&self, _transfer: TransferDetails) -> super::Result<()> {
+            Err(super::Error::CouldNotSendTokens(
+                "The network is unreachable.".to_string(),
+            ))
+        }
+    }
+
+    /// Registers the spends sent in memory, as the network would.
+    #[derive(Clone, Default)]
+    struct MockNetwork {
+        spends: Arc<Mutex<BTreeMap<DbcId, SignedSpend>>>,
+    }
+
+    impl MockNetwork {
+        fn registered_spends(&self) -> usize {
+            self.spends
+                .lock()
+                .expect("The lock not to be poisoned.")
+                .len()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl SendClient for MockNetwork {
+        async fn send(&self, transfer: TransferDetails) -> super::Result<()> {
+            let mut spends = self.spends.lock().expect("The lock not to be poisoned.");
+            for request in transfer.all_spend_requests {
+                let _ = spends.insert(*request.signed_spend.dbc_id(), request.signed_spend);
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl VerifyingClient for MockNetwork {
+        async fn verify(&self, _dbc: &Dbc) -> super::Result<()> {
+            Ok(())
+        }
+
+        async fn registered_spend(&self, dbc_id: &DbcId) -> super::Result<Option<SignedSpend>> {
+            let spends = self.spends.lock().expect("The lock not to be poisoned.");
+            Ok(spends.get(dbc_id).cloned())
+        }
+    }
+
     fn create_temp_dir() -> TempDir {
         TempDir::new().expect("Should be able to create a temp dir.")
     }
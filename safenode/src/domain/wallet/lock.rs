@@ -0,0 +1,125 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use fs2::FileExt;
+use lazy_static::lazy_static;
+use std::{
+    collections::BTreeSet,
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Filename of the lock file in the wallet dir.
+pub(super) const LOCK_FILE_NAME: &str = "lock";
+/// How long to wait for another wallet instance to release the wallet.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+lazy_static! {
+    /// The canonical paths of the wallet dirs locked by this process.
+    static ref HELD_LOCKS: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+}
+
+/// An exclusive advisory lock of a wallet dir, held by a loaded wallet until it's dropped, so that
+/// two wallet instances, in the same or in different processes, can't both use the wallet at the
+/// same time, e.g. spending the same dbc twice, or overwriting each other's changes to the files.
+#[derive(Debug)]
+pub(super) struct WalletLock {
+    /// The canonical path of the wallet dir.
+    wallet_dir: PathBuf,
+    file: File,
+}
+
+impl WalletLock {
+    /// Locks the wallet dir, waiting for a while if another wallet instance holds it.
+    pub(super) async fn acquire(wallet_dir: &Path) -> Result<Self> {
+        let start = Instant::now();
+        loop {
+            match Self::try_acquire(wallet_dir) {
+                Err(Error::WalletLocked) if start.elapsed() < LOCK_TIMEOUT => {
+                    tokio::time::sleep(LOCK_RETRY_INTERVAL).await
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Locks the wallet dir, unless another wallet instance holds it.
+    pub(super) fn try_acquire(wallet_dir: &Path) -> Result<Self> {
+        // The same dir can be reached through different paths.
+        let wallet_dir = wallet_dir.canonicalize()?;
+        let mut held_locks = HELD_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+        if held_locks.contains(&wallet_dir) {
+            return Err(Error::WalletLocked);
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(wallet_dir.join(LOCK_FILE_NAME))?;
+        if let Err(err) = file.try_lock_exclusive() {
+            if err.raw_os_error() == fs2::lock_contended_error().raw_os_error() {
+                return Err(Error::WalletLocked);
+            }
+            return Err(err.into());
+        }
+        let _ = held_locks.insert(wallet_dir.clone());
+        Ok(Self { wallet_dir, file })
+    }
+}
+
+impl Drop for WalletLock {
+    fn drop(&mut self) {
+        let mut held_locks = HELD_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = self.file.unlock();
+        let _ = held_locks.remove(&self.wallet_dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use assert_fs::TempDir;
+
+    #[test]
+    fn wallet_lock_is_exclusive_per_wallet_instance() -> Result<()> {
+        let dir = TempDir::new().expect("Should be able to create a temp dir.");
+        let other_process_lock = || -> std::io::Result<()> {
+            let file = File::open(dir.path().join(LOCK_FILE_NAME))?;
+            let result = file.try_lock_exclusive();
+            let _ = file.unlock();
+            result
+        };
+
+        let first = WalletLock::try_acquire(dir.path())?;
+        assert!(other_process_lock().is_err());
+        assert!(matches!(
+            WalletLock::try_acquire(dir.path()),
+            Err(Error::WalletLocked)
+        ));
+        assert!(matches!(
+            WalletLock::try_acquire(&dir.path().join(".")),
+            Err(Error::WalletLocked)
+        ));
+
+        drop(first);
+        assert!(other_process_lock().is_ok());
+
+        let second = WalletLock::try_acquire(&dir.path().join("."))?;
+        assert!(other_process_lock().is_err());
+        drop(second);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    lock::LOCK_FILE_NAME,
+};
+
+use std::path::Path;
+use tokio::fs;
+
+/// The version of the layout of the wallet dir written by this release.
+/// It's to be increased, along with a migration from the previous version,
+/// whenever a wallet file changes in a way the previous release can't read.
+pub const WALLET_FORMAT_VERSION: u32 = 1;
+
+/// Filename of the version of the layout of the wallet dir.
+const VERSION_FILE_NAME: &str = "version";
+
+/// Migrates the wallet dir from the layout of an older release to the current one, if needed,
+/// and stamps it with the current version. The files are backed up to a `pre-migration-v<version>`
+/// dir within the wallet dir before any migration. A wallet of a newer release is not opened,
+/// as it could lose what that release stored.
+pub(super) async fn migrate(wallet_dir: &Path) -> Result<()> {
+    let mut version = match format_version(wallet_dir).await? {
+        Some(version) if version == WALLET_FORMAT_VERSION => return Ok(()),
+        Some(version) if version > WALLET_FORMAT_VERSION => {
+            return Err(Error::UnsupportedWalletVersion(version))
+        }
+        Some(version) => version,
+        // Wallets were not stamped with a version before the first one.
+        None => 0,
+    };
+
+    if has_files(wallet_dir).await? {
+        backup(wallet_dir, version).await?;
+    }
+
+    while version < WALLET_FORMAT_VERSION {
+        match version {
+            // The layout of unversioned wallets is the one of the first version.
+            0 => {}
+            _ => unreachable!("A migration exists for every version before the current one."),
+        }
+        version += 1;
+        info!("Migrated the wallet at {wallet_dir:?} to version {version}");
+    }
+
+    fs::write(wallet_dir.join(VERSION_FILE_NAME), version.to_string()).await?;
+    Ok(())
+}
+
+async fn format_version(wallet_dir: &Path) -> Result<Option<u32>> {
+    let path = wallet_dir.join(VERSION_FILE_NAME);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let version = fs::read_to_string(path).await?;
+    version
+        .trim()
+        .parse()
+        .map(Some)
+        .map_err(|_| Error::InvalidWalletVersion(version))
+}
+
+/// Whether there are any wallet files, i.e. it's not a new wallet.
+async fn has_files(wallet_dir: &Path) -> Result<bool> {
+    let mut entries = fs::read_dir(wallet_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() && entry.file_name() != LOCK_FILE_NAME {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Copies the files of the wallet dir, but not its subdirs, which are not migrated.
+async fn backup(wallet_dir: &Path, version: u32) -> Result<()> {
+    let backup_dir = wallet_dir.join(format!("pre-migration-v{version}"));
+    fs::create_dir_all(&backup_dir).await?;
+    let mut entries = fs::read_dir(wallet_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            let _ = fs::copy(entry.path(), backup_dir.join(entry.file_name())).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use assert_fs::TempDir;
+
+    #[tokio::test]
+    async fn unversioned_wallets_are_stamped_and_newer_ones_refused() -> Result<()> {
+        let dir = TempDir::new().expect("Should be able to create a temp dir.");
+        let wallet_dir = dir.path();
+        fs::write(wallet_dir.join("wallet"), b"unversioned").await?;
+
+        migrate(wallet_dir).await?;
+        assert_eq!(
+            Some(WALLET_FORMAT_VERSION),
+            format_version(wallet_dir).await?
+        );
+        let backup = wallet_dir.join("pre-migration-v0").join("wallet");
+        assert_eq!(b"unversioned".to_vec(), fs::read(backup).await?);
+
+        let newer = WALLET_FORMAT_VERSION + 1;
+        fs::write(wallet_dir.join(VERSION_FILE_NAME), newer.to_string()).await?;
+        assert!(matches!(
+            migrate(wallet_dir).await,
+            Err(Error::UnsupportedWalletVersion(version)) if version == newer
+        ));
+
+        Ok(())
+    }
+}
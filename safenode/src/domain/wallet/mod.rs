@@ -52,22 +52,43 @@
 //! which eventually clears from the mempool and becomes spendable again.
 //!
 
+mod amount;
+mod commit;
+mod encryption;
 mod error;
+mod history;
+mod inbox;
 mod keys;
 mod local_store;
+mod lock;
+mod migration;
 mod network_store;
+mod payment_receipt;
+mod receive_address;
+mod signing;
+mod transfer_file;
 mod wallet_file;
+mod watch_only;
 
 pub use self::{
+    amount::TokenAmount,
     error::{Error, Result},
+    history::{HistoryEntry, HistoryFilter, TransferDirection},
+    inbox::{encrypt_for_inbox, inbox_name, INBOX_REGISTER_TAG},
     keys::parse_public_address,
     local_store::LocalWallet,
+    migration::WALLET_FORMAT_VERSION,
+    payment_receipt::{payment_reason, PaymentReceipt},
+    receive_address::ReceiveAddress,
+    signing::{SignedTransfer, UnsignedTransfer, SIGNING_FILE_VERSION},
+    transfer_file::{OfflineTransfer, TRANSFER_FILE_VERSION},
+    watch_only::WatchOnlyWallet,
     // network_store::NetworkWallet,
 };
 
 use super::client_transfers::{CreatedDbc, Outputs as TransferDetails};
 
-use sn_dbc::{Dbc, DbcId, DbcIdSource, PublicAddress, Token};
+use sn_dbc::{Dbc, DbcId, DbcIdSource, PublicAddress, SignedSpend, Token};
 
 use async_trait::async_trait;
 use std::collections::BTreeMap;
@@ -101,6 +122,8 @@ pub trait SendClient: Send + Sync + Clone {
 pub trait VerifyingClient: Send + Sync + Clone {
     ///
     async fn verify(&self, dbc: &Dbc) -> Result<()>;
+    /// Returns the spend of the dbc registered in the network, or `None` if it's not spent yet.
+    async fn registered_spend(&self, dbc_id: &DbcId) -> Result<Option<SignedSpend>>;
 }
 
 /// A wallet has an address and a balance.
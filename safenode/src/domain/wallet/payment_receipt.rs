@@ -0,0 +1,94 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use sn_dbc::{Hash, SignedSpend, Token};
+use xor_name::XorName;
+
+/// The hash the spends of a payment for storing data are made for, i.e. their reason,
+/// committing them to the addresses of the data paid for, in the given order.
+pub fn payment_reason(paid_for: &[XorName]) -> Hash {
+    let bytes: Vec<u8> = paid_for.iter().flat_map(|name| name.0).collect();
+    Hash::hash(&bytes)
+}
+
+/// The receipt of a payment for storing data, kept by the wallet which paid, to prove
+/// the payment was made if the network reports it missing, e.g. when the data is uploaded again.
+/// The spends of the payment are made for the hash of the addresses paid for, thus anyone
+/// can check they paid for those, and fetch them from the network to check they were registered.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaymentReceipt {
+    /// The addresses of the data paid for.
+    pub paid_for: Vec<XorName>,
+    /// The amount paid.
+    pub amount: Token,
+    /// The spends of the payment.
+    pub spends: Vec<SignedSpend>,
+    /// When the payment was made, in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl PaymentReceipt {
+    /// Creates the receipt of a payment made now.
+    pub fn new(paid_for: Vec<XorName>, amount: Token, spends: Vec<SignedSpend>) -> Self {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self {
+            paid_for,
+            amount,
+            spends,
+            timestamp,
+        }
+    }
+
+    /// Whether the data at the address was paid for.
+    pub fn covers(&self, address: &XorName) -> bool {
+        self.paid_for.contains(address)
+    }
+
+    /// Checks the spends are signed by the keys of the dbcs they spend,
+    /// and were made for the addresses paid for.
+    pub fn verify(&self) -> Result<()> {
+        if self.spends.is_empty() {
+            return Err(Error::InvalidPaymentReceipt(
+                "The receipt holds no spends".to_string(),
+            ));
+        }
+        let reason = payment_reason(&self.paid_for);
+        for spend in &self.spends {
+            spend.verify(spend.dst_tx_hash())?;
+            if spend.reason() != reason {
+                return Err(Error::InvalidPaymentReceipt(format!(
+                    "The spend of {:?} was not made for the addresses paid for",
+                    spend.dbc_id()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payment_reasons_commit_to_the_addresses_and_their_order() {
+        let mut rng = rand::thread_rng();
+        let a = XorName::random(&mut rng);
+        let b = XorName::random(&mut rng);
+
+        assert_eq!(payment_reason(&[a, b]), payment_reason(&[a, b]));
+        assert_ne!(payment_reason(&[a, b]), payment_reason(&[b, a]));
+        assert_ne!(payment_reason(&[a]), payment_reason(&[a, b]));
+    }
+}
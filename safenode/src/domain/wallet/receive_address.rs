@@ -0,0 +1,44 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::error::{Error, Result};
+
+use sn_dbc::{Dbc, DerivedKey, MainKey};
+
+use serde::{Deserialize, Serialize};
+
+/// A receiving address of the wallet, handed out to a single counterparty.
+/// Its key is derived from the main key of the wallet, at the index of the address,
+/// so the tokens sent to it can be spent with the main key alone.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiveAddress {
+    /// The index the key of the address is derived at.
+    pub index: u32,
+    /// What the address is for, e.g. the name of the counterparty it was handed out to.
+    pub label: String,
+}
+
+impl ReceiveAddress {
+    /// Derives the key of the address from the main key of the wallet.
+    pub(super) fn main_key(&self, main_key: &MainKey) -> Result<MainKey> {
+        let bytes: [u8; bls::SK_SIZE] = main_key
+            .to_bytes()
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::FailedToParseBlsKey)?;
+        let secret_key = bls::SecretKey::from_bytes(bytes)?;
+        Ok(MainKey::new(
+            secret_key.derive_child(&self.index.to_be_bytes()),
+        ))
+    }
+}
+
+/// Returns the derived key of the dbc, if any of the given keys can access it.
+pub(super) fn dbc_derived_key(dbc: &Dbc, keys: &[MainKey]) -> Option<DerivedKey> {
+    keys.iter().find_map(|key| dbc.derived_key(key).ok())
+}
@@ -0,0 +1,130 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! The intermediate artifacts of transfers signed on an air-gapped machine: the unsigned transfer
+//! built by the watch-only wallet on an online machine, and the signed transfer made of it by the
+//! wallet holding the key on the offline machine, to be broadcast from the online one.
+//! They are passed between the machines as files, e.g. on a USB stick.
+
+use super::{
+    error::{Error, Result},
+    TransferDetails,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sn_dbc::{Dbc, PublicAddress, Token};
+use tiny_keccak::{Hasher, Sha3};
+
+/// The version of the format of the unsigned and signed transfer files written by this client.
+/// Files of any version up to this one can be read.
+pub const SIGNING_FILE_VERSION: u16 = 1;
+
+/// A transfer to be signed by the wallet holding the key, with the dbcs its inputs are selected from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnsignedTransfer {
+    /// The dbcs of the watch-only wallet, which the inputs of the transfer are selected from.
+    pub inputs: Vec<Dbc>,
+    /// The amounts to send, and the addresses to send them to.
+    pub recipients: Vec<(Token, PublicAddress)>,
+    /// The address of the wallet, which any change is sent to.
+    pub change_to: PublicAddress,
+}
+
+/// A transfer signed by the wallet holding the key, to be registered in the network.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedTransfer {
+    /// The spends of the transfer, and the dbcs it created.
+    pub transfer: TransferDetails,
+    /// The amounts sent, and the addresses they were sent to.
+    pub recipients: Vec<(Token, PublicAddress)>,
+}
+
+/// The content of an unsigned or signed transfer file, as serialized.
+#[derive(Serialize, Deserialize)]
+struct SigningFileContent {
+    version: u16,
+    kind: String,
+    data: String,
+    checksum: String,
+}
+
+impl SigningFileContent {
+    /// The hex-encoded Sha3 hash of the fields, in a fixed order.
+    fn checksum(&self) -> String {
+        let mut hasher = Sha3::v256();
+        hasher.update(&self.version.to_be_bytes());
+        hasher.update(self.kind.as_bytes());
+        hasher.update(self.data.as_bytes());
+        let mut hash = [0; 32];
+        hasher.finalize(&mut hash);
+        hex::encode(hash)
+    }
+}
+
+impl UnsignedTransfer {
+    const KIND: &'static str = "unsigned_transfer";
+
+    /// Serialize the transfer into the content of an unsigned transfer file.
+    pub fn encode(&self) -> Result<String> {
+        encode(Self::KIND, self)
+    }
+
+    /// Deserialize a transfer from the content of an unsigned transfer file.
+    pub fn decode(text: &str) -> Result<Self> {
+        decode(Self::KIND, text)
+    }
+}
+
+impl SignedTransfer {
+    const KIND: &'static str = "signed_transfer";
+
+    /// Serialize the transfer into the content of a signed transfer file.
+    pub fn encode(&self) -> Result<String> {
+        encode(Self::KIND, self)
+    }
+
+    /// Deserialize a transfer from the content of a signed transfer file.
+    pub fn decode(text: &str) -> Result<Self> {
+        decode(Self::KIND, text)
+    }
+}
+
+fn encode<T: Serialize>(kind: &str, value: &T) -> Result<String> {
+    let mut content = SigningFileContent {
+        version: SIGNING_FILE_VERSION,
+        kind: kind.to_string(),
+        data: hex::encode(bincode::serialize(value)?),
+        checksum: String::new(),
+    };
+    content.checksum = content.checksum();
+    serde_json::to_string_pretty(&content)
+        .map_err(|err| Error::InvalidTransferFile(err.to_string()))
+}
+
+/// Deserializes the content of a file of the given kind, checking it's not corrupted.
+fn decode<T: DeserializeOwned>(kind: &str, text: &str) -> Result<T> {
+    let content: SigningFileContent =
+        serde_json::from_str(text).map_err(|err| Error::InvalidTransferFile(err.to_string()))?;
+    if content.version > SIGNING_FILE_VERSION {
+        return Err(Error::UnsupportedTransferFileVersion(content.version));
+    }
+    if content.checksum != content.checksum() {
+        return Err(Error::InvalidTransferFile(
+            "The checksum doesn't match the content, the file is corrupted".to_string(),
+        ));
+    }
+    if content.kind != kind {
+        return Err(Error::InvalidTransferFile(format!(
+            "Expected a {kind} file, but it's a {} file",
+            content.kind
+        )));
+    }
+    let data =
+        hex::decode(&content.data).map_err(|err| Error::InvalidTransferFile(err.to_string()))?;
+    Ok(bincode::deserialize(&data)?)
+}
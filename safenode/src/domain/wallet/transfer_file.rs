@@ -0,0 +1,181 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    keys::parse_public_address,
+};
+
+use sn_dbc::{Dbc, PublicAddress, Token};
+
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tiny_keccak::{Hasher, Sha3};
+
+/// The version of the transfer file format written by this client.
+/// Files of any version up to this one can be read.
+pub const TRANSFER_FILE_VERSION: u16 = 1;
+
+/// The tokens sent to a recipient, in a form to be passed over any channel, e.g. email or USB,
+/// and deposited by the recipient, even if using another version of the client.
+///
+/// It's stored as a JSON document with the format version, the dbcs hex-encoded, and a checksum
+/// of the content, to tell a corrupted file from a valid one. Fields not known to a client are
+/// ignored, so newer versions of the format can add fields while remaining readable by older clients.
+#[derive(Clone, Debug)]
+pub struct OfflineTransfer {
+    /// The dbcs created for the recipient.
+    pub dbcs: Vec<Dbc>,
+    /// The address the tokens were sent to.
+    pub recipient: PublicAddress,
+    /// The total amount sent.
+    pub amount: Token,
+    /// When the transfer was made, in seconds since the Unix epoch.
+    pub created: u64,
+}
+
+/// The content of a transfer file, as serialized.
+#[derive(Serialize, Deserialize)]
+struct TransferFileContent {
+    version: u16,
+    recipient: String,
+    amount: u64,
+    created: u64,
+    dbcs: Vec<String>,
+    checksum: String,
+}
+
+impl TransferFileContent {
+    /// The hex-encoded Sha3 hash of the fields, in a fixed order.
+    fn checksum(&self) -> String {
+        let mut hasher = Sha3::v256();
+        hasher.update(&self.version.to_be_bytes());
+        hasher.update(self.recipient.as_bytes());
+        hasher.update(&self.amount.to_be_bytes());
+        hasher.update(&self.created.to_be_bytes());
+        for dbc in &self.dbcs {
+            hasher.update(dbc.as_bytes());
+        }
+        let mut hash = [0; 32];
+        hasher.finalize(&mut hash);
+        hex::encode(hash)
+    }
+}
+
+impl OfflineTransfer {
+    /// A transfer of the given dbcs, made now.
+    pub fn new(dbcs: Vec<Dbc>, recipient: PublicAddress, amount: Token) -> Self {
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self {
+            dbcs,
+            recipient,
+            amount,
+            created,
+        }
+    }
+
+    /// Whether the text looks like a transfer file, rather than e.g. a hex-encoded dbc.
+    pub fn is_transfer_file(text: &str) -> bool {
+        text.trim_start().starts_with('{')
+    }
+
+    /// Serialize the transfer into the content of a transfer file.
+    pub fn encode(&self) -> Result<String> {
+        let mut content = TransferFileContent {
+            version: TRANSFER_FILE_VERSION,
+            recipient: hex::encode(self.recipient.to_bytes()),
+            amount: self.amount.as_nano(),
+            created: self.created,
+            dbcs: self
+                .dbcs
+                .iter()
+                .map(|dbc| dbc.to_hex())
+                .collect::<std::result::Result<_, _>>()?,
+            checksum: String::new(),
+        };
+        content.checksum = content.checksum();
+        serde_json::to_string_pretty(&content)
+            .map_err(|err| Error::InvalidTransferFile(err.to_string()))
+    }
+
+    /// Deserialize a transfer from the content of a transfer file, checking it's not corrupted.
+    pub fn decode(text: &str) -> Result<Self> {
+        let content: TransferFileContent = serde_json::from_str(text)
+            .map_err(|err| Error::InvalidTransferFile(err.to_string()))?;
+        if content.version > TRANSFER_FILE_VERSION {
+            return Err(Error::UnsupportedTransferFileVersion(content.version));
+        }
+        if content.checksum != content.checksum() {
+            return Err(Error::InvalidTransferFile(
+                "The checksum doesn't match the content, the file is corrupted".to_string(),
+            ));
+        }
+
+        let dbcs = content
+            .dbcs
+            .iter()
+            .map(|dbc| Dbc::from_hex(dbc))
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self {
+            dbcs,
+            recipient: parse_public_address(&content.recipient)?,
+            amount: Token::from_nano(content.amount),
+            created: content.created,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::domain::dbc_genesis::create_first_dbc_from_key;
+
+    use sn_dbc::MainKey;
+
+    #[test]
+    fn transfer_file_roundtrips_and_detects_corruption() -> eyre::Result<()> {
+        let key = MainKey::random();
+        let dbc = create_first_dbc_from_key(&key).expect("Genesis creation to succeed.");
+        let transfer = OfflineTransfer::new(
+            vec![dbc.clone()],
+            key.public_address(),
+            Token::from_nano(100),
+        );
+
+        let encoded = transfer.encode()?;
+        assert!(OfflineTransfer::is_transfer_file(&encoded));
+        assert!(!OfflineTransfer::is_transfer_file(&dbc.to_hex()?));
+
+        let decoded = OfflineTransfer::decode(&encoded)?;
+        assert_eq!(vec![dbc], decoded.dbcs);
+        assert_eq!(transfer.recipient, decoded.recipient);
+        assert_eq!(transfer.amount, decoded.amount);
+        assert_eq!(transfer.created, decoded.created);
+
+        let corrupted = encoded.replace("\"amount\": 100", "\"amount\": 1000");
+        assert!(matches!(
+            OfflineTransfer::decode(&corrupted),
+            Err(Error::InvalidTransferFile(_))
+        ));
+
+        let newer = encoded.replace(
+            &format!("\"version\": {TRANSFER_FILE_VERSION}"),
+            &format!("\"version\": {}", TRANSFER_FILE_VERSION + 1),
+        );
+        assert!(matches!(
+            OfflineTransfer::decode(&newer),
+            Err(Error::UnsupportedTransferFileVersion(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -6,16 +6,25 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{error::Result, public_address_name, KeyLessWallet};
+use super::{
+    encryption::{read_file, write_file},
+    error::Result,
+    public_address_name,
+    transfer_file::OfflineTransfer,
+    HistoryEntry, KeyLessWallet, PaymentReceipt, ReceiveAddress,
+};
 
 use crate::protocol::storage::DbcAddress;
 
 use sn_dbc::Dbc;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
 // Filename for storing a wallet.
 const WALLET_FILE_NAME: &str = "wallet";
+const HISTORY_FILE_NAME: &str = "history";
+const RECEIVE_ADDRESSES_FILE_NAME: &str = "receive_addresses";
+const PAYMENT_RECEIPTS_FILE_NAME: &str = "payment_receipts";
 const CREATED_DBCS_DIR_NAME: &str = "created_dbcs";
 const RECEIVED_DBCS_DIR_NAME: &str = "received_dbcs";
 
@@ -26,26 +35,108 @@ pub(super) async fn create_received_dbcs_dir(wallet_dir: &Path) -> Result<()> {
 }
 
 /// Writes the `KeyLessWallet` to the specified path.
-pub(super) async fn store_wallet(wallet_dir: &Path, wallet: &KeyLessWallet) -> Result<()> {
+pub(super) async fn store_wallet(
+    wallet_dir: &Path,
+    wallet: &KeyLessWallet,
+    passphrase: Option<&str>,
+) -> Result<()> {
     let wallet_path = wallet_dir.join(WALLET_FILE_NAME);
     let bytes = bincode::serialize(&wallet)?;
-    fs::write(&wallet_path, bytes).await?;
-    Ok(())
+    write_file(&wallet_path, &bytes, passphrase).await
 }
 
 /// Returns `Some(KeyLessWallet)` or None if file doesn't exist.
-pub(super) async fn get_wallet(wallet_dir: &Path) -> Result<Option<KeyLessWallet>> {
+pub(super) async fn get_wallet(
+    wallet_dir: &Path,
+    passphrase: Option<&str>,
+) -> Result<Option<KeyLessWallet>> {
     let path = wallet_dir.join(WALLET_FILE_NAME);
     if !path.is_file() {
         return Ok(None);
     }
 
-    let bytes = fs::read(&path).await?;
+    let bytes = read_file(&path, passphrase).await?;
     let wallet = bincode::deserialize(&bytes)?;
 
     Ok(Some(wallet))
 }
 
+/// Writes the history of the wallet's transfers to the specified path.
+pub(super) async fn store_history(
+    wallet_dir: &Path,
+    history: &[HistoryEntry],
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let history_path = wallet_dir.join(HISTORY_FILE_NAME);
+    let bytes = bincode::serialize(history)?;
+    write_file(&history_path, &bytes, passphrase).await
+}
+
+/// Returns the history of the wallet's transfers, which is empty if the file doesn't exist.
+pub(super) async fn get_history(
+    wallet_dir: &Path,
+    passphrase: Option<&str>,
+) -> Result<Vec<HistoryEntry>> {
+    let path = wallet_dir.join(HISTORY_FILE_NAME);
+    if !path.is_file() {
+        return Ok(vec![]);
+    }
+
+    let bytes = read_file(&path, passphrase).await?;
+    Ok(bincode::deserialize(&bytes)?)
+}
+
+/// Writes the receiving addresses handed out by the wallet to the specified path.
+pub(super) async fn store_receive_addresses(
+    wallet_dir: &Path,
+    receive_addresses: &[ReceiveAddress],
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let path = wallet_dir.join(RECEIVE_ADDRESSES_FILE_NAME);
+    let bytes = bincode::serialize(receive_addresses)?;
+    write_file(&path, &bytes, passphrase).await
+}
+
+/// Returns the receiving addresses handed out by the wallet, which are none if the file doesn't exist.
+pub(super) async fn get_receive_addresses(
+    wallet_dir: &Path,
+    passphrase: Option<&str>,
+) -> Result<Vec<ReceiveAddress>> {
+    let path = wallet_dir.join(RECEIVE_ADDRESSES_FILE_NAME);
+    if !path.is_file() {
+        return Ok(vec![]);
+    }
+
+    let bytes = read_file(&path, passphrase).await?;
+    Ok(bincode::deserialize(&bytes)?)
+}
+
+/// Writes the receipts of the payments for storage made by the wallet to the specified path.
+pub(super) async fn store_payment_receipts(
+    wallet_dir: &Path,
+    receipts: &[PaymentReceipt],
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let path = wallet_dir.join(PAYMENT_RECEIPTS_FILE_NAME);
+    let bytes = bincode::serialize(receipts)?;
+    write_file(&path, &bytes, passphrase).await
+}
+
+/// Returns the receipts of the payments for storage made by the wallet,
+/// which are none if the file doesn't exist.
+pub(super) async fn get_payment_receipts(
+    wallet_dir: &Path,
+    passphrase: Option<&str>,
+) -> Result<Vec<PaymentReceipt>> {
+    let path = wallet_dir.join(PAYMENT_RECEIPTS_FILE_NAME);
+    if !path.is_file() {
+        return Ok(vec![]);
+    }
+
+    let bytes = read_file(&path, passphrase).await?;
+    Ok(bincode::deserialize(&bytes)?)
+}
+
 /// Hex encode and write each `Dbc` to a separate file in respective
 /// recipient public address dir in the created dbcs dir. Each file is named after the dbc id.
 pub(super) async fn store_created_dbcs(created_dbcs: Vec<Dbc>, wallet_dir: &Path) -> Result<()> {
@@ -69,7 +160,29 @@ pub(super) async fn store_created_dbcs(created_dbcs: Vec<Dbc>, wallet_dir: &Path
     Ok(())
 }
 
-/// Loads all the dbcs found in the received dbcs dir.
+/// Write the transfer file to the dir of its recipient public address in the created dbcs dir.
+/// It's named after the id of its first dbc. Returns the path of the file.
+pub(super) async fn store_transfer_file(
+    transfer: &OfflineTransfer,
+    wallet_dir: &Path,
+) -> Result<PathBuf> {
+    let public_address_name = public_address_name(&transfer.recipient);
+    let public_address_dir = format!("public_address_{}", hex::encode(public_address_name));
+    let public_address_dir_path = wallet_dir
+        .join(CREATED_DBCS_DIR_NAME)
+        .join(public_address_dir);
+    fs::create_dir_all(&public_address_dir_path).await?;
+
+    let file_name = match transfer.dbcs.first() {
+        Some(dbc) => hex::encode(DbcAddress::from_dbc_id(&dbc.id()).name()),
+        None => format!("empty_{}", transfer.created),
+    };
+    let transfer_file_path = public_address_dir_path.join(format!("{file_name}.transfer"));
+    fs::write(&transfer_file_path, transfer.encode()?).await?;
+    Ok(transfer_file_path)
+}
+
+/// Loads all the dbcs found in the received dbcs dir, either hex-encoded or in transfer files.
 pub(super) async fn load_received_dbcs(wallet_dir: &Path) -> Result<Vec<Dbc>> {
     // The new dbcs dir within the wallet dir.
     let received_dbcs_path = wallet_dir.join(RECEIVED_DBCS_DIR_NAME);
@@ -84,6 +197,14 @@ pub(super) async fn load_received_dbcs(wallet_dir: &Path) -> Result<Vec<Dbc>> {
             println!("Reading deposited tokens from {file_name:?}.");
 
             let dbc_data = fs::read_to_string(entry.path()).await?;
+            if OfflineTransfer::is_transfer_file(&dbc_data) {
+                match OfflineTransfer::decode(&dbc_data) {
+                    Ok(transfer) => deposits.extend(transfer.dbcs),
+                    Err(err) => println!("This transfer file is not valid: {err}. Skipping it."),
+                }
+                continue;
+            }
+
             let dbc = match Dbc::from_hex(dbc_data.trim()) {
                 Ok(dbc) => dbc,
                 Err(_) => {
@@ -0,0 +1,226 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    commit::{begin_commit, finish_commit, recover_commit, write_atomically},
+    error::{Error, Result},
+    keys::parse_public_address,
+    lock::WalletLock,
+    signing::{SignedTransfer, UnsignedTransfer},
+    wallet_file::{create_received_dbcs_dir, load_received_dbcs, store_transfer_file},
+    OfflineTransfer, SendClient,
+};
+
+use sn_dbc::{Dbc, DbcId, PublicAddress, Token};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use tokio::fs;
+
+const WATCH_ONLY_DIR_NAME: &str = "watch_only_wallet";
+const PUBLIC_ADDRESS_FILE_NAME: &str = "public_address";
+const DBCS_FILE_NAME: &str = "dbcs";
+
+/// A wallet holding the dbcs of an address, without its key, e.g. on an online machine while
+/// the key is kept on an air-gapped one. It builds the transfers the wallet holding the key signs,
+/// and broadcasts them once signed. The amounts of the dbcs can't be revealed without the key,
+/// thus it has no balance. Only the dbcs sent to the main address of the key are watched.
+pub struct WatchOnlyWallet {
+    address: PublicAddress,
+    dbcs: BTreeMap<DbcId, Dbc>,
+    wallet_dir: PathBuf,
+    /// Keeps other processes from using the wallet while it's loaded.
+    _lock: WalletLock,
+}
+
+impl WatchOnlyWallet {
+    /// Creates the watch-only wallet of the address in the root dir, or loads it if it exists.
+    /// A watch-only wallet of another address is not replaced.
+    pub async fn create(root_dir: &Path, address: PublicAddress) -> Result<Self> {
+        let wallet_dir = root_dir.join(WATCH_ONLY_DIR_NAME);
+        fs::create_dir_all(&wallet_dir).await?;
+        create_received_dbcs_dir(&wallet_dir).await?;
+        let lock = WalletLock::acquire(&wallet_dir).await?;
+        recover_commit(&wallet_dir).await?;
+        match read_address(&wallet_dir).await? {
+            Some(existing) if existing != address => return Err(Error::WalletAlreadyExists),
+            Some(_) => {}
+            None => {
+                write_atomically(
+                    &wallet_dir.join(PUBLIC_ADDRESS_FILE_NAME),
+                    hex::encode(address.to_bytes()).as_bytes(),
+                )
+                .await?
+            }
+        }
+        Self::load_locked(wallet_dir, address, lock).await
+    }
+
+    /// Loads the watch-only wallet in the root dir, which has to be created first.
+    pub async fn load(root_dir: &Path) -> Result<Self> {
+        let wallet_dir = root_dir.join(WATCH_ONLY_DIR_NAME);
+        if !wallet_dir.is_dir() {
+            return Err(Error::WatchOnlyWalletNotFound);
+        }
+        let lock = WalletLock::acquire(&wallet_dir).await?;
+        recover_commit(&wallet_dir).await?;
+        let address = read_address(&wallet_dir)
+            .await?
+            .ok_or(Error::WatchOnlyWalletNotFound)?;
+        Self::load_locked(wallet_dir, address, lock).await
+    }
+
+    async fn load_locked(
+        wallet_dir: PathBuf,
+        address: PublicAddress,
+        lock: WalletLock,
+    ) -> Result<Self> {
+        let path = wallet_dir.join(DBCS_FILE_NAME);
+        let dbcs = if path.is_file() {
+            bincode::deserialize(&fs::read(path).await?)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            address,
+            dbcs,
+            wallet_dir,
+            _lock: lock,
+        })
+    }
+
+    /// Stores the dbcs of the wallet to disk.
+    pub async fn store(&self) -> Result<()> {
+        let staging_dir = begin_commit(&self.wallet_dir).await?;
+        write_atomically(
+            &staging_dir.join(DBCS_FILE_NAME),
+            &bincode::serialize(&self.dbcs)?,
+        )
+        .await?;
+        finish_commit(&self.wallet_dir).await
+    }
+
+    /// The address watched.
+    pub fn address(&self) -> PublicAddress {
+        self.address
+    }
+
+    /// The dbcs of the address held by the wallet, which are not known to be spent.
+    pub fn dbcs(&self) -> impl Iterator<Item = &Dbc> {
+        self.dbcs.values()
+    }
+
+    /// The dir the dbcs received are placed in, to be deposited, and the transfer files
+    /// for the recipients of the transfers broadcast are written to.
+    pub fn wallet_dir(&self) -> &Path {
+        &self.wallet_dir
+    }
+
+    /// Adds the dbcs sent to the address which the wallet doesn't hold yet, returning them.
+    /// Their spends have to be verified against the network beforehand.
+    pub fn deposit(&mut self, dbcs: Vec<Dbc>) -> Vec<Dbc> {
+        let mut deposited = vec![];
+        for dbc in dbcs {
+            if dbc.public_address() != &self.address || self.dbcs.contains_key(&dbc.id()) {
+                continue;
+            }
+            let _ = self.dbcs.insert(dbc.id(), dbc.clone());
+            deposited.push(dbc);
+        }
+        deposited
+    }
+
+    /// Loads the dbcs, or transfer files, placed in the `received_dbcs` dir of the wallet,
+    /// which it doesn't hold yet, to be verified and deposited.
+    pub async fn received_dbcs(&self) -> Result<Vec<Dbc>> {
+        let received = load_received_dbcs(&self.wallet_dir).await?;
+        Ok(received
+            .into_iter()
+            .filter(|dbc| {
+                dbc.public_address() == &self.address && !self.dbcs.contains_key(&dbc.id())
+            })
+            .collect())
+    }
+
+    /// Builds the transfer to the recipients, to be signed by the wallet holding the key.
+    /// All the dbcs of the wallet are given to select the inputs from, as their amounts are not known.
+    pub fn build_transfer(&self, to: Vec<(Token, PublicAddress)>) -> Result<UnsignedTransfer> {
+        if to.is_empty() {
+            return Err(Error::CouldNotSendTokens(
+                "No recipients were given.".into(),
+            ));
+        }
+        if self.dbcs.is_empty() {
+            return Err(Error::CouldNotSendTokens(
+                "The watch-only wallet holds no DBCs.".into(),
+            ));
+        }
+        Ok(UnsignedTransfer {
+            inputs: self.dbcs.values().cloned().collect(),
+            recipients: to,
+            change_to: self.address,
+        })
+    }
+
+    /// Registers the signed transfer in the network, then removes the dbcs it spent from the
+    /// wallet, and deposits its change. The dbcs created for each recipient are written to a
+    /// transfer file, to be handed to them, whose paths are returned.
+    pub async fn broadcast<C: SendClient>(
+        &mut self,
+        signed: &SignedTransfer,
+        client: &C,
+    ) -> Result<Vec<PathBuf>> {
+        let spent: Vec<_> = signed
+            .transfer
+            .all_spend_requests
+            .iter()
+            .map(|request| *request.signed_spend.dbc_id())
+            .collect();
+        if let Some(unknown) = spent.iter().find(|id| !self.dbcs.contains_key(*id)) {
+            return Err(Error::CouldNotSendTokens(format!(
+                "The transfer spends {unknown:?}, which the watch-only wallet doesn't hold."
+            )));
+        }
+
+        // Registering the same spends again is harmless, thus a failed broadcast can be retried.
+        client.send(signed.transfer.clone()).await?;
+
+        for id in &spent {
+            let _ = self.dbcs.remove(id);
+        }
+        let _ = self.deposit(signed.transfer.change_dbc.iter().cloned().collect());
+        self.store().await?;
+
+        let mut transfer_files = vec![];
+        for (amount, recipient) in &signed.recipients {
+            let dbcs: Vec<_> = signed
+                .transfer
+                .created_dbcs
+                .iter()
+                .filter(|created| created.dbc.public_address() == recipient)
+                .map(|created| created.dbc.clone())
+                .collect();
+            if dbcs.is_empty() {
+                continue;
+            }
+            let transfer = OfflineTransfer::new(dbcs, *recipient, *amount);
+            transfer_files.push(store_transfer_file(&transfer, &self.wallet_dir).await?);
+        }
+        Ok(transfer_files)
+    }
+}
+
+async fn read_address(wallet_dir: &Path) -> Result<Option<PublicAddress>> {
+    let path = wallet_dir.join(PUBLIC_ADDRESS_FILE_NAME);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let hex = fs::read_to_string(path).await?;
+    parse_public_address(hex.trim()).map(Some)
+}
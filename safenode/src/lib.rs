@@ -56,5 +56,10 @@ pub mod log;
 pub mod network;
 /// SAFE Node
 pub mod node;
+/// The peers to dial to join a network, as given to nodes and clients, or in network contacts.
+pub mod peers;
 /// SAFE Protocol
 pub mod protocol;
+/// Generators of realistic data and in-memory networks of nodes, for tests.
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
@@ -18,6 +18,15 @@ pub enum Error {
     #[cfg(feature = "otlp")]
     #[error("OpenTelemetry Tracing error: {0}")]
     OpenTelemetryTracing(#[from] opentelemetry::trace::TraceError),
+    #[cfg(feature = "otlp")]
+    #[error("OpenTelemetry Metrics error: {0}")]
+    OpenTelemetryMetrics(#[from] opentelemetry::metrics::MetricsError),
     #[error("Could not configure OTLP logging: {0}")]
     OtlpConfigurationError(String),
+    #[error("Invalid log output destination '{0}', expected `stdout`, `stderr` or `file:<dir>`")]
+    InvalidLogOutputDest(String),
+    #[error("Invalid log format '{0}', expected `default` or `json`")]
+    InvalidLogFormat(String),
+    #[error("Invalid log filter '{0}': {1}")]
+    InvalidLogFilter(String, String),
 }
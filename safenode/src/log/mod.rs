@@ -9,9 +9,12 @@
 mod appender;
 mod error;
 
+pub use self::error::Error;
+
 use self::error::Result;
 
-use std::path::PathBuf;
+use std::{fmt, path::PathBuf, str::FromStr};
+use tracing::Level;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_core::{Event, Subscriber};
 use tracing_subscriber::{
@@ -20,14 +23,103 @@ use tracing_subscriber::{
     fmt::{
         format::Writer,
         time::{FormatTime, SystemTime},
-        FmtContext, FormatEvent, FormatFields,
+        FmtContext, FormatEvent, FormatFields, FormattedFields, MakeWriter,
     },
     layer::Filter,
     prelude::*,
     registry::LookupSpan,
-    Layer, Registry,
+    EnvFilter, Layer, Registry,
 };
 
+/// Where the logs are written to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogOutputDest {
+    /// Standard output.
+    Stdout,
+    /// Standard error, e.g. to keep stdout for the output of a program.
+    Stderr,
+    /// Rotated log files in the given directory.
+    Path(PathBuf),
+}
+
+impl FromStr for LogOutputDest {
+    type Err = Error;
+
+    /// Parses `stdout`, `stderr`, or `file:<dir>`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "stdout" => Ok(Self::Stdout),
+            "stderr" => Ok(Self::Stderr),
+            _ => s
+                .strip_prefix("file:")
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| Self::Path(PathBuf::from(dir)))
+                .ok_or_else(|| Error::InvalidLogOutputDest(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for LogOutputDest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stdout => write!(f, "stdout"),
+            Self::Stderr => write!(f, "stderr"),
+            Self::Path(dir) => write!(f, "file:{}", dir.display()),
+        }
+    }
+}
+
+/// The format the log lines are written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable lines, see [`LogFormatter`].
+    #[default]
+    Default,
+    /// One JSON object per line, with the timestamp, level, target and fields of the event,
+    /// for log aggregation systems to ingest.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+
+    /// Parses `default` or `json`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "default" => Ok(Self::Default),
+            "json" => Ok(Self::Json),
+            _ => Err(Error::InvalidLogFormat(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// How the log files are rotated when logging to a directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogRotation {
+    /// The size in bytes a log file can reach before it's rotated.
+    pub max_file_bytes: usize,
+    /// The number of rotated log files kept, the oldest being deleted first.
+    pub max_files: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: 100,
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 /// Tracing log formatter setup for easier span viewing
 pub struct LogFormatter;
@@ -51,7 +143,14 @@ where
         write!(writer, "[")?;
         time.format_time(&mut writer)?;
         write!(writer, " {level} {module}")?;
-        ctx.visit_spans(|span| write!(writer, "/{}", span.name()))?;
+        ctx.visit_spans(|span| {
+            write!(writer, "/{}", span.name())?;
+            // e.g. the correlation id of the client operation a request is handled for
+            match span.extensions().get::<FormattedFields<N>>() {
+                Some(fields) if !fields.is_empty() => write!(writer, "{{{fields}}}"),
+                _ => Ok(()),
+            }
+        })?;
         write!(writer, "] ")?;
 
         // Add the log message and any fields associated with the event
@@ -69,46 +168,40 @@ pub struct TracingLayers {
 }
 
 impl TracingLayers {
-    fn fmt_layer(&mut self, optional_log_dir: &Option<PathBuf>) {
-        // Filter by log level of this crate only
-        let target_filters: Box<dyn Filter<Registry> + Send + Sync> =
-            Box::new(Targets::new().with_target(current_crate_str(), tracing::Level::TRACE));
+    fn fmt_layer(
+        &mut self,
+        dest: &LogOutputDest,
+        format: LogFormat,
+        filter: Option<&str>,
+        level: Level,
+        rotation: LogRotation,
+    ) -> Result<()> {
+        let target_filters = log_filter(filter, level)?;
         let fmt_layer = tracing_fmt::layer().with_ansi(false);
 
-        if let Some(log_dir) = optional_log_dir {
-            println!("Starting logging to directory: {log_dir:?}");
-
-            let logs_retained = 0;
-            let logs_max_bytes = 10485760;
-            let logs_max_lines = 5000;
-            let logs_uncompressed = 100;
-
-            let (non_blocking, worker_guard) = appender::file_rotater(
-                log_dir,
-                logs_max_bytes,
-                logs_max_lines,
-                logs_retained,
-                logs_uncompressed,
-            );
-            self.guard = Some(worker_guard);
-
-            let fmt_layer = fmt_layer.with_writer(non_blocking);
-
-            let layer = fmt_layer
-                .event_format(LogFormatter::default())
-                .with_filter(target_filters)
-                .boxed();
-            self.layers.push(layer);
-        } else {
-            println!("Starting logging to stdout");
-
-            let layer = fmt_layer
-                .with_target(false)
-                .event_format(LogFormatter::default())
-                .with_filter(target_filters)
-                .boxed();
-            self.layers.push(layer);
+        let layer = match dest {
+            LogOutputDest::Path(log_dir) => {
+                // Rotate by size only, and keep all the retained files uncompressed.
+                let logs_max_lines = 0;
+                let (non_blocking, worker_guard) = appender::file_rotater(
+                    log_dir,
+                    rotation.max_file_bytes,
+                    logs_max_lines,
+                    rotation.max_files,
+                    rotation.max_files,
+                );
+                self.guard = Some(worker_guard);
+
+                formatted(fmt_layer.with_writer(non_blocking), format)
+            }
+            LogOutputDest::Stdout => formatted(fmt_layer.with_target(false), format),
+            LogOutputDest::Stderr => formatted(
+                fmt_layer.with_target(false).with_writer(std::io::stderr),
+                format,
+            ),
         };
+        self.layers.push(layer.with_filter(target_filters).boxed());
+        Ok(())
     }
 
     #[cfg(feature = "otlp")]
@@ -133,14 +226,16 @@ impl TracingLayers {
         });
         println!("The opentelemetry traces are logged under the name: {service_name}");
 
+        let resource = vec![
+            KeyValue::new(SERVICE_NAME, service_name),
+            KeyValue::new(SERVICE_INSTANCE_ID, std::process::id().to_string()),
+        ];
         let tracer = opentelemetry_otlp::new_pipeline()
             .tracing()
             .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_env())
-            .with_trace_config(trace::config().with_resource(Resource::new(vec![
-                KeyValue::new(SERVICE_NAME, service_name),
-                KeyValue::new(SERVICE_INSTANCE_ID, std::process::id().to_string()),
-            ])))
+            .with_trace_config(trace::config().with_resource(Resource::new(resource.clone())))
             .install_batch(opentelemetry::runtime::Tokio)?;
+        install_otlp_metrics(resource)?;
 
         let env_filter = EnvFilter::builder()
             .with_default_directive(LevelFilter::INFO.into())
@@ -156,13 +251,140 @@ impl TracingLayers {
     }
 }
 
+#[cfg(feature = "otlp")]
+lazy_static::lazy_static! {
+    /// The controller pushing the metrics over OTLP. It's held for the life of the program,
+    /// as the metrics stop being pushed once it's dropped.
+    static ref OTLP_METRICS_CONTROLLER: std::sync::Mutex<
+        Option<opentelemetry::sdk::metrics::controllers::PushController>,
+    > = std::sync::Mutex::new(None);
+}
+
+/// Exports the metrics recorded with the global meter over the same OTLP pipeline as the traces,
+/// under the same service name.
+#[cfg(feature = "otlp")]
+fn install_otlp_metrics(resource: Vec<opentelemetry::KeyValue>) -> Result<()> {
+    use futures::StreamExt;
+    use opentelemetry_otlp::WithExportConfig;
+
+    let controller = opentelemetry_otlp::new_pipeline()
+        .metrics(tokio::spawn, |interval| {
+            // skip the first tick, which is immediate
+            opentelemetry::sdk::util::tokio_interval_stream(interval).skip(1)
+        })
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_env())
+        .with_resource(resource)
+        .build()?;
+    opentelemetry::global::set_meter_provider(controller.provider());
+
+    *OTLP_METRICS_CONTROLLER
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(controller);
+    Ok(())
+}
+
+/// The filter of the events logged: the given directives, e.g. `safenode=debug,libp2p=warn`,
+/// or else those of the `RUST_LOG` env var, or else the events of this crate at the given level
+/// or above.
+fn log_filter(
+    filter: Option<&str>,
+    level: Level,
+) -> Result<Box<dyn Filter<Registry> + Send + Sync>> {
+    let directives = match filter {
+        Some(filter) => Some(filter.to_string()),
+        None => std::env::var("RUST_LOG")
+            .ok()
+            .filter(|directives| !directives.is_empty()),
+    };
+    match directives {
+        Some(directives) => match EnvFilter::try_new(&directives) {
+            Ok(filter) => Ok(Box::new(filter)),
+            Err(err) => Err(Error::InvalidLogFilter(directives, err.to_string())),
+        },
+        // Filter by log level of this crate only
+        None => Ok(Box::new(
+            Targets::new().with_target(current_crate_str(), level),
+        )),
+    }
+}
+
+/// Formats the events logged by the layer in the given format.
+fn formatted<W>(
+    layer: tracing_fmt::Layer<
+        Registry,
+        tracing_fmt::format::DefaultFields,
+        tracing_fmt::format::Format,
+        W,
+    >,
+    format: LogFormat,
+) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Default => layer.event_format(LogFormatter::default()).boxed(),
+        LogFormat::Json => layer.json().with_target(true).boxed(),
+    }
+}
+
 /// Inits node logging, returning the global node guard if required.
 /// This guard should be held for the life of the program.
 ///
+/// The logs are written in the given `format`, and when logging to a directory, the log files
+/// are rotated as per the given `rotation`. The events logged are selected with the `filter`
+/// directives, e.g. `safenode=debug,libp2p=warn`, or else those of the `RUST_LOG` env var, or
+/// else all the events of this crate are logged.
+///
 /// Logging should be instantiated only once.
-pub fn init_node_logging(log_dir: &Option<PathBuf>) -> Result<Option<WorkerGuard>> {
+pub fn init_node_logging(
+    log_dir: &Option<PathBuf>,
+    format: LogFormat,
+    filter: Option<&str>,
+    rotation: LogRotation,
+) -> Result<Option<WorkerGuard>> {
+    let dest = if let Some(log_dir) = log_dir {
+        println!("Starting logging to directory: {log_dir:?}");
+        LogOutputDest::Path(log_dir.clone())
+    } else {
+        println!("Starting logging to stdout");
+        LogOutputDest::Stdout
+    };
+    init_logging_with_rotation(&dest, format, filter, Level::TRACE, rotation)
+}
+
+/// Inits logging to the given destination in the given format, returning the global guard if
+/// required. This guard should be held for the life of the program.
+///
+/// The events logged are selected with the `filter` directives, e.g. `safenode=debug,libp2p=warn`,
+/// or else those of the `RUST_LOG` env var, or else those of this crate at the given level
+/// or above are logged.
+///
+/// Logging should be instantiated only once.
+pub fn init_logging(
+    dest: &LogOutputDest,
+    format: LogFormat,
+    filter: Option<&str>,
+    level: Level,
+) -> Result<Option<WorkerGuard>> {
+    init_logging_with_rotation(dest, format, filter, level, LogRotation::default())
+}
+
+fn init_logging_with_rotation(
+    dest: &LogOutputDest,
+    format: LogFormat,
+    filter: Option<&str>,
+    level: Level,
+    rotation: LogRotation,
+) -> Result<Option<WorkerGuard>> {
     let mut layers = TracingLayers::default();
-    layers.fmt_layer(log_dir);
+    layers.fmt_layer(dest, format, filter, level, rotation)?;
+
+    #[cfg(feature = "console")]
+    {
+        // Serves the tasks of the runtimes to tokio-console, on 127.0.0.1:6669 by default,
+        // which can be changed with the TOKIO_CONSOLE_BIND env var.
+        layers.layers.push(console_subscriber::spawn().boxed());
+    }
 
     #[cfg(feature = "otlp")]
     {
@@ -170,7 +392,7 @@ pub fn init_node_logging(log_dir: &Option<PathBuf>) -> Result<Option<WorkerGuard
             Ok(_) => layers.otlp_layer()?,
             Err(_) => info!(
                 "The OTLP feature is enabled but the OTEL_EXPORTER_OTLP_ENDPOINT variable is not \
-                set, so traces will not be submitted."
+                set, so traces and metrics will not be submitted."
             ),
         }
     }
@@ -203,3 +425,42 @@ fn current_crate_str() -> &'static str {
     let m = module_path!();
     &m[..m.find(':').unwrap_or(m.len())]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_output_dest_parses_and_displays() -> Result<()> {
+        assert_eq!("stdout".parse::<LogOutputDest>()?, LogOutputDest::Stdout);
+        assert_eq!("stderr".parse::<LogOutputDest>()?, LogOutputDest::Stderr);
+
+        let dest = "file:/tmp/safe logs".parse::<LogOutputDest>()?;
+        assert_eq!(dest, LogOutputDest::Path(PathBuf::from("/tmp/safe logs")));
+        assert_eq!(dest.to_string(), "file:/tmp/safe logs");
+
+        assert!("file:".parse::<LogOutputDest>().is_err());
+        assert!("syslog".parse::<LogOutputDest>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn log_format_parses_and_displays() -> Result<()> {
+        for format in [LogFormat::Default, LogFormat::Json] {
+            assert_eq!(format.to_string().parse::<LogFormat>()?, format);
+        }
+        assert_eq!("json".parse::<LogFormat>()?, LogFormat::Json);
+        assert!("yaml".parse::<LogFormat>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn log_filter_directives_are_parsed() {
+        assert!(log_filter(Some("safenode=debug,libp2p=warn"), Level::INFO).is_ok());
+        assert!(log_filter(Some("safenode::node=trace"), Level::INFO).is_ok());
+        assert!(matches!(
+            log_filter(Some("safenode=loud"), Level::INFO),
+            Err(Error::InvalidLogFilter(..))
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::{
+    messages::{QueryResponse, Request, Response},
+    storage::Chunk,
+};
+
+use bytes::Bytes;
+use sn_dbc::SignedSpend;
+
+/// Env var for the comma-separated misbehaviours of a node, e.g. `wrong-chunks,ignore-replication`.
+const ADVERSARY_ENV_VAR: &str = "SAFE_ADVERSARY";
+
+/// The ways a node misbehaves, to test the quorum and double spend detection logic of clients
+/// and nodes against actual malicious peers, e.g. a few of those of a testnet.
+///
+/// Unlike the faults injected by the `chaos` feature, at random, the misbehaviours are
+/// deliberate: every chunk and spend the node serves is tampered with, and every replication
+/// from its peers ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AdversaryConfig {
+    /// Serve chunks whose content doesn't match their address.
+    pub serve_wrong_chunks: bool,
+    /// Serve spends conflicting with the ones stored, i.e. of the same DBCs, to other txs.
+    /// As the node doesn't hold the keys of the DBCs, their signatures don't verify.
+    pub conflicting_spends: bool,
+    /// Ignore the records and spends replicated to the node by its peers, and don't replicate
+    /// its own records to them.
+    pub ignore_replication: bool,
+}
+
+impl AdversaryConfig {
+    /// Reads the misbehaviours from the `SAFE_ADVERSARY` env var, as comma-separated
+    /// `wrong-chunks`, `conflicting-spends` and `ignore-replication`, so they can be set for
+    /// some of the nodes of a testnet. The node behaves if it's not set.
+    pub fn from_env() -> Self {
+        let config = std::env::var(ADVERSARY_ENV_VAR)
+            .map(|value| Self::parse(&value))
+            .unwrap_or_default();
+        if config != Self::default() {
+            warn!("This node misbehaves: {config:?}");
+        }
+        config
+    }
+
+    /// Parses comma-separated misbehaviours, ignoring the unknown ones.
+    pub fn parse(misbehaviours: &str) -> Self {
+        let mut config = Self::default();
+        for misbehaviour in misbehaviours.split(',').map(str::trim) {
+            match misbehaviour {
+                "wrong-chunks" => config.serve_wrong_chunks = true,
+                "conflicting-spends" => config.conflicting_spends = true,
+                "ignore-replication" => config.ignore_replication = true,
+                "" => {}
+                unknown => {
+                    warn!("Ignoring the unknown misbehaviour {unknown:?} of {ADVERSARY_ENV_VAR}")
+                }
+            }
+        }
+        config
+    }
+
+    /// Whether the request received from a peer is to be ignored, as a replication.
+    pub(super) fn ignores(&self, request: &Request) -> bool {
+        self.ignore_replication && matches!(request, Request::Event(_))
+    }
+
+    /// Tampers with the chunk or spend of the response, as configured.
+    pub(super) fn tamper(&self, response: Response) -> Response {
+        match response {
+            Response::Query(QueryResponse::GetChunk(Ok(chunk))) if self.serve_wrong_chunks => {
+                warn!("Serving a wrong chunk for {:?}", chunk.address());
+                Response::Query(QueryResponse::GetChunk(Ok(wrong_chunk(&chunk))))
+            }
+            Response::Query(QueryResponse::GetDbcSpend(Ok(spend))) if self.conflicting_spends => {
+                warn!("Serving a conflicting spend of {:?}", spend.dbc_id());
+                Response::Query(QueryResponse::GetDbcSpend(Ok(conflicting_spend(spend))))
+            }
+            response => response,
+        }
+    }
+}
+
+/// Returns a chunk with the content of the given one, plus a byte.
+fn wrong_chunk(chunk: &Chunk) -> Chunk {
+    let mut value = chunk.value().to_vec();
+    value.push(0);
+    Chunk::new(Bytes::from(value))
+}
+
+/// Returns the spend of the same DBC, to its tx stripped of its last output.
+fn conflicting_spend(mut spend: SignedSpend) -> SignedSpend {
+    let _ = spend.spend.dst_tx.outputs.pop();
+    spend
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{protocol::messages::Event, test_utils::random_signed_spends};
+
+    #[test]
+    fn adversary_config_parses_the_misbehaviours() {
+        assert_eq!(AdversaryConfig::parse(""), AdversaryConfig::default());
+        assert_eq!(
+            AdversaryConfig::parse("wrong-chunks, ignore-replication,unknown"),
+            AdversaryConfig {
+                serve_wrong_chunks: true,
+                conflicting_spends: false,
+                ignore_replication: true,
+            }
+        );
+    }
+
+    #[test]
+    fn adversary_tampers_with_chunks_and_spends() {
+        let adversary = AdversaryConfig::parse("wrong-chunks,conflicting-spends");
+
+        let chunk = Chunk::new(Bytes::from_static(b"chunk"));
+        let response = Response::Query(QueryResponse::GetChunk(Ok(chunk.clone())));
+        match adversary.tamper(response) {
+            Response::Query(QueryResponse::GetChunk(Ok(wrong))) => {
+                assert_ne!(wrong.address(), chunk.address());
+            }
+            response => panic!("Unexpected response: {response:?}"),
+        }
+
+        let spend = random_signed_spends(1).remove(0);
+        let response = Response::Query(QueryResponse::GetDbcSpend(Ok(spend.clone())));
+        match adversary.tamper(response) {
+            Response::Query(QueryResponse::GetDbcSpend(Ok(conflicting))) => {
+                assert_eq!(conflicting.dbc_id(), spend.dbc_id());
+                assert_ne!(conflicting.spend.hash(), spend.spend.hash());
+                assert!(conflicting.verify(conflicting.dst_tx_hash()).is_err());
+            }
+            response => panic!("Unexpected response: {response:?}"),
+        }
+    }
+
+    #[test]
+    fn behaving_node_neither_tampers_nor_ignores() {
+        let adversary = AdversaryConfig::default();
+        let chunk = Chunk::new(Bytes::from_static(b"chunk"));
+        let response = Response::Query(QueryResponse::GetChunk(Ok(chunk)));
+        assert_eq!(adversary.tamper(response.clone()), response);
+
+        let spend = random_signed_spends(1).remove(0);
+        let event = Request::Event(Event::DoubleSpendAttempted {
+            new: Box::new(spend.clone()),
+            existing: Box::new(spend),
+        });
+        assert!(!adversary.ignores(&event));
+        assert!(AdversaryConfig::parse("ignore-replication").ignores(&event));
+    }
+}
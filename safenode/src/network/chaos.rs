@@ -0,0 +1,159 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::protocol::{
+    messages::{QueryResponse, Response},
+    storage::Chunk,
+};
+
+use bytes::Bytes;
+use rand::Rng;
+use std::{str::FromStr, time::Duration};
+
+/// Env var for the probability, between 0 and 1, of dropping a message.
+const DROP_RATE_ENV_VAR: &str = "SAFE_CHAOS_DROP_RATE";
+/// Env var for the milliseconds of latency added to the messages received.
+const LATENCY_ENV_VAR: &str = "SAFE_CHAOS_LATENCY_MS";
+/// Env var for the maximum milliseconds of random latency added on top of the fixed one.
+const JITTER_ENV_VAR: &str = "SAFE_CHAOS_JITTER_MS";
+/// Env var for the probability, between 0 and 1, of corrupting a response sent.
+const CORRUPT_RATE_ENV_VAR: &str = "SAFE_CHAOS_CORRUPT_RATE";
+
+/// The faults injected into the messages handled by the `SwarmDriver`, to exercise the
+/// retries and quorum logic of clients under adverse conditions.
+///
+/// Requests received are dropped at the `drop_rate`, as are the responses sent, the peer
+/// having sent the request then seeing it fail. All the requests and responses received are
+/// delayed by the `latency` plus a random `jitter`. Chunks sent in responses are corrupted at
+/// the `corrupt_rate`, i.e. their content no longer matches their address.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChaosConfig {
+    /// Probability, between 0 and 1, of dropping a request received or a response sent.
+    pub drop_rate: f64,
+    /// Latency added to every request and response received.
+    pub latency: Duration,
+    /// Maximum random latency added on top of the fixed `latency`.
+    pub jitter: Duration,
+    /// Probability, between 0 and 1, of corrupting a chunk sent in a response.
+    pub corrupt_rate: f64,
+}
+
+impl ChaosConfig {
+    /// Reads the faults to inject from the `SAFE_CHAOS_DROP_RATE`, `SAFE_CHAOS_LATENCY_MS`,
+    /// `SAFE_CHAOS_JITTER_MS` and `SAFE_CHAOS_CORRUPT_RATE` env vars, so they can be set for
+    /// the nodes of a testnet. The env vars not set, or not valid, inject no faults.
+    pub fn from_env() -> Self {
+        let config = Self {
+            drop_rate: env_var(DROP_RATE_ENV_VAR).unwrap_or_default(),
+            latency: env_var(LATENCY_ENV_VAR)
+                .map(Duration::from_millis)
+                .unwrap_or_default(),
+            jitter: env_var(JITTER_ENV_VAR)
+                .map(Duration::from_millis)
+                .unwrap_or_default(),
+            corrupt_rate: env_var(CORRUPT_RATE_ENV_VAR).unwrap_or_default(),
+        };
+        if config != Self::default() {
+            warn!("Injecting faults into the network: {config:?}");
+        }
+        config
+    }
+
+    /// Whether the next message is to be dropped.
+    pub(super) fn should_drop(&self) -> bool {
+        happens(self.drop_rate)
+    }
+
+    /// The latency to add to the next message received, if any.
+    pub(super) fn delay(&self) -> Option<Duration> {
+        let jitter = if self.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            rand::thread_rng().gen_range(Duration::ZERO..=self.jitter)
+        };
+        Some(self.latency + jitter).filter(|delay| !delay.is_zero())
+    }
+
+    /// Corrupts the response at the `corrupt_rate`, if it contains a chunk.
+    pub(super) fn maybe_corrupt(&self, response: Response) -> Response {
+        match response {
+            Response::Query(QueryResponse::GetChunk(Ok(chunk))) if happens(self.corrupt_rate) => {
+                warn!("Corrupting chunk {:?} in response", chunk.address());
+                Response::Query(QueryResponse::GetChunk(Ok(corrupt(&chunk))))
+            }
+            response => response,
+        }
+    }
+}
+
+/// Returns a chunk with the content of the given one, with one of its bytes flipped.
+fn corrupt(chunk: &Chunk) -> Chunk {
+    let mut value = chunk.value().to_vec();
+    if value.is_empty() {
+        value.push(0);
+    } else {
+        let index = rand::thread_rng().gen_range(0..value.len());
+        value[index] = !value[index];
+    }
+    Chunk::new(Bytes::from(value))
+}
+
+fn happens(rate: f64) -> bool {
+    rate > 0.0 && rand::thread_rng().gen_bool(rate.min(1.0))
+}
+
+fn env_var<T: FromStr>(name: &str) -> Option<T> {
+    let value = std::env::var(name).ok()?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            warn!("Ignoring the invalid value {value:?} of {name}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chaos_config_injects_no_faults_by_default() {
+        let config = ChaosConfig::default();
+        assert!(!config.should_drop());
+        assert_eq!(config.delay(), None);
+
+        let chunk = Chunk::new(Bytes::from_static(b"chunk"));
+        let response = Response::Query(QueryResponse::GetChunk(Ok(chunk)));
+        assert_eq!(config.maybe_corrupt(response.clone()), response);
+    }
+
+    #[test]
+    fn chaos_config_injects_faults_at_their_rates() {
+        let config = ChaosConfig {
+            drop_rate: 1.0,
+            latency: Duration::from_millis(100),
+            jitter: Duration::from_millis(50),
+            corrupt_rate: 1.0,
+        };
+        assert!(config.should_drop());
+        let delay = config.delay().expect("Latency should be added.");
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(150));
+
+        let chunk = Chunk::new(Bytes::from_static(b"chunk"));
+        let response =
+            config.maybe_corrupt(Response::Query(QueryResponse::GetChunk(Ok(chunk.clone()))));
+        match response {
+            Response::Query(QueryResponse::GetChunk(Ok(corrupted))) => {
+                assert_eq!(corrupted.payload_size(), chunk.payload_size());
+                assert_ne!(corrupted.address(), chunk.address());
+            }
+            response => panic!("Unexpected response: {response:?}"),
+        }
+    }
+}
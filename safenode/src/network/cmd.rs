@@ -11,17 +11,23 @@ use super::{error::Error, MsgResponder, NetworkEvent, SwarmDriver};
 use crate::{
     network::error::Result,
     protocol::{
-        messages::{QueryResponse, Request, Response},
+        messages::{QueryResponse, RequestEnvelope, Response},
         NetworkAddress,
     },
 };
 
+use sn_dbc::Token;
+
 use libp2p::{
     kad::{Record, RecordKey},
     multiaddr::Protocol,
+    swarm::AddressScore,
     Multiaddr, PeerId,
 };
-use std::collections::{hash_map, HashSet};
+use std::{
+    collections::{hash_map, BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
 use tokio::sync::oneshot;
 
 /// Commands to send to the Swarm
@@ -45,8 +51,13 @@ pub enum SwarmCmd {
         key: NetworkAddress,
         sender: oneshot::Sender<HashSet<PeerId>>,
     },
+    /// Get the addresses we know of for each of the given peers, from our routing table
+    GetPeersAddresses {
+        peers: Vec<PeerId>,
+        sender: oneshot::Sender<Vec<(PeerId, Vec<Multiaddr>)>>,
+    },
     SendRequest {
-        req: Request,
+        req: RequestEnvelope,
         peer: PeerId,
         sender: oneshot::Sender<Result<Response>>,
     },
@@ -59,11 +70,24 @@ pub enum SwarmCmd {
     PutProvidedDataAsRecord {
         record: Record,
     },
-    /// Get data from the kademlia store
+    /// Get data from the kademlia store, along with the peer which held it, if not this one
     GetData {
         key: RecordKey,
-        sender: oneshot::Sender<Result<QueryResponse>>,
+        sender: oneshot::Sender<Result<(QueryResponse, Option<PeerId>)>>,
+    },
+    /// Get the cost of storing a new record in our local store
+    GetLocalStoreCost {
+        sender: oneshot::Sender<Token>,
     },
+    /// The port of the node was mapped in the router of its network to the given address, to be
+    /// advertised to peers as one the node is reachable at
+    PortMapped(Multiaddr),
+    /// The port of the node is no longer mapped to the given address, which is not to be
+    /// advertised to peers anymore
+    PortUnmapped(Multiaddr),
+    /// Replace the faults injected into the messages
+    #[cfg(feature = "chaos")]
+    SetChaos(super::ChaosConfig),
 }
 
 /// Snapshot of information kept in the Swarm's local state
@@ -73,6 +97,19 @@ pub struct SwarmLocalState {
     pub connected_peers: Vec<PeerId>,
     /// List of aaddresses the node is currently listening on
     pub listeners: Vec<Multiaddr>,
+    /// Number of peers in the routing table
+    pub routing_table_size: usize,
+    /// Stats of the Kademlia queries completed so far, per kind of query
+    pub kad_queries: BTreeMap<&'static str, KadQueryStats>,
+}
+
+/// Stats of the Kademlia queries of a kind completed by the Swarm
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KadQueryStats {
+    /// Number of queries completed
+    pub completed: u64,
+    /// Total time the queries took to complete
+    pub duration: Duration,
 }
 
 impl SwarmDriver {
@@ -82,6 +119,12 @@ impl SwarmDriver {
                 let query_id = self.swarm.behaviour_mut().kademlia.get_record(key);
                 let _ = self.pending_query.insert(query_id, sender);
             }
+            SwarmCmd::GetLocalStoreCost { sender } => {
+                let cost = self.swarm.behaviour_mut().kademlia.store_mut().store_cost();
+                sender
+                    .send(cost)
+                    .map_err(|_| Error::InternalMsgChannelDropped)?;
+            }
             SwarmCmd::PutProvidedDataAsRecord { record } => {
                 // TODO: when do we remove records. Do we need to?
                 let _ = self
@@ -149,6 +192,25 @@ impl SwarmDriver {
                     .pending_get_closest_peers
                     .insert(query_id, (sender, Default::default()));
             }
+            SwarmCmd::GetPeersAddresses { peers, sender } => {
+                let mut addresses: HashMap<PeerId, Vec<Multiaddr>> =
+                    peers.iter().map(|peer| (*peer, vec![])).collect();
+                for kbucket in self.swarm.behaviour_mut().kademlia.kbuckets() {
+                    for entry in kbucket.iter() {
+                        if let Some(addrs) = addresses.get_mut(entry.node.key.preimage()) {
+                            addrs.extend(entry.node.value.iter().cloned());
+                        }
+                    }
+                }
+
+                let peers_addresses = peers
+                    .into_iter()
+                    .map(|peer| (peer, addresses.remove(&peer).unwrap_or_default()))
+                    .collect();
+                sender
+                    .send(peers_addresses)
+                    .map_err(|_| Error::InternalMsgChannelDropped)?;
+            }
             SwarmCmd::SendRequest { req, peer, sender } => {
                 // If `self` is the recipient, forward the request directly to our upper layer to
                 // be handled.
@@ -157,7 +219,8 @@ impl SwarmDriver {
                     trace!("Sending request to self");
                     self.event_sender
                         .send(NetworkEvent::RequestReceived {
-                            req,
+                            req: req.request,
+                            correlation_id: req.correlation_id,
                             channel: MsgResponder::FromSelf(sender),
                         })
                         .await?;
@@ -179,7 +242,16 @@ impl SwarmDriver {
                         .send(Ok(resp))
                         .map_err(|_| Error::InternalMsgChannelDropped)?;
                 }
+                #[cfg(feature = "chaos")]
+                MsgResponder::FromPeer(channel) if self.chaos.should_drop() => {
+                    warn!("Dropping response {resp}, the peer won't receive it");
+                    drop(channel);
+                }
                 MsgResponder::FromPeer(channel) => {
+                    #[cfg(feature = "chaos")]
+                    let resp = self.chaos.maybe_corrupt(resp);
+                    #[cfg(feature = "adversary")]
+                    let resp = self.adversary.tamper(resp);
                     self.swarm
                         .behaviour_mut()
                         .request_response
@@ -188,15 +260,47 @@ impl SwarmDriver {
                 }
             },
             SwarmCmd::GetSwarmLocalState(sender) => {
+                let routing_table_size = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .kbuckets()
+                    .map(|kbucket| kbucket.num_entries())
+                    .sum();
                 let current_state = SwarmLocalState {
                     connected_peers: self.swarm.connected_peers().cloned().collect(),
                     listeners: self.swarm.listeners().cloned().collect(),
+                    routing_table_size,
+                    kad_queries: self.kad_query_stats.clone(),
                 };
 
                 sender
                     .send(current_state)
                     .map_err(|_| Error::InternalMsgChannelDropped)?;
             }
+            SwarmCmd::PortMapped(addr) => {
+                let local_peer_id = *self.swarm.local_peer_id();
+                let addr = addr.with(Protocol::P2p(local_peer_id.into()));
+                info!("Advertising the external address {addr}");
+                let _ = self
+                    .swarm
+                    .add_external_address(addr.clone(), AddressScore::Infinite);
+                self.event_sender
+                    .send(NetworkEvent::PortMapped(addr))
+                    .await?;
+            }
+            SwarmCmd::PortUnmapped(addr) => {
+                let local_peer_id = *self.swarm.local_peer_id();
+                let addr = addr.with(Protocol::P2p(local_peer_id.into()));
+                if self.swarm.remove_external_address(&addr) {
+                    info!("No longer advertising the external address {addr}");
+                }
+            }
+            #[cfg(feature = "chaos")]
+            SwarmCmd::SetChaos(config) => {
+                warn!("Injecting faults into the network: {config:?}");
+                self.chaos = config;
+            }
         }
         Ok(())
     }
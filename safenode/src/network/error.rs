@@ -47,6 +47,9 @@ pub enum Error {
     #[error("Dial Error")]
     DialError(#[from] DialError),
 
+    #[error("Noise handshake config error: {0}")]
+    Noise(#[from] libp2p::noise::NoiseError),
+
     #[error("This peer is already being dialed: {0}")]
     AlreadyDialingPeer(libp2p::PeerId),
 
@@ -73,4 +76,7 @@ pub enum Error {
 
     #[error("Record was not found locally")]
     RecordNotFound,
+
+    #[error("Failed to map the port in the router: {0}")]
+    PortMapping(String),
 }
@@ -9,47 +9,67 @@
 use super::{
     error::{Error, Result},
     msg::MsgCodec,
+    nat::{self, NatStatus},
     SwarmDriver,
 };
 use crate::{
     domain::storage::DiskBackedRecordStore,
     network::IDENTIFY_AGENT_STR,
     protocol::{
-        messages::{QueryResponse, Request, Response},
+        messages::{CorrelationId, QueryResponse, Request, RequestEnvelope, Response},
         storage::Chunk,
     },
 };
 use libp2p::{
-    kad::{GetRecordOk, Kademlia, KademliaEvent, QueryResult, K_VALUE},
+    autonat, dcutr,
+    kad::{GetRecordOk, Kademlia, KademliaEvent, QueryResult, QueryStats, K_VALUE},
     mdns,
     multiaddr::Protocol,
+    relay,
     request_response::{self, ResponseChannel as PeerResponseChannel},
-    swarm::{NetworkBehaviour, SwarmEvent},
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour, SwarmEvent},
     Multiaddr, PeerId,
 };
 use std::collections::{hash_map, HashSet};
 use tokio::sync::oneshot;
 use tracing::{info, warn};
 
+/// The number of peers a node not publicly reachable listens through, for it to stay
+/// reachable when one of them leaves.
+const RELAYS_LISTENED_THROUGH: usize = 2;
+
 #[derive(NetworkBehaviour)]
 #[behaviour(out_event = "NodeEvent")]
 pub(super) struct NodeBehaviour {
     pub(super) request_response: request_response::Behaviour<MsgCodec>,
     pub(super) kademlia: Kademlia<DiskBackedRecordStore>,
-    pub(super) mdns: mdns::tokio::Behaviour,
+    /// Only enabled on local networks, to discover their peers.
+    pub(super) mdns: Toggle<mdns::tokio::Behaviour>,
     pub(super) identify: libp2p::identify::Behaviour,
+    /// Only enabled in client mode, to reach peers through circuit relays.
+    pub(super) relay_client: Toggle<relay::client::Behaviour>,
+    /// Only enabled for nodes which relay the connections of peers behind NATs.
+    pub(super) relay_server: Toggle<relay::Behaviour>,
+    /// Only enabled for nodes which probe whether they are behind a NAT.
+    pub(super) autonat: Toggle<autonat::Behaviour>,
+    /// Enabled for clients, and for nodes which upgrade relayed connections by hole punching.
+    pub(super) dcutr: Toggle<dcutr::Behaviour>,
 }
 
 #[derive(Debug)]
 pub(super) enum NodeEvent {
-    MsgReceived(request_response::Event<Request, Response>),
+    MsgReceived(request_response::Event<RequestEnvelope, Response>),
     Kademlia(KademliaEvent),
     Mdns(Box<mdns::Event>),
     Identify(Box<libp2p::identify::Event>),
+    RelayClient(Box<relay::client::Event>),
+    RelayServer(Box<relay::Event>),
+    Autonat(Box<autonat::Event>),
+    Dcutr(Box<dcutr::Event>),
 }
 
-impl From<request_response::Event<Request, Response>> for NodeEvent {
-    fn from(event: request_response::Event<Request, Response>) -> Self {
+impl From<request_response::Event<RequestEnvelope, Response>> for NodeEvent {
+    fn from(event: request_response::Event<RequestEnvelope, Response>) -> Self {
         NodeEvent::MsgReceived(event)
     }
 }
@@ -72,6 +92,30 @@ impl From<libp2p::identify::Event> for NodeEvent {
     }
 }
 
+impl From<relay::client::Event> for NodeEvent {
+    fn from(event: relay::client::Event) -> Self {
+        NodeEvent::RelayClient(Box::new(event))
+    }
+}
+
+impl From<relay::Event> for NodeEvent {
+    fn from(event: relay::Event) -> Self {
+        NodeEvent::RelayServer(Box::new(event))
+    }
+}
+
+impl From<autonat::Event> for NodeEvent {
+    fn from(event: autonat::Event) -> Self {
+        NodeEvent::Autonat(Box::new(event))
+    }
+}
+
+impl From<dcutr::Event> for NodeEvent {
+    fn from(event: dcutr::Event) -> Self {
+        NodeEvent::Dcutr(Box::new(event))
+    }
+}
+
 #[derive(Debug)]
 /// Channel to send the `Response` through.
 pub enum MsgResponder {
@@ -88,6 +132,8 @@ pub enum NetworkEvent {
     RequestReceived {
         /// Request
         req: Request,
+        /// The id of the client operation the request is sent for, if any
+        correlation_id: Option<CorrelationId>,
         /// The channel to send the `Response` through
         channel: MsgResponder,
     },
@@ -95,6 +141,14 @@ pub enum NetworkEvent {
     PeerAdded(PeerId),
     /// Started listening on a new address
     NewListenAddr(Multiaddr),
+    /// The port listened on was mapped in the router of the local network, the node being
+    /// reachable from outside of it at the given address
+    PortMapped(Multiaddr),
+    /// Whether the node is publicly reachable changed, as probed with AutoNAT
+    NatStatusChanged(NatStatus),
+    /// The connection to the peer, relayed until then, was upgraded to a direct one by hole
+    /// punching
+    DirectConnectionUpgraded(PeerId),
 }
 
 impl SwarmDriver {
@@ -134,6 +188,7 @@ impl SwarmDriver {
                         closest_peers.peers.clone().into_iter().collect();
                     current_closest.extend(new_peers);
                     if current_closest.len() >= usize::from(K_VALUE) || step.last {
+                        self.record_kad_query("get_closest_peers", stats);
                         sender
                             .send(current_closest)
                             .map_err(|_| Error::InternalMsgChannelDropped)?;
@@ -157,10 +212,14 @@ impl SwarmDriver {
                             peer_record.peer
                         );
                         if let Some(sender) = self.pending_query.remove(id) {
+                            self.record_kad_query("get_record", stats);
                             sender
-                                .send(Ok(QueryResponse::GetChunk(Ok(Chunk::new(
-                                    peer_record.record.value.clone().into(),
-                                )))))
+                                .send(Ok((
+                                    QueryResponse::GetChunk(Ok(Chunk::new(
+                                        peer_record.record.value.clone().into(),
+                                    ))),
+                                    peer_record.peer,
+                                )))
                                 .map_err(|_| Error::InternalMsgChannelDropped)?;
                         }
                     } else {
@@ -168,6 +227,7 @@ impl SwarmDriver {
                         if step.last {
                             // To avoid the caller wait forever on a non-existring entry
                             if let Some(sender) = self.pending_query.remove(id) {
+                                self.record_kad_query("get_record", stats);
                                 sender
                                     .send(Err(Error::RecordNotFound))
                                     .map_err(|_| Error::InternalMsgChannelDropped)?;
@@ -212,6 +272,42 @@ impl SwarmDriver {
                     libp2p::identify::Event::Error { .. } => {}
                 }
             }
+            SwarmEvent::Behaviour(NodeEvent::RelayClient(event)) => {
+                info!("RelayClientEvent: {event:?}");
+            }
+            SwarmEvent::Behaviour(NodeEvent::RelayServer(event)) => {
+                info!("RelayServerEvent: {event:?}");
+            }
+            SwarmEvent::Behaviour(NodeEvent::Autonat(event)) => match *event {
+                autonat::Event::StatusChanged { old, new } => {
+                    info!("NAT status changed from {old:?} to {new:?}");
+                    if new == autonat::NatStatus::Private {
+                        if self.nat.relay_server {
+                            warn!("Relaying connections of peers while not publicly reachable");
+                        }
+                        self.listen_through_relays();
+                    }
+                    self.event_sender
+                        .send(NetworkEvent::NatStatusChanged(new.into()))
+                        .await?;
+                }
+                event => trace!("AutonatEvent: {event:?}"),
+            },
+            SwarmEvent::Behaviour(NodeEvent::Dcutr(event)) => match *event {
+                dcutr::Event::DirectConnectionUpgradeSucceeded { remote_peer_id } => {
+                    info!("Upgraded the relayed connection to {remote_peer_id} to a direct one");
+                    self.event_sender
+                        .send(NetworkEvent::DirectConnectionUpgraded(remote_peer_id))
+                        .await?;
+                }
+                dcutr::Event::DirectConnectionUpgradeFailed {
+                    remote_peer_id,
+                    error,
+                } => {
+                    warn!("Failed to upgrade the relayed connection to {remote_peer_id}: {error}");
+                }
+                event => trace!("DcutrEvent: {event:?}"),
+            },
             SwarmEvent::Behaviour(NodeEvent::Mdns(mdns_event)) => match *mdns_event {
                 mdns::Event::Discovered(list) => {
                     for (peer_id, multiaddr) in list {
@@ -252,6 +348,7 @@ impl SwarmDriver {
                     .send(NetworkEvent::NewListenAddr(address.clone()))
                     .await?;
                 info!("Local node is listening on {address:?}");
+                self.map_listen_port(&address);
             }
             SwarmEvent::IncomingConnection { .. } => {}
             SwarmEvent::ConnectionEstablished {
@@ -285,4 +382,61 @@ impl SwarmDriver {
         }
         Ok(())
     }
+
+    /// Map the port of the first address listened on by the node in the router of the
+    /// local network, if enabled and the address is on an IPv4 interface of the host.
+    fn map_listen_port(&mut self, address: &Multiaddr) {
+        if !self.nat.upnp || self.port_mapped {
+            return;
+        }
+        let mut protocols = address.iter();
+        if let (Some(Protocol::Ip4(ip)), Some(Protocol::Udp(port))) =
+            (protocols.next(), protocols.next())
+        {
+            if !ip.is_loopback() {
+                self.port_mapped = true;
+                nat::map_port_in_router(port, self.self_cmd_sender.clone());
+            }
+        }
+    }
+
+    /// Listen through circuit relays of peers in the routing table, if enabled and not already
+    /// done, for peers to reach the node despite its NAT.
+    fn listen_through_relays(&mut self) {
+        if !self.nat.relay_client || self.listening_through_relays {
+            return;
+        }
+        let relays: Vec<_> = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .kbuckets()
+            .flat_map(|bucket| {
+                bucket
+                    .iter()
+                    .map(|entry| (*entry.node.key.preimage(), entry.node.value.first().clone()))
+                    .collect::<Vec<_>>()
+            })
+            .take(RELAYS_LISTENED_THROUGH)
+            .collect();
+        for (relay, addr) in relays {
+            let circuit = addr
+                .with(Protocol::P2p(relay.into()))
+                .with(Protocol::P2pCircuit);
+            match self.swarm.listen_on(circuit.clone()) {
+                Ok(_) => {
+                    info!("Listening through the relay {circuit}");
+                    self.listening_through_relays = true;
+                }
+                Err(err) => warn!("Failed to listen through the relay {circuit}: {err}"),
+            }
+        }
+    }
+
+    /// Records the time a Kademlia query of the given kind took to complete.
+    fn record_kad_query(&mut self, kind: &'static str, stats: &QueryStats) {
+        let kad_query_stats = self.kad_query_stats.entry(kind).or_default();
+        kad_query_stats.completed += 1;
+        kad_query_stats.duration += stats.duration().unwrap_or_default();
+    }
 }
@@ -6,15 +6,27 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(feature = "adversary")]
+mod adversary;
+#[cfg(feature = "chaos")]
+mod chaos;
 mod cmd;
 mod error;
 mod event;
 mod msg;
-
+mod nat;
+#[cfg(any(test, feature = "test-utils"))]
+pub(crate) mod sim;
+
+#[cfg(feature = "adversary")]
+pub use self::adversary::AdversaryConfig;
+#[cfg(feature = "chaos")]
+pub use self::chaos::ChaosConfig;
 pub use self::{
-    cmd::SwarmLocalState,
+    cmd::{KadQueryStats, SwarmLocalState},
     error::Error,
     event::{MsgResponder, NetworkEvent},
+    nat::{NatStatus, NatTraversal},
 };
 
 use self::{
@@ -24,29 +36,38 @@ use self::{
     msg::{MsgCodec, MsgProtocol},
 };
 
-use crate::domain::storage::{DiskBackedRecordStore, DiskBackedRecordStoreConfig};
+use crate::domain::storage::{
+    DiskBackedRecordStore, DiskBackedRecordStoreConfig, RECORD_STORE_DIR_NAME,
+};
 use crate::protocol::{
-    messages::{QueryResponse, Request, Response},
+    messages::{CorrelationId, QueryResponse, Request, RequestEnvelope, Response},
     NetworkAddress,
 };
 
+use sn_dbc::Token;
+
 use futures::{future::select_all, StreamExt};
+#[cfg(feature = "adversary")]
+use libp2p::kad::KademliaStoreInserts;
 use libp2p::{
-    core::muxing::StreamMuxerBox,
-    identity,
+    autonat,
+    bandwidth::BandwidthSinks,
+    core::{muxing::StreamMuxerBox, upgrade},
+    dcutr, identity,
     kad::{Kademlia, KademliaConfig, QueryId, Record, RecordKey},
     mdns,
     multiaddr::Protocol,
+    noise, relay,
     request_response::{self, Config as RequestResponseConfig, ProtocolSupport, RequestId},
     swarm::{Swarm, SwarmBuilder},
-    Multiaddr, PeerId, Transport,
+    yamux, Multiaddr, PeerId, Transport, TransportExt,
 };
 use std::{
-    collections::{HashMap, HashSet},
-    iter,
+    collections::{BTreeMap, HashMap, HashSet},
     net::SocketAddr,
     num::NonZeroUsize,
     path::{Path, PathBuf},
+    sync::Arc,
     time::Duration,
 };
 use tokio::sync::{mpsc, oneshot};
@@ -89,14 +110,27 @@ pub struct SwarmDriver {
     pending_dial: HashMap<PeerId, oneshot::Sender<Result<()>>>,
     pending_get_closest_peers: PendingGetClosest,
     pending_requests: HashMap<RequestId, oneshot::Sender<Result<Response>>>,
-    pending_query: HashMap<QueryId, oneshot::Sender<Result<QueryResponse>>>,
+    pending_query: HashMap<QueryId, oneshot::Sender<Result<(QueryResponse, Option<PeerId>)>>>,
+    kad_query_stats: BTreeMap<&'static str, KadQueryStats>,
+    nat: NatTraversal,
+    /// For the tasks spawned by the driver to send it cmds, e.g. once a port is mapped.
+    self_cmd_sender: mpsc::Sender<SwarmCmd>,
+    /// Whether the port listened on has been mapped in the router, with UPnP.
+    port_mapped: bool,
+    /// Whether the node listens through circuit relays, not being publicly reachable.
+    listening_through_relays: bool,
+    #[cfg(feature = "chaos")]
+    chaos: ChaosConfig,
+    #[cfg(feature = "adversary")]
+    adversary: AdversaryConfig,
 }
 
 impl SwarmDriver {
     /// Creates a new `SwarmDriver` instance, along with a `Network` handle
     /// for sending commands and an `mpsc::Receiver<NetworkEvent>` for receiving
     /// network events. It initializes the swarm, sets up the transport, and
-    /// configures the Kademlia and mDNS behaviours for peer discovery.
+    /// configures the Kademlia and mDNS behaviours for peer discovery, along with
+    /// the enabled techniques to be reachable from behind a NAT.
     ///
     /// # Returns
     ///
@@ -109,6 +143,7 @@ impl SwarmDriver {
     pub fn new(
         addr: SocketAddr,
         root_dir: &Path,
+        nat: NatTraversal,
     ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
         let mut kad_cfg = KademliaConfig::default();
         let _ = kad_cfg
@@ -129,8 +164,28 @@ impl SwarmDriver {
             // Records never expire
             .set_record_ttl(None);
 
-        let (network, events_receiver, mut swarm_driver) =
-            Self::with(kad_cfg, false, Some(root_dir.join("record_store")))?;
+        #[cfg(feature = "adversary")]
+        let adversary = AdversaryConfig::from_env();
+        #[cfg(feature = "adversary")]
+        if adversary.ignore_replication {
+            // The records put by peers are then only logged, and the node's never replicated.
+            let _ = kad_cfg
+                .set_record_filtering(KademliaStoreInserts::FilterBoth)
+                .set_replication_interval(None);
+        }
+
+        let (network, events_receiver, mut swarm_driver) = Self::with(
+            kad_cfg,
+            false,
+            Some(root_dir.join(RECORD_STORE_DIR_NAME)),
+            nat,
+            true,
+            REQUEST_TIMEOUT,
+        )?;
+        #[cfg(feature = "adversary")]
+        {
+            swarm_driver.adversary = adversary;
+        }
 
         // Listen on the provided address
         let addr = Multiaddr::from(addr.ip())
@@ -146,6 +201,15 @@ impl SwarmDriver {
 
     /// Same as `new` API but creates the network components in client mode
     pub fn new_client() -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
+        Self::new_client_with(true, REQUEST_TIMEOUT)
+    }
+
+    /// Same as `new_client`, discovering peers on the local network with mDNS only if `local`,
+    /// and timing out requests which get no response within `request_timeout`.
+    pub fn new_client_with(
+        local: bool,
+        request_timeout: Duration,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
         // Create a Kademlia behaviour for client mode, i.e. set req/resp protocol
         // to outbound-only mode and don't listen on any address
         let mut kad_cfg = KademliaConfig::default(); // default query timeout is 60 secs
@@ -160,7 +224,14 @@ impl SwarmDriver {
                 NonZeroUsize::new(CLOSE_GROUP_SIZE).ok_or_else(|| Error::InvalidCloseGroupSize)?,
             );
 
-        Self::with(kad_cfg, true, None)
+        Self::with(
+            kad_cfg,
+            true,
+            None,
+            NatTraversal::default(),
+            local,
+            request_timeout,
+        )
     }
 
     // Private helper to create the network components with the provided config and req/res behaviour
@@ -168,6 +239,9 @@ impl SwarmDriver {
         kad_cfg: KademliaConfig,
         is_client: bool,
         disk_store_path: Option<PathBuf>,
+        nat: NatTraversal,
+        local: bool,
+        request_timeout: Duration,
     ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, SwarmDriver)> {
         // Create a random key for ourself.
         let keypair = identity::Keypair::generate_ed25519();
@@ -179,17 +253,21 @@ impl SwarmDriver {
         let request_response = {
             let mut cfg = RequestResponseConfig::default();
             let _ = cfg
-                .set_request_timeout(REQUEST_TIMEOUT)
+                .set_request_timeout(request_timeout)
                 .set_connection_keep_alive(CONNECTION_KEEP_ALIVE_TIMEOUT);
 
-            let req_res_protocol = if is_client {
-                ProtocolSupport::Outbound
-            } else {
-                ProtocolSupport::Full
+            let req_res_protocol = || {
+                if is_client {
+                    ProtocolSupport::Outbound
+                } else {
+                    ProtocolSupport::Full
+                }
             };
             request_response::Behaviour::new(
                 MsgCodec(),
-                iter::once((MsgProtocol(), req_res_protocol)),
+                MsgProtocol::ALL
+                    .into_iter()
+                    .map(|protocol| (protocol, req_res_protocol())),
                 cfg,
             )
         };
@@ -212,8 +290,8 @@ impl SwarmDriver {
             )
         };
 
-        // mDNS Behaviour
-        let mdns = {
+        // mDNS Behaviour, discovering the peers of a local network
+        let mdns = if local {
             let cfg = mdns::Config {
                 // lower query interval to speed up peer discovery
                 // this increases traffic, but means we no longer have clients unable to connect
@@ -221,7 +299,9 @@ impl SwarmDriver {
                 query_interval: Duration::from_secs(5),
                 ..Default::default()
             };
-            mdns::tokio::Behaviour::new(cfg, peer_id)?
+            Some(mdns::tokio::Behaviour::new(cfg, peer_id)?)
+        } else {
+            None
         };
 
         // Identify Behaviour
@@ -236,21 +316,65 @@ impl SwarmDriver {
             libp2p::identify::Behaviour::new(cfg)
         };
 
+        // Relay client Behaviour and Transport, which allow clients behind NATs
+        // to dial peers via circuit relays, i.e. `/<relay-addr>/p2p-circuit/p2p/<peer-id>`,
+        // and nodes behind NATs to listen through them
+        let (relay_transport, relay_client) = if is_client || nat.relay_client {
+            let (transport, behaviour) = relay::client::new(peer_id);
+            (Some(transport), Some(behaviour))
+        } else {
+            (None, None)
+        };
+
+        // Relay server Behaviour, for peers which are not publicly reachable to listen through
+        // the node, if it serves as a relay
+        let relay_server = (!is_client && nat.relay_server)
+            .then(|| relay::Behaviour::new(peer_id, relay::Config::default()));
+
+        // AutoNAT Behaviour, probing whether the node is publicly reachable
+        let autonat = nat
+            .autonat
+            .then(|| autonat::Behaviour::new(peer_id, autonat::Config::default()));
+
+        // DCUtR Behaviour, upgrading relayed connections to direct ones by hole punching,
+        // for clients to reach nodes behind NATs directly once connected through their relays
+        let dcutr = (is_client || nat.hole_punching).then(|| dcutr::Behaviour::new(peer_id));
+
         // Transport
         let transport = {
             // use the QUIC Protocol for transport
             let quic_config = libp2p_quic::Config::new(&keypair);
-            let transport = libp2p_quic::tokio::Transport::new(quic_config);
-            transport
-                .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
-                .boxed()
+            let transport = libp2p_quic::tokio::Transport::new(quic_config)
+                .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+
+            match relay_transport {
+                Some(relay_transport) => {
+                    // relayed connections are not QUIC, thus they need to be secured and multiplexed
+                    let relay_transport = relay_transport
+                        .upgrade(upgrade::Version::V1)
+                        .authenticate(noise::NoiseAuthenticated::xx(&keypair)?)
+                        .multiplex(yamux::YamuxConfig::default())
+                        .map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)));
+                    transport
+                        .or_transport(relay_transport)
+                        .map(|either_output, _| either_output.into_inner())
+                        .boxed()
+                }
+                None => transport.boxed(),
+            }
         };
+        // Count the bytes going through the transport, for the node to report its bandwidth.
+        let (transport, bandwidth) = transport.with_bandwidth_logging();
 
         let behaviour = NodeBehaviour {
             request_response,
             kademlia,
-            mdns,
+            mdns: mdns.into(),
             identify,
+            relay_client: relay_client.into(),
+            relay_server: relay_server.into(),
+            autonat: autonat.into(),
+            dcutr: dcutr.into(),
         };
         let swarm = SwarmBuilder::with_tokio_executor(transport, behaviour, peer_id).build();
 
@@ -264,12 +388,23 @@ impl SwarmDriver {
             pending_get_closest_peers: Default::default(),
             pending_requests: Default::default(),
             pending_query: Default::default(),
+            kad_query_stats: Default::default(),
+            nat,
+            self_cmd_sender: swarm_cmd_sender.clone(),
+            port_mapped: false,
+            listening_through_relays: false,
+            #[cfg(feature = "chaos")]
+            chaos: ChaosConfig::from_env(),
+            // Only nodes misbehave, their config being set once created.
+            #[cfg(feature = "adversary")]
+            adversary: AdversaryConfig::default(),
         };
 
         Ok((
             Network {
                 swarm_cmd_sender,
                 peer_id,
+                bandwidth: Some(bandwidth),
             },
             network_event_receiver,
             swarm_driver,
@@ -309,6 +444,8 @@ impl SwarmDriver {
 pub struct Network {
     pub(super) swarm_cmd_sender: mpsc::Sender<SwarmCmd>,
     pub(super) peer_id: PeerId,
+    /// Not set for the peers of a simulated network, which have no transport.
+    bandwidth: Option<Arc<BandwidthSinks>>,
 }
 
 impl Network {
@@ -356,6 +493,19 @@ impl Network {
         self.get_closest_peers(key, false).await
     }
 
+    /// Returns the addresses found in our routing table for each of the given peers.
+    /// A peer we hold no address for is returned with an empty list.
+    pub async fn get_peers_addresses(
+        &self,
+        peers: Vec<PeerId>,
+    ) -> Result<Vec<(PeerId, Vec<Multiaddr>)>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetPeersAddresses { peers, sender })
+            .await?;
+        let peers_addresses = receiver.await?;
+        Ok(peers_addresses)
+    }
+
     /// Send `Request` to the closest peers. If `self` is among the closest_peers, the `Request` is
     /// forwarded to itself and handled. Then a corresponding `Response` is created and is
     /// forwarded to iself. Hence the flow remains the same and there is no branching at the upper
@@ -403,6 +553,18 @@ impl Network {
 
     /// Get `Key` from our Storage
     pub async fn get_provided_data(&self, key: RecordKey) -> Result<Result<QueryResponse>> {
+        Ok(self
+            .get_provided_data_and_holder(key)
+            .await?
+            .map(|(response, _holder)| response))
+    }
+
+    /// Get `Key` from our Storage, along with the peer which held it,
+    /// `None` if it was found in the local store.
+    pub async fn get_provided_data_and_holder(
+        &self,
+        key: RecordKey,
+    ) -> Result<Result<(QueryResponse, Option<PeerId>)>> {
         let (sender, receiver) = oneshot::channel();
         self.send_swarm_cmd(SwarmCmd::GetData { key, sender })
             .await?;
@@ -412,6 +574,15 @@ impl Network {
             .map_err(|_e| Error::InternalMsgChannelDropped)
     }
 
+    /// Get the cost of storing a new record in our local store
+    pub async fn get_local_store_cost(&self) -> Result<Token> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetLocalStoreCost { sender })
+            .await?;
+        let cost = receiver.await?;
+        Ok(cost)
+    }
+
     /// Put data to KAD network as record
     pub async fn put_data_as_record(&self, record: Record) -> Result<()> {
         debug!(
@@ -429,6 +600,7 @@ impl Network {
     /// layers.
     pub async fn send_request(&self, req: Request, peer: PeerId) -> Result<Response> {
         let (sender, receiver) = oneshot::channel();
+        let req = envelope(req);
         self.send_swarm_cmd(SwarmCmd::SendRequest { req, peer, sender })
             .await?;
         receiver.await?
@@ -443,6 +615,7 @@ impl Network {
     /// Send `Request` to the the given `PeerId` and do _not_ await a response.
     pub async fn fire_and_forget(&self, req: Request, peer: PeerId) -> Result<()> {
         let (sender, _) = oneshot::channel();
+        let req = envelope(req);
         let swarm_cmd = SwarmCmd::SendRequest { req, peer, sender };
         self.send_swarm_cmd(swarm_cmd).await
     }
@@ -456,6 +629,19 @@ impl Network {
         Ok(state)
     }
 
+    /// Replaces the faults injected into the messages handled by the `SwarmDriver`.
+    #[cfg(feature = "chaos")]
+    pub async fn set_chaos(&self, config: ChaosConfig) -> Result<()> {
+        self.send_swarm_cmd(SwarmCmd::SetChaos(config)).await
+    }
+
+    /// Returns the total number of bytes received and sent over the network so far.
+    pub fn bandwidth(&self) -> (u64, u64) {
+        self.bandwidth.as_ref().map_or((0, 0), |bandwidth| {
+            (bandwidth.total_inbound(), bandwidth.total_outbound())
+        })
+    }
+
     // Helper to send SwarmCmd
     async fn send_swarm_cmd(&self, cmd: SwarmCmd) -> Result<()> {
         self.swarm_cmd_sender.send(cmd).await?;
@@ -536,6 +722,15 @@ impl Network {
     }
 }
 
+/// Wraps the request to be sent over the wire, along with the id of the client operation
+/// the current task is running for, if any.
+fn envelope(request: Request) -> RequestEnvelope {
+    RequestEnvelope {
+        correlation_id: CorrelationId::current(),
+        request,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::SwarmDriver;
@@ -580,6 +775,7 @@ mod tests {
                     .parse::<SocketAddr>()
                     .expect("0.0.0.0:0 should parse into a valid `SocketAddr`"),
                 Path::new(""),
+                NatTraversal::default(),
             )?;
             let _handle = tokio::spawn(driver.run());
 
@@ -647,6 +843,7 @@ mod tests {
                 .parse::<SocketAddr>()
                 .expect("0.0.0.0:0 should parse into a valid `SocketAddr`"),
             Path::new(""),
+            NatTraversal::default(),
         )?;
         let _driver_handle = tokio::spawn(driver.run());
 
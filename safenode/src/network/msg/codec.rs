@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::protocol::messages::{Request, Response};
+use crate::protocol::messages::{wire, Request, RequestEnvelope, Response};
 use async_trait::async_trait;
 use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use libp2p::{
@@ -16,81 +16,116 @@ use libp2p::{
 use serde::{de::DeserializeOwned, Serialize};
 use std::io;
 
+/// The protocols the messages are exchanged with, in order of preference.
 #[derive(Debug, Clone)]
-pub(crate) struct MsgProtocol();
+pub(crate) enum MsgProtocol {
+    /// The messages are encoded with a version, and their fields identified by name.
+    Versioned,
+    /// The messages are encoded as the peers which predate the versioning expect them,
+    /// the requests being sent without the id of the operation they're sent for.
+    /// To be removed once no such peer is left on the network.
+    Legacy,
+}
 #[derive(Clone)]
 pub(crate) struct MsgCodec();
 
+impl MsgProtocol {
+    /// The protocols supported, the versioned one being negotiated with the peers supporting it.
+    pub(crate) const ALL: [MsgProtocol; 2] = [MsgProtocol::Versioned, MsgProtocol::Legacy];
+}
+
 impl ProtocolName for MsgProtocol {
     fn protocol_name(&self) -> &[u8] {
-        "/safe/1".as_bytes()
+        match self {
+            MsgProtocol::Versioned => "/safe/3".as_bytes(),
+            MsgProtocol::Legacy => "/safe/1".as_bytes(),
+        }
     }
 }
 
 #[async_trait]
 impl request_response::Codec for MsgCodec {
     type Protocol = MsgProtocol;
-    type Request = Request;
+    type Request = RequestEnvelope;
     type Response = Response;
 
-    async fn read_request<T>(&mut self, _: &Self::Protocol, io: &mut T) -> io::Result<Self::Request>
+    async fn read_request<T>(
+        &mut self,
+        protocol: &Self::Protocol,
+        io: &mut T,
+    ) -> io::Result<Self::Request>
     where
         T: AsyncRead + Unpin + Send,
     {
-        read_and_decode(io).await
+        match protocol {
+            MsgProtocol::Versioned => read_and_decode(protocol, io).await,
+            MsgProtocol::Legacy => {
+                let request: Request = read_and_decode(protocol, io).await?;
+                Ok(RequestEnvelope {
+                    correlation_id: None,
+                    request,
+                })
+            }
+        }
     }
 
     async fn read_response<T>(
         &mut self,
-        _: &Self::Protocol,
+        protocol: &Self::Protocol,
         io: &mut T,
     ) -> io::Result<Self::Response>
     where
         T: AsyncRead + Unpin + Send,
     {
-        read_and_decode(io).await
+        read_and_decode(protocol, io).await
     }
 
     async fn write_request<T>(
         &mut self,
-        _: &Self::Protocol,
+        protocol: &Self::Protocol,
         io: &mut T,
         req: Self::Request,
     ) -> io::Result<()>
     where
         T: AsyncWrite + Unpin + Send,
     {
-        encode_and_write(io, req).await
+        match protocol {
+            MsgProtocol::Versioned => encode_and_write(protocol, io, req).await,
+            MsgProtocol::Legacy => encode_and_write(protocol, io, req.request).await,
+        }
     }
 
     async fn write_response<T>(
         &mut self,
-        _: &Self::Protocol,
+        protocol: &Self::Protocol,
         io: &mut T,
         res: Self::Response,
     ) -> io::Result<()>
     where
         T: AsyncWrite + Unpin + Send,
     {
-        encode_and_write(io, res).await
+        encode_and_write(protocol, io, res).await
     }
 }
 
-// Encodes the Response/Response using rmp_serde
-async fn encode_and_write<IO, T>(io: &mut IO, data: T) -> io::Result<()>
+// Encodes the Request/Response as expected by the peer, for the protocol negotiated with it
+async fn encode_and_write<IO, T>(protocol: &MsgProtocol, io: &mut IO, data: T) -> io::Result<()>
 where
     IO: AsyncWrite + Unpin,
     T: Serialize,
 {
-    let bytes = rmp_serde::to_vec(&data)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    let bytes = match protocol {
+        MsgProtocol::Versioned => wire::encode(&data),
+        MsgProtocol::Legacy => wire::encode_legacy(&data),
+    }
+    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
     write_length_prefixed(io, bytes).await?;
     io.close().await?;
     Ok(())
 }
 
-// Decodes the Response/Response using rmp_serde
-async fn read_and_decode<IO, T>(io: &mut IO) -> io::Result<T>
+// Decodes the Request/Response sent by the peer, for the protocol negotiated with it
+async fn read_and_decode<IO, T>(protocol: &MsgProtocol, io: &mut IO) -> io::Result<T>
 where
     IO: AsyncRead + Unpin,
     T: DeserializeOwned,
@@ -99,6 +134,9 @@ where
     if vec.is_empty() {
         return Err(io::ErrorKind::UnexpectedEof.into());
     }
-    rmp_serde::from_slice::<T>(vec.as_slice())
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    match protocol {
+        MsgProtocol::Versioned => wire::decode(&vec),
+        MsgProtocol::Legacy => wire::decode_legacy(&vec),
+    }
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
 }
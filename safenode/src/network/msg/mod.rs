@@ -12,7 +12,7 @@ pub(crate) use codec::{MsgCodec, MsgProtocol};
 
 use crate::{
     network::{error::Error, MsgResponder, NetworkEvent, SwarmDriver},
-    protocol::messages::{Request, Response},
+    protocol::messages::{RequestEnvelope, Response},
 };
 
 use libp2p::request_response::{self, Message};
@@ -22,7 +22,7 @@ impl SwarmDriver {
     /// Forwards `Request` to the upper layers using `Sender<NetworkEvent>`. Sends `Response` to the peers
     pub async fn handle_msg(
         &mut self,
-        event: request_response::Event<Request, Response>,
+        event: request_response::Event<RequestEnvelope, Response>,
     ) -> Result<(), Error> {
         match event {
             request_response::Event::Message { message, .. } => match message {
@@ -33,21 +33,53 @@ impl SwarmDriver {
                     ..
                 } => {
                     trace!("Received request with id: {request_id:?}, req: {request:?}");
-                    self.event_sender
-                        .send(NetworkEvent::RequestReceived {
-                            req: request,
-                            channel: MsgResponder::FromPeer(channel),
-                        })
-                        .await?
+                    #[cfg(feature = "adversary")]
+                    if self.adversary.ignores(&request.request) {
+                        warn!("Ignoring the replication with id: {request_id:?}");
+                        return Ok(());
+                    }
+                    let event = NetworkEvent::RequestReceived {
+                        req: request.request,
+                        correlation_id: request.correlation_id,
+                        channel: MsgResponder::FromPeer(channel),
+                    };
+                    #[cfg(feature = "chaos")]
+                    {
+                        if self.chaos.should_drop() {
+                            warn!("Dropping request with id: {request_id:?}");
+                            return Ok(());
+                        }
+                        if let Some(delay) = self.chaos.delay() {
+                            let event_sender = self.event_sender.clone();
+                            let _handle = tokio::spawn(async move {
+                                tokio::time::sleep(delay).await;
+                                if let Err(err) = event_sender.send(event).await {
+                                    warn!("Failed to forward a delayed request: {err}");
+                                }
+                            });
+                            return Ok(());
+                        }
+                    }
+                    self.event_sender.send(event).await?
                 }
                 Message::Response {
                     request_id,
                     response,
                 } => {
                     trace!("Got response for id: {request_id:?}, res: {response}.");
-                    self.pending_requests
+                    let sender = self
+                        .pending_requests
                         .remove(&request_id)
-                        .ok_or(Error::ReceivedResponseDropped(request_id))?
+                        .ok_or(Error::ReceivedResponseDropped(request_id))?;
+                    #[cfg(feature = "chaos")]
+                    if let Some(delay) = self.chaos.delay() {
+                        let _handle = tokio::spawn(async move {
+                            tokio::time::sleep(delay).await;
+                            let _ = sender.send(Ok(response));
+                        });
+                        return Ok(());
+                    }
+                    sender
                         .send(Ok(response))
                         .map_err(|_| Error::InternalMsgChannelDropped)?;
                 }
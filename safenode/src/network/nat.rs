@@ -0,0 +1,465 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{cmd::SwarmCmd, error::Result, Error};
+
+use igd::{aio::search_gateway, PortMappingProtocol, SearchOptions};
+use libp2p::{autonat, multiaddr::Protocol, Multiaddr};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddrV4, UdpSocket},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+
+/// How long the port mapped in the router is leased for. It's renewed halfway through.
+const MAPPING_LEASE: Duration = Duration::from_secs(60 * 60);
+
+/// The time waited before retrying to renew the lease of the mapped port after it first
+/// failed, doubling on each failure.
+const RENEWAL_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Number of times renewing the lease of the mapped port is attempted before the mapping is
+/// deemed lost, all within the half of the lease which is left when renewals start.
+const RENEWAL_ATTEMPTS: u32 = 6;
+
+/// The description of the port mapping, as shown by the router.
+const UPNP_DESCRIPTION: &str = "safenode";
+
+/// The port NAT-PMP gateways listen on.
+const NAT_PMP_PORT: u16 = 5351;
+
+/// The NAT-PMP request of the external address of the router: version 0, opcode 0.
+const NAT_PMP_EXTERNAL_ADDRESS_REQUEST: [u8; 2] = [0, 0];
+
+/// The NAT-PMP opcode of a request to map a UDP port.
+const NAT_PMP_MAP_UDP: u8 = 1;
+
+/// The time a NAT-PMP request is first waited a response for, doubling on each resend.
+const NAT_PMP_INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Number of times a NAT-PMP request is sent before giving up.
+const NAT_PMP_ATTEMPTS: u32 = 4;
+
+/// The techniques a node uses to be reachable by its peers from behind a NAT,
+/// e.g. that of the router of a home network. They are all disabled by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NatTraversal {
+    /// Map the port the node listens on in the router, with UPnP, or NAT-PMP if the router
+    /// doesn't support UPnP.
+    pub upnp: bool,
+    /// Ask peers to dial the node back, to learn whether it's publicly reachable, with AutoNAT.
+    pub autonat: bool,
+    /// Upgrade the connections relayed to the node to direct ones, with DCUtR.
+    pub hole_punching: bool,
+    /// Listen through circuit relays of peers when the node is not publicly reachable.
+    pub relay_client: bool,
+    /// Relay the connections of peers which are not publicly reachable, with circuit relay v2.
+    pub relay_server: bool,
+}
+
+impl NatTraversal {
+    /// All the techniques, for a node behind the router of a home network, which may or may not
+    /// support UPnP, and is of unknown type: whatever works is used, the rest is harmless.
+    pub fn home_network() -> Self {
+        Self {
+            upnp: true,
+            autonat: true,
+            hole_punching: true,
+            relay_client: true,
+            relay_server: false,
+        }
+    }
+}
+
+/// Whether the node is publicly reachable, as learnt by asking peers to dial it back.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NatStatus {
+    /// Peers could dial the node at the given address.
+    Public(Multiaddr),
+    /// Peers could not dial the node, which is behind a NAT or a firewall.
+    Private,
+    /// Not enough peers dialed the node back yet.
+    Unknown,
+}
+
+impl From<autonat::NatStatus> for NatStatus {
+    fn from(status: autonat::NatStatus) -> Self {
+        match status {
+            autonat::NatStatus::Public(addr) => Self::Public(addr),
+            autonat::NatStatus::Private => Self::Private,
+            autonat::NatStatus::Unknown => Self::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for NatStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Public(addr) => write!(f, "public at {addr}"),
+            Self::Private => write!(f, "private"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// How a port of the node was mapped in the router of its local network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PortMappingMethod {
+    Upnp,
+    NatPmp,
+}
+
+impl std::fmt::Display for PortMappingMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Upnp => write!(f, "UPnP"),
+            Self::NatPmp => write!(f, "NAT-PMP"),
+        }
+    }
+}
+
+impl PortMappingMethod {
+    /// Map the UDP port of this host to the same port of the router, if available, returning
+    /// the external address it was mapped to.
+    async fn map(self, port: u16) -> Result<SocketAddrV4> {
+        match self {
+            Self::Upnp => map_port_with_upnp(port).await,
+            Self::NatPmp => map_port_with_nat_pmp(port).await,
+        }
+    }
+}
+
+/// Map the UDP port in the router of the local network, with UPnP, or with NAT-PMP if the router
+/// doesn't support UPnP, and renew the lease until the swarm driver stops. The address the port
+/// is mapped to is sent back to the swarm driver, for it to be advertised to peers as an
+/// external address of the node, and withdrawn if the port is mapped to another address, or if
+/// the lease can't be renewed.
+pub(super) fn map_port_in_router(port: u16, swarm_cmd_sender: mpsc::Sender<SwarmCmd>) {
+    let _handle = tokio::spawn(async move {
+        let mut mapped: Option<(PortMappingMethod, SocketAddrV4)> = None;
+        let mut failed_renewals = 0;
+        loop {
+            let result = match mapped {
+                Some((method, _)) => method.map(port).await.map(|addr| (method, addr)),
+                None => match PortMappingMethod::Upnp.map(port).await {
+                    Ok(addr) => Ok((PortMappingMethod::Upnp, addr)),
+                    Err(err) => {
+                        debug!("Failed to map port {port} with UPnP, trying NAT-PMP: {err}");
+                        PortMappingMethod::NatPmp
+                            .map(port)
+                            .await
+                            .map(|addr| (PortMappingMethod::NatPmp, addr))
+                    }
+                },
+            };
+            let delay = match (result, mapped) {
+                (Ok((method, external_addr)), Some((_, mapped_addr)))
+                    if external_addr == mapped_addr =>
+                {
+                    trace!("Renewed the {method} lease of port {port}");
+                    failed_renewals = 0;
+                    MAPPING_LEASE / 2
+                }
+                (Ok((method, external_addr)), previous) => {
+                    info!("Mapped port {port} to {external_addr} with {method}");
+                    if let Some((_, previous_addr)) = previous {
+                        info!("Port {port} is no longer mapped to {previous_addr}");
+                        let cmd = SwarmCmd::PortUnmapped(quic_multiaddr(previous_addr));
+                        if swarm_cmd_sender.send(cmd).await.is_err() {
+                            return;
+                        }
+                    }
+                    let cmd = SwarmCmd::PortMapped(quic_multiaddr(external_addr));
+                    if swarm_cmd_sender.send(cmd).await.is_err() {
+                        return;
+                    }
+                    mapped = Some((method, external_addr));
+                    failed_renewals = 0;
+                    MAPPING_LEASE / 2
+                }
+                (Err(err), None) => {
+                    warn!("Failed to map port {port} in the router: {err}");
+                    return;
+                }
+                (Err(err), Some((method, mapped_addr))) => {
+                    failed_renewals += 1;
+                    match renewal_backoff(failed_renewals) {
+                        Some(backoff) => {
+                            warn!("Failed to renew the {method} lease of port {port}, retrying in {backoff:?}: {err}");
+                            backoff
+                        }
+                        None => {
+                            warn!("Lost the mapping of port {port} to {mapped_addr}, failing to renew its {method} lease: {err}");
+                            let cmd = SwarmCmd::PortUnmapped(quic_multiaddr(mapped_addr));
+                            let _ = swarm_cmd_sender.send(cmd).await;
+                            return;
+                        }
+                    }
+                }
+            };
+            tokio::time::sleep(delay).await;
+            if swarm_cmd_sender.is_closed() {
+                return;
+            }
+        }
+    });
+}
+
+/// The time to wait before retrying to renew the lease of the mapped port, after the given
+/// number of consecutive failures, or `None` once the mapping is deemed lost.
+fn renewal_backoff(failed_renewals: u32) -> Option<Duration> {
+    if failed_renewals == 0 || failed_renewals >= RENEWAL_ATTEMPTS {
+        return None;
+    }
+    Some(RENEWAL_BACKOFF * 2u32.pow(failed_renewals - 1))
+}
+
+/// The QUIC address of the node at the given external address of the router.
+fn quic_multiaddr(addr: SocketAddrV4) -> Multiaddr {
+    Multiaddr::from(*addr.ip())
+        .with(Protocol::Udp(addr.port()))
+        .with(Protocol::QuicV1)
+}
+
+/// Map the UDP port of this host to the same port of the router with UPnP, returning the
+/// external address.
+async fn map_port_with_upnp(port: u16) -> Result<SocketAddrV4> {
+    let gateway = search_gateway(SearchOptions::default())
+        .await
+        .map_err(|err| Error::PortMapping(err.to_string()))?;
+    let local_ip = local_ip_towards(gateway.addr)?;
+    gateway
+        .add_port(
+            PortMappingProtocol::UDP,
+            port,
+            SocketAddrV4::new(local_ip, port),
+            MAPPING_LEASE.as_secs() as u32,
+            UPNP_DESCRIPTION,
+        )
+        .await
+        .map_err(|err| Error::PortMapping(err.to_string()))?;
+    let external_ip = gateway
+        .get_external_ip()
+        .await
+        .map_err(|err| Error::PortMapping(err.to_string()))?;
+    Ok(SocketAddrV4::new(external_ip, port))
+}
+
+/// Map the UDP port of this host to a port of the router with NAT-PMP (RFC 6886), preferably
+/// the same one, returning the external address.
+async fn map_port_with_nat_pmp(port: u16) -> Result<SocketAddrV4> {
+    let gateway = SocketAddrV4::new(default_gateway()?, NAT_PMP_PORT);
+    let socket = tokio::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.connect(gateway).await?;
+
+    let response = nat_pmp_request(&socket, &NAT_PMP_EXTERNAL_ADDRESS_REQUEST).await?;
+    let external_ip = parse_nat_pmp_external_address(&response)?;
+    let request = nat_pmp_mapping_request(port, MAPPING_LEASE.as_secs() as u32);
+    let response = nat_pmp_request(&socket, &request).await?;
+    let external_port = parse_nat_pmp_mapping(&response, port)?;
+    Ok(SocketAddrV4::new(external_ip, external_port))
+}
+
+/// Send the request to the NAT-PMP gateway, resending it with a doubling timeout, as per
+/// RFC 6886, until it responds.
+async fn nat_pmp_request(socket: &tokio::net::UdpSocket, request: &[u8]) -> Result<Vec<u8>> {
+    let mut timeout = NAT_PMP_INITIAL_TIMEOUT;
+    let mut buf = [0; 16];
+    for _ in 0..NAT_PMP_ATTEMPTS {
+        let _ = socket.send(request).await?;
+        if let Ok(received) = tokio::time::timeout(timeout, socket.recv(&mut buf)).await {
+            return Ok(buf[..received?].to_vec());
+        }
+        timeout *= 2;
+    }
+    Err(Error::PortMapping(
+        "No response from a NAT-PMP gateway".to_string(),
+    ))
+}
+
+/// The NAT-PMP request of a mapping of the UDP port of this host, to the same port of the
+/// router if available, for the given lifetime in seconds.
+fn nat_pmp_mapping_request(port: u16, lifetime: u32) -> [u8; 12] {
+    let mut request = [0; 12];
+    request[1] = NAT_PMP_MAP_UDP;
+    request[4..6].copy_from_slice(&port.to_be_bytes());
+    request[6..8].copy_from_slice(&port.to_be_bytes());
+    request[8..12].copy_from_slice(&lifetime.to_be_bytes());
+    request
+}
+
+/// Checks the header of a NAT-PMP response to the request of the given opcode.
+fn check_nat_pmp_response(response: &[u8], opcode: u8, len: usize) -> Result<()> {
+    if response.len() < len || response[0] != 0 || response[1] != opcode | 0x80 {
+        return Err(Error::PortMapping(format!(
+            "Invalid NAT-PMP response {response:?}"
+        )));
+    }
+    match u16::from_be_bytes([response[2], response[3]]) {
+        0 => Ok(()),
+        code => Err(Error::PortMapping(format!(
+            "NAT-PMP request refused with result code {code}"
+        ))),
+    }
+}
+
+/// Parses the external IP of the router out of the NAT-PMP response to the request of it.
+fn parse_nat_pmp_external_address(response: &[u8]) -> Result<Ipv4Addr> {
+    check_nat_pmp_response(response, 0, 12)?;
+    Ok(Ipv4Addr::new(
+        response[8],
+        response[9],
+        response[10],
+        response[11],
+    ))
+}
+
+/// Parses the external port the given port was mapped to, out of the NAT-PMP response to
+/// the request of the mapping.
+fn parse_nat_pmp_mapping(response: &[u8], port: u16) -> Result<u16> {
+    check_nat_pmp_response(response, NAT_PMP_MAP_UDP, 16)?;
+    let internal_port = u16::from_be_bytes([response[8], response[9]]);
+    if internal_port != port {
+        return Err(Error::PortMapping(format!(
+            "NAT-PMP mapped port {internal_port} instead of {port}"
+        )));
+    }
+    Ok(u16::from_be_bytes([response[10], response[11]]))
+}
+
+/// The IPv4 address of the router of the local network: the gateway of the default route on
+/// Linux, or else the first address of the /24 subnet of the host, as is the case of most
+/// home networks.
+fn default_gateway() -> Result<Ipv4Addr> {
+    if let Some(gateway) = std::fs::read_to_string("/proc/net/route")
+        .ok()
+        .and_then(|routes| parse_default_gateway(&routes))
+    {
+        return Ok(gateway);
+    }
+    let local_ip = local_ip_towards(SocketAddrV4::new(Ipv4Addr::new(1, 1, 1, 1), 53))?;
+    Ok(Ipv4Addr::from((u32::from(local_ip) & 0xFFFF_FF00) | 1))
+}
+
+/// Parses the gateway of the default route out of the routing table in `/proc/net/route`,
+/// whose addresses are in hex, in the byte order of the host.
+fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+    routes.lines().skip(1).find_map(|route| {
+        let fields: Vec<_> = route.split_whitespace().collect();
+        match fields.as_slice() {
+            [_, "00000000", gateway, ..] => u32::from_str_radix(gateway, 16)
+                .ok()
+                .filter(|gateway| *gateway != 0)
+                .map(|gateway| Ipv4Addr::from(gateway.to_ne_bytes())),
+            _ => None,
+        }
+    })
+}
+
+/// The IP of the interface this host reaches the given address through, i.e. its IP on the
+/// local network when given that of the router. No packet is sent by connecting a UDP socket.
+fn local_ip_towards(addr: SocketAddrV4) -> Result<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.connect(addr)?;
+    match socket.local_addr()?.ip() {
+        IpAddr::V4(ip) => Ok(ip),
+        IpAddr::V6(ip) => Err(Error::PortMapping(format!(
+            "The gateway {addr} is reached through the IPv6 address {ip}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nat_traversal_is_opt_in() {
+        assert_eq!(
+            NatTraversal::default(),
+            NatTraversal {
+                upnp: false,
+                autonat: false,
+                hole_punching: false,
+                relay_client: false,
+                relay_server: false,
+            }
+        );
+        let home = NatTraversal::home_network();
+        assert!(home.upnp && home.autonat && home.hole_punching && home.relay_client);
+        assert!(!home.relay_server);
+    }
+
+    #[test]
+    fn nat_pmp_messages_are_encoded_and_parsed() -> Result<()> {
+        let request = nat_pmp_mapping_request(12000, 3600);
+        assert_eq!(
+            request,
+            [0, 1, 0, 0, 0x2e, 0xe0, 0x2e, 0xe0, 0, 0, 0x0e, 0x10]
+        );
+
+        let external_address = [0, 128, 0, 0, 0, 0, 0, 1, 81, 2, 69, 160];
+        assert_eq!(
+            parse_nat_pmp_external_address(&external_address)?,
+            Ipv4Addr::new(81, 2, 69, 160)
+        );
+
+        let mapping = [
+            0, 129, 0, 0, 0, 0, 0, 1, 0x2e, 0xe0, 0x2e, 0xe1, 0, 0, 0x0e, 0x10,
+        ];
+        assert_eq!(parse_nat_pmp_mapping(&mapping, 12000)?, 12001);
+        assert!(parse_nat_pmp_mapping(&mapping, 12002).is_err());
+
+        // Result code 2: not authorized/refused.
+        let refused = [0, 129, 0, 2, 0, 0, 0, 1, 0x2e, 0xe0, 0, 0, 0, 0, 0, 0];
+        assert!(parse_nat_pmp_mapping(&refused, 12000).is_err());
+        assert!(parse_nat_pmp_external_address(&mapping).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn default_gateway_is_parsed_from_the_routing_table() {
+        let routes = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\n\
+            eth0\t0000A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\n\
+            eth0\t00000000\t0100A8C0\t0003\t0\t0\t0\t00000000\n";
+        let expected = if cfg!(target_endian = "little") {
+            Ipv4Addr::new(192, 168, 0, 1)
+        } else {
+            Ipv4Addr::new(1, 0, 168, 192)
+        };
+        assert_eq!(parse_default_gateway(routes), Some(expected));
+        assert_eq!(parse_default_gateway("Iface\tDestination\tGateway\n"), None);
+    }
+
+    #[test]
+    fn local_ip_towards_the_loopback_is_the_loopback() -> Result<()> {
+        let ip = local_ip_towards(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 1900))?;
+        assert_eq!(ip, Ipv4Addr::LOCALHOST);
+        Ok(())
+    }
+
+    #[test]
+    fn failed_renewals_are_retried_with_a_doubling_backoff() {
+        let backoffs: Vec<_> = (1..RENEWAL_ATTEMPTS).map(renewal_backoff).collect();
+        assert_eq!(
+            backoffs,
+            [10, 20, 40, 80, 160].map(|secs| Some(Duration::from_secs(secs)))
+        );
+        assert_eq!(renewal_backoff(RENEWAL_ATTEMPTS), None);
+
+        // The renewals are all retried before the lease expires.
+        let retries: Duration = backoffs.into_iter().flatten().sum();
+        assert!(retries < MAPPING_LEASE / 2);
+    }
+
+    #[test]
+    fn external_addresses_are_quic_addresses() {
+        let addr = quic_multiaddr(SocketAddrV4::new(Ipv4Addr::new(81, 2, 69, 160), 12000));
+        assert_eq!(addr.to_string(), "/ip4/81.2.69.160/udp/12000/quic-v1");
+    }
+}
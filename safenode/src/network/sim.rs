@@ -0,0 +1,538 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! A simulated network, running many nodes and clients inside one process without sockets.
+//!
+//! Each peer gets a regular `Network` handle, whose `SwarmCmd`s are served by a single scheduler
+//! instead of a `SwarmDriver`. The requests and responses sent between peers are delivered after
+//! a latency drawn from a seeded RNG, in the order of their delivery time, and the peer ids are
+//! derived from the same seed. Run with tokio's time paused, the same test then delivers the same
+//! messages in the same order on every run, and the latencies take no actual time.
+
+use super::{
+    cmd::SwarmCmd,
+    error::{Error, Result},
+    MsgResponder, Network, NetworkEvent, SwarmLocalState, CLOSE_GROUP_SIZE,
+};
+
+use crate::{
+    domain::storage::{DiskBackedRecordStore, DiskBackedRecordStoreConfig, RECORD_STORE_DIR_NAME},
+    protocol::{
+        messages::{QueryResponse, RequestEnvelope, Response},
+        storage::Chunk,
+    },
+};
+
+use libp2p::{
+    identity,
+    kad::{kbucket::Key as KBucketKey, store::RecordStore, Record, RecordKey, K_VALUE},
+    request_response::OutboundFailure,
+    PeerId,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::{BTreeMap, HashSet},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::Instant,
+};
+
+/// The delay before retrying to deliver a request to a peer whose events channel is full.
+const RETRY_DELAY: Duration = Duration::from_millis(1);
+
+/// Handle to add, kill and inspect the peers of a simulated network.
+#[derive(Clone)]
+pub(crate) struct SimNetwork {
+    sim_cmd_sender: mpsc::UnboundedSender<SimCmd>,
+}
+
+impl SimNetwork {
+    /// Starts the scheduler of a new simulated network, delivering messages after a latency
+    /// in the given range, drawn from an RNG seeded with `seed`.
+    pub(crate) fn start(seed: u64, latency: RangeInclusive<Duration>) -> Self {
+        let (sim_cmd_sender, sim_cmd_receiver) = mpsc::unbounded_channel();
+        let (response_sender, response_receiver) = mpsc::unbounded_channel();
+        let scheduler = Scheduler {
+            rng: StdRng::seed_from_u64(seed),
+            latency,
+            peers: BTreeMap::new(),
+            sim_cmd_sender: sim_cmd_sender.clone(),
+            sim_cmd_receiver,
+            response_sender,
+            response_receiver,
+            scheduled: BTreeMap::new(),
+            next_seq: 0,
+        };
+        let _handle = tokio::spawn(scheduler.run());
+        Self { sim_cmd_sender }
+    }
+
+    /// Adds a node to the network, storing its records under the `root_dir`. The returned
+    /// network and events receiver are to be handled as those of a `SwarmDriver`.
+    pub(crate) async fn add_node(
+        &self,
+        root_dir: &Path,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>)> {
+        let storage_dir = root_dir.join(RECORD_STORE_DIR_NAME);
+        std::fs::create_dir_all(&storage_dir)?;
+        let (sender, receiver) = oneshot::channel();
+        self.send(SimCmd::AddPeer {
+            storage_dir: Some(storage_dir),
+            sender,
+        })?;
+        Ok(receiver.await?)
+    }
+
+    /// Adds a client to the network, i.e. a peer which holds no records and is never among
+    /// the closest peers to an address.
+    pub(crate) async fn add_client(&self) -> Result<Network> {
+        let (sender, receiver) = oneshot::channel();
+        self.send(SimCmd::AddPeer {
+            storage_dir: None,
+            sender,
+        })?;
+        let (network, _events) = receiver.await?;
+        Ok(network)
+    }
+
+    /// Kills the peer, which no longer receives any message, as if its process had crashed.
+    pub(crate) fn kill(&self, peer: PeerId) -> Result<()> {
+        self.send(SimCmd::Kill(peer))
+    }
+
+    /// Returns the live nodes holding the record, sorted by their distance to its key.
+    pub(crate) async fn holders(&self, key: RecordKey) -> Result<Vec<PeerId>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send(SimCmd::Holders { key, sender })?;
+        Ok(receiver.await?)
+    }
+
+    fn send(&self, cmd: SimCmd) -> Result<()> {
+        self.sim_cmd_sender
+            .send(cmd)
+            .map_err(|_| Error::InternalMsgChannelDropped)
+    }
+}
+
+enum SimCmd {
+    AddPeer {
+        storage_dir: Option<PathBuf>,
+        sender: oneshot::Sender<(Network, mpsc::Receiver<NetworkEvent>)>,
+    },
+    Kill(PeerId),
+    Holders {
+        key: RecordKey,
+        sender: oneshot::Sender<Vec<PeerId>>,
+    },
+    Swarm(PeerId, SwarmCmd),
+}
+
+enum Delivery {
+    Request {
+        to: PeerId,
+        req: RequestEnvelope,
+        sender: oneshot::Sender<Result<Response>>,
+    },
+    Response {
+        sender: oneshot::Sender<Result<Response>>,
+        response: Result<Response>,
+    },
+}
+
+struct SimPeer {
+    events: mpsc::Sender<NetworkEvent>,
+    /// Only nodes hold records, clients don't.
+    store: Option<DiskBackedRecordStore>,
+    alive: bool,
+}
+
+type PendingResponse = (oneshot::Sender<Result<Response>>, Result<Response>);
+
+struct Scheduler {
+    rng: StdRng,
+    latency: RangeInclusive<Duration>,
+    peers: BTreeMap<PeerId, SimPeer>,
+    sim_cmd_sender: mpsc::UnboundedSender<SimCmd>,
+    sim_cmd_receiver: mpsc::UnboundedReceiver<SimCmd>,
+    response_sender: mpsc::UnboundedSender<PendingResponse>,
+    response_receiver: mpsc::UnboundedReceiver<PendingResponse>,
+    /// The messages to deliver, by delivery time and then by the order they were sent in.
+    scheduled: BTreeMap<(Instant, u64), Delivery>,
+    next_seq: u64,
+}
+
+impl Scheduler {
+    async fn run(mut self) {
+        loop {
+            let next_delivery = self.scheduled.keys().next().map(|(at, _)| *at);
+            tokio::select! {
+                biased;
+                Some(cmd) = self.sim_cmd_receiver.recv() => self.handle_sim_cmd(cmd),
+                Some((sender, response)) = self.response_receiver.recv() => {
+                    let delay = self.latency();
+                    self.schedule(delay, Delivery::Response { sender, response });
+                }
+                _ = tokio::time::sleep_until(next_delivery.unwrap_or_else(Instant::now)),
+                    if next_delivery.is_some() =>
+                {
+                    if let Some((_, delivery)) = self.scheduled.pop_first() {
+                        self.deliver(delivery);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_sim_cmd(&mut self, cmd: SimCmd) {
+        match cmd {
+            SimCmd::AddPeer {
+                storage_dir,
+                sender,
+            } => {
+                let peer_added = self.add_peer(storage_dir);
+                let _ = sender.send(peer_added);
+            }
+            SimCmd::Kill(peer) => {
+                if let Some(peer) = self.peers.get_mut(&peer) {
+                    peer.alive = false;
+                }
+            }
+            SimCmd::Holders { key, sender } => {
+                let holders = self
+                    .closest_nodes(&key.to_vec(), None, usize::MAX)
+                    .into_iter()
+                    .filter(|peer| {
+                        self.peers
+                            .get(peer)
+                            .and_then(|peer| peer.store.as_ref())
+                            .map_or(false, |store| store.get(&key).is_some())
+                    })
+                    .collect();
+                let _ = sender.send(holders);
+            }
+            SimCmd::Swarm(peer, cmd) => self.handle_swarm_cmd(peer, cmd),
+        }
+    }
+
+    fn add_peer(
+        &mut self,
+        storage_dir: Option<PathBuf>,
+    ) -> (Network, mpsc::Receiver<NetworkEvent>) {
+        let mut secret: [u8; 32] = self.rng.gen();
+        let secret = identity::ed25519::SecretKey::from_bytes(&mut secret)
+            .expect("32 bytes should make a valid ed25519 secret key");
+        let keypair = identity::Keypair::from(identity::ed25519::Keypair::from(secret));
+        let peer_id = keypair.public().to_peer_id();
+
+        let (event_sender, event_receiver) = mpsc::channel(100);
+        let is_node = storage_dir.is_some();
+        let store = storage_dir.map(|storage_dir| {
+            let config = DiskBackedRecordStoreConfig {
+                max_value_bytes: 1024 * 1024,
+                storage_dir,
+                ..Default::default()
+            };
+            DiskBackedRecordStore::with_config(peer_id, config)
+        });
+
+        // Nodes learn about each other as soon as they join.
+        if is_node {
+            let nodes = self.closest_nodes(&peer_id.to_bytes(), None, usize::MAX);
+            for node in &nodes {
+                if let Some(peer) = self.peers.get(node) {
+                    let _ = peer.events.try_send(NetworkEvent::PeerAdded(peer_id));
+                }
+            }
+            if let Some(closest) = nodes.first() {
+                let _ = event_sender.try_send(NetworkEvent::PeerAdded(*closest));
+            }
+        }
+        let _ = self.peers.insert(
+            peer_id,
+            SimPeer {
+                events: event_sender,
+                store,
+                alive: true,
+            },
+        );
+
+        // The cmds of the peer are tagged with its id, to be handled by the scheduler.
+        let (swarm_cmd_sender, mut swarm_cmd_receiver) = mpsc::channel(100);
+        let sim_cmd_sender = self.sim_cmd_sender.clone();
+        let _handle = tokio::spawn(async move {
+            while let Some(cmd) = swarm_cmd_receiver.recv().await {
+                if sim_cmd_sender.send(SimCmd::Swarm(peer_id, cmd)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let network = Network {
+            swarm_cmd_sender,
+            peer_id,
+            bandwidth: None,
+        };
+        (network, event_receiver)
+    }
+
+    fn handle_swarm_cmd(&mut self, from: PeerId, cmd: SwarmCmd) {
+        match cmd {
+            SwarmCmd::SendRequest { req, peer, sender } => {
+                if peer == from {
+                    // As with the `SwarmDriver`, requests to self are handled right away.
+                    self.deliver(Delivery::Request {
+                        to: peer,
+                        req,
+                        sender,
+                    });
+                } else {
+                    let delay = self.latency();
+                    self.schedule(
+                        delay,
+                        Delivery::Request {
+                            to: peer,
+                            req,
+                            sender,
+                        },
+                    );
+                }
+            }
+            SwarmCmd::SendResponse { resp, channel } => match channel {
+                MsgResponder::FromSelf(channel) => {
+                    let _ = channel.send(Ok(resp));
+                }
+                MsgResponder::FromPeer(_) => {
+                    warn!("Responses to peers are never expected in a simulated network");
+                }
+            },
+            SwarmCmd::GetClosestPeers { key, sender } => {
+                let closest = self.closest_nodes(&key.as_bytes(), Some(from), K_VALUE.get());
+                let _ = sender.send(closest.into_iter().collect::<HashSet<_>>());
+            }
+            SwarmCmd::GetPeersAddresses { peers, sender } => {
+                let _ = sender.send(peers.into_iter().map(|peer| (peer, vec![])).collect());
+            }
+            SwarmCmd::PutProvidedDataAsRecord { record } => self.put_record(from, record),
+            SwarmCmd::GetData { key, sender } => {
+                let record = self
+                    .closest_nodes(&key.to_vec(), None, usize::MAX)
+                    .into_iter()
+                    .find_map(|peer| {
+                        self.peers
+                            .get(&peer)
+                            .and_then(|state| state.store.as_ref())
+                            .and_then(|store| store.get(&key))
+                            .map(|record| (record.value.clone(), peer))
+                    });
+                let _ = sender.send(match record {
+                    Some((value, holder)) => Ok((
+                        QueryResponse::GetChunk(Ok(Chunk::new(value.into()))),
+                        Some(holder).filter(|holder| *holder != from),
+                    )),
+                    None => Err(Error::RecordNotFound),
+                });
+            }
+            SwarmCmd::GetLocalStoreCost { sender } => {
+                if let Some(store) = self.peers.get(&from).and_then(|peer| peer.store.as_ref()) {
+                    let _ = sender.send(store.store_cost());
+                }
+            }
+            SwarmCmd::StartListening { sender, .. }
+            | SwarmCmd::Dial { sender, .. }
+            | SwarmCmd::AddToRoutingTable { sender, .. } => {
+                let _ = sender.send(Ok(()));
+            }
+            // The simulated peers are all reachable, there's no NAT to traverse.
+            SwarmCmd::PortMapped(_) | SwarmCmd::PortUnmapped(_) => {}
+            SwarmCmd::GetSwarmLocalState(sender) => {
+                let connected_peers: Vec<_> = self
+                    .peers
+                    .iter()
+                    .filter(|(peer, state)| **peer != from && state.alive)
+                    .map(|(peer, _)| *peer)
+                    .collect();
+                let _ = sender.send(SwarmLocalState {
+                    routing_table_size: connected_peers.len(),
+                    connected_peers,
+                    listeners: vec![],
+                    kad_queries: Default::default(),
+                });
+            }
+            #[cfg(feature = "chaos")]
+            SwarmCmd::SetChaos(_) => {
+                warn!("Faults are not injected into a simulated network");
+            }
+        }
+    }
+
+    fn deliver(&mut self, delivery: Delivery) {
+        match delivery {
+            Delivery::Request { to, req, sender } => {
+                let events = match self.peers.get(&to) {
+                    Some(peer) if peer.alive => peer.events.clone(),
+                    _ => {
+                        let response = Err(Error::OutboundError(OutboundFailure::DialFailure));
+                        let delay = self.latency();
+                        self.schedule(delay, Delivery::Response { sender, response });
+                        return;
+                    }
+                };
+
+                // The response is relayed through the scheduler, to be delivered after a latency.
+                let (response_sender, response_receiver) = oneshot::channel();
+                let event = NetworkEvent::RequestReceived {
+                    req: req.request,
+                    correlation_id: req.correlation_id,
+                    channel: MsgResponder::FromSelf(response_sender),
+                };
+                match events.try_send(event) {
+                    Ok(()) => {
+                        let responses = self.response_sender.clone();
+                        let _handle = tokio::spawn(async move {
+                            let response = response_receiver
+                                .await
+                                .unwrap_or(Err(Error::InternalMsgChannelDropped));
+                            let _ = responses.send((sender, response));
+                        });
+                    }
+                    Err(mpsc::error::TrySendError::Full(NetworkEvent::RequestReceived {
+                        req,
+                        correlation_id,
+                        ..
+                    })) => {
+                        let req = RequestEnvelope {
+                            correlation_id,
+                            request: req,
+                        };
+                        self.schedule(RETRY_DELAY, Delivery::Request { to, req, sender });
+                    }
+                    Err(_) => {
+                        let _ = sender
+                            .send(Err(Error::OutboundError(OutboundFailure::ConnectionClosed)));
+                    }
+                }
+            }
+            Delivery::Response { sender, response } => {
+                let _ = sender.send(response);
+            }
+        }
+    }
+
+    /// Stores the record on the closest nodes to its key, as well as on the node putting it,
+    /// as Kademlia does.
+    fn put_record(&mut self, from: PeerId, record: Record) {
+        let mut holders = self.closest_nodes(&record.key.to_vec(), None, CLOSE_GROUP_SIZE);
+        holders.push(from);
+        for holder in holders {
+            let store = self
+                .peers
+                .get_mut(&holder)
+                .filter(|peer| peer.alive)
+                .and_then(|peer| peer.store.as_mut());
+            if let Some(store) = store {
+                if let Err(err) = store.put(record.clone()) {
+                    warn!("Failed to store record {:?} on {holder}: {err}", record.key);
+                }
+            }
+        }
+    }
+
+    /// Returns up to `count` live nodes, sorted by their distance to the key.
+    fn closest_nodes(&self, key: &[u8], exclude: Option<PeerId>, count: usize) -> Vec<PeerId> {
+        let key = KBucketKey::new(key.to_vec());
+        let mut nodes: Vec<_> = self
+            .peers
+            .iter()
+            .filter(|(peer, state)| Some(**peer) != exclude && state.alive && state.store.is_some())
+            .map(|(peer, _)| *peer)
+            .collect();
+        nodes.sort_by_key(|peer| key.distance(&KBucketKey::from(*peer)));
+        nodes.truncate(count);
+        nodes
+    }
+
+    fn latency(&mut self) -> Duration {
+        self.rng.gen_range(self.latency.clone())
+    }
+
+    fn schedule(&mut self, delay: Duration, delivery: Delivery) {
+        let _ = self
+            .scheduled
+            .insert((Instant::now() + delay, self.next_seq), delivery);
+        self.next_seq += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        node::Node,
+        protocol::messages::{Cmd, CmdResponse, Query, Request},
+    };
+
+    use assert_fs::TempDir;
+    use bytes::Bytes;
+    use eyre::Result;
+
+    const NODE_COUNT: usize = 20;
+
+    // Stores a chunk on a simulated network, returning the nodes which ended up holding it.
+    async fn store_chunk(seed: u64, chunk: &Chunk) -> Result<Vec<PeerId>> {
+        let sim = SimNetwork::start(seed, Duration::from_millis(10)..=Duration::from_millis(200));
+        let root_dir = TempDir::new()?;
+        for i in 0..NODE_COUNT {
+            let node_dir = root_dir.path().join(format!("node-{i}"));
+            let (network, events) = sim.add_node(&node_dir).await?;
+            let _node = Node::start(network, events, vec![], &node_dir);
+        }
+        let client = sim.add_client().await?;
+
+        let responses = client
+            .client_send_to_closest(&Request::Cmd(Cmd::StoreChunk(chunk.clone())))
+            .await?;
+        assert_eq!(responses.len(), CLOSE_GROUP_SIZE);
+        for response in responses {
+            assert_eq!(response?, Response::Cmd(CmdResponse::StoreChunk(Ok(()))));
+        }
+
+        let holders = sim.holders(RecordKey::new(chunk.name())).await?;
+        assert_eq!(holders.len(), CLOSE_GROUP_SIZE);
+
+        // The chunk can still be retrieved once its closest holder is gone.
+        sim.kill(holders[0])?;
+        let response = client
+            .send_request(
+                Request::Query(Query::GetChunk(*chunk.address())),
+                holders[1],
+            )
+            .await?;
+        assert_eq!(
+            response,
+            Response::Query(QueryResponse::GetChunk(Ok(chunk.clone())))
+        );
+
+        Ok(holders)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn chunks_are_replicated_to_the_same_close_group_for_a_seed() -> Result<()> {
+        let chunk = Chunk::new(Bytes::from_static(b"simulated chunk"));
+
+        let holders = store_chunk(7, &chunk).await?;
+        assert_eq!(store_chunk(7, &chunk).await?, holders);
+        assert_ne!(store_chunk(8, &chunk).await?, holders);
+
+        Ok(())
+    }
+}
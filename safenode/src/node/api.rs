@@ -9,18 +9,22 @@
 use super::{
     error::{Error, Result},
     event::NodeEventsChannel,
+    metrics::{NodeMetrics, NodeState},
     Network, Node, NodeEvent,
 };
 
 use crate::{
     domain::dbc_genesis::is_genesis_parent_tx,
-    network::{close_group_majority, MsgResponder, NetworkEvent, SwarmDriver, SwarmLocalState},
+    network::{
+        close_group_majority, MsgResponder, NatTraversal, NetworkEvent, SwarmDriver,
+        SwarmLocalState,
+    },
     node::{RegisterStorage, Transfers},
     protocol::{
         error::{Error as ProtocolError, StorageError, TransferError},
         messages::{
-            Cmd, CmdResponse, Event, Query, QueryResponse, RegisterCmd, Request, Response,
-            SpendQuery,
+            Cmd, CmdResponse, CorrelationId, Event, Query, QueryResponse, RegisterCmd, Request,
+            Response, SpendQuery,
         },
         storage::{registers::User, DbcAddress},
         NetworkAddress,
@@ -33,8 +37,18 @@ use libp2p::{
     kad::{Record, RecordKey},
     Multiaddr, PeerId,
 };
-use std::{collections::BTreeSet, net::SocketAddr, path::Path};
-use tokio::{sync::mpsc, task::spawn};
+use std::{
+    collections::BTreeSet,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Instant,
+};
+use tokio::{
+    sync::mpsc,
+    task::{spawn, JoinHandle},
+};
+use tracing::{field, Instrument, Span};
 
 #[derive(Debug)]
 pub(super) struct TransferAction {
@@ -42,13 +56,33 @@ pub(super) struct TransferAction {
     parent_tx: Box<DbcTransaction>,
     parent_spends: BTreeSet<SignedSpend>,
     response_channel: MsgResponder,
+    started: Instant,
+    /// The client operation and span of the request the spend was sent with.
+    correlation_id: Option<CorrelationId>,
+    span: Span,
 }
 
 /// Once a node is started and running, the user obtains
 /// a `NodeRunning` object which can be used to interact with it.
+/// The state of the storage of a node, as returned by [`RunningNode::storage_info`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StorageInfo {
+    /// The number of records held by the node.
+    pub records: u64,
+    /// The size of the records held by the node on disk, in bytes.
+    pub records_bytes: u64,
+    /// The space available on the disk of the root dir of the node, in bytes,
+    /// if it could be measured.
+    pub available_bytes: Option<u64>,
+}
+
+#[derive(Clone)]
 pub struct RunningNode {
     network: Network,
     node_events_channel: NodeEventsChannel,
+    metrics: NodeMetrics,
+    /// The long-running tasks driving the node, aborted when it's shut down.
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
 }
 
 impl RunningNode {
@@ -67,13 +101,75 @@ impl RunningNode {
     pub fn node_events_channel(&self) -> &NodeEventsChannel {
         &self.node_events_channel
     }
+
+    /// Returns the metrics of this node in the Prometheus text format, e.g. its peers,
+    /// the records it holds, the requests it handled and the bandwidth it used.
+    pub async fn metrics(&self) -> Result<String> {
+        let state = self.node_state().await?;
+        Ok(self.metrics.render(state).await)
+    }
+
+    /// Logs a one-line summary of the status of this node at info level, i.e. its peers,
+    /// the records it holds, its memory usage, uptime and reward balance.
+    pub async fn log_status(&self) -> Result<()> {
+        let state = self.node_state().await?;
+        info!("{}", self.metrics.status(state).await);
+        Ok(())
+    }
+
+    /// Returns the number of records this node holds, their size on disk, and the space
+    /// left on the disk of its root dir.
+    pub async fn storage_info(&self) -> StorageInfo {
+        let (records, records_bytes, available_bytes) = self.metrics.storage().await;
+        StorageInfo {
+            records,
+            records_bytes,
+            available_bytes,
+        }
+    }
+
+    /// Stops the node, aborting the tasks driving it, and waits for them to have stopped, thus
+    /// for the port it listened on to be released, e.g. for it to be restarted in-process.
+    /// Any clone of this `RunningNode` is unusable afterwards.
+    pub async fn shutdown(self) {
+        let tasks = std::mem::take(&mut *self.lock_tasks());
+        for task in &tasks {
+            task.abort();
+        }
+        for task in tasks {
+            // Every task has been aborted, the error only tells as much.
+            let _ = task.await;
+        }
+        info!("The node {} has been shut down", self.network.peer_id);
+    }
+
+    fn push_task(&self, task: JoinHandle<()>) {
+        self.lock_tasks().push(task);
+    }
+
+    fn lock_tasks(&self) -> MutexGuard<'_, Vec<JoinHandle<()>>> {
+        self.tasks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    async fn node_state(&self) -> Result<NodeState> {
+        let state = self.network.get_swarm_local_state().await?;
+        let (inbound_bytes, outbound_bytes) = self.network.bandwidth();
+        Ok(NodeState {
+            connected_peers: state.connected_peers.len(),
+            routing_table_size: state.routing_table_size,
+            kad_queries: state.kad_queries,
+            inbound_bytes,
+            outbound_bytes,
+        })
+    }
 }
 
 impl Node {
     /// Asynchronously runs a new node instance, setting up the swarm driver,
     /// creating a data storage, and handling network events. Returns the
     /// created node and a `NodeEventsChannel` for listening to node-related
-    /// events.
+    /// events. The node uses the enabled NAT traversal techniques to be
+    /// reachable from behind a NAT.
     ///
     /// # Returns
     ///
@@ -86,11 +182,28 @@ impl Node {
         addr: SocketAddr,
         initial_peers: Vec<(PeerId, Multiaddr)>,
         root_dir: &Path,
+        nat: NatTraversal,
     ) -> Result<RunningNode> {
-        let (network, mut network_event_receiver, swarm_driver) = SwarmDriver::new(addr, root_dir)?;
+        let (network, network_event_receiver, swarm_driver) =
+            SwarmDriver::new(addr, root_dir, nat)?;
+        let swarm_driver_handle = spawn(swarm_driver.run());
+
+        let running_node = Self::start(network, network_event_receiver, initial_peers, root_dir);
+        running_node.push_task(swarm_driver_handle);
+        Ok(running_node)
+    }
+
+    /// Starts handling the events of the given network, e.g. that of a simulated network.
+    pub(crate) fn start(
+        network: Network,
+        mut network_event_receiver: mpsc::Receiver<NetworkEvent>,
+        initial_peers: Vec<(PeerId, Multiaddr)>,
+        root_dir: &Path,
+    ) -> RunningNode {
         let node_events_channel = NodeEventsChannel::default();
 
         let (transfer_action_sender, mut transfer_action_receiver) = mpsc::channel(100);
+        let metrics = NodeMetrics::new(root_dir);
 
         let mut node = Self {
             network: network.clone(),
@@ -99,10 +212,11 @@ impl Node {
             events_channel: node_events_channel.clone(),
             initial_peers,
             transfer_actor: transfer_action_sender,
+            metrics: metrics.clone(),
         };
 
-        let _handle = spawn(swarm_driver.run());
-        let _handle = spawn(async move {
+        let throughput_handle = spawn(metrics.clone().log_throughput(network.clone()));
+        let events_handle = spawn(async move {
             loop {
                 tokio::select! {
                     net_event = network_event_receiver.recv() => {
@@ -113,7 +227,10 @@ impl Node {
                     }
                     transfer_action = transfer_action_receiver.recv() => {
                         match transfer_action {
-                            Some(action) => node.handle_transfer_action(action).await,
+                            Some(action) => {
+                                let span = action.span.clone();
+                                node.handle_transfer_action(action).instrument(span).await
+                            }
                             None => error!("The `TransferAction` channel is closed")
                         }
                     }
@@ -121,21 +238,35 @@ impl Node {
             }
         });
 
-        Ok(RunningNode {
+        RunningNode {
             network,
             node_events_channel,
-        })
+            metrics,
+            tasks: Arc::new(Mutex::new(vec![throughput_handle, events_handle])),
+        }
     }
 
     // **** Private helpers *****
 
     async fn handle_network_event(&mut self, event: NetworkEvent) {
         match event {
-            NetworkEvent::RequestReceived { req, channel } => {
-                self.handle_request(req, channel).await
+            NetworkEvent::RequestReceived {
+                req,
+                correlation_id,
+                channel,
+            } => {
+                // The requests the node sends while handling this one are part of the same
+                // client operation, and its logs are correlated with those of the client.
+                let span = info_span!(
+                    "Handling request",
+                    correlation_id = correlation_id.map(field::display)
+                );
+                let handling = self.handle_request(req, channel).instrument(span);
+                CorrelationId::scope(correlation_id, handling).await
             }
             NetworkEvent::PeerAdded(peer) => {
                 self.events_channel.broadcast(NodeEvent::ConnectedToNetwork);
+                self.events_channel.broadcast(NodeEvent::PeerAdded(peer));
                 let key = NetworkAddress::from_peer(peer);
                 let network = self.network.clone();
                 let _handle = spawn(async move {
@@ -155,31 +286,49 @@ impl Node {
                     }
                 });
             }
+            NetworkEvent::PortMapped(addr) => {
+                self.events_channel.broadcast(NodeEvent::PortMapped(addr));
+            }
+            NetworkEvent::NatStatusChanged(status) => {
+                self.events_channel.broadcast(NodeEvent::NatStatus(status));
+            }
+            NetworkEvent::DirectConnectionUpgraded(peer) => {
+                self.events_channel
+                    .broadcast(NodeEvent::DirectConnectionUpgraded(peer));
+            }
         }
     }
 
     async fn handle_request(&mut self, request: Request, response_channel: MsgResponder) {
         trace!("Handling request: {request:?}");
+        let started = Instant::now();
         match request {
-            Request::Cmd(cmd) => self.handle_cmd(cmd, response_channel).await,
-            Request::Query(query) => self.handle_query(query, response_channel).await,
+            Request::Cmd(cmd) => self.handle_cmd(cmd, response_channel, started).await,
+            Request::Query(query) => self.handle_query(query, response_channel, started).await,
             Request::Event(event) => {
-                let result = match event {
+                let (kind, event_kind, result) = match event {
                     Event::ValidSpendReceived {
                         spend,
                         parent_tx,
                         parent_spends,
-                    } => {
+                    } => (
+                        "valid_spend",
+                        "valid_spend_event",
                         self.transfers
                             .try_add(spend, parent_tx, parent_spends)
-                            .await
-                    }
-                    Event::DoubleSpendAttempted { new, existing } => {
+                            .await,
+                    ),
+                    Event::DoubleSpendAttempted { new, existing } => (
+                        "double_spend",
+                        "double_spend_event",
                         self.transfers
                             .try_add_double(new.as_ref(), existing.as_ref())
-                            .await
-                    }
+                            .await,
+                    ),
                 };
+                self.metrics.record_replication_event(kind);
+                self.metrics
+                    .record_request(event_kind, result.is_err(), started);
 
                 if let Err(err) = result {
                     warn!("Error handling network request event: {err}");
@@ -188,7 +337,12 @@ impl Node {
         }
     }
 
-    async fn handle_query(&mut self, query: Query, response_channel: MsgResponder) {
+    async fn handle_query(
+        &mut self,
+        query: Query,
+        response_channel: MsgResponder,
+        started: Instant,
+    ) {
         let resp = match query {
             Query::Register(query) => self.registers.read(&query, User::Anyone).await,
             Query::GetChunk(address) => {
@@ -204,6 +358,14 @@ impl Node {
                     }
                 }
             }
+            Query::GetStoreCost(address) => {
+                trace!("Getting store cost for {address:?}");
+                let result = self.network.get_local_store_cost().await.map_err(|err| {
+                    error!("Error getting local store cost: {err}");
+                    ProtocolError::Storage(StorageError::StoreCostUnavailable(err.to_string()))
+                });
+                QueryResponse::GetStoreCost(result)
+            }
             Query::Spend(query) => match query {
                 SpendQuery::GetDbcSpend(address) => {
                     let res = self
@@ -216,11 +378,11 @@ impl Node {
                 }
             },
         };
-        self.send_response(Response::Query(resp), response_channel)
+        self.send_response(Response::Query(resp), response_channel, started)
             .await;
     }
 
-    async fn handle_cmd(&mut self, cmd: Cmd, response_channel: MsgResponder) {
+    async fn handle_cmd(&mut self, cmd: Cmd, response_channel: MsgResponder, started: Instant) {
         match cmd {
             Cmd::StoreChunk(chunk) => {
                 let addr = *chunk.address();
@@ -246,7 +408,7 @@ impl Node {
                         ))
                     }
                 };
-                self.send_response(Response::Cmd(resp), response_channel)
+                self.send_response(Response::Cmd(resp), response_channel, started)
                     .await;
             }
             Cmd::Register(cmd) => {
@@ -268,8 +430,13 @@ impl Node {
                             .broadcast(NodeEvent::RegisterEdited(xorname));
                         CmdResponse::EditRegister(result)
                     }
+                    RegisterCmd::TransferOwnership(_) => {
+                        self.events_channel
+                            .broadcast(NodeEvent::RegisterOwnershipTransferred(xorname));
+                        CmdResponse::TransferRegisterOwnership(result)
+                    }
                 };
-                self.send_response(Response::Cmd(resp), response_channel)
+                self.send_response(Response::Cmd(resp), response_channel, started)
                     .await;
             }
             Cmd::SpendDbc {
@@ -278,17 +445,23 @@ impl Node {
             } => {
                 let network = self.network.clone();
                 let transfer_actor = self.transfer_actor.clone();
-
-                let _handler = spawn(async move {
-                    handle_spend_dbc(
-                        network,
-                        transfer_actor,
-                        response_channel,
-                        signed_spend,
-                        parent_tx,
-                    )
-                    .await
-                });
+                let metrics = self.metrics.clone();
+
+                let _handler = spawn(CorrelationId::propagate(
+                    async move {
+                        handle_spend_dbc(
+                            network,
+                            transfer_actor,
+                            metrics,
+                            response_channel,
+                            signed_spend,
+                            parent_tx,
+                            started,
+                        )
+                        .await
+                    }
+                    .in_current_span(),
+                ));
             }
         }
     }
@@ -299,6 +472,9 @@ impl Node {
             parent_tx,
             parent_spends,
             response_channel,
+            started,
+            correlation_id,
+            span: _,
         } = action;
 
         let result = self
@@ -312,8 +488,9 @@ impl Node {
 
         let network = self.network.clone();
         let events_channel = self.events_channel.clone();
+        let metrics = self.metrics.clone();
 
-        let _handler = spawn(async move {
+        let handling = async move {
             let resp = match result {
                 Ok(()) => {
                     let dbc_id = *signed_spend.dbc_id();
@@ -358,16 +535,25 @@ impl Node {
                 other => other.map_err(ProtocolError::Transfers),
             };
 
-            if let Err(err) = network
-                .send_response(Response::Cmd(CmdResponse::Spend(resp)), response_channel)
-                .await
-            {
+            let resp = Response::Cmd(CmdResponse::Spend(resp));
+            metrics.record_response(&resp, started);
+            if let Err(err) = network.send_response(resp, response_channel).await {
                 warn!("Error while sending response: {err:?}");
             }
-        });
+        };
+        let _handler = spawn(CorrelationId::scope(
+            correlation_id,
+            handling.in_current_span(),
+        ));
     }
 
-    async fn send_response(&self, resp: Response, response_channel: MsgResponder) {
+    async fn send_response(
+        &self,
+        resp: Response,
+        response_channel: MsgResponder,
+        started: Instant,
+    ) {
+        self.metrics.record_response(&resp, started);
         if let Err(err) = self.network.send_response(resp, response_channel).await {
             warn!("Error while sending response: {err:?}");
         }
@@ -398,9 +584,11 @@ fn double_spend_attempt(new: Box<SignedSpend>, existing: Box<SignedSpend>) -> Op
 async fn handle_spend_dbc(
     network: Network,
     transfer_actor: mpsc::Sender<TransferAction>,
+    metrics: NodeMetrics,
     response_channel: MsgResponder,
     signed_spend: Box<SignedSpend>,
     parent_tx: Box<DbcTransaction>,
+    started: Instant,
 ) {
     // First we fetch all parent spends from the network.
     // They shall naturally all exist as valid spends for this current
@@ -417,10 +605,9 @@ async fn handle_spend_dbc(
                 )))
             };
 
-            if let Err(err) = network
-                .send_response(Response::Cmd(resp), response_channel)
-                .await
-            {
+            let resp = Response::Cmd(resp);
+            metrics.record_response(&resp, started);
+            if let Err(err) = network.send_response(resp, response_channel).await {
                 warn!("Error while sending response: {err:?}");
             }
             return;
@@ -434,6 +621,9 @@ async fn handle_spend_dbc(
         parent_tx,
         parent_spends,
         response_channel,
+        started,
+        correlation_id: CorrelationId::current(),
+        span: Span::current(),
     };
 
     // Then we try to add the spend to the transfers.
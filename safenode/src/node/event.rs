@@ -6,8 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::protocol::storage::{ChunkAddress, RegisterAddress};
+use crate::{
+    network::NatStatus,
+    protocol::storage::{ChunkAddress, RegisterAddress},
+};
 
+use libp2p::{Multiaddr, PeerId};
 use sn_dbc::DbcId;
 use tokio::sync::broadcast;
 
@@ -44,12 +48,23 @@ impl NodeEventsChannel {
 pub enum NodeEvent {
     /// The node has been connected to the network
     ConnectedToNetwork,
+    /// A peer has been added to the routing table of the node
+    PeerAdded(PeerId),
+    /// The port of the node was mapped in the router of its local network, the node being
+    /// reachable by peers outside of it at the given address
+    PortMapped(Multiaddr),
+    /// Whether the node is publicly reachable changed, as learnt by asking peers to dial it back
+    NatStatus(NatStatus),
+    /// The connection to a peer, relayed until then, was upgraded to a direct one by hole punching
+    DirectConnectionUpgraded(PeerId),
     /// A Chunk has been stored in local storage
     ChunkStored(ChunkAddress),
     /// A Register has been created in local storage
     RegisterCreated(RegisterAddress),
     /// A Register edit operation has been applied in local storage
     RegisterEdited(RegisterAddress),
+    /// A Register ownership transfer has been applied in local storage
+    RegisterOwnershipTransferred(RegisterAddress),
     /// A DBC Spend has been stored in local storage
     SpendStored(DbcId),
 }
@@ -0,0 +1,672 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    domain::{
+        storage::{
+            DOUBLE_SPENDS_STORE_DIR_NAME, RECORD_STORE_DIR_NAME, REGISTERS_STORE_DIR_NAME,
+            VALID_SPENDS_STORE_DIR_NAME,
+        },
+        wallet::{LocalWallet, Wallet},
+    },
+    network::{KadQueryStats, Network},
+    protocol::messages::{CmdResponse, QueryResponse, Response},
+};
+
+use sn_dbc::Token;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+/// The interval at which a summary of the throughput of a node is logged.
+const THROUGHPUT_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The types of the records held by a node, along with the dir within its root dir they're stored in.
+const RECORD_DIRS: [(&str, &str); 4] = [
+    ("chunk", RECORD_STORE_DIR_NAME),
+    ("register", REGISTERS_STORE_DIR_NAME),
+    ("spend", VALID_SPENDS_STORE_DIR_NAME),
+    ("double_spend", DOUBLE_SPENDS_STORE_DIR_NAME),
+];
+
+/// The counts and durations of the requests of a kind handled by a node.
+#[derive(Clone, Copy, Default)]
+struct RequestStats {
+    handled: u64,
+    errors: u64,
+    duration: Duration,
+}
+
+/// The metrics of a running node, rendered in the Prometheus text format, for operators to
+/// scrape and plug into their dashboards. The requests handled are counted as they're responded
+/// to, while the state of the node, e.g. its peers and the records it holds, is read when rendered.
+///
+/// With the `otlp` feature, the key counters are also recorded with OpenTelemetry instruments,
+/// exported over the OTLP pipeline set up by the logging.
+#[derive(Clone)]
+pub(super) struct NodeMetrics {
+    root_dir: PathBuf,
+    started: Instant,
+    requests: Arc<Mutex<BTreeMap<&'static str, RequestStats>>>,
+    /// The number of cmds handled, i.e. data stored on the node.
+    puts: Arc<AtomicU64>,
+    /// The number of queries handled, i.e. data got from the node.
+    gets: Arc<AtomicU64>,
+    replication_events: Arc<Mutex<BTreeMap<&'static str, u64>>>,
+    #[cfg(feature = "otlp")]
+    otlp: otlp::Instruments,
+}
+
+/// The state of the node read when the metrics are rendered.
+pub(super) struct NodeState {
+    pub(super) connected_peers: usize,
+    pub(super) routing_table_size: usize,
+    pub(super) kad_queries: BTreeMap<&'static str, KadQueryStats>,
+    pub(super) inbound_bytes: u64,
+    pub(super) outbound_bytes: u64,
+}
+
+impl NodeMetrics {
+    pub(super) fn new(root_dir: &Path) -> Self {
+        Self {
+            root_dir: root_dir.to_path_buf(),
+            started: Instant::now(),
+            requests: Arc::default(),
+            puts: Arc::default(),
+            gets: Arc::default(),
+            replication_events: Arc::default(),
+            #[cfg(feature = "otlp")]
+            otlp: otlp::Instruments::new(root_dir),
+        }
+    }
+
+    /// Records a request of the given kind, handled since `started`.
+    pub(super) fn record_request(&self, kind: &'static str, failed: bool, started: Instant) {
+        let mut requests = self.requests.lock().unwrap_or_else(PoisonError::into_inner);
+        let stats = requests.entry(kind).or_default();
+        let duration = started.elapsed();
+        stats.handled += 1;
+        stats.duration += duration;
+        if failed {
+            stats.errors += 1;
+        }
+        #[cfg(feature = "otlp")]
+        self.otlp.record_request(kind, failed, duration);
+    }
+
+    /// Records data of the given kind being replicated to the node by its peers.
+    pub(super) fn record_replication_event(&self, kind: &'static str) {
+        *self
+            .replication_events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(kind)
+            .or_default() += 1;
+        #[cfg(feature = "otlp")]
+        self.otlp.record_replication_event(kind);
+    }
+
+    /// Records the request answered with the response, handled since `started`.
+    pub(super) fn record_response(&self, response: &Response, started: Instant) {
+        let (kind, failed) = response_kind(response);
+        self.record_request(kind, failed, started);
+        let ops = match response {
+            Response::Cmd(_) => &self.puts,
+            Response::Query(_) => &self.gets,
+        };
+        let _ = ops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Logs a summary of the throughput of the node every `THROUGHPUT_LOG_INTERVAL`, i.e. the
+    /// puts and gets it handled per second, the latency of its Kademlia queries, and the size
+    /// of its routing table.
+    pub(super) async fn log_throughput(self, network: Network) {
+        let mut interval = tokio::time::interval(THROUGHPUT_LOG_INTERVAL);
+        // The first tick completes immediately.
+        let _ = interval.tick().await;
+        let mut previous = (self.puts(), self.gets(), BTreeMap::new(), Instant::now());
+        loop {
+            let _ = interval.tick().await;
+            let state = match network.get_swarm_local_state().await {
+                Ok(state) => state,
+                Err(err) => {
+                    warn!("Failed to get the state of the swarm to log the throughput: {err}");
+                    continue;
+                }
+            };
+            let (puts, gets, now) = (self.puts(), self.gets(), Instant::now());
+            let (previous_puts, previous_gets, previous_queries, since) = &previous;
+            let secs = now.duration_since(*since).as_secs_f64();
+            let query_latencies = state
+                .kad_queries
+                .iter()
+                .map(|(kind, stats)| {
+                    let previous = previous_queries.get(kind).copied().unwrap_or_default();
+                    let completed = stats.completed - previous.completed;
+                    let latency = (stats.duration - previous.duration)
+                        .checked_div(completed as u32)
+                        .unwrap_or_default();
+                    format!("{kind}: {completed} in {latency:?} on average")
+                })
+                .collect::<Vec<_>>();
+            info!(
+                "Throughput over the last {secs:.0}s: {:.2} puts/s, {:.2} gets/s, \
+                {} peers in the routing table, {} connected peers, Kademlia queries [{}]",
+                (puts - previous_puts) as f64 / secs,
+                (gets - previous_gets) as f64 / secs,
+                state.routing_table_size,
+                state.connected_peers.len(),
+                query_latencies.join(", ")
+            );
+            previous = (puts, gets, state.kad_queries, now);
+        }
+    }
+
+    fn puts(&self) -> u64 {
+        self.puts.load(Ordering::Relaxed)
+    }
+
+    fn gets(&self) -> u64 {
+        self.gets.load(Ordering::Relaxed)
+    }
+
+    /// Returns a one-line summary of the status of the node, i.e. its peers, the records it holds
+    /// and their size on disk, its memory usage, uptime and reward balance.
+    pub(super) async fn status(&self, state: NodeState) -> String {
+        let (records, bytes) = self
+            .records_held()
+            .await
+            .iter()
+            .fold((0, 0), |(records, bytes), (_, (files, size))| {
+                (records + files, bytes + size)
+            });
+        let memory = memory_usage().map_or_else(|| "unknown".to_string(), format_bytes);
+        let uptime = self.started.elapsed().as_secs();
+        let mut status = format!(
+            "Status: {} connected peers, {} in the routing table, {records} records held \
+            ({} on disk), {memory} of memory used, up for {}h{:02}m",
+            state.connected_peers,
+            state.routing_table_size,
+            format_bytes(bytes),
+            uptime / 3600,
+            uptime % 3600 / 60,
+        );
+        if let Some(balance) = self.reward_balance().await {
+            let _ = write!(status, ", reward balance of {}", Token::from_nano(balance));
+        }
+        status
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub(super) async fn render(&self, state: NodeState) -> String {
+        let records = self.records_held().await;
+        let requests = self
+            .requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let replication_events = self
+            .replication_events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        let mut out = String::new();
+        write_metric(
+            &mut out,
+            "safenode_uptime_seconds",
+            "The number of seconds since the node was started.",
+            "gauge",
+            &[(String::new(), self.started.elapsed().as_secs_f64())],
+        );
+        write_metric(
+            &mut out,
+            "safenode_connected_peers",
+            "The number of peers the node is connected to.",
+            "gauge",
+            &[(String::new(), state.connected_peers as f64)],
+        );
+        write_metric(
+            &mut out,
+            "safenode_routing_table_size",
+            "The number of peers in the routing table of the node.",
+            "gauge",
+            &[(String::new(), state.routing_table_size as f64)],
+        );
+        write_metric(
+            &mut out,
+            "safenode_records_held",
+            "The number of records held by the node, per type.",
+            "gauge",
+            &records
+                .iter()
+                .map(|(kind, (files, _))| (format!("type=\"{kind}\""), *files as f64))
+                .collect::<Vec<_>>(),
+        );
+        write_metric(
+            &mut out,
+            "safenode_disk_usage_bytes",
+            "The number of bytes of the records held by the node on disk, per type.",
+            "gauge",
+            &records
+                .iter()
+                .map(|(kind, (_, bytes))| (format!("type=\"{kind}\""), *bytes as f64))
+                .collect::<Vec<_>>(),
+        );
+        let by_kind = |value: fn(&RequestStats) -> f64| {
+            requests
+                .iter()
+                .map(|(kind, stats)| (format!("kind=\"{kind}\""), value(stats)))
+                .collect::<Vec<_>>()
+        };
+        write_metric(
+            &mut out,
+            "safenode_requests_total",
+            "The number of requests handled by the node, per kind.",
+            "counter",
+            &by_kind(|stats| stats.handled as f64),
+        );
+        write_metric(
+            &mut out,
+            "safenode_request_errors_total",
+            "The number of requests the node handled with an error, per kind.",
+            "counter",
+            &by_kind(|stats| stats.errors as f64),
+        );
+        write_metric(
+            &mut out,
+            "safenode_request_duration_seconds",
+            "The time the node took to handle the requests, per kind.",
+            "summary",
+            &[],
+        );
+        for (kind, stats) in &requests {
+            let _ = writeln!(
+                out,
+                "safenode_request_duration_seconds_sum{{kind=\"{kind}\"}} {}",
+                stats.duration.as_secs_f64()
+            );
+            let _ = writeln!(
+                out,
+                "safenode_request_duration_seconds_count{{kind=\"{kind}\"}} {}",
+                stats.handled
+            );
+        }
+        write_metric(
+            &mut out,
+            "safenode_puts_total",
+            "The number of cmds handled by the node, storing data on it.",
+            "counter",
+            &[(String::new(), self.puts() as f64)],
+        );
+        write_metric(
+            &mut out,
+            "safenode_gets_total",
+            "The number of queries handled by the node, getting data from it.",
+            "counter",
+            &[(String::new(), self.gets() as f64)],
+        );
+        write_metric(
+            &mut out,
+            "safenode_kad_query_duration_seconds",
+            "The time the Kademlia queries of the node took to complete, per kind.",
+            "summary",
+            &[],
+        );
+        for (kind, stats) in &state.kad_queries {
+            let _ = writeln!(
+                out,
+                "safenode_kad_query_duration_seconds_sum{{kind=\"{kind}\"}} {}",
+                stats.duration.as_secs_f64()
+            );
+            let _ = writeln!(
+                out,
+                "safenode_kad_query_duration_seconds_count{{kind=\"{kind}\"}} {}",
+                stats.completed
+            );
+        }
+        write_metric(
+            &mut out,
+            "safenode_replication_events_total",
+            "The number of times data was replicated to the node by its peers, per kind.",
+            "counter",
+            &replication_events
+                .iter()
+                .map(|(kind, count)| (format!("kind=\"{kind}\""), *count as f64))
+                .collect::<Vec<_>>(),
+        );
+        write_metric(
+            &mut out,
+            "safenode_bandwidth_bytes_total",
+            "The number of bytes the node received and sent over the network.",
+            "counter",
+            &[
+                (
+                    "direction=\"inbound\"".to_string(),
+                    state.inbound_bytes as f64,
+                ),
+                (
+                    "direction=\"outbound\"".to_string(),
+                    state.outbound_bytes as f64,
+                ),
+            ],
+        );
+        if let Some(balance) = self.reward_balance().await {
+            write_metric(
+                &mut out,
+                "safenode_reward_balance_nanos",
+                "The balance of the wallet of the node, in nanos.",
+                "gauge",
+                &[(String::new(), balance as f64)],
+            );
+        }
+        out
+    }
+
+    /// The number of records held by the node and their size in bytes, along with the space
+    /// available on the disk of its root dir, if it could be measured.
+    pub(super) async fn storage(&self) -> (u64, u64, Option<u64>) {
+        let (records, bytes) = self
+            .records_held()
+            .await
+            .iter()
+            .fold((0, 0), |(records, bytes), (_, (files, size))| {
+                (records + files, bytes + size)
+            });
+        let available = match fs2::available_space(&self.root_dir) {
+            Ok(available) => Some(available),
+            Err(err) => {
+                warn!(
+                    "Failed to measure the space available in {:?}: {err}",
+                    self.root_dir
+                );
+                None
+            }
+        };
+        (records, bytes, available)
+    }
+
+    /// The number of records held by the node, and their size in bytes, per type.
+    async fn records_held(&self) -> [(&'static str, (u64, u64)); 4] {
+        let root_dir = self.root_dir.clone();
+        tokio::task::spawn_blocking(move || {
+            RECORD_DIRS.map(|(kind, dir)| (kind, dir_usage(&root_dir.join(dir))))
+        })
+        .await
+        .unwrap_or_else(|err| {
+            warn!("Failed to measure the records held: {err}");
+            RECORD_DIRS.map(|(kind, _)| (kind, (0, 0)))
+        })
+    }
+
+    /// The balance of the wallet in the root dir of the node, if it has an unencrypted one
+    /// which is not in use by another wallet instance.
+    async fn reward_balance(&self) -> Option<u64> {
+        if !LocalWallet::exists(&self.root_dir) {
+            return None;
+        }
+        match LocalWallet::load_from(&self.root_dir).await {
+            Ok(wallet) => Some(wallet.balance().as_nano()),
+            Err(err) => {
+                debug!("Not reporting the reward balance, as the wallet can't be loaded: {err}");
+                None
+            }
+        }
+    }
+}
+
+/// Writes the help and type lines of a metric, followed by its samples, given with their labels.
+fn write_metric(out: &mut String, name: &str, help: &str, kind: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{name} {value}");
+        } else {
+            let _ = writeln!(out, "{name}{{{labels}}} {value}");
+        }
+    }
+}
+
+/// The number of files in the dir, and their total size in bytes.
+fn dir_usage(dir: &Path) -> (u64, u64) {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .fold((0, 0), |(files, bytes), metadata| {
+            (files + 1, bytes + metadata.len())
+        })
+}
+
+/// The number of bytes of memory used by this process, if it can be read.
+fn memory_usage() -> Option<u64> {
+    use sysinfo::{ProcessExt, System, SystemExt};
+
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = System::new();
+    if !system.refresh_process(pid) {
+        return None;
+    }
+    system.process(pid).map(|process| process.memory())
+}
+
+/// Formats the number of bytes in the largest unit it's at least one of, e.g. `1.5 MiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// The kind of the request a response answers, and whether it failed.
+fn response_kind(response: &Response) -> (&'static str, bool) {
+    match response {
+        Response::Cmd(response) => match response {
+            CmdResponse::Spend(result) => ("spend_dbc", result.is_err()),
+            CmdResponse::StoreChunk(result) => ("store_chunk", result.is_err()),
+            CmdResponse::CreateRegister(result) => ("create_register", result.is_err()),
+            CmdResponse::EditRegister(result) => ("edit_register", result.is_err()),
+            CmdResponse::TransferRegisterOwnership(result) => {
+                ("transfer_register_ownership", result.is_err())
+            }
+        },
+        Response::Query(response) => match response {
+            QueryResponse::GetDbcSpend(result) => ("get_dbc_spend", result.is_err()),
+            QueryResponse::GetChunk(result) => ("get_chunk", result.is_err()),
+            QueryResponse::GetRegister(result) => ("get_register", result.is_err()),
+            QueryResponse::GetRegisterEntry(result) => ("get_register_entry", result.is_err()),
+            QueryResponse::GetRegisterOwner(result) => ("get_register_owner", result.is_err()),
+            QueryResponse::ReadRegister(result) => ("read_register", result.is_err()),
+            QueryResponse::GetRegisterPolicy(result) => ("get_register_policy", result.is_err()),
+            QueryResponse::GetRegisterUserPermissions(result) => {
+                ("get_register_user_permissions", result.is_err())
+            }
+            QueryResponse::GetStoreCost(result) => ("get_store_cost", result.is_err()),
+        },
+    }
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use super::{dir_usage, RECORD_DIRS};
+
+    use opentelemetry::{
+        global,
+        metrics::{Counter, Histogram, Unit},
+        KeyValue,
+    };
+    use std::{path::Path, time::Duration};
+
+    /// The OpenTelemetry instruments of the metrics of a node, recorded with the global meter.
+    #[derive(Clone)]
+    pub(super) struct Instruments {
+        requests: Counter<u64>,
+        request_errors: Counter<u64>,
+        request_duration: Histogram<f64>,
+        replication_events: Counter<u64>,
+    }
+
+    impl Instruments {
+        pub(super) fn new(root_dir: &Path) -> Self {
+            let meter = global::meter("safenode");
+            let root_dir = root_dir.to_path_buf();
+            // The records held are measured every time the metrics are exported.
+            let _storage_bytes = meter
+                .u64_value_observer("safenode.storage.bytes", move |observer| {
+                    for (kind, dir) in RECORD_DIRS {
+                        let (_, bytes) = dir_usage(&root_dir.join(dir));
+                        observer.observe(bytes, &[KeyValue::new("type", kind)]);
+                    }
+                })
+                .with_description("The number of bytes of the records held by the node on disk.")
+                .with_unit(Unit::new("By"))
+                .init();
+
+            Self {
+                requests: meter
+                    .u64_counter("safenode.requests")
+                    .with_description("The number of requests handled by the node.")
+                    .init(),
+                request_errors: meter
+                    .u64_counter("safenode.request_errors")
+                    .with_description("The number of requests the node handled with an error.")
+                    .init(),
+                request_duration: meter
+                    .f64_histogram("safenode.request_duration")
+                    .with_description("The time the node took to handle the requests.")
+                    .with_unit(Unit::new("s"))
+                    .init(),
+                replication_events: meter
+                    .u64_counter("safenode.replication_events")
+                    .with_description("The number of times data was replicated to the node.")
+                    .init(),
+            }
+        }
+
+        pub(super) fn record_request(&self, kind: &'static str, failed: bool, duration: Duration) {
+            let attributes = [KeyValue::new("kind", kind)];
+            self.requests.add(1, &attributes);
+            if failed {
+                self.request_errors.add(1, &attributes);
+            }
+            self.request_duration
+                .record(duration.as_secs_f64(), &attributes);
+        }
+
+        pub(super) fn record_replication_event(&self, kind: &'static str) {
+            self.replication_events
+                .add(1, &[KeyValue::new("kind", kind)]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use assert_fs::TempDir;
+
+    #[tokio::test]
+    async fn metrics_are_rendered_in_the_prometheus_format() {
+        let dir = TempDir::new().expect("Should be able to create a temp dir.");
+        let chunks_dir = dir.path().join(RECORD_STORE_DIR_NAME);
+        std::fs::create_dir_all(&chunks_dir).expect("Should be able to create the record store.");
+        std::fs::write(chunks_dir.join("a"), [0; 10]).expect("Should be able to write a record.");
+        std::fs::write(chunks_dir.join("b"), [0; 5]).expect("Should be able to write a record.");
+
+        let metrics = NodeMetrics::new(dir.path());
+        let started = Instant::now();
+        metrics.record_response(&Response::Cmd(CmdResponse::StoreChunk(Ok(()))), started);
+        metrics.record_response(&Response::Cmd(CmdResponse::StoreChunk(Ok(()))), started);
+        metrics.record_request("valid_spend_event", true, started);
+        metrics.record_replication_event("valid_spend");
+
+        let rendered = metrics
+            .render(NodeState {
+                connected_peers: 3,
+                routing_table_size: 5,
+                kad_queries: BTreeMap::from([(
+                    "get_record",
+                    KadQueryStats {
+                        completed: 2,
+                        duration: Duration::from_millis(1500),
+                    },
+                )]),
+                inbound_bytes: 100,
+                outbound_bytes: 200,
+            })
+            .await;
+        for line in [
+            "# TYPE safenode_connected_peers gauge",
+            "safenode_connected_peers 3",
+            "safenode_routing_table_size 5",
+            "safenode_puts_total 2",
+            "safenode_gets_total 0",
+            "safenode_kad_query_duration_seconds_sum{kind=\"get_record\"} 1.5",
+            "safenode_kad_query_duration_seconds_count{kind=\"get_record\"} 2",
+            "safenode_records_held{type=\"chunk\"} 2",
+            "safenode_records_held{type=\"register\"} 0",
+            "safenode_disk_usage_bytes{type=\"chunk\"} 15",
+            "safenode_requests_total{kind=\"store_chunk\"} 2",
+            "safenode_request_errors_total{kind=\"store_chunk\"} 0",
+            "safenode_request_errors_total{kind=\"valid_spend_event\"} 1",
+            "safenode_request_duration_seconds_count{kind=\"store_chunk\"} 2",
+            "safenode_replication_events_total{kind=\"valid_spend\"} 1",
+            "safenode_bandwidth_bytes_total{direction=\"outbound\"} 200",
+        ] {
+            assert!(
+                rendered.lines().any(|rendered| rendered == line),
+                "{line:?} should be in:\n{rendered}"
+            );
+        }
+        // No wallet was created in the root dir of the node.
+        assert!(!rendered.contains("safenode_reward_balance_nanos"));
+    }
+
+    #[tokio::test]
+    async fn status_is_summarised_in_one_line() {
+        let dir = TempDir::new().expect("Should be able to create a temp dir.");
+        let chunks_dir = dir.path().join(RECORD_STORE_DIR_NAME);
+        std::fs::create_dir_all(&chunks_dir).expect("Should be able to create the record store.");
+        std::fs::write(chunks_dir.join("a"), [0; 2048]).expect("Should be able to write a record.");
+
+        let status = NodeMetrics::new(dir.path())
+            .status(NodeState {
+                connected_peers: 3,
+                routing_table_size: 5,
+                kad_queries: BTreeMap::new(),
+                inbound_bytes: 0,
+                outbound_bytes: 0,
+            })
+            .await;
+        assert_eq!(status.lines().count(), 1);
+        assert!(status.starts_with(
+            "Status: 3 connected peers, 5 in the routing table, 1 records held (2.0 KiB on disk)"
+        ));
+        assert!(!status.contains("reward balance"));
+
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.5 MiB");
+    }
+}
@@ -9,13 +9,14 @@
 mod api;
 mod error;
 mod event;
+mod metrics;
 
 pub use self::{
-    api::RunningNode,
+    api::{RunningNode, StorageInfo},
     event::{NodeEvent, NodeEventsChannel, NodeEventsReceiver},
 };
 
-use self::api::TransferAction;
+use self::{api::TransferAction, metrics::NodeMetrics};
 
 use crate::{
     domain::{node_transfers::Transfers, storage::RegisterStorage},
@@ -36,4 +37,5 @@ pub struct Node {
     /// Peers that are dialed at startup of node.
     initial_peers: Vec<(PeerId, Multiaddr)>,
     transfer_actor: mpsc::Sender<TransferAction>,
+    metrics: NodeMetrics,
 }
@@ -0,0 +1,35 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::Multiaddr;
+use std::path::PathBuf;
+use thiserror::Error;
+
+pub(super) type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors reading the contacts of a network.
+#[derive(Debug, Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The address {0} does not contain `/p2p/<PeerId>`")]
+    MissingPeerId(Multiaddr),
+    #[error("The address {0} contains an invalid `/p2p/<PeerId>`")]
+    InvalidPeerId(Multiaddr),
+    #[error("Invalid peer address {0:?} in the {1} env var: {2}")]
+    InvalidEnvPeer(String, &'static str, String),
+    #[error("Failed to access the contacts file {0:?}: {1}")]
+    ContactsFile(PathBuf, std::io::Error),
+    #[error("Invalid contacts: {0}")]
+    InvalidContacts(#[from] serde_json::Error),
+    #[error("Failed to fetch the contacts from {0}: {1}")]
+    ContactsFetch(String, reqwest::Error),
+    #[error("The contacts at {0} are larger than {1} bytes")]
+    ContactsTooLarge(String, usize),
+    #[error("The contacts of the network list no peers")]
+    NoContacts,
+}
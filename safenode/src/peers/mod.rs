@@ -0,0 +1,287 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+mod error;
+
+pub use self::error::Error;
+
+use self::error::Result;
+
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, path::Path, time::Duration};
+
+/// Env var for comma-separated peers to dial, e.g. to join a local testnet.
+pub const SAFE_PEERS_ENV_VAR: &str = "SAFE_PEERS";
+
+/// Time given to fetch the contacts of a network from a url, from connecting to reading the body.
+const CONTACTS_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Max size of the contacts fetched from a url, far above that of any list of peers.
+const MAX_CONTACTS_SIZE: usize = 1024 * 1024;
+
+/// The contacts of a network, i.e. the peers to dial to join it, as shared between the nodes
+/// and clients of the network in a JSON document, e.g.
+/// `{"network_id":"testnet-1","peers":["/ip4/13.40.152.226/udp/12000/quic-v1/p2p/<PeerId>"]}`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkContacts {
+    /// An identifier of the network, e.g. to tell testnets apart, for reference only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_id: Option<String>,
+    /// The addresses of the peers, each ending with `/p2p/<PeerId>`.
+    pub peers: Vec<Multiaddr>,
+}
+
+impl NetworkContacts {
+    /// Reads the contacts from the file at the given path.
+    pub fn read(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read(path).map_err(|err| Error::ContactsFile(path.to_path_buf(), err))?;
+        Self::parse(&content)
+    }
+
+    /// Writes the contacts to the file at the given path, replacing any previous one.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_vec_pretty(self)?;
+        std::fs::write(path, content).map_err(|err| Error::ContactsFile(path.to_path_buf(), err))
+    }
+
+    /// Fetches the contacts from the given url, e.g. those published for a public network.
+    pub async fn fetch(url: &str) -> Result<Self> {
+        Self::fetch_within(url, CONTACTS_FETCH_TIMEOUT, MAX_CONTACTS_SIZE).await
+    }
+
+    /// Fetches the contacts from the given url, failing if it takes longer than the timeout
+    /// or if the body is larger than the max size.
+    async fn fetch_within(url: &str, timeout: Duration, max_size: usize) -> Result<Self> {
+        let fetch_error = |err| Error::ContactsFetch(url.to_string(), err);
+        let mut response = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map(|client| client.get(url))
+            .map_err(fetch_error)?
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(fetch_error)?;
+
+        let too_large = || Error::ContactsTooLarge(url.to_string(), max_size);
+        if response.content_length().unwrap_or(0) > max_size as u64 {
+            return Err(too_large());
+        }
+        let mut content = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(fetch_error)? {
+            if content.len() + chunk.len() > max_size {
+                return Err(too_large());
+            }
+            content.extend_from_slice(&chunk);
+        }
+        Self::parse(&content)
+    }
+
+    /// Fetches the contacts if the given source is an http(s) url, or reads them from
+    /// the file at that path otherwise.
+    pub async fn load(source: &str) -> Result<Self> {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            Self::fetch(source).await
+        } else {
+            Self::read(Path::new(source))
+        }
+    }
+
+    /// Returns the peers to dial, along with their ids.
+    pub fn peers(&self) -> Result<Vec<(PeerId, Multiaddr)>> {
+        parse_peer_multiaddrs(&self.peers)
+    }
+
+    /// Parses the contacts out of a JSON document, checking the peers it lists all have an id.
+    fn parse(content: &[u8]) -> Result<Self> {
+        let contacts: Self = serde_json::from_slice(content)?;
+        if contacts.peers.is_empty() {
+            return Err(Error::NoContacts);
+        }
+        let _ = contacts.peers()?;
+        Ok(contacts)
+    }
+}
+
+/// Returns the id of the peer at the given address, from its last `/p2p/<PeerId>` component,
+/// as relayed addresses (`/<relay-addr>/p2p/<relay-id>/p2p-circuit/p2p/<PeerId>`) contain more
+/// than one.
+pub fn peer_id_of(multiaddr: &Multiaddr) -> Result<PeerId> {
+    let multihash = multiaddr
+        .iter()
+        .filter_map(|protocol| match protocol {
+            Protocol::P2p(hash) => Some(hash),
+            _ => None,
+        })
+        .last()
+        .ok_or_else(|| Error::MissingPeerId(multiaddr.clone()))?;
+    PeerId::from_multihash(multihash).map_err(|_| Error::InvalidPeerId(multiaddr.clone()))
+}
+
+/// Parses the ids of the peers at the given addresses, failing on the first invalid one.
+pub fn parse_peer_multiaddrs(multiaddrs: &[Multiaddr]) -> Result<Vec<(PeerId, Multiaddr)>> {
+    multiaddrs
+        .iter()
+        .map(|multiaddr| Ok((peer_id_of(multiaddr)?, multiaddr.clone())))
+        .collect()
+}
+
+/// Returns the comma-separated peers in the `SAFE_PEERS` env var, if it's set.
+pub fn peers_from_env() -> Result<Vec<(PeerId, Multiaddr)>> {
+    let value = match std::env::var(SAFE_PEERS_ENV_VAR) {
+        Ok(value) => value,
+        Err(_) => return Ok(vec![]),
+    };
+    let multiaddrs = value
+        .split(',')
+        .map(str::trim)
+        .filter(|peer| !peer.is_empty())
+        .map(|peer| {
+            peer.parse::<Multiaddr>().map_err(|err| {
+                Error::InvalidEnvPeer(peer.to_string(), SAFE_PEERS_ENV_VAR, err.to_string())
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    parse_peer_multiaddrs(&multiaddrs)
+}
+
+/// Returns the peers to dial to join a network: the given ones, e.g. from `--peer` flags, along
+/// with those of the network contacts at the given path or url, if any, without duplicates.
+pub async fn bootstrap_peers(
+    multiaddrs: &[Multiaddr],
+    contacts: Option<&str>,
+) -> Result<Vec<(PeerId, Multiaddr)>> {
+    let mut peers = parse_peer_multiaddrs(multiaddrs)?;
+    if let Some(source) = contacts {
+        let contacts = NetworkContacts::load(source).await?;
+        info!(
+            "Read {} peers from the contacts at {source}",
+            contacts.peers.len()
+        );
+        peers.extend(contacts.peers()?);
+    }
+    let mut seen = BTreeSet::new();
+    peers.retain(|(_, multiaddr)| seen.insert(multiaddr.to_string()));
+    Ok(peers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    const PEER: &str =
+        "/ip4/13.40.152.226/udp/12000/quic-v1/p2p/12D3KooWRi6wF7yxWLuPSNskXc6kQ5cJ6eaymeMbCRdTnMesPgFx";
+    const RELAYED_PEER: &str = "/ip4/1.2.3.4/udp/12000/quic-v1\
+        /p2p/12D3KooWCRN4jQjyACrHq4mAq1ZLDDnA1E9cDGoGuXP1pZbRDJee/p2p-circuit\
+        /p2p/12D3KooWRi6wF7yxWLuPSNskXc6kQ5cJ6eaymeMbCRdTnMesPgFx";
+
+    #[test]
+    fn the_peer_id_is_that_of_the_last_p2p_component() -> eyre::Result<()> {
+        let peer: Multiaddr = PEER.parse()?;
+        let relayed: Multiaddr = RELAYED_PEER.parse()?;
+        assert_eq!(peer_id_of(&relayed)?, peer_id_of(&peer)?);
+
+        let without_id: Multiaddr = "/ip4/13.40.152.226/udp/12000/quic-v1".parse()?;
+        assert!(matches!(
+            peer_id_of(&without_id),
+            Err(Error::MissingPeerId(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn contacts_are_written_and_read_back() -> eyre::Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("contacts.json");
+        let contacts = NetworkContacts {
+            network_id: Some("testnet".to_string()),
+            peers: vec![PEER.parse()?],
+        };
+        contacts.write(&path)?;
+        assert_eq!(NetworkContacts::read(&path)?, contacts);
+
+        std::fs::write(
+            &path,
+            r#"{"peers":["/ip4/13.40.152.226/udp/12000/quic-v1"]}"#,
+        )?;
+        assert!(matches!(
+            NetworkContacts::read(&path),
+            Err(Error::MissingPeerId(_))
+        ));
+        std::fs::write(&path, r#"{"peers":[]}"#)?;
+        assert!(matches!(
+            NetworkContacts::read(&path),
+            Err(Error::NoContacts)
+        ));
+        Ok(())
+    }
+
+    /// Serves the given body, without a content length if it's not `Some`, to a single request.
+    async fn serve_once(body: String, content_length: Option<usize>) -> eyre::Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/contacts.json", listener.local_addr()?);
+        let _handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await?;
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await?;
+            let header = match content_length {
+                Some(length) => format!("Content-Length: {length}"),
+                None => "Connection: close".to_string(),
+            };
+            let response = format!("HTTP/1.1 200 OK\r\n{header}\r\n\r\n{body}");
+            stream.write_all(response.as_bytes()).await?;
+            stream.shutdown().await
+        });
+        Ok(url)
+    }
+
+    #[tokio::test]
+    async fn contacts_are_fetched_within_their_max_size() -> eyre::Result<()> {
+        let body = format!(r#"{{"peers":["{PEER}"]}}"#);
+
+        let url = serve_once(body.clone(), Some(body.len())).await?;
+        let contacts =
+            NetworkContacts::fetch_within(&url, Duration::from_secs(5), body.len()).await?;
+        assert_eq!(contacts.peers, vec![PEER.parse::<Multiaddr>()?]);
+
+        let url = serve_once(body.clone(), Some(body.len())).await?;
+        assert!(matches!(
+            NetworkContacts::fetch_within(&url, Duration::from_secs(5), body.len() - 1).await,
+            Err(Error::ContactsTooLarge(_, _))
+        ));
+
+        // Without a content length, the body is cut short once it exceeds the max size.
+        let url = serve_once(body.clone(), None).await?;
+        assert!(matches!(
+            NetworkContacts::fetch_within(&url, Duration::from_secs(5), body.len() - 1).await,
+            Err(Error::ContactsTooLarge(_, _))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fetching_the_contacts_times_out() -> eyre::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/contacts.json", listener.local_addr()?);
+
+        // The connection is accepted by the OS, but never answered.
+        let result =
+            NetworkContacts::fetch_within(&url, Duration::from_millis(200), MAX_CONTACTS_SIZE)
+                .await;
+        assert!(matches!(result, Err(Error::ContactsFetch(_, err)) if err.is_timeout()));
+        drop(listener);
+        Ok(())
+    }
+}
@@ -47,6 +47,14 @@ pub enum StorageError {
     /// Access denied for user
     #[error("Access denied for user: {0:?}")]
     AccessDenied(User),
+    /// A Register ownership transfer is not the next one to be applied
+    #[error("Register ownership transfer #{found} is not the next one, which is #{expected}")]
+    OwnershipTransferOutOfSequence {
+        /// The sequence number of the next transfer to be applied
+        expected: u64,
+        /// The sequence number of the transfer
+        found: u64,
+    },
     /// Entry is too big to fit inside a register
     #[error("Entry is too big to fit inside a register: {size}, max: {max}")]
     EntryTooBig {
@@ -90,6 +98,9 @@ pub enum StorageError {
     /// A spend that was attempted to be added already existed.
     #[error("A spend that was attempted to be added already existed: {0:?}")]
     AlreadyExists(DbcAddress),
+    /// The cost of storing data could not be obtained.
+    #[error("Store cost could not be obtained: {0}")]
+    StoreCostUnavailable(String),
     /// Cannot verify a Spend signature.
     #[error("Spend signature is invalid: {0}")]
     InvalidSpendSignature(String),
@@ -0,0 +1,83 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use serde::{Deserialize, Serialize};
+use std::{fmt, future::Future};
+
+tokio::task_local! {
+    static CURRENT: CorrelationId;
+}
+
+/// The id of an operation of a client, e.g. the upload of a file, carried by all the requests
+/// sent for it, so that the operation can be traced across the logs of the client and nodes.
+///
+/// The id of the operation a task is running for is kept in a task-local, and picked up by the
+/// network when sending requests from that task.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CorrelationId(u64);
+
+impl CorrelationId {
+    /// Returns a new random id.
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+
+    /// Returns the id of the operation the current task is running for, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|id| *id).ok()
+    }
+
+    /// Runs the future as part of the operation of the given id, if any.
+    pub async fn scope<F: Future>(id: Option<Self>, future: F) -> F::Output {
+        match id {
+            Some(id) => CURRENT.scope(id, future).await,
+            None => future.await,
+        }
+    }
+
+    /// Wraps the future to run as part of the operation the current task is running for,
+    /// if any, e.g. to spawn it as another task.
+    pub fn propagate<F: Future>(future: F) -> impl Future<Output = F::Output> {
+        Self::scope(Self::current(), future)
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl fmt::Debug for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CorrelationId({self})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn correlation_id_is_kept_within_its_scope() {
+        assert_eq!(CorrelationId::current(), None);
+
+        let id = CorrelationId::random();
+        let (current, propagated) = CorrelationId::scope(Some(id), async {
+            let propagated =
+                tokio::spawn(CorrelationId::propagate(async { CorrelationId::current() }));
+            (CorrelationId::current(), propagated.await)
+        })
+        .await;
+        assert_eq!(current, Some(id));
+        assert_eq!(propagated.ok().flatten(), Some(id));
+
+        assert_eq!(CorrelationId::current(), None);
+        assert_eq!(id.to_string().len(), 16);
+    }
+}
@@ -8,21 +8,25 @@
 
 //! Data messages and their possible responses.
 mod cmd;
+mod correlation_id;
 mod event;
 mod node_id;
 mod query;
 mod register;
 mod response;
 mod spend;
+pub mod wire;
 
 pub use self::{
     cmd::Cmd,
+    correlation_id::CorrelationId,
     event::Event,
     node_id::NodeId,
     query::Query,
     register::{
         CreateRegister, EditRegister, RegisterCmd, RegisterQuery, ReplicatedRegisterLog,
-        SignedRegisterCreate, SignedRegisterEdit,
+        SignedRegisterCreate, SignedRegisterEdit, SignedRegisterOwnershipTransfer,
+        TransferRegisterOwnership,
     },
     response::{CmdResponse, QueryResponse},
     spend::SpendQuery,
@@ -50,6 +54,16 @@ pub enum Request {
     Event(Event),
 }
 
+/// A request as sent over the wire, along with the id of the client operation it's sent for,
+/// if any.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestEnvelope {
+    /// The id of the operation the request is sent for.
+    pub correlation_id: Option<CorrelationId>,
+    /// The request.
+    pub request: Request,
+}
+
 /// A response to peers in the network.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Response {
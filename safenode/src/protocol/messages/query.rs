@@ -36,6 +36,12 @@ pub enum Query {
     ///
     /// [`Spend`]: super::transfers::SpendQuery.
     Spend(SpendQuery),
+    /// Retrieve the cost of storing a record at the given address.
+    ///
+    /// This should eventually lead to a [`GetStoreCost`] response.
+    ///
+    /// [`GetStoreCost`]: super::QueryResponse::GetStoreCost
+    GetStoreCost(NetworkAddress),
 }
 
 impl Query {
@@ -45,6 +51,7 @@ impl Query {
             Query::GetChunk(address) => NetworkAddress::from_chunk_address(*address),
             Query::Register(query) => NetworkAddress::from_register_address(query.dst()),
             Query::Spend(query) => NetworkAddress::from_dbc_address(query.dst()),
+            Query::GetStoreCost(address) => address.clone(),
         }
     }
 }
@@ -61,6 +68,9 @@ impl std::fmt::Display for Query {
             Query::Spend(query) => {
                 write!(f, "Query::Spend({query:?})")
             }
+            Query::GetStoreCost(address) => {
+                write!(f, "Query::GetStoreCost({address:?})")
+            }
         }
     }
 }
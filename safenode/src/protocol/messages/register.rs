@@ -86,6 +86,8 @@ pub enum RegisterCmd {
     Create(SignedRegisterCreate),
     /// Edit the [`Register`].
     Edit(SignedRegisterEdit),
+    /// Transfer the ownership of the [`Register`] to another user.
+    TransferOwnership(SignedRegisterOwnershipTransfer),
 }
 
 ///
@@ -123,6 +125,24 @@ pub struct EditRegister {
     pub edit: RegisterOp<Entry>,
 }
 
+///
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct TransferRegisterOwnership {
+    /// The address of the [`Register`] to transfer.
+    pub address: RegisterAddress,
+    /// The owner of the [`Register`] at the time the transfer was made.
+    ///
+    /// This allows replicas to apply a chain of transfers in the order they were made.
+    pub current_owner: User,
+    /// The user the ownership is transferred to.
+    pub new_owner: User,
+    /// The number of transfers of the [`Register`] made before this one, i.e. `0` for the first.
+    ///
+    /// Replicas only apply the transfer following the last one they applied, so that a transfer
+    /// can't be replayed, and transfers are applied in the order they were made.
+    pub sequence: u64,
+}
+
 /// A signed cmd to create a [`Register`].
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SignedRegisterCreate {
@@ -145,6 +165,17 @@ pub struct SignedRegisterEdit {
     pub auth: DataAuthority,
 }
 
+/// A [`Register`] ownership transfer signed by its current owner.
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct SignedRegisterOwnershipTransfer {
+    /// The operation to perform.
+    pub op: TransferRegisterOwnership,
+    /// A signature carrying authority to perform the operation.
+    ///
+    /// This will be verified against the Register's current owner.
+    pub auth: DataAuthority,
+}
+
 impl SignedRegisterCreate {
     /// Returns the dst address of the register.
     pub fn dst(&self) -> RegisterAddress {
@@ -159,6 +190,13 @@ impl SignedRegisterEdit {
     }
 }
 
+impl SignedRegisterOwnershipTransfer {
+    /// Returns the dst address of the register.
+    pub fn dst(&self) -> RegisterAddress {
+        self.op.address
+    }
+}
+
 impl RegisterQuery {
     /// Returns the dst address for the query.
     pub fn dst(&self) -> RegisterAddress {
@@ -185,6 +223,7 @@ impl RegisterCmd {
         match self {
             Self::Create(cmd) => cmd.dst(),
             Self::Edit(cmd) => cmd.dst(),
+            Self::TransferOwnership(cmd) => cmd.dst(),
         }
     }
 }
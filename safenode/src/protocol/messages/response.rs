@@ -17,7 +17,7 @@ use crate::protocol::{
 #[allow(unused_imports)] // needed by rustdocs links
 use super::RegisterQuery;
 
-use sn_dbc::SignedSpend;
+use sn_dbc::{SignedSpend, Token};
 
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeSet, fmt::Debug};
@@ -59,6 +59,13 @@ pub enum QueryResponse {
     GetRegisterPolicy(Result<Policy>),
     /// Response to [`RegisterQuery::GetUserPermissions`].
     GetRegisterUserPermissions(Result<Permissions>),
+    //
+    // ===== Store cost =====
+    //
+    /// Response to [`GetStoreCost`]
+    ///
+    /// [`GetStoreCost`]: crate::protocol::messages::Query::GetStoreCost
+    GetStoreCost(Result<Token>),
 }
 
 /// The response to a Cmd, containing the query result.
@@ -81,6 +88,8 @@ pub enum CmdResponse {
     CreateRegister(Result<()>),
     /// Response to RegisterCmd::Edit.
     EditRegister(Result<()>),
+    /// Response to RegisterCmd::TransferOwnership.
+    TransferRegisterOwnership(Result<()>),
 }
 
 impl std::fmt::Display for QueryResponse {
@@ -0,0 +1,153 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! The encoding of the messages sent between peers.
+//!
+//! A message is a version byte followed by the MessagePack encoding of the message, with its
+//! struct fields and enum variants identified by name rather than position. Nodes and clients of
+//! adjacent versions thus stay compatible as long as fields are only added with a
+//! `#[serde(default)]`, and neither fields nor variants are renamed or removed.
+//!
+//! The encoding of the golden files under `golden/` must never change for a given version:
+//! a change of the encoding of existing messages requires a new version.
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+/// The version of the encoding of the messages sent, the first byte of the encoded messages.
+pub const WIRE_VERSION: u8 = 1;
+
+/// The oldest version of the encoding of the messages which can still be decoded.
+pub const MIN_SUPPORTED_WIRE_VERSION: u8 = 1;
+
+/// A specialised `Result` type for the encoding of messages.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors encoding or decoding a message.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The message is empty, i.e. has no version.
+    #[error("The message is empty")]
+    Empty,
+    /// The message was encoded with a version this peer doesn't know how to decode.
+    #[error("The message has an unsupported version {0}, supported versions are {MIN_SUPPORTED_WIRE_VERSION} to {WIRE_VERSION}")]
+    UnsupportedVersion(u8),
+    /// The message couldn't be encoded.
+    #[error("The message couldn't be encoded: {0}")]
+    Encoding(String),
+    /// The message couldn't be decoded.
+    #[error("The message couldn't be decoded: {0}")]
+    Decoding(String),
+}
+
+/// Encode the message with the current version of the encoding.
+pub fn encode<T: Serialize>(msg: &T) -> Result<Vec<u8>> {
+    let msg = rmp_serde::to_vec_named(msg).map_err(|err| Error::Encoding(err.to_string()))?;
+    let mut bytes = Vec::with_capacity(msg.len() + 1);
+    bytes.push(WIRE_VERSION);
+    bytes.extend(msg);
+    Ok(bytes)
+}
+
+/// Decode a message encoded with any of the supported versions of the encoding.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    match bytes.split_first() {
+        None => Err(Error::Empty),
+        Some((&version, msg)) if (MIN_SUPPORTED_WIRE_VERSION..=WIRE_VERSION).contains(&version) => {
+            rmp_serde::from_slice(msg).map_err(|err| Error::Decoding(err.to_string()))
+        }
+        Some((&version, _)) => Err(Error::UnsupportedVersion(version)),
+    }
+}
+
+/// Encode the message as the peers which predate the versioning of the encoding expect it,
+/// i.e. with no version, and with the struct fields identified by position.
+pub(crate) fn encode_legacy<T: Serialize>(msg: &T) -> Result<Vec<u8>> {
+    rmp_serde::to_vec(msg).map_err(|err| Error::Encoding(err.to_string()))
+}
+
+/// Decode a message encoded by a peer which predates the versioning of the encoding.
+pub(crate) fn decode_legacy<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    if bytes.is_empty() {
+        return Err(Error::Empty);
+    }
+    rmp_serde::from_slice(bytes).map_err(|err| Error::Decoding(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{
+        messages::{Cmd, CmdResponse, ReplicatedData, Request, RequestEnvelope, Response},
+        storage::Chunk,
+    };
+
+    use bytes::Bytes;
+    use eyre::Result;
+    use std::fmt::Debug;
+
+    fn golden_chunk() -> Chunk {
+        Chunk::new(Bytes::from_static(b"golden chunk"))
+    }
+
+    fn golden_request() -> RequestEnvelope {
+        RequestEnvelope {
+            correlation_id: None,
+            request: Request::Cmd(Cmd::StoreChunk(golden_chunk())),
+        }
+    }
+
+    // Checks the message is encoded as in the golden file, and decoded back from it.
+    fn assert_golden<T>(msg: &T, golden: &str) -> Result<()>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let golden = hex::decode(golden.trim())?;
+        assert_eq!(encode(msg)?, golden);
+        assert_eq!(&decode::<T>(&golden)?, msg);
+        Ok(())
+    }
+
+    #[test]
+    fn messages_match_the_golden_files_of_their_version() -> Result<()> {
+        assert_golden(
+            &golden_request(),
+            include_str!("golden/request_store_chunk.v1.hex"),
+        )?;
+        assert_golden(
+            &Response::Cmd(CmdResponse::StoreChunk(Ok(()))),
+            include_str!("golden/response_store_chunk_ok.v1.hex"),
+        )?;
+        assert_golden(
+            &ReplicatedData::Chunk(golden_chunk()),
+            include_str!("golden/replicated_chunk.v1.hex"),
+        )
+    }
+
+    #[test]
+    fn messages_of_peers_predating_versions_are_still_understood() -> Result<()> {
+        // Such peers send requests without an envelope.
+        let request = golden_request().request;
+        let golden = hex::decode(include_str!("golden/request_store_chunk.legacy.hex").trim())?;
+        assert_eq!(encode_legacy(&request)?, golden);
+        assert_eq!(decode_legacy::<Request>(&golden)?, request);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() -> Result<()> {
+        let mut bytes = encode(&golden_request())?;
+        bytes[0] = WIRE_VERSION + 1;
+        assert_eq!(
+            decode::<RequestEnvelope>(&bytes),
+            Err(Error::UnsupportedVersion(WIRE_VERSION + 1))
+        );
+        assert_eq!(decode::<RequestEnvelope>(&[]), Err(Error::Empty));
+        Ok(())
+    }
+}
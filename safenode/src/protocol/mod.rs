@@ -12,6 +12,8 @@ pub mod error;
 pub mod messages;
 /// Storage types for spends, chunks and registers.
 pub mod storage;
+/// Shareable encoding of network addresses.
+pub mod xorurl;
 
 use self::storage::{ChunkAddress, DbcAddress, RegisterAddress};
 
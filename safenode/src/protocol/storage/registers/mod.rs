@@ -30,6 +30,8 @@ pub struct Register {
     pub crdt: RegisterCrdt,
     ///
     pub policy: Policy,
+    /// The number of times the ownership of the Register was transferred.
+    pub ownership_transfers: u64,
 }
 
 /// CRDT operation that can be applied to a Register
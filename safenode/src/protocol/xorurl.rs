@@ -0,0 +1,202 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use multibase::Base;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+use xor_name::{XorName, XOR_NAME_LEN};
+
+/// The scheme XorUrls start with.
+pub const XOR_URL_SCHEME: &str = "safe://";
+
+// The version of the encoding, the first byte of the encoded content.
+const XOR_URL_VERSION: u8 = 1;
+
+// The bytes identifying each type of data, the second byte of the encoded content.
+const FILE_TYPE: u8 = 0;
+const FILES_CONTAINER_TYPE: u8 = 1;
+const REGISTER_TYPE: u8 = 2;
+const SPEND_TYPE: u8 = 3;
+
+/// A specialised `Result` type for XorUrls.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors parsing a XorUrl.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The url doesn't start with `safe://`.
+    #[error("The XorUrl doesn't start with '{XOR_URL_SCHEME}'")]
+    InvalidScheme,
+    /// The url isn't valid multibase.
+    #[error("The XorUrl couldn't be decoded: {0}")]
+    InvalidEncoding(String),
+    /// The url was encoded with an unknown version.
+    #[error("The XorUrl has an unsupported version {0}")]
+    UnsupportedVersion(u8),
+    /// The url points at an unknown type of data.
+    #[error("The XorUrl has an unknown data type {0}")]
+    UnknownDataType(u8),
+    /// The url content doesn't have the expected length for its data type.
+    #[error("The XorUrl content has an invalid length of {0} bytes")]
+    InvalidLength(usize),
+}
+
+/// The type of data a [`XorUrl`] points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    /// A file, i.e. its data map chunk or its only chunk.
+    File,
+    /// A FilesContainer, stored on the network as a file.
+    FilesContainer,
+    /// A register, with its type tag.
+    Register(u64),
+    /// The spend of a DBC.
+    Spend,
+}
+
+/// A shareable, URL-safe encoding of a network address along with the type of data it points at,
+/// e.g. `safe://bae...`. The content is base32 encoded, and prefixed with a version and the type
+/// of data, so links can be pasted around without ambiguity about what they point at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XorUrl {
+    /// The type of data the url points at.
+    pub data_type: DataType,
+    /// The address of the data.
+    pub name: XorName,
+}
+
+impl XorUrl {
+    /// Create a url pointing at the data of the given type at the given address.
+    pub fn new(data_type: DataType, name: XorName) -> Self {
+        Self { data_type, name }
+    }
+
+    /// Encode the url into its string representation.
+    pub fn encode(&self) -> String {
+        let mut bytes = vec![XOR_URL_VERSION];
+        match self.data_type {
+            DataType::File => bytes.push(FILE_TYPE),
+            DataType::FilesContainer => bytes.push(FILES_CONTAINER_TYPE),
+            DataType::Register(_) => bytes.push(REGISTER_TYPE),
+            DataType::Spend => bytes.push(SPEND_TYPE),
+        }
+        bytes.extend_from_slice(&self.name.0);
+        if let DataType::Register(tag) = self.data_type {
+            bytes.extend_from_slice(&tag.to_be_bytes());
+        }
+        format!(
+            "{XOR_URL_SCHEME}{}",
+            multibase::encode(Base::Base32Lower, bytes)
+        )
+    }
+
+    /// Decode a url from its string representation.
+    pub fn decode(url: &str) -> Result<Self> {
+        let encoded = url
+            .strip_prefix(XOR_URL_SCHEME)
+            .ok_or(Error::InvalidScheme)?;
+        let (_base, bytes) =
+            multibase::decode(encoded).map_err(|err| Error::InvalidEncoding(err.to_string()))?;
+
+        let (version, data_type, content) = match bytes.as_slice() {
+            [version, data_type, content @ ..] => (*version, *data_type, content),
+            _ => return Err(Error::InvalidLength(bytes.len())),
+        };
+        if version != XOR_URL_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let (data_type, expected_len) = match data_type {
+            FILE_TYPE => (DataType::File, XOR_NAME_LEN),
+            FILES_CONTAINER_TYPE => (DataType::FilesContainer, XOR_NAME_LEN),
+            REGISTER_TYPE => {
+                let tag = content
+                    .get(XOR_NAME_LEN..)
+                    .and_then(|tag| tag.try_into().ok())
+                    .map(u64::from_be_bytes)
+                    .ok_or(Error::InvalidLength(content.len()))?;
+                (DataType::Register(tag), XOR_NAME_LEN + 8)
+            }
+            SPEND_TYPE => (DataType::Spend, XOR_NAME_LEN),
+            other => return Err(Error::UnknownDataType(other)),
+        };
+        if content.len() != expected_len {
+            return Err(Error::InvalidLength(content.len()));
+        }
+
+        let mut name = [0; XOR_NAME_LEN];
+        name.copy_from_slice(&content[..XOR_NAME_LEN]);
+        Ok(Self {
+            data_type,
+            name: XorName(name),
+        })
+    }
+}
+
+impl fmt::Display for XorUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.encode())
+    }
+}
+
+impl FromStr for XorUrl {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::decode(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xorurl_roundtrips_all_data_types() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        for data_type in [
+            DataType::File,
+            DataType::FilesContainer,
+            DataType::Register(3006),
+            DataType::Spend,
+        ] {
+            let url = XorUrl::new(data_type, XorName::random(&mut rng));
+            let encoded = url.encode();
+            assert!(encoded.starts_with(XOR_URL_SCHEME));
+            assert!(encoded
+                .chars()
+                .skip(XOR_URL_SCHEME.len())
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+            assert_eq!(encoded.parse::<XorUrl>()?, url);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn xorurl_rejects_invalid_urls() {
+        let name = XorName::random(&mut rand::thread_rng());
+        let encoded = XorUrl::new(DataType::File, name).encode();
+
+        assert_eq!(
+            XorUrl::decode(&encoded[XOR_URL_SCHEME.len()..]),
+            Err(Error::InvalidScheme)
+        );
+        assert!(matches!(
+            XorUrl::decode(&format!("{encoded}aa")),
+            Err(Error::InvalidLength(_)) | Err(Error::InvalidEncoding(_))
+        ));
+
+        let mut bytes = vec![XOR_URL_VERSION + 1, FILE_TYPE];
+        bytes.extend_from_slice(&name.0);
+        let url = format!(
+            "{XOR_URL_SCHEME}{}",
+            multibase::encode(Base::Base32Lower, bytes)
+        );
+        assert_eq!(XorUrl::decode(&url), Err(Error::UnsupportedVersion(2)));
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Generators of realistic data, and an in-memory network of nodes, for the tests of apps
+//! built on the network, and for property-based tests.
+//!
+//! The data is valid as the nodes expect it, e.g. the register cmds are signed by the owner
+//! of the register, and the spends are signed by the owner of the DBCs they spend. The
+//! generators panic if the data cannot be built, which is not expected to happen.
+
+use crate::{
+    domain::{
+        client_transfers::{create_transfer, SpendRequest},
+        dbc_genesis::create_genesis_dbc,
+        storage::RegisterReplica,
+    },
+    network::{sim::SimNetwork, Error, Network},
+    node::{Node, RunningNode},
+    protocol::{
+        messages::{
+            CreateRegister, EditRegister, RegisterCmd, SignedRegisterCreate, SignedRegisterEdit,
+        },
+        storage::{
+            registers::{DataAuthority, Policy, User},
+            Chunk,
+        },
+    },
+};
+
+use bincode::serialize;
+use bls::SecretKey;
+use bytes::Bytes;
+use libp2p::{kad::RecordKey, PeerId};
+use rand::{Rng, RngCore};
+use sn_dbc::{Hash, MainKey, SignedSpend, Token};
+use std::{collections::BTreeSet, ops::RangeInclusive, path::Path, time::Duration};
+
+/// The range of the latency of the messages delivered between the peers of an [`InMemoryTestnet`].
+pub const DEFAULT_LATENCY: RangeInclusive<Duration> =
+    Duration::from_millis(10)..=Duration::from_millis(200);
+
+/// A chunk of `size` random bytes.
+pub fn random_chunk(size: usize) -> Chunk {
+    let mut bytes = vec![0u8; size];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    Chunk::new(Bytes::from(bytes))
+}
+
+/// The cmds creating a register of a random name and tag, owned by a new key, followed by
+/// `edits` cmds writing random entries, each atop the previous one.
+/// Returns the key of the owner along with the cmds.
+pub fn random_register_cmds(edits: usize) -> (SecretKey, Vec<RegisterCmd>) {
+    let sk = SecretKey::random();
+    let owner = User::Key(sk.public_key());
+    let name = xor_name::rand::random();
+    let tag = rand::thread_rng().gen();
+
+    let op = CreateRegister {
+        name,
+        tag,
+        policy: Policy {
+            owner,
+            permissions: Default::default(),
+        },
+    };
+    let auth = sign(&sk, &op);
+    let mut cmds = vec![RegisterCmd::Create(SignedRegisterCreate { op, auth })];
+
+    let mut register = RegisterReplica::new_owned(owner, name, tag);
+    let mut children = BTreeSet::new();
+    for _ in 0..edits {
+        let entry = rand::thread_rng().gen::<[u8; 32]>().to_vec();
+        let (hash, edit) = register
+            .write(entry, children)
+            .expect("Writing a random entry to a new register should succeed.");
+        let op = EditRegister {
+            address: *register.address(),
+            edit,
+        };
+        let auth = sign(&sk, &op);
+        cmds.push(RegisterCmd::Edit(SignedRegisterEdit { op, auth }));
+        children = BTreeSet::from([hash]);
+    }
+
+    (sk, cmds)
+}
+
+/// The requests to spend `count` DBCs, split out of a new genesis DBC, each to a random address.
+/// The genesis DBC is returned along with them, to be set as the genesis DBC of the nodes
+/// expected to accept the spends, with [`set_genesis_dbc`](crate::domain::dbc_genesis::set_genesis_dbc).
+pub fn random_spend_requests(count: usize) -> (sn_dbc::Dbc, Vec<SpendRequest>) {
+    let rng = &mut rand::thread_rng();
+    let owner = MainKey::random();
+    let genesis =
+        create_genesis_dbc(owner.public_address()).expect("Creating a genesis DBC should succeed.");
+    let derived_key = genesis
+        .derived_key(&owner)
+        .expect("The genesis DBC should be owned by its owner.");
+    let amount = genesis
+        .revealed_amount(&derived_key)
+        .expect("The amount of the genesis DBC should be revealed by its owner.")
+        .value();
+
+    let recipients = (0..count)
+        .map(|_| {
+            let share = amount / count.max(1) as u64;
+            (Token::from_nano(share), owner.random_dbc_id_src(rng))
+        })
+        .collect();
+    let split = create_transfer(
+        vec![(genesis.clone(), derived_key)],
+        recipients,
+        owner.public_address(),
+        Hash::default(),
+    )
+    .expect("Splitting the genesis DBC should succeed.");
+
+    let spends = split
+        .created_dbcs
+        .into_iter()
+        .flat_map(|created| {
+            let derived_key = created
+                .dbc
+                .derived_key(&owner)
+                .expect("The split DBCs should be owned by the owner of the genesis DBC.");
+            let to = MainKey::random().random_dbc_id_src(rng);
+            create_transfer(
+                vec![(created.dbc, derived_key)],
+                vec![(Token::from_nano(created.amount.value()), to)],
+                owner.public_address(),
+                Hash::default(),
+            )
+            .expect("Spending a split DBC should succeed.")
+            .all_spend_requests
+        })
+        .collect();
+
+    (genesis, spends)
+}
+
+/// `count` signed spends of DBCs split out of a new genesis DBC, as [`random_spend_requests`].
+pub fn random_signed_spends(count: usize) -> Vec<SignedSpend> {
+    let (_genesis, requests) = random_spend_requests(count);
+    requests
+        .into_iter()
+        .map(|request| request.signed_spend)
+        .collect()
+}
+
+fn sign<T: serde::Serialize>(sk: &SecretKey, op: &T) -> DataAuthority {
+    let bytes = serialize(op).expect("Register ops should serialise.");
+    DataAuthority {
+        public_key: sk.public_key(),
+        signature: sk.sign(bytes),
+    }
+}
+
+/// A network of nodes running inside the process, exchanging messages without sockets.
+///
+/// The messages are delivered after a latency drawn from an RNG seeded with the given seed,
+/// which the peer ids are derived from too. With tokio's time paused, e.g. with
+/// `#[tokio::test(start_paused = true)]`, the same test then delivers the same messages
+/// in the same order on every run, without waiting for the latencies.
+pub struct InMemoryTestnet {
+    sim: SimNetwork,
+    nodes: Vec<RunningNode>,
+}
+
+impl InMemoryTestnet {
+    /// Start `node_count` nodes, each storing its records in a dir of its own under the `root_dir`.
+    pub async fn start(seed: u64, node_count: usize, root_dir: &Path) -> Result<Self, Error> {
+        let sim = SimNetwork::start(seed, DEFAULT_LATENCY);
+        let mut nodes = Vec::with_capacity(node_count);
+        for i in 0..node_count {
+            let node_dir = root_dir.join(format!("node-{i}"));
+            let (network, events) = sim.add_node(&node_dir).await?;
+            nodes.push(Node::start(network, events, vec![], &node_dir));
+        }
+        Ok(Self { sim, nodes })
+    }
+
+    /// The nodes of the network, in the order they were started.
+    pub fn nodes(&self) -> &[RunningNode] {
+        &self.nodes
+    }
+
+    /// Add a client to the network, i.e. a peer which holds no records, returning the handle
+    /// to send its requests to the nodes with.
+    pub async fn add_client(&self) -> Result<Network, Error> {
+        self.sim.add_client().await
+    }
+
+    /// Kill the node, which no longer receives any message, as if its process had crashed.
+    pub fn kill(&self, peer: PeerId) -> Result<(), Error> {
+        self.sim.kill(peer)
+    }
+
+    /// The live nodes holding the record, sorted by their distance to its key.
+    pub async fn holders(&self, key: RecordKey) -> Result<Vec<PeerId>, Error> {
+        self.sim.holders(key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        domain::storage::RegisterStorage,
+        network::CLOSE_GROUP_SIZE,
+        protocol::messages::{Cmd, CmdResponse, Request, Response},
+    };
+
+    use assert_fs::TempDir;
+    use eyre::Result;
+
+    #[tokio::test]
+    async fn random_register_cmds_are_accepted_by_the_storage() -> Result<()> {
+        let root_dir = TempDir::new()?;
+        let storage = RegisterStorage::new(root_dir.path());
+        let (_sk, cmds) = random_register_cmds(3);
+        assert_eq!(cmds.len(), 4);
+        for cmd in &cmds {
+            storage.write(cmd).await?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn random_signed_spends_spend_distinct_dbcs() {
+        let spends = random_signed_spends(3);
+        let dbc_ids: BTreeSet<_> = spends.iter().map(|spend| *spend.dbc_id()).collect();
+        assert_eq!(dbc_ids.len(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn chunks_stored_on_an_in_memory_testnet_are_held_by_a_close_group() -> Result<()> {
+        let root_dir = TempDir::new()?;
+        let testnet = InMemoryTestnet::start(3, 12, root_dir.path()).await?;
+        let client = testnet.add_client().await?;
+
+        let chunk = random_chunk(1024);
+        let responses = client
+            .client_send_to_closest(&Request::Cmd(Cmd::StoreChunk(chunk.clone())))
+            .await?;
+        for response in responses {
+            assert_eq!(response?, Response::Cmd(CmdResponse::StoreChunk(Ok(()))));
+        }
+        let holders = testnet.holders(RecordKey::new(chunk.name())).await?;
+        assert_eq!(holders.len(), CLOSE_GROUP_SIZE);
+        Ok(())
+    }
+}
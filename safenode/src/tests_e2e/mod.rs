@@ -23,7 +23,8 @@ use eyre::Result;
 
 #[tokio::test(flavor = "multi_thread")]
 async fn multiple_sequential_transfers_succeed() -> Result<()> {
-    let _log_appender_guard = crate::log::init_node_logging(&None)?;
+    let _log_appender_guard =
+        crate::log::init_node_logging(&None, Default::default(), None, Default::default())?;
 
     let first_wallet_dir = TempDir::new()?;
     let first_wallet_balance = Token::from_nano(1_000_000_000);
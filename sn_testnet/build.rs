@@ -8,7 +8,10 @@
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(feature = "verify-nodes")]
-    tonic_build::compile_protos("../safenode/src/protocol/safenode_proto/safenode.proto")?;
+    tonic_build::configure().compile(
+        &["../safenode/src/protocol/safenode_proto/safenode/v1/safenode.proto"],
+        &["../safenode/src/protocol/safenode_proto"],
+    )?;
 
     Ok(())
 }
@@ -13,7 +13,7 @@ use tonic::Request;
 // this includes code generated from .proto files
 #[allow(unused_qualifications)]
 mod safenode_proto {
-    tonic::include_proto!("safenode_proto");
+    tonic::include_proto!("safenode.v1");
 }
 
 use color_eyre::Result;
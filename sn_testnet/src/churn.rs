@@ -0,0 +1,334 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::Testnet;
+
+use color_eyre::Result;
+#[cfg(test)]
+use mockall::automock;
+use rand::seq::IteratorRandom;
+use std::{
+    fmt,
+    path::PathBuf,
+    process::Command,
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// The genesis node is never killed, as it holds the genesis DBC other nodes rely on.
+const GENESIS_NODE_NUMBER: usize = 1;
+
+/// The rates at which the nodes of a testnet are killed, restarted and added while it's churned.
+#[derive(Clone, Debug)]
+pub struct ChurnConfig {
+    /// The interval between killing nodes.
+    pub kill_interval: Duration,
+    /// How long a killed node stays down before being restarted.
+    ///
+    /// If not set, killed nodes are never restarted.
+    pub restart_delay: Option<Duration>,
+    /// The interval between adding new nodes.
+    ///
+    /// If not set, no nodes are added.
+    pub add_interval: Option<Duration>,
+    /// The interval between checks of the availability of the data on the network.
+    pub check_interval: Duration,
+    /// The minimum number of running nodes, below which no more nodes are killed.
+    pub min_running_nodes: usize,
+    /// How long to churn the testnet for.
+    ///
+    /// If not set, the testnet is churned until the process is stopped.
+    pub duration: Option<Duration>,
+}
+
+impl Default for ChurnConfig {
+    fn default() -> Self {
+        Self {
+            kill_interval: Duration::from_secs(30),
+            restart_delay: Some(Duration::from_secs(10)),
+            add_interval: None,
+            check_interval: Duration::from_secs(60),
+            min_running_nodes: 10,
+            duration: None,
+        }
+    }
+}
+
+/// This trait checks the data stored on a testnet can still be retrieved, e.g. while it's churned.
+#[cfg_attr(test, automock)]
+pub trait DataChecker {
+    /// Returns the data which couldn't be retrieved.
+    fn check(&self) -> Result<Vec<String>>;
+}
+
+/// Checks the files at the given addresses can be retrieved, using the `safe` client.
+#[derive(Clone, Debug)]
+pub struct SafeCliChecker {
+    safe_bin_path: PathBuf,
+    addresses: Vec<String>,
+}
+
+impl SafeCliChecker {
+    /// Create a checker of the files at the given addresses, e.g. uploaded with
+    /// `safe files upload` before the testnet is churned.
+    pub fn new(safe_bin_path: PathBuf, addresses: Vec<String>) -> Self {
+        Self {
+            safe_bin_path,
+            addresses,
+        }
+    }
+}
+
+impl DataChecker for SafeCliChecker {
+    fn check(&self) -> Result<Vec<String>> {
+        let mut unavailable = Vec::new();
+        for address in &self.addresses {
+            let output = Command::new(&self.safe_bin_path)
+                .args(["files", "verify", address])
+                .output()?;
+            if !output.status.success() {
+                warn!(
+                    "File {address} could not be retrieved: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                unavailable.push(address.clone());
+            }
+        }
+        Ok(unavailable)
+    }
+}
+
+/// What happened to a testnet while it was churned.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChurnReport {
+    pub killed: usize,
+    pub restarted: usize,
+    pub added: usize,
+    pub checks: usize,
+    pub failed_checks: usize,
+}
+
+impl fmt::Display for ChurnReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes killed, {} restarted and {} added; {} of {} data checks failed",
+            self.killed, self.restarted, self.added, self.failed_checks, self.checks
+        )
+    }
+}
+
+impl Testnet {
+    /// Churns the testnet, continuously killing, restarting and adding nodes at the rates of the
+    /// config, while checking the data on the network can still be retrieved.
+    ///
+    /// Only the nodes launched by this instance are killed, and never the genesis node. A last
+    /// data check is made once the duration of the churn has elapsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The rates at which nodes are killed, restarted and added.
+    /// * `checker` - Checks the availability of the data on the network.
+    /// * `node_args` - Additional arguments to pass to the restarted and added nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a node can't be killed or launched, or if the data can't be checked.
+    /// Data which can't be retrieved is not an error, but counted in the returned report.
+    pub fn churn(
+        &mut self,
+        config: &ChurnConfig,
+        checker: &dyn DataChecker,
+        node_args: Vec<String>,
+    ) -> Result<ChurnReport> {
+        let start = Instant::now();
+        let end = config.duration.map(|duration| start + duration);
+        let mut next_kill = start + config.kill_interval;
+        let mut next_add = config.add_interval.map(|interval| start + interval);
+        let mut next_check = start + config.check_interval;
+        let mut restarts: Vec<(Instant, usize)> = Vec::new();
+        let mut report = ChurnReport::default();
+
+        loop {
+            let next = [Some(next_check), next_add]
+                .into_iter()
+                .chain(restarts.iter().map(|(at, _)| Some(*at)))
+                .flatten()
+                .fold(next_kill, Instant::min);
+            if let Some(end) = end.filter(|end| next > *end) {
+                std::thread::sleep(end.saturating_duration_since(Instant::now()));
+                break;
+            }
+            std::thread::sleep(next.saturating_duration_since(Instant::now()));
+            let now = Instant::now();
+
+            let (due, pending): (Vec<_>, Vec<_>) =
+                restarts.into_iter().partition(|(at, _)| *at <= now);
+            restarts = pending;
+            for (_, node_number) in due {
+                self.restart_node(node_number, node_args.clone())?;
+                report.restarted += 1;
+            }
+
+            if next_kill <= now {
+                next_kill = now + config.kill_interval;
+                match self.node_to_kill(config.min_running_nodes) {
+                    Some(node_number) => {
+                        self.kill_node(node_number)?;
+                        report.killed += 1;
+                        if let Some(delay) = config.restart_delay {
+                            restarts.push((now + delay, node_number));
+                        }
+                    }
+                    None => info!(
+                        "Not killing a node, as only {} of the minimum {} are running",
+                        self.node_pids.len(),
+                        config.min_running_nodes
+                    ),
+                }
+            }
+
+            if next_add.map_or(false, |at| at <= now) {
+                next_add = config.add_interval.map(|interval| now + interval);
+                let _ = self.add_node(node_args.clone())?;
+                report.added += 1;
+            }
+
+            if next_check <= now {
+                next_check = now + config.check_interval;
+                self.check_data(checker, &mut report)?;
+            }
+        }
+
+        self.check_data(checker, &mut report)?;
+        info!("Churn of the testnet complete: {report}");
+        Ok(report)
+    }
+
+    fn node_to_kill(&self, min_running_nodes: usize) -> Option<usize> {
+        if self.node_pids.len() <= min_running_nodes {
+            return None;
+        }
+        self.node_pids
+            .keys()
+            .copied()
+            .filter(|node_number| *node_number != GENESIS_NODE_NUMBER)
+            .choose(&mut rand::thread_rng())
+    }
+
+    fn check_data(&self, checker: &dyn DataChecker, report: &mut ChurnReport) -> Result<()> {
+        report.checks += 1;
+        let unavailable = checker.check()?;
+        if unavailable.is_empty() {
+            info!(
+                "All data could be retrieved with {} nodes running",
+                self.node_pids.len()
+            );
+        } else {
+            report.failed_checks += 1;
+            warn!(
+                "{} items of data could not be retrieved with {} nodes running: {unavailable:?}",
+                unavailable.len(),
+                self.node_pids.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{MockNodeLauncher, SAFENODE_BIN_NAME, TESTNET_DIR_NAME};
+    use assert_fs::prelude::*;
+
+    fn testnet_with_running_nodes(
+        nodes_dir: &assert_fs::fixture::ChildPath,
+        node_count: usize,
+        node_launcher: MockNodeLauncher,
+    ) -> Result<Testnet> {
+        let mut testnet = Testnet::new(
+            PathBuf::from(SAFENODE_BIN_NAME),
+            0,
+            nodes_dir.to_path_buf(),
+            false,
+            Box::new(node_launcher),
+        )?;
+        for i in 1..=node_count {
+            nodes_dir.child(format!("safenode-{i}")).create_dir_all()?;
+            let _ = testnet.node_pids.insert(i, i as u32);
+        }
+        Ok(testnet)
+    }
+
+    #[test]
+    fn churn_should_restart_the_killed_nodes_but_never_the_genesis_node() -> Result<()> {
+        let tmp_data_dir = assert_fs::TempDir::new()?;
+        let nodes_dir = tmp_data_dir.child(TESTNET_DIR_NAME);
+
+        let mut node_launcher = MockNodeLauncher::new();
+        node_launcher
+            .expect_kill()
+            .withf(|pid| *pid != GENESIS_NODE_NUMBER as u32)
+            .returning(|_| Ok(()));
+        node_launcher.expect_launch().returning(|_, _| Ok(10));
+        let mut checker = MockDataChecker::new();
+        checker.expect_check().times(1).returning(|| Ok(vec![]));
+
+        let mut testnet = testnet_with_running_nodes(&nodes_dir, 3, node_launcher)?;
+        let config = ChurnConfig {
+            kill_interval: Duration::from_millis(20),
+            restart_delay: Some(Duration::ZERO),
+            add_interval: None,
+            check_interval: Duration::from_secs(3600),
+            min_running_nodes: 1,
+            duration: Some(Duration::from_millis(50)),
+        };
+        let report = testnet.churn(&config, &checker, vec![])?;
+
+        assert!(report.killed > 0);
+        assert_eq!(report.restarted, report.killed);
+        assert_eq!(report.checks, 1);
+        assert_eq!(report.failed_checks, 0);
+        assert_eq!(testnet.node_pids.len(), 3);
+        assert_eq!(testnet.node_pids.get(&GENESIS_NODE_NUMBER), Some(&1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn churn_should_not_kill_nodes_below_the_minimum_running() -> Result<()> {
+        let tmp_data_dir = assert_fs::TempDir::new()?;
+        let nodes_dir = tmp_data_dir.child(TESTNET_DIR_NAME);
+
+        let mut node_launcher = MockNodeLauncher::new();
+        node_launcher.expect_kill().never();
+        node_launcher.expect_launch().never();
+        let mut checker = MockDataChecker::new();
+        checker
+            .expect_check()
+            .returning(|| Ok(vec!["some address".to_string()]));
+
+        let mut testnet = testnet_with_running_nodes(&nodes_dir, 3, node_launcher)?;
+        let config = ChurnConfig {
+            kill_interval: Duration::from_millis(10),
+            restart_delay: None,
+            add_interval: None,
+            check_interval: Duration::from_millis(20),
+            min_running_nodes: 3,
+            duration: Some(Duration::from_millis(50)),
+        };
+        let report = testnet.churn(&config, &checker, vec![])?;
+
+        assert_eq!(report.killed, 0);
+        assert!(report.checks > 1);
+        assert_eq!(report.failed_checks, report.checks);
+
+        Ok(())
+    }
+}
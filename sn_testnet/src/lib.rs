@@ -5,12 +5,18 @@
 // under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
+mod churn;
+
+pub use churn::{ChurnConfig, ChurnReport, DataChecker, SafeCliChecker};
+
 use color_eyre::{eyre::eyre, Result};
 #[cfg(test)]
 use mockall::automock;
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
 use tracing::{debug, info};
 
 pub const DEFAULT_NODE_LAUNCH_INTERVAL: u64 = 1000;
@@ -27,19 +33,46 @@ const TESTNET_DIR_NAME: &str = "local-test-network";
 /// launching processes.
 #[cfg_attr(test, automock)]
 pub trait NodeLauncher {
-    fn launch(&self, node_bin_path: &Path, args: Vec<String>) -> Result<()>;
+    /// Launches a node process, returning its process id.
+    fn launch(&self, node_bin_path: &Path, args: Vec<String>) -> Result<u32>;
+
+    /// Kills a node process previously launched, by its process id.
+    fn kill(&self, pid: u32) -> Result<()>;
 }
 
 #[derive(Default)]
-pub struct SafeNodeLauncher {}
+pub struct SafeNodeLauncher {
+    // The launched processes are kept so they can be killed, and reaped once they have been.
+    processes: Mutex<BTreeMap<u32, Child>>,
+}
 impl NodeLauncher for SafeNodeLauncher {
-    fn launch(&self, node_bin_path: &Path, args: Vec<String>) -> Result<()> {
+    fn launch(&self, node_bin_path: &Path, args: Vec<String>) -> Result<u32> {
         debug!("Running {:#?} with args: {:#?}", node_bin_path, args);
-        Command::new(node_bin_path)
+        let child = Command::new(node_bin_path)
             .args(args)
             .stdout(Stdio::inherit())
             .stderr(Stdio::inherit())
             .spawn()?;
+        let pid = child.id();
+        let _ = self
+            .processes
+            .lock()
+            .map_err(|_| eyre!("The launched processes lock is poisoned"))?
+            .insert(pid, child);
+        Ok(pid)
+    }
+
+    fn kill(&self, pid: u32) -> Result<()> {
+        let child = self
+            .processes
+            .lock()
+            .map_err(|_| eyre!("The launched processes lock is poisoned"))?
+            .remove(&pid);
+        let mut child =
+            child.ok_or_else(|| eyre!("No node process was launched with PID {pid}"))?;
+        debug!("Killing node process with PID {pid}");
+        child.kill()?;
+        let _ = child.wait()?;
         Ok(())
     }
 }
@@ -139,6 +172,8 @@ pub struct Testnet {
     pub nodes_dir_path: PathBuf,
     pub flamegraph_mode: bool,
     pub node_count: usize,
+    /// The process ids of the nodes launched by this instance, by the number of the node.
+    pub node_pids: BTreeMap<usize, u32>,
     pub launcher: Box<dyn NodeLauncher>,
 }
 
@@ -179,6 +214,7 @@ impl Testnet {
             nodes_dir_path,
             flamegraph_mode,
             node_count,
+            node_pids: BTreeMap::new(),
             launcher,
         })
     }
@@ -203,7 +239,7 @@ impl Testnet {
     /// * The node process fails
     /// * The network has already been launched previously
     pub fn launch_genesis(
-        &self,
+        &mut self,
         _address: Option<SocketAddr>,
         node_args: Vec<String>,
     ) -> Result<()> {
@@ -222,7 +258,8 @@ impl Testnet {
         std::fs::create_dir_all(node_data_dir_path)?;
 
         let launch_bin = self.get_launch_bin();
-        self.launcher.launch(&launch_bin, launch_args)?;
+        let pid = self.launcher.launch(&launch_bin, launch_args)?;
+        let _ = self.node_pids.insert(1, pid);
         info!(
             "Delaying for {} seconds before launching other nodes",
             self.node_launch_interval / 1000
@@ -271,7 +308,8 @@ impl Testnet {
                 node_args.clone(),
             )?;
             let launch_bin = self.get_launch_bin();
-            self.launcher.launch(&launch_bin, launch_args)?;
+            let pid = self.launcher.launch(&launch_bin, launch_args)?;
+            let _ = self.node_pids.insert(i, pid);
 
             if i < end {
                 info!(
@@ -285,6 +323,76 @@ impl Testnet {
         Ok(())
     }
 
+    /// Kills the process of a node launched by this instance, as if it had crashed.
+    ///
+    /// The data and logs of the node are kept, so it can be restarted with `restart_node`.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_number` - The number of the node, i.e. the `n` of its `safenode-n` directory.
+    pub fn kill_node(&mut self, node_number: usize) -> Result<()> {
+        let pid = self
+            .node_pids
+            .remove(&node_number)
+            .ok_or_else(|| eyre!("Node {node_number} is not running"))?;
+        info!("Killing node {node_number} (PID: {pid})...");
+        self.launcher.kill(pid)
+    }
+
+    /// Restarts a node previously killed, using its existing data and logs directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_number` - The number of the node, i.e. the `n` of its `safenode-n` directory.
+    /// * `node_args` - Additional arguments to pass to the node process, e.g., --json-logs.
+    pub fn restart_node(&mut self, node_number: usize, node_args: Vec<String>) -> Result<()> {
+        if self.node_pids.contains_key(&node_number) {
+            return Err(eyre!("Node {node_number} is already running"));
+        }
+        if !self
+            .nodes_dir_path
+            .join(format!("safenode-{node_number}"))
+            .exists()
+        {
+            return Err(eyre!("Node {node_number} was never launched"));
+        }
+
+        info!("Restarting node {node_number}...");
+        self.launch_node(node_number, node_args)
+    }
+
+    /// Launches a single new node joining the network, returning its number.
+    ///
+    /// # Arguments
+    ///
+    /// * `node_args` - Additional arguments to pass to the node process, e.g., --json-logs.
+    pub fn add_node(&mut self, node_args: Vec<String>) -> Result<usize> {
+        // Nodes may have been launched by other instances, so the first unused number is taken.
+        let node_number = (self.node_count + 1..)
+            .find(|i| !self.nodes_dir_path.join(format!("safenode-{i}")).exists())
+            .ok_or_else(|| eyre!("Unable to find a number for the new node"))?;
+        std::fs::create_dir_all(self.nodes_dir_path.join(format!("safenode-{node_number}")))?;
+
+        info!("Adding node {node_number}...");
+        self.launch_node(node_number, node_args)?;
+        self.node_count += 1;
+        Ok(node_number)
+    }
+
+    fn launch_node(&mut self, node_number: usize, node_args: Vec<String>) -> Result<()> {
+        let rpc_address = format!("127.0.0.1:{}", 12000 + node_number).parse()?;
+        let launch_args = self.get_launch_args(
+            format!("safenode-{node_number}"),
+            Some(rpc_address),
+            None,
+            node_args,
+        )?;
+        let launch_bin = self.get_launch_bin();
+        let pid = self.launcher.launch(&launch_bin, launch_args)?;
+        let _ = self.node_pids.insert(node_number, pid);
+        Ok(())
+    }
+
     fn get_launch_args(
         &self,
         node_name: String,
@@ -348,7 +456,7 @@ mod test {
     #[test]
     fn new_should_create_a_testnet_with_zero_nodes_when_no_previous_network_exists() -> Result<()> {
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _| Ok(1));
 
         let testnet = Testnet::new(
             PathBuf::from(SAFENODE_BIN_NAME),
@@ -380,7 +488,7 @@ mod test {
         }
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _| Ok(1));
         let testnet = Testnet::new(
             PathBuf::from(SAFENODE_BIN_NAME),
             30000,
@@ -413,7 +521,7 @@ mod test {
         random_dir.create_dir_all()?;
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _| Ok(1));
 
         let testnet = Testnet::new(
             PathBuf::from(SAFENODE_BIN_NAME),
@@ -463,9 +571,9 @@ mod test {
                     "--json-logs".to_string(),
                 ]),
             )
-            .returning(|_, _| Ok(()));
+            .returning(|_, _| Ok(1));
 
-        let testnet = Testnet::new(
+        let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
             nodes_dir.path().to_path_buf(),
@@ -512,9 +620,9 @@ mod test {
                     "--json-logs".to_string(),
                 ]),
             )
-            .returning(|_, _| Ok(()));
+            .returning(|_, _| Ok(1));
 
-        let testnet = Testnet::new(
+        let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
             nodes_dir.path().to_path_buf(),
@@ -536,8 +644,8 @@ mod test {
         nodes_dir.create_dir_all()?;
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
-        let testnet = Testnet::new(
+        node_launcher.expect_launch().returning(|_, _| Ok(1));
+        let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
             nodes_dir.path().to_path_buf(),
@@ -564,8 +672,8 @@ mod test {
         let nodes_dir = tmp_data_dir.child(TESTNET_DIR_NAME);
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
-        let testnet = Testnet::new(
+        node_launcher.expect_launch().returning(|_, _| Ok(1));
+        let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
             nodes_dir.path().to_path_buf(),
@@ -628,9 +736,9 @@ mod test {
                     "--json-logs".to_string(),
                 ]),
             )
-            .returning(|_, _| Ok(()));
+            .returning(|_, _| Ok(1));
 
-        let testnet = Testnet::new(
+        let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
             nodes_dir.path().to_path_buf(),
@@ -660,9 +768,9 @@ mod test {
         }
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _| Ok(1));
 
-        let testnet = Testnet::new(
+        let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
             nodes_dir.path().to_path_buf(),
@@ -718,7 +826,7 @@ mod test {
                         "--json-logs".to_string(),
                     ]),
                 )
-                .returning(|_, _| Ok(()));
+                .returning(|_, _| Ok(1));
         }
 
         let mut testnet = Testnet::new(
@@ -750,7 +858,7 @@ mod test {
         network_contacts_file.write_str("section tree content")?;
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _| Ok(1));
         let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
@@ -783,7 +891,7 @@ mod test {
         network_contacts_file.write_str("section tree content")?;
 
         let mut node_launcher = MockNodeLauncher::new();
-        node_launcher.expect_launch().returning(|_, _| Ok(()));
+        node_launcher.expect_launch().returning(|_, _| Ok(1));
         let mut testnet = Testnet::new(
             node_bin_path.path().to_path_buf(),
             NODE_LAUNCH_INTERVAL,
@@ -850,7 +958,7 @@ mod test {
                         "--json-logs".to_string(),
                     ]),
                 )
-                .returning(|_, _| Ok(()));
+                .returning(|_, _| Ok(1));
         }
 
         let mut testnet = Testnet::new(
@@ -902,7 +1010,7 @@ mod test {
                         "--json-logs".to_string(),
                     ]),
                 )
-                .returning(|_, _| Ok(()));
+                .returning(|_, _| Ok(1));
         }
 
         let mut testnet = Testnet::new(
@@ -30,17 +30,24 @@
 #[cfg(feature = "verify-nodes")]
 mod check_testnet;
 
-use sn_testnet::{Testnet, DEFAULT_NODE_LAUNCH_INTERVAL, SAFENODE_BIN_NAME};
+use sn_testnet::{
+    ChurnConfig, SafeCliChecker, Testnet, DEFAULT_NODE_LAUNCH_INTERVAL, SAFENODE_BIN_NAME,
+};
 
 use clap::Parser;
 use color_eyre::{eyre::eyre, Help, Result};
 use std::{
     path::PathBuf,
     process::{Command, Stdio},
+    time::Duration,
 };
 use tracing::{debug, info};
 
 const DEFAULT_NODE_COUNT: u32 = 25;
+#[cfg(not(target_os = "windows"))]
+const SAFE_BIN_NAME: &str = "safe";
+#[cfg(target_os = "windows")]
+const SAFE_BIN_NAME: &str = "safe.exe";
 
 #[derive(Debug, clap::StructOpt)]
 #[clap(name = "testnet", version)]
@@ -92,6 +99,56 @@ struct Cmd {
     #[clap(short = 'c', long, env = "NODE_COUNT")]
     node_count: Option<u32>,
 
+    /// Churn the testnet once its nodes are launched, continuously killing, restarting and adding
+    /// nodes, until the process is stopped or the --churn-duration elapsed.
+    ///
+    /// Only the nodes launched by this process are killed, and never the genesis node.
+    #[clap(long)]
+    churn: bool,
+
+    /// Interval between killing nodes while churning, in seconds. Defaults to 30.
+    #[clap(long, value_name = "SECS")]
+    churn_kill_interval: Option<u64>,
+
+    /// Time a killed node stays down before being restarted, in seconds. Defaults to 10.
+    #[clap(long, value_name = "SECS", conflicts_with = "churn-no-restart")]
+    churn_restart_delay: Option<u64>,
+
+    /// Never restart the nodes killed while churning.
+    #[clap(long)]
+    churn_no_restart: bool,
+
+    /// Interval between adding new nodes while churning, in seconds.
+    ///
+    /// If not supplied, no nodes are added.
+    #[clap(long, value_name = "SECS")]
+    churn_add_interval: Option<u64>,
+
+    /// The minimum number of running nodes, below which no more nodes are killed. Defaults to 10.
+    #[clap(long, value_name = "COUNT")]
+    churn_min_nodes: Option<usize>,
+
+    /// How long to churn the testnet for, in seconds.
+    ///
+    /// If not supplied, the testnet is churned until the process is stopped.
+    #[clap(long, value_name = "SECS")]
+    churn_duration: Option<u64>,
+
+    /// Interval between checks of the data while churning, in seconds. Defaults to 60.
+    #[clap(long, value_name = "SECS")]
+    churn_check_interval: Option<u64>,
+
+    /// Address of a file to check can still be retrieved while churning, e.g. as uploaded with
+    /// `safe files upload` before. Can be supplied multiple times.
+    #[clap(long = "churn-check-address", value_name = "ADDRESS")]
+    churn_check_addresses: Vec<String>,
+
+    /// Optional path to the safe binary, used to check the data while churning.
+    ///
+    /// If not supplied we will assume that safe is on PATH.
+    #[clap(long, value_name = "FILE_PATH")]
+    safe_path: Option<PathBuf>,
+
     /// Specify any additional arguments to pass to safenode on launch, e.g., --json-logs.
     ///
     /// Any arguments must be valid safenode arguments.
@@ -125,7 +182,11 @@ async fn main() -> Result<()> {
         node_bin_path.push(SAFENODE_BIN_NAME);
     }
 
-    if args.join_network {
+    if args.churn && args.flame {
+        return Err(eyre!("The testnet cannot be churned in flamegraph mode"));
+    }
+
+    let mut testnet = if args.join_network {
         let node_count = args.node_count.ok_or_else(|| {
             eyre!("A node count must be specified for joining an existing network")
                 .suggestion("Please try again using the --node-count argument")
@@ -136,22 +197,55 @@ async fn main() -> Result<()> {
                 .unwrap_or(DEFAULT_NODE_LAUNCH_INTERVAL),
             node_count,
             args.network_contacts_path,
-            args.node_args,
+            args.node_args.clone(),
         )
-        .await?;
-        return Ok(());
+        .await?
+    } else {
+        run_network(
+            node_bin_path,
+            args.node_launch_interval
+                .unwrap_or(DEFAULT_NODE_LAUNCH_INTERVAL),
+            args.node_count.unwrap_or(DEFAULT_NODE_COUNT),
+            args.node_args.clone(),
+            args.flame,
+        )
+        .await?
+    };
+
+    if args.churn {
+        let defaults = ChurnConfig::default();
+        let config = ChurnConfig {
+            kill_interval: args
+                .churn_kill_interval
+                .map_or(defaults.kill_interval, Duration::from_secs),
+            restart_delay: if args.churn_no_restart {
+                None
+            } else {
+                args.churn_restart_delay
+                    .map(Duration::from_secs)
+                    .or(defaults.restart_delay)
+            },
+            add_interval: args.churn_add_interval.map(Duration::from_secs),
+            check_interval: args
+                .churn_check_interval
+                .map_or(defaults.check_interval, Duration::from_secs),
+            min_running_nodes: args.churn_min_nodes.unwrap_or(defaults.min_running_nodes),
+            duration: args.churn_duration.map(Duration::from_secs),
+        };
+        let checker = SafeCliChecker::new(
+            args.safe_path
+                .unwrap_or_else(|| PathBuf::from(SAFE_BIN_NAME)),
+            args.churn_check_addresses,
+        );
+        let report = testnet.churn(&config, &checker, args.node_args)?;
+        println!("Churn of the testnet complete: {report}");
+        if report.failed_checks > 0 {
+            return Err(eyre!(
+                "Data could not be retrieved during the churn: {report}"
+            ));
+        }
     }
 
-    run_network(
-        node_bin_path,
-        args.node_launch_interval
-            .unwrap_or(DEFAULT_NODE_LAUNCH_INTERVAL),
-        args.node_count.unwrap_or(DEFAULT_NODE_COUNT),
-        args.node_args,
-        args.flame,
-    )
-    .await?;
-
     Ok(())
 }
 
@@ -220,7 +314,7 @@ async fn run_network(
     node_count: u32,
     node_args: Vec<String>,
     flamegraph_mode: bool,
-) -> Result<()> {
+) -> Result<Testnet> {
     let (mut testnet, network_contacts_path) = Testnet::configure()
         .node_bin_path(node_bin_path)
         .node_launch_interval(node_launch_interval)
@@ -234,7 +328,7 @@ async fn run_network(
     #[cfg(feature = "verify-nodes")]
     check_testnet::run(&testnet.nodes_dir_path, node_count).await?;
 
-    Ok(())
+    Ok(testnet)
 }
 
 async fn join_network(
@@ -243,7 +337,7 @@ async fn join_network(
     node_count: u32,
     network_contacts_path: Option<PathBuf>,
     node_args: Vec<String>,
-) -> Result<()> {
+) -> Result<Testnet> {
     let (mut testnet, default_network_contacts_path) = Testnet::configure()
         .node_bin_path(node_bin_path)
         .node_launch_interval(node_launch_interval)
@@ -252,7 +346,7 @@ async fn join_network(
     // The testnet::node_count is set to total_count - 1 to offset for the genesis.
     // Then plus 2 for start. Hence need an offset 1 here.
     testnet.launch_nodes(node_count as usize + 1, &network_contacts_path, node_args)?;
-    Ok(())
+    Ok(testnet)
 }
 
 fn init_tracing() -> Result<()> {